@@ -0,0 +1,306 @@
+/// Archive - results past their retention as Parquet in S3-compatible storage
+///
+/// The retention sweeper hands expired results over in batches; each batch
+/// becomes one zstd-compressed Parquet file named after the time range it
+/// covers (`<prefix>/results-<first>-<last>.parquet`, Unix seconds), so a
+/// restore only fetches the files overlapping the range asked for. Restored
+/// results are marked and kept for a week before the sweeper removes them
+/// again, without archiving them twice.
+use anyhow::{Context, Result, anyhow};
+use arrow_array::{
+    Array, BinaryArray, BooleanArray, Int32Array, Int64Array, RecordBatch, StringArray, UInt64Array,
+};
+use arrow_schema::{DataType, Field, Schema};
+use bytes::Bytes;
+use futures::TryStreamExt;
+use object_store::ObjectStore;
+use object_store::aws::AmazonS3Builder;
+use object_store::path::Path;
+use parquet::arrow::ArrowWriter;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use std::sync::Arc;
+use std::time::SystemTime;
+use uuid::Uuid;
+
+use crate::config::ArchiveConfig;
+use crate::database::Database;
+use crate::database::models::{Monitor, MonitorResult};
+use crate::monitoring::types::MonitorStatus;
+
+/// Archive of results in an object store
+pub struct ResultArchive {
+    store: Arc<dyn ObjectStore>,
+    prefix: String,
+}
+
+impl ResultArchive {
+    /// Open the archive if a bucket is configured
+    pub fn from_config(config: &ArchiveConfig) -> Result<Option<Self>> {
+        let Some(bucket) = &config.bucket else {
+            return Ok(None);
+        };
+
+        let mut builder = AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .with_region(&config.region)
+            .with_allow_http(config.allow_http);
+        if let Some(endpoint) = &config.endpoint {
+            builder = builder.with_endpoint(endpoint);
+        }
+        if let Some(key) = &config.access_key_id {
+            builder = builder.with_access_key_id(key);
+        }
+        if let Some(secret) = &config.secret_access_key {
+            builder = builder.with_secret_access_key(secret);
+        }
+        let store = builder.build().context("Invalid archive settings")?;
+
+        Ok(Some(Self {
+            store: Arc::new(store),
+            prefix: config.prefix.trim_matches('/').to_string(),
+        }))
+    }
+
+    /// Upload a batch of results as one file
+    pub async fn archive(&self, results: &[MonitorResult]) -> Result<()> {
+        let (Some(first), Some(last)) = (
+            results.iter().map(|r| Monitor::timestamp_to_i64(r.timestamp)).min(),
+            results.iter().map(|r| Monitor::timestamp_to_i64(r.timestamp)).max(),
+        ) else {
+            return Ok(());
+        };
+
+        let path = Path::from(format!("{}/{}", self.prefix, file_name(first, last)));
+        self.store.put(&path, encode(results)?.into()).await?;
+        Ok(())
+    }
+
+    /// Import the archived results from `from` to `to` (Unix seconds, both
+    /// included), returning how many were added
+    pub async fn restore(&self, database: &dyn Database, from: i64, to: i64) -> Result<u64> {
+        let prefix = Path::from(self.prefix.as_str());
+        let objects: Vec<_> = self.store.list(Some(&prefix)).try_collect().await?;
+
+        let mut restored = 0;
+        for object in objects {
+            let Some((first, last)) = object.location.filename().and_then(parse_file_name) else {
+                continue;
+            };
+            if last < from || first > to {
+                continue;
+            }
+
+            let data = self.store.get(&object.location).await?.bytes().await?;
+            let results: Vec<_> = decode(data)
+                .with_context(|| format!("Failed to read {}", object.location))?
+                .into_iter()
+                .filter(|r| (from..=to).contains(&Monitor::timestamp_to_i64(r.timestamp)))
+                .collect();
+            restored += database.import_results(&results, Some(SystemTime::now())).await?;
+        }
+
+        Ok(restored)
+    }
+}
+
+fn file_name(first: i64, last: i64) -> String {
+    format!("results-{first:010}-{last:010}.parquet")
+}
+
+/// Time range of an archive file from its name
+fn parse_file_name(name: &str) -> Option<(i64, i64)> {
+    let range = name.strip_prefix("results-")?.strip_suffix(".parquet")?;
+    let (first, last) = range.split_once('-')?;
+    Some((first.parse().ok()?, last.parse().ok()?))
+}
+
+fn schema() -> Schema {
+    Schema::new(vec![
+        Field::new("monitor_uuid", DataType::Utf8, false),
+        Field::new("timestamp", DataType::Int64, false),
+        Field::new("status", DataType::Utf8, false),
+        Field::new("latency_ms", DataType::UInt64, true),
+        Field::new("status_code", DataType::Int32, true),
+        Field::new("error_message", DataType::Utf8, true),
+        Field::new("peer_id", DataType::Utf8, false),
+        Field::new("signature", DataType::Binary, true),
+        Field::new("created_at", DataType::Int64, false),
+        Field::new("city", DataType::Utf8, true),
+        Field::new("country", DataType::Utf8, true),
+        Field::new("region", DataType::Utf8, true),
+        Field::new("maintenance", DataType::Boolean, false),
+    ])
+}
+
+/// Results as a zstd-compressed Parquet file
+fn encode(results: &[MonitorResult]) -> Result<Vec<u8>> {
+    let schema = Arc::new(schema());
+    let text = |f: fn(&MonitorResult) -> Option<&str>| {
+        Arc::new(results.iter().map(f).collect::<StringArray>()) as Arc<dyn Array>
+    };
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(
+                results
+                    .iter()
+                    .map(|r| r.monitor_uuid.to_string())
+                    .map(Some)
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(
+                results
+                    .iter()
+                    .map(|r| Monitor::timestamp_to_i64(r.timestamp))
+                    .collect::<Int64Array>(),
+            ),
+            Arc::new(results.iter().map(|r| Some(r.status.to_string())).collect::<StringArray>()),
+            Arc::new(results.iter().map(|r| r.latency_ms).collect::<UInt64Array>()),
+            Arc::new(results.iter().map(|r| r.status_code.map(i32::from)).collect::<Int32Array>()),
+            text(|r| r.error_message.as_deref()),
+            text(|r| Some(r.peer_id.as_str())),
+            Arc::new(results.iter().map(|r| r.signature.as_deref()).collect::<BinaryArray>()),
+            Arc::new(
+                results
+                    .iter()
+                    .map(|r| Monitor::timestamp_to_i64(r.created_at))
+                    .collect::<Int64Array>(),
+            ),
+            text(|r| r.city.as_deref()),
+            text(|r| r.country.as_deref()),
+            text(|r| r.region.as_deref()),
+            Arc::new(results.iter().map(|r| Some(r.maintenance)).collect::<BooleanArray>()),
+        ],
+    )?;
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build();
+    let mut data = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut data, schema, Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(data)
+}
+
+/// Results of a Parquet file written by `encode`
+fn decode(data: Bytes) -> Result<Vec<MonitorResult>> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(data)?.build()?;
+
+    let mut results = Vec::new();
+    for batch in reader {
+        let batch = batch?;
+        let text = |name| column::<StringArray>(&batch, name);
+        let (uuids, statuses, errors, peers) =
+            (text("monitor_uuid")?, text("status")?, text("error_message")?, text("peer_id")?);
+        let (cities, countries, regions) = (text("city")?, text("country")?, text("region")?);
+        let timestamps = column::<Int64Array>(&batch, "timestamp")?;
+        let created = column::<Int64Array>(&batch, "created_at")?;
+        let latencies = column::<UInt64Array>(&batch, "latency_ms")?;
+        let codes = column::<Int32Array>(&batch, "status_code")?;
+        let signatures = column::<BinaryArray>(&batch, "signature")?;
+        // Files archived before maintenance windows existed lack the column
+        let maintenance = column::<BooleanArray>(&batch, "maintenance").ok();
+
+        let optional =
+            |array: &StringArray, i| array.is_valid(i).then(|| array.value(i).to_string());
+        for i in 0..batch.num_rows() {
+            results.push(MonitorResult {
+                id: None,
+                monitor_uuid: Uuid::parse_str(uuids.value(i))?,
+                timestamp: Monitor::i64_to_timestamp(timestamps.value(i)),
+                status: match statuses.value(i) {
+                    "up" => MonitorStatus::Up,
+                    "down" => MonitorStatus::Down,
+                    "degraded" => MonitorStatus::Degraded,
+                    _ => MonitorStatus::Unknown,
+                },
+                latency_ms: latencies.is_valid(i).then(|| latencies.value(i)),
+                status_code: codes.is_valid(i).then(|| codes.value(i) as u16),
+                error_message: optional(errors, i),
+                peer_id: peers.value(i).to_string(),
+                signature: signatures.is_valid(i).then(|| signatures.value(i).to_vec()),
+                created_at: Monitor::i64_to_timestamp(created.value(i)),
+                city: optional(cities, i),
+                country: optional(countries, i),
+                region: optional(regions, i),
+                maintenance: maintenance.is_some_and(|m| m.value(i)),
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+fn column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> Result<&'a T> {
+    batch
+        .column_by_name(name)
+        .and_then(|column| column.as_any().downcast_ref::<T>())
+        .ok_or_else(|| anyhow!("Archive file lacks column {name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        let name = file_name(1_700_000_000, 1_700_003_600);
+        assert_eq!(name, "results-1700000000-1700003600.parquet");
+        assert_eq!(parse_file_name(&name), Some((1_700_000_000, 1_700_003_600)));
+        assert_eq!(parse_file_name("results-1-2.csv"), None);
+        assert_eq!(parse_file_name("notes.parquet"), None);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let monitor = Uuid::new_v4();
+        let up = MonitorResult {
+            id: Some(1),
+            monitor_uuid: monitor,
+            timestamp: Monitor::i64_to_timestamp(1_700_000_000),
+            status: MonitorStatus::Up,
+            latency_ms: Some(42),
+            status_code: Some(200),
+            error_message: None,
+            peer_id: "local".to_string(),
+            signature: Some(vec![1, 2, 3]),
+            created_at: Monitor::i64_to_timestamp(1_700_000_001),
+            city: Some("Berlin".to_string()),
+            country: Some("DE".to_string()),
+            region: None,
+            maintenance: false,
+        };
+        let down = MonitorResult {
+            id: Some(2),
+            status: MonitorStatus::Down,
+            latency_ms: None,
+            status_code: None,
+            error_message: Some("timeout".to_string()),
+            signature: None,
+            maintenance: true,
+            ..up.clone()
+        };
+
+        let data = encode(&[up.clone(), down.clone()]).unwrap();
+        let decoded = decode(Bytes::from(data)).unwrap();
+        assert_eq!(decoded.len(), 2);
+        for (decoded, original) in decoded.iter().zip([&up, &down]) {
+            assert_eq!(decoded.id, None);
+            assert_eq!(decoded.monitor_uuid, original.monitor_uuid);
+            assert_eq!(decoded.timestamp, original.timestamp);
+            assert_eq!(decoded.status, original.status);
+            assert_eq!(decoded.latency_ms, original.latency_ms);
+            assert_eq!(decoded.status_code, original.status_code);
+            assert_eq!(decoded.error_message, original.error_message);
+            assert_eq!(decoded.peer_id, original.peer_id);
+            assert_eq!(decoded.signature, original.signature);
+            assert_eq!(decoded.created_at, original.created_at);
+            assert_eq!(decoded.city, original.city);
+            assert_eq!(decoded.region, original.region);
+            assert_eq!(decoded.maintenance, original.maintenance);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+/// Bus - events the running service publishes for the TUI over ZeroMQ
+///
+/// The service binds a PUB socket on the `[zeromq]` address and sends one
+/// JSON message per local result it stores, plus a heartbeat so subscribers
+/// can tell it is there. The TUI subscribes to refresh as soon as results
+/// land, and polls the database instead while no heartbeat arrives.
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::config::ZeroMQ;
+use crate::monitoring::types::MonitorStatus;
+
+/// How often the service announces itself on the bus
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Heartbeats that may be missed before the bus counts as absent
+const MISSED_HEARTBEATS: u32 = 3;
+
+/// Event published on the bus
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BusEvent {
+    /// A local check result was stored
+    ResultStored {
+        monitor_uuid: Uuid,
+        status: MonitorStatus,
+    },
+    Heartbeat,
+}
+
+/// Address the service binds, e.g. `tcp://*:5555`
+fn bind_endpoint(config: &ZeroMQ) -> String {
+    format!("tcp://{}:{}", config.bind, config.port)
+}
+
+/// Address subscribers connect to; wildcard binds are reached on loopback
+fn connect_endpoint(config: &ZeroMQ) -> String {
+    let host = match config.bind.as_str() {
+        "*" | "0.0.0.0" => "127.0.0.1",
+        host => host,
+    };
+    format!("tcp://{host}:{}", config.port)
+}
+
+/// Publishing side of the bus, shared by the service tasks
+pub struct BusPublisher {
+    socket: Mutex<zmq::Socket>,
+}
+
+impl BusPublisher {
+    /// Bind the PUB socket on the configured address
+    pub fn bind(config: &ZeroMQ) -> Result<Arc<Self>> {
+        let endpoint = bind_endpoint(config);
+        let socket = zmq::Context::new().socket(zmq::PUB)?;
+        // Nothing is queued for subscribers once the service stops
+        socket.set_linger(0)?;
+        socket
+            .bind(&endpoint)
+            .with_context(|| format!("Failed to bind bus on {endpoint}"))?;
+        Ok(Arc::new(Self { socket: Mutex::new(socket) }))
+    }
+
+    /// Publish an event; subscribers that are missing or behind miss it
+    pub fn publish(&self, event: &BusEvent) {
+        let Ok(message) = serde_json::to_vec(event) else {
+            return;
+        };
+        if let Err(e) = self.socket.lock().unwrap().send(message, zmq::DONTWAIT) {
+            debug!("Failed to publish bus event: {}", e);
+        }
+    }
+
+    /// Spawn the heartbeat; it runs until the task is aborted
+    pub fn spawn_heartbeat(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+            loop {
+                interval.tick().await;
+                self.publish(&BusEvent::Heartbeat);
+            }
+        })
+    }
+}
+
+/// Subscribing side of the bus, read without blocking
+pub struct BusSubscriber {
+    socket: zmq::Socket,
+    /// When the last event arrived
+    last_seen: Option<Instant>,
+}
+
+impl BusSubscriber {
+    /// Subscribe to the service on the configured address; succeeds whether
+    /// or not the service is running
+    pub fn connect(config: &ZeroMQ) -> Result<Self> {
+        let endpoint = connect_endpoint(config);
+        let socket = zmq::Context::new().socket(zmq::SUB)?;
+        socket.set_linger(0)?;
+        socket.set_subscribe(b"")?;
+        socket
+            .connect(&endpoint)
+            .with_context(|| format!("Failed to connect bus to {endpoint}"))?;
+        Ok(Self { socket, last_seen: None })
+    }
+
+    /// Events received since the last call
+    pub fn drain(&mut self) -> Vec<BusEvent> {
+        let mut events = Vec::new();
+        while let Ok(message) = self.socket.recv_bytes(zmq::DONTWAIT) {
+            self.last_seen = Some(Instant::now());
+            match serde_json::from_slice(&message) {
+                Ok(event) => events.push(event),
+                Err(e) => debug!("Ignoring malformed bus event: {}", e),
+            }
+        }
+        events
+    }
+
+    /// Whether the service was heard from recently
+    pub fn is_live(&self) -> bool {
+        is_recent(self.last_seen, Instant::now())
+    }
+}
+
+fn is_recent(last_seen: Option<Instant>, now: Instant) -> bool {
+    last_seen.is_some_and(|at| now.duration_since(at) < HEARTBEAT_INTERVAL * MISSED_HEARTBEATS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoints() {
+        let config = ZeroMQ { bind: "*".into(), port: 5555 };
+        assert_eq!(bind_endpoint(&config), "tcp://*:5555");
+        assert_eq!(connect_endpoint(&config), "tcp://127.0.0.1:5555");
+
+        let config = ZeroMQ { bind: "10.0.0.2".into(), port: 6000 };
+        assert_eq!(connect_endpoint(&config), "tcp://10.0.0.2:6000");
+    }
+
+    #[test]
+    fn test_event_wire_format() {
+        let uuid = Uuid::new_v4();
+        let event = BusEvent::ResultStored { monitor_uuid: uuid, status: MonitorStatus::Down };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "result_stored");
+        assert_eq!(json["monitor_uuid"], uuid.to_string());
+        assert_eq!(serde_json::from_value::<BusEvent>(json).unwrap(), event);
+
+        assert_eq!(serde_json::to_string(&BusEvent::Heartbeat).unwrap(), r#"{"type":"heartbeat"}"#);
+    }
+
+    #[test]
+    fn test_liveness() {
+        let now = Instant::now();
+        assert!(!is_recent(None, now));
+        assert!(is_recent(Some(now), now));
+        assert!(!is_recent(Some(now), now + HEARTBEAT_INTERVAL * MISSED_HEARTBEATS));
+    }
+}
@@ -0,0 +1,179 @@
+/// Cache - size-bounded maps whose entries expire
+///
+/// Used for orchestrator state keyed by what the network tells us about
+/// (peers, targets), which would otherwise grow for as long as the service
+/// runs. An entry expires `ttl` after it was last written; when the map is
+/// full, the least recently written entry makes room. Callers pass the
+/// current time in, so expiry is driven by their own clock. Each map's size
+/// is recorded as `uppe.cache.size`, labelled with its name.
+use opentelemetry::KeyValue;
+use opentelemetry::global;
+use opentelemetry::metrics::Gauge;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+struct Entry<V> {
+    value: V,
+    written: Instant,
+    /// Position in the write order
+    tick: u64,
+}
+
+/// Map with a maximum size and a time to live per entry
+#[derive(Debug, Clone)]
+pub struct ExpiringMap<K, V> {
+    capacity: usize,
+    ttl: Duration,
+    entries: HashMap<K, Entry<V>>,
+    /// Keys by last write, oldest first
+    order: BTreeMap<u64, K>,
+    tick: u64,
+    labels: [KeyValue; 1],
+    size_gauge: Gauge<u64>,
+}
+
+impl<K: Clone + Eq + Hash, V> ExpiringMap<K, V> {
+    /// Create a map; `name` labels its size metric
+    pub fn new(name: &'static str, capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ttl,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            labels: [KeyValue::new("cache", name)],
+            size_gauge: global::meter("uppe-service")
+                .u64_gauge("uppe.cache.size")
+                .with_description("Entries held in an internal cache")
+                .build(),
+        }
+    }
+
+    /// Value of a live entry
+    pub fn get(&self, key: &K, now: Instant) -> Option<&V> {
+        self.entries
+            .get(key)
+            .filter(|entry| !self.is_expired(entry, now))
+            .map(|entry| &entry.value)
+    }
+
+    /// Mutable value of a live entry, without refreshing it
+    pub fn get_mut(&mut self, key: &K, now: Instant) -> Option<&mut V> {
+        let ttl = self.ttl;
+        self.entries
+            .get_mut(key)
+            .filter(|entry| now.saturating_duration_since(entry.written) < ttl)
+            .map(|entry| &mut entry.value)
+    }
+
+    /// Write an entry, returning the live value it replaced
+    pub fn insert(&mut self, key: K, value: V, now: Instant) -> Option<V> {
+        self.expire(now);
+        let previous = self.make_room(&key);
+        self.write(key, value, now);
+        previous.map(|entry| entry.value)
+    }
+
+    /// Refresh an entry, creating it with `default` first if there is none
+    pub fn get_or_insert_with(
+        &mut self,
+        key: K,
+        now: Instant,
+        default: impl FnOnce() -> V,
+    ) -> &mut V {
+        self.expire(now);
+        let value = match self.make_room(&key) {
+            Some(entry) => entry.value,
+            None => default(),
+        };
+        self.write(key.clone(), value, now);
+        &mut self.entries.get_mut(&key).expect("entry was just written").value
+    }
+
+    /// Live entries, plus expired ones not dropped yet
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Drop the entries that expired by `now`
+    pub fn expire(&mut self, now: Instant) {
+        while let Some((_, key)) = self.order.first_key_value() {
+            if !self.entries.get(key).is_some_and(|entry| self.is_expired(entry, now)) {
+                break;
+            }
+            if let Some((_, key)) = self.order.pop_first() {
+                self.entries.remove(&key);
+            }
+        }
+        self.record_size();
+    }
+
+    fn is_expired(&self, entry: &Entry<V>, now: Instant) -> bool {
+        now.saturating_duration_since(entry.written) >= self.ttl
+    }
+
+    /// Take out the entry for `key`, or evict the oldest one if the map is full
+    fn make_room(&mut self, key: &K) -> Option<Entry<V>> {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.tick);
+            return Some(entry);
+        }
+        if self.entries.len() >= self.capacity
+            && let Some((_, oldest)) = self.order.pop_first()
+        {
+            self.entries.remove(&oldest);
+        }
+        None
+    }
+
+    fn write(&mut self, key: K, value: V, now: Instant) {
+        self.tick += 1;
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, Entry { value, written: now, tick: self.tick });
+        self.record_size();
+    }
+
+    fn record_size(&self) {
+        self.size_gauge.record(self.entries.len() as u64, &self.labels);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_expire() {
+        let start = Instant::now();
+        let mut map = ExpiringMap::new("test", 10, Duration::from_secs(60));
+        map.insert("a", 1, start);
+        map.insert("b", 2, start + Duration::from_secs(30));
+
+        let later = start + Duration::from_secs(60);
+        assert_eq!(map.get(&"a", later), None);
+        assert_eq!(map.get(&"b", later), Some(&2));
+        // Refreshing an entry restarts its time to live
+        *map.get_or_insert_with("b", later, || 0) += 1;
+        map.expire(start + Duration::from_secs(100));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&"b", start + Duration::from_secs(100)), Some(&3));
+    }
+
+    #[test]
+    fn test_full_map_evicts_oldest() {
+        let now = Instant::now();
+        let mut map = ExpiringMap::new("test", 2, Duration::from_secs(60));
+        map.insert("a", 1, now);
+        map.insert("b", 2, now);
+        // Writing "a" again makes "b" the oldest
+        assert_eq!(map.insert("a", 3, now), Some(1));
+        map.insert("c", 4, now);
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&"a", now), Some(&3));
+        assert_eq!(map.get(&"b", now), None);
+        assert_eq!(map.get(&"c", now), Some(&4));
+    }
+}
@@ -0,0 +1,115 @@
+/// Cluster encryption - seals updates exchanged between an owner's own nodes
+///
+/// Every node of a cluster is configured with the same 32-byte key. Updates
+/// are encrypted with ChaCha20-Poly1305 under a key expanded from it with HKDF,
+/// so only cluster nodes can read them and an update that opens was sent by
+/// one. The gossip topic is expanded from the key too, so every cluster gets
+/// its own topic and the topic name does not give the key away.
+use anyhow::{Result, anyhow};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::p2p::messages::CLUSTER_TOPIC_PREFIX;
+
+/// Domain separation for the HKDF expansions
+const CIPHER_INFO: &[u8] = b"uppe/cluster/cipher/v1";
+const TOPIC_INFO: &[u8] = b"uppe/cluster/topic/v1";
+
+/// An update sealed for the nodes of a cluster
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedClusterMessage {
+    /// ChaCha20-Poly1305 nonce
+    pub nonce: [u8; 12],
+    /// Encrypted, JSON-serialized update
+    pub ciphertext: Vec<u8>,
+}
+
+/// Key shared by the nodes of a cluster
+pub struct ClusterKey {
+    cipher: ChaCha20Poly1305,
+    topic: String,
+}
+
+impl std::fmt::Debug for ClusterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClusterKey").field("topic", &self.topic).finish_non_exhaustive()
+    }
+}
+
+impl ClusterKey {
+    /// Parse a key given as 64 hex characters
+    pub fn from_hex(key: &str) -> Result<Self> {
+        let bytes = hex::decode(key.trim()).map_err(|e| anyhow!("Invalid cluster key: {}", e))?;
+        let secret: [u8; 32] = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| anyhow!("Cluster key is {} bytes, not 32", bytes.len()))?;
+
+        let hkdf = Hkdf::<Sha256>::new(None, &secret);
+        let mut key = [0u8; 32];
+        hkdf.expand(CIPHER_INFO, &mut key)
+            .map_err(|e| anyhow!("Failed to derive key: {}", e))?;
+        let mut topic_id = [0u8; 16];
+        hkdf.expand(TOPIC_INFO, &mut topic_id)
+            .map_err(|e| anyhow!("Failed to derive topic: {}", e))?;
+
+        Ok(Self {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+            topic: format!("{CLUSTER_TOPIC_PREFIX}{}", hex::encode(topic_id)),
+        })
+    }
+
+    /// Gossip topic the cluster's updates are published on
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Encrypt an update for the cluster
+    pub fn seal(&self, plaintext: &[u8]) -> Result<SealedClusterMessage> {
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| anyhow!("Failed to encrypt cluster update"))?;
+        Ok(SealedClusterMessage { nonce, ciphertext })
+    }
+
+    /// Decrypt an update sealed with the cluster's key
+    pub fn open(&self, sealed: &SealedClusterMessage) -> Result<Vec<u8>> {
+        self.cipher
+            .decrypt(Nonce::from_slice(&sealed.nonce), sealed.ciphertext.as_slice())
+            .map_err(|_| anyhow!("Failed to decrypt cluster update: other cluster or corrupted"))
+    }
+}
+
+/// A new random cluster key, as 64 hex characters
+pub fn generate_cluster_key() -> String {
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    hex::encode(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let key = ClusterKey::from_hex(&generate_cluster_key()).unwrap();
+        let other = ClusterKey::from_hex(&generate_cluster_key()).unwrap();
+        assert!(key.topic().starts_with(CLUSTER_TOPIC_PREFIX));
+        assert_ne!(key.topic(), other.topic());
+
+        let sealed = key.seal(b"update").unwrap();
+        assert_eq!(key.open(&sealed).unwrap(), b"update");
+        assert!(other.open(&sealed).is_err());
+
+        assert!(ClusterKey::from_hex("abcd").is_err());
+        assert!(ClusterKey::from_hex("not hex").is_err());
+    }
+}
@@ -0,0 +1,106 @@
+/// Result encryption for private monitors
+///
+/// Helpers encrypt results for the monitor owner so only the owner can read
+/// them. The owner's Ed25519 key is converted to X25519, combined with a
+/// per-message ephemeral key, and the shared secret is expanded with HKDF into
+/// a ChaCha20-Poly1305 key.
+use anyhow::{Result, anyhow};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use ed25519_dalek::VerifyingKey;
+use hkdf::Hkdf;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use super::keys::KeyPair;
+use crate::monitoring::types::CheckResult;
+
+/// Domain separation for the HKDF expansion
+const HKDF_INFO: &[u8] = b"uppe/encrypted-result/v1";
+
+/// A result sealed for a single recipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedResult {
+    /// Sender's ephemeral X25519 public key
+    pub ephemeral_public_key: [u8; 32],
+    /// ChaCha20-Poly1305 nonce
+    pub nonce: [u8; 12],
+    /// Encrypted, JSON-serialized `CheckResult`
+    pub ciphertext: Vec<u8>,
+}
+
+/// Encrypt a result for the owner identified by its Ed25519 public key
+pub fn encrypt_result(result: &CheckResult, owner_public_key: &[u8; 32]) -> Result<SealedResult> {
+    let owner = VerifyingKey::from_bytes(owner_public_key)
+        .map_err(|e| anyhow!("Invalid owner public key: {}", e))?
+        .to_montgomery();
+
+    let mut ephemeral_secret = [0u8; 32];
+    OsRng.fill_bytes(&mut ephemeral_secret);
+    let ephemeral_public_key = MontgomeryPoint::mul_base_clamped(ephemeral_secret).to_bytes();
+    let shared = owner.mul_clamped(ephemeral_secret);
+
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+
+    let cipher = cipher_for(&shared, &ephemeral_public_key)?;
+    let plaintext = serde_json::to_vec(result)?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| anyhow!("Failed to encrypt result"))?;
+
+    Ok(SealedResult { ephemeral_public_key, nonce, ciphertext })
+}
+
+/// Decrypt a result sealed for our keypair
+pub fn decrypt_result(sealed: &SealedResult, keypair: &KeyPair) -> Result<CheckResult> {
+    let ephemeral = MontgomeryPoint(sealed.ephemeral_public_key);
+    let shared = ephemeral.mul_clamped(keypair.signing_key.to_scalar_bytes());
+
+    let cipher = cipher_for(&shared, &sealed.ephemeral_public_key)?;
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&sealed.nonce), sealed.ciphertext.as_slice())
+        .map_err(|_| anyhow!("Failed to decrypt result: not addressed to us or corrupted"))?;
+
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+fn cipher_for(
+    shared: &MontgomeryPoint,
+    ephemeral_public_key: &[u8; 32],
+) -> Result<ChaCha20Poly1305> {
+    let hkdf = Hkdf::<Sha256>::new(Some(ephemeral_public_key), shared.as_bytes());
+    let mut key = [0u8; 32];
+    hkdf.expand(HKDF_INFO, &mut key)
+        .map_err(|e| anyhow!("Failed to derive key: {}", e))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::keys::generate_keypair;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let owner = generate_keypair();
+        let other = generate_keypair();
+        let result = CheckResult::new(
+            Uuid::new_v4(),
+            "https://example.com".to_string(),
+            "helper".to_string(),
+        )
+        .success(42, Some(200));
+
+        let sealed = encrypt_result(&result, &owner.public_key_bytes()).unwrap();
+        let opened = decrypt_result(&sealed, &owner).unwrap();
+        assert_eq!(opened.monitor_id, result.monitor_id);
+        assert_eq!(opened.latency_ms, Some(42));
+
+        assert!(decrypt_result(&sealed, &other).is_err());
+    }
+}
@@ -1,8 +1,30 @@
-use anyhow::{Context, Result};
+/// Ed25519 keypair generation and storage
+///
+/// The keypair file holds either the bare 32-byte secret key or, when a
+/// passphrase is set, the key sealed with XChaCha20-Poly1305 under a key
+/// derived from the passphrase with Argon2id. The passphrase comes from
+/// `UPPE_KEYPAIR_PASSPHRASE` or is prompted for when the file is encrypted;
+/// loading a plaintext file with a passphrase set encrypts it in place.
+use anyhow::{Context, Result, anyhow};
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
 use ed25519_dalek::{SigningKey, VerifyingKey};
+use rand::RngCore;
 use rand::rngs::OsRng;
 use std::fs;
-use std::path::Path;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+/// Environment variable holding the keypair passphrase
+pub const PASSPHRASE_ENV: &str = "UPPE_KEYPAIR_PASSPHRASE";
+
+/// Starts an encrypted keypair file, and is authenticated along with the key
+const ENCRYPTED_MAGIC: &[u8; 8] = b"UPPEKEY1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+/// Magic, salt, nonce, then the sealed key and its 16-byte tag
+const ENCRYPTED_LEN: usize = ENCRYPTED_MAGIC.len() + SALT_LEN + NONCE_LEN + 32 + 16;
 
 /// KeyPair for signing and verification
 #[derive(Clone)]
@@ -38,25 +60,90 @@ pub fn generate_keypair() -> KeyPair {
     KeyPair::new(signing_key)
 }
 
-/// Save a keypair to a file
-pub fn save_keypair(keypair: &KeyPair, path: &Path) -> Result<()> {
+/// Path of the keypair file, `UPPE_KEYPAIR_PATH` or `uppe_keypair.key`
+pub fn keypair_path() -> PathBuf {
+    std::env::var("UPPE_KEYPAIR_PATH")
+        .unwrap_or_else(|_| "uppe_keypair.key".to_string())
+        .into()
+}
+
+/// Whether a keypair file is encrypted
+pub fn is_encrypted(path: &Path) -> Result<bool> {
+    let bytes = fs::read(path).context("Failed to read keypair file")?;
+    Ok(bytes.starts_with(ENCRYPTED_MAGIC))
+}
+
+/// Passphrase from `UPPE_KEYPAIR_PASSPHRASE`, or prompted for when the file
+/// at `path` is encrypted; `None` keeps the keypair in plaintext
+pub fn passphrase(path: &Path) -> Result<Option<String>> {
+    if let Some(passphrase) = std::env::var(PASSPHRASE_ENV).ok().filter(|p| !p.is_empty()) {
+        return Ok(Some(passphrase));
+    }
+    if path.exists() && is_encrypted(path)? {
+        return prompt_passphrase(&format!("Passphrase for {}: ", path.display())).map(Some);
+    }
+    Ok(None)
+}
+
+/// Prompt for a passphrase on the terminal without echoing it
+pub fn prompt_passphrase(prompt: &str) -> Result<String> {
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("The keypair is encrypted; set {PASSPHRASE_ENV} to unlock it");
+    }
+    let passphrase = rpassword::prompt_password(prompt).context("Failed to read passphrase")?;
+    if passphrase.is_empty() {
+        anyhow::bail!("The passphrase cannot be empty");
+    }
+    Ok(passphrase)
+}
+
+/// Save a keypair to a file, encrypted when a passphrase is given
+pub fn save_keypair(keypair: &KeyPair, path: &Path, passphrase: Option<&str>) -> Result<()> {
     let secret_bytes = keypair.signing_key.to_bytes();
+    let contents = match passphrase {
+        Some(passphrase) => seal(&secret_bytes, passphrase)?,
+        None => secret_bytes.to_vec(),
+    };
 
     // Create parent directory if it doesn't exist
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
 
-    fs::write(path, secret_bytes).context("Failed to write keypair to file")?;
+    // Write next to the file and rename, so re-encrypting never leaves a
+    // half-written key behind
+    let tmp = path.with_extension("tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(&tmp)
+        .and_then(|mut file| file.write_all(&contents).and_then(|()| file.sync_all()))
+        .context("Failed to write keypair to file")?;
+    fs::rename(&tmp, path).context("Failed to write keypair to file")?;
 
-    tracing::info!("Saved keypair to: {}", path.display());
+    tracing::info!(
+        "Saved {} keypair to: {}",
+        if passphrase.is_some() { "encrypted" } else { "plaintext" },
+        path.display()
+    );
     Ok(())
 }
 
-/// Load a keypair from a file
-pub fn load_keypair(path: &Path) -> Result<KeyPair> {
+/// Load a keypair from a file; an encrypted one needs its passphrase
+pub fn load_keypair(path: &Path, passphrase: Option<&str>) -> Result<KeyPair> {
     let secret_bytes = fs::read(path).context("Failed to read keypair file")?;
 
+    let secret_bytes = if secret_bytes.starts_with(ENCRYPTED_MAGIC) {
+        let passphrase = passphrase.ok_or_else(|| {
+            anyhow!("The keypair is encrypted; set {PASSPHRASE_ENV} to unlock it")
+        })?;
+        open(&secret_bytes, passphrase)?
+    } else {
+        secret_bytes
+    };
+
     if secret_bytes.len() != 32 {
         anyhow::bail!("Invalid keypair file: expected 32 bytes, got {}", secret_bytes.len());
     }
@@ -68,19 +155,76 @@ pub fn load_keypair(path: &Path) -> Result<KeyPair> {
     Ok(KeyPair::new(signing_key))
 }
 
-/// Load or generate a keypair
+/// Load or generate a keypair, taking the passphrase from the environment or
+/// a prompt
 pub fn load_or_generate_keypair(path: &Path) -> Result<KeyPair> {
+    let passphrase = passphrase(path)?;
+    load_or_generate_keypair_with(path, passphrase.as_deref())
+}
+
+/// Load or generate a keypair; a plaintext keypair is encrypted when a
+/// passphrase is given
+pub fn load_or_generate_keypair_with(path: &Path, passphrase: Option<&str>) -> Result<KeyPair> {
     if path.exists() {
         tracing::info!("Loading existing keypair from: {}", path.display());
-        load_keypair(path)
+        let keypair = load_keypair(path, passphrase)?;
+        if passphrase.is_some() && !is_encrypted(path)? {
+            tracing::info!("Encrypting the plaintext keypair with the passphrase");
+            save_keypair(&keypair, path, passphrase)?;
+        }
+        Ok(keypair)
     } else {
         tracing::info!("Generating new keypair and saving to: {}", path.display());
         let keypair = generate_keypair();
-        save_keypair(&keypair, path)?;
+        save_keypair(&keypair, path, passphrase)?;
         Ok(keypair)
     }
 }
 
+/// Key sealing the keypair, derived from the passphrase with Argon2id
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive the keypair key: {e}"))?;
+    Ok(key)
+}
+
+fn seal(secret: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: secret, aad: ENCRYPTED_MAGIC })
+        .map_err(|_| anyhow!("Failed to encrypt the keypair"))?;
+
+    let mut sealed = Vec::with_capacity(ENCRYPTED_LEN);
+    sealed.extend_from_slice(ENCRYPTED_MAGIC);
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+fn open(sealed: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if sealed.len() != ENCRYPTED_LEN {
+        anyhow::bail!(
+            "Invalid encrypted keypair file: expected {ENCRYPTED_LEN} bytes, got {}",
+            sealed.len()
+        );
+    }
+    let (salt, rest) = sealed[ENCRYPTED_MAGIC.len()..].split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, salt)?);
+    cipher
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad: ENCRYPTED_MAGIC })
+        .map_err(|_| anyhow!("Wrong passphrase or corrupted keypair file"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,9 +242,42 @@ mod tests {
         let path = dir.path().join("test_keypair.key");
 
         let original = generate_keypair();
-        save_keypair(&original, &path).unwrap();
+        save_keypair(&original, &path, None).unwrap();
+
+        let loaded = load_keypair(&path, None).unwrap();
+        assert_eq!(original.public_key_bytes(), loaded.public_key_bytes());
+    }
+
+    #[test]
+    fn test_encrypted_keypair() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_keypair.key");
+
+        let original = generate_keypair();
+        save_keypair(&original, &path, Some("hunter2")).unwrap();
+        assert!(is_encrypted(&path).unwrap());
+        assert_eq!(fs::read(&path).unwrap().len(), ENCRYPTED_LEN);
+
+        let loaded = load_keypair(&path, Some("hunter2")).unwrap();
+        assert_eq!(original.public_key_bytes(), loaded.public_key_bytes());
+        assert!(load_keypair(&path, Some("hunter3")).is_err());
+        assert!(load_keypair(&path, None).is_err());
+    }
+
+    #[test]
+    fn test_plaintext_keypair_is_encrypted_with_passphrase() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_keypair.key");
+
+        let original = load_or_generate_keypair_with(&path, None).unwrap();
+        assert!(!is_encrypted(&path).unwrap());
+
+        let migrated = load_or_generate_keypair_with(&path, Some("hunter2")).unwrap();
+        assert_eq!(original.public_key_bytes(), migrated.public_key_bytes());
+        assert!(is_encrypted(&path).unwrap());
+        assert!(!path.with_extension("tmp").exists());
 
-        let loaded = load_keypair(&path).unwrap();
+        let loaded = load_or_generate_keypair_with(&path, Some("hunter2")).unwrap();
         assert_eq!(original.public_key_bytes(), loaded.public_key_bytes());
     }
 
@@ -110,10 +287,10 @@ mod tests {
         let path = dir.path().join("test_keypair.key");
 
         // First call should generate
-        let first = load_or_generate_keypair(&path).unwrap();
+        let first = load_or_generate_keypair_with(&path, None).unwrap();
 
         // Second call should load the same key
-        let second = load_or_generate_keypair(&path).unwrap();
+        let second = load_or_generate_keypair_with(&path, None).unwrap();
 
         assert_eq!(first.public_key_bytes(), second.public_key_bytes());
     }
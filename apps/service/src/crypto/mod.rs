@@ -1,3 +1,5 @@
+pub mod cluster;
+pub mod encryption;
 pub mod keys;
 /// Cryptographic operations for signing and verifying monitoring results
 ///
@@ -8,6 +10,14 @@ pub mod keys;
 pub mod signing;
 pub mod verification;
 
-pub use keys::{KeyPair, load_or_generate_keypair};
-pub use signing::sign_result;
-pub use verification::verify_result;
+pub use cluster::{ClusterKey, SealedClusterMessage};
+pub use encryption::{SealedResult, decrypt_result, encrypt_result};
+pub use keys::{KeyPair, keypair_path, load_or_generate_keypair};
+pub use signing::{
+    sign_assignment_request, sign_identity_binding, sign_location_claim,
+    sign_membership_announcement, sign_monitor_retraction, sign_result,
+};
+pub use verification::{
+    verify_assignment_request, verify_check_result, verify_identity_binding, verify_location_claim,
+    verify_membership_announcement, verify_monitor_retraction, verify_peer_result, verify_result,
+};
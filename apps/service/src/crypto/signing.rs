@@ -4,11 +4,17 @@ use serde::Serialize;
 use std::time::SystemTime;
 
 use super::keys::KeyPair;
-use crate::monitoring::types::CheckResult;
+use crate::database::models::HelperAssignment;
+use crate::location::{Location, LocationClaim};
+use crate::monitoring::types::{CertificateInfo, CheckResult};
+use crate::p2p::{
+    HelperAssignmentRequest, IdentityBinding, MembershipAnnouncement, MonitorRetraction,
+    PayloadSchema,
+};
 
 /// Message structure for signing
 #[derive(Serialize)]
-struct SignableMessage {
+struct SignableMessage<'a> {
     monitor_id: String,
     target: String,
     timestamp: u64,
@@ -16,6 +22,10 @@ struct SignableMessage {
     latency_ms: Option<u64>,
     status_code: Option<u16>,
     peer_id: String,
+    /// Left out when absent, so results without a certificate sign the same
+    /// bytes as before it was covered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    certificate: Option<&'a CertificateInfo>,
 }
 
 /// Sign a monitoring result
@@ -29,6 +39,7 @@ pub fn sign_result(result: &CheckResult, keypair: &KeyPair) -> Result<Vec<u8>> {
         latency_ms: result.latency_ms,
         status_code: result.status_code,
         peer_id: result.peer_id.clone(),
+        certificate: result.certificate.as_ref(),
     };
 
     // Serialize to JSON for canonical representation
@@ -40,6 +51,268 @@ pub fn sign_result(result: &CheckResult, keypair: &KeyPair) -> Result<Vec<u8>> {
     Ok(signature.to_bytes().to_vec())
 }
 
+/// Canonical form of a result an external agent checked; unlike
+/// `SignableMessage` it covers the error message, as agent results are
+/// stored as submitted
+#[derive(Serialize)]
+struct SignableAgentResult<'a> {
+    monitor_id: String,
+    target: &'a str,
+    timestamp: u64,
+    status: String,
+    latency_ms: Option<u64>,
+    status_code: Option<u16>,
+    error_message: Option<&'a str>,
+    peer_id: &'a str,
+}
+
+/// Bytes an external agent signs for a result
+pub(crate) fn agent_result_bytes(result: &CheckResult) -> Result<Vec<u8>> {
+    let message = SignableAgentResult {
+        monitor_id: result.monitor_id.to_string(),
+        target: &result.target,
+        timestamp: result.timestamp.duration_since(SystemTime::UNIX_EPOCH)?.as_secs(),
+        status: result.status.to_string(),
+        latency_ms: result.latency_ms,
+        status_code: result.status_code,
+        error_message: result.error_message.as_deref(),
+        peer_id: &result.peer_id,
+    };
+
+    Ok(serde_json::to_vec(&message)?)
+}
+
+/// Canonical form of a helper assignment request for signing
+#[derive(Serialize)]
+struct SignableAssignment<'a> {
+    assignment_id: String,
+    monitor_id: String,
+    owner_peer_id: &'a str,
+    owner_public_key: String,
+    helper_peer_id: &'a str,
+    target: &'a str,
+    check_type: &'a str,
+    interval_seconds: u64,
+    expires_at: Option<u64>,
+}
+
+/// Bytes an owner signs for a helper assignment request
+pub(crate) fn assignment_message_bytes(request: &HelperAssignmentRequest) -> Result<Vec<u8>> {
+    let message = SignableAssignment {
+        assignment_id: request.assignment_id.to_string(),
+        monitor_id: request.monitor_id.to_string(),
+        owner_peer_id: &request.owner_peer_id,
+        owner_public_key: hex::encode(request.owner_public_key),
+        helper_peer_id: &request.helper_peer_id,
+        target: &request.target,
+        check_type: &request.check_type,
+        interval_seconds: request.interval_seconds,
+        expires_at: request.expires_at,
+    };
+
+    Ok(serde_json::to_vec(&message)?)
+}
+
+/// Build the signed request asking a helper to take on an owner-side assignment
+pub fn sign_assignment_request(
+    assignment: &HelperAssignment,
+    keypair: &KeyPair,
+) -> Result<HelperAssignmentRequest> {
+    let expires_at = match assignment.expires_at {
+        Some(at) => Some(at.duration_since(SystemTime::UNIX_EPOCH)?.as_secs()),
+        None => None,
+    };
+    let mut request = HelperAssignmentRequest {
+        assignment_id: assignment.assignment_id,
+        monitor_id: assignment.monitor_uuid,
+        owner_peer_id: keypair.public_key_hex(),
+        owner_public_key: keypair.public_key_bytes(),
+        helper_peer_id: assignment.helper_peer_id.clone(),
+        target: assignment.target.clone(),
+        check_type: assignment.check_type.clone(),
+        interval_seconds: assignment.interval_seconds,
+        expires_at,
+        signature: Vec::new(),
+        schema: PayloadSchema::default(),
+    };
+    let message_bytes = assignment_message_bytes(&request)?;
+    request.signature = keypair.signing_key.sign(&message_bytes).to_bytes().to_vec();
+
+    Ok(request)
+}
+
+/// Canonical form of a monitor retraction for signing
+#[derive(Serialize)]
+struct SignableRetraction<'a> {
+    monitor_id: String,
+    owner_peer_id: &'a str,
+    owner_public_key: String,
+    retracted_at: u64,
+}
+
+/// Bytes an owner signs for a monitor retraction
+pub(crate) fn retraction_message_bytes(retraction: &MonitorRetraction) -> Result<Vec<u8>> {
+    let message = SignableRetraction {
+        monitor_id: retraction.monitor_id.to_string(),
+        owner_peer_id: &retraction.owner_peer_id,
+        owner_public_key: hex::encode(retraction.owner_public_key),
+        retracted_at: retraction.retracted_at,
+    };
+
+    Ok(serde_json::to_vec(&message)?)
+}
+
+/// Build a signed retraction of a monitor's shared results
+pub fn sign_monitor_retraction(
+    monitor_id: uuid::Uuid,
+    keypair: &KeyPair,
+    now: SystemTime,
+) -> Result<MonitorRetraction> {
+    let mut retraction = MonitorRetraction {
+        monitor_id,
+        owner_peer_id: keypair.public_key_hex(),
+        owner_public_key: keypair.public_key_bytes(),
+        retracted_at: now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs(),
+        signature: Vec::new(),
+        schema: PayloadSchema::default(),
+    };
+    let message_bytes = retraction_message_bytes(&retraction)?;
+    retraction.signature = keypair.signing_key.sign(&message_bytes).to_bytes().to_vec();
+
+    Ok(retraction)
+}
+
+/// Canonical form of a membership announcement for signing
+#[derive(Serialize)]
+struct SignableMembership<'a> {
+    host: &'a str,
+    peer_id: &'a str,
+    public_key: String,
+    joined: bool,
+    announced_at: u64,
+}
+
+/// Bytes a peer signs for a membership announcement
+pub(crate) fn membership_message_bytes(announcement: &MembershipAnnouncement) -> Result<Vec<u8>> {
+    let message = SignableMembership {
+        host: &announcement.host,
+        peer_id: &announcement.peer_id,
+        public_key: hex::encode(announcement.public_key),
+        joined: announcement.joined,
+        announced_at: announcement.announced_at,
+    };
+
+    Ok(serde_json::to_vec(&message)?)
+}
+
+/// Build a signed announcement of joining or leaving a community domain
+pub fn sign_membership_announcement(
+    host: &str,
+    joined: bool,
+    keypair: &KeyPair,
+    now: SystemTime,
+) -> Result<MembershipAnnouncement> {
+    let mut announcement = MembershipAnnouncement {
+        host: host.to_string(),
+        peer_id: keypair.public_key_hex(),
+        public_key: keypair.public_key_bytes(),
+        joined,
+        announced_at: now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs(),
+        signature: Vec::new(),
+        schema: PayloadSchema::default(),
+    };
+    let message_bytes = membership_message_bytes(&announcement)?;
+    announcement.signature = keypair.signing_key.sign(&message_bytes).to_bytes().to_vec();
+
+    Ok(announcement)
+}
+
+/// Canonical form of an identity binding for signing
+#[derive(Serialize)]
+struct SignableBinding<'a> {
+    libp2p_peer_id: &'a str,
+    app_peer_id: &'a str,
+    public_key: String,
+    issued_at: u64,
+}
+
+/// Bytes a node signs for an identity binding
+pub(crate) fn identity_binding_bytes(binding: &IdentityBinding) -> Result<Vec<u8>> {
+    let message = SignableBinding {
+        libp2p_peer_id: &binding.libp2p_peer_id,
+        app_peer_id: &binding.app_peer_id,
+        public_key: hex::encode(binding.public_key),
+        issued_at: binding.issued_at,
+    };
+
+    Ok(serde_json::to_vec(&message)?)
+}
+
+/// Bind this node's libp2p peer ID to its app key
+pub fn sign_identity_binding(
+    libp2p_peer_id: &str,
+    keypair: &KeyPair,
+    now: SystemTime,
+) -> Result<IdentityBinding> {
+    let mut binding = IdentityBinding {
+        libp2p_peer_id: libp2p_peer_id.to_string(),
+        app_peer_id: keypair.public_key_hex(),
+        public_key: keypair.public_key_bytes(),
+        issued_at: now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs(),
+        signature: Vec::new(),
+        schema: PayloadSchema::default(),
+    };
+    let message_bytes = identity_binding_bytes(&binding)?;
+    binding.signature = keypair.signing_key.sign(&message_bytes).to_bytes().to_vec();
+
+    Ok(binding)
+}
+
+/// Canonical form of a location claim for signing
+#[derive(Serialize)]
+struct SignableLocation<'a> {
+    peer_id: &'a str,
+    city: Option<&'a str>,
+    country: Option<&'a str>,
+    region: Option<&'a str>,
+    issued_at: u64,
+}
+
+/// Bytes a node signs for a location claim
+pub(crate) fn location_claim_bytes(
+    peer_id: &str,
+    location: &Location,
+    issued_at: u64,
+) -> Result<Vec<u8>> {
+    let message = SignableLocation {
+        peer_id,
+        city: location.city.as_deref(),
+        country: location.country.as_deref(),
+        region: location.region.as_deref(),
+        issued_at,
+    };
+
+    Ok(serde_json::to_vec(&message)?)
+}
+
+/// Sign this node's location so peers can attribute its results to it
+pub fn sign_location_claim(
+    location: &Location,
+    keypair: &KeyPair,
+    now: SystemTime,
+) -> Result<LocationClaim> {
+    let peer_id = keypair.public_key_hex();
+    let issued_at = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+    let message_bytes = location_claim_bytes(&peer_id, location, issued_at)?;
+
+    Ok(LocationClaim {
+        peer_id,
+        location: location.clone(),
+        issued_at,
+        signature: keypair.signing_key.sign(&message_bytes).to_bytes().to_vec(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
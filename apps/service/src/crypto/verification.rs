@@ -4,11 +4,20 @@ use ed25519_dalek::{Signature, Verifier, VerifyingKey};
 use serde::Serialize;
 use std::time::SystemTime;
 
+use super::signing::{
+    agent_result_bytes, assignment_message_bytes, identity_binding_bytes, location_claim_bytes,
+    membership_message_bytes, retraction_message_bytes,
+};
 use crate::database::models::PeerResult;
+use crate::location::LocationClaim;
+use crate::monitoring::types::{CertificateInfo, CheckResult};
+use crate::p2p::{
+    HelperAssignmentRequest, IdentityBinding, MembershipAnnouncement, MonitorRetraction,
+};
 
 /// Message structure for verification (must match signing format)
 #[derive(Serialize)]
-struct SignableMessage {
+struct SignableMessage<'a> {
     monitor_id: String,
     target: String,
     timestamp: u64,
@@ -16,7 +25,25 @@ struct SignableMessage {
     latency_ms: Option<u64>,
     status_code: Option<u16>,
     peer_id: String,
+    /// Left out when absent, so results without a certificate sign the same
+    /// bytes as before it was covered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    certificate: Option<&'a CertificateInfo>,
 }
+/// Verify the signature of a result as received from the network, against
+/// the public key it came with
+pub fn verify_peer_result(result: &crate::p2p::PeerResult) -> Result<bool> {
+    let Some(db_result) = PeerResult::from_p2p_result(result) else {
+        return Ok(false);
+    };
+    let public_key = result.public_key.as_deref().ok_or_else(|| anyhow!("No public key"))?;
+    let public_key: [u8; 32] = public_key
+        .try_into()
+        .map_err(|_| anyhow!("Invalid public key length: {} bytes", public_key.len()))?;
+
+    verify_result(&db_result, &public_key, &result.result.target)
+}
+
 /// Verify a peer result signature
 pub fn verify_result(
     result: &PeerResult,
@@ -44,6 +71,7 @@ pub fn verify_result(
         latency_ms: result.latency_ms,
         status_code: result.status_code,
         peer_id: result.peer_id.clone(),
+        certificate: result.certificate.as_ref(),
     };
 
     // Serialize to JSON (same as signing)
@@ -56,13 +84,127 @@ pub fn verify_result(
     }
 }
 
+/// Verify the signature of a result an external agent signed with its own
+/// key, over `agent_result_bytes`
+pub fn verify_check_result(result: &CheckResult, public_key_bytes: &[u8; 32]) -> Result<bool> {
+    let verifying_key = VerifyingKey::from_bytes(public_key_bytes)
+        .map_err(|e| anyhow!("Invalid public key: {}", e))?;
+    let Some(Ok(sig_bytes)) = result.signature.as_deref().map(<[u8; 64]>::try_from) else {
+        return Ok(false);
+    };
+    let signature = Signature::from_bytes(&sig_bytes);
+
+    let message_bytes = agent_result_bytes(result)?;
+    Ok(verifying_key.verify(&message_bytes, &signature).is_ok())
+}
+
+/// Verify that a helper assignment request really comes from its claimed owner
+///
+/// The embedded public key must be the one `owner_peer_id` encodes, and the
+/// signature must be valid for that key.
+pub fn verify_assignment_request(request: &HelperAssignmentRequest) -> Result<bool> {
+    if hex::encode(request.owner_public_key) != request.owner_peer_id.to_lowercase() {
+        return Ok(false);
+    }
+
+    let verifying_key = VerifyingKey::from_bytes(&request.owner_public_key)
+        .map_err(|e| anyhow!("Invalid public key: {}", e))?;
+
+    let Ok(sig_bytes) = <[u8; 64]>::try_from(request.signature.as_slice()) else {
+        return Ok(false);
+    };
+    let signature = Signature::from_bytes(&sig_bytes);
+
+    let message_bytes = assignment_message_bytes(request)?;
+    Ok(verifying_key.verify(&message_bytes, &signature).is_ok())
+}
+
+/// Verify that a monitor retraction really comes from its claimed owner
+pub fn verify_monitor_retraction(retraction: &MonitorRetraction) -> Result<bool> {
+    if hex::encode(retraction.owner_public_key) != retraction.owner_peer_id.to_lowercase() {
+        return Ok(false);
+    }
+
+    let verifying_key = VerifyingKey::from_bytes(&retraction.owner_public_key)
+        .map_err(|e| anyhow!("Invalid public key: {}", e))?;
+
+    let Ok(sig_bytes) = <[u8; 64]>::try_from(retraction.signature.as_slice()) else {
+        return Ok(false);
+    };
+    let signature = Signature::from_bytes(&sig_bytes);
+
+    let message_bytes = retraction_message_bytes(retraction)?;
+    Ok(verifying_key.verify(&message_bytes, &signature).is_ok())
+}
+
+/// Verify that a membership announcement was signed by the peer it names
+pub fn verify_membership_announcement(announcement: &MembershipAnnouncement) -> Result<bool> {
+    if hex::encode(announcement.public_key) != announcement.peer_id.to_lowercase() {
+        return Ok(false);
+    }
+
+    let verifying_key = VerifyingKey::from_bytes(&announcement.public_key)
+        .map_err(|e| anyhow!("Invalid public key: {}", e))?;
+
+    let Ok(sig_bytes) = <[u8; 64]>::try_from(announcement.signature.as_slice()) else {
+        return Ok(false);
+    };
+    let signature = Signature::from_bytes(&sig_bytes);
+
+    let message_bytes = membership_message_bytes(announcement)?;
+    Ok(verifying_key.verify(&message_bytes, &signature).is_ok())
+}
+
+/// Verify that an identity binding was signed by the app key it names
+///
+/// Whether the libp2p peer it names really published it is up to the caller.
+pub fn verify_identity_binding(binding: &IdentityBinding) -> Result<bool> {
+    if hex::encode(binding.public_key) != binding.app_peer_id.to_lowercase() {
+        return Ok(false);
+    }
+
+    let verifying_key = VerifyingKey::from_bytes(&binding.public_key)
+        .map_err(|e| anyhow!("Invalid public key: {}", e))?;
+
+    let Ok(sig_bytes) = <[u8; 64]>::try_from(binding.signature.as_slice()) else {
+        return Ok(false);
+    };
+    let signature = Signature::from_bytes(&sig_bytes);
+
+    let message_bytes = identity_binding_bytes(binding)?;
+    Ok(verifying_key.verify(&message_bytes, &signature).is_ok())
+}
+
+/// Verify a location claim made by the holder of `public_key_bytes`
+///
+/// The claim must name the peer ID that key encodes, carry a valid signature
+/// and still be fresh.
+pub fn verify_location_claim(claim: &LocationClaim, public_key_bytes: &[u8; 32]) -> Result<bool> {
+    if hex::encode(public_key_bytes) != claim.peer_id.to_lowercase()
+        || !claim.is_fresh(SystemTime::now())
+    {
+        return Ok(false);
+    }
+
+    let verifying_key = VerifyingKey::from_bytes(public_key_bytes)
+        .map_err(|e| anyhow!("Invalid public key: {}", e))?;
+
+    let Ok(sig_bytes) = <[u8; 64]>::try_from(claim.signature.as_slice()) else {
+        return Ok(false);
+    };
+    let signature = Signature::from_bytes(&sig_bytes);
+
+    let message_bytes = location_claim_bytes(&claim.peer_id, &claim.location, claim.issued_at)?;
+    Ok(verifying_key.verify(&message_bytes, &signature).is_ok())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::crypto::keys::generate_keypair;
     use crate::crypto::signing::sign_result;
-    use crate::monitoring::types::CheckResult;
     use crate::monitoring::types::MonitorStatus;
+    use crate::p2p::PayloadSchema;
     use uuid::Uuid;
 
     #[test]
@@ -94,6 +236,8 @@ mod tests {
             city: None,
             country: None,
             region: None,
+            source_peer_id: None,
+            certificate: None,
         };
 
         // Verify the signature
@@ -102,6 +246,49 @@ mod tests {
         assert!(is_valid);
     }
 
+    #[test]
+    fn test_verify_result_covers_certificate() {
+        let keypair = generate_keypair();
+        let target = "example.com:443".to_string();
+        let mut check_result = CheckResult::new(Uuid::new_v4(), target.clone(), "peer".into());
+        check_result = check_result.success(100, None);
+        check_result.certificate = Some(CertificateInfo {
+            days_until_expiry: 3,
+            not_after: 1_800_000_000,
+            issuer: "CN=Example CA".to_string(),
+            chain_valid: true,
+        });
+
+        let peer_result = crate::p2p::PeerResult {
+            signature: Some(sign_result(&check_result, &keypair).unwrap()),
+            result: check_result,
+            public_key: Some(keypair.public_key_bytes().to_vec()),
+            peer_id: "peer".to_string(),
+            location: None,
+            received_at: SystemTime::now(),
+            signature_valid: None,
+            identity_bound: None,
+        };
+        let stored = PeerResult::from_p2p_result(&peer_result).unwrap();
+        assert!(verify_result(&stored, &keypair.public_key_bytes(), &target).unwrap());
+
+        // Each certificate detail is covered by the signature
+        let tampered: [fn(&mut CertificateInfo); 4] = [
+            |cert| cert.days_until_expiry = 300,
+            |cert| cert.not_after += 86_400,
+            |cert| cert.issuer = "CN=Other CA".to_string(),
+            |cert| cert.chain_valid = false,
+        ];
+        for tamper in tampered {
+            let mut forged = stored.clone();
+            tamper(forged.certificate.as_mut().unwrap());
+            assert!(!verify_result(&forged, &keypair.public_key_bytes(), &target).unwrap());
+        }
+        let mut stripped = stored.clone();
+        stripped.certificate = None;
+        assert!(!verify_result(&stripped, &keypair.public_key_bytes(), &target).unwrap());
+    }
+
     #[test]
     fn test_verify_invalid_signature() {
         let keypair = generate_keypair();
@@ -123,6 +310,8 @@ mod tests {
             city: None,
             country: None,
             region: None,
+            source_peer_id: None,
+            certificate: None,
         };
 
         let is_valid =
@@ -131,4 +320,166 @@ mod tests {
 
         assert!(!is_valid);
     }
+
+    fn assignment_request(owner: &crate::crypto::KeyPair) -> HelperAssignmentRequest {
+        HelperAssignmentRequest {
+            assignment_id: Uuid::new_v4(),
+            monitor_id: Uuid::new_v4(),
+            owner_peer_id: owner.public_key_hex(),
+            owner_public_key: owner.public_key_bytes(),
+            helper_peer_id: "helper".to_string(),
+            target: "https://example.com".to_string(),
+            check_type: "https".to_string(),
+            interval_seconds: 60,
+            expires_at: None,
+            signature: Vec::new(),
+            schema: PayloadSchema::default(),
+        }
+    }
+
+    #[test]
+    fn test_verify_check_result() {
+        use ed25519_dalek::Signer;
+
+        let keypair = generate_keypair();
+        let mut result =
+            CheckResult::new(Uuid::new_v4(), "https://example.com".to_string(), "agent".into())
+                .success(100, Some(200));
+        let message = agent_result_bytes(&result).unwrap();
+        result.signature = Some(keypair.signing_key.sign(&message).to_bytes().to_vec());
+        assert!(verify_check_result(&result, &keypair.public_key_bytes()).unwrap());
+
+        // Another key, a changed field or a truncated signature fail
+        assert!(!verify_check_result(&result, &generate_keypair().public_key_bytes()).unwrap());
+        let mut changed = result.clone();
+        changed.latency_ms = Some(5);
+        assert!(!verify_check_result(&changed, &keypair.public_key_bytes()).unwrap());
+        changed = result.clone();
+        changed.error_message = Some("connection refused".to_string());
+        assert!(!verify_check_result(&changed, &keypair.public_key_bytes()).unwrap());
+        changed = result.clone();
+        changed.signature.as_mut().unwrap().pop();
+        assert!(!verify_check_result(&changed, &keypair.public_key_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_verify_assignment_request() {
+        use ed25519_dalek::Signer;
+
+        let sign_assignment_request =
+            |request: &HelperAssignmentRequest, keypair: &crate::crypto::KeyPair| {
+                let message_bytes = assignment_message_bytes(request).unwrap();
+                keypair.signing_key.sign(&message_bytes).to_bytes().to_vec()
+            };
+        let owner = generate_keypair();
+        let mut request = assignment_request(&owner);
+        request.signature = sign_assignment_request(&request, &owner);
+        assert!(verify_assignment_request(&request).unwrap());
+
+        // Requests built from an owner-side assignment verify too
+        let now = SystemTime::now();
+        let assignment = request.to_helper_assignment(now).unwrap();
+        let built = crate::crypto::sign_assignment_request(&assignment, &owner).unwrap();
+        assert!(verify_assignment_request(&built).unwrap());
+        assert_eq!(built.helper_peer_id, request.helper_peer_id);
+
+        // Tampering with the request invalidates the signature
+        let mut tampered = request.clone();
+        tampered.target = "http://10.0.0.1".to_string();
+        assert!(!verify_assignment_request(&tampered).unwrap());
+
+        // A forger can't claim someone else's owner ID with their own key
+        let forger = generate_keypair();
+        let mut forged = assignment_request(&forger);
+        forged.owner_peer_id = owner.public_key_hex();
+        forged.signature = sign_assignment_request(&forged, &forger);
+        assert!(!verify_assignment_request(&forged).unwrap());
+    }
+
+    #[test]
+    fn test_verify_location_claim() {
+        use crate::crypto::signing::sign_location_claim;
+        use crate::location::Location;
+
+        let keypair = generate_keypair();
+        let location = Location::manual(Some("Berlin".to_string()), Some("DE".to_string()), None);
+        let claim = sign_location_claim(&location, &keypair, SystemTime::now()).unwrap();
+        assert!(verify_location_claim(&claim, &keypair.public_key_bytes()).unwrap());
+
+        // Moving the claim elsewhere invalidates the signature
+        let mut moved = claim.clone();
+        moved.location.city = Some("Paris".to_string());
+        assert!(!verify_location_claim(&moved, &keypair.public_key_bytes()).unwrap());
+
+        // A claim can't be replayed by another peer
+        let other = generate_keypair();
+        assert!(!verify_location_claim(&claim, &other.public_key_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_verify_monitor_retraction() {
+        use crate::crypto::signing::sign_monitor_retraction;
+
+        let owner = generate_keypair();
+        let retraction =
+            sign_monitor_retraction(Uuid::new_v4(), &owner, SystemTime::now()).unwrap();
+        assert!(verify_monitor_retraction(&retraction).unwrap());
+
+        // A retraction can't be moved to another monitor
+        let mut moved = retraction.clone();
+        moved.monitor_id = Uuid::new_v4();
+        assert!(!verify_monitor_retraction(&moved).unwrap());
+
+        // Only the owner can retract its monitor's results
+        let forger = generate_keypair();
+        let mut forged =
+            sign_monitor_retraction(retraction.monitor_id, &forger, SystemTime::now()).unwrap();
+        forged.owner_peer_id = owner.public_key_hex();
+        assert!(!verify_monitor_retraction(&forged).unwrap());
+    }
+
+    #[test]
+    fn test_verify_membership_announcement() {
+        use crate::crypto::signing::sign_membership_announcement;
+
+        let peer = generate_keypair();
+        let announcement =
+            sign_membership_announcement("example.com", true, &peer, SystemTime::now()).unwrap();
+        assert!(verify_membership_announcement(&announcement).unwrap());
+
+        // A join can't be turned into a leave, or moved to another domain
+        let mut flipped = announcement.clone();
+        flipped.joined = false;
+        assert!(!verify_membership_announcement(&flipped).unwrap());
+        let mut moved = announcement.clone();
+        moved.host = "example.org".to_string();
+        assert!(!verify_membership_announcement(&moved).unwrap());
+
+        // A peer can only announce itself
+        let forger = generate_keypair();
+        let mut forged =
+            sign_membership_announcement("example.com", false, &forger, SystemTime::now()).unwrap();
+        forged.peer_id = peer.public_key_hex();
+        assert!(!verify_membership_announcement(&forged).unwrap());
+    }
+
+    #[test]
+    fn test_verify_identity_binding() {
+        use crate::crypto::signing::sign_identity_binding;
+
+        let keypair = generate_keypair();
+        let binding = sign_identity_binding("12D3KooWA", &keypair, SystemTime::now()).unwrap();
+        assert!(verify_identity_binding(&binding).unwrap());
+
+        // A binding can't be moved to another libp2p peer
+        let mut moved = binding.clone();
+        moved.libp2p_peer_id = "12D3KooWB".to_string();
+        assert!(!verify_identity_binding(&moved).unwrap());
+
+        // Nor can another key claim it
+        let forger = generate_keypair();
+        let mut forged = sign_identity_binding("12D3KooWA", &forger, SystemTime::now()).unwrap();
+        forged.app_peer_id = keypair.public_key_hex();
+        assert!(!verify_identity_binding(&forged).unwrap());
+    }
 }
@@ -2,7 +2,7 @@ use anyhow::Result;
 use libsql::Connection;
 
 /// Schema version - increment when making schema changes
-const SCHEMA_VERSION: i32 = 3;
+const SCHEMA_VERSION: i32 = 29;
 
 /// Run database migrations
 ///
@@ -46,6 +46,136 @@ pub async fn run_migrations(conn: &Connection) -> Result<()> {
         record_migration(conn, 3, "Add status pages, settings, and network tables").await?;
     }
 
+    if current_version < 4 {
+        run_migration_v4(conn).await?;
+        record_migration(conn, 4, "Add helper assignments table").await?;
+    }
+
+    if current_version < 5 {
+        run_migration_v5(conn).await?;
+        record_migration(conn, 5, "Add helper capacity settings and utilization stats").await?;
+    }
+
+    if current_version < 6 {
+        run_migration_v6(conn).await?;
+        record_migration(conn, 6, "Add audit events table").await?;
+    }
+
+    if current_version < 7 {
+        run_migration_v7(conn).await?;
+        record_migration(conn, 7, "Add per-monitor result retention").await?;
+    }
+
+    if current_version < 8 {
+        run_migration_v8(conn).await?;
+        record_migration(conn, 8, "Add owner sync state table").await?;
+    }
+
+    if current_version < 9 {
+        run_migration_v9(conn).await?;
+        record_migration(conn, 9, "Add result agreement table").await?;
+    }
+
+    if current_version < 10 {
+        run_migration_v10(conn).await?;
+        record_migration(conn, 10, "Add monitor visibility and result retraction").await?;
+    }
+
+    if current_version < 11 {
+        run_migration_v11(conn).await?;
+        record_migration(conn, 11, "Add multi-vantage probe results table").await?;
+    }
+
+    if current_version < 12 {
+        run_migration_v12(conn).await?;
+        record_migration(conn, 12, "Add notification channels").await?;
+    }
+
+    if current_version < 13 {
+        run_migration_v13(conn).await?;
+        record_migration(conn, 13, "Add monitor tags").await?;
+    }
+
+    if current_version < 14 {
+        run_migration_v14(conn).await?;
+        record_migration(conn, 14, "Add peer addresses, bans and result sources").await?;
+    }
+
+    if current_version < 15 {
+        run_migration_v15(conn).await?;
+        record_migration(conn, 15, "Add DHT debug operations").await?;
+    }
+
+    if current_version < 16 {
+        run_migration_v16(conn).await?;
+        record_migration(conn, 16, "Add last result time to helper assignments").await?;
+    }
+
+    if current_version < 17 {
+        run_migration_v17(conn).await?;
+        record_migration(conn, 17, "Add restore time to results").await?;
+    }
+
+    if current_version < 18 {
+        run_migration_v18(conn).await?;
+        record_migration(conn, 18, "Add seen-message cache counts to network stats").await?;
+    }
+
+    if current_version < 19 {
+        run_migration_v19(conn).await?;
+        record_migration(conn, 19, "Add hourly network stats").await?;
+    }
+
+    if current_version < 20 {
+        run_migration_v20(conn).await?;
+        record_migration(conn, 20, "Index results for their lookups").await?;
+    }
+
+    if current_version < 21 {
+        run_migration_v21(conn).await?;
+        record_migration(conn, 21, "Add peer greylist counts").await?;
+    }
+
+    if current_version < 22 {
+        run_migration_v22(conn).await?;
+        record_migration(conn, 22, "Add deleted monitors").await?;
+    }
+
+    if current_version < 23 {
+        run_migration_v23(conn).await?;
+        record_migration(conn, 23, "Add maintenance windows").await?;
+    }
+
+    if current_version < 24 {
+        run_migration_v24(conn).await?;
+        record_migration(conn, 24, "Add followed targets").await?;
+    }
+
+    if current_version < 25 {
+        run_migration_v25(conn).await?;
+        record_migration(conn, 25, "Add notification routes").await?;
+    }
+
+    if current_version < 26 {
+        run_migration_v26(conn).await?;
+        record_migration(conn, 26, "Add community domains").await?;
+    }
+
+    if current_version < 27 {
+        run_migration_v27(conn).await?;
+        record_migration(conn, 27, "Add heartbeat grace").await?;
+    }
+
+    if current_version < 28 {
+        run_migration_v28(conn).await?;
+        record_migration(conn, 28, "Add result rollups").await?;
+    }
+
+    if current_version < 29 {
+        run_migration_v29(conn).await?;
+        record_migration(conn, 29, "Add peer result certificates").await?;
+    }
+
     tracing::info!(
         "Database migrations completed successfully (now at version {})",
         SCHEMA_VERSION
@@ -442,3 +572,667 @@ async fn run_migration_v3(conn: &Connection) -> Result<()> {
     tracing::info!("Added status pages, settings, incidents, and network tables");
     Ok(())
 }
+
+/// Migration v4: Add helper assignments table
+/// Stores both owner-side assignments (peers helping us) and helper-side duties
+/// (monitors we check for other owners) so they survive restarts
+async fn run_migration_v4(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS helper_assignments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            assignment_id TEXT NOT NULL UNIQUE,
+            role TEXT NOT NULL,
+            monitor_uuid TEXT NOT NULL,
+            owner_peer_id TEXT NOT NULL,
+            helper_peer_id TEXT NOT NULL,
+            target TEXT NOT NULL,
+            check_type TEXT NOT NULL,
+            interval_seconds INTEGER NOT NULL DEFAULT 60,
+            status TEXT NOT NULL DEFAULT 'pending',
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            expires_at INTEGER
+        )",
+        (),
+    )
+    .await?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_helper_assignments_role ON helper_assignments(role)",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_helper_assignments_monitor ON \
+         helper_assignments(monitor_uuid)",
+        (),
+    )
+    .await?;
+
+    tracing::info!("Added helper assignments table");
+    Ok(())
+}
+
+/// Migration v5: Add helper capacity settings and utilization stats
+/// Capacity limits live in `settings` so they can be changed while running;
+/// utilization is recorded alongside the other network stats
+async fn run_migration_v5(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE network_stats ADD COLUMN helper_assignments INTEGER DEFAULT 0", ())
+        .await?;
+    conn.execute(
+        "ALTER TABLE network_stats ADD COLUMN helper_max_assignments INTEGER DEFAULT 0",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "ALTER TABLE network_stats ADD COLUMN helper_checks_last_hour INTEGER DEFAULT 0",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "ALTER TABLE network_stats ADD COLUMN helper_max_checks_per_hour INTEGER DEFAULT 0",
+        (),
+    )
+    .await?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    for (key, value) in
+        [("helper_max_assignments", "10"), ("helper_max_checks_per_owner_per_hour", "100")]
+    {
+        conn.execute(
+            "INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES (?, ?, ?)",
+            libsql::params![key, value, now],
+        )
+        .await?;
+    }
+
+    tracing::info!("Added helper capacity settings and utilization stats");
+    Ok(())
+}
+
+/// Migration v6: Append-only audit log
+async fn run_migration_v6(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS audit_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            peer_id TEXT,
+            message TEXT NOT NULL
+        )",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_audit_events_timestamp ON audit_events(timestamp)",
+        (),
+    )
+    .await?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_audit_events_kind ON audit_events(kind)", ())
+        .await?;
+
+    // Events are only ever appended; old rows are removed by retention
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS audit_events_append_only
+         BEFORE UPDATE ON audit_events
+         BEGIN
+            SELECT RAISE(ABORT, 'audit_events is append-only');
+         END",
+        (),
+    )
+    .await?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES (?, ?, ?)",
+        libsql::params!["audit_retention_days", "90", now],
+    )
+    .await?;
+
+    tracing::info!("Created audit events table");
+    Ok(())
+}
+
+/// Migration v7: Per-monitor result retention
+async fn run_migration_v7(conn: &Connection) -> Result<()> {
+    // NULL uses the global `result_retention_days`; 0 keeps everything
+    conn.execute("ALTER TABLE monitors ADD COLUMN retention_days INTEGER", ())
+        .await?;
+
+    tracing::info!("Added per-monitor retention column");
+    Ok(())
+}
+
+/// Migration v8: Owner sync progress
+async fn run_migration_v8(conn: &Connection) -> Result<()> {
+    // Single row, rewritten by the service as a sync round progresses
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS owner_sync_state (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            running INTEGER NOT NULL DEFAULT 0,
+            monitors_total INTEGER NOT NULL DEFAULT 0,
+            monitors_synced INTEGER NOT NULL DEFAULT 0,
+            records_found INTEGER NOT NULL DEFAULT 0,
+            failures INTEGER NOT NULL DEFAULT 0,
+            last_started_at INTEGER,
+            last_completed_at INTEGER,
+            next_attempt_at INTEGER,
+            last_error TEXT
+        )",
+        (),
+    )
+    .await?;
+
+    tracing::info!("Created owner sync state table");
+    Ok(())
+}
+
+/// Migration v9: Local vs peer result agreement per time bucket
+async fn run_migration_v9(conn: &Connection) -> Result<()> {
+    // `timestamp` is the bucket start, so retention sweeps treat rows like results
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS result_agreement (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            monitor_uuid TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            bucket_seconds INTEGER NOT NULL,
+            local_status TEXT NOT NULL,
+            peers_total INTEGER NOT NULL,
+            peers_agreeing INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            UNIQUE(monitor_uuid, timestamp),
+            FOREIGN KEY (monitor_uuid) REFERENCES monitors(uuid) ON DELETE CASCADE
+        )",
+        (),
+    )
+    .await?;
+
+    tracing::info!("Created result agreement table");
+    Ok(())
+}
+
+/// Migration v10: Monitor visibility and retraction of shared results
+async fn run_migration_v10(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE monitors ADD COLUMN visibility TEXT NOT NULL DEFAULT 'public'", ())
+        .await?;
+    // Set once a private monitor's earlier public results were retracted
+    conn.execute("ALTER TABLE monitors ADD COLUMN retracted_at INTEGER", ()).await?;
+    conn.execute(
+        "ALTER TABLE monitor_results ADD COLUMN retracted INTEGER NOT NULL DEFAULT 0",
+        (),
+    )
+    .await?;
+    conn.execute("ALTER TABLE peer_results ADD COLUMN retracted INTEGER NOT NULL DEFAULT 0", ())
+        .await?;
+
+    tracing::info!("Added monitor visibility and result retraction columns");
+    Ok(())
+}
+
+/// Migration v11: On-demand probes of a monitor from several peers
+async fn run_migration_v11(conn: &Connection) -> Result<()> {
+    // `timestamp` is when the probe was requested, so retention sweeps treat
+    // rows like results; `vantages` holds each peer's answer as JSON
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS multi_vantage_results (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            uuid TEXT NOT NULL UNIQUE,
+            monitor_uuid TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            peers_requested INTEGER NOT NULL,
+            region TEXT,
+            state TEXT NOT NULL DEFAULT 'pending',
+            status TEXT,
+            peers_responded INTEGER NOT NULL DEFAULT 0,
+            peers_up INTEGER NOT NULL DEFAULT 0,
+            latency_min_ms INTEGER,
+            latency_median_ms INTEGER,
+            latency_max_ms INTEGER,
+            vantages TEXT NOT NULL DEFAULT '[]',
+            error TEXT,
+            completed_at INTEGER,
+            FOREIGN KEY (monitor_uuid) REFERENCES monitors(uuid) ON DELETE CASCADE
+        )",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_multi_vantage_monitor ON \
+         multi_vantage_results(monitor_uuid, timestamp DESC)",
+        (),
+    )
+    .await?;
+
+    tracing::info!("Created multi-vantage results table");
+    Ok(())
+}
+
+/// Migration v12: Notification channels and the monitors they are attached to
+async fn run_migration_v12(conn: &Connection) -> Result<()> {
+    // `target` is the URL notifications are posted to; the last test-fire is
+    // kept so the TUI can show whether a channel works
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS notification_channels (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            uuid TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            target TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            last_test_at INTEGER,
+            last_test_error TEXT
+        )",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS monitor_notification_channels (
+            monitor_uuid TEXT NOT NULL,
+            channel_uuid TEXT NOT NULL,
+            PRIMARY KEY (monitor_uuid, channel_uuid),
+            FOREIGN KEY (monitor_uuid) REFERENCES monitors(uuid) ON DELETE CASCADE,
+            FOREIGN KEY (channel_uuid) REFERENCES notification_channels(uuid) ON DELETE CASCADE
+        )",
+        (),
+    )
+    .await?;
+
+    tracing::info!("Created notification channel tables");
+    Ok(())
+}
+
+/// Migration v13: Tags for grouping monitors
+async fn run_migration_v13(conn: &Connection) -> Result<()> {
+    // Comma-separated, normalized by `Monitor::parse_tags`
+    conn.execute("ALTER TABLE monitors ADD COLUMN tags TEXT NOT NULL DEFAULT ''", ())
+        .await?;
+
+    tracing::info!("Added monitor tags column");
+    Ok(())
+}
+
+/// Migration v14: Peer addresses and bans, the sender of each peer result, and
+/// probes limited to one peer
+async fn run_migration_v14(conn: &Connection) -> Result<()> {
+    // JSON array of the addresses the peer was last reached on
+    conn.execute("ALTER TABLE peers ADD COLUMN addresses TEXT NOT NULL DEFAULT '[]'", ())
+        .await?;
+    conn.execute("ALTER TABLE peers ADD COLUMN banned_at INTEGER", ()).await?;
+
+    // The libp2p peer that delivered a result, next to the key that signed it
+    conn.execute("ALTER TABLE peer_results ADD COLUMN source_peer_id TEXT", ())
+        .await?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_peer_results_source ON peer_results(source_peer_id, \
+         timestamp)",
+        (),
+    )
+    .await?;
+
+    conn.execute("ALTER TABLE multi_vantage_results ADD COLUMN vantage_peer_id TEXT", ())
+        .await?;
+
+    tracing::info!("Added peer addresses, bans and result sources");
+    Ok(())
+}
+
+/// Migration v15: DHT lookups and publishes requested from the TUI
+async fn run_migration_v15(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS dht_operations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            uuid TEXT NOT NULL UNIQUE,
+            kind TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT,
+            state TEXT NOT NULL DEFAULT 'pending',
+            records TEXT NOT NULL DEFAULT '[]',
+            error TEXT,
+            timestamp INTEGER NOT NULL,
+            completed_at INTEGER
+        )",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_dht_operations_state ON dht_operations(state, timestamp)",
+        (),
+    )
+    .await?;
+
+    tracing::info!("Created DHT operations table");
+    Ok(())
+}
+
+/// Migration v16: When each helper assignment last carried a result, so both
+/// sides can see whether it is still working
+async fn run_migration_v16(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE helper_assignments ADD COLUMN last_result_at INTEGER", ())
+        .await?;
+
+    tracing::info!("Added last result time to helper assignments");
+    Ok(())
+}
+
+/// Migration v17: When a result was restored from the archive, so restored
+/// results are kept for a while and not archived twice
+async fn run_migration_v17(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE monitor_results ADD COLUMN restored_at INTEGER", ())
+        .await?;
+
+    tracing::info!("Added restore time to results");
+    Ok(())
+}
+
+/// Migration v18: Lookups and hits of the seen-message cache in network stats
+async fn run_migration_v18(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE network_stats ADD COLUMN seen_lookups INTEGER DEFAULT 0", ())
+        .await?;
+    conn.execute("ALTER TABLE network_stats ADD COLUMN seen_hits INTEGER DEFAULT 0", ())
+        .await?;
+
+    tracing::info!("Added seen-message cache counts to network stats");
+    Ok(())
+}
+
+/// Migration v19: Hourly aggregates of network stats snapshots past their
+/// full-resolution window; counters hold the highest value of the hour
+async fn run_migration_v19(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS network_stats_hourly (
+            hour INTEGER PRIMARY KEY,
+            samples INTEGER NOT NULL,
+            avg_online_peers REAL NOT NULL,
+            max_online_peers INTEGER NOT NULL,
+            total_peers INTEGER NOT NULL,
+            checks_performed INTEGER NOT NULL,
+            checks_received INTEGER NOT NULL,
+            bandwidth_used_mb INTEGER NOT NULL,
+            helper_assignments INTEGER NOT NULL
+        )",
+        (),
+    )
+    .await?;
+
+    tracing::info!("Added hourly network stats");
+    Ok(())
+}
+
+/// Migration v20: Composite indexes matching the result lookups, replacing
+/// the single-column indexes they cover
+async fn run_migration_v20(conn: &Connection) -> Result<()> {
+    // Duplicate checks for incoming results and retractions of an owner's results
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_peer_results_monitor_peer ON peer_results(monitor_uuid, \
+         peer_id, timestamp)",
+        (),
+    )
+    .await?;
+    // Verified peer results of a monitor over a time range, for agreement
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_peer_results_monitor_verified ON \
+         peer_results(monitor_uuid, verified, timestamp)",
+        (),
+    )
+    .await?;
+    // Statuses and latencies of a monitor over a time range, read from the
+    // index alone for uptime and rollups
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_monitor_results_uptime ON monitor_results(monitor_uuid, \
+         timestamp, status, latency_ms)",
+        (),
+    )
+    .await?;
+
+    // Prefixes of the composite indexes, or too unselective to be used
+    for index in [
+        "idx_monitor_results_monitor_uuid",
+        "idx_monitor_results_status",
+        "idx_peer_results_monitor_uuid",
+        "idx_peer_results_verified",
+    ] {
+        conn.execute(&format!("DROP INDEX IF EXISTS {index}"), ()).await?;
+    }
+
+    tracing::info!("Indexed results for their lookups");
+    Ok(())
+}
+
+/// Migration v21: How often each peer was greylisted for flooding, and when
+/// it last was
+async fn run_migration_v21(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE peers ADD COLUMN greylist_count INTEGER NOT NULL DEFAULT 0", ())
+        .await?;
+    conn.execute("ALTER TABLE peers ADD COLUMN greylisted_at INTEGER", ()).await?;
+
+    tracing::info!("Added peer greylist counts");
+    Ok(())
+}
+
+/// Migration v22: When each monitor was deleted, so nodes of a cluster delete
+/// it too instead of restoring it from the others
+async fn run_migration_v22(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS deleted_monitors (
+            uuid TEXT PRIMARY KEY,
+            deleted_at INTEGER NOT NULL
+        )",
+        (),
+    )
+    .await?;
+
+    tracing::info!("Added deleted monitors");
+    Ok(())
+}
+
+/// Migration v23: Maintenance windows, and a flag on results checked during one
+async fn run_migration_v23(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS maintenance_windows (
+            uuid TEXT PRIMARY KEY,
+            monitor_uuid TEXT,
+            starts_at INTEGER NOT NULL,
+            ends_at INTEGER NOT NULL,
+            reason TEXT NOT NULL DEFAULT '',
+            created_at INTEGER NOT NULL
+        )",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "ALTER TABLE monitor_results ADD COLUMN maintenance INTEGER NOT NULL DEFAULT 0",
+        (),
+    )
+    .await?;
+    // Uptime and rollups skip maintenance results; keep reading them from the
+    // index alone
+    conn.execute("DROP INDEX IF EXISTS idx_monitor_results_uptime", ()).await?;
+    conn.execute(
+        "CREATE INDEX idx_monitor_results_uptime ON monitor_results(monitor_uuid, timestamp, \
+         status, latency_ms, maintenance)",
+        (),
+    )
+    .await?;
+
+    tracing::info!("Added maintenance windows");
+    Ok(())
+}
+
+/// Migration v24: Hosts followed without checking them, and the monitors of
+/// other nodes found checking them
+async fn run_migration_v24(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS follows (
+            host TEXT PRIMARY KEY,
+            created_at INTEGER NOT NULL
+        )",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS follow_monitors (
+            host TEXT NOT NULL,
+            monitor_uuid TEXT NOT NULL,
+            first_seen INTEGER NOT NULL,
+            PRIMARY KEY (host, monitor_uuid)
+        )",
+        (),
+    )
+    .await?;
+
+    tracing::info!("Added followed targets");
+    Ok(())
+}
+
+/// Migration v25: Notification routes for monitors, tags and the default,
+/// taking over the channels attached to monitors
+async fn run_migration_v25(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS notification_routes (
+            scope TEXT PRIMARY KEY,
+            escalate_after_secs INTEGER,
+            updated_at INTEGER NOT NULL
+        )",
+        (),
+    )
+    .await?;
+    // `escalation` tells the channels alerted when escalating from the others
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS notification_route_channels (
+            scope TEXT NOT NULL,
+            channel_uuid TEXT NOT NULL,
+            escalation INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (scope, channel_uuid, escalation)
+        )",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "INSERT OR IGNORE INTO notification_routes (scope, updated_at)
+         SELECT DISTINCT 'monitor:' || monitor_uuid, strftime('%s', 'now')
+         FROM monitor_notification_channels",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "INSERT OR IGNORE INTO notification_route_channels (scope, channel_uuid)
+         SELECT 'monitor:' || monitor_uuid, channel_uuid FROM monitor_notification_channels",
+        (),
+    )
+    .await?;
+    conn.execute("DROP TABLE monitor_notification_channels", ()).await?;
+
+    tracing::info!("Added notification routes");
+    Ok(())
+}
+
+/// Migration v26: Community domains joined from here, and the membership
+/// announcements of every peer
+async fn run_migration_v26(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS community_domains (
+            host TEXT PRIMARY KEY,
+            joined INTEGER NOT NULL,
+            monitor_uuid TEXT,
+            updated_at INTEGER NOT NULL
+        )",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS group_memberships (
+            host TEXT NOT NULL,
+            peer_id TEXT NOT NULL,
+            joined INTEGER NOT NULL,
+            announced_at INTEGER NOT NULL,
+            PRIMARY KEY (host, peer_id)
+        )",
+        (),
+    )
+    .await?;
+
+    tracing::info!("Added community domains");
+    Ok(())
+}
+
+/// Migration v27: How late a heartbeat monitor's ping may be
+async fn run_migration_v27(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE monitors ADD COLUMN grace_seconds INTEGER NOT NULL DEFAULT 60", ())
+        .await?;
+
+    tracing::info!("Added heartbeat grace column");
+    Ok(())
+}
+
+/// Migration v28: Hourly and daily rollups of our results, kept past the
+/// results themselves, and the index finding results that still need rolling
+/// up
+async fn run_migration_v28(conn: &Connection) -> Result<()> {
+    for table in ["result_rollups_hourly", "result_rollups_daily"] {
+        conn.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {table} (
+                    monitor_uuid TEXT NOT NULL,
+                    bucket_start INTEGER NOT NULL,
+                    checks INTEGER NOT NULL,
+                    up INTEGER NOT NULL,
+                    down INTEGER NOT NULL,
+                    avg_latency_ms INTEGER,
+                    p95_latency_ms INTEGER,
+                    max_latency_ms INTEGER,
+                    rolled_up_at INTEGER NOT NULL,
+                    PRIMARY KEY (monitor_uuid, bucket_start)
+                )"
+            ),
+            (),
+        )
+        .await?;
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_result_rollups_hourly_bucket ON \
+         result_rollups_hourly(bucket_start)",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_result_rollups_hourly_rolled_up ON \
+         result_rollups_hourly(rolled_up_at)",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_monitor_results_created_at ON monitor_results(created_at)",
+        (),
+    )
+    .await?;
+
+    tracing::info!("Added result rollups");
+    Ok(())
+}
+
+/// Migration v29: Certificate details peers report with their results, kept
+/// so the stored result's signature can still be checked
+async fn run_migration_v29(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE peer_results ADD COLUMN certificate TEXT", ()).await?;
+
+    tracing::info!("Added peer result certificate column");
+    Ok(())
+}
+
+/// Refresh the statistics the query planner picks indexes by
+///
+/// Runs on every start; the analysis limit keeps it quick on large databases
+/// by sampling each index instead of reading it whole.
+pub async fn analyze(conn: &Connection) -> Result<()> {
+    conn.execute_batch("PRAGMA analysis_limit = 1000; ANALYZE;").await?;
+    Ok(())
+}
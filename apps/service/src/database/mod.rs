@@ -1,16 +1,47 @@
 pub mod migrations;
 pub mod models;
+pub mod postgres;
 /// Database abstraction layer
 ///
 /// This module provides a unified interface for database operations,
 /// supporting both LibSQL (SQLite) and PostgreSQL backends.
 pub mod repository;
 
+pub use postgres::PostgresDatabaseImpl;
 pub use repository::{Database, DatabaseImpl};
 
 use anyhow::Result;
+use std::sync::Arc;
 
-/// Initialize database with schema
+use crate::config::{DatabaseBackend, DatabaseConfig, DatabasePoolConfig};
+use crate::pool::LibsqlPool;
+
+/// Initialize database with schema and refresh its query planner statistics
 pub async fn initialize_database(conn: &libsql::Connection) -> Result<()> {
-    migrations::run_migrations(conn).await
+    migrations::run_migrations(conn).await?;
+    if let Err(e) = migrations::analyze(conn).await {
+        tracing::warn!("Failed to analyze the database: {}", e);
+    }
+    Ok(())
+}
+
+/// Open the database `database.backend` selects and bring its schema up to date
+///
+/// The libsql backend runs on `pool`; the postgres backend connects to
+/// `database.url` with a pool of its own, leaving `pool` unused.
+pub async fn open(
+    database: &DatabaseConfig,
+    pool_config: &DatabasePoolConfig,
+    pool: LibsqlPool,
+) -> Result<Arc<dyn Database>> {
+    match database.backend {
+        DatabaseBackend::Libsql => {
+            initialize_database(&*pool.get().await?).await?;
+            Ok(Arc::new(DatabaseImpl::new_from_pool(pool)))
+        }
+        DatabaseBackend::Postgres => {
+            tracing::info!("Connecting to the PostgreSQL database...");
+            Ok(Arc::new(PostgresDatabaseImpl::connect(database, pool_config).await?))
+        }
+    }
 }
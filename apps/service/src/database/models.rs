@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
-use crate::monitoring::types::MonitorStatus;
+use crate::monitoring::types::{CertificateInfo, MonitorStatus};
 
 /// Monitor model - represents a monitoring target
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,7 +15,22 @@ pub struct Monitor {
     pub check_type: String,
     pub interval_seconds: u64,
     pub timeout_seconds: u64,
+    /// Seconds a heartbeat monitor's ping may be late before it is down
+    #[serde(default = "Monitor::default_grace_seconds")]
+    pub grace_seconds: u64,
     pub enabled: bool,
+    /// Days of results to keep instead of the global retention; 0 keeps everything
+    pub retention_days: Option<u64>,
+    /// Whether results are shared with the public network
+    pub visibility: MonitorVisibility,
+    /// Lowercase tags grouping monitors, e.g. "prod" or "eu-west"
+    pub tags: Vec<String>,
+    /// HTTP status codes or ranges counted as up, e.g. "200" or "200-299"
+    #[serde(default)]
+    pub expected_status_codes: Vec<String>,
+    /// Headers sent with HTTP checks
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
     pub created_at: SystemTime,
     pub updated_at: SystemTime,
 }
@@ -31,12 +47,51 @@ impl Monitor {
             check_type,
             interval_seconds: 30,
             timeout_seconds: 10,
+            grace_seconds: Self::default_grace_seconds(),
             enabled: true,
+            retention_days: None,
+            visibility: MonitorVisibility::default(),
+            tags: Vec::new(),
+            expected_status_codes: Vec::new(),
+            headers: BTreeMap::new(),
             created_at: now,
             updated_at: now,
         }
     }
 
+    /// Check type of monitors the monitored system pings instead of being checked
+    pub const HEARTBEAT: &'static str = "heartbeat";
+
+    fn default_grace_seconds() -> u64 {
+        60
+    }
+
+    /// Whether the monitored system pings this monitor; its target is the
+    /// token it pings with
+    pub fn is_heartbeat(&self) -> bool {
+        self.check_type == Self::HEARTBEAT
+    }
+
+    /// A random token for a heartbeat monitor to be pinged with
+    pub fn heartbeat_token() -> String {
+        let mut bytes = [0u8; 16];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut bytes);
+        hex::encode(bytes)
+    }
+
+    /// Tags from comma or space separated input, lowercased and without
+    /// duplicates; a leading '#' is dropped
+    pub fn parse_tags(input: &str) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in input.split(|c: char| c == ',' || c.is_whitespace()) {
+            let tag = tag.trim().trim_start_matches('#').to_lowercase();
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
     /// Convert SystemTime to Unix timestamp
     pub fn timestamp_to_i64(time: SystemTime) -> i64 {
         time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
@@ -48,6 +103,40 @@ impl Monitor {
     }
 }
 
+/// Who a monitor's results are shared with
+///
+/// Results of public monitors are gossiped to the network; private monitors
+/// are only checked by ourselves and helpers we assign. Making a public monitor
+/// private retracts what was shared before.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MonitorVisibility {
+    #[default]
+    Public,
+    Private,
+}
+
+impl std::fmt::Display for MonitorVisibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MonitorVisibility::Public => write!(f, "public"),
+            MonitorVisibility::Private => write!(f, "private"),
+        }
+    }
+}
+
+impl std::str::FromStr for MonitorVisibility {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "public" => Ok(MonitorVisibility::Public),
+            "private" => Ok(MonitorVisibility::Private),
+            other => Err(anyhow::anyhow!("Unknown monitor visibility: {}", other)),
+        }
+    }
+}
+
 /// MonitorResult model - represents a monitoring check result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitorResult {
@@ -64,6 +153,9 @@ pub struct MonitorResult {
     pub city: Option<String>,
     pub country: Option<String>,
     pub region: Option<String>,
+    /// Checked during a maintenance window; left out of uptime
+    #[serde(default)]
+    pub maintenance: bool,
 }
 
 impl MonitorResult {
@@ -85,6 +177,7 @@ impl MonitorResult {
             city: location.city,
             country: location.country,
             region: location.region,
+            maintenance: check_result.maintenance,
         }
     }
 }
@@ -106,6 +199,10 @@ pub struct PeerResult {
     pub city: Option<String>,
     pub country: Option<String>,
     pub region: Option<String>,
+    /// libp2p peer that delivered the result; `peer_id` is the key that signed it
+    pub source_peer_id: Option<String>,
+    /// Certificate details the peer reported; covered by the signature
+    pub certificate: Option<CertificateInfo>,
 }
 
 impl PeerResult {
@@ -126,9 +223,12 @@ impl PeerResult {
             signature,
             verified: false, // Will be verified later
             created_at: p2p_result.received_at,
-            city: None, // TODO: Add geolocation lookup
+            // Filled in from the sender's location claim once it is verified
+            city: None,
             country: None,
             region: None,
+            source_peer_id: None,
+            certificate: p2p_result.result.certificate.clone(),
         }
         .into()
     }
@@ -147,9 +247,25 @@ pub struct Peer {
     pub location_city: Option<String>,
     pub location_region: Option<String>,
     pub location_country: Option<String>,
+    /// Addresses the peer was last reached on, most recent first
+    pub addresses: Vec<String>,
+    /// When an operator banned the peer; results and requests from banned
+    /// peers are dropped
+    pub banned_at: Option<SystemTime>,
+    /// How often the peer was greylisted for flooding us, and when it last was
+    pub greylist_count: u32,
+    pub greylisted_at: Option<SystemTime>,
 }
 
 impl Peer {
+    /// Contribution score a peer earns for each verified check it shares for
+    /// a community domain it takes part in
+    pub const CONTRIBUTION_PER_CHECK: f64 = 0.01;
+
+    /// Contribution score a peer loses each time it is greylisted for
+    /// flooding us, as much as a hundred shared checks earn
+    pub const GREYLIST_PENALTY: f64 = 1.0;
+
     pub fn new_online(peer_id: String, now: SystemTime) -> Self {
         Self {
             peer_id,
@@ -162,6 +278,10 @@ impl Peer {
             location_city: None,
             location_region: None,
             location_country: None,
+            addresses: Vec::new(),
+            banned_at: None,
+            greylist_count: 0,
+            greylisted_at: None,
         }
     }
 }
@@ -175,4 +295,1202 @@ pub struct NetworkStats {
     pub checks_performed: i64,
     pub checks_received: i64,
     pub bandwidth_used_mb: i64,
+    /// Helper duties currently accepted for other owners
+    pub helper_assignments: i64,
+    /// Configured maximum number of helper duties
+    pub helper_max_assignments: i64,
+    /// Checks run in the last hour for the busiest owner
+    pub helper_checks_last_hour: i64,
+    /// Configured maximum checks per owner per hour
+    pub helper_max_checks_per_hour: i64,
+    /// Gossip results looked up in the seen-message cache since start
+    pub seen_lookups: i64,
+    /// Lookups that found a duplicate
+    pub seen_hits: i64,
+}
+
+impl NetworkStats {
+    /// Share of gossip results that were duplicates; `None` before any arrived
+    pub fn seen_hit_rate(&self) -> Option<f64> {
+        (self.seen_lookups > 0).then(|| self.seen_hits as f64 / self.seen_lookups as f64)
+    }
+}
+
+/// Which side of a helper relationship this node is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssignmentRole {
+    /// We own the monitor and asked another peer to help check it
+    Owner,
+    /// Another peer owns the monitor and we accepted to check it for them
+    Helper,
+}
+
+impl std::fmt::Display for AssignmentRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssignmentRole::Owner => write!(f, "owner"),
+            AssignmentRole::Helper => write!(f, "helper"),
+        }
+    }
+}
+
+impl std::str::FromStr for AssignmentRole {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "owner" => Ok(AssignmentRole::Owner),
+            "helper" => Ok(AssignmentRole::Helper),
+            other => Err(anyhow::anyhow!("Unknown assignment role: {}", other)),
+        }
+    }
+}
+
+/// Helper assignment persisted so owner assignments and accepted helper duties
+/// survive restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelperAssignment {
+    pub assignment_id: Uuid,
+    pub role: AssignmentRole,
+    pub monitor_uuid: Uuid,
+    pub owner_peer_id: String,
+    pub helper_peer_id: String,
+    pub target: String,
+    pub check_type: String,
+    pub interval_seconds: u64,
+    /// "pending" until the other side confirms, then "accepted"
+    pub status: String,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+    pub expires_at: Option<SystemTime>,
+    /// When a result under the assignment was last received (owner side) or
+    /// sent (helper side)
+    pub last_result_at: Option<SystemTime>,
+}
+
+impl HelperAssignment {
+    /// Whether the assignment has passed its expiry time
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Whether the other side has confirmed the assignment
+    pub fn is_accepted(&self) -> bool {
+        self.status == "accepted"
+    }
+
+    /// Short hash of the target, to tell duties apart without showing the
+    /// owner's target
+    pub fn target_hash(&self) -> String {
+        use sha2::{Digest, Sha256};
+        hex::encode(&Sha256::digest(self.target.as_bytes())[..6])
+    }
+}
+
+/// What an audit event records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditKind {
+    /// A peer message failed signature verification
+    SignatureFailure,
+    /// Work from a peer was refused because a limit was reached
+    RateLimited,
+    /// A helper request was refused by the probe target policy
+    PolicyRejected,
+    /// An operator changed monitors or settings
+    AdminAction,
+    /// A peer was banned
+    PeerBanned,
+}
+
+impl std::fmt::Display for AuditKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditKind::SignatureFailure => write!(f, "signature_failure"),
+            AuditKind::RateLimited => write!(f, "rate_limited"),
+            AuditKind::PolicyRejected => write!(f, "policy_rejected"),
+            AuditKind::AdminAction => write!(f, "admin_action"),
+            AuditKind::PeerBanned => write!(f, "peer_banned"),
+        }
+    }
+}
+
+impl std::str::FromStr for AuditKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "signature_failure" => Ok(AuditKind::SignatureFailure),
+            "rate_limited" => Ok(AuditKind::RateLimited),
+            "policy_rejected" => Ok(AuditKind::PolicyRejected),
+            "admin_action" => Ok(AuditKind::AdminAction),
+            "peer_banned" => Ok(AuditKind::PeerBanned),
+            other => Err(anyhow::anyhow!("Unknown audit event kind: {}", other)),
+        }
+    }
+}
+
+/// Security-relevant event kept in the append-only audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub id: Option<i64>,
+    pub timestamp: SystemTime,
+    pub kind: AuditKind,
+    /// Peer the event concerns, if any
+    pub peer_id: Option<String>,
+    pub message: String,
+}
+
+impl AuditEvent {
+    pub fn new(kind: AuditKind, peer_id: Option<String>, message: impl Into<String>) -> Self {
+        Self { id: None, timestamp: SystemTime::now(), kind, peer_id, message: message.into() }
+    }
+
+    /// An operator action taken through the CLI or TUI
+    pub fn admin(message: impl Into<String>) -> Self {
+        Self::new(AuditKind::AdminAction, None, message)
+    }
+}
+
+/// A node setting from the settings table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Setting {
+    pub key: String,
+    pub value: String,
+    pub updated_at: SystemTime,
+}
+
+/// Progress of pulling our monitors' results back out of the DHT
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnerSyncState {
+    /// Whether a sync round is in progress
+    pub running: bool,
+    pub monitors_total: u64,
+    pub monitors_synced: u64,
+    /// Results found in the DHT during the current or last round
+    pub records_found: u64,
+    /// Consecutive failed rounds
+    pub failures: u32,
+    pub last_started_at: Option<SystemTime>,
+    pub last_completed_at: Option<SystemTime>,
+    pub next_attempt_at: Option<SystemTime>,
+    pub last_error: Option<String>,
+}
+
+/// How many verified peers agreed with our own result for a monitor in one time bucket
+///
+/// Agreement is about availability: degraded counts as up, since latency
+/// depends on where a peer checks from. Peers that could not determine a
+/// status are left out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResultAgreement {
+    pub monitor_uuid: Uuid,
+    /// Start of the bucket
+    pub bucket_start: SystemTime,
+    pub bucket_seconds: u64,
+    /// Our latest status in the bucket
+    pub local_status: MonitorStatus,
+    /// Distinct verified peers that checked the monitor in the bucket
+    pub peers_total: u32,
+    /// Peers whose latest status agrees with ours
+    pub peers_agreeing: u32,
+    pub updated_at: SystemTime,
+}
+
+impl ResultAgreement {
+    /// Human readable summary, e.g. "4/5 peers agree up"
+    pub fn summary(&self) -> String {
+        if self.peers_total == 0 {
+            return "no peer confirmation".to_string();
+        }
+        format!("{}/{} peers agree {}", self.peers_agreeing, self.peers_total, self.local_status)
+    }
+}
+
+/// Our own results of a monitor summarised over a time bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultRollup {
+    pub bucket_start: SystemTime,
+    pub checks: u64,
+    /// Checks that reached the target (up or degraded)
+    pub up: u64,
+    pub down: u64,
+    pub avg_latency_ms: Option<u64>,
+    /// Only known for stored rollups; the ones computed on the fly leave it out
+    pub p95_latency_ms: Option<u64>,
+    pub max_latency_ms: Option<u64>,
+}
+
+impl ResultRollup {
+    /// Summarise the status and latency of the results in a bucket
+    pub fn from_results(
+        bucket_start: SystemTime,
+        results: &[(MonitorStatus, Option<u64>)],
+    ) -> Self {
+        let mut latencies: Vec<u64> = results.iter().filter_map(|(_, latency)| *latency).collect();
+        latencies.sort_unstable();
+        let avg_latency_ms = (!latencies.is_empty()).then(|| {
+            (latencies.iter().sum::<u64>() as f64 / latencies.len() as f64).round() as u64
+        });
+        // Nearest rank
+        let p95_latency_ms = (!latencies.is_empty())
+            .then(|| latencies[(latencies.len() * 95).div_ceil(100).saturating_sub(1)]);
+
+        Self {
+            bucket_start,
+            checks: results.len() as u64,
+            up: results
+                .iter()
+                .filter(|(status, _)| matches!(status, MonitorStatus::Up | MonitorStatus::Degraded))
+                .count() as u64,
+            down: results.iter().filter(|(status, _)| *status == MonitorStatus::Down).count()
+                as u64,
+            avg_latency_ms,
+            p95_latency_ms,
+            max_latency_ms: latencies.last().copied(),
+        }
+    }
+
+    /// Merge shorter buckets into one starting at `bucket_start`
+    ///
+    /// Averages are weighted by checks, and the p95 is the highest of theirs,
+    /// which bounds the p95 of the merged bucket from above.
+    pub fn merge(bucket_start: SystemTime, rollups: &[ResultRollup]) -> Self {
+        let (latency_sum, latency_checks) = rollups
+            .iter()
+            .filter_map(|r| r.avg_latency_ms.map(|avg| (avg * r.checks, r.checks)))
+            .fold((0, 0), |(sum, checks), (s, c)| (sum + s, checks + c));
+
+        Self {
+            bucket_start,
+            checks: rollups.iter().map(|r| r.checks).sum(),
+            up: rollups.iter().map(|r| r.up).sum(),
+            down: rollups.iter().map(|r| r.down).sum(),
+            avg_latency_ms: (latency_checks > 0)
+                .then(|| (latency_sum as f64 / latency_checks as f64).round() as u64),
+            p95_latency_ms: rollups.iter().filter_map(|r| r.p95_latency_ms).max(),
+            max_latency_ms: rollups.iter().filter_map(|r| r.max_latency_ms).max(),
+        }
+    }
+}
+
+/// Resolution of the stored result rollups
+///
+/// Hourly rollups are kept for `retention.hourly_rollup_days`, daily ones for
+/// as long as the monitor exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RollupPeriod {
+    Hourly,
+    Daily,
+}
+
+impl RollupPeriod {
+    /// Length of a bucket
+    pub fn seconds(self) -> u64 {
+        match self {
+            RollupPeriod::Hourly => 60 * 60,
+            RollupPeriod::Daily => 24 * 60 * 60,
+        }
+    }
+}
+
+impl std::fmt::Display for RollupPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RollupPeriod::Hourly => write!(f, "hourly"),
+            RollupPeriod::Daily => write!(f, "daily"),
+        }
+    }
+}
+
+impl std::str::FromStr for RollupPeriod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hourly" => Ok(RollupPeriod::Hourly),
+            "daily" => Ok(RollupPeriod::Daily),
+            other => Err(anyhow::anyhow!("Unknown rollup period: {}", other)),
+        }
+    }
+}
+
+/// Progress of an on-demand multi-vantage probe
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeState {
+    /// Requested, waiting for the service to pick it up
+    #[default]
+    Pending,
+    /// Sent to peers, collecting their responses
+    Running,
+    /// All responses arrived or the deadline passed
+    Complete,
+    /// Could not be sent, e.g. no matching peers
+    Failed,
+}
+
+impl std::fmt::Display for ProbeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProbeState::Pending => write!(f, "pending"),
+            ProbeState::Running => write!(f, "running"),
+            ProbeState::Complete => write!(f, "complete"),
+            ProbeState::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl std::str::FromStr for ProbeState {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(ProbeState::Pending),
+            "running" => Ok(ProbeState::Running),
+            "complete" => Ok(ProbeState::Complete),
+            "failed" => Ok(ProbeState::Failed),
+            other => Err(anyhow::anyhow!("Unknown probe state: {}", other)),
+        }
+    }
+}
+
+/// What a DHT debug operation does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DhtOperationKind {
+    /// Look up the records stored under a key
+    Get,
+    /// Store a record under a key
+    Put,
+}
+
+impl std::fmt::Display for DhtOperationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DhtOperationKind::Get => write!(f, "get"),
+            DhtOperationKind::Put => write!(f, "put"),
+        }
+    }
+}
+
+impl std::str::FromStr for DhtOperationKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "get" => Ok(DhtOperationKind::Get),
+            "put" => Ok(DhtOperationKind::Put),
+            other => Err(anyhow::anyhow!("Unknown DHT operation: {}", other)),
+        }
+    }
+}
+
+/// A DHT lookup or publish requested from the TUI for debugging, carried out
+/// by the running service like a multi-vantage probe
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DhtOperation {
+    pub uuid: Uuid,
+    pub kind: DhtOperationKind,
+    /// Key without the debug namespace prefix
+    pub key: String,
+    /// Value to store, for puts
+    pub value: Option<String>,
+    pub state: ProbeState,
+    /// Values found, for gets; non-UTF-8 values are shown lossily
+    pub records: Vec<String>,
+    pub error: Option<String>,
+    pub requested_at: SystemTime,
+    pub completed_at: Option<SystemTime>,
+}
+
+impl DhtOperation {
+    /// A new operation waiting for the service
+    pub fn pending(kind: DhtOperationKind, key: String, value: Option<String>) -> Self {
+        Self {
+            uuid: Uuid::new_v4(),
+            kind,
+            key,
+            value,
+            state: ProbeState::Pending,
+            records: Vec::new(),
+            error: None,
+            requested_at: SystemTime::now(),
+            completed_at: None,
+        }
+    }
+
+    /// Human readable outcome, e.g. "2 record(s) found"
+    pub fn summary(&self) -> String {
+        match (self.state, self.kind) {
+            (ProbeState::Pending | ProbeState::Running, _) => self.state.to_string(),
+            (ProbeState::Failed, _) => {
+                format!("failed: {}", self.error.as_deref().unwrap_or("unknown error"))
+            }
+            (ProbeState::Complete, DhtOperationKind::Get) => match self.records.first() {
+                Some(first) if self.records.len() == 1 => format!("found: {first}"),
+                Some(_) => format!("{} record(s) found", self.records.len()),
+                None => "not found".to_string(),
+            },
+            (ProbeState::Complete, DhtOperationKind::Put) => "stored".to_string(),
+        }
+    }
+}
+
+/// One peer's answer to a multi-vantage probe
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vantage {
+    /// libp2p peer ID of the peer that probed
+    pub peer_id: String,
+    pub status: MonitorStatus,
+    pub latency_ms: Option<u64>,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    /// Where the peer said it checks from, if known
+    pub region: Option<String>,
+}
+
+/// A monitor probed on demand from several peers at once, aggregated into a
+/// single result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiVantageResult {
+    pub uuid: Uuid,
+    pub monitor_uuid: Uuid,
+    pub requested_at: SystemTime,
+    pub peers_requested: u32,
+    /// Only peers in this country or region are asked
+    pub region: Option<String>,
+    /// Only this peer is asked
+    pub vantage_peer_id: Option<String>,
+    pub state: ProbeState,
+    /// Overall status once complete: up or down if all vantages agree,
+    /// degraded if they disagree, unknown without answers
+    pub status: Option<MonitorStatus>,
+    pub peers_responded: u32,
+    /// Vantages that reached the target
+    pub peers_up: u32,
+    pub latency_min_ms: Option<u64>,
+    pub latency_median_ms: Option<u64>,
+    pub latency_max_ms: Option<u64>,
+    pub vantages: Vec<Vantage>,
+    pub error: Option<String>,
+    pub completed_at: Option<SystemTime>,
+}
+
+impl MultiVantageResult {
+    /// A new probe request waiting for the service
+    pub fn pending(monitor_uuid: Uuid, peers_requested: u32, region: Option<String>) -> Self {
+        Self {
+            uuid: Uuid::new_v4(),
+            monitor_uuid,
+            requested_at: SystemTime::now(),
+            peers_requested,
+            region,
+            vantage_peer_id: None,
+            state: ProbeState::Pending,
+            status: None,
+            peers_responded: 0,
+            peers_up: 0,
+            latency_min_ms: None,
+            latency_median_ms: None,
+            latency_max_ms: None,
+            vantages: Vec::new(),
+            error: None,
+            completed_at: None,
+        }
+    }
+
+    /// Human readable summary, e.g. "3/4 vantages up, median 120ms"
+    pub fn summary(&self) -> String {
+        match self.state {
+            ProbeState::Pending | ProbeState::Running => self.state.to_string(),
+            ProbeState::Failed => {
+                format!("failed: {}", self.error.as_deref().unwrap_or("unknown error"))
+            }
+            ProbeState::Complete => {
+                let mut summary = format!("{}/{} vantages up", self.peers_up, self.peers_responded);
+                if let Some(median) = self.latency_median_ms {
+                    summary.push_str(&format!(", median {median}ms"));
+                }
+                summary
+            }
+        }
+    }
+}
+
+/// Service a notification channel posts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationKind {
+    /// Generic JSON webhook
+    Webhook,
+    /// Slack incoming webhook
+    Slack,
+    /// Discord channel webhook
+    Discord,
+    /// Telegram bot message to the chat in the target's `chat_id`
+    Telegram,
+    /// ntfy topic; the target is the topic URL
+    Ntfy,
+    /// Gotify application message; the target carries the app token
+    Gotify,
+}
+
+impl std::fmt::Display for NotificationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotificationKind::Webhook => write!(f, "webhook"),
+            NotificationKind::Slack => write!(f, "slack"),
+            NotificationKind::Discord => write!(f, "discord"),
+            NotificationKind::Telegram => write!(f, "telegram"),
+            NotificationKind::Ntfy => write!(f, "ntfy"),
+            NotificationKind::Gotify => write!(f, "gotify"),
+        }
+    }
+}
+
+impl std::str::FromStr for NotificationKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "webhook" => Ok(NotificationKind::Webhook),
+            "slack" => Ok(NotificationKind::Slack),
+            "discord" => Ok(NotificationKind::Discord),
+            "telegram" => Ok(NotificationKind::Telegram),
+            "ntfy" => Ok(NotificationKind::Ntfy),
+            "gotify" => Ok(NotificationKind::Gotify),
+            other => Err(anyhow::anyhow!("Unknown notification kind: {}", other)),
+        }
+    }
+}
+
+/// Service incident, open until it is resolved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Incident {
+    pub uuid: Uuid,
+    pub title: String,
+    /// e.g. "investigating", "identified", "monitoring"
+    pub status: String,
+    /// e.g. "minor", "major", "critical"
+    pub severity: String,
+    pub monitor_uuid: Option<Uuid>,
+    pub started_at: SystemTime,
+    pub resolved_at: Option<SystemTime>,
+}
+
+impl Incident {
+    pub const INVESTIGATING: &'static str = "investigating";
+    pub const MONITORING: &'static str = "monitoring";
+    pub const RESOLVED: &'static str = "resolved";
+
+    /// A new incident for a monitor found down at `started_at`
+    pub fn for_monitor(monitor: &Monitor, started_at: SystemTime) -> Self {
+        Self {
+            uuid: Uuid::new_v4(),
+            title: format!("{} is down", monitor.name),
+            status: Self::INVESTIGATING.to_string(),
+            severity: "major".to_string(),
+            monitor_uuid: Some(monitor.uuid),
+            started_at,
+            resolved_at: None,
+        }
+    }
+}
+
+/// An entry in an incident's timeline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncidentUpdate {
+    pub incident_uuid: Uuid,
+    /// Status of the incident as of this update
+    pub status: String,
+    pub message: String,
+    pub created_at: SystemTime,
+}
+
+/// Where notifications for the monitors it is attached to are sent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationChannel {
+    pub uuid: Uuid,
+    pub name: String,
+    pub kind: NotificationKind,
+    /// URL notifications are posted to
+    pub target: String,
+    pub enabled: bool,
+    pub created_at: SystemTime,
+    /// When the channel was last test-fired
+    pub last_test_at: Option<SystemTime>,
+    /// Why the last test-fire failed; `None` if it succeeded
+    pub last_test_error: Option<String>,
+}
+
+impl NotificationChannel {
+    /// A new enabled channel
+    pub fn new(name: String, kind: NotificationKind, target: String) -> Self {
+        Self {
+            uuid: Uuid::new_v4(),
+            name,
+            kind,
+            target,
+            enabled: true,
+            created_at: SystemTime::now(),
+            last_test_at: None,
+            last_test_error: None,
+        }
+    }
+
+    /// Outcome of the last test-fire, e.g. "ok", "failed: timeout" or "never"
+    pub fn test_summary(&self) -> String {
+        match (&self.last_test_at, &self.last_test_error) {
+            (None, _) => "never".to_string(),
+            (Some(_), None) => "ok".to_string(),
+            (Some(_), Some(error)) => format!("failed: {error}"),
+        }
+    }
+}
+
+/// Monitors a notification route applies to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum RouteScope {
+    /// Every monitor; what the other scopes inherit
+    Default,
+    /// Monitors with the tag
+    Tag(String),
+    /// A single monitor
+    Monitor(Uuid),
+}
+
+impl std::fmt::Display for RouteScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RouteScope::Default => write!(f, "default"),
+            RouteScope::Tag(tag) => write!(f, "tag:{tag}"),
+            RouteScope::Monitor(uuid) => write!(f, "monitor:{uuid}"),
+        }
+    }
+}
+
+impl std::str::FromStr for RouteScope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "default" => Ok(RouteScope::Default),
+            Some(("tag", tag)) => match Monitor::parse_tags(tag).as_slice() {
+                [tag] => Ok(RouteScope::Tag(tag.clone())),
+                _ => Err(anyhow::anyhow!("Invalid tag in route scope: {s}")),
+            },
+            Some(("monitor", uuid)) => Ok(RouteScope::Monitor(Uuid::parse_str(uuid)?)),
+            _ => Err(anyhow::anyhow!(
+                "Invalid route scope: {s} (expected default, tag:<tag> or monitor:<uuid>)"
+            )),
+        }
+    }
+}
+
+impl From<RouteScope> for String {
+    fn from(scope: RouteScope) -> Self {
+        scope.to_string()
+    }
+}
+
+impl TryFrom<String> for RouteScope {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Alerting more channels about a monitor that stays down
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscalationPolicy {
+    /// How long a monitor is down before escalating; 0 never escalates
+    pub after_secs: u64,
+    /// Channels alerted when escalating, and again once the monitor recovers
+    pub channels: Vec<Uuid>,
+}
+
+/// Which channels alert about the monitors in a scope, and how alerts escalate
+///
+/// A monitor takes its channels and escalation policy each from the most
+/// specific route setting them: its own, then those of its tags in order,
+/// then the default route.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationRoute {
+    pub scope: RouteScope,
+    /// Channels alerted when a monitor changes status; empty inherits them
+    pub channels: Vec<Uuid>,
+    /// `None` inherits the escalation policy
+    pub escalation: Option<EscalationPolicy>,
+    pub updated_at: SystemTime,
+}
+
+impl NotificationRoute {
+    /// A route that inherits everything
+    pub fn new(scope: RouteScope) -> Self {
+        Self { scope, channels: Vec::new(), escalation: None, updated_at: SystemTime::now() }
+    }
+
+    /// Whether the route sets nothing of its own
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty() && self.escalation.is_none()
+    }
+
+    /// Channels and escalation policy applying to a monitor
+    pub fn resolve(monitor: &Monitor, routes: &[NotificationRoute]) -> Routing {
+        let scopes = std::iter::once(RouteScope::Monitor(monitor.uuid))
+            .chain(monitor.tags.iter().cloned().map(RouteScope::Tag))
+            .chain(std::iter::once(RouteScope::Default));
+        let mut routing = Routing::default();
+        for scope in scopes {
+            let Some(route) = routes.iter().find(|route| route.scope == scope) else {
+                continue;
+            };
+            if routing.channels_from.is_none() && !route.channels.is_empty() {
+                routing.channels = route.channels.clone();
+                routing.channels_from = Some(scope.clone());
+            }
+            if routing.escalation_from.is_none() && route.escalation.is_some() {
+                routing.escalation =
+                    route.escalation.clone().filter(|policy| policy.after_secs > 0);
+                routing.escalation_from = Some(scope);
+            }
+        }
+        routing
+    }
+}
+
+/// Where a monitor's alerts go, resolved from the notification routes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Routing {
+    /// Channels alerted when the monitor changes status
+    pub channels: Vec<Uuid>,
+    /// Route the channels come from; `None` when no route sets any
+    pub channels_from: Option<RouteScope>,
+    /// How alerts escalate; `None` when they do not
+    pub escalation: Option<EscalationPolicy>,
+    /// Route the escalation policy comes from
+    pub escalation_from: Option<RouteScope>,
+}
+
+/// Public status page showing a set of monitors
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusPage {
+    pub uuid: Uuid,
+    pub title: String,
+    /// Lowercase letters, digits and dashes; the page is served under it
+    pub slug: String,
+    pub description: String,
+    /// Inactive pages are not served
+    pub is_active: bool,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+}
+
+impl StatusPage {
+    /// A new active page without monitors
+    pub fn new(title: String, slug: String, description: String) -> Self {
+        let now = SystemTime::now();
+        Self {
+            uuid: Uuid::new_v4(),
+            title,
+            slug,
+            description,
+            is_active: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Whether a slug only uses lowercase letters, digits and dashes
+    pub fn is_valid_slug(slug: &str) -> bool {
+        !slug.is_empty()
+            && slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    }
+}
+
+/// Time during which a monitor's checks are expected to fail
+///
+/// Results checked during a window are stored but marked, and left out of
+/// uptime, alerts, agreement and what is shared with peers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    pub uuid: Uuid,
+    /// Monitor under maintenance; `None` covers every monitor
+    pub monitor_uuid: Option<Uuid>,
+    pub starts_at: SystemTime,
+    pub ends_at: SystemTime,
+    pub reason: String,
+    pub created_at: SystemTime,
+}
+
+impl MaintenanceWindow {
+    /// A new window from `starts_at` up to, but not including, `ends_at`
+    pub fn new(
+        monitor_uuid: Option<Uuid>,
+        starts_at: SystemTime,
+        ends_at: SystemTime,
+        reason: String,
+    ) -> Self {
+        Self {
+            uuid: Uuid::new_v4(),
+            monitor_uuid,
+            starts_at,
+            ends_at,
+            reason,
+            created_at: SystemTime::now(),
+        }
+    }
+
+    /// Whether the window covers a check of a monitor at a time
+    pub fn covers(&self, monitor_uuid: Uuid, time: SystemTime) -> bool {
+        self.monitor_uuid.is_none_or(|uuid| uuid == monitor_uuid)
+            && self.starts_at <= time
+            && time < self.ends_at
+    }
+}
+
+/// Host followed without checking it, from what peers share about it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Follow {
+    /// Lowercase host name or address, e.g. `example.com`
+    pub host: String,
+    pub created_at: SystemTime,
+}
+
+impl Follow {
+    /// Follow the host of a target, if it has one
+    pub fn new(target: &str) -> Option<Self> {
+        Some(Self { host: Self::host_of(target)?, created_at: SystemTime::now() })
+    }
+
+    /// Host a monitor target points at, whatever the check type: the host of a
+    /// URL, or the target without its port
+    pub fn host_of(target: &str) -> Option<String> {
+        let target = target.trim();
+        let host = match url::Url::parse(target) {
+            Ok(url) if url.has_host() => url.host_str()?.to_string(),
+            _ => {
+                let authority = target.split('/').next()?;
+                authority.rsplit_once(':').map_or(authority, |(host, _)| host).to_string()
+            }
+        };
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        (!host.is_empty()).then_some(host)
+    }
+}
+
+/// Where peers see a followed host, from the latest verified result of each
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FollowSummary {
+    /// Distinct verified peers with a usable status
+    pub peers_total: u32,
+    /// Peers whose latest result reached the host
+    pub peers_up: u32,
+    pub avg_latency_ms: Option<u64>,
+    pub last_result_at: Option<SystemTime>,
+}
+
+impl FollowSummary {
+    /// How far back results count towards a summary
+    pub const WINDOW: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+    /// Latest results per peer monitor read for a summary
+    pub const RESULTS_PER_MONITOR: usize = 100;
+
+    /// Summarise the verified results checked since `since`
+    pub fn from_results(results: &[PeerResult], since: SystemTime) -> Self {
+        let mut latest: HashMap<&str, &PeerResult> = HashMap::new();
+        for result in results.iter().filter(|r| r.verified && r.timestamp >= since) {
+            let entry = latest.entry(result.peer_id.as_str()).or_insert(result);
+            if result.timestamp > entry.timestamp {
+                *entry = result;
+            }
+        }
+
+        let mut summary = Self::default();
+        let mut latencies = Vec::new();
+        for result in latest.values() {
+            let Some(up) = result.status.is_available() else {
+                continue;
+            };
+            summary.peers_total += 1;
+            if up {
+                summary.peers_up += 1;
+                latencies.extend(result.latency_ms);
+            }
+            summary.last_result_at = summary.last_result_at.max(Some(result.timestamp));
+        }
+        if !latencies.is_empty() {
+            summary.avg_latency_ms = Some(latencies.iter().sum::<u64>() / latencies.len() as u64);
+        }
+        summary
+    }
+
+    /// Up when most peers reach the host, down when none do, degraded between
+    pub fn status(&self) -> MonitorStatus {
+        match (self.peers_up, self.peers_total) {
+            (_, 0) => MonitorStatus::Unknown,
+            (0, _) => MonitorStatus::Down,
+            (up, total) if up * 2 > total => MonitorStatus::Up,
+            _ => MonitorStatus::Degraded,
+        }
+    }
+
+    /// Human readable summary, e.g. "3/4 peers up"
+    pub fn summary(&self) -> String {
+        if self.peers_total == 0 {
+            return "no recent peer results".to_string();
+        }
+        format!("{}/{} peers up", self.peers_up, self.peers_total)
+    }
+}
+
+/// Community domain this node joined or left monitoring
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommunityDomain {
+    /// Lowercase host name or address, as for follows
+    pub host: String,
+    /// Whether this node takes part; a left domain is kept so the leave is
+    /// announced
+    pub joined: bool,
+    /// Public monitor joining added, which leaving pauses
+    pub monitor_uuid: Option<Uuid>,
+    pub updated_at: SystemTime,
+}
+
+/// A peer's latest announcement about monitoring a community domain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupMembership {
+    pub host: String,
+    /// Ed25519 public key (hex) of the peer, which signed the announcement
+    pub peer_id: String,
+    pub joined: bool,
+    pub announced_at: SystemTime,
+}
+
+/// Peers monitoring a community domain together
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicMonitorGroup {
+    pub host: String,
+    /// Peers whose latest announcement joined, sorted
+    pub participating_peers: Vec<String>,
+}
+
+impl PublicMonitorGroup {
+    /// How long a join counts without being announced again
+    pub const MEMBERSHIP_TTL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+    /// Groups with at least one participant, by host
+    pub fn from_memberships(memberships: &[GroupMembership], now: SystemTime) -> Vec<Self> {
+        let mut groups: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for membership in memberships {
+            let current = now
+                .duration_since(membership.announced_at)
+                .is_ok_and(|age| age <= Self::MEMBERSHIP_TTL);
+            if membership.joined && current {
+                groups.entry(&membership.host).or_default().push(membership.peer_id.clone());
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(host, mut participating_peers)| {
+                participating_peers.sort();
+                Self { host: host.to_string(), participating_peers }
+            })
+            .collect()
+    }
+
+    /// Groups along with our own record of each, including domains we joined
+    /// that nobody else announced yet, by host
+    pub fn listing(
+        memberships: &[GroupMembership],
+        domains: &[CommunityDomain],
+        now: SystemTime,
+    ) -> Vec<(Self, Option<CommunityDomain>)> {
+        let mut groups = Self::from_memberships(memberships, now);
+        for domain in domains.iter().filter(|domain| domain.joined) {
+            if !groups.iter().any(|group| group.host == domain.host) {
+                groups.push(Self { host: domain.host.clone(), participating_peers: Vec::new() });
+            }
+        }
+        groups.sort_by(|a, b| a.host.cmp(&b.host));
+        groups
+            .into_iter()
+            .map(|group| {
+                let domain = domains.iter().find(|domain| domain.host == group.host).cloned();
+                (group, domain)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_notification_route_resolve() {
+        let mut monitor =
+            Monitor::new("API".to_string(), "https://example.com".to_string(), "http".to_string());
+        monitor.tags = vec!["prod".to_string(), "eu".to_string()];
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        let mut default = NotificationRoute::new(RouteScope::Default);
+        default.channels = vec![a];
+        default.escalation = Some(EscalationPolicy { after_secs: 600, channels: vec![c] });
+        let mut eu = NotificationRoute::new("tag:eu".parse().unwrap());
+        eu.channels = vec![b];
+        let mut routes = vec![default, eu];
+
+        let routing = NotificationRoute::resolve(&monitor, &routes);
+        assert_eq!(routing.channels, vec![b]);
+        assert_eq!(routing.channels_from, Some(RouteScope::Tag("eu".to_string())));
+        assert_eq!(routing.escalation.as_ref().map(|e| e.after_secs), Some(600));
+        assert_eq!(routing.escalation_from, Some(RouteScope::Default));
+
+        // The monitor's own route wins; turning escalation off doesn't inherit it
+        let mut own = NotificationRoute::new(RouteScope::Monitor(monitor.uuid));
+        own.escalation = Some(EscalationPolicy { after_secs: 0, channels: Vec::new() });
+        routes.push(own);
+        let routing = NotificationRoute::resolve(&monitor, &routes);
+        assert_eq!(routing.channels, vec![b]);
+        assert_eq!(routing.escalation, None);
+        assert_eq!(routing.escalation_from, Some(RouteScope::Monitor(monitor.uuid)));
+
+        assert_eq!(NotificationRoute::resolve(&monitor, &[]), Routing::default());
+        assert!("tag:".parse::<RouteScope>().is_err());
+        assert_eq!(
+            format!("monitor:{}", monitor.uuid).parse::<RouteScope>().unwrap(),
+            RouteScope::Monitor(monitor.uuid)
+        );
+    }
+
+    #[test]
+    fn test_public_monitor_group_listing() {
+        let now = SystemTime::now();
+        let membership = |host: &str, peer_id: &str, joined, secs_ago| GroupMembership {
+            host: host.to_string(),
+            peer_id: peer_id.to_string(),
+            joined,
+            announced_at: now - Duration::from_secs(secs_ago),
+        };
+        let memberships = [
+            membership("example.com", "peer-b", true, 60),
+            membership("example.com", "peer-a", true, 120),
+            membership("example.com", "peer-c", false, 60),
+            // A join that wasn't repeated has expired
+            membership("example.org", "peer-a", true, 2 * 3600),
+        ];
+        let groups = PublicMonitorGroup::from_memberships(&memberships, now);
+        assert_eq!(
+            groups,
+            vec![PublicMonitorGroup {
+                host: "example.com".to_string(),
+                participating_peers: vec!["peer-a".to_string(), "peer-b".to_string()],
+            }]
+        );
+
+        let domain = |host: &str, joined| CommunityDomain {
+            host: host.to_string(),
+            joined,
+            monitor_uuid: None,
+            updated_at: now,
+        };
+        let domains = [domain("example.net", true), domain("example.com", false)];
+        let listing = PublicMonitorGroup::listing(&memberships, &domains, now);
+        let hosts: Vec<(&str, Option<bool>)> = listing
+            .iter()
+            .map(|(group, domain)| (group.host.as_str(), domain.as_ref().map(|d| d.joined)))
+            .collect();
+        assert_eq!(hosts, vec![("example.com", Some(false)), ("example.net", Some(true))]);
+    }
+
+    #[test]
+    fn test_follow_host_of() {
+        assert_eq!(Follow::host_of("https://Example.com/status").as_deref(), Some("example.com"));
+        assert_eq!(Follow::host_of("http://example.com:8080").as_deref(), Some("example.com"));
+        assert_eq!(Follow::host_of("example.com:443").as_deref(), Some("example.com"));
+        assert_eq!(Follow::host_of("example.com.").as_deref(), Some("example.com"));
+        assert_eq!(Follow::host_of("1.1.1.1").as_deref(), Some("1.1.1.1"));
+        assert_eq!(Follow::host_of(" "), None);
+    }
+
+    #[test]
+    fn test_follow_summary() {
+        let now = SystemTime::now();
+        let result = |peer: &str, secs_ago: u64, status, verified| {
+            let mut result = PeerResult {
+                id: None,
+                monitor_uuid: Uuid::new_v4(),
+                timestamp: now - Duration::from_secs(secs_ago),
+                status,
+                latency_ms: Some(100),
+                status_code: None,
+                error_message: None,
+                peer_id: peer.to_string(),
+                signature: Vec::new(),
+                verified,
+                created_at: now,
+                city: None,
+                country: None,
+                region: None,
+                source_peer_id: None,
+                certificate: None,
+            };
+            if status == MonitorStatus::Down {
+                result.latency_ms = None;
+            }
+            result
+        };
+        let since = now - Duration::from_secs(600);
+
+        let summary = FollowSummary::from_results(
+            &[
+                result("a", 60, MonitorStatus::Down, true),
+                // The latest result of a peer counts
+                result("a", 30, MonitorStatus::Up, true),
+                result("b", 30, MonitorStatus::Down, true),
+                result("c", 30, MonitorStatus::Degraded, true),
+                result("d", 30, MonitorStatus::Down, false),
+                result("e", 900, MonitorStatus::Down, true),
+                result("f", 30, MonitorStatus::Unknown, true),
+            ],
+            since,
+        );
+        assert_eq!(summary.peers_total, 3);
+        assert_eq!(summary.peers_up, 2);
+        assert_eq!(summary.avg_latency_ms, Some(100));
+        assert_eq!(summary.status(), MonitorStatus::Up);
+        assert_eq!(summary.summary(), "2/3 peers up");
+
+        let down =
+            FollowSummary::from_results(&[result("b", 30, MonitorStatus::Down, true)], since);
+        assert_eq!(down.status(), MonitorStatus::Down);
+        assert_eq!(FollowSummary::from_results(&[], since).status(), MonitorStatus::Unknown);
+    }
+
+    #[test]
+    fn test_result_rollup() {
+        let hour = UNIX_EPOCH + Duration::from_secs(3600);
+        let mut results: Vec<_> = (1..=20).map(|ms| (MonitorStatus::Up, Some(ms * 10))).collect();
+        results.push((MonitorStatus::Degraded, Some(1000)));
+        results.push((MonitorStatus::Down, None));
+        let rollup = ResultRollup::from_results(hour, &results);
+        assert_eq!((rollup.checks, rollup.up, rollup.down), (22, 21, 1));
+        assert_eq!(rollup.avg_latency_ms, Some(148));
+        assert_eq!(rollup.p95_latency_ms, Some(200));
+        assert_eq!(rollup.max_latency_ms, Some(1000));
+
+        let empty = ResultRollup::from_results(hour, &[(MonitorStatus::Down, None)]);
+        assert_eq!((empty.avg_latency_ms, empty.p95_latency_ms), (None, None));
+
+        let day = ResultRollup::merge(UNIX_EPOCH, &[rollup, empty]);
+        assert_eq!((day.checks, day.up, day.down), (23, 21, 2));
+        assert_eq!(day.avg_latency_ms, Some(148));
+        assert_eq!(day.p95_latency_ms, Some(200));
+        assert_eq!(day.max_latency_ms, Some(1000));
+    }
 }
@@ -0,0 +1,730 @@
+use anyhow::Result;
+use tokio_postgres::Client;
+
+/// Schema version - increment when making schema changes
+const SCHEMA_VERSION: i32 = 9;
+
+/// Run the PostgreSQL migrations
+///
+/// Version 1 creates the schema the LibSQL migrations arrive at by their
+/// version 21, with the same tables, columns and indexes; timestamps are Unix
+/// seconds and flags are 0 or 1 as there. Later schema changes get a
+/// migration here as well as in the LibSQL migrations.
+pub async fn run_migrations(client: &Client) -> Result<()> {
+    // Several nodes may start against the same database at once; the lock
+    // lets one of them migrate while the others wait and find it up to date
+    client.execute("SELECT pg_advisory_lock($1)", &[&MIGRATION_LOCK]).await?;
+    let migrated = migrate(client).await;
+    client.execute("SELECT pg_advisory_unlock($1)", &[&MIGRATION_LOCK]).await?;
+    migrated
+}
+
+/// Key of the advisory lock held while migrating
+const MIGRATION_LOCK: i64 = 0x7570_7065;
+
+async fn migrate(client: &Client) -> Result<()> {
+    // Create schema_migrations table first (tracks applied migrations)
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                applied_at BIGINT NOT NULL,
+                description TEXT
+            )",
+        )
+        .await?;
+
+    let current_version = get_current_version(client).await?;
+
+    if current_version >= SCHEMA_VERSION {
+        tracing::info!("PostgreSQL schema is up to date (version {})", current_version);
+        return Ok(());
+    }
+
+    tracing::info!(
+        "Running PostgreSQL migrations from version {} to {}",
+        current_version,
+        SCHEMA_VERSION
+    );
+
+    if current_version < 1 {
+        run_migration_v1(client).await?;
+        record_migration(client, 1, "Initial schema").await?;
+    }
+
+    if current_version < 2 {
+        run_migration_v2(client).await?;
+        record_migration(client, 2, "Add deleted monitors").await?;
+    }
+
+    if current_version < 3 {
+        run_migration_v3(client).await?;
+        record_migration(client, 3, "Add maintenance windows").await?;
+    }
+
+    if current_version < 4 {
+        run_migration_v4(client).await?;
+        record_migration(client, 4, "Add followed targets").await?;
+    }
+
+    if current_version < 5 {
+        run_migration_v5(client).await?;
+        record_migration(client, 5, "Add notification routes").await?;
+    }
+
+    if current_version < 6 {
+        run_migration_v6(client).await?;
+        record_migration(client, 6, "Add community domains").await?;
+    }
+
+    if current_version < 7 {
+        run_migration_v7(client).await?;
+        record_migration(client, 7, "Add heartbeat grace").await?;
+    }
+
+    if current_version < 8 {
+        run_migration_v8(client).await?;
+        record_migration(client, 8, "Add result rollups").await?;
+    }
+
+    if current_version < 9 {
+        run_migration_v9(client).await?;
+        record_migration(client, 9, "Add peer result certificates").await?;
+    }
+
+    tracing::info!(
+        "PostgreSQL migrations completed successfully (now at version {})",
+        SCHEMA_VERSION
+    );
+
+    Ok(())
+}
+
+/// Get current schema version from database
+async fn get_current_version(client: &Client) -> Result<i32> {
+    let row = client.query_one("SELECT MAX(version) FROM schema_migrations", &[]).await?;
+    let version: Option<i32> = row.try_get(0)?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Record that a migration was applied
+async fn record_migration(client: &Client, version: i32, description: &str) -> Result<()> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    client
+        .execute(
+            "INSERT INTO schema_migrations (version, applied_at, description) VALUES ($1, $2, $3)",
+            &[&version, &now, &description],
+        )
+        .await?;
+
+    tracing::info!("Applied PostgreSQL migration v{}: {}", version, description);
+    Ok(())
+}
+
+/// Migration v1: The schema of LibSQL version 21
+///
+/// The LibSQL schema declares foreign keys without SQLite enforcing them, and
+/// results of monitors checked for other owners have no monitor row, so none
+/// are declared here.
+async fn run_migration_v1(client: &Client) -> Result<()> {
+    // ============================================================
+    // Monitors and their results
+    // ============================================================
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS monitors (
+                id BIGSERIAL PRIMARY KEY,
+                uuid TEXT NOT NULL UNIQUE,
+                name TEXT NOT NULL,
+                target TEXT NOT NULL,
+                check_type TEXT NOT NULL,
+                interval_seconds BIGINT NOT NULL DEFAULT 30,
+                timeout_seconds BIGINT NOT NULL DEFAULT 10,
+                enabled BIGINT NOT NULL DEFAULT 1,
+                created_at BIGINT NOT NULL,
+                updated_at BIGINT NOT NULL,
+                expected_status_codes TEXT DEFAULT '[]',
+                headers TEXT DEFAULT '{}',
+                body TEXT DEFAULT '',
+                user_id TEXT,
+                retention_days BIGINT,
+                visibility TEXT NOT NULL DEFAULT 'public',
+                retracted_at BIGINT,
+                tags TEXT NOT NULL DEFAULT ''
+            );
+
+            CREATE TABLE IF NOT EXISTS monitor_results (
+                id BIGSERIAL PRIMARY KEY,
+                monitor_uuid TEXT NOT NULL,
+                timestamp BIGINT NOT NULL,
+                status TEXT NOT NULL,
+                latency_ms BIGINT,
+                status_code BIGINT,
+                error_message TEXT,
+                peer_id TEXT NOT NULL,
+                signature BYTEA,
+                created_at BIGINT NOT NULL,
+                city TEXT,
+                country TEXT,
+                region TEXT,
+                retracted BIGINT NOT NULL DEFAULT 0,
+                restored_at BIGINT
+            );
+
+            CREATE TABLE IF NOT EXISTS peer_results (
+                id BIGSERIAL PRIMARY KEY,
+                monitor_uuid TEXT NOT NULL,
+                timestamp BIGINT NOT NULL,
+                status TEXT NOT NULL,
+                latency_ms BIGINT,
+                status_code BIGINT,
+                error_message TEXT,
+                peer_id TEXT NOT NULL,
+                signature BYTEA NOT NULL,
+                verified BIGINT NOT NULL DEFAULT 0,
+                created_at BIGINT NOT NULL,
+                city TEXT,
+                country TEXT,
+                region TEXT,
+                retracted BIGINT NOT NULL DEFAULT 0,
+                source_peer_id TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_monitors_enabled ON monitors(enabled);
+            CREATE INDEX IF NOT EXISTS idx_monitors_created_at ON monitors(created_at DESC);
+            CREATE INDEX IF NOT EXISTS idx_monitor_results_timestamp ON monitor_results(timestamp \
+             DESC);
+            CREATE INDEX IF NOT EXISTS idx_monitor_results_monitor_timestamp ON \
+             monitor_results(monitor_uuid, timestamp DESC);
+            CREATE INDEX IF NOT EXISTS idx_monitor_results_peer_id ON monitor_results(peer_id);
+            CREATE INDEX IF NOT EXISTS idx_monitor_results_uptime ON monitor_results(monitor_uuid, \
+             timestamp, status, latency_ms);
+            CREATE INDEX IF NOT EXISTS idx_peer_results_timestamp ON peer_results(timestamp DESC);
+            CREATE INDEX IF NOT EXISTS idx_peer_results_monitor_timestamp ON \
+             peer_results(monitor_uuid, timestamp DESC);
+            CREATE INDEX IF NOT EXISTS idx_peer_results_peer_id ON peer_results(peer_id);
+            CREATE INDEX IF NOT EXISTS idx_peer_results_source ON peer_results(source_peer_id, \
+             timestamp);
+            CREATE INDEX IF NOT EXISTS idx_peer_results_monitor_peer ON peer_results(monitor_uuid, \
+             peer_id, timestamp);
+            CREATE INDEX IF NOT EXISTS idx_peer_results_monitor_verified ON \
+             peer_results(monitor_uuid, verified, timestamp);",
+        )
+        .await?;
+
+    // ============================================================
+    // Status pages and incidents
+    // ============================================================
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS status_pages (
+                id BIGSERIAL PRIMARY KEY,
+                uuid TEXT NOT NULL UNIQUE,
+                title TEXT NOT NULL,
+                slug TEXT NOT NULL UNIQUE,
+                description TEXT DEFAULT '',
+                custom_domain TEXT,
+                logo_url TEXT,
+                primary_color TEXT DEFAULT '#3B82F6',
+                is_active BIGINT NOT NULL DEFAULT 1,
+                visits BIGINT NOT NULL DEFAULT 0,
+                user_id TEXT,
+                created_at BIGINT NOT NULL,
+                updated_at BIGINT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS status_page_monitors (
+                status_page_id TEXT NOT NULL,
+                monitor_uuid TEXT NOT NULL,
+                display_order BIGINT DEFAULT 0,
+                PRIMARY KEY (status_page_id, monitor_uuid)
+            );
+
+            CREATE TABLE IF NOT EXISTS incidents (
+                id BIGSERIAL PRIMARY KEY,
+                uuid TEXT NOT NULL UNIQUE,
+                title TEXT NOT NULL,
+                description TEXT,
+                status TEXT NOT NULL DEFAULT 'investigating',
+                severity TEXT NOT NULL DEFAULT 'minor',
+                monitor_uuid TEXT,
+                started_at BIGINT NOT NULL,
+                resolved_at BIGINT,
+                created_at BIGINT NOT NULL,
+                updated_at BIGINT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS incident_updates (
+                id BIGSERIAL PRIMARY KEY,
+                incident_uuid TEXT NOT NULL,
+                status TEXT NOT NULL,
+                message TEXT NOT NULL,
+                created_at BIGINT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_status_pages_active ON status_pages(is_active);
+            CREATE INDEX IF NOT EXISTS idx_incidents_status ON incidents(status);
+            CREATE INDEX IF NOT EXISTS idx_incidents_monitor ON incidents(monitor_uuid);
+            CREATE INDEX IF NOT EXISTS idx_incident_updates_incident ON \
+             incident_updates(incident_uuid);",
+        )
+        .await?;
+
+    // ============================================================
+    // Settings
+    // ============================================================
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at BIGINT NOT NULL
+            )",
+        )
+        .await?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    let default_settings = [
+        ("display_name", "Uppe. Node"),
+        ("email", ""),
+        ("timezone", "UTC"),
+        ("email_notifications", "true"),
+        ("incident_reports", "false"),
+        ("network_updates", "true"),
+        ("discovery_method", "dht_and_bootstrap"),
+        ("contribute_to_network", "true"),
+        ("max_bandwidth_mb_per_day", "100"),
+        ("max_concurrent_checks", "50"),
+        ("auto_accept_requests", "true"),
+        ("default_interval_seconds", "60"),
+        ("default_timeout_seconds", "10"),
+        ("detailed_logging", "false"),
+        ("result_retention_days", "30"),
+        ("cluster_name", "My Cluster"),
+        ("cluster_is_public", "true"),
+        ("cluster_max_size", "25"),
+        ("cluster_join_policy", "open"),
+        ("cluster_min_contribution_score", "1.5"),
+        ("helper_max_assignments", "10"),
+        ("helper_max_checks_per_owner_per_hour", "100"),
+        ("audit_retention_days", "90"),
+    ];
+
+    for (key, value) in default_settings {
+        client
+            .execute(
+                "INSERT INTO settings (key, value, updated_at) VALUES ($1, $2, $3) ON CONFLICT \
+                 (key) DO NOTHING",
+                &[&key, &value, &now],
+            )
+            .await?;
+    }
+
+    // ============================================================
+    // Network stats and peers
+    // ============================================================
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS network_stats (
+                id BIGSERIAL PRIMARY KEY,
+                timestamp BIGINT NOT NULL,
+                total_peers BIGINT DEFAULT 0,
+                online_peers BIGINT DEFAULT 0,
+                checks_performed BIGINT DEFAULT 0,
+                checks_received BIGINT DEFAULT 0,
+                bandwidth_used_mb BIGINT DEFAULT 0,
+                helper_assignments BIGINT DEFAULT 0,
+                helper_max_assignments BIGINT DEFAULT 0,
+                helper_checks_last_hour BIGINT DEFAULT 0,
+                helper_max_checks_per_hour BIGINT DEFAULT 0,
+                seen_lookups BIGINT DEFAULT 0,
+                seen_hits BIGINT DEFAULT 0
+            );
+
+            CREATE TABLE IF NOT EXISTS network_stats_hourly (
+                hour BIGINT PRIMARY KEY,
+                samples BIGINT NOT NULL,
+                avg_online_peers DOUBLE PRECISION NOT NULL,
+                max_online_peers BIGINT NOT NULL,
+                total_peers BIGINT NOT NULL,
+                checks_performed BIGINT NOT NULL,
+                checks_received BIGINT NOT NULL,
+                bandwidth_used_mb BIGINT NOT NULL,
+                helper_assignments BIGINT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS peers (
+                peer_id TEXT PRIMARY KEY,
+                location_country TEXT,
+                location_region TEXT,
+                location_city TEXT,
+                status TEXT NOT NULL DEFAULT 'online',
+                checks_per_day BIGINT DEFAULT 0,
+                last_seen BIGINT NOT NULL,
+                uptime_percentage DOUBLE PRECISION DEFAULT 100.0,
+                contribution_score DOUBLE PRECISION DEFAULT 1.0,
+                joined_at BIGINT NOT NULL,
+                addresses TEXT NOT NULL DEFAULT '[]',
+                banned_at BIGINT,
+                greylist_count BIGINT NOT NULL DEFAULT 0,
+                greylisted_at BIGINT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_network_stats_timestamp ON network_stats(timestamp \
+             DESC);
+            CREATE INDEX IF NOT EXISTS idx_peers_status ON peers(status);
+            CREATE INDEX IF NOT EXISTS idx_peers_last_seen ON peers(last_seen DESC);",
+        )
+        .await?;
+
+    // ============================================================
+    // Helper assignments and owner sync
+    // ============================================================
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS helper_assignments (
+                id BIGSERIAL PRIMARY KEY,
+                assignment_id TEXT NOT NULL UNIQUE,
+                role TEXT NOT NULL,
+                monitor_uuid TEXT NOT NULL,
+                owner_peer_id TEXT NOT NULL,
+                helper_peer_id TEXT NOT NULL,
+                target TEXT NOT NULL,
+                check_type TEXT NOT NULL,
+                interval_seconds BIGINT NOT NULL DEFAULT 60,
+                status TEXT NOT NULL DEFAULT 'pending',
+                created_at BIGINT NOT NULL,
+                updated_at BIGINT NOT NULL,
+                expires_at BIGINT,
+                last_result_at BIGINT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_helper_assignments_role ON helper_assignments(role);
+            CREATE INDEX IF NOT EXISTS idx_helper_assignments_monitor ON \
+             helper_assignments(monitor_uuid);
+
+            CREATE TABLE IF NOT EXISTS owner_sync_state (
+                id BIGINT PRIMARY KEY CHECK (id = 1),
+                running BIGINT NOT NULL DEFAULT 0,
+                monitors_total BIGINT NOT NULL DEFAULT 0,
+                monitors_synced BIGINT NOT NULL DEFAULT 0,
+                records_found BIGINT NOT NULL DEFAULT 0,
+                failures BIGINT NOT NULL DEFAULT 0,
+                last_started_at BIGINT,
+                last_completed_at BIGINT,
+                next_attempt_at BIGINT,
+                last_error TEXT
+            );",
+        )
+        .await?;
+
+    // ============================================================
+    // Audit log
+    // ============================================================
+    // Events are only ever appended; old rows are removed by retention
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS audit_events (
+                id BIGSERIAL PRIMARY KEY,
+                timestamp BIGINT NOT NULL,
+                kind TEXT NOT NULL,
+                peer_id TEXT,
+                message TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_audit_events_timestamp ON audit_events(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_audit_events_kind ON audit_events(kind);
+
+            CREATE OR REPLACE FUNCTION audit_events_append_only() RETURNS trigger AS $$
+            BEGIN
+                RAISE EXCEPTION 'audit_events is append-only';
+            END;
+            $$ LANGUAGE plpgsql;
+
+            DROP TRIGGER IF EXISTS audit_events_append_only ON audit_events;
+            CREATE TRIGGER audit_events_append_only BEFORE UPDATE ON audit_events
+                FOR EACH ROW EXECUTE FUNCTION audit_events_append_only();",
+        )
+        .await?;
+
+    // ============================================================
+    // Agreement, multi-vantage probes and DHT operations
+    // ============================================================
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS result_agreement (
+                id BIGSERIAL PRIMARY KEY,
+                monitor_uuid TEXT NOT NULL,
+                timestamp BIGINT NOT NULL,
+                bucket_seconds BIGINT NOT NULL,
+                local_status TEXT NOT NULL,
+                peers_total BIGINT NOT NULL,
+                peers_agreeing BIGINT NOT NULL,
+                updated_at BIGINT NOT NULL,
+                UNIQUE (monitor_uuid, timestamp)
+            );
+
+            CREATE TABLE IF NOT EXISTS multi_vantage_results (
+                id BIGSERIAL PRIMARY KEY,
+                uuid TEXT NOT NULL UNIQUE,
+                monitor_uuid TEXT NOT NULL,
+                timestamp BIGINT NOT NULL,
+                peers_requested BIGINT NOT NULL,
+                region TEXT,
+                state TEXT NOT NULL DEFAULT 'pending',
+                status TEXT,
+                peers_responded BIGINT NOT NULL DEFAULT 0,
+                peers_up BIGINT NOT NULL DEFAULT 0,
+                latency_min_ms BIGINT,
+                latency_median_ms BIGINT,
+                latency_max_ms BIGINT,
+                vantages TEXT NOT NULL DEFAULT '[]',
+                error TEXT,
+                completed_at BIGINT,
+                vantage_peer_id TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_multi_vantage_monitor ON \
+             multi_vantage_results(monitor_uuid, timestamp DESC);
+
+            CREATE TABLE IF NOT EXISTS dht_operations (
+                id BIGSERIAL PRIMARY KEY,
+                uuid TEXT NOT NULL UNIQUE,
+                kind TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT,
+                state TEXT NOT NULL DEFAULT 'pending',
+                records TEXT NOT NULL DEFAULT '[]',
+                error TEXT,
+                timestamp BIGINT NOT NULL,
+                completed_at BIGINT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_dht_operations_state ON dht_operations(state, \
+             timestamp);",
+        )
+        .await?;
+
+    // ============================================================
+    // Notification channels
+    // ============================================================
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS notification_channels (
+                id BIGSERIAL PRIMARY KEY,
+                uuid TEXT NOT NULL UNIQUE,
+                name TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                target TEXT NOT NULL,
+                enabled BIGINT NOT NULL DEFAULT 1,
+                created_at BIGINT NOT NULL,
+                last_test_at BIGINT,
+                last_test_error TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS monitor_notification_channels (
+                monitor_uuid TEXT NOT NULL,
+                channel_uuid TEXT NOT NULL,
+                PRIMARY KEY (monitor_uuid, channel_uuid)
+            );",
+        )
+        .await?;
+
+    tracing::info!("Created PostgreSQL schema");
+    Ok(())
+}
+
+/// Migration v2: When each monitor was deleted, as LibSQL migration v22
+async fn run_migration_v2(client: &Client) -> Result<()> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS deleted_monitors (
+                uuid TEXT PRIMARY KEY,
+                deleted_at BIGINT NOT NULL
+            )",
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Migration v3: Maintenance windows and the result flag, as LibSQL migration v23
+async fn run_migration_v3(client: &Client) -> Result<()> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS maintenance_windows (
+                uuid TEXT PRIMARY KEY,
+                monitor_uuid TEXT,
+                starts_at BIGINT NOT NULL,
+                ends_at BIGINT NOT NULL,
+                reason TEXT NOT NULL DEFAULT '',
+                created_at BIGINT NOT NULL
+            );
+            ALTER TABLE monitor_results
+                ADD COLUMN IF NOT EXISTS maintenance BIGINT NOT NULL DEFAULT 0;
+            DROP INDEX IF EXISTS idx_monitor_results_uptime;
+            CREATE INDEX idx_monitor_results_uptime ON monitor_results(monitor_uuid, timestamp, \
+             status, latency_ms, maintenance);",
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Migration v4: Followed hosts and their monitors, as LibSQL migration v24
+async fn run_migration_v4(client: &Client) -> Result<()> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS follows (
+                host TEXT PRIMARY KEY,
+                created_at BIGINT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS follow_monitors (
+                host TEXT NOT NULL,
+                monitor_uuid TEXT NOT NULL,
+                first_seen BIGINT NOT NULL,
+                PRIMARY KEY (host, monitor_uuid)
+            );",
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Migration v5: Notification routes taking over the channels attached to
+/// monitors, as LibSQL migration v25
+async fn run_migration_v5(client: &Client) -> Result<()> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS notification_routes (
+                scope TEXT PRIMARY KEY,
+                escalate_after_secs BIGINT,
+                updated_at BIGINT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS notification_route_channels (
+                scope TEXT NOT NULL,
+                channel_uuid TEXT NOT NULL,
+                escalation BIGINT NOT NULL DEFAULT 0,
+                PRIMARY KEY (scope, channel_uuid, escalation)
+            );
+            INSERT INTO notification_routes (scope, updated_at)
+                SELECT DISTINCT 'monitor:' || monitor_uuid, EXTRACT(EPOCH FROM NOW())::BIGINT
+                FROM monitor_notification_channels
+                ON CONFLICT DO NOTHING;
+            INSERT INTO notification_route_channels (scope, channel_uuid)
+                SELECT 'monitor:' || monitor_uuid, channel_uuid FROM monitor_notification_channels
+                ON CONFLICT DO NOTHING;
+            DROP TABLE monitor_notification_channels;",
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Migration v6: Community domains and peer membership announcements
+async fn run_migration_v6(client: &Client) -> Result<()> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS community_domains (
+                host TEXT PRIMARY KEY,
+                joined BIGINT NOT NULL,
+                monitor_uuid TEXT,
+                updated_at BIGINT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS group_memberships (
+                host TEXT NOT NULL,
+                peer_id TEXT NOT NULL,
+                joined BIGINT NOT NULL,
+                announced_at BIGINT NOT NULL,
+                PRIMARY KEY (host, peer_id)
+            );",
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Migration v7: How late a heartbeat monitor's ping may be
+async fn run_migration_v7(client: &Client) -> Result<()> {
+    client
+        .batch_execute(
+            "ALTER TABLE monitors
+                ADD COLUMN IF NOT EXISTS grace_seconds BIGINT NOT NULL DEFAULT 60;",
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Migration v8: Hourly and daily rollups of our results
+async fn run_migration_v8(client: &Client) -> Result<()> {
+    for table in ["result_rollups_hourly", "result_rollups_daily"] {
+        client
+            .batch_execute(&format!(
+                "CREATE TABLE IF NOT EXISTS {table} (
+                    monitor_uuid TEXT NOT NULL,
+                    bucket_start BIGINT NOT NULL,
+                    checks BIGINT NOT NULL,
+                    up BIGINT NOT NULL,
+                    down BIGINT NOT NULL,
+                    avg_latency_ms BIGINT,
+                    p95_latency_ms BIGINT,
+                    max_latency_ms BIGINT,
+                    rolled_up_at BIGINT NOT NULL,
+                    PRIMARY KEY (monitor_uuid, bucket_start)
+                );"
+            ))
+            .await?;
+    }
+    client
+        .batch_execute(
+            "CREATE INDEX IF NOT EXISTS idx_result_rollups_hourly_bucket
+                ON result_rollups_hourly(bucket_start);
+            CREATE INDEX IF NOT EXISTS idx_result_rollups_hourly_rolled_up
+                ON result_rollups_hourly(rolled_up_at);
+            CREATE INDEX IF NOT EXISTS idx_monitor_results_created_at
+                ON monitor_results(created_at);",
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Migration v9: Certificate details peers report with their results
+async fn run_migration_v9(client: &Client) -> Result<()> {
+    client
+        .batch_execute("ALTER TABLE peer_results ADD COLUMN IF NOT EXISTS certificate TEXT;")
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::postgres::tests::test_database;
+
+    #[tokio::test]
+    #[ignore = "needs a PostgreSQL server at UPPE_TEST_DATABASE_URL"]
+    async fn test_migrations_are_idempotent() {
+        // Connecting migrates; migrating again, even from two nodes at once,
+        // finds the schema up to date
+        let db = test_database().await;
+        let (first, second) = (db.get_client().await.unwrap(), db.get_client().await.unwrap());
+        let (a, b) = tokio::join!(run_migrations(&first), run_migrations(&second));
+        a.unwrap();
+        b.unwrap();
+
+        assert_eq!(get_current_version(&first).await.unwrap(), SCHEMA_VERSION);
+        let row = first.query_one("SELECT COUNT(*) FROM schema_migrations", &[]).await.unwrap();
+        assert_eq!(row.get::<_, i64>(0), i64::from(SCHEMA_VERSION));
+    }
+}
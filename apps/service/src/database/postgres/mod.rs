@@ -0,0 +1,2478 @@
+//! PostgreSQL backend
+//!
+//! Lets several nodes share one central store. The schema mirrors the LibSQL
+//! one, so queries differ only where the SQL dialects do.
+
+mod migrations;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod, Runtime};
+use rustls::RootCertStore;
+use tokio_postgres::Row;
+use tokio_postgres_rustls::MakeRustlsConnect;
+use uuid::Uuid;
+
+pub use migrations::run_migrations;
+
+use super::models::{
+    AssignmentRole, AuditEvent, AuditKind, CommunityDomain, DhtOperation, EscalationPolicy, Follow,
+    GroupMembership, HelperAssignment, Incident, IncidentUpdate, MaintenanceWindow, Monitor,
+    MonitorResult, MultiVantageResult, NetworkStats, NotificationChannel, NotificationRoute,
+    OwnerSyncState, Peer, PeerResult, ProbeState, ResultAgreement, ResultRollup, RollupPeriod,
+    RouteScope, Setting, StatusPage, Vantage,
+};
+use super::repository::{
+    DHT_OPERATION_COLUMNS, Database, INCIDENT_COLUMNS, MAINTENANCE_WINDOW_COLUMNS,
+    MAX_PEER_ADDRESSES, MONITOR_COLUMNS, MULTI_VANTAGE_COLUMNS, PEER_COLUMNS, PEER_RESULT_COLUMNS,
+    ROLLUP_COLUMNS, STATUS_PAGE_COLUMNS, parse_json_column, parse_status, rollup_table,
+};
+use crate::config::{DatabaseConfig, DatabasePoolConfig};
+use crate::monitoring::types::{CheckResult, MonitorStatus};
+
+/// PostgreSQL database implementation
+pub struct PostgresDatabaseImpl {
+    pool: Pool,
+}
+
+impl PostgresDatabaseImpl {
+    /// Connect to the server at `database.url` and bring its schema up to date
+    ///
+    /// TLS is used when the server offers it, verified against the Mozilla
+    /// root program; `sslmode=require` in the URL refuses plain connections.
+    pub async fn connect(database: &DatabaseConfig, pool: &DatabasePoolConfig) -> Result<Self> {
+        let url = database
+            .url
+            .as_deref()
+            .ok_or_else(|| anyhow!("database.url is required for the postgres backend"))?;
+        let config: tokio_postgres::Config = url.parse().context("Invalid database.url")?;
+
+        let roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        let tls = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+        let manager = Manager::from_config(
+            config,
+            MakeRustlsConnect::new(tls),
+            ManagerConfig { recycling_method: RecyclingMethod::Fast },
+        );
+        let pool = Pool::builder(manager)
+            .max_size(pool.max_size)
+            .wait_timeout(Some(Duration::from_secs(pool.wait_timeout_secs)))
+            .runtime(Runtime::Tokio1)
+            .build()?;
+
+        let database = Self::new_from_pool(pool);
+        run_migrations(&*database.get_client().await?).await?;
+
+        Ok(database)
+    }
+
+    /// Create a new database instance from a pool
+    pub fn new_from_pool(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    /// Get a client from the pool
+    async fn get_client(&self) -> Result<deadpool_postgres::Object> {
+        Ok(self.pool.get().await?)
+    }
+}
+
+#[async_trait]
+impl Database for PostgresDatabaseImpl {
+    async fn get_enabled_monitors(&self) -> Result<Vec<Monitor>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(&format!("SELECT {MONITOR_COLUMNS} FROM monitors WHERE enabled = 1"), &[])
+            .await?;
+
+        rows.iter().map(monitor_from_row).collect()
+    }
+
+    async fn get_monitors(&self) -> Result<Vec<Monitor>> {
+        let client = self.get_client().await?;
+        let rows = client.query(&format!("SELECT {MONITOR_COLUMNS} FROM monitors"), &[]).await?;
+
+        rows.iter().map(monitor_from_row).collect()
+    }
+
+    async fn get_monitor_by_uuid(&self, uuid: Uuid) -> Result<Option<Monitor>> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_opt(
+                &format!("SELECT {MONITOR_COLUMNS} FROM monitors WHERE uuid = $1"),
+                &[&uuid.to_string()],
+            )
+            .await?;
+
+        row.as_ref().map(monitor_from_row).transpose()
+    }
+
+    async fn save_monitor(&self, monitor: &Monitor) -> Result<i64> {
+        let client = self.get_client().await?;
+        let created_at = Monitor::timestamp_to_i64(monitor.created_at);
+        let updated_at = Monitor::timestamp_to_i64(monitor.updated_at);
+        let interval_seconds = monitor.interval_seconds as i64;
+        let timeout_seconds = monitor.timeout_seconds as i64;
+        let enabled = monitor.enabled as i64;
+        let retention_days = monitor.retention_days.map(|days| days as i64);
+        let visibility = monitor.visibility.to_string();
+        let tags = monitor.tags.join(",");
+        let expected_status_codes = serde_json::to_string(&monitor.expected_status_codes)?;
+        let headers = serde_json::to_string(&monitor.headers)?;
+        let grace_seconds = monitor.grace_seconds as i64;
+
+        if let Some(id) = monitor.id {
+            // Update existing monitor
+            client
+                .execute(
+                    "UPDATE monitors SET name = $1, target = $2, check_type = $3, \
+                     interval_seconds = $4, timeout_seconds = $5, enabled = $6, retention_days = \
+                     $7, visibility = $8, retracted_at = CASE WHEN $8 = 'public' THEN NULL ELSE \
+                     retracted_at END, updated_at = $9, tags = $11, expected_status_codes = $12, \
+                     headers = $13, grace_seconds = $14 WHERE id = $10",
+                    &[
+                        &monitor.name,
+                        &monitor.target,
+                        &monitor.check_type,
+                        &interval_seconds,
+                        &timeout_seconds,
+                        &enabled,
+                        &retention_days,
+                        &visibility,
+                        &updated_at,
+                        &id,
+                        &tags,
+                        &expected_status_codes,
+                        &headers,
+                        &grace_seconds,
+                    ],
+                )
+                .await?;
+            Ok(id)
+        } else {
+            // Insert new monitor
+            let row = client
+                .query_one(
+                    "INSERT INTO monitors (uuid, name, target, check_type, interval_seconds, \
+                     timeout_seconds, enabled, retention_days, visibility, created_at, \
+                     updated_at, tags, expected_status_codes, headers, grace_seconds) VALUES ($1, \
+                     $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15) RETURNING id",
+                    &[
+                        &monitor.uuid.to_string(),
+                        &monitor.name,
+                        &monitor.target,
+                        &monitor.check_type,
+                        &interval_seconds,
+                        &timeout_seconds,
+                        &enabled,
+                        &retention_days,
+                        &visibility,
+                        &created_at,
+                        &updated_at,
+                        &tags,
+                        &expected_status_codes,
+                        &headers,
+                        &grace_seconds,
+                    ],
+                )
+                .await?;
+
+            Ok(row.try_get(0)?)
+        }
+    }
+
+    async fn delete_monitor(&self, uuid: Uuid) -> Result<()> {
+        let client = self.get_client().await?;
+        let scope = RouteScope::Monitor(uuid).to_string();
+        let uuid = uuid.to_string();
+
+        for table in [
+            "monitor_results",
+            "peer_results",
+            "result_agreement",
+            "multi_vantage_results",
+            "maintenance_windows",
+            "result_rollups_hourly",
+            "result_rollups_daily",
+        ] {
+            client
+                .execute(&format!("DELETE FROM {table} WHERE monitor_uuid = $1"), &[&uuid])
+                .await?;
+        }
+        for table in ["notification_route_channels", "notification_routes"] {
+            client
+                .execute(&format!("DELETE FROM {table} WHERE scope = $1"), &[&scope])
+                .await?;
+        }
+        client.execute("DELETE FROM monitors WHERE uuid = $1", &[&uuid]).await?;
+        client
+            .execute(
+                "INSERT INTO deleted_monitors (uuid, deleted_at) VALUES ($1, $2) ON CONFLICT \
+                 (uuid) DO UPDATE SET deleted_at = excluded.deleted_at",
+                &[&uuid, &Monitor::timestamp_to_i64(std::time::SystemTime::now())],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_deleted_monitors(&self) -> Result<Vec<(Uuid, std::time::SystemTime)>> {
+        let client = self.get_client().await?;
+        let rows = client.query("SELECT uuid, deleted_at FROM deleted_monitors", &[]).await?;
+
+        rows.iter()
+            .map(|row| {
+                let uuid: String = row.try_get(0)?;
+                Ok((Uuid::parse_str(&uuid)?, Monitor::i64_to_timestamp(row.try_get(1)?)))
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(skip_all, fields(monitor_id = %result.monitor_id))]
+    async fn save_result(&self, result: &CheckResult) -> Result<i64> {
+        let client = self.get_client().await?;
+        let timestamp = Monitor::timestamp_to_i64(result.timestamp);
+        let created_at = Monitor::timestamp_to_i64(std::time::SystemTime::now());
+        let location = crate::location::get_location();
+
+        let row = client
+            .query_one(
+                "INSERT INTO monitor_results (monitor_uuid, timestamp, status, latency_ms, \
+                 status_code, error_message, peer_id, signature, created_at, city, country, \
+                 region, maintenance) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, \
+                 $13) RETURNING id",
+                &[
+                    &result.monitor_id.to_string(),
+                    &timestamp,
+                    &result.status.to_string(),
+                    &result.latency_ms.map(|v| v as i64),
+                    &result.status_code.map(|v| v as i64),
+                    &result.error_message,
+                    &result.peer_id,
+                    &result.signature,
+                    &created_at,
+                    &location.city,
+                    &location.country,
+                    &location.region,
+                    &(result.maintenance as i64),
+                ],
+            )
+            .await?;
+
+        Ok(row.try_get(0)?)
+    }
+
+    #[tracing::instrument(skip_all, fields(monitor_id = %result.monitor_uuid, peer_id = %result.peer_id))]
+    async fn save_peer_result(&self, result: &PeerResult) -> Result<i64> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_one(
+                "INSERT INTO peer_results (monitor_uuid, timestamp, status, latency_ms, \
+                 status_code, error_message, peer_id, signature, verified, created_at, city, \
+                 country, region, source_peer_id, certificate) VALUES ($1, $2, $3, $4, $5, $6, \
+                 $7, $8, $9, $10, $11, $12, $13, $14, $15) RETURNING id",
+                &[
+                    &result.monitor_uuid.to_string(),
+                    &Monitor::timestamp_to_i64(result.timestamp),
+                    &result.status.to_string(),
+                    &result.latency_ms.map(|v| v as i64),
+                    &result.status_code.map(|v| v as i64),
+                    &result.error_message,
+                    &result.peer_id,
+                    &result.signature,
+                    &(result.verified as i64),
+                    &Monitor::timestamp_to_i64(result.created_at),
+                    &result.city,
+                    &result.country,
+                    &result.region,
+                    &result.source_peer_id,
+                    &result.certificate.as_ref().map(serde_json::to_string).transpose()?,
+                ],
+            )
+            .await?;
+
+        Ok(row.try_get(0)?)
+    }
+
+    async fn get_recent_results(
+        &self,
+        monitor_uuid: Uuid,
+        limit: usize,
+    ) -> Result<Vec<MonitorResult>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {MONITOR_RESULT_COLUMNS} FROM monitor_results WHERE monitor_uuid = $1 \
+                     ORDER BY timestamp DESC LIMIT $2"
+                ),
+                &[&monitor_uuid.to_string(), &(limit as i64)],
+            )
+            .await?;
+
+        rows.iter().map(monitor_result_from_row).collect()
+    }
+
+    async fn get_latest_results(&self) -> Result<Vec<MonitorResult>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT DISTINCT ON (monitor_uuid) {MONITOR_RESULT_COLUMNS} FROM \
+                     monitor_results ORDER BY monitor_uuid, timestamp DESC"
+                ),
+                &[],
+            )
+            .await?;
+
+        rows.iter().map(monitor_result_from_row).collect()
+    }
+
+    async fn get_peer_results(&self, monitor_uuid: Uuid, limit: usize) -> Result<Vec<PeerResult>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {PEER_RESULT_COLUMNS} FROM peer_results WHERE monitor_uuid = $1 ORDER \
+                     BY timestamp DESC LIMIT $2"
+                ),
+                &[&monitor_uuid.to_string(), &(limit as i64)],
+            )
+            .await?;
+
+        rows.iter().map(peer_result_from_row).collect()
+    }
+
+    async fn upsert_peer(&self, peer: &Peer) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO peers (peer_id, status, last_seen, joined_at, contribution_score, \
+                 uptime_percentage, checks_per_day, location_city, location_region, \
+                 location_country) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                 ON CONFLICT (peer_id) DO UPDATE SET status = excluded.status, last_seen = \
+                 excluded.last_seen, location_city = COALESCE(excluded.location_city, \
+                 peers.location_city), location_region = COALESCE(excluded.location_region, \
+                 peers.location_region), location_country = COALESCE(excluded.location_country, \
+                 peers.location_country)",
+                &[
+                    &peer.peer_id,
+                    &peer.status,
+                    &Monitor::timestamp_to_i64(peer.last_seen),
+                    &Monitor::timestamp_to_i64(peer.joined_at),
+                    &peer.contribution_score,
+                    &peer.uptime_percentage,
+                    &peer.checks_per_day,
+                    &peer.location_city,
+                    &peer.location_region,
+                    &peer.location_country,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn mark_peer_offline(&self, peer_id: &str, now: std::time::SystemTime) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "UPDATE peers SET status = 'offline', last_seen = $1 WHERE peer_id = $2",
+                &[&Monitor::timestamp_to_i64(now), &peer_id],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn insert_network_stats(&self, stats: &NetworkStats) -> Result<i64> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_one(
+                "INSERT INTO network_stats (timestamp, total_peers, online_peers, \
+                 checks_performed, checks_received, bandwidth_used_mb, helper_assignments, \
+                 helper_max_assignments, helper_checks_last_hour, helper_max_checks_per_hour, \
+                 seen_lookups, seen_hits) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, \
+                 $12) RETURNING id",
+                &[
+                    &Monitor::timestamp_to_i64(stats.timestamp),
+                    &stats.total_peers,
+                    &stats.online_peers,
+                    &stats.checks_performed,
+                    &stats.checks_received,
+                    &stats.bandwidth_used_mb,
+                    &stats.helper_assignments,
+                    &stats.helper_max_assignments,
+                    &stats.helper_checks_last_hour,
+                    &stats.helper_max_checks_per_hour,
+                    &stats.seen_lookups,
+                    &stats.seen_hits,
+                ],
+            )
+            .await?;
+
+        Ok(row.try_get(0)?)
+    }
+
+    async fn get_latest_network_stats(&self) -> Result<Option<NetworkStats>> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_opt(
+                "SELECT timestamp, total_peers, online_peers, checks_performed, checks_received, \
+                 bandwidth_used_mb, helper_assignments, helper_max_assignments, \
+                 helper_checks_last_hour, helper_max_checks_per_hour, seen_lookups, seen_hits \
+                 FROM network_stats ORDER BY timestamp DESC LIMIT 1",
+                &[],
+            )
+            .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let counter =
+            |idx: usize| row.try_get::<_, Option<i64>>(idx).map(Option::unwrap_or_default);
+        Ok(Some(NetworkStats {
+            timestamp: Monitor::i64_to_timestamp(row.try_get(0)?),
+            total_peers: counter(1)?,
+            online_peers: counter(2)?,
+            checks_performed: counter(3)?,
+            checks_received: counter(4)?,
+            bandwidth_used_mb: counter(5)?,
+            helper_assignments: counter(6)?,
+            helper_max_assignments: counter(7)?,
+            helper_checks_last_hour: counter(8)?,
+            helper_max_checks_per_hour: counter(9)?,
+            seen_lookups: counter(10)?,
+            seen_hits: counter(11)?,
+        }))
+    }
+
+    async fn rollup_network_stats(&self, before: std::time::SystemTime) -> Result<u64> {
+        let mut client = self.get_client().await?;
+        let before = Monitor::timestamp_to_i64(before);
+        let tx = client.transaction().await?;
+
+        // An hour rolled up in two parts is merged with what is there
+        tx.execute(
+            "INSERT INTO network_stats_hourly AS h (hour, samples, avg_online_peers, \
+             max_online_peers, total_peers, checks_performed, checks_received, bandwidth_used_mb, \
+             helper_assignments)
+             SELECT timestamp / 3600 * 3600, COUNT(*), COALESCE(AVG(online_peers), 0)::DOUBLE \
+             PRECISION, COALESCE(MAX(online_peers), 0), COALESCE(MAX(total_peers), 0), \
+             COALESCE(MAX(checks_performed), 0), COALESCE(MAX(checks_received), 0), \
+             COALESCE(MAX(bandwidth_used_mb), 0), COALESCE(MAX(helper_assignments), 0)
+             FROM network_stats WHERE timestamp < $1 GROUP BY timestamp / 3600 * 3600
+             ON CONFLICT (hour) DO UPDATE SET
+                avg_online_peers = (h.avg_online_peers * h.samples
+                    + excluded.avg_online_peers * excluded.samples)
+                    / (h.samples + excluded.samples),
+                samples = h.samples + excluded.samples,
+                max_online_peers = GREATEST(h.max_online_peers, excluded.max_online_peers),
+                total_peers = GREATEST(h.total_peers, excluded.total_peers),
+                checks_performed = GREATEST(h.checks_performed, excluded.checks_performed),
+                checks_received = GREATEST(h.checks_received, excluded.checks_received),
+                bandwidth_used_mb = GREATEST(h.bandwidth_used_mb, excluded.bandwidth_used_mb),
+                helper_assignments = GREATEST(h.helper_assignments, excluded.helper_assignments)",
+            &[&before],
+        )
+        .await?;
+        let removed =
+            tx.execute("DELETE FROM network_stats WHERE timestamp < $1", &[&before]).await?;
+        tx.commit().await?;
+
+        Ok(removed)
+    }
+
+    async fn save_helper_assignment(&self, assignment: &HelperAssignment) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO helper_assignments (assignment_id, role, monitor_uuid, \
+                 owner_peer_id, helper_peer_id, target, check_type, interval_seconds, status, \
+                 created_at, updated_at, expires_at, last_result_at) VALUES ($1, $2, $3, $4, $5, \
+                 $6, $7, $8, $9, $10, $11, $12, $13)
+                 ON CONFLICT (assignment_id) DO UPDATE SET status = excluded.status, target = \
+                 excluded.target, check_type = excluded.check_type, interval_seconds = \
+                 excluded.interval_seconds, updated_at = excluded.updated_at, expires_at = \
+                 excluded.expires_at",
+                &[
+                    &assignment.assignment_id.to_string(),
+                    &assignment.role.to_string(),
+                    &assignment.monitor_uuid.to_string(),
+                    &assignment.owner_peer_id,
+                    &assignment.helper_peer_id,
+                    &assignment.target,
+                    &assignment.check_type,
+                    &(assignment.interval_seconds as i64),
+                    &assignment.status,
+                    &Monitor::timestamp_to_i64(assignment.created_at),
+                    &Monitor::timestamp_to_i64(assignment.updated_at),
+                    &assignment.expires_at.map(Monitor::timestamp_to_i64),
+                    &assignment.last_result_at.map(Monitor::timestamp_to_i64),
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_helper_assignments(&self, role: AssignmentRole) -> Result<Vec<HelperAssignment>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                "SELECT assignment_id, role, monitor_uuid, owner_peer_id, helper_peer_id, target, \
+                 check_type, interval_seconds, status, created_at, updated_at, expires_at, \
+                 last_result_at FROM helper_assignments WHERE role = $1 ORDER BY created_at ASC",
+                &[&role.to_string()],
+            )
+            .await?;
+
+        let mut assignments = Vec::new();
+        for row in rows {
+            let assignment_id: String = row.try_get(0)?;
+            let role: String = row.try_get(1)?;
+            let monitor_uuid: String = row.try_get(2)?;
+
+            assignments.push(HelperAssignment {
+                assignment_id: Uuid::parse_str(&assignment_id)?,
+                role: role.parse()?,
+                monitor_uuid: Uuid::parse_str(&monitor_uuid)?,
+                owner_peer_id: row.try_get(3)?,
+                helper_peer_id: row.try_get(4)?,
+                target: row.try_get(5)?,
+                check_type: row.try_get(6)?,
+                interval_seconds: row.try_get::<_, i64>(7)? as u64,
+                status: row.try_get(8)?,
+                created_at: Monitor::i64_to_timestamp(row.try_get(9)?),
+                updated_at: Monitor::i64_to_timestamp(row.try_get(10)?),
+                expires_at: row.try_get::<_, Option<i64>>(11)?.map(Monitor::i64_to_timestamp),
+                last_result_at: row.try_get::<_, Option<i64>>(12)?.map(Monitor::i64_to_timestamp),
+            });
+        }
+
+        Ok(assignments)
+    }
+
+    async fn record_assignment_result(
+        &self,
+        assignment_id: Uuid,
+        at: std::time::SystemTime,
+    ) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "UPDATE helper_assignments SET last_result_at = $1 WHERE assignment_id = $2",
+                &[&Monitor::timestamp_to_i64(at), &assignment_id.to_string()],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_helper_assignment(&self, assignment_id: Uuid) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "DELETE FROM helper_assignments WHERE assignment_id = $1",
+                &[&assignment_id.to_string()],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let client = self.get_client().await?;
+        let row = client.query_opt("SELECT value FROM settings WHERE key = $1", &[&key]).await?;
+
+        Ok(row.map(|row| row.try_get(0)).transpose()?)
+    }
+
+    async fn get_settings(&self) -> Result<Vec<Setting>> {
+        let client = self.get_client().await?;
+        let rows = client.query("SELECT key, value, updated_at FROM settings", &[]).await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(Setting {
+                    key: row.try_get(0)?,
+                    value: row.try_get(1)?,
+                    updated_at: Monitor::i64_to_timestamp(row.try_get(2)?),
+                })
+            })
+            .collect()
+    }
+
+    async fn save_setting(&self, setting: &Setting) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO settings (key, value, updated_at) VALUES ($1, $2, $3) ON CONFLICT \
+                 (key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+                &[&setting.key, &setting.value, &Monitor::timestamp_to_i64(setting.updated_at)],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_results(
+        &self,
+        now: std::time::SystemTime,
+        default_days: u64,
+    ) -> Result<u64> {
+        let client = self.get_client().await?;
+        let now = Monitor::timestamp_to_i64(now);
+        let default_days = default_days as i64;
+
+        // A retention of 0 days keeps everything, whether it comes from the
+        // monitor's override or the default
+        let mut removed = 0;
+        for (table, restored) in [
+            ("monitor_results", RESTORED_KEPT),
+            ("peer_results", ""),
+            ("result_agreement", ""),
+            ("multi_vantage_results", ""),
+        ] {
+            removed += client
+                .execute(
+                    &format!(
+                        "DELETE FROM {table} WHERE id IN (
+                            SELECT r.id FROM {table} r
+                            LEFT JOIN monitors m ON m.uuid = r.monitor_uuid
+                            WHERE COALESCE(m.retention_days, $2) > 0
+                              AND r.timestamp < $1 - COALESCE(m.retention_days, $2) * 86400
+                              {restored}
+                        )"
+                    ),
+                    &[&now, &default_days],
+                )
+                .await?;
+        }
+
+        Ok(removed)
+    }
+
+    async fn get_expired_results(
+        &self,
+        now: std::time::SystemTime,
+        default_days: u64,
+        limit: usize,
+    ) -> Result<Vec<MonitorResult>> {
+        let client = self.get_client().await?;
+        let columns = MONITOR_RESULT_COLUMNS.split(", ").map(|c| format!("r.{}", c.trim()));
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM monitor_results r
+                     LEFT JOIN monitors m ON m.uuid = r.monitor_uuid
+                     WHERE COALESCE(m.retention_days, $2) > 0
+                       AND r.timestamp < $1 - COALESCE(m.retention_days, $2) * 86400
+                       AND r.restored_at IS NULL
+                     ORDER BY r.timestamp LIMIT $3",
+                    columns.collect::<Vec<_>>().join(", ")
+                ),
+                &[&Monitor::timestamp_to_i64(now), &(default_days as i64), &(limit as i64)],
+            )
+            .await?;
+
+        rows.iter().map(monitor_result_from_row).collect()
+    }
+
+    async fn delete_results(&self, ids: &[i64]) -> Result<u64> {
+        let client = self.get_client().await?;
+        let removed = client
+            .execute("DELETE FROM monitor_results WHERE id = ANY($1)", &[&ids])
+            .await?;
+
+        Ok(removed)
+    }
+
+    async fn import_results(
+        &self,
+        results: &[MonitorResult],
+        restored_at: Option<std::time::SystemTime>,
+    ) -> Result<u64> {
+        let mut client = self.get_client().await?;
+        let restored_at = restored_at.map(Monitor::timestamp_to_i64);
+        let tx = client.transaction().await?;
+        let mut added = 0;
+        for result in results {
+            added += tx
+                .execute(
+                    "INSERT INTO monitor_results (monitor_uuid, timestamp, status, latency_ms, \
+                     status_code, error_message, peer_id, signature, created_at, city, country, \
+                     region, restored_at, maintenance) SELECT $1::TEXT, $2::BIGINT, $3::TEXT, \
+                     $4::BIGINT, $5::BIGINT, $6::TEXT, $7::TEXT, $8::BYTEA, $9::BIGINT, \
+                     $10::TEXT, $11::TEXT, $12::TEXT, $13::BIGINT, $14::BIGINT WHERE NOT EXISTS \
+                     (SELECT 1 FROM monitor_results WHERE monitor_uuid = $1 AND timestamp = $2)",
+                    &[
+                        &result.monitor_uuid.to_string(),
+                        &Monitor::timestamp_to_i64(result.timestamp),
+                        &result.status.to_string(),
+                        &result.latency_ms.map(|v| v as i64),
+                        &result.status_code.map(|v| v as i64),
+                        &result.error_message,
+                        &result.peer_id,
+                        &result.signature,
+                        &Monitor::timestamp_to_i64(result.created_at),
+                        &result.city,
+                        &result.country,
+                        &result.region,
+                        &restored_at,
+                        &(result.maintenance as i64),
+                    ],
+                )
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(added)
+    }
+
+    async fn append_audit_event(&self, event: &AuditEvent) -> Result<i64> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_one(
+                "INSERT INTO audit_events (timestamp, kind, peer_id, message) VALUES ($1, $2, $3, \
+                 $4) RETURNING id",
+                &[
+                    &Monitor::timestamp_to_i64(event.timestamp),
+                    &event.kind.to_string(),
+                    &event.peer_id,
+                    &event.message,
+                ],
+            )
+            .await?;
+
+        Ok(row.try_get(0)?)
+    }
+
+    async fn get_audit_events(
+        &self,
+        kind: Option<AuditKind>,
+        limit: usize,
+    ) -> Result<Vec<AuditEvent>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                "SELECT id, timestamp, kind, peer_id, message FROM audit_events WHERE ($1::TEXT \
+                 IS NULL OR kind = $1) ORDER BY timestamp DESC, id DESC LIMIT $2",
+                &[&kind.map(|kind| kind.to_string()), &(limit as i64)],
+            )
+            .await?;
+
+        let mut events = Vec::new();
+        for row in rows {
+            let kind: String = row.try_get(2)?;
+            events.push(AuditEvent {
+                id: Some(row.try_get(0)?),
+                timestamp: Monitor::i64_to_timestamp(row.try_get(1)?),
+                kind: kind.parse()?,
+                peer_id: row.try_get(3)?,
+                message: row.try_get(4)?,
+            });
+        }
+
+        Ok(events)
+    }
+
+    async fn delete_audit_events_before(&self, cutoff: std::time::SystemTime) -> Result<u64> {
+        let client = self.get_client().await?;
+        let removed = client
+            .execute(
+                "DELETE FROM audit_events WHERE timestamp < $1",
+                &[&Monitor::timestamp_to_i64(cutoff)],
+            )
+            .await?;
+
+        Ok(removed)
+    }
+
+    async fn has_peer_result(
+        &self,
+        monitor_uuid: Uuid,
+        peer_id: &str,
+        timestamp: std::time::SystemTime,
+    ) -> Result<bool> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_opt(
+                "SELECT 1 FROM peer_results WHERE monitor_uuid = $1 AND peer_id = $2 AND \
+                 timestamp = $3 LIMIT 1",
+                &[&monitor_uuid.to_string(), &peer_id, &Monitor::timestamp_to_i64(timestamp)],
+            )
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    async fn has_result(
+        &self,
+        monitor_uuid: Uuid,
+        peer_id: &str,
+        timestamp: std::time::SystemTime,
+    ) -> Result<bool> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_opt(
+                "SELECT 1 FROM monitor_results WHERE monitor_uuid = $1 AND peer_id = $2 AND \
+                 timestamp = $3 LIMIT 1",
+                &[&monitor_uuid.to_string(), &peer_id, &Monitor::timestamp_to_i64(timestamp)],
+            )
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    async fn get_owner_sync_state(&self) -> Result<Option<OwnerSyncState>> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_opt(
+                "SELECT running, monitors_total, monitors_synced, records_found, failures, \
+                 last_started_at, last_completed_at, next_attempt_at, last_error FROM \
+                 owner_sync_state WHERE id = 1",
+                &[],
+            )
+            .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        Ok(Some(OwnerSyncState {
+            running: row.try_get::<_, i64>(0)? != 0,
+            monitors_total: row.try_get::<_, i64>(1)? as u64,
+            monitors_synced: row.try_get::<_, i64>(2)? as u64,
+            records_found: row.try_get::<_, i64>(3)? as u64,
+            failures: row.try_get::<_, i64>(4)? as u32,
+            last_started_at: row.try_get::<_, Option<i64>>(5)?.map(Monitor::i64_to_timestamp),
+            last_completed_at: row.try_get::<_, Option<i64>>(6)?.map(Monitor::i64_to_timestamp),
+            next_attempt_at: row.try_get::<_, Option<i64>>(7)?.map(Monitor::i64_to_timestamp),
+            last_error: row.try_get(8)?,
+        }))
+    }
+
+    async fn save_owner_sync_state(&self, state: &OwnerSyncState) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO owner_sync_state (id, running, monitors_total, monitors_synced, \
+                 records_found, failures, last_started_at, last_completed_at, next_attempt_at, \
+                 last_error) VALUES (1, $1, $2, $3, $4, $5, $6, $7, $8, $9)
+                 ON CONFLICT (id) DO UPDATE SET running = excluded.running, monitors_total = \
+                 excluded.monitors_total, monitors_synced = excluded.monitors_synced, \
+                 records_found = excluded.records_found, failures = excluded.failures, \
+                 last_started_at = excluded.last_started_at, last_completed_at = \
+                 excluded.last_completed_at, next_attempt_at = excluded.next_attempt_at, \
+                 last_error = excluded.last_error",
+                &[
+                    &(state.running as i64),
+                    &(state.monitors_total as i64),
+                    &(state.monitors_synced as i64),
+                    &(state.records_found as i64),
+                    &(state.failures as i64),
+                    &state.last_started_at.map(Monitor::timestamp_to_i64),
+                    &state.last_completed_at.map(Monitor::timestamp_to_i64),
+                    &state.next_attempt_at.map(Monitor::timestamp_to_i64),
+                    &state.last_error,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_local_statuses(
+        &self,
+        monitor_uuid: Uuid,
+        start: std::time::SystemTime,
+        end: std::time::SystemTime,
+    ) -> Result<Vec<(std::time::SystemTime, MonitorStatus)>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                "SELECT timestamp, status FROM monitor_results WHERE monitor_uuid = $1 AND \
+                 timestamp >= $2 AND timestamp < $3 AND maintenance = 0",
+                &[
+                    &monitor_uuid.to_string(),
+                    &Monitor::timestamp_to_i64(start),
+                    &Monitor::timestamp_to_i64(end),
+                ],
+            )
+            .await?;
+
+        let mut statuses = Vec::new();
+        for row in rows {
+            let status: String = row.try_get(1)?;
+            statuses.push((Monitor::i64_to_timestamp(row.try_get(0)?), parse_status(&status)));
+        }
+
+        Ok(statuses)
+    }
+
+    async fn get_peer_results_between(
+        &self,
+        monitor_uuid: Uuid,
+        start: std::time::SystemTime,
+        end: std::time::SystemTime,
+    ) -> Result<Vec<PeerResult>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {PEER_RESULT_COLUMNS} FROM peer_results WHERE monitor_uuid = $1 AND \
+                     timestamp >= $2 AND timestamp < $3 ORDER BY timestamp ASC"
+                ),
+                &[
+                    &monitor_uuid.to_string(),
+                    &Monitor::timestamp_to_i64(start),
+                    &Monitor::timestamp_to_i64(end),
+                ],
+            )
+            .await?;
+
+        rows.iter().map(peer_result_from_row).collect()
+    }
+
+    async fn get_peer_results_page(
+        &self,
+        monitor_uuid: Uuid,
+        since: std::time::SystemTime,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<PeerResult>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {PEER_RESULT_COLUMNS} FROM peer_results WHERE monitor_uuid = $1 AND \
+                     verified = 1 AND retracted = 0 AND timestamp >= $2 AND id > $3 ORDER BY id \
+                     ASC LIMIT $4"
+                ),
+                &[
+                    &monitor_uuid.to_string(),
+                    &Monitor::timestamp_to_i64(since),
+                    &after_id,
+                    &(limit as i64),
+                ],
+            )
+            .await?;
+
+        rows.iter().map(peer_result_from_row).collect()
+    }
+
+    async fn get_verified_peer_statuses(
+        &self,
+        monitor_uuid: Uuid,
+        start: std::time::SystemTime,
+        end: std::time::SystemTime,
+    ) -> Result<Vec<(String, std::time::SystemTime, MonitorStatus)>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                "SELECT peer_id, timestamp, status FROM peer_results WHERE monitor_uuid = $1 AND \
+                 verified = 1 AND timestamp >= $2 AND timestamp < $3",
+                &[
+                    &monitor_uuid.to_string(),
+                    &Monitor::timestamp_to_i64(start),
+                    &Monitor::timestamp_to_i64(end),
+                ],
+            )
+            .await?;
+
+        let mut statuses = Vec::new();
+        for row in rows {
+            let status: String = row.try_get(2)?;
+            statuses.push((
+                row.try_get(0)?,
+                Monitor::i64_to_timestamp(row.try_get(1)?),
+                parse_status(&status),
+            ));
+        }
+
+        Ok(statuses)
+    }
+
+    async fn save_result_agreement(&self, agreement: &ResultAgreement) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO result_agreement (monitor_uuid, timestamp, bucket_seconds, \
+                 local_status, peers_total, peers_agreeing, updated_at) VALUES ($1, $2, $3, $4, \
+                 $5, $6, $7)
+                 ON CONFLICT (monitor_uuid, timestamp) DO UPDATE SET bucket_seconds = \
+                 excluded.bucket_seconds, local_status = excluded.local_status, peers_total = \
+                 excluded.peers_total, peers_agreeing = excluded.peers_agreeing, updated_at = \
+                 excluded.updated_at",
+                &[
+                    &agreement.monitor_uuid.to_string(),
+                    &Monitor::timestamp_to_i64(agreement.bucket_start),
+                    &(agreement.bucket_seconds as i64),
+                    &agreement.local_status.to_string(),
+                    &(agreement.peers_total as i64),
+                    &(agreement.peers_agreeing as i64),
+                    &Monitor::timestamp_to_i64(agreement.updated_at),
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_result_agreements(
+        &self,
+        monitor_uuid: Uuid,
+        limit: usize,
+    ) -> Result<Vec<ResultAgreement>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                "SELECT timestamp, bucket_seconds, local_status, peers_total, peers_agreeing, \
+                 updated_at FROM result_agreement WHERE monitor_uuid = $1 ORDER BY timestamp DESC \
+                 LIMIT $2",
+                &[&monitor_uuid.to_string(), &(limit as i64)],
+            )
+            .await?;
+
+        let mut agreements = Vec::new();
+        for row in rows {
+            let status: String = row.try_get(2)?;
+            agreements.push(ResultAgreement {
+                monitor_uuid,
+                bucket_start: Monitor::i64_to_timestamp(row.try_get(0)?),
+                bucket_seconds: row.try_get::<_, i64>(1)? as u64,
+                local_status: parse_status(&status),
+                peers_total: row.try_get::<_, i64>(3)? as u32,
+                peers_agreeing: row.try_get::<_, i64>(4)? as u32,
+                updated_at: Monitor::i64_to_timestamp(row.try_get(5)?),
+            });
+        }
+
+        Ok(agreements)
+    }
+
+    async fn get_result_rollups(
+        &self,
+        monitor_uuid: Uuid,
+        start: std::time::SystemTime,
+        bucket_seconds: u64,
+    ) -> Result<Vec<ResultRollup>> {
+        let bucket_seconds = bucket_seconds.max(1) as i64;
+        let start = Monitor::timestamp_to_i64(start);
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                "SELECT (timestamp / $2) * $2 AS bucket, COUNT(*), SUM(CASE WHEN status IN ('up', \
+                 'degraded') THEN 1 ELSE 0 END), SUM(CASE WHEN status = 'down' THEN 1 ELSE 0 \
+                 END), AVG(latency_ms)::DOUBLE PRECISION, MAX(latency_ms) FROM monitor_results \
+                 WHERE monitor_uuid = $1 AND timestamp >= $3 AND maintenance = 0 GROUP BY bucket \
+                 ORDER BY bucket",
+                &[&monitor_uuid.to_string(), &bucket_seconds, &(start - start % bucket_seconds)],
+            )
+            .await?;
+
+        let mut rollups = Vec::new();
+        for row in rows {
+            rollups.push(ResultRollup {
+                bucket_start: Monitor::i64_to_timestamp(row.try_get(0)?),
+                checks: row.try_get::<_, i64>(1)? as u64,
+                up: row.try_get::<_, i64>(2)? as u64,
+                down: row.try_get::<_, i64>(3)? as u64,
+                avg_latency_ms: row.try_get::<_, Option<f64>>(4)?.map(|v| v.round() as u64),
+                p95_latency_ms: None,
+                max_latency_ms: row.try_get::<_, Option<i64>>(5)?.map(|v| v as u64),
+            });
+        }
+
+        Ok(rollups)
+    }
+
+    async fn rollup_results(&self, now: std::time::SystemTime) -> Result<u64> {
+        let mut client = self.get_client().await?;
+        let now = Monitor::timestamp_to_i64(now);
+        let hour = RollupPeriod::Hourly.seconds() as i64;
+        let day = RollupPeriod::Daily.seconds() as i64;
+
+        // The hour the last rollup ran in was not over yet, so its results
+        // are picked up again
+        let last: Option<i64> = client
+            .query_one("SELECT MAX(rolled_up_at) FROM result_rollups_hourly", &[])
+            .await?
+            .try_get(0)?;
+        let last = last.unwrap_or_default();
+        let rows = client
+            .query(
+                "SELECT DISTINCT monitor_uuid, timestamp / 3600 * 3600 FROM monitor_results WHERE \
+                 created_at >= $1 AND timestamp < $2 AND maintenance = 0",
+                &[&(last - last % hour), &(now - now % hour)],
+            )
+            .await?;
+        let mut hours = Vec::new();
+        for row in rows {
+            hours.push((row.try_get::<_, String>(0)?, row.try_get::<_, i64>(1)?));
+        }
+        if hours.is_empty() {
+            return Ok(0);
+        }
+
+        let tx = client.transaction().await?;
+        let mut days = std::collections::BTreeSet::new();
+        for (monitor_uuid, bucket) in &hours {
+            let rows = tx
+                .query(
+                    "SELECT status, latency_ms FROM monitor_results WHERE monitor_uuid = $1 AND \
+                     timestamp >= $2 AND timestamp < $3 AND maintenance = 0",
+                    &[monitor_uuid, bucket, &(bucket + hour)],
+                )
+                .await?;
+            let mut results = Vec::new();
+            for row in rows {
+                results.push((
+                    parse_status(&row.try_get::<_, String>(0)?),
+                    row.try_get::<_, Option<i64>>(1)?.map(|v| v as u64),
+                ));
+            }
+            let rollup = ResultRollup::from_results(Monitor::i64_to_timestamp(*bucket), &results);
+            save_rollup(&tx, RollupPeriod::Hourly, monitor_uuid, &rollup, now).await?;
+            days.insert((monitor_uuid.clone(), bucket - bucket % day));
+        }
+
+        for (monitor_uuid, bucket) in days {
+            let rows = tx
+                .query(
+                    &format!(
+                        "SELECT {ROLLUP_COLUMNS} FROM result_rollups_hourly WHERE monitor_uuid = \
+                         $1 AND bucket_start >= $2 AND bucket_start < $3"
+                    ),
+                    &[&monitor_uuid, &bucket, &(bucket + day)],
+                )
+                .await?;
+            let hourly = rows.iter().map(rollup_from_row).collect::<Result<Vec<_>>>()?;
+            let rollup = ResultRollup::merge(Monitor::i64_to_timestamp(bucket), &hourly);
+            save_rollup(&tx, RollupPeriod::Daily, &monitor_uuid, &rollup, now).await?;
+        }
+        tx.commit().await?;
+
+        Ok(hours.len() as u64)
+    }
+
+    async fn get_rollups(
+        &self,
+        monitor_uuid: Uuid,
+        period: RollupPeriod,
+        start: std::time::SystemTime,
+    ) -> Result<Vec<ResultRollup>> {
+        let seconds = period.seconds() as i64;
+        let start = Monitor::timestamp_to_i64(start);
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {ROLLUP_COLUMNS} FROM {} WHERE monitor_uuid = $1 AND bucket_start >= \
+                     $2 ORDER BY bucket_start",
+                    rollup_table(period)
+                ),
+                &[&monitor_uuid.to_string(), &(start - start % seconds)],
+            )
+            .await?;
+
+        rows.iter().map(rollup_from_row).collect()
+    }
+
+    async fn delete_rollups_before(
+        &self,
+        period: RollupPeriod,
+        before: std::time::SystemTime,
+    ) -> Result<u64> {
+        let client = self.get_client().await?;
+        let removed = client
+            .execute(
+                &format!("DELETE FROM {} WHERE bucket_start < $1", rollup_table(period)),
+                &[&Monitor::timestamp_to_i64(before)],
+            )
+            .await?;
+
+        Ok(removed)
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let client = self.get_client().await?;
+        client.query_one("SELECT 1", &[]).await?;
+        Ok(())
+    }
+
+    async fn get_private_monitor_uuids(&self) -> Result<Vec<Uuid>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query("SELECT uuid FROM monitors WHERE visibility = 'private'", &[])
+            .await?;
+
+        let mut uuids = Vec::new();
+        for row in rows {
+            let uuid: String = row.try_get(0)?;
+            uuids.push(Uuid::parse_str(&uuid)?);
+        }
+
+        Ok(uuids)
+    }
+
+    async fn get_monitors_to_retract(&self) -> Result<Vec<Monitor>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {MONITOR_COLUMNS} FROM monitors WHERE visibility = 'private' AND \
+                     retracted_at IS NULL"
+                ),
+                &[],
+            )
+            .await?;
+
+        rows.iter().map(monitor_from_row).collect()
+    }
+
+    async fn retract_monitor_results(
+        &self,
+        monitor_uuid: Uuid,
+        at: std::time::SystemTime,
+    ) -> Result<u64> {
+        let client = self.get_client().await?;
+        let monitor_uuid = monitor_uuid.to_string();
+        let marked = client
+            .execute(
+                "UPDATE monitor_results SET retracted = 1 WHERE monitor_uuid = $1 AND retracted = \
+                 0",
+                &[&monitor_uuid],
+            )
+            .await?;
+        client
+            .execute(
+                "UPDATE monitors SET retracted_at = $1 WHERE uuid = $2",
+                &[&Monitor::timestamp_to_i64(at), &monitor_uuid],
+            )
+            .await?;
+
+        Ok(marked)
+    }
+
+    async fn retract_peer_results(&self, monitor_uuid: Uuid, owner_peer_id: &str) -> Result<u64> {
+        let client = self.get_client().await?;
+        let marked = client
+            .execute(
+                "UPDATE peer_results SET retracted = 1 WHERE monitor_uuid = $1 AND peer_id = $2 \
+                 AND retracted = 0",
+                &[&monitor_uuid.to_string(), &owner_peer_id],
+            )
+            .await?;
+
+        Ok(marked)
+    }
+
+    async fn get_online_peers(&self) -> Result<Vec<Peer>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {PEER_COLUMNS} FROM peers WHERE status = 'online' AND banned_at IS \
+                     NULL ORDER BY last_seen DESC"
+                ),
+                &[],
+            )
+            .await?;
+
+        rows.iter().map(peer_from_row).collect()
+    }
+
+    async fn get_peers(&self) -> Result<Vec<Peer>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {PEER_COLUMNS} FROM peers ORDER BY status = 'online' DESC, last_seen \
+                     DESC"
+                ),
+                &[],
+            )
+            .await?;
+
+        rows.iter().map(peer_from_row).collect()
+    }
+
+    async fn record_peer_address(&self, peer_id: &str, address: &str) -> Result<()> {
+        let client = self.get_client().await?;
+        let Some(row) = client
+            .query_opt("SELECT addresses FROM peers WHERE peer_id = $1", &[&peer_id])
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let stored: String = row.try_get(0)?;
+        let mut addresses = serde_json::from_str::<Vec<String>>(&stored).unwrap_or_default();
+        addresses.retain(|known| known != address);
+        addresses.insert(0, address.to_string());
+        addresses.truncate(MAX_PEER_ADDRESSES);
+
+        client
+            .execute(
+                "UPDATE peers SET addresses = $1 WHERE peer_id = $2",
+                &[&serde_json::to_string(&addresses)?, &peer_id],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_peer_banned(
+        &self,
+        peer_id: &str,
+        banned_at: Option<std::time::SystemTime>,
+    ) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "UPDATE peers SET banned_at = $1 WHERE peer_id = $2",
+                &[&banned_at.map(Monitor::timestamp_to_i64), &peer_id],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_banned_peer_ids(&self) -> Result<Vec<String>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query("SELECT peer_id FROM peers WHERE banned_at IS NOT NULL", &[])
+            .await?;
+
+        Ok(rows.iter().map(|row| row.try_get(0)).collect::<Result<_, _>>()?)
+    }
+
+    async fn record_peer_greylisted(&self, peer_id: &str, at: std::time::SystemTime) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "UPDATE peers SET greylist_count = greylist_count + 1, greylisted_at = $1 WHERE \
+                 peer_id = $2",
+                &[&Monitor::timestamp_to_i64(at), &peer_id],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_results_from_peer(
+        &self,
+        source_peer_id: &str,
+        limit: usize,
+    ) -> Result<Vec<PeerResult>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {PEER_RESULT_COLUMNS} FROM peer_results WHERE source_peer_id = $1 \
+                     ORDER BY timestamp DESC LIMIT $2"
+                ),
+                &[&source_peer_id, &(limit as i64)],
+            )
+            .await?;
+
+        rows.iter().map(peer_result_from_row).collect()
+    }
+
+    async fn count_results_from_peer(&self, source_peer_id: &str) -> Result<(u64, u64)> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_one(
+                "SELECT COUNT(*), COALESCE(SUM(verified), 0)::BIGINT FROM peer_results WHERE \
+                 source_peer_id = $1",
+                &[&source_peer_id],
+            )
+            .await?;
+
+        Ok((row.try_get::<_, i64>(0)? as u64, row.try_get::<_, i64>(1)? as u64))
+    }
+
+    async fn save_multi_vantage_result(&self, result: &MultiVantageResult) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO multi_vantage_results (uuid, monitor_uuid, timestamp, \
+                 peers_requested, region, state, status, peers_responded, peers_up, \
+                 latency_min_ms, latency_median_ms, latency_max_ms, vantages, error, \
+                 completed_at, vantage_peer_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, \
+                 $11, $12, $13, $14, $15, $16)
+                 ON CONFLICT (uuid) DO UPDATE SET state = excluded.state, status = \
+                 excluded.status, peers_responded = excluded.peers_responded, peers_up = \
+                 excluded.peers_up, latency_min_ms = excluded.latency_min_ms, latency_median_ms = \
+                 excluded.latency_median_ms, latency_max_ms = excluded.latency_max_ms, vantages = \
+                 excluded.vantages, error = excluded.error, completed_at = excluded.completed_at",
+                &[
+                    &result.uuid.to_string(),
+                    &result.monitor_uuid.to_string(),
+                    &Monitor::timestamp_to_i64(result.requested_at),
+                    &(result.peers_requested as i64),
+                    &result.region,
+                    &result.state.to_string(),
+                    &result.status.map(|status| status.to_string()),
+                    &(result.peers_responded as i64),
+                    &(result.peers_up as i64),
+                    &result.latency_min_ms.map(|v| v as i64),
+                    &result.latency_median_ms.map(|v| v as i64),
+                    &result.latency_max_ms.map(|v| v as i64),
+                    &serde_json::to_string(&result.vantages)?,
+                    &result.error,
+                    &result.completed_at.map(Monitor::timestamp_to_i64),
+                    &result.vantage_peer_id,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_multi_vantage_results_in_state(
+        &self,
+        state: ProbeState,
+    ) -> Result<Vec<MultiVantageResult>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {MULTI_VANTAGE_COLUMNS} FROM multi_vantage_results WHERE state = $1 \
+                     ORDER BY timestamp ASC"
+                ),
+                &[&state.to_string()],
+            )
+            .await?;
+
+        rows.iter().map(multi_vantage_from_row).collect()
+    }
+
+    async fn get_multi_vantage_results(
+        &self,
+        monitor_uuid: Uuid,
+        limit: usize,
+    ) -> Result<Vec<MultiVantageResult>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {MULTI_VANTAGE_COLUMNS} FROM multi_vantage_results WHERE monitor_uuid \
+                     = $1 ORDER BY timestamp DESC LIMIT $2"
+                ),
+                &[&monitor_uuid.to_string(), &(limit as i64)],
+            )
+            .await?;
+
+        rows.iter().map(multi_vantage_from_row).collect()
+    }
+
+    async fn save_dht_operation(&self, operation: &DhtOperation) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO dht_operations (uuid, kind, key, value, state, records, error, \
+                 timestamp, completed_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                 ON CONFLICT (uuid) DO UPDATE SET state = excluded.state, records = \
+                 excluded.records, error = excluded.error, completed_at = excluded.completed_at",
+                &[
+                    &operation.uuid.to_string(),
+                    &operation.kind.to_string(),
+                    &operation.key,
+                    &operation.value,
+                    &operation.state.to_string(),
+                    &serde_json::to_string(&operation.records)?,
+                    &operation.error,
+                    &Monitor::timestamp_to_i64(operation.requested_at),
+                    &operation.completed_at.map(Monitor::timestamp_to_i64),
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_dht_operations_in_state(&self, state: ProbeState) -> Result<Vec<DhtOperation>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {DHT_OPERATION_COLUMNS} FROM dht_operations WHERE state = $1 ORDER BY \
+                     timestamp ASC"
+                ),
+                &[&state.to_string()],
+            )
+            .await?;
+
+        rows.iter().map(dht_operation_from_row).collect()
+    }
+
+    async fn get_dht_operations(&self, limit: usize) -> Result<Vec<DhtOperation>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {DHT_OPERATION_COLUMNS} FROM dht_operations ORDER BY timestamp DESC, \
+                     id DESC LIMIT $1"
+                ),
+                &[&(limit as i64)],
+            )
+            .await?;
+
+        rows.iter().map(dht_operation_from_row).collect()
+    }
+
+    async fn get_open_incidents(&self) -> Result<Vec<Incident>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {INCIDENT_COLUMNS} FROM incidents WHERE resolved_at IS NULL ORDER BY \
+                     started_at"
+                ),
+                &[],
+            )
+            .await?;
+
+        rows.iter().map(incident_from_row).collect()
+    }
+
+    async fn get_incidents(&self, limit: usize) -> Result<Vec<Incident>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {INCIDENT_COLUMNS} FROM incidents ORDER BY started_at DESC, id DESC \
+                     LIMIT $1"
+                ),
+                &[&(limit as i64)],
+            )
+            .await?;
+
+        rows.iter().map(incident_from_row).collect()
+    }
+
+    async fn get_incident(&self, uuid: Uuid) -> Result<Option<Incident>> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_opt(
+                &format!("SELECT {INCIDENT_COLUMNS} FROM incidents WHERE uuid = $1"),
+                &[&uuid.to_string()],
+            )
+            .await?;
+
+        row.as_ref().map(incident_from_row).transpose()
+    }
+
+    async fn save_incident(&self, incident: &Incident) -> Result<()> {
+        let client = self.get_client().await?;
+        let now = Monitor::timestamp_to_i64(std::time::SystemTime::now());
+        client
+            .execute(
+                "INSERT INTO incidents (uuid, title, status, severity, monitor_uuid, started_at, \
+                 resolved_at, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
+                 ON CONFLICT (uuid) DO UPDATE SET title = excluded.title, status = \
+                 excluded.status, severity = excluded.severity, resolved_at = \
+                 excluded.resolved_at, updated_at = excluded.updated_at",
+                &[
+                    &incident.uuid.to_string(),
+                    &incident.title,
+                    &incident.status,
+                    &incident.severity,
+                    &incident.monitor_uuid.map(|uuid| uuid.to_string()),
+                    &Monitor::timestamp_to_i64(incident.started_at),
+                    &incident.resolved_at.map(Monitor::timestamp_to_i64),
+                    &now,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn add_incident_update(&self, update: &IncidentUpdate) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO incident_updates (incident_uuid, status, message, created_at) VALUES \
+                 ($1, $2, $3, $4)",
+                &[
+                    &update.incident_uuid.to_string(),
+                    &update.status,
+                    &update.message,
+                    &Monitor::timestamp_to_i64(update.created_at),
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_incident_updates(&self, incident_uuid: Uuid) -> Result<Vec<IncidentUpdate>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                "SELECT status, message, created_at FROM incident_updates WHERE incident_uuid = \
+                 $1 ORDER BY created_at, id",
+                &[&incident_uuid.to_string()],
+            )
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(IncidentUpdate {
+                    incident_uuid,
+                    status: row.try_get(0)?,
+                    message: row.try_get(1)?,
+                    created_at: Monitor::i64_to_timestamp(row.try_get(2)?),
+                })
+            })
+            .collect()
+    }
+
+    async fn get_notification_channels(&self) -> Result<Vec<NotificationChannel>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                "SELECT uuid, name, kind, target, enabled, created_at, last_test_at, \
+                 last_test_error FROM notification_channels ORDER BY name",
+                &[],
+            )
+            .await?;
+
+        let mut channels = Vec::new();
+        for row in rows {
+            let uuid: String = row.try_get(0)?;
+            let kind: String = row.try_get(2)?;
+            channels.push(NotificationChannel {
+                uuid: Uuid::parse_str(&uuid)?,
+                name: row.try_get(1)?,
+                kind: kind.parse()?,
+                target: row.try_get(3)?,
+                enabled: row.try_get::<_, i64>(4)? != 0,
+                created_at: Monitor::i64_to_timestamp(row.try_get(5)?),
+                last_test_at: row.try_get::<_, Option<i64>>(6)?.map(Monitor::i64_to_timestamp),
+                last_test_error: row.try_get(7)?,
+            });
+        }
+
+        Ok(channels)
+    }
+
+    async fn save_notification_channel(&self, channel: &NotificationChannel) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO notification_channels (uuid, name, kind, target, enabled, \
+                 created_at, last_test_at, last_test_error) VALUES ($1, $2, $3, $4, $5, $6, $7, \
+                 $8)
+                 ON CONFLICT (uuid) DO UPDATE SET name = excluded.name, kind = excluded.kind, \
+                 target = excluded.target, enabled = excluded.enabled, last_test_at = \
+                 excluded.last_test_at, last_test_error = excluded.last_test_error",
+                &[
+                    &channel.uuid.to_string(),
+                    &channel.name,
+                    &channel.kind.to_string(),
+                    &channel.target,
+                    &(channel.enabled as i64),
+                    &Monitor::timestamp_to_i64(channel.created_at),
+                    &channel.last_test_at.map(Monitor::timestamp_to_i64),
+                    &channel.last_test_error,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_notification_channel(&self, uuid: Uuid) -> Result<()> {
+        let client = self.get_client().await?;
+        let uuid = uuid.to_string();
+        client
+            .execute("DELETE FROM notification_route_channels WHERE channel_uuid = $1", &[&uuid])
+            .await?;
+        client
+            .execute("DELETE FROM notification_channels WHERE uuid = $1", &[&uuid])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn record_notification_test(&self, uuid: Uuid, error: Option<&str>) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "UPDATE notification_channels SET last_test_at = $1, last_test_error = $2 WHERE \
+                 uuid = $3",
+                &[
+                    &Monitor::timestamp_to_i64(std::time::SystemTime::now()),
+                    &error,
+                    &uuid.to_string(),
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_notification_routes(&self) -> Result<Vec<NotificationRoute>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                "SELECT scope, escalate_after_secs, updated_at FROM notification_routes ORDER BY \
+                 scope",
+                &[],
+            )
+            .await?;
+
+        let mut routes = Vec::new();
+        for row in rows {
+            let scope: String = row.try_get(0)?;
+            routes.push(NotificationRoute {
+                scope: scope.parse()?,
+                channels: Vec::new(),
+                escalation: row
+                    .try_get::<_, Option<i64>>(1)?
+                    .map(|secs| EscalationPolicy { after_secs: secs as u64, channels: Vec::new() }),
+                updated_at: Monitor::i64_to_timestamp(row.try_get(2)?),
+            });
+        }
+
+        let rows = client
+            .query("SELECT scope, channel_uuid, escalation FROM notification_route_channels", &[])
+            .await?;
+        for row in rows {
+            let scope: String = row.try_get(0)?;
+            let channel: String = row.try_get(1)?;
+            let Some(route) = routes.iter_mut().find(|route| route.scope.to_string() == scope)
+            else {
+                continue;
+            };
+            let channel = Uuid::parse_str(&channel)?;
+            match (&mut route.escalation, row.try_get::<_, i64>(2)? != 0) {
+                (_, false) => route.channels.push(channel),
+                (Some(policy), true) => policy.channels.push(channel),
+                (None, true) => {}
+            }
+        }
+
+        Ok(routes)
+    }
+
+    async fn save_notification_route(&self, route: &NotificationRoute) -> Result<()> {
+        let mut client = self.get_client().await?;
+        let scope = route.scope.to_string();
+        let tx = client.transaction().await?;
+        tx.execute(
+            "INSERT INTO notification_routes (scope, escalate_after_secs, updated_at) VALUES ($1, \
+             $2, $3) ON CONFLICT (scope) DO UPDATE SET escalate_after_secs = \
+             excluded.escalate_after_secs, updated_at = excluded.updated_at",
+            &[
+                &scope,
+                &route.escalation.as_ref().map(|policy| policy.after_secs as i64),
+                &Monitor::timestamp_to_i64(route.updated_at),
+            ],
+        )
+        .await?;
+        tx.execute("DELETE FROM notification_route_channels WHERE scope = $1", &[&scope])
+            .await?;
+        let escalation = route.escalation.iter().flat_map(|policy| &policy.channels);
+        let channels = route
+            .channels
+            .iter()
+            .map(|uuid| (uuid, 0i64))
+            .chain(escalation.map(|uuid| (uuid, 1)));
+        for (channel, escalation) in channels {
+            tx.execute(
+                "INSERT INTO notification_route_channels (scope, channel_uuid, escalation) VALUES \
+                 ($1, $2, $3) ON CONFLICT DO NOTHING",
+                &[&scope, &channel.to_string(), &escalation],
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn delete_notification_route(&self, scope: &RouteScope) -> Result<bool> {
+        let client = self.get_client().await?;
+        let scope = scope.to_string();
+        client
+            .execute("DELETE FROM notification_route_channels WHERE scope = $1", &[&scope])
+            .await?;
+        let removed = client
+            .execute("DELETE FROM notification_routes WHERE scope = $1", &[&scope])
+            .await?;
+
+        Ok(removed > 0)
+    }
+
+    async fn get_status_pages(&self) -> Result<Vec<StatusPage>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(&format!("SELECT {STATUS_PAGE_COLUMNS} FROM status_pages ORDER BY slug"), &[])
+            .await?;
+
+        rows.iter().map(status_page_from_row).collect()
+    }
+
+    async fn get_status_page(&self, slug: &str) -> Result<Option<StatusPage>> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_opt(
+                &format!("SELECT {STATUS_PAGE_COLUMNS} FROM status_pages WHERE slug = $1"),
+                &[&slug],
+            )
+            .await?;
+
+        row.as_ref().map(status_page_from_row).transpose()
+    }
+
+    async fn save_status_page(&self, page: &StatusPage) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO status_pages (uuid, title, slug, description, is_active, created_at, \
+                 updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 ON CONFLICT (uuid) DO UPDATE SET title = excluded.title, slug = excluded.slug, \
+                 description = excluded.description, is_active = excluded.is_active, updated_at = \
+                 excluded.updated_at",
+                &[
+                    &page.uuid.to_string(),
+                    &page.title,
+                    &page.slug,
+                    &page.description,
+                    &(page.is_active as i64),
+                    &Monitor::timestamp_to_i64(page.created_at),
+                    &Monitor::timestamp_to_i64(page.updated_at),
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_status_page_monitors(&self, page_uuid: Uuid) -> Result<Vec<Monitor>> {
+        let client = self.get_client().await?;
+        let columns = MONITOR_COLUMNS.split(", ").map(|c| format!("m.{}", c.trim()));
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM monitors m JOIN status_page_monitors s ON s.monitor_uuid = \
+                     m.uuid WHERE s.status_page_id = $1 ORDER BY s.display_order",
+                    columns.collect::<Vec<_>>().join(", ")
+                ),
+                &[&page_uuid.to_string()],
+            )
+            .await?;
+
+        rows.iter().map(monitor_from_row).collect()
+    }
+
+    async fn set_status_page_monitor(
+        &self,
+        page_uuid: Uuid,
+        monitor_uuid: Uuid,
+        shown: bool,
+    ) -> Result<()> {
+        let client = self.get_client().await?;
+        let sql = if shown {
+            "INSERT INTO status_page_monitors (status_page_id, monitor_uuid, display_order) SELECT \
+             $1::TEXT, $2::TEXT, COALESCE(MAX(display_order), 0) + 1 FROM status_page_monitors \
+             WHERE status_page_id = $1 ON CONFLICT DO NOTHING"
+        } else {
+            "DELETE FROM status_page_monitors WHERE status_page_id = $1 AND monitor_uuid = $2"
+        };
+        client
+            .execute(sql, &[&page_uuid.to_string(), &monitor_uuid.to_string()])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_maintenance_windows(
+        &self,
+        now: std::time::SystemTime,
+    ) -> Result<Vec<MaintenanceWindow>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {MAINTENANCE_WINDOW_COLUMNS} FROM maintenance_windows WHERE ends_at > \
+                     $1 ORDER BY starts_at"
+                ),
+                &[&Monitor::timestamp_to_i64(now)],
+            )
+            .await?;
+
+        rows.iter().map(maintenance_window_from_row).collect()
+    }
+
+    async fn save_maintenance_window(&self, window: &MaintenanceWindow) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO maintenance_windows (uuid, monitor_uuid, starts_at, ends_at, reason, \
+                 created_at) VALUES ($1, $2, $3, $4, $5, $6)",
+                &[
+                    &window.uuid.to_string(),
+                    &window.monitor_uuid.map(|uuid| uuid.to_string()),
+                    &Monitor::timestamp_to_i64(window.starts_at),
+                    &Monitor::timestamp_to_i64(window.ends_at),
+                    &window.reason,
+                    &Monitor::timestamp_to_i64(window.created_at),
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_maintenance_window(&self, uuid: Uuid) -> Result<bool> {
+        let client = self.get_client().await?;
+        let deleted = client
+            .execute("DELETE FROM maintenance_windows WHERE uuid = $1", &[&uuid.to_string()])
+            .await?;
+
+        Ok(deleted > 0)
+    }
+
+    async fn get_follows(&self) -> Result<Vec<Follow>> {
+        let client = self.get_client().await?;
+        let rows = client.query("SELECT host, created_at FROM follows ORDER BY host", &[]).await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(Follow {
+                    host: row.try_get(0)?,
+                    created_at: Monitor::i64_to_timestamp(row.try_get(1)?),
+                })
+            })
+            .collect()
+    }
+
+    async fn save_follow(&self, follow: &Follow) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO follows (host, created_at) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                &[&follow.host, &Monitor::timestamp_to_i64(follow.created_at)],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_follow(&self, host: &str) -> Result<bool> {
+        let client = self.get_client().await?;
+        client.execute("DELETE FROM follow_monitors WHERE host = $1", &[&host]).await?;
+        let deleted = client.execute("DELETE FROM follows WHERE host = $1", &[&host]).await?;
+
+        Ok(deleted > 0)
+    }
+
+    async fn get_follow_monitors(&self, host: &str) -> Result<Vec<Uuid>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                "SELECT monitor_uuid FROM follow_monitors WHERE host = $1 ORDER BY first_seen",
+                &[&host],
+            )
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let uuid: String = row.try_get(0)?;
+                Ok(Uuid::parse_str(&uuid)?)
+            })
+            .collect()
+    }
+
+    async fn add_follow_monitor(&self, host: &str, monitor_uuid: Uuid) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO follow_monitors (host, monitor_uuid, first_seen) VALUES ($1, $2, $3) \
+                 ON CONFLICT DO NOTHING",
+                &[
+                    &host,
+                    &monitor_uuid.to_string(),
+                    &Monitor::timestamp_to_i64(std::time::SystemTime::now()),
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_community_domains(&self) -> Result<Vec<CommunityDomain>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                "SELECT host, joined, monitor_uuid, updated_at FROM community_domains ORDER BY \
+                 host",
+                &[],
+            )
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let monitor_uuid: Option<String> = row.try_get(2)?;
+                Ok(CommunityDomain {
+                    host: row.try_get(0)?,
+                    joined: row.try_get::<_, i64>(1)? != 0,
+                    monitor_uuid: monitor_uuid.as_deref().map(Uuid::parse_str).transpose()?,
+                    updated_at: Monitor::i64_to_timestamp(row.try_get(3)?),
+                })
+            })
+            .collect()
+    }
+
+    async fn save_community_domain(&self, domain: &CommunityDomain) -> Result<()> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO community_domains (host, joined, monitor_uuid, updated_at) VALUES \
+                 ($1, $2, $3, $4)
+                 ON CONFLICT (host) DO UPDATE SET joined = excluded.joined, monitor_uuid = \
+                 excluded.monitor_uuid, updated_at = excluded.updated_at",
+                &[
+                    &domain.host,
+                    &(domain.joined as i64),
+                    &domain.monitor_uuid.map(|uuid| uuid.to_string()),
+                    &Monitor::timestamp_to_i64(domain.updated_at),
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_group_memberships(&self) -> Result<Vec<GroupMembership>> {
+        let client = self.get_client().await?;
+        let rows = client
+            .query(
+                "SELECT host, peer_id, joined, announced_at FROM group_memberships ORDER BY host, \
+                 peer_id",
+                &[],
+            )
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(GroupMembership {
+                    host: row.try_get(0)?,
+                    peer_id: row.try_get(1)?,
+                    joined: row.try_get::<_, i64>(2)? != 0,
+                    announced_at: Monitor::i64_to_timestamp(row.try_get(3)?),
+                })
+            })
+            .collect()
+    }
+
+    async fn save_group_membership(&self, membership: &GroupMembership) -> Result<bool> {
+        let client = self.get_client().await?;
+        let saved = client
+            .execute(
+                "INSERT INTO group_memberships (host, peer_id, joined, announced_at) VALUES ($1, \
+                 $2, $3, $4)
+                 ON CONFLICT (host, peer_id) DO UPDATE SET joined = excluded.joined, announced_at \
+                 = excluded.announced_at
+                 WHERE excluded.announced_at > group_memberships.announced_at",
+                &[
+                    &membership.host,
+                    &membership.peer_id,
+                    &(membership.joined as i64),
+                    &Monitor::timestamp_to_i64(membership.announced_at),
+                ],
+            )
+            .await?;
+
+        Ok(saved > 0)
+    }
+
+    async fn add_peer_contribution(&self, peer_id: &str, score: f64) -> Result<()> {
+        let client = self.get_client().await?;
+        let now = Monitor::timestamp_to_i64(std::time::SystemTime::now());
+        client
+            .execute(
+                "INSERT INTO peers (peer_id, status, last_seen, joined_at, contribution_score)
+                 VALUES ($1, 'online', $2, $2, 1.0 + $3::DOUBLE PRECISION)
+                 ON CONFLICT (peer_id) DO UPDATE SET contribution_score = \
+                 COALESCE(peers.contribution_score, 1.0) + $3::DOUBLE PRECISION",
+                &[&peer_id, &now, &score],
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Extra condition keeping restored results for a week after their restore,
+/// whatever their age
+const RESTORED_KEPT: &str = "AND (r.restored_at IS NULL OR r.restored_at < $1 - 7 * 86400)";
+
+/// Columns `monitor_result_from_row` expects, in order
+const MONITOR_RESULT_COLUMNS: &str = "id, monitor_uuid, timestamp, status, latency_ms, \
+                                      status_code, error_message, peer_id, signature, created_at, \
+                                      city, country, region, maintenance";
+
+fn monitor_result_from_row(row: &Row) -> Result<MonitorResult> {
+    let monitor_uuid: String = row.try_get(1)?;
+    let status: String = row.try_get(3)?;
+
+    Ok(MonitorResult {
+        id: Some(row.try_get(0)?),
+        monitor_uuid: Uuid::parse_str(&monitor_uuid)?,
+        timestamp: Monitor::i64_to_timestamp(row.try_get(2)?),
+        status: parse_status(&status),
+        latency_ms: row.try_get::<_, Option<i64>>(4)?.map(|v| v as u64),
+        status_code: row.try_get::<_, Option<i64>>(5)?.map(|v| v as u16),
+        error_message: row.try_get(6)?,
+        peer_id: row.try_get(7)?,
+        signature: row.try_get(8)?,
+        created_at: Monitor::i64_to_timestamp(row.try_get(9)?),
+        city: row.try_get(10)?,
+        country: row.try_get(11)?,
+        region: row.try_get(12)?,
+        maintenance: row.try_get::<_, i64>(13)? != 0,
+    })
+}
+
+fn maintenance_window_from_row(row: &Row) -> Result<MaintenanceWindow> {
+    let uuid: String = row.try_get(0)?;
+    let monitor_uuid: Option<String> = row.try_get(1)?;
+    Ok(MaintenanceWindow {
+        uuid: Uuid::parse_str(&uuid)?,
+        monitor_uuid: monitor_uuid.as_deref().map(Uuid::parse_str).transpose()?,
+        starts_at: Monitor::i64_to_timestamp(row.try_get(2)?),
+        ends_at: Monitor::i64_to_timestamp(row.try_get(3)?),
+        reason: row.try_get(4)?,
+        created_at: Monitor::i64_to_timestamp(row.try_get(5)?),
+    })
+}
+
+fn status_page_from_row(row: &Row) -> Result<StatusPage> {
+    let uuid: String = row.try_get(0)?;
+    let description: Option<String> = row.try_get(3)?;
+    Ok(StatusPage {
+        uuid: Uuid::parse_str(&uuid)?,
+        title: row.try_get(1)?,
+        slug: row.try_get(2)?,
+        description: description.unwrap_or_default(),
+        is_active: row.try_get::<_, i64>(4)? != 0,
+        created_at: Monitor::i64_to_timestamp(row.try_get(5)?),
+        updated_at: Monitor::i64_to_timestamp(row.try_get(6)?),
+    })
+}
+
+fn monitor_from_row(row: &Row) -> Result<Monitor> {
+    let uuid: String = row.try_get(1)?;
+    let visibility: String = row.try_get(11)?;
+    let tags: String = row.try_get(12)?;
+
+    Ok(Monitor {
+        id: Some(row.try_get(0)?),
+        uuid: Uuid::parse_str(&uuid)?,
+        name: row.try_get(2)?,
+        target: row.try_get(3)?,
+        check_type: row.try_get(4)?,
+        interval_seconds: row.try_get::<_, i64>(5)? as u64,
+        timeout_seconds: row.try_get::<_, i64>(6)? as u64,
+        grace_seconds: row.try_get::<_, i64>(15)?.max(0) as u64,
+        enabled: row.try_get::<_, i64>(7)? != 0,
+        retention_days: row.try_get::<_, Option<i64>>(10)?.map(|days| days.max(0) as u64),
+        visibility: visibility.parse().unwrap_or_default(),
+        tags: Monitor::parse_tags(&tags),
+        expected_status_codes: parse_json_column(row.try_get(13)?),
+        headers: parse_json_column(row.try_get(14)?),
+        created_at: Monitor::i64_to_timestamp(row.try_get(8)?),
+        updated_at: Monitor::i64_to_timestamp(row.try_get(9)?),
+    })
+}
+
+fn peer_from_row(row: &Row) -> Result<Peer> {
+    let addresses: String = row.try_get(10)?;
+
+    Ok(Peer {
+        peer_id: row.try_get(0)?,
+        status: row.try_get(1)?,
+        last_seen: Monitor::i64_to_timestamp(row.try_get(2)?),
+        joined_at: Monitor::i64_to_timestamp(row.try_get(3)?),
+        contribution_score: row.try_get::<_, Option<f64>>(4)?.unwrap_or(1.0),
+        uptime_percentage: row.try_get::<_, Option<f64>>(5)?.unwrap_or(100.0),
+        checks_per_day: row.try_get::<_, Option<i64>>(6)?.unwrap_or(0),
+        location_city: row.try_get(7)?,
+        location_region: row.try_get(8)?,
+        location_country: row.try_get(9)?,
+        addresses: serde_json::from_str(&addresses).unwrap_or_default(),
+        banned_at: row.try_get::<_, Option<i64>>(11)?.map(Monitor::i64_to_timestamp),
+        greylist_count: row.try_get::<_, Option<i64>>(12)?.unwrap_or(0) as u32,
+        greylisted_at: row.try_get::<_, Option<i64>>(13)?.map(Monitor::i64_to_timestamp),
+    })
+}
+
+fn peer_result_from_row(row: &Row) -> Result<PeerResult> {
+    let monitor_uuid: String = row.try_get(1)?;
+    let status: String = row.try_get(3)?;
+    let certificate: Option<String> = row.try_get(15)?;
+
+    Ok(PeerResult {
+        id: Some(row.try_get(0)?),
+        monitor_uuid: Uuid::parse_str(&monitor_uuid)?,
+        timestamp: Monitor::i64_to_timestamp(row.try_get(2)?),
+        status: parse_status(&status),
+        latency_ms: row.try_get::<_, Option<i64>>(4)?.map(|v| v as u64),
+        status_code: row.try_get::<_, Option<i64>>(5)?.map(|v| v as u16),
+        error_message: row.try_get(6)?,
+        peer_id: row.try_get(7)?,
+        signature: row.try_get(8)?,
+        verified: row.try_get::<_, i64>(9)? != 0,
+        created_at: Monitor::i64_to_timestamp(row.try_get(10)?),
+        city: row.try_get(11)?,
+        country: row.try_get(12)?,
+        region: row.try_get(13)?,
+        source_peer_id: row.try_get(14)?,
+        certificate: certificate.and_then(|json| serde_json::from_str(&json).ok()),
+    })
+}
+
+fn multi_vantage_from_row(row: &Row) -> Result<MultiVantageResult> {
+    let uuid: String = row.try_get(0)?;
+    let monitor_uuid: String = row.try_get(1)?;
+    let state: String = row.try_get(5)?;
+    let status: Option<String> = row.try_get(6)?;
+    let vantages: String = row.try_get(12)?;
+
+    Ok(MultiVantageResult {
+        uuid: Uuid::parse_str(&uuid)?,
+        monitor_uuid: Uuid::parse_str(&monitor_uuid)?,
+        requested_at: Monitor::i64_to_timestamp(row.try_get(2)?),
+        peers_requested: row.try_get::<_, i64>(3)? as u32,
+        region: row.try_get(4)?,
+        state: state.parse()?,
+        status: status.as_deref().map(parse_status),
+        peers_responded: row.try_get::<_, i64>(7)? as u32,
+        peers_up: row.try_get::<_, i64>(8)? as u32,
+        latency_min_ms: row.try_get::<_, Option<i64>>(9)?.map(|v| v as u64),
+        latency_median_ms: row.try_get::<_, Option<i64>>(10)?.map(|v| v as u64),
+        latency_max_ms: row.try_get::<_, Option<i64>>(11)?.map(|v| v as u64),
+        vantages: serde_json::from_str::<Vec<Vantage>>(&vantages).unwrap_or_default(),
+        error: row.try_get(13)?,
+        completed_at: row.try_get::<_, Option<i64>>(14)?.map(Monitor::i64_to_timestamp),
+        vantage_peer_id: row.try_get(15)?,
+    })
+}
+
+fn rollup_from_row(row: &Row) -> Result<ResultRollup> {
+    Ok(ResultRollup {
+        bucket_start: Monitor::i64_to_timestamp(row.try_get(0)?),
+        checks: row.try_get::<_, i64>(1)? as u64,
+        up: row.try_get::<_, i64>(2)? as u64,
+        down: row.try_get::<_, i64>(3)? as u64,
+        avg_latency_ms: row.try_get::<_, Option<i64>>(4)?.map(|v| v as u64),
+        p95_latency_ms: row.try_get::<_, Option<i64>>(5)?.map(|v| v as u64),
+        max_latency_ms: row.try_get::<_, Option<i64>>(6)?.map(|v| v as u64),
+    })
+}
+
+/// Replace the stored rollup of a monitor's bucket
+async fn save_rollup(
+    tx: &tokio_postgres::Transaction<'_>,
+    period: RollupPeriod,
+    monitor_uuid: &str,
+    rollup: &ResultRollup,
+    rolled_up_at: i64,
+) -> Result<()> {
+    tx.execute(
+        &format!(
+            "INSERT INTO {} (monitor_uuid, {ROLLUP_COLUMNS}, rolled_up_at) VALUES ($1, $2, $3, \
+             $4, $5, $6, $7, $8, $9) ON CONFLICT (monitor_uuid, bucket_start) DO UPDATE SET \
+             checks = excluded.checks, up = excluded.up, down = excluded.down, avg_latency_ms = \
+             excluded.avg_latency_ms, p95_latency_ms = excluded.p95_latency_ms, max_latency_ms = \
+             excluded.max_latency_ms, rolled_up_at = excluded.rolled_up_at",
+            rollup_table(period)
+        ),
+        &[
+            &monitor_uuid,
+            &Monitor::timestamp_to_i64(rollup.bucket_start),
+            &(rollup.checks as i64),
+            &(rollup.up as i64),
+            &(rollup.down as i64),
+            &rollup.avg_latency_ms.map(|v| v as i64),
+            &rollup.p95_latency_ms.map(|v| v as i64),
+            &rollup.max_latency_ms.map(|v| v as i64),
+            &rolled_up_at,
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+fn incident_from_row(row: &Row) -> Result<Incident> {
+    let uuid: String = row.try_get(0)?;
+    let monitor_uuid: Option<String> = row.try_get(4)?;
+
+    Ok(Incident {
+        uuid: Uuid::parse_str(&uuid)?,
+        title: row.try_get(1)?,
+        status: row.try_get(2)?,
+        severity: row.try_get(3)?,
+        monitor_uuid: monitor_uuid.map(|uuid| Uuid::parse_str(&uuid)).transpose()?,
+        started_at: Monitor::i64_to_timestamp(row.try_get(5)?),
+        resolved_at: row.try_get::<_, Option<i64>>(6)?.map(Monitor::i64_to_timestamp),
+    })
+}
+
+fn dht_operation_from_row(row: &Row) -> Result<DhtOperation> {
+    let uuid: String = row.try_get(0)?;
+    let kind: String = row.try_get(1)?;
+    let state: String = row.try_get(4)?;
+    let records: String = row.try_get(5)?;
+
+    Ok(DhtOperation {
+        uuid: Uuid::parse_str(&uuid)?,
+        kind: kind.parse()?,
+        key: row.try_get(2)?,
+        value: row.try_get(3)?,
+        state: state.parse()?,
+        records: serde_json::from_str(&records).unwrap_or_default(),
+        error: row.try_get(6)?,
+        requested_at: Monitor::i64_to_timestamp(row.try_get(7)?),
+        completed_at: row.try_get::<_, Option<i64>>(8)?.map(Monitor::i64_to_timestamp),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DatabaseBackend, DatabaseConfig, DatabasePoolConfig};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    /// Server the tests run against, such as `postgres://uppe@localhost/uppe_test`
+    ///
+    /// The tests need a server, so they are ignored by default. Run them with
+    /// `UPPE_TEST_DATABASE_URL=... cargo test -p uppe-service postgres -- --ignored`.
+    const TEST_URL_VAR: &str = "UPPE_TEST_DATABASE_URL";
+
+    /// Connect to the test server
+    pub(super) async fn test_database() -> PostgresDatabaseImpl {
+        let url = std::env::var(TEST_URL_VAR)
+            .unwrap_or_else(|_| panic!("{TEST_URL_VAR} must be set to run the PostgreSQL tests"));
+        let config = DatabaseConfig { backend: DatabaseBackend::Postgres, url: Some(url) };
+        PostgresDatabaseImpl::connect(&config, &DatabasePoolConfig::default())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a PostgreSQL server at UPPE_TEST_DATABASE_URL"]
+    async fn test_monitor_round_trip() {
+        let db = test_database().await;
+
+        let mut monitor = Monitor::new(
+            "API".to_string(),
+            "https://api.example.com/health".to_string(),
+            "http".to_string(),
+        );
+        monitor.tags = vec!["api".to_string(), "prod".to_string()];
+        monitor.expected_status_codes = vec!["2xx".to_string()];
+        monitor.headers.insert("Accept".to_string(), "application/json".to_string());
+        monitor.created_at = UNIX_EPOCH + Duration::from_secs(1_790_000_000);
+        monitor.updated_at = monitor.created_at;
+        let id = db.save_monitor(&monitor).await.unwrap();
+        monitor.id = Some(id);
+
+        let stored = db.get_monitor_by_uuid(monitor.uuid).await.unwrap().unwrap();
+        assert_eq!(stored.name, monitor.name);
+        assert_eq!(stored.target, monitor.target);
+        assert_eq!(stored.check_type, monitor.check_type);
+        assert_eq!(stored.interval_seconds, monitor.interval_seconds);
+        assert_eq!(stored.grace_seconds, monitor.grace_seconds);
+        assert_eq!(stored.visibility, monitor.visibility);
+        assert_eq!(stored.tags, monitor.tags);
+        assert_eq!(stored.expected_status_codes, monitor.expected_status_codes);
+        assert_eq!(stored.headers, monitor.headers);
+        assert_eq!(stored.created_at, monitor.created_at);
+
+        // Saving again updates the monitor in place
+        monitor.enabled = false;
+        assert_eq!(db.save_monitor(&monitor).await.unwrap(), id);
+        let stored = db.get_monitor_by_uuid(monitor.uuid).await.unwrap().unwrap();
+        assert_eq!(stored.id, Some(id));
+        assert!(!stored.enabled);
+
+        db.delete_monitor(monitor.uuid).await.unwrap();
+        assert!(db.get_monitor_by_uuid(monitor.uuid).await.unwrap().is_none());
+        let deleted = db.get_deleted_monitors().await.unwrap();
+        assert!(deleted.iter().any(|(uuid, _)| *uuid == monitor.uuid));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a PostgreSQL server at UPPE_TEST_DATABASE_URL"]
+    async fn test_result_round_trip() {
+        let db = test_database().await;
+
+        let monitor =
+            Monitor::new("Web".to_string(), "https://example.com".to_string(), "http".to_string());
+        db.save_monitor(&monitor).await.unwrap();
+
+        let timestamp = UNIX_EPOCH + Duration::from_secs(1_790_000_000);
+        let mut result =
+            CheckResult::new(monitor.uuid, monitor.target.clone(), "peer-a".to_string());
+        result.timestamp = timestamp;
+        result.status = MonitorStatus::Down;
+        result.latency_ms = Some(250);
+        result.status_code = Some(503);
+        result.error_message = Some("Service Unavailable".to_string());
+        result.signature = Some(vec![1, 2, 3]);
+        assert!(!db.has_result(monitor.uuid, "peer-a", timestamp).await.unwrap());
+        db.save_result(&result).await.unwrap();
+
+        assert!(db.has_result(monitor.uuid, "peer-a", timestamp).await.unwrap());
+        assert!(!db.has_result(monitor.uuid, "peer-b", timestamp).await.unwrap());
+        let stored = db.get_recent_results(monitor.uuid, 10).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].timestamp, timestamp);
+        assert_eq!(stored[0].status, MonitorStatus::Down);
+        assert_eq!(stored[0].latency_ms, Some(250));
+        assert_eq!(stored[0].status_code, Some(503));
+        assert_eq!(stored[0].error_message.as_deref(), Some("Service Unavailable"));
+        assert_eq!(stored[0].peer_id, "peer-a");
+        assert_eq!(stored[0].signature, Some(vec![1, 2, 3]));
+
+        db.delete_monitor(monitor.uuid).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a PostgreSQL server at UPPE_TEST_DATABASE_URL"]
+    async fn test_setting_round_trip() {
+        let db = test_database().await;
+
+        let key = format!("test:{}", Uuid::new_v4());
+        assert_eq!(db.get_setting(&key).await.unwrap(), None);
+        for value in ["first", "second"] {
+            let setting = Setting {
+                key: key.clone(),
+                value: value.to_string(),
+                updated_at: SystemTime::now(),
+            };
+            db.save_setting(&setting).await.unwrap();
+            assert_eq!(db.get_setting(&key).await.unwrap().as_deref(), Some(value));
+        }
+    }
+}
@@ -3,10 +3,17 @@ use anyhow::Result;
 use async_trait::async_trait;
 use libsql::{Connection, params};
 use std::sync::Arc;
+use std::time::Instant;
 use uuid::Uuid;
 
-use super::models::{Monitor, MonitorResult, NetworkStats, Peer, PeerResult};
-use crate::monitoring::types::CheckResult;
+use super::models::{
+    AssignmentRole, AuditEvent, AuditKind, CommunityDomain, DhtOperation, EscalationPolicy, Follow,
+    GroupMembership, HelperAssignment, Incident, IncidentUpdate, MaintenanceWindow, Monitor,
+    MonitorResult, MultiVantageResult, NetworkStats, NotificationChannel, NotificationRoute,
+    OwnerSyncState, Peer, PeerResult, ProbeState, ResultAgreement, ResultRollup, RollupPeriod,
+    RouteScope, Setting, StatusPage, Vantage,
+};
+use crate::monitoring::types::{CheckResult, MonitorStatus};
 use crate::pool::LibsqlPool;
 
 /// Database trait for abstracting database operations
@@ -15,15 +22,21 @@ pub trait Database: Send + Sync {
     /// Get all enabled monitors
     async fn get_enabled_monitors(&self) -> Result<Vec<Monitor>>;
 
+    /// Get all monitors, enabled or paused
+    async fn get_monitors(&self) -> Result<Vec<Monitor>>;
+
     /// Get a monitor by UUID
     async fn get_monitor_by_uuid(&self, uuid: Uuid) -> Result<Option<Monitor>>;
 
     /// Save a monitor
     async fn save_monitor(&self, monitor: &Monitor) -> Result<i64>;
 
-    /// Delete a monitor by UUID
+    /// Delete a monitor by UUID, remembering when it was deleted
     async fn delete_monitor(&self, uuid: Uuid) -> Result<()>;
 
+    /// UUIDs of deleted monitors and when each was deleted
+    async fn get_deleted_monitors(&self) -> Result<Vec<(Uuid, std::time::SystemTime)>>;
+
     /// Save a monitoring result
     async fn save_result(&self, result: &CheckResult) -> Result<i64>;
 
@@ -37,6 +50,9 @@ pub trait Database: Send + Sync {
         limit: usize,
     ) -> Result<Vec<MonitorResult>>;
 
+    /// Latest result of every monitor that has one
+    async fn get_latest_results(&self) -> Result<Vec<MonitorResult>>;
+
     /// Get peer results for a monitor
     async fn get_peer_results(&self, monitor_uuid: Uuid, limit: usize) -> Result<Vec<PeerResult>>;
 
@@ -51,6 +67,360 @@ pub trait Database: Send + Sync {
 
     /// Get latest network stats
     async fn get_latest_network_stats(&self) -> Result<Option<NetworkStats>>;
+
+    /// Roll network stats snapshots older than `before` into hourly
+    /// aggregates and delete them, returning how many were rolled up
+    async fn rollup_network_stats(&self, before: std::time::SystemTime) -> Result<u64>;
+
+    /// Insert or update a helper assignment
+    async fn save_helper_assignment(&self, assignment: &HelperAssignment) -> Result<()>;
+
+    /// Get all persisted helper assignments for a role
+    async fn get_helper_assignments(&self, role: AssignmentRole) -> Result<Vec<HelperAssignment>>;
+
+    /// Record that a result under a helper assignment was received or sent
+    async fn record_assignment_result(
+        &self,
+        assignment_id: Uuid,
+        at: std::time::SystemTime,
+    ) -> Result<()>;
+
+    /// Delete a helper assignment by its ID
+    async fn delete_helper_assignment(&self, assignment_id: Uuid) -> Result<()>;
+
+    /// Get a node setting by key
+    async fn get_setting(&self, key: &str) -> Result<Option<String>>;
+
+    /// Get all node settings
+    async fn get_settings(&self) -> Result<Vec<Setting>>;
+
+    /// Insert or replace a node setting
+    async fn save_setting(&self, setting: &Setting) -> Result<()>;
+
+    /// Delete local and peer results older than their monitor's retention, or
+    /// `default_days` for monitors without an override, returning how many were removed
+    async fn delete_expired_results(
+        &self,
+        now: std::time::SystemTime,
+        default_days: u64,
+    ) -> Result<u64>;
+
+    /// Our results past their retention that were not restored from the
+    /// archive, oldest first, at most `limit`
+    async fn get_expired_results(
+        &self,
+        now: std::time::SystemTime,
+        default_days: u64,
+        limit: usize,
+    ) -> Result<Vec<MonitorResult>>;
+
+    /// Delete our results by id, returning how many were removed
+    async fn delete_results(&self, ids: &[i64]) -> Result<u64>;
+
+    /// Insert results unless a result with the same monitor and timestamp is
+    /// stored, returning how many were added; `restored_at` marks results
+    /// restored from the archive
+    async fn import_results(
+        &self,
+        results: &[MonitorResult],
+        restored_at: Option<std::time::SystemTime>,
+    ) -> Result<u64>;
+
+    /// Append an event to the audit log
+    async fn append_audit_event(&self, event: &AuditEvent) -> Result<i64>;
+
+    /// Get the most recent audit events, newest first, optionally of one kind
+    async fn get_audit_events(
+        &self,
+        kind: Option<AuditKind>,
+        limit: usize,
+    ) -> Result<Vec<AuditEvent>>;
+
+    /// Delete audit events older than `cutoff`, returning how many were removed
+    async fn delete_audit_events_before(&self, cutoff: std::time::SystemTime) -> Result<u64>;
+
+    /// Whether a peer result with the same monitor, peer and timestamp is stored
+    async fn has_peer_result(
+        &self,
+        monitor_uuid: Uuid,
+        peer_id: &str,
+        timestamp: std::time::SystemTime,
+    ) -> Result<bool>;
+
+    /// Whether one of our results with the same monitor and timestamp is stored
+    async fn has_result(
+        &self,
+        monitor_uuid: Uuid,
+        peer_id: &str,
+        timestamp: std::time::SystemTime,
+    ) -> Result<bool>;
+
+    /// Get the owner sync progress, if a sync ever ran
+    async fn get_owner_sync_state(&self) -> Result<Option<OwnerSyncState>>;
+
+    /// Replace the owner sync progress
+    async fn save_owner_sync_state(&self, state: &OwnerSyncState) -> Result<()>;
+
+    /// Timestamps and statuses of our own results for a monitor in `[start, end)`,
+    /// leaving out those checked during maintenance
+    async fn get_local_statuses(
+        &self,
+        monitor_uuid: Uuid,
+        start: std::time::SystemTime,
+        end: std::time::SystemTime,
+    ) -> Result<Vec<(std::time::SystemTime, MonitorStatus)>>;
+
+    /// Peer results for a monitor checked in `[start, end)`, oldest first
+    async fn get_peer_results_between(
+        &self,
+        monitor_uuid: Uuid,
+        start: std::time::SystemTime,
+        end: std::time::SystemTime,
+    ) -> Result<Vec<PeerResult>>;
+
+    /// Up to `limit` verified, unretracted peer results for a monitor checked
+    /// at or after `since`, with IDs above `after_id`, in ID order
+    async fn get_peer_results_page(
+        &self,
+        monitor_uuid: Uuid,
+        since: std::time::SystemTime,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<PeerResult>>;
+
+    /// Peer IDs, timestamps and statuses of verified peer results for a monitor
+    /// in `[start, end)`
+    async fn get_verified_peer_statuses(
+        &self,
+        monitor_uuid: Uuid,
+        start: std::time::SystemTime,
+        end: std::time::SystemTime,
+    ) -> Result<Vec<(String, std::time::SystemTime, MonitorStatus)>>;
+
+    /// Insert or update the agreement for a monitor's bucket
+    async fn save_result_agreement(&self, agreement: &ResultAgreement) -> Result<()>;
+
+    /// Get the most recent agreement buckets for a monitor, newest first
+    async fn get_result_agreements(
+        &self,
+        monitor_uuid: Uuid,
+        limit: usize,
+    ) -> Result<Vec<ResultAgreement>>;
+
+    /// Our results for a monitor since the bucket `start` falls into, rolled up
+    /// into buckets of `bucket_seconds`; results checked during maintenance and
+    /// buckets without results are left out
+    async fn get_result_rollups(
+        &self,
+        monitor_uuid: Uuid,
+        start: std::time::SystemTime,
+        bucket_seconds: u64,
+    ) -> Result<Vec<ResultRollup>>;
+
+    /// Roll our results that came in since the last rollup into the hourly
+    /// rollups of the complete hours before `now`, and those hours into the
+    /// daily rollups of their days; returns how many hours were rolled up
+    ///
+    /// Results checked during maintenance are left out, as in
+    /// `get_result_rollups`. Late and imported results roll their hour up
+    /// again.
+    async fn rollup_results(&self, now: std::time::SystemTime) -> Result<u64>;
+
+    /// Stored rollups of a monitor from the bucket `start` falls into on
+    async fn get_rollups(
+        &self,
+        monitor_uuid: Uuid,
+        period: RollupPeriod,
+        start: std::time::SystemTime,
+    ) -> Result<Vec<ResultRollup>>;
+
+    /// Delete the rollups of buckets that started before `before`, returning
+    /// how many were removed
+    async fn delete_rollups_before(
+        &self,
+        period: RollupPeriod,
+        before: std::time::SystemTime,
+    ) -> Result<u64>;
+
+    /// Check that a pooled connection can run a query
+    async fn ping(&self) -> Result<()>;
+
+    /// UUIDs of all private monitors
+    async fn get_private_monitor_uuids(&self) -> Result<Vec<Uuid>>;
+
+    /// Private monitors whose earlier public results were not retracted yet
+    async fn get_monitors_to_retract(&self) -> Result<Vec<Monitor>>;
+
+    /// Mark a monitor's results as retracted and record when, returning how
+    /// many results were marked
+    async fn retract_monitor_results(
+        &self,
+        monitor_uuid: Uuid,
+        at: std::time::SystemTime,
+    ) -> Result<u64>;
+
+    /// Mark results an owner shared for a monitor as retracted, returning how
+    /// many were marked
+    async fn retract_peer_results(&self, monitor_uuid: Uuid, owner_peer_id: &str) -> Result<u64>;
+
+    /// Peers currently marked online and not banned, most recently seen first
+    async fn get_online_peers(&self) -> Result<Vec<Peer>>;
+
+    /// Every peer seen so far, online ones first, then most recently seen first
+    async fn get_peers(&self) -> Result<Vec<Peer>>;
+
+    /// Remember an address a peer was reached on, keeping the most recent few
+    async fn record_peer_address(&self, peer_id: &str, address: &str) -> Result<()>;
+
+    /// Ban a peer as of `banned_at`, or lift its ban with `None`
+    async fn set_peer_banned(
+        &self,
+        peer_id: &str,
+        banned_at: Option<std::time::SystemTime>,
+    ) -> Result<()>;
+
+    /// IDs of the peers currently banned
+    async fn get_banned_peer_ids(&self) -> Result<Vec<String>>;
+
+    /// Count a greylisting of a peer for flooding us, at `at`
+    async fn record_peer_greylisted(&self, peer_id: &str, at: std::time::SystemTime) -> Result<()>;
+
+    /// Get the most recent results delivered by a peer, newest first
+    async fn get_results_from_peer(
+        &self,
+        source_peer_id: &str,
+        limit: usize,
+    ) -> Result<Vec<PeerResult>>;
+
+    /// Number of results delivered by a peer, and how many of them were verified
+    async fn count_results_from_peer(&self, source_peer_id: &str) -> Result<(u64, u64)>;
+
+    /// Insert or update a multi-vantage probe
+    async fn save_multi_vantage_result(&self, result: &MultiVantageResult) -> Result<()>;
+
+    /// Multi-vantage probes in a given state, oldest first
+    async fn get_multi_vantage_results_in_state(
+        &self,
+        state: ProbeState,
+    ) -> Result<Vec<MultiVantageResult>>;
+
+    /// Get the most recent multi-vantage probes of a monitor, newest first
+    async fn get_multi_vantage_results(
+        &self,
+        monitor_uuid: Uuid,
+        limit: usize,
+    ) -> Result<Vec<MultiVantageResult>>;
+
+    /// Insert or update a DHT debug operation
+    async fn save_dht_operation(&self, operation: &DhtOperation) -> Result<()>;
+
+    /// DHT debug operations in a given state, oldest first
+    async fn get_dht_operations_in_state(&self, state: ProbeState) -> Result<Vec<DhtOperation>>;
+
+    /// Get the most recent DHT debug operations, newest first
+    async fn get_dht_operations(&self, limit: usize) -> Result<Vec<DhtOperation>>;
+
+    /// Incidents that are not resolved yet, oldest first
+    async fn get_open_incidents(&self) -> Result<Vec<Incident>>;
+
+    /// Get the most recent incidents, newest first
+    async fn get_incidents(&self, limit: usize) -> Result<Vec<Incident>>;
+
+    /// Get an incident by UUID
+    async fn get_incident(&self, uuid: Uuid) -> Result<Option<Incident>>;
+
+    /// Insert or update an incident
+    async fn save_incident(&self, incident: &Incident) -> Result<()>;
+
+    /// Append an update to an incident's timeline
+    async fn add_incident_update(&self, update: &IncidentUpdate) -> Result<()>;
+
+    /// An incident's timeline, oldest first
+    async fn get_incident_updates(&self, incident_uuid: Uuid) -> Result<Vec<IncidentUpdate>>;
+
+    /// All notification channels, by name
+    async fn get_notification_channels(&self) -> Result<Vec<NotificationChannel>>;
+
+    /// Insert or update a notification channel
+    async fn save_notification_channel(&self, channel: &NotificationChannel) -> Result<()>;
+
+    /// Delete a notification channel and remove it from all routes
+    async fn delete_notification_channel(&self, uuid: Uuid) -> Result<()>;
+
+    /// Record the outcome of test-firing a channel; `error` is `None` on success
+    async fn record_notification_test(&self, uuid: Uuid, error: Option<&str>) -> Result<()>;
+
+    /// All notification routes
+    async fn get_notification_routes(&self) -> Result<Vec<NotificationRoute>>;
+
+    /// Insert or replace a notification route
+    async fn save_notification_route(&self, route: &NotificationRoute) -> Result<()>;
+
+    /// Delete a notification route, returning whether there was one
+    async fn delete_notification_route(&self, scope: &RouteScope) -> Result<bool>;
+
+    /// All status pages, by slug
+    async fn get_status_pages(&self) -> Result<Vec<StatusPage>>;
+
+    /// Status page with the given slug
+    async fn get_status_page(&self, slug: &str) -> Result<Option<StatusPage>>;
+
+    /// Insert or update a status page
+    async fn save_status_page(&self, page: &StatusPage) -> Result<()>;
+
+    /// Monitors shown on a status page, in display order
+    async fn get_status_page_monitors(&self, page_uuid: Uuid) -> Result<Vec<Monitor>>;
+
+    /// Add a monitor to the end of a status page, or remove it
+    async fn set_status_page_monitor(
+        &self,
+        page_uuid: Uuid,
+        monitor_uuid: Uuid,
+        shown: bool,
+    ) -> Result<()>;
+
+    /// Maintenance windows that have not ended by `now`, soonest first
+    async fn get_maintenance_windows(
+        &self,
+        now: std::time::SystemTime,
+    ) -> Result<Vec<MaintenanceWindow>>;
+
+    /// Insert a maintenance window
+    async fn save_maintenance_window(&self, window: &MaintenanceWindow) -> Result<()>;
+
+    /// Delete a maintenance window, returning whether it existed
+    async fn delete_maintenance_window(&self, uuid: Uuid) -> Result<bool>;
+
+    /// Get all followed hosts
+    async fn get_follows(&self) -> Result<Vec<Follow>>;
+
+    /// Follow a host, keeping an existing follow as is
+    async fn save_follow(&self, follow: &Follow) -> Result<()>;
+
+    /// Stop following a host, returning whether it was followed
+    async fn delete_follow(&self, host: &str) -> Result<bool>;
+
+    /// Peer monitors seen checking a followed host
+    async fn get_follow_monitors(&self, host: &str) -> Result<Vec<Uuid>>;
+
+    /// Record a peer monitor seen checking a followed host
+    async fn add_follow_monitor(&self, host: &str, monitor_uuid: Uuid) -> Result<()>;
+
+    /// Community domains this node joined or left
+    async fn get_community_domains(&self) -> Result<Vec<CommunityDomain>>;
+
+    /// Insert or replace a community domain
+    async fn save_community_domain(&self, domain: &CommunityDomain) -> Result<()>;
+
+    /// Latest announcement of each peer about each community domain
+    async fn get_group_memberships(&self) -> Result<Vec<GroupMembership>>;
+
+    /// Store a membership announcement unless a later one from the peer is
+    /// stored, returning whether it was
+    async fn save_group_membership(&self, membership: &GroupMembership) -> Result<bool>;
+
+    /// Add to a peer's contribution score, recording the peer if it is new
+    async fn add_peer_contribution(&self, peer_id: &str, score: f64) -> Result<()>;
 }
 
 /// LibSQL database implementation
@@ -74,7 +444,10 @@ impl DatabaseImpl {
 
     /// Get a connection from the pool
     async fn get_conn(&self) -> Result<deadpool::managed::Object<crate::pool::LibsqlManager>> {
-        Ok(self.pool.get().await?)
+        let started = Instant::now();
+        let conn = self.pool.get().await;
+        self.pool.manager().record_wait(started.elapsed());
+        Ok(conn?)
     }
 }
 
@@ -82,33 +455,25 @@ impl DatabaseImpl {
 impl Database for DatabaseImpl {
     async fn get_enabled_monitors(&self) -> Result<Vec<Monitor>> {
         let conn = self.get_conn().await?;
-        let mut stmt = conn
-            .prepare(
-                "SELECT id, uuid, name, target, check_type, interval_seconds, timeout_seconds, \
-                 enabled, created_at, updated_at FROM monitors WHERE enabled = 1",
-            )
+        let mut rows = conn
+            .query(&format!("SELECT {MONITOR_COLUMNS} FROM monitors WHERE enabled = 1"), ())
             .await?;
 
-        let mut rows = stmt.query(()).await?;
         let mut monitors = Vec::new();
-
         while let Some(row) = rows.next().await? {
-            let uuid_str: String = row.get(1)?;
-            let created_at: i64 = row.get(8)?;
-            let updated_at: i64 = row.get(9)?;
+            monitors.push(monitor_from_row(&row)?);
+        }
 
-            monitors.push(Monitor {
-                id: Some(row.get(0)?),
-                uuid: Uuid::parse_str(&uuid_str)?,
-                name: row.get(2)?,
-                target: row.get(3)?,
-                check_type: row.get(4)?,
-                interval_seconds: row.get::<i64>(5)? as u64,
-                timeout_seconds: row.get::<i64>(6)? as u64,
-                enabled: row.get::<i64>(7)? != 0,
-                created_at: Monitor::i64_to_timestamp(created_at),
-                updated_at: Monitor::i64_to_timestamp(updated_at),
-            });
+        Ok(monitors)
+    }
+
+    async fn get_monitors(&self) -> Result<Vec<Monitor>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn.query(&format!("SELECT {MONITOR_COLUMNS} FROM monitors"), ()).await?;
+
+        let mut monitors = Vec::new();
+        while let Some(row) = rows.next().await? {
+            monitors.push(monitor_from_row(&row)?);
         }
 
         Ok(monitors)
@@ -116,34 +481,16 @@ impl Database for DatabaseImpl {
 
     async fn get_monitor_by_uuid(&self, uuid: Uuid) -> Result<Option<Monitor>> {
         let conn = self.get_conn().await?;
-        let mut stmt = conn
-            .prepare(
-                "SELECT id, uuid, name, target, check_type, interval_seconds, timeout_seconds, \
-                 enabled, created_at, updated_at FROM monitors WHERE uuid = ?",
+        let mut rows = conn
+            .query(
+                &format!("SELECT {MONITOR_COLUMNS} FROM monitors WHERE uuid = ?"),
+                params![uuid.to_string()],
             )
             .await?;
 
-        let mut rows = stmt.query(params![uuid.to_string()]).await?;
-
-        if let Some(row) = rows.next().await? {
-            let uuid_str: String = row.get(1)?;
-            let created_at: i64 = row.get(8)?;
-            let updated_at: i64 = row.get(9)?;
-
-            Ok(Some(Monitor {
-                id: Some(row.get(0)?),
-                uuid: Uuid::parse_str(&uuid_str)?,
-                name: row.get(2)?,
-                target: row.get(3)?,
-                check_type: row.get(4)?,
-                interval_seconds: row.get::<i64>(5)? as u64,
-                timeout_seconds: row.get::<i64>(6)? as u64,
-                enabled: row.get::<i64>(7)? != 0,
-                created_at: Monitor::i64_to_timestamp(created_at),
-                updated_at: Monitor::i64_to_timestamp(updated_at),
-            }))
-        } else {
-            Ok(None)
+        match rows.next().await? {
+            Some(row) => Ok(Some(monitor_from_row(&row)?)),
+            None => Ok(None),
         }
     }
 
@@ -155,8 +502,11 @@ impl Database for DatabaseImpl {
         if let Some(id) = monitor.id {
             // Update existing monitor
             conn.execute(
-                "UPDATE monitors SET name = ?, target = ?, check_type = ?, interval_seconds = ?, \
-                 timeout_seconds = ?, enabled = ?, updated_at = ? WHERE id = ?",
+                "UPDATE monitors SET name = ?1, target = ?2, check_type = ?3, interval_seconds = \
+                 ?4, timeout_seconds = ?5, enabled = ?6, retention_days = ?7, visibility = ?8, \
+                 retracted_at = CASE WHEN ?8 = 'public' THEN NULL ELSE retracted_at END, \
+                 updated_at = ?9, tags = ?11, expected_status_codes = ?12, headers = ?13, \
+                 grace_seconds = ?14 WHERE id = ?10",
                 params![
                     monitor.name.clone(),
                     monitor.target.clone(),
@@ -164,8 +514,14 @@ impl Database for DatabaseImpl {
                     monitor.interval_seconds as i64,
                     monitor.timeout_seconds as i64,
                     if monitor.enabled { 1 } else { 0 },
+                    monitor.retention_days.map(|days| days as i64),
+                    monitor.visibility.to_string(),
                     updated_at,
-                    id
+                    id,
+                    monitor.tags.join(","),
+                    serde_json::to_string(&monitor.expected_status_codes)?,
+                    serde_json::to_string(&monitor.headers)?,
+                    monitor.grace_seconds as i64
                 ],
             )
             .await?;
@@ -174,8 +530,9 @@ impl Database for DatabaseImpl {
             // Insert new monitor
             conn.execute(
                 "INSERT INTO monitors (uuid, name, target, check_type, interval_seconds, \
-                 timeout_seconds, enabled, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, \
-                 ?, ?)",
+                 timeout_seconds, enabled, retention_days, visibility, created_at, updated_at, \
+                 tags, expected_status_codes, headers, grace_seconds) VALUES (?, ?, ?, ?, ?, ?, \
+                 ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                 params![
                     monitor.uuid.to_string(),
                     monitor.name.clone(),
@@ -184,8 +541,14 @@ impl Database for DatabaseImpl {
                     monitor.interval_seconds as i64,
                     monitor.timeout_seconds as i64,
                     if monitor.enabled { 1 } else { 0 },
+                    monitor.retention_days.map(|days| days as i64),
+                    monitor.visibility.to_string(),
                     created_at,
-                    updated_at
+                    updated_at,
+                    monitor.tags.join(","),
+                    serde_json::to_string(&monitor.expected_status_codes)?,
+                    serde_json::to_string(&monitor.headers)?,
+                    monitor.grace_seconds as i64
                 ],
             )
             .await?;
@@ -207,14 +570,64 @@ impl Database for DatabaseImpl {
         // Delete peer results as well
         conn.execute("DELETE FROM peer_results WHERE monitor_uuid = ?", params![uuid.to_string()])
             .await?;
+        conn.execute(
+            "DELETE FROM result_agreement WHERE monitor_uuid = ?",
+            params![uuid.to_string()],
+        )
+        .await?;
+        conn.execute(
+            "DELETE FROM multi_vantage_results WHERE monitor_uuid = ?",
+            params![uuid.to_string()],
+        )
+        .await?;
+        for period in [RollupPeriod::Hourly, RollupPeriod::Daily] {
+            conn.execute(
+                &format!("DELETE FROM {} WHERE monitor_uuid = ?", rollup_table(period)),
+                params![uuid.to_string()],
+            )
+            .await?;
+        }
+        let scope = RouteScope::Monitor(uuid).to_string();
+        conn.execute(
+            "DELETE FROM notification_route_channels WHERE scope = ?",
+            params![scope.clone()],
+        )
+        .await?;
+        conn.execute("DELETE FROM notification_routes WHERE scope = ?", params![scope])
+            .await?;
+        conn.execute(
+            "DELETE FROM maintenance_windows WHERE monitor_uuid = ?",
+            params![uuid.to_string()],
+        )
+        .await?;
 
         // Now delete the monitor itself
         conn.execute("DELETE FROM monitors WHERE uuid = ?", params![uuid.to_string()])
             .await?;
+        conn.execute(
+            "INSERT INTO deleted_monitors (uuid, deleted_at) VALUES (?, ?) ON CONFLICT(uuid) DO \
+             UPDATE SET deleted_at = excluded.deleted_at",
+            params![uuid.to_string(), Monitor::timestamp_to_i64(std::time::SystemTime::now())],
+        )
+        .await?;
 
         Ok(())
     }
 
+    async fn get_deleted_monitors(&self) -> Result<Vec<(Uuid, std::time::SystemTime)>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn.query("SELECT uuid, deleted_at FROM deleted_monitors", ()).await?;
+
+        let mut deleted = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let uuid: String = row.get(0)?;
+            deleted.push((Uuid::parse_str(&uuid)?, Monitor::i64_to_timestamp(row.get(1)?)));
+        }
+
+        Ok(deleted)
+    }
+
+    #[tracing::instrument(skip_all, fields(monitor_id = %result.monitor_id))]
     async fn save_result(&self, result: &CheckResult) -> Result<i64> {
         let conn = self.get_conn().await?;
         let timestamp = Monitor::timestamp_to_i64(result.timestamp);
@@ -223,8 +636,8 @@ impl Database for DatabaseImpl {
 
         conn.execute(
             "INSERT INTO monitor_results (monitor_uuid, timestamp, status, latency_ms, \
-             status_code, error_message, peer_id, signature, created_at, city, country, region) \
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+             status_code, error_message, peer_id, signature, created_at, city, country, region, \
+             maintenance) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 result.monitor_id.to_string(),
                 timestamp,
@@ -237,7 +650,8 @@ impl Database for DatabaseImpl {
                 created_at,
                 location.city,
                 location.country,
-                location.region
+                location.region,
+                result.maintenance as i64
             ],
         )
         .await?;
@@ -245,6 +659,7 @@ impl Database for DatabaseImpl {
         Ok(conn.last_insert_rowid())
     }
 
+    #[tracing::instrument(skip_all, fields(monitor_id = %result.monitor_uuid, peer_id = %result.peer_id))]
     async fn save_peer_result(&self, result: &PeerResult) -> Result<i64> {
         let conn = self.get_conn().await?;
         let timestamp = Monitor::timestamp_to_i64(result.timestamp);
@@ -252,8 +667,8 @@ impl Database for DatabaseImpl {
 
         conn.execute(
             "INSERT INTO peer_results (monitor_uuid, timestamp, status, latency_ms, status_code, \
-             error_message, peer_id, signature, verified, created_at, city, country, region) \
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+             error_message, peer_id, signature, verified, created_at, city, country, region, \
+             source_peer_id, certificate) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 result.monitor_uuid.to_string(),
                 timestamp,
@@ -267,7 +682,9 @@ impl Database for DatabaseImpl {
                 created_at,
                 result.city.clone(),
                 result.country.clone(),
-                result.region.clone()
+                result.region.clone(),
+                result.source_peer_id.clone(),
+                result.certificate.as_ref().map(serde_json::to_string).transpose()?
             ],
         )
         .await?;
@@ -281,43 +698,33 @@ impl Database for DatabaseImpl {
         limit: usize,
     ) -> Result<Vec<MonitorResult>> {
         let conn = self.get_conn().await?;
-        let mut stmt = conn
-            .prepare(
-                "SELECT id, monitor_uuid, timestamp, status, latency_ms, status_code, \
-                 error_message, peer_id, signature, created_at, city, country, region FROM \
-                 monitor_results WHERE monitor_uuid = ? ORDER BY timestamp DESC LIMIT ?",
-            )
-            .await?;
+        let mut stmt = conn.prepare(MONITOR_RESULTS_QUERY).await?;
 
         let mut rows = stmt.query(params![monitor_uuid.to_string(), limit as i64]).await?;
         let mut results = Vec::new();
 
         while let Some(row) = rows.next().await? {
-            let monitor_uuid_str: String = row.get(1)?;
-            let status_str: String = row.get(3)?;
-            let timestamp: i64 = row.get(2)?;
-            let created_at: i64 = row.get(9)?;
+            results.push(monitor_result_from_row(&row)?);
+        }
 
-            results.push(MonitorResult {
-                id: Some(row.get(0)?),
-                monitor_uuid: Uuid::parse_str(&monitor_uuid_str)?,
-                timestamp: Monitor::i64_to_timestamp(timestamp),
-                status: match status_str.as_str() {
-                    "up" => crate::monitoring::types::MonitorStatus::Up,
-                    "down" => crate::monitoring::types::MonitorStatus::Down,
-                    "degraded" => crate::monitoring::types::MonitorStatus::Degraded,
-                    _ => crate::monitoring::types::MonitorStatus::Unknown,
-                },
-                latency_ms: row.get::<Option<i64>>(4)?.map(|v| v as u64),
-                status_code: row.get::<Option<i64>>(5)?.map(|v| v as u16),
-                error_message: row.get(6)?,
-                peer_id: row.get(7)?,
-                signature: row.get(8)?,
-                created_at: Monitor::i64_to_timestamp(created_at),
-                city: row.get(10)?,
-                country: row.get(11)?,
-                region: row.get(12)?,
-            });
+        Ok(results)
+    }
+
+    async fn get_latest_results(&self) -> Result<Vec<MonitorResult>> {
+        let conn = self.get_conn().await?;
+        // SQLite takes the other columns from the row holding MAX(timestamp)
+        let mut rows = conn
+            .query(
+                "SELECT id, monitor_uuid, MAX(timestamp), status, latency_ms, status_code, \
+                 error_message, peer_id, signature, created_at, city, country, region, \
+                 maintenance FROM monitor_results GROUP BY monitor_uuid",
+                (),
+            )
+            .await?;
+
+        let mut results = Vec::new();
+        while let Some(row) = rows.next().await? {
+            results.push(monitor_result_from_row(&row)?);
         }
 
         Ok(results)
@@ -325,44 +732,16 @@ impl Database for DatabaseImpl {
 
     async fn get_peer_results(&self, monitor_uuid: Uuid, limit: usize) -> Result<Vec<PeerResult>> {
         let conn = self.get_conn().await?;
-        let mut stmt = conn
-            .prepare(
-                "SELECT id, monitor_uuid, timestamp, status, latency_ms, status_code, \
-                 error_message, peer_id, signature, verified, created_at FROM peer_results WHERE \
-                 monitor_uuid = ? ORDER BY timestamp DESC LIMIT ?",
+        let mut rows = conn
+            .query(
+                &format!("SELECT {PEER_RESULT_COLUMNS} {PEER_RESULTS_QUERY}"),
+                params![monitor_uuid.to_string(), limit as i64],
             )
             .await?;
 
-        let mut rows = stmt.query(params![monitor_uuid.to_string(), limit as i64]).await?;
         let mut results = Vec::new();
-
         while let Some(row) = rows.next().await? {
-            let monitor_uuid_str: String = row.get(1)?;
-            let status_str: String = row.get(3)?;
-            let timestamp: i64 = row.get(2)?;
-            let created_at: i64 = row.get(10)?;
-
-            results.push(PeerResult {
-                id: Some(row.get(0)?),
-                monitor_uuid: Uuid::parse_str(&monitor_uuid_str)?,
-                timestamp: Monitor::i64_to_timestamp(timestamp),
-                status: match status_str.as_str() {
-                    "up" => crate::monitoring::types::MonitorStatus::Up,
-                    "down" => crate::monitoring::types::MonitorStatus::Down,
-                    "degraded" => crate::monitoring::types::MonitorStatus::Degraded,
-                    _ => crate::monitoring::types::MonitorStatus::Unknown,
-                },
-                latency_ms: row.get::<Option<i64>>(4)?.map(|v| v as u64),
-                status_code: row.get::<Option<i64>>(5)?.map(|v| v as u16),
-                error_message: row.get(6)?,
-                peer_id: row.get(7)?,
-                signature: row.get(8)?,
-                verified: row.get::<i64>(9)? != 0,
-                created_at: Monitor::i64_to_timestamp(created_at),
-                city: row.get(11).ok(),
-                country: row.get(12).ok(),
-                region: row.get(13).ok(),
-            });
+            results.push(peer_result_from_row(&row)?);
         }
 
         Ok(results)
@@ -378,7 +757,10 @@ impl Database for DatabaseImpl {
              uptime_percentage, checks_per_day, location_city, location_region, location_country)
              VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
              ON CONFLICT(peer_id) DO UPDATE SET status=excluded.status, \
-             last_seen=excluded.last_seen",
+             last_seen=excluded.last_seen, location_city=COALESCE(excluded.location_city, \
+             peers.location_city), location_region=COALESCE(excluded.location_region, \
+             peers.location_region), location_country=COALESCE(excluded.location_country, \
+             peers.location_country)",
             params![
                 peer.peer_id.clone(),
                 peer.status.clone(),
@@ -410,21 +792,29 @@ impl Database for DatabaseImpl {
         Ok(())
     }
 
+    #[tracing::instrument(skip_all)]
     async fn insert_network_stats(&self, stats: &NetworkStats) -> Result<i64> {
         let conn = self.get_conn().await?;
         let ts = Monitor::timestamp_to_i64(stats.timestamp);
 
         conn.execute(
             "INSERT INTO network_stats (timestamp, total_peers, online_peers, checks_performed, \
-             checks_received, bandwidth_used_mb)
-             VALUES (?, ?, ?, ?, ?, ?)",
+             checks_received, bandwidth_used_mb, helper_assignments, helper_max_assignments, \
+             helper_checks_last_hour, helper_max_checks_per_hour, seen_lookups, seen_hits)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 ts,
                 stats.total_peers,
                 stats.online_peers,
                 stats.checks_performed,
                 stats.checks_received,
-                stats.bandwidth_used_mb
+                stats.bandwidth_used_mb,
+                stats.helper_assignments,
+                stats.helper_max_assignments,
+                stats.helper_checks_last_hour,
+                stats.helper_max_checks_per_hour,
+                stats.seen_lookups,
+                stats.seen_hits
             ],
         )
         .await?;
@@ -432,12 +822,49 @@ impl Database for DatabaseImpl {
         Ok(conn.last_insert_rowid())
     }
 
+    async fn rollup_network_stats(&self, before: std::time::SystemTime) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        let before = Monitor::timestamp_to_i64(before);
+        let tx = conn.transaction().await?;
+
+        // An hour rolled up in two parts is merged with what is there
+        tx.execute(
+            "INSERT INTO network_stats_hourly (hour, samples, avg_online_peers, max_online_peers, \
+             total_peers, checks_performed, checks_received, bandwidth_used_mb, \
+             helper_assignments)
+             SELECT timestamp / 3600 * 3600, COUNT(*), AVG(online_peers), MAX(online_peers), \
+             MAX(total_peers), MAX(checks_performed), MAX(checks_received), \
+             MAX(bandwidth_used_mb), MAX(helper_assignments)
+             FROM network_stats WHERE timestamp < ?1 GROUP BY timestamp / 3600
+             ON CONFLICT(hour) DO UPDATE SET
+                avg_online_peers = (avg_online_peers * samples
+                    + excluded.avg_online_peers * excluded.samples)
+                    / (samples + excluded.samples),
+                samples = samples + excluded.samples,
+                max_online_peers = MAX(max_online_peers, excluded.max_online_peers),
+                total_peers = MAX(total_peers, excluded.total_peers),
+                checks_performed = MAX(checks_performed, excluded.checks_performed),
+                checks_received = MAX(checks_received, excluded.checks_received),
+                bandwidth_used_mb = MAX(bandwidth_used_mb, excluded.bandwidth_used_mb),
+                helper_assignments = MAX(helper_assignments, excluded.helper_assignments)",
+            params![before],
+        )
+        .await?;
+        let removed = tx
+            .execute("DELETE FROM network_stats WHERE timestamp < ?1", params![before])
+            .await?;
+        tx.commit().await?;
+
+        Ok(removed)
+    }
+
     async fn get_latest_network_stats(&self) -> Result<Option<NetworkStats>> {
         let conn = self.get_conn().await?;
         let mut stmt = conn
             .prepare(
                 "SELECT timestamp, total_peers, online_peers, checks_performed, checks_received, \
-                 bandwidth_used_mb
+                 bandwidth_used_mb, helper_assignments, helper_max_assignments, \
+                 helper_checks_last_hour, helper_max_checks_per_hour, seen_lookups, seen_hits
                  FROM network_stats ORDER BY timestamp DESC LIMIT 1",
             )
             .await?;
@@ -453,9 +880,2120 @@ impl Database for DatabaseImpl {
                 checks_performed: row.get(3)?,
                 checks_received: row.get(4)?,
                 bandwidth_used_mb: row.get(5)?,
+                helper_assignments: row.get::<Option<i64>>(6)?.unwrap_or(0),
+                helper_max_assignments: row.get::<Option<i64>>(7)?.unwrap_or(0),
+                helper_checks_last_hour: row.get::<Option<i64>>(8)?.unwrap_or(0),
+                helper_max_checks_per_hour: row.get::<Option<i64>>(9)?.unwrap_or(0),
+                seen_lookups: row.get::<Option<i64>>(10)?.unwrap_or(0),
+                seen_hits: row.get::<Option<i64>>(11)?.unwrap_or(0),
             }))
         } else {
             Ok(None)
         }
     }
+
+    async fn save_helper_assignment(&self, assignment: &HelperAssignment) -> Result<()> {
+        let conn = self.get_conn().await?;
+        let created_at = Monitor::timestamp_to_i64(assignment.created_at);
+        let updated_at = Monitor::timestamp_to_i64(assignment.updated_at);
+        let expires_at = assignment.expires_at.map(Monitor::timestamp_to_i64);
+        let last_result_at = assignment.last_result_at.map(Monitor::timestamp_to_i64);
+
+        conn.execute(
+            "INSERT INTO helper_assignments (assignment_id, role, monitor_uuid, owner_peer_id, \
+             helper_peer_id, target, check_type, interval_seconds, status, created_at, \
+             updated_at, expires_at, last_result_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(assignment_id) DO UPDATE SET status=excluded.status, \
+             target=excluded.target, check_type=excluded.check_type, \
+             interval_seconds=excluded.interval_seconds, updated_at=excluded.updated_at, \
+             expires_at=excluded.expires_at",
+            params![
+                assignment.assignment_id.to_string(),
+                assignment.role.to_string(),
+                assignment.monitor_uuid.to_string(),
+                assignment.owner_peer_id.clone(),
+                assignment.helper_peer_id.clone(),
+                assignment.target.clone(),
+                assignment.check_type.clone(),
+                assignment.interval_seconds as i64,
+                assignment.status.clone(),
+                created_at,
+                updated_at,
+                expires_at,
+                last_result_at
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_helper_assignments(&self, role: AssignmentRole) -> Result<Vec<HelperAssignment>> {
+        let conn = self.get_conn().await?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT assignment_id, role, monitor_uuid, owner_peer_id, helper_peer_id, target, \
+                 check_type, interval_seconds, status, created_at, updated_at, expires_at, \
+                 last_result_at FROM helper_assignments WHERE role = ? ORDER BY created_at ASC",
+            )
+            .await?;
+
+        let mut rows = stmt.query(params![role.to_string()]).await?;
+        let mut assignments = Vec::new();
+
+        while let Some(row) = rows.next().await? {
+            let assignment_id: String = row.get(0)?;
+            let role: String = row.get(1)?;
+            let monitor_uuid: String = row.get(2)?;
+            let created_at: i64 = row.get(9)?;
+            let updated_at: i64 = row.get(10)?;
+            let expires_at: Option<i64> = row.get(11)?;
+            let last_result_at: Option<i64> = row.get(12)?;
+
+            assignments.push(HelperAssignment {
+                assignment_id: Uuid::parse_str(&assignment_id)?,
+                role: role.parse()?,
+                monitor_uuid: Uuid::parse_str(&monitor_uuid)?,
+                owner_peer_id: row.get(3)?,
+                helper_peer_id: row.get(4)?,
+                target: row.get(5)?,
+                check_type: row.get(6)?,
+                interval_seconds: row.get::<i64>(7)? as u64,
+                status: row.get(8)?,
+                created_at: Monitor::i64_to_timestamp(created_at),
+                updated_at: Monitor::i64_to_timestamp(updated_at),
+                expires_at: expires_at.map(Monitor::i64_to_timestamp),
+                last_result_at: last_result_at.map(Monitor::i64_to_timestamp),
+            });
+        }
+
+        Ok(assignments)
+    }
+
+    async fn record_assignment_result(
+        &self,
+        assignment_id: Uuid,
+        at: std::time::SystemTime,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE helper_assignments SET last_result_at = ? WHERE assignment_id = ?",
+            params![Monitor::timestamp_to_i64(at), assignment_id.to_string()],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_helper_assignment(&self, assignment_id: Uuid) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "DELETE FROM helper_assignments WHERE assignment_id = ?",
+            params![assignment_id.to_string()],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn.query("SELECT value FROM settings WHERE key = ?", params![key]).await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_settings(&self) -> Result<Vec<Setting>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn.query("SELECT key, value, updated_at FROM settings", ()).await?;
+
+        let mut settings = Vec::new();
+        while let Some(row) = rows.next().await? {
+            settings.push(Setting {
+                key: row.get(0)?,
+                value: row.get(1)?,
+                updated_at: Monitor::i64_to_timestamp(row.get(2)?),
+            });
+        }
+
+        Ok(settings)
+    }
+
+    async fn save_setting(&self, setting: &Setting) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO settings (key, value, updated_at) VALUES (?, ?, ?) ON CONFLICT(key) DO \
+             UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+            params![
+                setting.key.clone(),
+                setting.value.clone(),
+                Monitor::timestamp_to_i64(setting.updated_at)
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_results(
+        &self,
+        now: std::time::SystemTime,
+        default_days: u64,
+    ) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        let now = Monitor::timestamp_to_i64(now);
+        let default_days = default_days as i64;
+
+        // A retention of 0 days keeps everything, whether it comes from the
+        // monitor's override or the default
+        let mut removed = 0;
+        for (table, restored) in [
+            ("monitor_results", RESTORED_KEPT),
+            ("peer_results", ""),
+            ("result_agreement", ""),
+            ("multi_vantage_results", ""),
+        ] {
+            removed += conn
+                .execute(
+                    &format!(
+                        "DELETE FROM {table} WHERE id IN (
+                            SELECT r.id FROM {table} r
+                            LEFT JOIN monitors m ON m.uuid = r.monitor_uuid
+                            WHERE COALESCE(m.retention_days, ?2) > 0
+                              AND r.timestamp < ?1 - COALESCE(m.retention_days, ?2) * 86400
+                              {restored}
+                        )"
+                    ),
+                    params![now, default_days],
+                )
+                .await?;
+        }
+
+        Ok(removed)
+    }
+
+    async fn get_expired_results(
+        &self,
+        now: std::time::SystemTime,
+        default_days: u64,
+        limit: usize,
+    ) -> Result<Vec<MonitorResult>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT r.id, r.monitor_uuid, r.timestamp, r.status, r.latency_ms, r.status_code, \
+                 r.error_message, r.peer_id, r.signature, r.created_at, r.city, r.country, \
+                 r.region, r.maintenance FROM monitor_results r
+                 LEFT JOIN monitors m ON m.uuid = r.monitor_uuid
+                 WHERE COALESCE(m.retention_days, ?2) > 0
+                   AND r.timestamp < ?1 - COALESCE(m.retention_days, ?2) * 86400
+                   AND r.restored_at IS NULL
+                 ORDER BY r.timestamp LIMIT ?3",
+                params![Monitor::timestamp_to_i64(now), default_days as i64, limit as i64],
+            )
+            .await?;
+
+        let mut results = Vec::new();
+        while let Some(row) = rows.next().await? {
+            results.push(monitor_result_from_row(&row)?);
+        }
+
+        Ok(results)
+    }
+
+    async fn delete_results(&self, ids: &[i64]) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        let tx = conn.transaction().await?;
+        let mut removed = 0;
+        for id in ids {
+            removed += tx.execute("DELETE FROM monitor_results WHERE id = ?", params![*id]).await?;
+        }
+        tx.commit().await?;
+
+        Ok(removed)
+    }
+
+    async fn import_results(
+        &self,
+        results: &[MonitorResult],
+        restored_at: Option<std::time::SystemTime>,
+    ) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        let restored_at = restored_at.map(Monitor::timestamp_to_i64);
+        let tx = conn.transaction().await?;
+        let mut added = 0;
+        for result in results {
+            added += tx
+                .execute(
+                    "INSERT INTO monitor_results (monitor_uuid, timestamp, status, latency_ms, \
+                     status_code, error_message, peer_id, signature, created_at, city, country, \
+                     region, restored_at, maintenance) SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, \
+                     ?10, ?11, ?12, ?13, ?14 WHERE NOT EXISTS (SELECT 1 FROM monitor_results \
+                     WHERE monitor_uuid = ?1 AND timestamp = ?2)",
+                    params![
+                        result.monitor_uuid.to_string(),
+                        Monitor::timestamp_to_i64(result.timestamp),
+                        result.status.to_string(),
+                        result.latency_ms.map(|v| v as i64),
+                        result.status_code.map(|v| v as i64),
+                        result.error_message.clone(),
+                        result.peer_id.clone(),
+                        result.signature.clone(),
+                        Monitor::timestamp_to_i64(result.created_at),
+                        result.city.clone(),
+                        result.country.clone(),
+                        result.region.clone(),
+                        restored_at,
+                        result.maintenance as i64
+                    ],
+                )
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(added)
+    }
+
+    async fn append_audit_event(&self, event: &AuditEvent) -> Result<i64> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO audit_events (timestamp, kind, peer_id, message) VALUES (?, ?, ?, ?)",
+            params![
+                Monitor::timestamp_to_i64(event.timestamp),
+                event.kind.to_string(),
+                event.peer_id.clone(),
+                event.message.clone()
+            ],
+        )
+        .await?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    async fn get_audit_events(
+        &self,
+        kind: Option<AuditKind>,
+        limit: usize,
+    ) -> Result<Vec<AuditEvent>> {
+        let conn = self.get_conn().await?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, timestamp, kind, peer_id, message FROM audit_events WHERE (?1 IS NULL \
+                 OR kind = ?1) ORDER BY timestamp DESC, id DESC LIMIT ?2",
+            )
+            .await?;
+
+        let mut rows = stmt.query(params![kind.map(|kind| kind.to_string()), limit as i64]).await?;
+        let mut events = Vec::new();
+
+        while let Some(row) = rows.next().await? {
+            let timestamp: i64 = row.get(1)?;
+            let kind: String = row.get(2)?;
+
+            events.push(AuditEvent {
+                id: Some(row.get(0)?),
+                timestamp: Monitor::i64_to_timestamp(timestamp),
+                kind: kind.parse()?,
+                peer_id: row.get(3)?,
+                message: row.get(4)?,
+            });
+        }
+
+        Ok(events)
+    }
+
+    async fn delete_audit_events_before(&self, cutoff: std::time::SystemTime) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        let removed = conn
+            .execute(
+                "DELETE FROM audit_events WHERE timestamp < ?",
+                params![Monitor::timestamp_to_i64(cutoff)],
+            )
+            .await?;
+
+        Ok(removed)
+    }
+
+    async fn has_peer_result(
+        &self,
+        monitor_uuid: Uuid,
+        peer_id: &str,
+        timestamp: std::time::SystemTime,
+    ) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                HAS_PEER_RESULT_QUERY,
+                params![monitor_uuid.to_string(), peer_id, Monitor::timestamp_to_i64(timestamp)],
+            )
+            .await?;
+
+        Ok(rows.next().await?.is_some())
+    }
+
+    async fn has_result(
+        &self,
+        monitor_uuid: Uuid,
+        peer_id: &str,
+        timestamp: std::time::SystemTime,
+    ) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                HAS_RESULT_QUERY,
+                params![monitor_uuid.to_string(), peer_id, Monitor::timestamp_to_i64(timestamp)],
+            )
+            .await?;
+
+        Ok(rows.next().await?.is_some())
+    }
+
+    async fn get_owner_sync_state(&self) -> Result<Option<OwnerSyncState>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT running, monitors_total, monitors_synced, records_found, failures, \
+                 last_started_at, last_completed_at, next_attempt_at, last_error FROM \
+                 owner_sync_state WHERE id = 1",
+                (),
+            )
+            .await?;
+
+        let Some(row) = rows.next().await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(OwnerSyncState {
+            running: row.get::<i64>(0)? != 0,
+            monitors_total: row.get::<i64>(1)? as u64,
+            monitors_synced: row.get::<i64>(2)? as u64,
+            records_found: row.get::<i64>(3)? as u64,
+            failures: row.get::<i64>(4)? as u32,
+            last_started_at: row.get::<Option<i64>>(5)?.map(Monitor::i64_to_timestamp),
+            last_completed_at: row.get::<Option<i64>>(6)?.map(Monitor::i64_to_timestamp),
+            next_attempt_at: row.get::<Option<i64>>(7)?.map(Monitor::i64_to_timestamp),
+            last_error: row.get(8)?,
+        }))
+    }
+
+    async fn save_owner_sync_state(&self, state: &OwnerSyncState) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT OR REPLACE INTO owner_sync_state (id, running, monitors_total, \
+             monitors_synced, records_found, failures, last_started_at, last_completed_at, \
+             next_attempt_at, last_error) VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                if state.running { 1 } else { 0 },
+                state.monitors_total as i64,
+                state.monitors_synced as i64,
+                state.records_found as i64,
+                state.failures as i64,
+                state.last_started_at.map(Monitor::timestamp_to_i64),
+                state.last_completed_at.map(Monitor::timestamp_to_i64),
+                state.next_attempt_at.map(Monitor::timestamp_to_i64),
+                state.last_error.clone()
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_local_statuses(
+        &self,
+        monitor_uuid: Uuid,
+        start: std::time::SystemTime,
+        end: std::time::SystemTime,
+    ) -> Result<Vec<(std::time::SystemTime, MonitorStatus)>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                RESULT_STATUSES_QUERY,
+                params![
+                    monitor_uuid.to_string(),
+                    Monitor::timestamp_to_i64(start),
+                    Monitor::timestamp_to_i64(end)
+                ],
+            )
+            .await?;
+
+        let mut statuses = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let status: String = row.get(1)?;
+            statuses.push((Monitor::i64_to_timestamp(row.get(0)?), parse_status(&status)));
+        }
+
+        Ok(statuses)
+    }
+
+    async fn get_peer_results_between(
+        &self,
+        monitor_uuid: Uuid,
+        start: std::time::SystemTime,
+        end: std::time::SystemTime,
+    ) -> Result<Vec<PeerResult>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!("SELECT {PEER_RESULT_COLUMNS} {PEER_RESULTS_BETWEEN_QUERY}"),
+                params![
+                    monitor_uuid.to_string(),
+                    Monitor::timestamp_to_i64(start),
+                    Monitor::timestamp_to_i64(end)
+                ],
+            )
+            .await?;
+
+        let mut results = Vec::new();
+        while let Some(row) = rows.next().await? {
+            results.push(peer_result_from_row(&row)?);
+        }
+
+        Ok(results)
+    }
+
+    async fn get_peer_results_page(
+        &self,
+        monitor_uuid: Uuid,
+        since: std::time::SystemTime,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<PeerResult>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!("SELECT {PEER_RESULT_COLUMNS} {PEER_RESULTS_PAGE_QUERY}"),
+                params![
+                    monitor_uuid.to_string(),
+                    Monitor::timestamp_to_i64(since),
+                    after_id,
+                    limit as i64
+                ],
+            )
+            .await?;
+
+        let mut results = Vec::new();
+        while let Some(row) = rows.next().await? {
+            results.push(peer_result_from_row(&row)?);
+        }
+
+        Ok(results)
+    }
+
+    async fn get_verified_peer_statuses(
+        &self,
+        monitor_uuid: Uuid,
+        start: std::time::SystemTime,
+        end: std::time::SystemTime,
+    ) -> Result<Vec<(String, std::time::SystemTime, MonitorStatus)>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                PEER_RESULT_STATUSES_QUERY,
+                params![
+                    monitor_uuid.to_string(),
+                    Monitor::timestamp_to_i64(start),
+                    Monitor::timestamp_to_i64(end)
+                ],
+            )
+            .await?;
+
+        let mut statuses = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let status: String = row.get(2)?;
+            statuses.push((
+                row.get(0)?,
+                Monitor::i64_to_timestamp(row.get(1)?),
+                parse_status(&status),
+            ));
+        }
+
+        Ok(statuses)
+    }
+
+    async fn save_result_agreement(&self, agreement: &ResultAgreement) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO result_agreement (monitor_uuid, timestamp, bucket_seconds, local_status, \
+             peers_total, peers_agreeing, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(monitor_uuid, timestamp) DO UPDATE SET \
+             bucket_seconds=excluded.bucket_seconds, local_status=excluded.local_status, \
+             peers_total=excluded.peers_total, peers_agreeing=excluded.peers_agreeing, \
+             updated_at=excluded.updated_at",
+            params![
+                agreement.monitor_uuid.to_string(),
+                Monitor::timestamp_to_i64(agreement.bucket_start),
+                agreement.bucket_seconds as i64,
+                agreement.local_status.to_string(),
+                agreement.peers_total as i64,
+                agreement.peers_agreeing as i64,
+                Monitor::timestamp_to_i64(agreement.updated_at)
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_result_agreements(
+        &self,
+        monitor_uuid: Uuid,
+        limit: usize,
+    ) -> Result<Vec<ResultAgreement>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT timestamp, bucket_seconds, local_status, peers_total, peers_agreeing, \
+                 updated_at FROM result_agreement WHERE monitor_uuid = ? ORDER BY timestamp DESC \
+                 LIMIT ?",
+                params![monitor_uuid.to_string(), limit as i64],
+            )
+            .await?;
+
+        let mut agreements = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let status: String = row.get(2)?;
+            agreements.push(ResultAgreement {
+                monitor_uuid,
+                bucket_start: Monitor::i64_to_timestamp(row.get(0)?),
+                bucket_seconds: row.get::<i64>(1)? as u64,
+                local_status: parse_status(&status),
+                peers_total: row.get::<i64>(3)? as u32,
+                peers_agreeing: row.get::<i64>(4)? as u32,
+                updated_at: Monitor::i64_to_timestamp(row.get(5)?),
+            });
+        }
+
+        Ok(agreements)
+    }
+
+    async fn get_result_rollups(
+        &self,
+        monitor_uuid: Uuid,
+        start: std::time::SystemTime,
+        bucket_seconds: u64,
+    ) -> Result<Vec<ResultRollup>> {
+        let bucket_seconds = bucket_seconds.max(1) as i64;
+        let start = Monitor::timestamp_to_i64(start);
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                RESULT_ROLLUPS_QUERY,
+                params![monitor_uuid.to_string(), bucket_seconds, start - start % bucket_seconds],
+            )
+            .await?;
+
+        let mut rollups = Vec::new();
+        while let Some(row) = rows.next().await? {
+            rollups.push(ResultRollup {
+                bucket_start: Monitor::i64_to_timestamp(row.get(0)?),
+                checks: row.get::<i64>(1)? as u64,
+                up: row.get::<i64>(2)? as u64,
+                down: row.get::<i64>(3)? as u64,
+                avg_latency_ms: row.get::<Option<f64>>(4)?.map(|v| v.round() as u64),
+                p95_latency_ms: None,
+                max_latency_ms: row.get::<Option<i64>>(5)?.map(|v| v as u64),
+            });
+        }
+
+        Ok(rollups)
+    }
+
+    async fn rollup_results(&self, now: std::time::SystemTime) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        let now = Monitor::timestamp_to_i64(now);
+        let hour = RollupPeriod::Hourly.seconds() as i64;
+        let day = RollupPeriod::Daily.seconds() as i64;
+
+        // The hour the last rollup ran in was not over yet, so its results
+        // are picked up again
+        let mut rows = conn.query(LAST_ROLLUP_QUERY, ()).await?;
+        let last = match rows.next().await? {
+            Some(row) => row.get::<Option<i64>>(0)?.unwrap_or_default(),
+            None => 0,
+        };
+        let mut rows = conn
+            .query(PENDING_ROLLUPS_QUERY, params![last - last % hour, now - now % hour])
+            .await?;
+        let mut hours = Vec::new();
+        while let Some(row) = rows.next().await? {
+            hours.push((row.get::<String>(0)?, row.get::<i64>(1)?));
+        }
+        if hours.is_empty() {
+            return Ok(0);
+        }
+
+        let tx = conn.transaction().await?;
+        let mut days = std::collections::BTreeSet::new();
+        for (monitor_uuid, bucket) in &hours {
+            let mut rows = tx
+                .query(ROLLUP_RESULTS_QUERY, params![monitor_uuid.clone(), *bucket, bucket + hour])
+                .await?;
+            let mut results = Vec::new();
+            while let Some(row) = rows.next().await? {
+                results.push((
+                    parse_status(&row.get::<String>(0)?),
+                    row.get::<Option<i64>>(1)?.map(|v| v as u64),
+                ));
+            }
+            let rollup = ResultRollup::from_results(Monitor::i64_to_timestamp(*bucket), &results);
+            save_rollup(&tx, RollupPeriod::Hourly, monitor_uuid, &rollup, now).await?;
+            days.insert((monitor_uuid.clone(), bucket - bucket % day));
+        }
+
+        for (monitor_uuid, bucket) in days {
+            let mut rows = tx
+                .query(
+                    &format!(
+                        "SELECT {ROLLUP_COLUMNS} FROM result_rollups_hourly WHERE monitor_uuid = \
+                         ? AND bucket_start >= ? AND bucket_start < ?"
+                    ),
+                    params![monitor_uuid.clone(), bucket, bucket + day],
+                )
+                .await?;
+            let mut hourly = Vec::new();
+            while let Some(row) = rows.next().await? {
+                hourly.push(rollup_from_row(&row)?);
+            }
+            let rollup = ResultRollup::merge(Monitor::i64_to_timestamp(bucket), &hourly);
+            save_rollup(&tx, RollupPeriod::Daily, &monitor_uuid, &rollup, now).await?;
+        }
+        tx.commit().await?;
+
+        Ok(hours.len() as u64)
+    }
+
+    async fn get_rollups(
+        &self,
+        monitor_uuid: Uuid,
+        period: RollupPeriod,
+        start: std::time::SystemTime,
+    ) -> Result<Vec<ResultRollup>> {
+        let seconds = period.seconds() as i64;
+        let start = Monitor::timestamp_to_i64(start);
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {ROLLUP_COLUMNS} FROM {} WHERE monitor_uuid = ? AND bucket_start >= ? \
+                     ORDER BY bucket_start",
+                    rollup_table(period)
+                ),
+                params![monitor_uuid.to_string(), start - start % seconds],
+            )
+            .await?;
+
+        let mut rollups = Vec::new();
+        while let Some(row) = rows.next().await? {
+            rollups.push(rollup_from_row(&row)?);
+        }
+
+        Ok(rollups)
+    }
+
+    async fn delete_rollups_before(
+        &self,
+        period: RollupPeriod,
+        before: std::time::SystemTime,
+    ) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        let removed = conn
+            .execute(
+                &format!("DELETE FROM {} WHERE bucket_start < ?", rollup_table(period)),
+                params![Monitor::timestamp_to_i64(before)],
+            )
+            .await?;
+
+        Ok(removed)
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.query("SELECT 1", ()).await?.next().await?;
+        Ok(())
+    }
+
+    async fn get_private_monitor_uuids(&self) -> Result<Vec<Uuid>> {
+        let conn = self.get_conn().await?;
+        let mut rows =
+            conn.query("SELECT uuid FROM monitors WHERE visibility = 'private'", ()).await?;
+
+        let mut uuids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let uuid: String = row.get(0)?;
+            uuids.push(Uuid::parse_str(&uuid)?);
+        }
+
+        Ok(uuids)
+    }
+
+    async fn get_monitors_to_retract(&self) -> Result<Vec<Monitor>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {MONITOR_COLUMNS} FROM monitors WHERE visibility = 'private' AND \
+                     retracted_at IS NULL"
+                ),
+                (),
+            )
+            .await?;
+
+        let mut monitors = Vec::new();
+        while let Some(row) = rows.next().await? {
+            monitors.push(monitor_from_row(&row)?);
+        }
+
+        Ok(monitors)
+    }
+
+    async fn retract_monitor_results(
+        &self,
+        monitor_uuid: Uuid,
+        at: std::time::SystemTime,
+    ) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        let marked = conn.execute(RETRACT_RESULTS_QUERY, params![monitor_uuid.to_string()]).await?;
+        conn.execute(
+            "UPDATE monitors SET retracted_at = ? WHERE uuid = ?",
+            params![Monitor::timestamp_to_i64(at), monitor_uuid.to_string()],
+        )
+        .await?;
+
+        Ok(marked)
+    }
+
+    async fn retract_peer_results(&self, monitor_uuid: Uuid, owner_peer_id: &str) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        let marked = conn
+            .execute(RETRACT_PEER_RESULTS_QUERY, params![monitor_uuid.to_string(), owner_peer_id])
+            .await?;
+
+        Ok(marked)
+    }
+
+    async fn get_online_peers(&self) -> Result<Vec<Peer>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {PEER_COLUMNS} FROM peers WHERE status = 'online' AND banned_at IS \
+                     NULL ORDER BY last_seen DESC"
+                ),
+                (),
+            )
+            .await?;
+
+        let mut peers = Vec::new();
+        while let Some(row) = rows.next().await? {
+            peers.push(peer_from_row(&row)?);
+        }
+
+        Ok(peers)
+    }
+
+    async fn get_peers(&self) -> Result<Vec<Peer>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {PEER_COLUMNS} FROM peers ORDER BY status = 'online' DESC, last_seen \
+                     DESC"
+                ),
+                (),
+            )
+            .await?;
+
+        let mut peers = Vec::new();
+        while let Some(row) = rows.next().await? {
+            peers.push(peer_from_row(&row)?);
+        }
+
+        Ok(peers)
+    }
+
+    async fn record_peer_address(&self, peer_id: &str, address: &str) -> Result<()> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query("SELECT addresses FROM peers WHERE peer_id = ?", params![peer_id])
+            .await?;
+        let Some(row) = rows.next().await? else {
+            return Ok(());
+        };
+
+        let stored: String = row.get(0)?;
+        let mut addresses = serde_json::from_str::<Vec<String>>(&stored).unwrap_or_default();
+        addresses.retain(|known| known != address);
+        addresses.insert(0, address.to_string());
+        addresses.truncate(MAX_PEER_ADDRESSES);
+
+        conn.execute(
+            "UPDATE peers SET addresses = ? WHERE peer_id = ?",
+            params![serde_json::to_string(&addresses)?, peer_id],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_peer_banned(
+        &self,
+        peer_id: &str,
+        banned_at: Option<std::time::SystemTime>,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE peers SET banned_at = ? WHERE peer_id = ?",
+            params![banned_at.map(Monitor::timestamp_to_i64), peer_id],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn record_peer_greylisted(&self, peer_id: &str, at: std::time::SystemTime) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE peers SET greylist_count = greylist_count + 1, greylisted_at = ? WHERE \
+             peer_id = ?",
+            params![Monitor::timestamp_to_i64(at), peer_id],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_banned_peer_ids(&self) -> Result<Vec<String>> {
+        let conn = self.get_conn().await?;
+        let mut rows =
+            conn.query("SELECT peer_id FROM peers WHERE banned_at IS NOT NULL", ()).await?;
+
+        let mut peer_ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            peer_ids.push(row.get(0)?);
+        }
+
+        Ok(peer_ids)
+    }
+
+    async fn get_results_from_peer(
+        &self,
+        source_peer_id: &str,
+        limit: usize,
+    ) -> Result<Vec<PeerResult>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!("SELECT {PEER_RESULT_COLUMNS} {RESULTS_FROM_PEER_QUERY}"),
+                params![source_peer_id, limit as i64],
+            )
+            .await?;
+
+        let mut results = Vec::new();
+        while let Some(row) = rows.next().await? {
+            results.push(peer_result_from_row(&row)?);
+        }
+
+        Ok(results)
+    }
+
+    async fn count_results_from_peer(&self, source_peer_id: &str) -> Result<(u64, u64)> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn.query(COUNT_RESULTS_FROM_PEER_QUERY, params![source_peer_id]).await?;
+
+        match rows.next().await? {
+            Some(row) => Ok((row.get::<i64>(0)? as u64, row.get::<i64>(1)? as u64)),
+            None => Ok((0, 0)),
+        }
+    }
+
+    async fn save_multi_vantage_result(&self, result: &MultiVantageResult) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO multi_vantage_results (uuid, monitor_uuid, timestamp, peers_requested, \
+             region, state, status, peers_responded, peers_up, latency_min_ms, latency_median_ms, \
+             latency_max_ms, vantages, error, completed_at, vantage_peer_id) VALUES (?, ?, ?, ?, \
+             ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(uuid) DO UPDATE SET state=excluded.state, status=excluded.status, \
+             peers_responded=excluded.peers_responded, peers_up=excluded.peers_up, \
+             latency_min_ms=excluded.latency_min_ms, \
+             latency_median_ms=excluded.latency_median_ms, \
+             latency_max_ms=excluded.latency_max_ms, vantages=excluded.vantages, \
+             error=excluded.error, completed_at=excluded.completed_at",
+            params![
+                result.uuid.to_string(),
+                result.monitor_uuid.to_string(),
+                Monitor::timestamp_to_i64(result.requested_at),
+                result.peers_requested as i64,
+                result.region.clone(),
+                result.state.to_string(),
+                result.status.map(|status| status.to_string()),
+                result.peers_responded as i64,
+                result.peers_up as i64,
+                result.latency_min_ms.map(|v| v as i64),
+                result.latency_median_ms.map(|v| v as i64),
+                result.latency_max_ms.map(|v| v as i64),
+                serde_json::to_string(&result.vantages)?,
+                result.error.clone(),
+                result.completed_at.map(Monitor::timestamp_to_i64),
+                result.vantage_peer_id.clone()
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_multi_vantage_results_in_state(
+        &self,
+        state: ProbeState,
+    ) -> Result<Vec<MultiVantageResult>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {MULTI_VANTAGE_COLUMNS} FROM multi_vantage_results WHERE state = ? \
+                     ORDER BY timestamp ASC"
+                ),
+                params![state.to_string()],
+            )
+            .await?;
+
+        let mut results = Vec::new();
+        while let Some(row) = rows.next().await? {
+            results.push(multi_vantage_from_row(&row)?);
+        }
+
+        Ok(results)
+    }
+
+    async fn get_multi_vantage_results(
+        &self,
+        monitor_uuid: Uuid,
+        limit: usize,
+    ) -> Result<Vec<MultiVantageResult>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {MULTI_VANTAGE_COLUMNS} FROM multi_vantage_results WHERE monitor_uuid \
+                     = ? ORDER BY timestamp DESC LIMIT ?"
+                ),
+                params![monitor_uuid.to_string(), limit as i64],
+            )
+            .await?;
+
+        let mut results = Vec::new();
+        while let Some(row) = rows.next().await? {
+            results.push(multi_vantage_from_row(&row)?);
+        }
+
+        Ok(results)
+    }
+
+    async fn save_dht_operation(&self, operation: &DhtOperation) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO dht_operations (uuid, kind, key, value, state, records, error, \
+             timestamp, completed_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(uuid) DO UPDATE SET state=excluded.state, records=excluded.records, \
+             error=excluded.error, completed_at=excluded.completed_at",
+            params![
+                operation.uuid.to_string(),
+                operation.kind.to_string(),
+                operation.key.clone(),
+                operation.value.clone(),
+                operation.state.to_string(),
+                serde_json::to_string(&operation.records)?,
+                operation.error.clone(),
+                Monitor::timestamp_to_i64(operation.requested_at),
+                operation.completed_at.map(Monitor::timestamp_to_i64)
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_dht_operations_in_state(&self, state: ProbeState) -> Result<Vec<DhtOperation>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {DHT_OPERATION_COLUMNS} FROM dht_operations WHERE state = ? ORDER BY \
+                     timestamp ASC"
+                ),
+                params![state.to_string()],
+            )
+            .await?;
+
+        let mut operations = Vec::new();
+        while let Some(row) = rows.next().await? {
+            operations.push(dht_operation_from_row(&row)?);
+        }
+
+        Ok(operations)
+    }
+
+    async fn get_dht_operations(&self, limit: usize) -> Result<Vec<DhtOperation>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {DHT_OPERATION_COLUMNS} FROM dht_operations ORDER BY timestamp DESC, \
+                     id DESC LIMIT ?"
+                ),
+                params![limit as i64],
+            )
+            .await?;
+
+        let mut operations = Vec::new();
+        while let Some(row) = rows.next().await? {
+            operations.push(dht_operation_from_row(&row)?);
+        }
+
+        Ok(operations)
+    }
+
+    async fn get_open_incidents(&self) -> Result<Vec<Incident>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {INCIDENT_COLUMNS} FROM incidents WHERE resolved_at IS NULL ORDER BY \
+                     started_at"
+                ),
+                (),
+            )
+            .await?;
+
+        let mut incidents = Vec::new();
+        while let Some(row) = rows.next().await? {
+            incidents.push(incident_from_row(&row)?);
+        }
+
+        Ok(incidents)
+    }
+
+    async fn get_incidents(&self, limit: usize) -> Result<Vec<Incident>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {INCIDENT_COLUMNS} FROM incidents ORDER BY started_at DESC, id DESC \
+                     LIMIT ?"
+                ),
+                params![limit as i64],
+            )
+            .await?;
+
+        let mut incidents = Vec::new();
+        while let Some(row) = rows.next().await? {
+            incidents.push(incident_from_row(&row)?);
+        }
+
+        Ok(incidents)
+    }
+
+    async fn get_incident(&self, uuid: Uuid) -> Result<Option<Incident>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!("SELECT {INCIDENT_COLUMNS} FROM incidents WHERE uuid = ?"),
+                params![uuid.to_string()],
+            )
+            .await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(Some(incident_from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn save_incident(&self, incident: &Incident) -> Result<()> {
+        let conn = self.get_conn().await?;
+        let now = Monitor::timestamp_to_i64(std::time::SystemTime::now());
+        conn.execute(
+            "INSERT INTO incidents (uuid, title, status, severity, monitor_uuid, started_at, \
+             resolved_at, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(uuid) DO UPDATE SET title=excluded.title, status=excluded.status, \
+             severity=excluded.severity, resolved_at=excluded.resolved_at, \
+             updated_at=excluded.updated_at",
+            params![
+                incident.uuid.to_string(),
+                incident.title.clone(),
+                incident.status.clone(),
+                incident.severity.clone(),
+                incident.monitor_uuid.map(|uuid| uuid.to_string()),
+                Monitor::timestamp_to_i64(incident.started_at),
+                incident.resolved_at.map(Monitor::timestamp_to_i64),
+                now,
+                now
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn add_incident_update(&self, update: &IncidentUpdate) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO incident_updates (incident_uuid, status, message, created_at) VALUES (?, \
+             ?, ?, ?)",
+            params![
+                update.incident_uuid.to_string(),
+                update.status.clone(),
+                update.message.clone(),
+                Monitor::timestamp_to_i64(update.created_at)
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_incident_updates(&self, incident_uuid: Uuid) -> Result<Vec<IncidentUpdate>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT status, message, created_at FROM incident_updates WHERE incident_uuid = ? \
+                 ORDER BY created_at, id",
+                params![incident_uuid.to_string()],
+            )
+            .await?;
+
+        let mut updates = Vec::new();
+        while let Some(row) = rows.next().await? {
+            updates.push(IncidentUpdate {
+                incident_uuid,
+                status: row.get(0)?,
+                message: row.get(1)?,
+                created_at: Monitor::i64_to_timestamp(row.get(2)?),
+            });
+        }
+
+        Ok(updates)
+    }
+
+    async fn get_notification_channels(&self) -> Result<Vec<NotificationChannel>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT uuid, name, kind, target, enabled, created_at, last_test_at, \
+                 last_test_error FROM notification_channels ORDER BY name",
+                (),
+            )
+            .await?;
+
+        let mut channels = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let uuid: String = row.get(0)?;
+            let kind: String = row.get(2)?;
+            channels.push(NotificationChannel {
+                uuid: Uuid::parse_str(&uuid)?,
+                name: row.get(1)?,
+                kind: kind.parse()?,
+                target: row.get(3)?,
+                enabled: row.get::<i64>(4)? != 0,
+                created_at: Monitor::i64_to_timestamp(row.get(5)?),
+                last_test_at: row.get::<Option<i64>>(6)?.map(Monitor::i64_to_timestamp),
+                last_test_error: row.get(7)?,
+            });
+        }
+
+        Ok(channels)
+    }
+
+    async fn save_notification_channel(&self, channel: &NotificationChannel) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO notification_channels (uuid, name, kind, target, enabled, created_at, \
+             last_test_at, last_test_error) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(uuid) DO UPDATE SET name=excluded.name, kind=excluded.kind, \
+             target=excluded.target, enabled=excluded.enabled, \
+             last_test_at=excluded.last_test_at, last_test_error=excluded.last_test_error",
+            params![
+                channel.uuid.to_string(),
+                channel.name.clone(),
+                channel.kind.to_string(),
+                channel.target.clone(),
+                channel.enabled as i64,
+                Monitor::timestamp_to_i64(channel.created_at),
+                channel.last_test_at.map(Monitor::timestamp_to_i64),
+                channel.last_test_error.clone()
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_notification_channel(&self, uuid: Uuid) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "DELETE FROM notification_route_channels WHERE channel_uuid = ?",
+            params![uuid.to_string()],
+        )
+        .await?;
+        conn.execute("DELETE FROM notification_channels WHERE uuid = ?", params![uuid.to_string()])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn record_notification_test(&self, uuid: Uuid, error: Option<&str>) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE notification_channels SET last_test_at = ?, last_test_error = ? WHERE uuid = ?",
+            params![
+                Monitor::timestamp_to_i64(std::time::SystemTime::now()),
+                error.map(str::to_string),
+                uuid.to_string()
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_notification_routes(&self) -> Result<Vec<NotificationRoute>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT scope, escalate_after_secs, updated_at FROM notification_routes ORDER BY \
+                 scope",
+                (),
+            )
+            .await?;
+
+        let mut routes = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let scope: String = row.get(0)?;
+            routes.push(NotificationRoute {
+                scope: scope.parse()?,
+                channels: Vec::new(),
+                escalation: row
+                    .get::<Option<i64>>(1)?
+                    .map(|secs| EscalationPolicy { after_secs: secs as u64, channels: Vec::new() }),
+                updated_at: Monitor::i64_to_timestamp(row.get(2)?),
+            });
+        }
+
+        let mut rows = conn
+            .query("SELECT scope, channel_uuid, escalation FROM notification_route_channels", ())
+            .await?;
+        while let Some(row) = rows.next().await? {
+            let scope: String = row.get(0)?;
+            let channel: String = row.get(1)?;
+            let Some(route) = routes.iter_mut().find(|route| route.scope.to_string() == scope)
+            else {
+                continue;
+            };
+            let channel = Uuid::parse_str(&channel)?;
+            match (&mut route.escalation, row.get::<i64>(2)? != 0) {
+                (_, false) => route.channels.push(channel),
+                (Some(policy), true) => policy.channels.push(channel),
+                (None, true) => {}
+            }
+        }
+
+        Ok(routes)
+    }
+
+    async fn save_notification_route(&self, route: &NotificationRoute) -> Result<()> {
+        let conn = self.get_conn().await?;
+        let scope = route.scope.to_string();
+        let tx = conn.transaction().await?;
+        tx.execute(
+            "INSERT INTO notification_routes (scope, escalate_after_secs, updated_at) VALUES (?, \
+             ?, ?) ON CONFLICT(scope) DO UPDATE SET \
+             escalate_after_secs=excluded.escalate_after_secs, updated_at=excluded.updated_at",
+            params![
+                scope.clone(),
+                route.escalation.as_ref().map(|policy| policy.after_secs as i64),
+                Monitor::timestamp_to_i64(route.updated_at)
+            ],
+        )
+        .await?;
+        tx.execute(
+            "DELETE FROM notification_route_channels WHERE scope = ?",
+            params![scope.clone()],
+        )
+        .await?;
+        let escalation = route.escalation.iter().flat_map(|policy| &policy.channels);
+        let channels = route
+            .channels
+            .iter()
+            .map(|uuid| (uuid, 0))
+            .chain(escalation.map(|uuid| (uuid, 1)));
+        for (channel, escalation) in channels {
+            tx.execute(
+                "INSERT OR IGNORE INTO notification_route_channels (scope, channel_uuid, \
+                 escalation) VALUES (?, ?, ?)",
+                params![scope.clone(), channel.to_string(), escalation as i64],
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn delete_notification_route(&self, scope: &RouteScope) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let scope = scope.to_string();
+        conn.execute(
+            "DELETE FROM notification_route_channels WHERE scope = ?",
+            params![scope.clone()],
+        )
+        .await?;
+        let removed = conn
+            .execute("DELETE FROM notification_routes WHERE scope = ?", params![scope])
+            .await?;
+
+        Ok(removed > 0)
+    }
+
+    async fn get_status_pages(&self) -> Result<Vec<StatusPage>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(&format!("SELECT {STATUS_PAGE_COLUMNS} FROM status_pages ORDER BY slug"), ())
+            .await?;
+
+        let mut pages = Vec::new();
+        while let Some(row) = rows.next().await? {
+            pages.push(status_page_from_row(&row)?);
+        }
+
+        Ok(pages)
+    }
+
+    async fn get_status_page(&self, slug: &str) -> Result<Option<StatusPage>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!("SELECT {STATUS_PAGE_COLUMNS} FROM status_pages WHERE slug = ?"),
+                params![slug],
+            )
+            .await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(Some(status_page_from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn save_status_page(&self, page: &StatusPage) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO status_pages (uuid, title, slug, description, is_active, created_at, \
+             updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(uuid) DO UPDATE SET title=excluded.title, slug=excluded.slug, \
+             description=excluded.description, is_active=excluded.is_active, \
+             updated_at=excluded.updated_at",
+            params![
+                page.uuid.to_string(),
+                page.title.clone(),
+                page.slug.clone(),
+                page.description.clone(),
+                page.is_active as i64,
+                Monitor::timestamp_to_i64(page.created_at),
+                Monitor::timestamp_to_i64(page.updated_at)
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_status_page_monitors(&self, page_uuid: Uuid) -> Result<Vec<Monitor>> {
+        let conn = self.get_conn().await?;
+        let columns = MONITOR_COLUMNS.split(", ").map(|c| format!("m.{}", c.trim()));
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM monitors m JOIN status_page_monitors s ON s.monitor_uuid = \
+                     m.uuid WHERE s.status_page_id = ? ORDER BY s.display_order",
+                    columns.collect::<Vec<_>>().join(", ")
+                ),
+                params![page_uuid.to_string()],
+            )
+            .await?;
+
+        let mut monitors = Vec::new();
+        while let Some(row) = rows.next().await? {
+            monitors.push(monitor_from_row(&row)?);
+        }
+
+        Ok(monitors)
+    }
+
+    async fn set_status_page_monitor(
+        &self,
+        page_uuid: Uuid,
+        monitor_uuid: Uuid,
+        shown: bool,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        let sql = if shown {
+            "INSERT OR IGNORE INTO status_page_monitors (status_page_id, monitor_uuid, \
+             display_order) SELECT ?1, ?2, COALESCE(MAX(display_order), 0) + 1 FROM \
+             status_page_monitors WHERE status_page_id = ?1"
+        } else {
+            "DELETE FROM status_page_monitors WHERE status_page_id = ?1 AND monitor_uuid = ?2"
+        };
+        conn.execute(sql, params![page_uuid.to_string(), monitor_uuid.to_string()])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_maintenance_windows(
+        &self,
+        now: std::time::SystemTime,
+    ) -> Result<Vec<MaintenanceWindow>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {MAINTENANCE_WINDOW_COLUMNS} FROM maintenance_windows WHERE ends_at > \
+                     ? ORDER BY starts_at"
+                ),
+                params![Monitor::timestamp_to_i64(now)],
+            )
+            .await?;
+
+        let mut windows = Vec::new();
+        while let Some(row) = rows.next().await? {
+            windows.push(maintenance_window_from_row(&row)?);
+        }
+
+        Ok(windows)
+    }
+
+    async fn save_maintenance_window(&self, window: &MaintenanceWindow) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO maintenance_windows (uuid, monitor_uuid, starts_at, ends_at, reason, \
+             created_at) VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                window.uuid.to_string(),
+                window.monitor_uuid.map(|uuid| uuid.to_string()),
+                Monitor::timestamp_to_i64(window.starts_at),
+                Monitor::timestamp_to_i64(window.ends_at),
+                window.reason.clone(),
+                Monitor::timestamp_to_i64(window.created_at)
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_maintenance_window(&self, uuid: Uuid) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let deleted = conn
+            .execute("DELETE FROM maintenance_windows WHERE uuid = ?", params![uuid.to_string()])
+            .await?;
+
+        Ok(deleted > 0)
+    }
+
+    async fn get_follows(&self) -> Result<Vec<Follow>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn.query("SELECT host, created_at FROM follows ORDER BY host", ()).await?;
+
+        let mut follows = Vec::new();
+        while let Some(row) = rows.next().await? {
+            follows.push(Follow {
+                host: row.get(0)?,
+                created_at: Monitor::i64_to_timestamp(row.get(1)?),
+            });
+        }
+
+        Ok(follows)
+    }
+
+    async fn save_follow(&self, follow: &Follow) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT OR IGNORE INTO follows (host, created_at) VALUES (?, ?)",
+            params![follow.host.clone(), Monitor::timestamp_to_i64(follow.created_at)],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_follow(&self, host: &str) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        conn.execute("DELETE FROM follow_monitors WHERE host = ?", params![host])
+            .await?;
+        let deleted = conn.execute("DELETE FROM follows WHERE host = ?", params![host]).await?;
+
+        Ok(deleted > 0)
+    }
+
+    async fn get_follow_monitors(&self, host: &str) -> Result<Vec<Uuid>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT monitor_uuid FROM follow_monitors WHERE host = ? ORDER BY first_seen",
+                params![host],
+            )
+            .await?;
+
+        let mut monitors = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let uuid: String = row.get(0)?;
+            monitors.push(Uuid::parse_str(&uuid)?);
+        }
+
+        Ok(monitors)
+    }
+
+    async fn add_follow_monitor(&self, host: &str, monitor_uuid: Uuid) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT OR IGNORE INTO follow_monitors (host, monitor_uuid, first_seen) VALUES (?, ?, \
+             ?)",
+            params![
+                host,
+                monitor_uuid.to_string(),
+                Monitor::timestamp_to_i64(std::time::SystemTime::now())
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_community_domains(&self) -> Result<Vec<CommunityDomain>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT host, joined, monitor_uuid, updated_at FROM community_domains ORDER BY \
+                 host",
+                (),
+            )
+            .await?;
+
+        let mut domains = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let monitor_uuid: Option<String> = row.get(2)?;
+            domains.push(CommunityDomain {
+                host: row.get(0)?,
+                joined: row.get::<i64>(1)? != 0,
+                monitor_uuid: monitor_uuid.as_deref().map(Uuid::parse_str).transpose()?,
+                updated_at: Monitor::i64_to_timestamp(row.get(3)?),
+            });
+        }
+
+        Ok(domains)
+    }
+
+    async fn save_community_domain(&self, domain: &CommunityDomain) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT OR REPLACE INTO community_domains (host, joined, monitor_uuid, updated_at) \
+             VALUES (?, ?, ?, ?)",
+            params![
+                domain.host.clone(),
+                domain.joined as i64,
+                domain.monitor_uuid.map(|uuid| uuid.to_string()),
+                Monitor::timestamp_to_i64(domain.updated_at)
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_group_memberships(&self) -> Result<Vec<GroupMembership>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT host, peer_id, joined, announced_at FROM group_memberships ORDER BY host, \
+                 peer_id",
+                (),
+            )
+            .await?;
+
+        let mut memberships = Vec::new();
+        while let Some(row) = rows.next().await? {
+            memberships.push(GroupMembership {
+                host: row.get(0)?,
+                peer_id: row.get(1)?,
+                joined: row.get::<i64>(2)? != 0,
+                announced_at: Monitor::i64_to_timestamp(row.get(3)?),
+            });
+        }
+
+        Ok(memberships)
+    }
+
+    async fn save_group_membership(&self, membership: &GroupMembership) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let saved = conn
+            .execute(
+                "INSERT INTO group_memberships (host, peer_id, joined, announced_at) VALUES (?, \
+                 ?, ?, ?)
+                 ON CONFLICT(host, peer_id) DO UPDATE SET joined = excluded.joined, announced_at = \
+                 excluded.announced_at
+                 WHERE excluded.announced_at > group_memberships.announced_at",
+                params![
+                    membership.host.clone(),
+                    membership.peer_id.clone(),
+                    membership.joined as i64,
+                    Monitor::timestamp_to_i64(membership.announced_at)
+                ],
+            )
+            .await?;
+
+        Ok(saved > 0)
+    }
+
+    async fn add_peer_contribution(&self, peer_id: &str, score: f64) -> Result<()> {
+        let conn = self.get_conn().await?;
+        let now = Monitor::timestamp_to_i64(std::time::SystemTime::now());
+        conn.execute(
+            "INSERT INTO peers (peer_id, status, last_seen, joined_at, contribution_score)
+             VALUES (?, 'online', ?, ?, 1.0 + ?)
+             ON CONFLICT(peer_id) DO UPDATE SET contribution_score = \
+             COALESCE(peers.contribution_score, 1.0) + ?",
+            params![peer_id, now, now, score, score],
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+// Result lookups run for every check or incoming result, or by the TUI and
+// status pages; the tests check that each one is answered from an index.
+// Those selecting peer results follow `SELECT {PEER_RESULT_COLUMNS}`.
+
+/// Latest results of a monitor
+const MONITOR_RESULTS_QUERY: &str = "SELECT id, monitor_uuid, timestamp, status, latency_ms, \
+                                     status_code, error_message, peer_id, signature, created_at, \
+                                     city, country, region, maintenance FROM monitor_results \
+                                     WHERE monitor_uuid = ? ORDER BY timestamp DESC LIMIT ?";
+/// Whether we stored a result for a monitor at a time
+const HAS_RESULT_QUERY: &str = "SELECT 1 FROM monitor_results WHERE monitor_uuid = ? AND peer_id \
+                                = ? AND timestamp = ? LIMIT 1";
+/// Statuses of a monitor's results over a time range, outside maintenance
+const RESULT_STATUSES_QUERY: &str = "SELECT timestamp, status FROM monitor_results WHERE \
+                                     monitor_uuid = ? AND timestamp >= ? AND timestamp < ? AND \
+                                     maintenance = 0";
+/// Status counts and latencies of a monitor's results per time bucket,
+/// outside maintenance
+const RESULT_ROLLUPS_QUERY: &str =
+    "SELECT (timestamp / ?2) * ?2 AS bucket, COUNT(*), SUM(CASE WHEN status IN ('up', 'degraded') \
+     THEN 1 ELSE 0 END), SUM(CASE WHEN status = 'down' THEN 1 ELSE 0 END), AVG(latency_ms), \
+     MAX(latency_ms) FROM monitor_results WHERE monitor_uuid = ?1 AND timestamp >= ?3 AND \
+     maintenance = 0 GROUP BY bucket ORDER BY bucket";
+/// When results were last rolled up
+const LAST_ROLLUP_QUERY: &str = "SELECT MAX(rolled_up_at) FROM result_rollups_hourly";
+/// Monitors and hours with results created since ?1 in hours that ended by ?2
+const PENDING_ROLLUPS_QUERY: &str = "SELECT DISTINCT monitor_uuid, timestamp / 3600 * 3600 FROM \
+                                     monitor_results WHERE created_at >= ?1 AND timestamp < ?2 \
+                                     AND maintenance = 0";
+/// Statuses and latencies of a monitor's results in `[?2, ?3)`, outside
+/// maintenance
+const ROLLUP_RESULTS_QUERY: &str = "SELECT status, latency_ms FROM monitor_results WHERE \
+                                    monitor_uuid = ?1 AND timestamp >= ?2 AND timestamp < ?3 AND \
+                                    maintenance = 0";
+/// Mark a monitor's results as retracted
+const RETRACT_RESULTS_QUERY: &str =
+    "UPDATE monitor_results SET retracted = 1 WHERE monitor_uuid = ? AND retracted = 0";
+/// Latest peer results for a monitor
+const PEER_RESULTS_QUERY: &str =
+    "FROM peer_results WHERE monitor_uuid = ? ORDER BY timestamp DESC LIMIT ?";
+/// Peer results for a monitor over a time range
+const PEER_RESULTS_BETWEEN_QUERY: &str = "FROM peer_results WHERE monitor_uuid = ? AND timestamp \
+                                          >= ? AND timestamp < ? ORDER BY timestamp ASC";
+/// Page of verified peer results for a monitor since a time, for results sync
+const PEER_RESULTS_PAGE_QUERY: &str = "FROM peer_results WHERE monitor_uuid = ? AND verified = 1 \
+                                       AND retracted = 0 AND timestamp >= ? AND id > ? ORDER BY \
+                                       id ASC LIMIT ?";
+/// Whether a peer's result for a monitor at a time was stored
+const HAS_PEER_RESULT_QUERY: &str =
+    "SELECT 1 FROM peer_results WHERE monitor_uuid = ? AND peer_id = ? AND timestamp = ? LIMIT 1";
+/// Statuses of verified peer results for a monitor over a time range
+const PEER_RESULT_STATUSES_QUERY: &str = "SELECT peer_id, timestamp, status FROM peer_results \
+                                          WHERE monitor_uuid = ? AND verified = 1 AND timestamp \
+                                          >= ? AND timestamp < ?";
+/// Mark an owner's results for a monitor as retracted
+const RETRACT_PEER_RESULTS_QUERY: &str = "UPDATE peer_results SET retracted = 1 WHERE \
+                                          monitor_uuid = ? AND peer_id = ? AND retracted = 0";
+/// Latest results a libp2p peer delivered
+const RESULTS_FROM_PEER_QUERY: &str =
+    "FROM peer_results WHERE source_peer_id = ? ORDER BY timestamp DESC LIMIT ?";
+/// Results a libp2p peer delivered and how many of them were verified
+const COUNT_RESULTS_FROM_PEER_QUERY: &str =
+    "SELECT COUNT(*), COALESCE(SUM(verified), 0) FROM peer_results WHERE source_peer_id = ?";
+
+/// Extra condition keeping restored results for a week after their restore,
+/// whatever their age
+const RESTORED_KEPT: &str = "AND (r.restored_at IS NULL OR r.restored_at < ?1 - 7 * 86400)";
+
+/// Columns `maintenance_window_from_row` expects, in order
+pub(super) const MAINTENANCE_WINDOW_COLUMNS: &str =
+    "uuid, monitor_uuid, starts_at, ends_at, reason, created_at";
+
+fn maintenance_window_from_row(row: &libsql::Row) -> Result<MaintenanceWindow> {
+    let uuid: String = row.get(0)?;
+    let monitor_uuid: Option<String> = row.get(1)?;
+    Ok(MaintenanceWindow {
+        uuid: Uuid::parse_str(&uuid)?,
+        monitor_uuid: monitor_uuid.as_deref().map(Uuid::parse_str).transpose()?,
+        starts_at: Monitor::i64_to_timestamp(row.get(2)?),
+        ends_at: Monitor::i64_to_timestamp(row.get(3)?),
+        reason: row.get(4)?,
+        created_at: Monitor::i64_to_timestamp(row.get(5)?),
+    })
+}
+
+/// Columns `status_page_from_row` expects, in order
+pub(super) const STATUS_PAGE_COLUMNS: &str =
+    "uuid, title, slug, description, is_active, created_at, updated_at";
+
+fn status_page_from_row(row: &libsql::Row) -> Result<StatusPage> {
+    let uuid: String = row.get(0)?;
+    let description: Option<String> = row.get(3)?;
+    Ok(StatusPage {
+        uuid: Uuid::parse_str(&uuid)?,
+        title: row.get(1)?,
+        slug: row.get(2)?,
+        description: description.unwrap_or_default(),
+        is_active: row.get::<i64>(4)? != 0,
+        created_at: Monitor::i64_to_timestamp(row.get(5)?),
+        updated_at: Monitor::i64_to_timestamp(row.get(6)?),
+    })
+}
+
+/// Columns `monitor_from_row` expects, in order
+pub(super) const MONITOR_COLUMNS: &str =
+    "id, uuid, name, target, check_type, interval_seconds, timeout_seconds, enabled, created_at, \
+     updated_at, retention_days, visibility, tags, expected_status_codes, headers, grace_seconds";
+
+fn monitor_from_row(row: &libsql::Row) -> Result<Monitor> {
+    let uuid_str: String = row.get(1)?;
+    let visibility: String = row.get(11)?;
+    let tags: String = row.get(12)?;
+
+    Ok(Monitor {
+        id: Some(row.get(0)?),
+        uuid: Uuid::parse_str(&uuid_str)?,
+        name: row.get(2)?,
+        target: row.get(3)?,
+        check_type: row.get(4)?,
+        interval_seconds: row.get::<i64>(5)? as u64,
+        timeout_seconds: row.get::<i64>(6)? as u64,
+        grace_seconds: row.get::<i64>(15)?.max(0) as u64,
+        enabled: row.get::<i64>(7)? != 0,
+        retention_days: row.get::<Option<i64>>(10)?.map(|days| days.max(0) as u64),
+        visibility: visibility.parse().unwrap_or_default(),
+        tags: Monitor::parse_tags(&tags),
+        expected_status_codes: parse_json_column(row.get(13)?),
+        headers: parse_json_column(row.get(14)?),
+        created_at: Monitor::i64_to_timestamp(row.get(8)?),
+        updated_at: Monitor::i64_to_timestamp(row.get(9)?),
+    })
+}
+
+/// A JSON column's value, or the default when it is NULL or unreadable
+pub(super) fn parse_json_column<T: serde::de::DeserializeOwned + Default>(
+    value: Option<String>,
+) -> T {
+    value.and_then(|value| serde_json::from_str(&value).ok()).unwrap_or_default()
+}
+
+/// Build a result from `id, monitor_uuid, timestamp, status, latency_ms,
+/// status_code, error_message, peer_id, signature, created_at, city, country,
+/// region, maintenance`
+fn monitor_result_from_row(row: &libsql::Row) -> Result<MonitorResult> {
+    let monitor_uuid_str: String = row.get(1)?;
+    let status_str: String = row.get(3)?;
+    let timestamp: i64 = row.get(2)?;
+    let created_at: i64 = row.get(9)?;
+
+    Ok(MonitorResult {
+        id: Some(row.get(0)?),
+        monitor_uuid: Uuid::parse_str(&monitor_uuid_str)?,
+        timestamp: Monitor::i64_to_timestamp(timestamp),
+        status: match status_str.as_str() {
+            "up" => MonitorStatus::Up,
+            "down" => MonitorStatus::Down,
+            "degraded" => MonitorStatus::Degraded,
+            _ => MonitorStatus::Unknown,
+        },
+        latency_ms: row.get::<Option<i64>>(4)?.map(|v| v as u64),
+        status_code: row.get::<Option<i64>>(5)?.map(|v| v as u16),
+        error_message: row.get(6)?,
+        peer_id: row.get(7)?,
+        signature: row.get(8)?,
+        created_at: Monitor::i64_to_timestamp(created_at),
+        city: row.get(10)?,
+        country: row.get(11)?,
+        region: row.get(12)?,
+        maintenance: row.get::<i64>(13)? != 0,
+    })
+}
+
+/// Addresses kept per peer by `record_peer_address`
+pub(super) const MAX_PEER_ADDRESSES: usize = 5;
+
+/// Columns `peer_from_row` expects, in order
+pub(super) const PEER_COLUMNS: &str = "peer_id, status, last_seen, joined_at, contribution_score, \
+                                       uptime_percentage, checks_per_day, location_city, \
+                                       location_region, location_country, addresses, banned_at, \
+                                       greylist_count, greylisted_at";
+
+fn peer_from_row(row: &libsql::Row) -> Result<Peer> {
+    let addresses: String = row.get(10)?;
+
+    Ok(Peer {
+        peer_id: row.get(0)?,
+        status: row.get(1)?,
+        last_seen: Monitor::i64_to_timestamp(row.get(2)?),
+        joined_at: Monitor::i64_to_timestamp(row.get(3)?),
+        contribution_score: row.get::<Option<f64>>(4)?.unwrap_or(1.0),
+        uptime_percentage: row.get::<Option<f64>>(5)?.unwrap_or(100.0),
+        checks_per_day: row.get::<Option<i64>>(6)?.unwrap_or(0),
+        location_city: row.get(7)?,
+        location_region: row.get(8)?,
+        location_country: row.get(9)?,
+        addresses: serde_json::from_str(&addresses).unwrap_or_default(),
+        banned_at: row.get::<Option<i64>>(11)?.map(Monitor::i64_to_timestamp),
+        greylist_count: row.get::<Option<i64>>(12)?.unwrap_or(0) as u32,
+        greylisted_at: row.get::<Option<i64>>(13)?.map(Monitor::i64_to_timestamp),
+    })
+}
+
+/// Columns `peer_result_from_row` expects, in order
+pub(super) const PEER_RESULT_COLUMNS: &str =
+    "id, monitor_uuid, timestamp, status, latency_ms, status_code, error_message, peer_id, \
+     signature, verified, created_at, city, country, region, source_peer_id, certificate";
+
+fn peer_result_from_row(row: &libsql::Row) -> Result<PeerResult> {
+    let monitor_uuid: String = row.get(1)?;
+    let status: String = row.get(3)?;
+    let certificate: Option<String> = row.get(15)?;
+
+    Ok(PeerResult {
+        id: Some(row.get(0)?),
+        monitor_uuid: Uuid::parse_str(&monitor_uuid)?,
+        timestamp: Monitor::i64_to_timestamp(row.get(2)?),
+        status: parse_status(&status),
+        latency_ms: row.get::<Option<i64>>(4)?.map(|v| v as u64),
+        status_code: row.get::<Option<i64>>(5)?.map(|v| v as u16),
+        error_message: row.get(6)?,
+        peer_id: row.get(7)?,
+        signature: row.get(8)?,
+        verified: row.get::<i64>(9)? != 0,
+        created_at: Monitor::i64_to_timestamp(row.get(10)?),
+        city: row.get(11)?,
+        country: row.get(12)?,
+        region: row.get(13)?,
+        source_peer_id: row.get(14)?,
+        certificate: certificate.and_then(|json| serde_json::from_str(&json).ok()),
+    })
+}
+
+/// Columns `multi_vantage_from_row` expects, in order
+pub(super) const MULTI_VANTAGE_COLUMNS: &str =
+    "uuid, monitor_uuid, timestamp, peers_requested, region, state, status, peers_responded, \
+     peers_up, latency_min_ms, latency_median_ms, latency_max_ms, vantages, error, completed_at, \
+     vantage_peer_id";
+
+fn multi_vantage_from_row(row: &libsql::Row) -> Result<MultiVantageResult> {
+    let uuid: String = row.get(0)?;
+    let monitor_uuid: String = row.get(1)?;
+    let state: String = row.get(5)?;
+    let status: Option<String> = row.get(6)?;
+    let vantages: String = row.get(12)?;
+
+    Ok(MultiVantageResult {
+        uuid: Uuid::parse_str(&uuid)?,
+        monitor_uuid: Uuid::parse_str(&monitor_uuid)?,
+        requested_at: Monitor::i64_to_timestamp(row.get(2)?),
+        peers_requested: row.get::<i64>(3)? as u32,
+        region: row.get(4)?,
+        state: state.parse()?,
+        status: status.as_deref().map(parse_status),
+        peers_responded: row.get::<i64>(7)? as u32,
+        peers_up: row.get::<i64>(8)? as u32,
+        latency_min_ms: row.get::<Option<i64>>(9)?.map(|v| v as u64),
+        latency_median_ms: row.get::<Option<i64>>(10)?.map(|v| v as u64),
+        latency_max_ms: row.get::<Option<i64>>(11)?.map(|v| v as u64),
+        vantages: serde_json::from_str::<Vec<Vantage>>(&vantages).unwrap_or_default(),
+        error: row.get(13)?,
+        completed_at: row.get::<Option<i64>>(14)?.map(Monitor::i64_to_timestamp),
+        vantage_peer_id: row.get(15)?,
+    })
+}
+
+/// Columns `dht_operation_from_row` expects, in order
+pub(super) const DHT_OPERATION_COLUMNS: &str =
+    "uuid, kind, key, value, state, records, error, timestamp, completed_at";
+
+fn dht_operation_from_row(row: &libsql::Row) -> Result<DhtOperation> {
+    let uuid: String = row.get(0)?;
+    let kind: String = row.get(1)?;
+    let state: String = row.get(4)?;
+    let records: String = row.get(5)?;
+
+    Ok(DhtOperation {
+        uuid: Uuid::parse_str(&uuid)?,
+        kind: kind.parse()?,
+        key: row.get(2)?,
+        value: row.get(3)?,
+        state: state.parse()?,
+        records: serde_json::from_str(&records).unwrap_or_default(),
+        error: row.get(6)?,
+        requested_at: Monitor::i64_to_timestamp(row.get(7)?),
+        completed_at: row.get::<Option<i64>>(8)?.map(Monitor::i64_to_timestamp),
+    })
+}
+
+pub(super) const INCIDENT_COLUMNS: &str =
+    "uuid, title, status, severity, monitor_uuid, started_at, resolved_at";
+
+fn incident_from_row(row: &libsql::Row) -> Result<Incident> {
+    let uuid: String = row.get(0)?;
+    let monitor_uuid: Option<String> = row.get(4)?;
+
+    Ok(Incident {
+        uuid: Uuid::parse_str(&uuid)?,
+        title: row.get(1)?,
+        status: row.get(2)?,
+        severity: row.get(3)?,
+        monitor_uuid: monitor_uuid.map(|uuid| Uuid::parse_str(&uuid)).transpose()?,
+        started_at: Monitor::i64_to_timestamp(row.get(5)?),
+        resolved_at: row.get::<Option<i64>>(6)?.map(Monitor::i64_to_timestamp),
+    })
+}
+
+pub(super) const ROLLUP_COLUMNS: &str =
+    "bucket_start, checks, up, down, avg_latency_ms, p95_latency_ms, max_latency_ms";
+
+/// Table holding the rollups of a period
+pub(super) fn rollup_table(period: RollupPeriod) -> &'static str {
+    match period {
+        RollupPeriod::Hourly => "result_rollups_hourly",
+        RollupPeriod::Daily => "result_rollups_daily",
+    }
+}
+
+fn rollup_from_row(row: &libsql::Row) -> Result<ResultRollup> {
+    Ok(ResultRollup {
+        bucket_start: Monitor::i64_to_timestamp(row.get(0)?),
+        checks: row.get::<i64>(1)? as u64,
+        up: row.get::<i64>(2)? as u64,
+        down: row.get::<i64>(3)? as u64,
+        avg_latency_ms: row.get::<Option<i64>>(4)?.map(|v| v as u64),
+        p95_latency_ms: row.get::<Option<i64>>(5)?.map(|v| v as u64),
+        max_latency_ms: row.get::<Option<i64>>(6)?.map(|v| v as u64),
+    })
+}
+
+/// Replace the stored rollup of a monitor's bucket
+async fn save_rollup(
+    conn: &Connection,
+    period: RollupPeriod,
+    monitor_uuid: &str,
+    rollup: &ResultRollup,
+    rolled_up_at: i64,
+) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO {} (monitor_uuid, {ROLLUP_COLUMNS}, rolled_up_at) VALUES (?1, \
+             ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            rollup_table(period)
+        ),
+        params![
+            monitor_uuid.to_string(),
+            Monitor::timestamp_to_i64(rollup.bucket_start),
+            rollup.checks as i64,
+            rollup.up as i64,
+            rollup.down as i64,
+            rollup.avg_latency_ms.map(|v| v as i64),
+            rollup.p95_latency_ms.map(|v| v as i64),
+            rollup.max_latency_ms.map(|v| v as i64),
+            rolled_up_at
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+pub(super) fn parse_status(status: &str) -> MonitorStatus {
+    match status {
+        "up" => MonitorStatus::Up,
+        "down" => MonitorStatus::Down,
+        "degraded" => MonitorStatus::Degraded,
+        _ => MonitorStatus::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::initialize_database;
+
+    /// Steps of a query's plan, such as `SEARCH peer_results USING INDEX ...`
+    async fn query_plan(conn: &Connection, sql: &str) -> Vec<String> {
+        let mut rows = conn.query(&format!("EXPLAIN QUERY PLAN {sql}"), ()).await.unwrap();
+        let mut steps = Vec::new();
+        while let Some(row) = rows.next().await.unwrap() {
+            steps.push(row.get::<String>(3).unwrap());
+        }
+        steps
+    }
+
+    #[tokio::test]
+    async fn test_result_queries_use_indexes() {
+        let database = libsql::Builder::new_local(":memory:").build().await.unwrap();
+        let conn = database.connect().unwrap();
+        initialize_database(&conn).await.unwrap();
+
+        let peer_results = |query| format!("SELECT {PEER_RESULT_COLUMNS} {query}");
+        let queries = [
+            MONITOR_RESULTS_QUERY.to_string(),
+            HAS_RESULT_QUERY.to_string(),
+            RESULT_STATUSES_QUERY.to_string(),
+            RESULT_ROLLUPS_QUERY.to_string(),
+            PENDING_ROLLUPS_QUERY.to_string(),
+            ROLLUP_RESULTS_QUERY.to_string(),
+            RETRACT_RESULTS_QUERY.to_string(),
+            peer_results(PEER_RESULTS_QUERY),
+            peer_results(PEER_RESULTS_BETWEEN_QUERY),
+            peer_results(PEER_RESULTS_PAGE_QUERY),
+            HAS_PEER_RESULT_QUERY.to_string(),
+            PEER_RESULT_STATUSES_QUERY.to_string(),
+            RETRACT_PEER_RESULTS_QUERY.to_string(),
+            peer_results(RESULTS_FROM_PEER_QUERY),
+            COUNT_RESULTS_FROM_PEER_QUERY.to_string(),
+        ];
+
+        for query in &queries {
+            let plan = query_plan(&conn, query).await;
+            assert!(
+                plan.iter().any(|step| step.starts_with("SEARCH"))
+                    && !plan.iter().any(|step| step.starts_with("SCAN")),
+                "{query} is not answered from an index: {plan:?}"
+            );
+        }
+
+        // Statuses and rollups are read from the index alone
+        for query in [RESULT_STATUSES_QUERY, RESULT_ROLLUPS_QUERY, ROLLUP_RESULTS_QUERY] {
+            let plan = query_plan(&conn, query).await;
+            assert!(plan.iter().any(|step| step.contains("COVERING INDEX")), "{plan:?}");
+        }
+    }
 }
@@ -0,0 +1,249 @@
+/// Export - writing the monitors, results or peers on screen to a file
+///
+/// Each kind of data is flattened into named columns, then written as CSV or
+/// as a JSON array of objects, picked by the file extension. Timestamps are
+/// Unix seconds so the files sort and diff cleanly.
+use anyhow::{Result, anyhow};
+use serde_json::{Map, Value, json};
+use std::path::Path;
+
+use crate::database::models::{Monitor, MonitorResult, Peer};
+
+/// File format of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    /// Format matching the path's extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase) {
+            Some(ext) if ext == "csv" => Ok(ExportFormat::Csv),
+            Some(ext) if ext == "json" => Ok(ExportFormat::Json),
+            _ => Err(anyhow!("Export path must end in .csv or .json")),
+        }
+    }
+}
+
+/// Data to export
+pub enum ExportData<'a> {
+    Monitors(&'a [Monitor]),
+    Results(&'a [MonitorResult]),
+    Peers(&'a [Peer]),
+}
+
+impl ExportData<'_> {
+    /// Short name of the data, used in default file names
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportData::Monitors(_) => "monitors",
+            ExportData::Results(_) => "results",
+            ExportData::Peers(_) => "peers",
+        }
+    }
+
+    /// Column names and one row of values per item
+    fn table(&self) -> (&'static [&'static str], Vec<Vec<Value>>) {
+        let secs = Monitor::timestamp_to_i64;
+        match self {
+            ExportData::Monitors(monitors) => (
+                &[
+                    "uuid",
+                    "name",
+                    "target",
+                    "check_type",
+                    "interval_seconds",
+                    "timeout_seconds",
+                    "enabled",
+                    "visibility",
+                    "tags",
+                    "created_at",
+                ],
+                monitors
+                    .iter()
+                    .map(|m| {
+                        vec![
+                            json!(m.uuid.to_string()),
+                            json!(m.name),
+                            json!(m.target),
+                            json!(m.check_type),
+                            json!(m.interval_seconds),
+                            json!(m.timeout_seconds),
+                            json!(m.enabled),
+                            json!(m.visibility.to_string()),
+                            json!(m.tags.join(" ")),
+                            json!(secs(m.created_at)),
+                        ]
+                    })
+                    .collect(),
+            ),
+            ExportData::Results(results) => (
+                &[
+                    "monitor_uuid",
+                    "timestamp",
+                    "status",
+                    "latency_ms",
+                    "status_code",
+                    "error_message",
+                    "peer_id",
+                    "city",
+                    "country",
+                    "region",
+                ],
+                results
+                    .iter()
+                    .map(|r| {
+                        vec![
+                            json!(r.monitor_uuid.to_string()),
+                            json!(secs(r.timestamp)),
+                            json!(r.status.to_string()),
+                            json!(r.latency_ms),
+                            json!(r.status_code),
+                            json!(r.error_message),
+                            json!(r.peer_id),
+                            json!(r.city),
+                            json!(r.country),
+                            json!(r.region),
+                        ]
+                    })
+                    .collect(),
+            ),
+            ExportData::Peers(peers) => (
+                &[
+                    "peer_id",
+                    "status",
+                    "last_seen",
+                    "joined_at",
+                    "contribution_score",
+                    "uptime_percentage",
+                    "checks_per_day",
+                    "city",
+                    "region",
+                    "country",
+                    "addresses",
+                    "banned_at",
+                    "greylist_count",
+                    "greylisted_at",
+                ],
+                peers
+                    .iter()
+                    .map(|p| {
+                        vec![
+                            json!(p.peer_id),
+                            json!(p.status),
+                            json!(secs(p.last_seen)),
+                            json!(secs(p.joined_at)),
+                            json!(p.contribution_score),
+                            json!(p.uptime_percentage),
+                            json!(p.checks_per_day),
+                            json!(p.location_city),
+                            json!(p.location_region),
+                            json!(p.location_country),
+                            json!(p.addresses.join(" ")),
+                            json!(p.banned_at.map(secs)),
+                            json!(p.greylist_count),
+                            json!(p.greylisted_at.map(secs)),
+                        ]
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Number of items
+    pub fn len(&self) -> usize {
+        match self {
+            ExportData::Monitors(monitors) => monitors.len(),
+            ExportData::Results(results) => results.len(),
+            ExportData::Peers(peers) => peers.len(),
+        }
+    }
+}
+
+/// Quote a CSV field when it holds a separator, quote or line break
+fn csv_field(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// Render the data in the given format
+pub fn render(data: &ExportData<'_>, format: ExportFormat) -> Result<String> {
+    let (columns, rows) = data.table();
+    match format {
+        ExportFormat::Csv => {
+            let mut out = columns.join(",");
+            out.push('\n');
+            for row in rows {
+                out.push_str(&row.iter().map(csv_field).collect::<Vec<_>>().join(","));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+        ExportFormat::Json => {
+            let objects: Vec<Value> = rows
+                .into_iter()
+                .map(|row| {
+                    let object: Map<String, Value> =
+                        columns.iter().map(|column| column.to_string()).zip(row).collect();
+                    Value::Object(object)
+                })
+                .collect();
+            Ok(serde_json::to_string_pretty(&objects)?)
+        }
+    }
+}
+
+/// Write the data to `path`, in the format its extension names
+pub fn write(path: &Path, data: &ExportData<'_>) -> Result<()> {
+    let format = ExportFormat::from_path(path)?;
+    std::fs::write(path, render(data, format)?)
+        .map_err(|e| anyhow!("Failed to write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(ExportFormat::from_path(Path::new("out.csv")).unwrap(), ExportFormat::Csv);
+        assert_eq!(ExportFormat::from_path(Path::new("a/b.JSON")).unwrap(), ExportFormat::Json);
+        assert!(ExportFormat::from_path(Path::new("out.txt")).is_err());
+        assert!(ExportFormat::from_path(Path::new("out")).is_err());
+    }
+
+    #[test]
+    fn test_render_monitors() {
+        let mut monitor = Monitor::new(
+            "API, \"main\"".to_string(),
+            "https://example.com".to_string(),
+            "https".to_string(),
+        );
+        monitor.tags = vec!["prod".to_string(), "eu".to_string()];
+        let monitors = [monitor];
+        let data = ExportData::Monitors(&monitors);
+
+        let csv = render(&data, ExportFormat::Csv).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("uuid,name,target,"));
+        let row = lines.next().unwrap();
+        assert!(row.contains(",\"API, \"\"main\"\"\",https://example.com,https,30,10,true,"));
+        assert!(row.contains(",prod eu,"));
+        assert_eq!(lines.next(), None);
+
+        let json: Value =
+            serde_json::from_str(&render(&data, ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["name"], "API, \"main\"");
+        assert_eq!(json[0]["interval_seconds"], 30);
+    }
+}
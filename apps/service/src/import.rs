@@ -0,0 +1,287 @@
+/// Import - reading the history other monitoring tools exported into a monitor
+///
+/// A CSV file with a header row is mapped onto results by column: a timestamp,
+/// a status and optionally a latency in milliseconds. Columns are looked up
+/// under the names exports commonly use unless named explicitly. Timestamps
+/// are Unix seconds or milliseconds, or dates like "2024-01-31 12:00:00" (UTC
+/// unless they carry an offset). Rows that can't be read are skipped and
+/// reported by line, so one odd row doesn't lose the rest of the history.
+use anyhow::{Result, anyhow};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::civil::days_from_civil;
+use crate::database::models::MonitorResult;
+use crate::monitoring::types::MonitorStatus;
+
+/// Peer ID imported results are stored under, telling them from our checks
+pub const IMPORT_PEER_ID: &str = "import";
+
+/// Column names tried when none is given, compared case-insensitively
+const TIMESTAMP_COLUMNS: &[&str] = &["timestamp", "time", "date", "datetime", "checked_at"];
+const STATUS_COLUMNS: &[&str] = &["status", "state", "result", "up"];
+const LATENCY_COLUMNS: &[&str] =
+    &["latency_ms", "latency", "response_time", "response_time_ms", "duration", "ping", "ms"];
+
+/// Millisecond timestamps are told from second ones by size; 10^11 seconds
+/// is over a thousand years away
+const MILLIS_ABOVE: f64 = 1e11;
+
+/// Columns to read, by header name; `None` looks for a common name
+#[derive(Debug, Clone, Default)]
+pub struct ColumnNames {
+    pub timestamp: Option<String>,
+    pub status: Option<String>,
+    pub latency: Option<String>,
+}
+
+/// Results read from a file, and the rows that were skipped
+#[derive(Debug, Default)]
+pub struct Import {
+    pub results: Vec<MonitorResult>,
+    /// Line and reason of each skipped row
+    pub skipped: Vec<(usize, String)>,
+}
+
+/// Read the results in a CSV export for a monitor
+///
+/// Fails when the header lacks a timestamp or status column.
+pub fn parse_csv(
+    text: &str,
+    monitor_uuid: Uuid,
+    columns: &ColumnNames,
+    now: SystemTime,
+) -> Result<Import> {
+    let mut records = csv_records(text).into_iter();
+    let (_, header) = records.next().ok_or_else(|| anyhow!("The file is empty"))?;
+    let find = |name: &Option<String>, defaults: &[&str]| match name {
+        Some(name) => header
+            .iter()
+            .position(|column| column.trim().eq_ignore_ascii_case(name))
+            .map(Some)
+            .ok_or_else(|| anyhow!("No column named '{}'", name)),
+        None => Ok(defaults
+            .iter()
+            .find_map(|name| header.iter().position(|c| c.trim().eq_ignore_ascii_case(name)))),
+    };
+    let timestamp_at = find(&columns.timestamp, TIMESTAMP_COLUMNS)?
+        .ok_or_else(|| anyhow!("No timestamp column found; name it with --timestamp-column"))?;
+    let status_at = find(&columns.status, STATUS_COLUMNS)?
+        .ok_or_else(|| anyhow!("No status column found; name it with --status-column"))?;
+    let latency_at = find(&columns.latency, LATENCY_COLUMNS)?;
+
+    let mut import = Import::default();
+    for (line, record) in records {
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let field = |at: usize| record.get(at).map(|field| field.trim()).unwrap_or_default();
+        let row = (|| {
+            let timestamp = parse_timestamp(field(timestamp_at))?;
+            let status = parse_status(field(status_at))?;
+            let latency_ms = match latency_at.map(field) {
+                None | Some("") => None,
+                Some(latency) => Some(parse_latency(latency)?),
+            };
+            Ok::<_, String>((timestamp, status, latency_ms))
+        })();
+        match row {
+            Ok((timestamp, status, latency_ms)) => import.results.push(MonitorResult {
+                id: None,
+                monitor_uuid,
+                timestamp,
+                status,
+                latency_ms,
+                status_code: None,
+                error_message: None,
+                peer_id: IMPORT_PEER_ID.to_string(),
+                signature: None,
+                created_at: now,
+                city: None,
+                country: None,
+                region: None,
+                maintenance: false,
+            }),
+            Err(reason) => import.skipped.push((line, reason)),
+        }
+    }
+
+    Ok(import)
+}
+
+/// Records of a CSV file with the line each starts on; quoted fields may hold
+/// separators, doubled quotes and line breaks
+fn csv_records(text: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push((start, std::mem::take(&mut record)));
+                line += 1;
+                start = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((start, record));
+    }
+    records
+}
+
+/// A Unix timestamp in seconds or milliseconds, or a date and time
+fn parse_timestamp(value: &str) -> Result<SystemTime, String> {
+    if let Ok(number) = value.parse::<f64>() {
+        let secs = if number.abs() >= MILLIS_ABOVE { number / 1000.0 } else { number };
+        return Duration::try_from_secs_f64(secs)
+            .map(|since| UNIX_EPOCH + since)
+            .map_err(|_| format!("Timestamp {value} is before 1970"));
+    }
+    parse_date_time(value).ok_or_else(|| format!("Unreadable timestamp '{value}'"))
+}
+
+/// "YYYY-MM-DD[ T]HH:MM[:SS[.fff]]" with an optional "Z" or "±HH[:]MM"
+fn parse_date_time(value: &str) -> Option<SystemTime> {
+    let number = |text: &str| -> Option<i64> {
+        (!text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| text.parse().ok())
+            .flatten()
+    };
+    let (date, time) = value.split_once([' ', 'T'])?;
+    let mut date = date.split('-');
+    let (year, month, day) = (number(date.next()?)?, number(date.next()?)?, number(date.next()?)?);
+    if date.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Split off the offset, then the fraction of a second
+    let (time, offset_secs) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else if let Some(at) = time.rfind(['+', '-']) {
+        let (time, offset) = time.split_at(at);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let digits = offset[1..].replace(':', "");
+        if digits.len() != 4 || !digits.is_ascii() {
+            return None;
+        }
+        let (hours, minutes) = (number(&digits[..2])?, number(&digits[2..])?);
+        (time, sign * (hours * 3600 + minutes * 60))
+    } else {
+        (time, 0)
+    };
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut time = time.split(':');
+    let hours = number(time.next()?)?;
+    let minutes = number(time.next()?)?;
+    let seconds = time.next().map(number).unwrap_or(Some(0))?;
+    if time.next().is_some() || hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+    let millis = match fraction {
+        "" => 0,
+        fraction => {
+            number(fraction)?;
+            let digits = fraction.bytes().chain(*b"000").take(3);
+            digits.fold(0, |millis, digit| millis * 10 + u64::from(digit - b'0'))
+        }
+    };
+
+    let secs = days_from_civil(year, month, day) * 86_400 + hours * 3600 + minutes * 60 + seconds
+        - offset_secs;
+    let secs = u64::try_from(secs).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_millis(millis))
+}
+
+/// A status as other tools write it
+fn parse_status(value: &str) -> Result<MonitorStatus, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "up" | "ok" | "online" | "success" | "healthy" | "1" | "true" => Ok(MonitorStatus::Up),
+        "down" | "fail" | "failed" | "failure" | "error" | "offline" | "0" | "false" => {
+            Ok(MonitorStatus::Down)
+        }
+        "degraded" | "warning" | "warn" | "slow" | "pending" => Ok(MonitorStatus::Degraded),
+        _ => Err(format!("Unknown status '{value}'")),
+    }
+}
+
+/// Milliseconds, possibly with an "ms" suffix and a fraction
+fn parse_latency(value: &str) -> Result<u64, String> {
+    let number = value.strip_suffix("ms").unwrap_or(value).trim();
+    match number.parse::<f64>() {
+        Ok(ms) if ms.is_finite() && ms >= 0.0 => Ok(ms.round() as u64),
+        _ => Err(format!("Unreadable latency '{value}'")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(parse_timestamp("1706702400"), Ok(at(1_706_702_400)));
+        assert_eq!(parse_timestamp("1706702400000"), Ok(at(1_706_702_400)));
+        assert_eq!(parse_timestamp("2024-01-31 12:00:00"), Ok(at(1_706_702_400)));
+        assert_eq!(parse_timestamp("2024-01-31T12:00Z"), Ok(at(1_706_702_400)));
+        assert_eq!(parse_timestamp("2024-01-31T14:00:00+02:00"), Ok(at(1_706_702_400)));
+        assert_eq!(parse_timestamp("2000-02-29 23:59:59"), Ok(at(951_868_799)));
+        assert_eq!(
+            parse_timestamp("2024-01-31 12:00:00.250"),
+            Ok(at(1_706_702_400) + Duration::from_millis(250))
+        );
+        assert!(parse_timestamp("2024-13-01 00:00").is_err());
+        assert!(parse_timestamp("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let csv = "\u{feff}Time,Status,Response Time,Note\r\n2024-01-31 12:00:00,UP,120.4,\"fine, \
+                   really\"\r\n2024-01-31 12:01:00,down,,\"multi\nline\"\r\n2024-01-31 \
+                   12:02:00,maybe,80,\r\n\r\n2024-01-31 12:03:00,ok,95ms,\r\n";
+        let monitor = Uuid::new_v4();
+        let columns =
+            ColumnNames { latency: Some("response time".to_string()), ..Default::default() };
+        let import = parse_csv(csv, monitor, &columns, SystemTime::now()).unwrap();
+
+        let statuses: Vec<_> = import.results.iter().map(|r| (r.status, r.latency_ms)).collect();
+        assert_eq!(
+            statuses,
+            [
+                (MonitorStatus::Up, Some(120)),
+                (MonitorStatus::Down, None),
+                (MonitorStatus::Up, Some(95))
+            ]
+        );
+        assert!(import.results.iter().all(|r| r.monitor_uuid == monitor));
+        assert_eq!(import.skipped, [(5, "Unknown status 'maybe'".to_string())]);
+
+        assert!(
+            parse_csv("when,up\n", monitor, &ColumnNames::default(), SystemTime::now()).is_err()
+        );
+        let columns = ColumnNames { status: Some("health".to_string()), ..Default::default() };
+        assert!(parse_csv(csv, monitor, &columns, SystemTime::now()).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+/// Write-ahead journal for in-flight orchestrator state
+///
+/// Work that is not in the database yet is appended to a JSON-lines file before
+/// it starts and marked done once it is stored: check results on their way from
+/// the scheduler through the result pipeline, helper assignments being accepted
+/// and agreement buckets being saved. Records are written without fsync, so
+/// they survive the process crashing but not necessarily the machine.
+///
+/// On startup `Journal::open` returns whatever the previous run left
+/// unfinished and `recover` reconciles it: results missing from the database go
+/// back through the pipeline, which stores them and shares them with peers
+/// again, helper assignments that are still valid are stored, and agreement
+/// buckets are saved. Entries that fail to recover stay in the journal for the
+/// next start.
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::database::models::{HelperAssignment, ResultAgreement};
+use crate::monitoring::CheckResult;
+use crate::queue::QueueSender;
+
+/// Number of records after which the file is rewritten with only pending entries
+const COMPACT_AFTER: usize = 1000;
+
+/// In-flight work recorded in the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// A check result that is not stored yet
+    Result(CheckResult),
+    /// A helper assignment we accepted but did not store yet
+    Assignment(HelperAssignment),
+    /// An agreement bucket that is not saved yet
+    Agreement(ResultAgreement),
+}
+
+/// One line of the journal file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Record {
+    Begin { id: u64, entry: Box<JournalEntry> },
+    Done { id: u64 },
+}
+
+struct JournalInner {
+    file: File,
+    next_id: u64,
+    pending: BTreeMap<u64, JournalEntry>,
+    /// Records written since the file was last compacted
+    records: usize,
+}
+
+/// Append-only journal of in-flight work
+pub struct Journal {
+    path: PathBuf,
+    inner: Mutex<JournalInner>,
+}
+
+impl Journal {
+    /// Open the journal at `path`, returning the entries a previous run left
+    /// unfinished; they stay pending until recovered
+    pub fn open(path: &Path) -> Result<(Self, Vec<(u64, JournalEntry)>)> {
+        let pending = match File::open(path) {
+            Ok(file) => read_pending(BufReader::new(file)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let file = rewrite(path, &pending)?;
+        let next_id = pending.keys().next_back().map(|id| id + 1).unwrap_or(0);
+        let unfinished = pending.iter().map(|(id, entry)| (*id, entry.clone())).collect();
+        let journal = Self {
+            path: path.to_path_buf(),
+            inner: Mutex::new(JournalInner { file, next_id, pending, records: 0 }),
+        };
+
+        Ok((journal, unfinished))
+    }
+
+    /// Record work that is about to start, returning its journal ID
+    pub fn begin(&self, entry: JournalEntry) -> u64 {
+        let mut inner = self.inner.lock().unwrap();
+        let id = inner.next_id;
+        inner.next_id += 1;
+
+        self.append(&mut inner, &Record::Begin { id, entry: Box::new(entry.clone()) });
+        inner.pending.insert(id, entry);
+        id
+    }
+
+    /// Mark work as stored
+    pub fn done(&self, id: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.pending.remove(&id).is_none() {
+            return;
+        }
+        self.append(&mut inner, &Record::Done { id });
+
+        if inner.records >= COMPACT_AFTER {
+            match rewrite(&self.path, &inner.pending) {
+                Ok(file) => {
+                    inner.file = file;
+                    inner.records = 0;
+                }
+                Err(e) => warn!("Failed to compact journal {}: {}", self.path.display(), e),
+            }
+        }
+    }
+
+    /// Mark a check result as stored
+    pub fn result_done(&self, monitor_id: Uuid, timestamp: SystemTime) {
+        let id = self.inner.lock().unwrap().pending.iter().find_map(|(id, entry)| match entry {
+            JournalEntry::Result(result)
+                if result.monitor_id == monitor_id && result.timestamp == timestamp =>
+            {
+                Some(*id)
+            }
+            _ => None,
+        });
+
+        if let Some(id) = id {
+            self.done(id);
+        }
+    }
+
+    fn append(&self, inner: &mut JournalInner, record: &Record) {
+        let written = serde_json::to_string(record)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| Ok(writeln!(inner.file, "{line}")?));
+        match written {
+            Ok(()) => inner.records += 1,
+            Err(e) => warn!("Failed to write journal {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+/// Entries begun but never marked done; a torn last line is skipped
+fn read_pending(reader: impl BufRead) -> BTreeMap<u64, JournalEntry> {
+    let mut pending = BTreeMap::new();
+
+    for line in reader.lines().map_while(Result::ok) {
+        match serde_json::from_str::<Record>(&line) {
+            Ok(Record::Begin { id, entry }) => {
+                pending.insert(id, *entry);
+            }
+            Ok(Record::Done { id }) => {
+                pending.remove(&id);
+            }
+            Err(_) => warn!("Skipping unreadable journal record"),
+        }
+    }
+
+    pending
+}
+
+/// Replace the journal with only the pending entries, returning the file to append to
+fn rewrite(path: &Path, pending: &BTreeMap<u64, JournalEntry>) -> Result<File> {
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut tmp = File::create(&tmp_path)?;
+        for (id, entry) in pending {
+            let record = Record::Begin { id: *id, entry: Box::new(entry.clone()) };
+            writeln!(tmp, "{}", serde_json::to_string(&record)?)?;
+        }
+        tmp.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)?;
+
+    Ok(OpenOptions::new().append(true).open(path)?)
+}
+
+/// What the recovery pass did
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RecoverySummary {
+    pub results_replayed: usize,
+    pub assignments_restored: usize,
+    pub agreements_saved: usize,
+    /// Entries already in the database or no longer valid
+    pub skipped: usize,
+    /// Entries left in the journal for the next start
+    pub failed: usize,
+}
+
+/// Reconcile entries a previous run left unfinished with the database,
+/// replaying missing results through `result_tx`
+pub async fn recover(
+    journal: &Journal,
+    entries: Vec<(u64, JournalEntry)>,
+    database: &dyn Database,
+    result_tx: &QueueSender<CheckResult>,
+) -> RecoverySummary {
+    let mut summary = RecoverySummary::default();
+    if entries.is_empty() {
+        return summary;
+    }
+
+    for (id, entry) in entries {
+        match recover_entry(entry, database, result_tx, &mut summary).await {
+            Ok(true) => journal.done(id),
+            // Replayed results are marked done by the pipeline once stored
+            Ok(false) => {}
+            Err(e) => {
+                warn!("Failed to recover journal entry {}: {}", id, e);
+                summary.failed += 1;
+            }
+        }
+    }
+
+    info!(
+        "Journal recovery: {} result(s) replayed, {} assignment(s) and {} agreement(s) restored, \
+         {} skipped, {} failed",
+        summary.results_replayed,
+        summary.assignments_restored,
+        summary.agreements_saved,
+        summary.skipped,
+        summary.failed
+    );
+    summary
+}
+
+async fn recover_entry(
+    entry: JournalEntry,
+    database: &dyn Database,
+    result_tx: &QueueSender<CheckResult>,
+    summary: &mut RecoverySummary,
+) -> Result<bool> {
+    match entry {
+        JournalEntry::Result(result) => {
+            if database
+                .has_result(result.monitor_id, &result.peer_id, result.timestamp)
+                .await?
+            {
+                summary.skipped += 1;
+            } else {
+                result_tx.send(result).await?;
+                summary.results_replayed += 1;
+                return Ok(false);
+            }
+        }
+        JournalEntry::Assignment(assignment) => {
+            let stored = database
+                .get_helper_assignments(assignment.role)
+                .await?
+                .iter()
+                .any(|stored| stored.assignment_id == assignment.assignment_id);
+            if stored || assignment.is_expired(SystemTime::now()) {
+                summary.skipped += 1;
+            } else {
+                database.save_helper_assignment(&assignment).await?;
+                summary.assignments_restored += 1;
+            }
+        }
+        JournalEntry::Agreement(agreement) => {
+            database.save_result_agreement(&agreement).await?;
+            summary.agreements_saved += 1;
+        }
+    }
+
+    Ok(true)
+}
+
+/// Journal location from `UPPE_JOURNAL_PATH`, next to the keypair by default
+pub fn journal_path() -> PathBuf {
+    std::env::var("UPPE_JOURNAL_PATH")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("uppe_journal.jsonl"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    fn result(secs: u64) -> CheckResult {
+        let mut result = CheckResult::new(
+            Uuid::new_v4(),
+            "https://example.com".to_string(),
+            "local".to_string(),
+        );
+        result.timestamp = UNIX_EPOCH + std::time::Duration::from_secs(secs);
+        result
+    }
+
+    #[test]
+    fn test_journal_keeps_unfinished_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+
+        let (journal, unfinished) = Journal::open(&path).unwrap();
+        assert!(unfinished.is_empty());
+
+        let stored = result(100);
+        let lost = result(200);
+        journal.begin(JournalEntry::Result(stored.clone()));
+        journal.begin(JournalEntry::Result(lost.clone()));
+        journal.result_done(stored.monitor_id, stored.timestamp);
+        drop(journal);
+
+        // A torn write at the end of the file is skipped
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{{\"op\":\"beg").unwrap();
+
+        let (journal, unfinished) = Journal::open(&path).unwrap();
+        assert_eq!(unfinished.len(), 1);
+        let (id, JournalEntry::Result(recovered)) = &unfinished[0] else {
+            panic!("expected a result entry");
+        };
+        assert_eq!(recovered.monitor_id, lost.monitor_id);
+
+        // New entries don't reuse the pending entry's ID
+        assert!(journal.begin(JournalEntry::Result(result(300))) > *id);
+        journal.done(*id);
+        drop(journal);
+
+        let (_, unfinished) = Journal::open(&path).unwrap();
+        assert_eq!(unfinished.len(), 1);
+    }
+}
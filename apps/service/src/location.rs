@@ -1,16 +1,31 @@
 use crate::config::LocationPrivacy;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::Path;
 use std::sync::{Arc, OnceLock, RwLock};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 static LOCATION_CACHE: OnceLock<Arc<RwLock<LocationCache>>> = OnceLock::new();
 
+/// Local GeoLite2 City database, if one was configured
+static GEOIP_READER: OnceLock<maxminddb::Reader<Vec<u8>>> = OnceLock::new();
+
+/// How long a peer's location claim is trusted after it was issued
+const LOCATION_CLAIM_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// Allowed clock skew for claims issued "in the future"
+const LOCATION_CLAIM_MAX_SKEW: Duration = Duration::from_secs(5 * 60);
+
 struct LocationCache {
     location: Location,
     privacy_level: LocationPrivacy,
     last_update: Instant,
     update_interval: Duration,
+    /// Whether the region may be inferred from round trips; not when the
+    /// location was configured or tracking is disabled
+    infer: bool,
+    /// Region inferred from round trips, used while the location is unknown
+    inferred_region: Option<String>,
 }
 
 /// Response from ip-api.com geolocation service
@@ -40,6 +55,20 @@ impl Location {
         Self { city, country, region }
     }
 
+    /// Create a manually configured location, deriving the region from the
+    /// country when it is not given
+    pub fn manual(city: Option<String>, country: Option<String>, region: Option<String>) -> Self {
+        let country = country.map(|cc| cc.trim().to_uppercase()).filter(|cc| !cc.is_empty());
+        let region = region
+            .or_else(|| country.as_ref().map(|cc| Location::region_from_country(cc).to_string()));
+        Self { city, country, region }
+    }
+
+    /// Whether the location says anything beyond "Unknown"
+    pub fn is_known(&self) -> bool {
+        self.city.is_some() || self.country.is_some()
+    }
+
     /// Create unknown/unconfigured location
     pub fn unknown() -> Self {
         Self { city: None, country: None, region: Some("Unknown".to_string()) }
@@ -112,8 +141,117 @@ impl Location {
     }
 }
 
-/// Fetch location from IP geolocation API
+/// Round trip in milliseconds below which a reference point is taken to be in
+/// our own region
+const NEARBY_RTT_MS: u64 = 40;
+
+/// Well-known hosts and their regions, timed to infer ours when no other
+/// anchors are configured
+pub const DEFAULT_REGION_ANCHORS: [(&str, &str); 7] = [
+    ("ec2.us-east-1.amazonaws.com:443", "North America"),
+    ("ec2.sa-east-1.amazonaws.com:443", "South America"),
+    ("ec2.eu-central-1.amazonaws.com:443", "Europe"),
+    ("ec2.ap-northeast-1.amazonaws.com:443", "Asia"),
+    ("ec2.ap-southeast-2.amazonaws.com:443", "Oceania"),
+    ("ec2.me-central-1.amazonaws.com:443", "Middle East"),
+    ("ec2.af-south-1.amazonaws.com:443", "Africa"),
+];
+
+/// Lowest round trips plausible between regions an ocean apart, in
+/// milliseconds; set below what the shortest cable routes allow
+const REGION_RTT_FLOORS: [(&str, &str, u64); 17] = [
+    ("North America", "Europe", 50),
+    ("North America", "Asia", 80),
+    ("North America", "Oceania", 120),
+    ("North America", "Middle East", 100),
+    ("North America", "Africa", 60),
+    ("South America", "Europe", 50),
+    ("South America", "Asia", 200),
+    ("South America", "Oceania", 150),
+    ("South America", "Middle East", 120),
+    ("South America", "Africa", 60),
+    ("Europe", "Asia", 60),
+    ("Europe", "Oceania", 200),
+    ("Asia", "Oceania", 40),
+    ("Middle East", "Oceania", 80),
+    ("Africa", "Oceania", 120),
+    ("Africa", "Asia", 40),
+    ("Middle East", "Asia", 20),
+];
+
+/// Lowest round trip in milliseconds a node in one region can have to a
+/// node in another
+///
+/// Used to catch location claims contradicted by how quickly a peer answers:
+/// a peer claiming another continent cannot answer faster than light allows.
+/// Neighbouring, unknown or identical regions have no floor.
+pub fn min_rtt_ms(from_region: &str, to_region: &str) -> u64 {
+    REGION_RTT_FLOORS
+        .iter()
+        .find(|(a, b, _)| {
+            (from_region.eq_ignore_ascii_case(a) && to_region.eq_ignore_ascii_case(b))
+                || (from_region.eq_ignore_ascii_case(b) && to_region.eq_ignore_ascii_case(a))
+        })
+        .map_or(0, |(_, _, ms)| *ms)
+}
+
+/// Coarse region estimate from round trips to reference points with a known
+/// region, given as `(region, rtt_ms)`
+///
+/// The region of the closest reference point is taken when it answered within
+/// `NEARBY_RTT_MS` and no other round trip is faster than the floors between
+/// regions allow from there; otherwise there is no estimate.
+pub fn infer_region(samples: &[(String, u64)]) -> Option<String> {
+    let samples: Vec<&(String, u64)> = samples
+        .iter()
+        .filter(|(region, _)| !region.eq_ignore_ascii_case("Unknown"))
+        .collect();
+    let (region, rtt_ms) = samples.iter().min_by_key(|(_, rtt_ms)| *rtt_ms)?;
+    if *rtt_ms > NEARBY_RTT_MS {
+        return None;
+    }
+    samples
+        .iter()
+        .all(|(other, other_rtt_ms)| *other_rtt_ms >= min_rtt_ms(region, other))
+        .then(|| region.clone())
+}
+
+/// Open a GeoLite2 City database for local lookups
+///
+/// Once opened, location updates only ask a public service for this node's
+/// address and resolve the location locally.
+pub fn init_geoip(path: &Path) -> Result<()> {
+    let reader = maxminddb::Reader::open_readfile(path)?;
+    let _ = GEOIP_READER.set(reader);
+    Ok(())
+}
+
+/// Look up an address in the GeoLite2 database
+fn lookup_geoip(reader: &maxminddb::Reader<Vec<u8>>, ip: IpAddr) -> Result<Location> {
+    let record: maxminddb::geoip2::City = reader.lookup(ip)?;
+
+    let city = record
+        .city
+        .and_then(|city| city.names)
+        .and_then(|names| names.get("en").map(|name| name.to_string()));
+    let country = record.country.and_then(|country| country.iso_code).map(str::to_string);
+    let region = country.as_ref().map(|cc| Location::region_from_country(cc).to_string());
+
+    Ok(Location::new(city, country, region))
+}
+
+/// Fetch this node's public address
+async fn fetch_public_ip() -> Result<IpAddr> {
+    let ip = reqwest::get("http://api.ipify.org").await?.text().await?;
+    Ok(ip.trim().parse()?)
+}
+
+/// Fetch location, preferring the local GeoLite2 database over the IP API
 async fn fetch_location_from_ip() -> Result<Location> {
+    if let Some(reader) = GEOIP_READER.get() {
+        return lookup_geoip(reader, fetch_public_ip().await?);
+    }
+
     // Use ip-api.com - free, no API key required, 45 requests/minute
     let response =
         reqwest::get("http://ip-api.com/json/?fields=status,city,countryCode,regionName")
@@ -142,6 +280,8 @@ pub fn init_location_cache(update_interval_secs: u64, privacy_level: LocationPri
         privacy_level,
         last_update: Instant::now() - Duration::from_secs(update_interval_secs + 1), /* Force immediate update */
         update_interval: Duration::from_secs(update_interval_secs),
+        infer: true,
+        inferred_region: None,
     };
 
     let _ = LOCATION_CACHE.set(Arc::new(RwLock::new(cache)));
@@ -155,6 +295,7 @@ pub fn init_location(location: Location) {
     {
         cache.location = location;
         cache.last_update = Instant::now();
+        cache.infer = false;
     }
 }
 
@@ -166,17 +307,42 @@ pub fn get_location() -> Location {
             privacy_level: LocationPrivacy::Full,
             last_update: Instant::now(),
             update_interval: Duration::from_secs(0),
+            infer: false,
+            inferred_region: None,
         }))
     });
 
     // Try to read current location
     if let Ok(cache_guard) = cache.read() {
+        if !cache_guard.location.is_known()
+            && let Some(region) = &cache_guard.inferred_region
+        {
+            return Location::new(None, None, Some(region.clone()));
+        }
         return cache_guard.location.clone();
     }
 
     Location::unknown()
 }
 
+/// Whether the region should be inferred from round trips: neither GeoIP nor
+/// the IP API told the location, and it was not configured
+pub fn needs_inferred_region() -> bool {
+    LOCATION_CACHE
+        .get()
+        .and_then(|cache| cache.read().ok().map(|cache| cache.infer && !cache.location.is_known()))
+        .unwrap_or(false)
+}
+
+/// Use an inferred region while the location is unknown; `None` clears it
+pub fn set_inferred_region(region: Option<String>) {
+    if let Some(cache) = LOCATION_CACHE.get()
+        && let Ok(mut cache) = cache.write()
+    {
+        cache.inferred_region = region;
+    }
+}
+
 /// Update location from IP (non-blocking, spawns background task)
 pub fn update_location_from_ip() {
     if let Some(cache) = LOCATION_CACHE.get() {
@@ -215,6 +381,28 @@ pub fn check_and_update_location() {
     }
 }
 
+/// A node's location, signed with its key so peers can attribute results to it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LocationClaim {
+    /// Hex-encoded public key of the node making the claim
+    pub peer_id: String,
+    /// Location after the node's privacy setting was applied
+    pub location: Location,
+    /// Unix timestamp the claim was made at
+    pub issued_at: u64,
+    /// Ed25519 signature over the canonical claim
+    pub signature: Vec<u8>,
+}
+
+impl LocationClaim {
+    /// Whether the claim was issued recently enough to trust at `now`
+    pub fn is_fresh(&self, now: SystemTime) -> bool {
+        let now = now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        self.issued_at <= now + LOCATION_CLAIM_MAX_SKEW.as_secs()
+            && now.saturating_sub(self.issued_at) <= LOCATION_CLAIM_MAX_AGE.as_secs()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -243,4 +431,58 @@ mod tests {
         assert_eq!(Location::region_from_country("AU"), "Oceania");
         assert_eq!(Location::region_from_country("ZZ"), "Other");
     }
+
+    #[test]
+    fn test_min_rtt_between_regions() {
+        assert_eq!(min_rtt_ms("Europe", "Oceania"), 200);
+        assert_eq!(min_rtt_ms("oceania", "europe"), 200);
+        assert_eq!(min_rtt_ms("Europe", "Europe"), 0);
+        assert_eq!(min_rtt_ms("Europe", "Africa"), 0);
+        assert_eq!(min_rtt_ms("Unknown", "Asia"), 0);
+    }
+
+    #[test]
+    fn test_infer_region() {
+        let samples = |samples: &[(&str, u64)]| -> Vec<(String, u64)> {
+            samples.iter().map(|(region, rtt)| (region.to_string(), *rtt)).collect()
+        };
+
+        assert_eq!(
+            infer_region(&samples(&[("Europe", 12), ("North America", 95), ("Asia", 240)])),
+            Some("Europe".to_string())
+        );
+        // Nothing close enough, or round trips that contradict each other
+        assert_eq!(infer_region(&samples(&[("Europe", 80), ("Asia", 150)])), None);
+        assert_eq!(infer_region(&samples(&[("Europe", 10), ("Oceania", 30)])), None);
+        assert_eq!(infer_region(&samples(&[("Unknown", 5)])), None);
+        assert_eq!(infer_region(&[]), None);
+    }
+
+    #[test]
+    fn test_manual_location_derives_region() {
+        let loc = Location::manual(None, Some("de".to_string()), None);
+        assert_eq!(loc.country.as_deref(), Some("DE"));
+        assert_eq!(loc.region.as_deref(), Some("Europe"));
+
+        let loc = Location::manual(None, Some("DE".to_string()), Some("Berlin".to_string()));
+        assert_eq!(loc.region.as_deref(), Some("Berlin"));
+    }
+
+    #[test]
+    fn test_location_claim_freshness() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let mut claim = LocationClaim {
+            peer_id: "peer".to_string(),
+            location: Location::unknown(),
+            issued_at: 1_000_000,
+            signature: Vec::new(),
+        };
+        assert!(claim.is_fresh(now));
+
+        claim.issued_at = 1_000_000 - LOCATION_CLAIM_MAX_AGE.as_secs() - 1;
+        assert!(!claim.is_fresh(now));
+
+        claim.issued_at = 1_000_000 + LOCATION_CLAIM_MAX_SKEW.as_secs() + 1;
+        assert!(!claim.is_fresh(now));
+    }
 }
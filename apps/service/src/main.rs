@@ -3,42 +3,346 @@ use std::path;
 
 use clap::{Parser, Subcommand, crate_authors, crate_version};
 
+mod archive;
+mod bus;
+mod cache;
+mod civil;
 mod config;
 mod crypto;
 mod database;
+mod export;
+mod import;
+mod journal;
 mod location;
 mod models;
 mod monitoring;
+mod notifications;
 mod orchestrator;
 mod p2p;
+mod policy;
 mod pool;
+mod queue;
+mod redact;
+mod report;
+mod status;
 mod tui;
 mod validation;
 
 #[derive(Subcommand, Debug)]
 enum MonitorCmd {
     /// List all monitors
-    List,
+    List {
+        /// Only list monitors with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
     /// Add a new monitor
     Add {
         /// Name of the monitor
         #[arg(long)]
         name: String,
-        /// Target (URL/host)
+        /// Target (URL/host); for heartbeat monitors the token they are pinged
+        /// with, generated when omitted
         #[arg(long)]
-        target: String,
-        /// Check type (http, https, tcp, icmp)
+        target: Option<String>,
+        /// Check type (http, https, tcp, icmp, cert_expiry, heartbeat)
         #[arg(long, default_value = "http")]
         check_type: String,
-        /// Interval in seconds
+        /// Interval in seconds; heartbeat monitors expect a ping this often
         #[arg(long, default_value_t = 30)]
         interval: u64,
         /// Timeout in seconds
         #[arg(long, default_value_t = 10)]
         timeout: u64,
+        /// Seconds a heartbeat ping may be late before the monitor is down
+        #[arg(long, default_value_t = 60)]
+        grace: u64,
+        /// Days of results to keep instead of the global retention (0 keeps everything)
+        #[arg(long)]
+        retention_days: Option<u64>,
+        /// Who results are shared with (public, private)
+        #[arg(long, default_value = "public")]
+        visibility: database::models::MonitorVisibility,
+        /// Comma-separated tags grouping the monitor (e.g. prod,eu-west)
+        #[arg(long, default_value = "")]
+        tags: String,
+    },
+    /// Replace a monitor's tags
+    Tags {
+        /// UUID of the monitor
+        #[arg(long)]
+        uuid: uuid::Uuid,
+        /// Comma-separated tags; empty clears them
+        #[arg(long)]
+        tags: String,
+    },
+    /// Set or clear a monitor's result retention
+    Retention {
+        /// UUID of the monitor
+        #[arg(long)]
+        uuid: uuid::Uuid,
+        /// Days of results to keep (0 keeps everything); omit to use the global retention
+        #[arg(long)]
+        days: Option<u64>,
+    },
+    /// Make a monitor public or private; going private retracts shared results
+    Visibility {
+        /// UUID of the monitor
+        #[arg(long)]
+        uuid: uuid::Uuid,
+        /// New visibility (public, private)
+        #[arg(long)]
+        visibility: database::models::MonitorVisibility,
+    },
+    /// Probe a public monitor once from several peers; the running service
+    /// sends the probes and stores the aggregated result
+    Probe {
+        /// UUID of the monitor
+        #[arg(long)]
+        uuid: uuid::Uuid,
+        /// Number of peers to ask
+        #[arg(long, default_value_t = 3)]
+        peers: u32,
+        /// Only ask peers in this country or region
+        #[arg(long)]
+        region: Option<String>,
+    },
+    /// Show recent multi-vantage probes of a monitor
+    Probes {
+        /// UUID of the monitor
+        #[arg(long)]
+        uuid: uuid::Uuid,
+        /// Maximum number of probes to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum NotifyCmd {
+    /// List notification channels and the outcome of their last test
+    List,
+    /// Add a notification channel
+    Add {
+        /// Name of the channel
+        #[arg(long)]
+        name: String,
+        /// Service behind the URL (webhook, slack, discord, telegram, ntfy,
+        /// gotify); defaults to webhook, or the service of an Apprise URL
+        #[arg(long)]
+        kind: Option<database::models::NotificationKind>,
+        /// URL notifications are posted to, or an Apprise URL such as
+        /// discord://id/token, slack://A/B/C, tgram://bot_token/chat_id,
+        /// ntfys://host/topic or gotifys://host/app_token
+        #[arg(long)]
+        url: String,
+    },
+    /// Remove a notification channel
+    Remove {
+        /// UUID of the channel
+        #[arg(long)]
+        uuid: uuid::Uuid,
+    },
+    /// Send a test notification to a channel
+    Test {
+        /// UUID of the channel
+        #[arg(long)]
+        uuid: uuid::Uuid,
+    },
+    /// List notification routes
+    Routes,
+    /// Set the notification route of a scope, replacing it
+    Route {
+        /// Scope of the route: default, tag:<tag> or monitor:<uuid>
+        #[arg(long)]
+        scope: database::models::RouteScope,
+        /// Channel alerted on status changes; none inherits the channels
+        #[arg(long = "channel")]
+        channels: Vec<uuid::Uuid>,
+        /// Escalate outages lasting this many minutes; 0 turns escalation off
+        #[arg(long)]
+        escalate_after: Option<u64>,
+        /// Channel escalations are sent to
+        #[arg(long = "escalate-to")]
+        escalate_to: Vec<uuid::Uuid>,
+    },
+    /// Remove the notification route of a scope so it inherits everything
+    Unroute {
+        /// Scope of the route: default, tag:<tag> or monitor:<uuid>
+        #[arg(long)]
+        scope: database::models::RouteScope,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CommunityCmd {
+    /// List community domains, who takes part and whether we do
+    List,
+    /// Join monitoring a domain, adding a public monitor unless one checks it
+    Join {
+        /// Host or URL of the domain; a bare host is checked over HTTPS
+        #[arg(long)]
+        target: String,
+    },
+    /// Leave monitoring a domain, pausing the monitor joining added
+    Leave {
+        #[arg(long)]
+        host: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ArchiveCmd {
+    /// Re-import archived results of a time range; they are kept for a week
+    Restore {
+        /// Start of the range, Unix seconds
+        #[arg(long)]
+        from: i64,
+        /// End of the range, Unix seconds
+        #[arg(long)]
+        to: i64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ImportCmd {
+    /// Import a monitor's history from a CSV export of another tool; rows with
+    /// the timestamp of a stored result are left out, and results older than
+    /// the monitor's retention go with the next sweep
+    Results {
+        /// CSV file with a header row
+        file: path::PathBuf,
+        /// UUID of the monitor the results belong to
+        #[arg(long)]
+        uuid: uuid::Uuid,
+        /// Column holding the timestamp; defaults to timestamp, time or date
+        #[arg(long)]
+        timestamp_column: Option<String>,
+        /// Column holding the status (up/down/degraded, ok/fail, 1/0, ...);
+        /// defaults to status or state
+        #[arg(long)]
+        status_column: Option<String>,
+        /// Column holding the latency in milliseconds; defaults to latency_ms,
+        /// latency or response_time if present
+        #[arg(long)]
+        latency_column: Option<String>,
+        /// Only report what would be imported
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum KeypairCmd {
+    /// Encrypt the keypair with a passphrase, or change it; the service reads
+    /// the passphrase from UPPE_KEYPAIR_PASSPHRASE or prompts for it
+    Passphrase {
+        /// Store the keypair in plaintext again
+        #[arg(long)]
+        remove: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PageCmd {
+    /// List status pages and their monitors
+    List,
+    /// Add a status page
+    Add {
+        /// Slug the page is served under (lowercase letters, digits, dashes)
+        #[arg(long)]
+        slug: String,
+        /// Title of the page
+        #[arg(long)]
+        title: String,
+        /// Description shown below the title
+        #[arg(long, default_value = "")]
+        description: String,
+    },
+    /// Show a monitor on a status page
+    Show {
+        /// Slug of the page
+        #[arg(long)]
+        slug: String,
+        /// UUID of the monitor
+        #[arg(long)]
+        uuid: uuid::Uuid,
+    },
+    /// Remove a monitor from a status page
+    Hide {
+        /// Slug of the page
+        #[arg(long)]
+        slug: String,
+        /// UUID of the monitor
+        #[arg(long)]
+        uuid: uuid::Uuid,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum MaintenanceCmd {
+    /// List maintenance windows that have not ended yet
+    List,
+    /// Declare a maintenance window
+    Add {
+        /// UUID of the monitor; leave out to cover every monitor
+        #[arg(long)]
+        uuid: Option<uuid::Uuid>,
+        /// Start of the window, Unix seconds; defaults to now
+        #[arg(long)]
+        start: Option<i64>,
+        /// Length of the window in minutes
+        #[arg(long)]
+        minutes: u64,
+        /// Why the monitor is under maintenance
+        #[arg(long, default_value = "")]
+        reason: String,
+    },
+    /// Remove a maintenance window
+    Remove {
+        /// UUID of the window
+        #[arg(long)]
+        uuid: uuid::Uuid,
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum FollowCmd {
+    /// List followed hosts and what peers currently see of them
+    List,
+    /// Follow a host; a URL or `host:port` is reduced to its host
+    Add {
+        #[arg(long)]
+        host: String,
+    },
+    /// Stop following a host
+    Remove {
+        #[arg(long)]
+        host: String,
+    },
+}
+
+#[cfg(feature = "chaos")]
+#[derive(Subcommand, Debug)]
+enum ChaosCmd {
+    /// Show the faults being injected
+    Show,
+    /// Inject faults; options left out keep their current value
+    Set {
+        /// Share of gossip messages from peers to drop
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        gossip_drop_percent: Option<u8>,
+        /// Delay to add before each result is stored
+        #[arg(long)]
+        db_write_delay_ms: Option<u64>,
+        /// Share of owner sync DHT lookups to fail
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        dht_failure_percent: Option<u8>,
+    },
+    /// Stop injecting faults
+    Off,
+}
+
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Run the Uppe. service (orchestrator)
@@ -52,6 +356,80 @@ enum Commands {
     },
     /// Launch interactive TUI
     Tui,
+    /// Show recent audit log events
+    Audit {
+        /// Only show events of this kind (e.g. signature_failure, rate_limited,
+        /// policy_rejected, admin_action, peer_banned)
+        #[arg(long)]
+        kind: Option<database::models::AuditKind>,
+        /// Maximum number of events to show
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Notification channel commands
+    Notify {
+        #[command(subcommand)]
+        cmd: NotifyCmd,
+    },
+    /// Status page commands; pages are served by the running service when
+    /// `[status_page]` has a bind address
+    Page {
+        #[command(subcommand)]
+        cmd: PageCmd,
+    },
+    /// Maintenance window commands; results checked during a window are
+    /// stored but not alerted about, shared or counted towards uptime
+    Maintenance {
+        #[command(subcommand)]
+        cmd: MaintenanceCmd,
+    },
+    /// Follow public targets without checking them, from the results peers
+    /// share about them
+    Follow {
+        #[command(subcommand)]
+        cmd: FollowCmd,
+    },
+    /// Monitor community domains together with other peers; the running
+    /// service announces joins and leaves within 30 seconds
+    Community {
+        #[command(subcommand)]
+        cmd: CommunityCmd,
+    },
+    /// Fault injection commands; the running service picks up changes within
+    /// 30 seconds
+    #[cfg(feature = "chaos")]
+    Chaos {
+        #[command(subcommand)]
+        cmd: ChaosCmd,
+    },
+    /// Result archive commands; the running service archives results past
+    /// their retention when `[archive]` has a bucket
+    Archive {
+        #[command(subcommand)]
+        cmd: ArchiveCmd,
+    },
+    /// Import history from other monitoring tools
+    Import {
+        #[command(subcommand)]
+        cmd: ImportCmd,
+    },
+    /// Print a summary of monitors, peers and open incidents; exits with
+    /// status 2 while an enabled monitor is down
+    Status {
+        /// Print the summary as JSON
+        #[arg(long, conflicts_with = "plain")]
+        json: bool,
+        /// Print the summary as plain text (the default)
+        #[arg(long)]
+        plain: bool,
+    },
+    /// Print a new random key for `[cluster] key`
+    ClusterKey,
+    /// Keypair commands; the keypair is read from `UPPE_KEYPAIR_PATH`
+    Keypair {
+        #[command(subcommand)]
+        cmd: KeypairCmd,
+    },
 }
 
 #[derive(Parser, Debug)]
@@ -71,13 +449,6 @@ struct Args {
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_target(false)
-        .with_thread_ids(false)
-        .with_level(true)
-        .init();
-
     let cli = Args::parse();
 
     if cli.version {
@@ -89,6 +460,14 @@ async fn main() -> anyhow::Result<()> {
     // Load configuration
     let cfg =
         config::Config::from_config(cli.config.as_ref()).expect("Failed to load configuration");
+    let config_path = config::Config::resolve_path(cli.config.as_ref()).ok();
+
+    // Initialize tracing, exporting spans and metrics if a collector is configured;
+    // logs would mix into the status output scripts parse
+    let _telemetry = match cli.command {
+        Some(Commands::Status { .. } | Commands::ClusterKey | Commands::Keypair { .. }) => None,
+        _ => logger::init_with_otlp(cfg.telemetry.otlp().as_ref()),
+    };
 
     // Initialize database pool - use shared database location
     // Default to shared/data/libsql.db in project root, using CARGO_MANIFEST_DIR when available
@@ -135,20 +514,23 @@ async fn main() -> anyhow::Result<()> {
 
     let db = libsql::Builder::new_local(&db_path).build().await?;
 
-    let manager = pool::LibsqlManager::new(db);
-    let pool: pool::LibsqlPool = deadpool::managed::Pool::builder(manager)
-        .config(deadpool::managed::PoolConfig::default())
-        .build()
-        .expect("Failed to build database pool");
+    let pool = pool::build(db, &cfg.database_pool).expect("Failed to build database pool");
 
     // Initialize location system
     let location_update_interval = cfg.preferences.location_update_interval_secs;
 
-    // Check if user has manually set location via environment variables
-    let manual_location = location::Location::new(
-        std::env::var("UPPE_LOCATION_CITY").ok(),
-        std::env::var("UPPE_LOCATION_COUNTRY").ok(),
-        std::env::var("UPPE_LOCATION_REGION").ok(),
+    // Check if user has manually set location via environment variables or config
+    let location_override = &cfg.preferences.location;
+    let manual_location = location::Location::manual(
+        std::env::var("UPPE_LOCATION_CITY")
+            .ok()
+            .or_else(|| location_override.city.clone()),
+        std::env::var("UPPE_LOCATION_COUNTRY")
+            .ok()
+            .or_else(|| location_override.country.clone()),
+        std::env::var("UPPE_LOCATION_REGION")
+            .ok()
+            .or_else(|| location_override.region.clone()),
     );
 
     let has_manual_location = manual_location.city.is_some()
@@ -170,6 +552,16 @@ async fn main() -> anyhow::Result<()> {
             location_update_interval,
             cfg.preferences.location_privacy
         );
+        if let Some(path) = &cfg.preferences.geoip_database {
+            match location::init_geoip(path) {
+                Ok(()) => tracing::info!("Using GeoLite2 database {}", path.display()),
+                Err(e) => tracing::warn!(
+                    "Failed to open GeoLite2 database {}, falling back to IP API: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
         location::init_location_cache(location_update_interval, cfg.preferences.location_privacy);
         location::update_location_from_ip(); // Trigger first update
     }
@@ -179,43 +571,71 @@ async fn main() -> anyhow::Result<()> {
             tracing::info!("Starting Uppe. service...");
             tracing::info!("P2P network enabled: {}", cfg.preferences.use_peerup_layer);
 
-            // Use LocalSet for P2P network (libp2p Swarm is !Send)
-            let local = tokio::task::LocalSet::new();
-            local
-                .run_until(async move { orchestrator::Orchestrator::start(cfg, pool).await })
-                .await?;
+            orchestrator::Orchestrator::start(cfg, config_path, pool).await?;
         }
         Commands::Migrate => {
             tracing::info!("Running database migrations...");
-            let conn = pool.get().await?;
-            database::initialize_database(&conn).await?;
+            database::open(&cfg.database, &cfg.database_pool, pool).await?;
             println!("Migrations completed.");
         }
         Commands::Monitor { cmd } => {
-            use database::{Database, DatabaseImpl};
-            let dbi = DatabaseImpl::new_from_pool(pool);
+            let dbi = database::open(&cfg.database, &cfg.database_pool, pool).await?;
             match cmd {
-                MonitorCmd::List => {
-                    let monitors = dbi.get_enabled_monitors().await?;
+                MonitorCmd::List { tag } => {
+                    let tag = tag.map(|tag| tag.trim_start_matches('#').to_lowercase());
+                    let monitors: Vec<_> = dbi
+                        .get_enabled_monitors()
+                        .await?
+                        .into_iter()
+                        .filter(|m| tag.as_ref().is_none_or(|tag| m.tags.contains(tag)))
+                        .collect();
                     if monitors.is_empty() {
                         println!("No monitors found.");
                     } else {
                         for m in monitors {
                             println!(
-                                "- {} [{}] -> {} (every {}s, timeout {}s)",
+                                "- {} [{}] -> {} (every {}s, timeout {}s){}{}",
                                 m.name,
                                 m.check_type,
                                 m.target,
                                 m.interval_seconds,
-                                m.timeout_seconds
+                                m.timeout_seconds,
+                                if m.visibility == database::models::MonitorVisibility::Private {
+                                    " (private)"
+                                } else {
+                                    ""
+                                },
+                                if m.tags.is_empty() {
+                                    String::new()
+                                } else {
+                                    format!(" #{}", m.tags.join(" #"))
+                                }
                             );
                         }
                     }
                 }
-                MonitorCmd::Add { name, target, check_type, interval, timeout } => {
+                MonitorCmd::Add {
+                    name,
+                    target,
+                    check_type,
+                    interval,
+                    timeout,
+                    grace,
+                    retention_days,
+                    visibility,
+                    tags,
+                } => {
                     // Validate inputs before creating monitor
                     use crate::validation::*;
 
+                    let heartbeat = check_type == database::models::Monitor::HEARTBEAT;
+                    let Some(target) = target
+                        .or_else(|| heartbeat.then(database::models::Monitor::heartbeat_token))
+                    else {
+                        eprintln!("Error: --target is required for {check_type} monitors");
+                        std::process::exit(1);
+                    };
+
                     let name_result = validate_monitor_name(&name);
                     if !name_result.is_valid {
                         eprintln!("Error: {}", name_result.error.unwrap_or_default());
@@ -240,29 +660,818 @@ async fn main() -> anyhow::Result<()> {
                         std::process::exit(1);
                     }
 
+                    let grace_result = validate_grace(grace);
+                    if !grace_result.is_valid {
+                        eprintln!("Error: {}", grace_result.error.unwrap_or_default());
+                        std::process::exit(1);
+                    }
+
+                    let tags = database::models::Monitor::parse_tags(&tags);
+                    let tags_result = validate_tags(&tags);
+                    if !tags_result.is_valid {
+                        eprintln!("Error: {}", tags_result.error.unwrap_or_default());
+                        std::process::exit(1);
+                    }
+
                     let mut monitor = database::models::Monitor::new(name, target, check_type);
                     monitor.interval_seconds = interval;
                     monitor.timeout_seconds = timeout;
+                    monitor.grace_seconds = grace;
+                    monitor.retention_days = retention_days;
+                    monitor.visibility = visibility;
+                    monitor.tags = tags;
                     let id = dbi.save_monitor(&monitor).await?;
+                    dbi.append_audit_event(&database::models::AuditEvent::admin(format!(
+                        "Added monitor '{}' ({}) via CLI",
+                        monitor.name, monitor.uuid
+                    )))
+                    .await?;
                     println!("Added monitor with id {} and uuid {}", id, monitor.uuid);
+                    if heartbeat {
+                        println!(
+                            "Ping it with: POST /api/v1/heartbeat/{} on the management API",
+                            monitor.target
+                        );
+                    }
+                }
+                MonitorCmd::Retention { uuid, days } => {
+                    let Some(mut monitor) = dbi.get_monitor_by_uuid(uuid).await? else {
+                        eprintln!("Error: No monitor with uuid {uuid}");
+                        std::process::exit(1);
+                    };
+
+                    monitor.retention_days = days;
+                    monitor.updated_at = std::time::SystemTime::now();
+                    dbi.save_monitor(&monitor).await?;
+
+                    let retention = match days {
+                        Some(0) => "keep all results".to_string(),
+                        Some(days) => format!("keep {days} days of results"),
+                        None => "use the global retention".to_string(),
+                    };
+                    dbi.append_audit_event(&database::models::AuditEvent::admin(format!(
+                        "Set monitor '{}' ({}) to {} via CLI",
+                        monitor.name, monitor.uuid, retention
+                    )))
+                    .await?;
+                    println!("Monitor {} will now {}", monitor.uuid, retention);
+                }
+                MonitorCmd::Tags { uuid, tags } => {
+                    let Some(mut monitor) = dbi.get_monitor_by_uuid(uuid).await? else {
+                        eprintln!("Error: No monitor with uuid {uuid}");
+                        std::process::exit(1);
+                    };
+                    let tags = database::models::Monitor::parse_tags(&tags);
+                    let tags_result = validation::validate_tags(&tags);
+                    if !tags_result.is_valid {
+                        eprintln!("Error: {}", tags_result.error.unwrap_or_default());
+                        std::process::exit(1);
+                    }
+
+                    monitor.tags = tags;
+                    monitor.updated_at = std::time::SystemTime::now();
+                    dbi.save_monitor(&monitor).await?;
+                    dbi.append_audit_event(&database::models::AuditEvent::admin(format!(
+                        "Set tags of monitor '{}' ({}) to [{}] via CLI",
+                        monitor.name,
+                        monitor.uuid,
+                        monitor.tags.join(", ")
+                    )))
+                    .await?;
+                    println!("Monitor {} tags: [{}]", monitor.uuid, monitor.tags.join(", "));
+                }
+                MonitorCmd::Visibility { uuid, visibility } => {
+                    let Some(mut monitor) = dbi.get_monitor_by_uuid(uuid).await? else {
+                        eprintln!("Error: No monitor with uuid {uuid}");
+                        std::process::exit(1);
+                    };
+                    if monitor.visibility == visibility {
+                        println!("Monitor {} is already {}", monitor.uuid, visibility);
+                        return Ok(());
+                    }
+
+                    monitor.visibility = visibility;
+                    monitor.updated_at = std::time::SystemTime::now();
+                    dbi.save_monitor(&monitor).await?;
+                    dbi.append_audit_event(&database::models::AuditEvent::admin(format!(
+                        "Made monitor '{}' ({}) {} via CLI",
+                        monitor.name, monitor.uuid, visibility
+                    )))
+                    .await?;
+
+                    match visibility {
+                        database::models::MonitorVisibility::Private => println!(
+                            "Monitor {} is now private; the running service retracts its shared \
+                             results",
+                            monitor.uuid
+                        ),
+                        database::models::MonitorVisibility::Public => {
+                            println!("Monitor {} is now public", monitor.uuid)
+                        }
+                    }
+                }
+                MonitorCmd::Probe { uuid, peers, region } => {
+                    let Some(monitor) = dbi.get_monitor_by_uuid(uuid).await? else {
+                        eprintln!("Error: No monitor with uuid {uuid}");
+                        std::process::exit(1);
+                    };
+                    if monitor.visibility == database::models::MonitorVisibility::Private {
+                        eprintln!("Error: Monitor {uuid} is private and cannot be probed by peers");
+                        std::process::exit(1);
+                    }
+                    if peers == 0 {
+                        eprintln!("Error: At least one peer is needed");
+                        std::process::exit(1);
+                    }
+
+                    let probe = database::models::MultiVantageResult::pending(uuid, peers, region);
+                    dbi.save_multi_vantage_result(&probe).await?;
+                    println!(
+                        "Queued probe {} of monitor {} from {} peer(s){}; the running service \
+                         sends it",
+                        probe.uuid,
+                        monitor.uuid,
+                        peers,
+                        probe.region.as_deref().map(|r| format!(" in {r}")).unwrap_or_default()
+                    );
+                }
+                MonitorCmd::Probes { uuid, limit } => {
+                    let probes = dbi.get_multi_vantage_results(uuid, limit).await?;
+                    if probes.is_empty() {
+                        println!("No probes found.");
+                    }
+                    for probe in probes {
+                        let timestamp = probe
+                            .requested_at
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .map(|d| d.as_secs())
+                            .unwrap_or(0);
+                        println!(
+                            "{} {} [{}] {}",
+                            timestamp,
+                            probe.uuid,
+                            probe.status.map(|s| s.to_string()).unwrap_or_else(|| "-".into()),
+                            probe.summary()
+                        );
+                        for vantage in &probe.vantages {
+                            println!(
+                                "    {} ({}): {}{}",
+                                vantage.peer_id,
+                                vantage.region.as_deref().unwrap_or("unknown location"),
+                                vantage.status,
+                                vantage
+                                    .latency_ms
+                                    .map(|ms| format!(" in {ms}ms"))
+                                    .or_else(|| vantage.error.as_ref().map(|e| format!(" ({e})")))
+                                    .unwrap_or_default()
+                            );
+                        }
+                    }
                 }
             }
         }
         Commands::Tui => {
             // Get peer ID and P2P status
-            let keypair_path = path::PathBuf::from("uppe_keypair.key");
-            let peer_id = if let Ok(kp) = crypto::load_or_generate_keypair(&keypair_path) {
+            let peer_id = if let Ok(kp) = crypto::load_or_generate_keypair(&crypto::keypair_path())
+            {
                 kp.public_key_hex()
             } else {
                 "unknown".to_string()
             };
             let p2p_enabled = cfg.preferences.use_peerup_layer;
 
-            // Use LocalSet for P2P network (libp2p Swarm is !Send)
-            let local = tokio::task::LocalSet::new();
-            local
-                .run_until(async move { tui::run_tui_with_p2p(pool, peer_id, p2p_enabled).await })
-                .await?;
+            let theme = tui::theme::Theme::from_config(&cfg.tui)?;
+            let bus = bus::BusSubscriber::connect(&cfg.zeromq)
+                .inspect_err(|e| tracing::warn!("TUI falls back to polling: {}", e))
+                .ok();
+            let db = database::open(&cfg.database, &cfg.database_pool, pool).await?;
+            tui::run_tui_with_p2p(db.as_ref(), peer_id, p2p_enabled, theme, bus).await?;
+        }
+        Commands::Audit { kind, limit } => {
+            let dbi = database::open(&cfg.database, &cfg.database_pool, pool).await?;
+
+            let events = dbi.get_audit_events(kind, limit).await?;
+            if events.is_empty() {
+                println!("No audit events found.");
+            }
+            for event in events {
+                let timestamp = event
+                    .timestamp
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                println!(
+                    "{} [{}] {}: {}",
+                    timestamp,
+                    event.kind,
+                    event.peer_id.as_deref().unwrap_or("-"),
+                    event.message
+                );
+            }
+        }
+        Commands::Status { json, plain: _ } => {
+            let dbi = database::open(&cfg.database, &cfg.database_pool, pool).await?;
+
+            let report = status::StatusReport::load(dbi.as_ref()).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", report.plain());
+            }
+            if report.any_down() {
+                std::process::exit(status::DOWN_EXIT_CODE);
+            }
+        }
+        Commands::Notify { cmd } => {
+            use database::models::{AuditEvent, NotificationChannel};
+            let dbi = database::open(&cfg.database, &cfg.database_pool, pool).await?;
+
+            match cmd {
+                NotifyCmd::List => {
+                    let channels = dbi.get_notification_channels().await?;
+                    if channels.is_empty() {
+                        println!("No notification channels found.");
+                    }
+                    for channel in channels {
+                        println!(
+                            "- {} {} [{}] -> {} (last test: {}){}",
+                            channel.uuid,
+                            channel.name,
+                            channel.kind,
+                            channel.target,
+                            channel.test_summary(),
+                            if channel.enabled { "" } else { " (disabled)" }
+                        );
+                    }
+                }
+                NotifyCmd::Add { name, kind, url } => {
+                    if name.trim().is_empty() {
+                        eprintln!("Error: Channel name cannot be empty");
+                        std::process::exit(1);
+                    }
+                    let (kind, url) = match notifications::parse_apprise(&url) {
+                        Ok(Some((apprise_kind, target))) => {
+                            if kind.is_some_and(|kind| kind != apprise_kind) {
+                                eprintln!("Error: The URL is for a {apprise_kind} channel");
+                                std::process::exit(1);
+                            }
+                            (apprise_kind, target)
+                        }
+                        Ok(None) => {
+                            (kind.unwrap_or(database::models::NotificationKind::Webhook), url)
+                        }
+                        Err(e) => {
+                            eprintln!("Error: {e}");
+                            std::process::exit(1);
+                        }
+                    };
+                    if let Err(e) = notifications::validate_target(&url) {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+
+                    let channel = NotificationChannel::new(name.trim().to_string(), kind, url);
+                    dbi.save_notification_channel(&channel).await?;
+                    dbi.append_audit_event(&AuditEvent::admin(format!(
+                        "Added notification channel '{}' ({}) via CLI",
+                        channel.name, channel.uuid
+                    )))
+                    .await?;
+                    println!("Added notification channel {} ({})", channel.name, channel.uuid);
+                }
+                NotifyCmd::Remove { uuid } => {
+                    dbi.delete_notification_channel(uuid).await?;
+                    dbi.append_audit_event(&AuditEvent::admin(format!(
+                        "Removed notification channel {uuid} via CLI"
+                    )))
+                    .await?;
+                    println!("Removed notification channel {uuid}");
+                }
+                NotifyCmd::Test { uuid } => {
+                    let channels = dbi.get_notification_channels().await?;
+                    let Some(channel) = channels.iter().find(|c| c.uuid == uuid) else {
+                        eprintln!("Error: No notification channel with uuid {uuid}");
+                        std::process::exit(1);
+                    };
+                    match notifications::test_fire(dbi.as_ref(), channel).await {
+                        Ok(()) => println!("Test notification sent to {}", channel.name),
+                        Err(e) => {
+                            eprintln!("Error: Test notification to {} failed: {e}", channel.name);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                NotifyCmd::Routes => {
+                    let routes = dbi.get_notification_routes().await?;
+                    if routes.is_empty() {
+                        println!("No notification routes found.");
+                    }
+                    let list_uuids = |uuids: &[uuid::Uuid]| {
+                        uuids.iter().map(|u| u.to_string()).collect::<Vec<_>>().join(", ")
+                    };
+                    for route in routes {
+                        let escalation = match &route.escalation {
+                            None => "inherited".to_string(),
+                            Some(policy) if policy.after_secs == 0 => "off".to_string(),
+                            Some(policy) => format!(
+                                "after {} min -> {}",
+                                policy.after_secs / 60,
+                                list_uuids(&policy.channels)
+                            ),
+                        };
+                        println!(
+                            "- {}: channels {}, escalation {escalation}",
+                            route.scope,
+                            if route.channels.is_empty() {
+                                "inherited".to_string()
+                            } else {
+                                list_uuids(&route.channels)
+                            }
+                        );
+                    }
+                }
+                NotifyCmd::Route { scope, channels, escalate_after, escalate_to } => {
+                    use database::models::{EscalationPolicy, NotificationRoute, RouteScope};
+                    if let RouteScope::Monitor(uuid) = scope
+                        && dbi.get_monitor_by_uuid(uuid).await?.is_none()
+                    {
+                        eprintln!("Error: No monitor with uuid {uuid}");
+                        std::process::exit(1);
+                    }
+                    let known = dbi.get_notification_channels().await?;
+                    if let Some(unknown) = channels
+                        .iter()
+                        .chain(&escalate_to)
+                        .find(|uuid| !known.iter().any(|c| c.uuid == **uuid))
+                    {
+                        eprintln!("Error: No notification channel with uuid {unknown}");
+                        std::process::exit(1);
+                    }
+                    if escalate_after.is_none() && !escalate_to.is_empty() {
+                        eprintln!("Error: --escalate-to needs --escalate-after");
+                        std::process::exit(1);
+                    }
+
+                    let mut route = NotificationRoute::new(scope);
+                    route.channels = channels;
+                    route.escalation = escalate_after.map(|mins| EscalationPolicy {
+                        after_secs: mins.saturating_mul(60),
+                        channels: escalate_to,
+                    });
+                    if route.is_empty() {
+                        dbi.delete_notification_route(&route.scope).await?;
+                    } else {
+                        dbi.save_notification_route(&route).await?;
+                    }
+                    dbi.append_audit_event(&AuditEvent::admin(format!(
+                        "Set notification route {} via CLI",
+                        route.scope
+                    )))
+                    .await?;
+                    println!("Set notification route {}", route.scope);
+                }
+                NotifyCmd::Unroute { scope } => {
+                    if !dbi.delete_notification_route(&scope).await? {
+                        eprintln!("Error: No notification route for {scope}");
+                        std::process::exit(1);
+                    }
+                    dbi.append_audit_event(&AuditEvent::admin(format!(
+                        "Removed notification route {scope} via CLI"
+                    )))
+                    .await?;
+                    println!("Removed notification route {scope}");
+                }
+            }
+        }
+        Commands::Page { cmd } => {
+            use database::models::{AuditEvent, StatusPage};
+            let dbi = database::open(&cfg.database, &cfg.database_pool, pool).await?;
+
+            let shown = matches!(cmd, PageCmd::Show { .. });
+            match cmd {
+                PageCmd::List => {
+                    let pages = dbi.get_status_pages().await?;
+                    if pages.is_empty() {
+                        println!("No status pages found.");
+                    }
+                    for page in pages {
+                        println!(
+                            "- {} {}{}",
+                            page.slug,
+                            page.title,
+                            if page.is_active { "" } else { " (inactive)" }
+                        );
+                        for m in dbi.get_status_page_monitors(page.uuid).await? {
+                            println!("    {} {} -> {}", m.uuid, m.name, m.target);
+                        }
+                    }
+                }
+                PageCmd::Add { slug, title, description } => {
+                    if !StatusPage::is_valid_slug(&slug) {
+                        eprintln!(
+                            "Error: Slug may only contain lowercase letters, digits and dashes"
+                        );
+                        std::process::exit(1);
+                    }
+                    if title.trim().is_empty() {
+                        eprintln!("Error: Page title cannot be empty");
+                        std::process::exit(1);
+                    }
+                    if dbi.get_status_page(&slug).await?.is_some() {
+                        eprintln!("Error: A status page with slug {slug} already exists");
+                        std::process::exit(1);
+                    }
+
+                    let page = StatusPage::new(title.trim().to_string(), slug, description);
+                    dbi.save_status_page(&page).await?;
+                    dbi.append_audit_event(&AuditEvent::admin(format!(
+                        "Added status page '{}' ({}) via CLI",
+                        page.slug, page.uuid
+                    )))
+                    .await?;
+                    println!("Added status page {} ({})", page.slug, page.uuid);
+                }
+                PageCmd::Show { slug, uuid } | PageCmd::Hide { slug, uuid } => {
+                    let Some(page) = dbi.get_status_page(&slug).await? else {
+                        eprintln!("Error: No status page with slug {slug}");
+                        std::process::exit(1);
+                    };
+                    let Some(monitor) = dbi.get_monitor_by_uuid(uuid).await? else {
+                        eprintln!("Error: No monitor with uuid {uuid}");
+                        std::process::exit(1);
+                    };
+
+                    dbi.set_status_page_monitor(page.uuid, monitor.uuid, shown).await?;
+                    let (verb, preposition) =
+                        if shown { ("Added", "to") } else { ("Removed", "from") };
+                    dbi.append_audit_event(&AuditEvent::admin(format!(
+                        "{verb} monitor '{}' ({}) {preposition} status page '{}' via CLI",
+                        monitor.name, monitor.uuid, page.slug
+                    )))
+                    .await?;
+                    println!(
+                        "{verb} monitor {} {preposition} status page {}",
+                        monitor.name, page.slug
+                    );
+                }
+            }
+        }
+        Commands::Maintenance { cmd } => {
+            use database::models::{AuditEvent, MaintenanceWindow, Monitor};
+            let dbi = database::open(&cfg.database, &cfg.database_pool, pool).await?;
+
+            match cmd {
+                MaintenanceCmd::List => {
+                    let windows = dbi.get_maintenance_windows(std::time::SystemTime::now()).await?;
+                    if windows.is_empty() {
+                        println!("No maintenance windows found.");
+                    }
+                    for window in windows {
+                        let monitor = match window.monitor_uuid {
+                            Some(uuid) => match dbi.get_monitor_by_uuid(uuid).await? {
+                                Some(monitor) => monitor.name,
+                                None => uuid.to_string(),
+                            },
+                            None => "all monitors".to_string(),
+                        };
+                        println!(
+                            "- {} {} from {} to {}{}",
+                            window.uuid,
+                            monitor,
+                            Monitor::timestamp_to_i64(window.starts_at),
+                            Monitor::timestamp_to_i64(window.ends_at),
+                            if window.reason.is_empty() {
+                                String::new()
+                            } else {
+                                format!(" ({})", window.reason)
+                            }
+                        );
+                    }
+                }
+                MaintenanceCmd::Add { uuid, start, minutes, reason } => {
+                    if minutes == 0 {
+                        eprintln!("Error: --minutes must be at least 1");
+                        std::process::exit(1);
+                    }
+                    let name = match uuid {
+                        Some(uuid) => match dbi.get_monitor_by_uuid(uuid).await? {
+                            Some(monitor) => format!("monitor '{}'", monitor.name),
+                            None => {
+                                eprintln!("Error: No monitor with uuid {uuid}");
+                                std::process::exit(1);
+                            }
+                        },
+                        None => "all monitors".to_string(),
+                    };
+
+                    let starts_at = match start {
+                        Some(start) => Monitor::i64_to_timestamp(start),
+                        None => std::time::SystemTime::now(),
+                    };
+                    let ends_at = starts_at + std::time::Duration::from_secs(minutes * 60);
+                    let window = MaintenanceWindow::new(uuid, starts_at, ends_at, reason);
+                    dbi.save_maintenance_window(&window).await?;
+                    dbi.append_audit_event(&AuditEvent::admin(format!(
+                        "Added maintenance window {} for {name} via CLI",
+                        window.uuid
+                    )))
+                    .await?;
+                    println!("Added maintenance window {} for {name}", window.uuid);
+                }
+                MaintenanceCmd::Remove { uuid } => {
+                    if !dbi.delete_maintenance_window(uuid).await? {
+                        eprintln!("Error: No maintenance window with uuid {uuid}");
+                        std::process::exit(1);
+                    }
+                    dbi.append_audit_event(&AuditEvent::admin(format!(
+                        "Removed maintenance window {uuid} via CLI"
+                    )))
+                    .await?;
+                    println!("Removed maintenance window {uuid}");
+                }
+            }
+        }
+        #[cfg(feature = "chaos")]
+        Commands::Chaos { cmd } => {
+            use database::models::{AuditEvent, Setting};
+            use orchestrator::chaos::{
+                DB_WRITE_DELAY_SETTING, DHT_FAILURE_SETTING, FaultSettings, GOSSIP_DROP_SETTING,
+            };
+            let dbi = database::open(&cfg.database, &cfg.database_pool, pool).await?;
+
+            let changes = match cmd {
+                ChaosCmd::Show => {
+                    println!("{:?}", FaultSettings::from_settings(&dbi).await?);
+                    return Ok(());
+                }
+                ChaosCmd::Set { gossip_drop_percent, db_write_delay_ms, dht_failure_percent } => [
+                    (GOSSIP_DROP_SETTING, gossip_drop_percent.map(u64::from)),
+                    (DB_WRITE_DELAY_SETTING, db_write_delay_ms),
+                    (DHT_FAILURE_SETTING, dht_failure_percent.map(u64::from)),
+                ],
+                ChaosCmd::Off => [
+                    (GOSSIP_DROP_SETTING, Some(0)),
+                    (DB_WRITE_DELAY_SETTING, Some(0)),
+                    (DHT_FAILURE_SETTING, Some(0)),
+                ],
+            };
+            let now = std::time::SystemTime::now();
+            for (key, value) in changes {
+                if let Some(value) = value {
+                    dbi.save_setting(&Setting {
+                        key: key.to_string(),
+                        value: value.to_string(),
+                        updated_at: now,
+                    })
+                    .await?;
+                }
+            }
+            let faults = FaultSettings::from_settings(&dbi).await?;
+            dbi.append_audit_event(&AuditEvent::admin(format!(
+                "Set injected faults to {faults:?} via CLI"
+            )))
+            .await?;
+            println!("Injecting faults: {faults:?}");
+        }
+        Commands::Follow { cmd } => {
+            use database::models::{AuditEvent, Follow, FollowSummary};
+            let dbi = database::open(&cfg.database, &cfg.database_pool, pool).await?;
+
+            match cmd {
+                FollowCmd::List => {
+                    let follows = dbi.get_follows().await?;
+                    if follows.is_empty() {
+                        println!("Not following any hosts.");
+                    }
+                    let since = std::time::SystemTime::now() - FollowSummary::WINDOW;
+                    for follow in follows {
+                        let mut results = Vec::new();
+                        for monitor_uuid in dbi.get_follow_monitors(&follow.host).await? {
+                            results.extend(
+                                dbi.get_peer_results(
+                                    monitor_uuid,
+                                    FollowSummary::RESULTS_PER_MONITOR,
+                                )
+                                .await?,
+                            );
+                        }
+                        let summary = FollowSummary::from_results(&results, since);
+                        let latency = summary
+                            .avg_latency_ms
+                            .map(|ms| format!(", {ms}ms average"))
+                            .unwrap_or_default();
+                        println!(
+                            "- {} {} ({}{})",
+                            follow.host,
+                            summary.status(),
+                            summary.summary(),
+                            latency
+                        );
+                    }
+                }
+                FollowCmd::Add { host } => {
+                    let Some(follow) = Follow::new(&host) else {
+                        eprintln!("Error: '{host}' has no host to follow");
+                        std::process::exit(1);
+                    };
+                    dbi.save_follow(&follow).await?;
+                    dbi.append_audit_event(&AuditEvent::admin(format!(
+                        "Followed {} via CLI",
+                        follow.host
+                    )))
+                    .await?;
+                    println!("Following {}", follow.host);
+                }
+                FollowCmd::Remove { host } => {
+                    let host = Follow::host_of(&host).unwrap_or(host);
+                    if !dbi.delete_follow(&host).await? {
+                        eprintln!("Error: Not following {host}");
+                        std::process::exit(1);
+                    }
+                    dbi.append_audit_event(&AuditEvent::admin(format!(
+                        "Stopped following {host} via CLI"
+                    )))
+                    .await?;
+                    println!("Stopped following {host}");
+                }
+            }
+        }
+        Commands::Community { cmd } => {
+            use database::models::{AuditEvent, PublicMonitorGroup};
+            use orchestrator::community;
+            let dbi = database::open(&cfg.database, &cfg.database_pool, pool).await?;
+
+            match cmd {
+                CommunityCmd::List => {
+                    let listing = PublicMonitorGroup::listing(
+                        &dbi.get_group_memberships().await?,
+                        &dbi.get_community_domains().await?,
+                        std::time::SystemTime::now(),
+                    );
+                    if listing.is_empty() {
+                        println!("No community domains found.");
+                    }
+                    for (group, domain) in listing {
+                        println!(
+                            "- {} {} peer(s){}",
+                            group.host,
+                            group.participating_peers.len(),
+                            if domain.is_some_and(|domain| domain.joined) {
+                                " (joined)"
+                            } else {
+                                ""
+                            }
+                        );
+                    }
+                }
+                CommunityCmd::Join { target } => {
+                    let domain = match community::join(dbi.as_ref(), &target).await {
+                        Ok(domain) => domain,
+                        Err(e) => {
+                            eprintln!("Error: {e}");
+                            std::process::exit(1);
+                        }
+                    };
+                    dbi.append_audit_event(&AuditEvent::admin(format!(
+                        "Joined monitoring {} via CLI",
+                        domain.host
+                    )))
+                    .await?;
+                    match domain.monitor_uuid {
+                        Some(uuid) => println!("Joined {} (monitor {uuid})", domain.host),
+                        None => println!("Joined {} with the monitor checking it", domain.host),
+                    }
+                }
+                CommunityCmd::Leave { host } => {
+                    let Some(domain) = community::leave(dbi.as_ref(), &host).await? else {
+                        eprintln!("Error: Not monitoring {host}");
+                        std::process::exit(1);
+                    };
+                    dbi.append_audit_event(&AuditEvent::admin(format!(
+                        "Left monitoring {} via CLI",
+                        domain.host
+                    )))
+                    .await?;
+                    println!("Left {}", domain.host);
+                }
+            }
+        }
+        Commands::Archive { cmd: ArchiveCmd::Restore { from, to } } => {
+            use database::models::AuditEvent;
+            if from > to {
+                eprintln!("Error: --from must not be after --to");
+                std::process::exit(1);
+            }
+            let Some(archive) = archive::ResultArchive::from_config(&cfg.archive)? else {
+                eprintln!("Error: No archive bucket configured in [archive]");
+                std::process::exit(1);
+            };
+            let dbi = database::open(&cfg.database, &cfg.database_pool, pool).await?;
+
+            let restored = archive.restore(dbi.as_ref(), from, to).await?;
+            dbi.append_audit_event(&AuditEvent::admin(format!(
+                "Restored {restored} archived results from {from} to {to} via CLI"
+            )))
+            .await?;
+            println!("Restored {restored} results");
+        }
+        Commands::Import {
+            cmd:
+                ImportCmd::Results {
+                    file,
+                    uuid,
+                    timestamp_column,
+                    status_column,
+                    latency_column,
+                    dry_run,
+                },
+        } => {
+            use database::models::AuditEvent;
+            let dbi = database::open(&cfg.database, &cfg.database_pool, pool).await?;
+            let Some(monitor) = dbi.get_monitor_by_uuid(uuid).await? else {
+                eprintln!("Error: No monitor with uuid {uuid}");
+                std::process::exit(1);
+            };
+
+            let text = std::fs::read_to_string(&file)
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", file.display()))?;
+            let columns = import::ColumnNames {
+                timestamp: timestamp_column,
+                status: status_column,
+                latency: latency_column,
+            };
+            let parsed = match import::parse_csv(
+                &text,
+                monitor.uuid,
+                &columns,
+                std::time::SystemTime::now(),
+            ) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    std::process::exit(1);
+                }
+            };
+            for (line, reason) in parsed.skipped.iter().take(10) {
+                eprintln!("Skipped line {line}: {reason}");
+            }
+            if parsed.skipped.len() > 10 {
+                eprintln!("Skipped {} more lines", parsed.skipped.len() - 10);
+            }
+
+            if dry_run {
+                println!(
+                    "Would import up to {} results into '{}', skipping {} rows",
+                    parsed.results.len(),
+                    monitor.name,
+                    parsed.skipped.len()
+                );
+                return Ok(());
+            }
+            let imported = dbi.import_results(&parsed.results, None).await?;
+            dbi.append_audit_event(&AuditEvent::admin(format!(
+                "Imported {imported} results into monitor '{}' ({}) from {} via CLI",
+                monitor.name,
+                monitor.uuid,
+                file.display()
+            )))
+            .await?;
+            println!(
+                "Imported {imported} results into '{}'; {} were already stored, {} rows skipped",
+                monitor.name,
+                parsed.results.len() as u64 - imported,
+                parsed.skipped.len()
+            );
+        }
+        Commands::ClusterKey => {
+            println!("{}", crypto::cluster::generate_cluster_key());
+        }
+        Commands::Keypair { cmd: KeypairCmd::Passphrase { remove } } => {
+            use crypto::keys;
+            let path = crypto::keypair_path();
+            if !path.exists() {
+                eprintln!("Error: No keypair at {}", path.display());
+                std::process::exit(1);
+            }
+            let current = if keys::is_encrypted(&path)? { keys::passphrase(&path)? } else { None };
+            let keypair = keys::load_keypair(&path, current.as_deref())?;
+
+            let passphrase = if remove {
+                None
+            } else {
+                let passphrase = keys::prompt_passphrase("New passphrase: ")?;
+                if keys::prompt_passphrase("Repeat the new passphrase: ")? != passphrase {
+                    eprintln!("Error: The passphrases do not match");
+                    std::process::exit(1);
+                }
+                Some(passphrase)
+            };
+            keys::save_keypair(&keypair, &path, passphrase.as_deref())?;
+            if remove {
+                println!("Stored the keypair at {} in plaintext", path.display());
+            } else {
+                println!("Encrypted the keypair at {}", path.display());
+            }
         }
     }
 
@@ -1,6 +1,19 @@
 use anyhow::{Result, anyhow};
-use std::time::{Duration, Instant};
+use rustls::client::WebPkiServerVerifier;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::time::timeout;
+use tokio_rustls::TlsConnector;
+use url::Url;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use super::types::CertificateInfo;
+use crate::config::CertExpiryConfig;
 
 /// Type of monitoring check to perform
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -9,6 +22,35 @@ pub enum CheckType {
     Https,
     Tcp,
     Icmp,
+    CertExpiry,
+}
+
+/// How much a check costs to run, deciding which pool it runs in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckClass {
+    /// A single connection or packet (TCP, ICMP)
+    Light,
+    /// A full request or TLS handshake (HTTP, HTTPS, certificate expiry)
+    Heavy,
+}
+
+impl CheckClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CheckClass::Light => "light",
+            CheckClass::Heavy => "heavy",
+        }
+    }
+}
+
+impl CheckType {
+    /// Pool the check runs in
+    pub fn class(self) -> CheckClass {
+        match self {
+            CheckType::Http | CheckType::Https | CheckType::CertExpiry => CheckClass::Heavy,
+            CheckType::Tcp | CheckType::Icmp => CheckClass::Light,
+        }
+    }
 }
 
 /// Checker trait for different types of monitoring checks
@@ -31,6 +73,19 @@ impl HttpChecker {
 
         Ok(Self { client })
     }
+
+    /// Checker for a probe a peer asked for: it connects only to `addrs`,
+    /// checked for `host` by the probe policy, and doesn't follow redirects,
+    /// whose targets were never checked
+    pub fn pinned(timeout_seconds: u64, host: &str, addrs: &[SocketAddr]) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(timeout_seconds))
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve_to_addrs(host, addrs)
+            .build()?;
+
+        Ok(Self { client })
+    }
 }
 
 #[async_trait::async_trait]
@@ -60,11 +115,19 @@ impl Checker for HttpChecker {
 /// TCP port checker
 pub struct TcpChecker {
     timeout_duration: Duration,
+    /// Addresses to connect to instead of resolving the target
+    addrs: Option<Vec<SocketAddr>>,
 }
 
 impl TcpChecker {
     pub fn new(timeout_seconds: u64) -> Self {
-        Self { timeout_duration: Duration::from_secs(timeout_seconds) }
+        Self { timeout_duration: Duration::from_secs(timeout_seconds), addrs: None }
+    }
+
+    /// Connect only to `addrs`, which the probe policy checked
+    pub fn with_addrs(mut self, addrs: &[SocketAddr]) -> Self {
+        self.addrs = Some(addrs.to_vec());
+        self
     }
 }
 
@@ -73,7 +136,12 @@ impl Checker for TcpChecker {
     async fn check(&self, target: &str) -> Result<(u64, Option<u16>)> {
         let start = Instant::now();
 
-        let connect = tokio::net::TcpStream::connect(target);
+        let connect = async {
+            match &self.addrs {
+                Some(addrs) => tokio::net::TcpStream::connect(addrs.as_slice()).await,
+                None => tokio::net::TcpStream::connect(target).await,
+            }
+        };
 
         timeout(self.timeout_duration, connect)
             .await
@@ -108,3 +176,337 @@ impl Checker for IcmpChecker {
         ))
     }
 }
+
+/// What a certificate expiry check concludes about a certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CertVerdict {
+    /// Valid and not expiring soon
+    Valid,
+    /// Expiring within the warning threshold
+    Expiring,
+    /// Expired, expiring within the critical threshold, or not trusted
+    Failing(String),
+}
+
+impl CertVerdict {
+    /// Judge a certificate against the configured thresholds
+    pub fn of(certificate: &CertificateInfo, thresholds: &CertExpiryConfig) -> Self {
+        let days = certificate.days_until_expiry;
+        if days < 0 {
+            CertVerdict::Failing(format!("Certificate expired {} day(s) ago", -days))
+        } else if !certificate.chain_valid {
+            CertVerdict::Failing("Certificate chain is not trusted".to_string())
+        } else if days <= i64::from(thresholds.critical_days) {
+            CertVerdict::Failing(format!("Certificate expires in {days} day(s)"))
+        } else if days <= i64::from(thresholds.warning_days) {
+            CertVerdict::Expiring
+        } else {
+            CertVerdict::Valid
+        }
+    }
+}
+
+/// TLS certificate expiry checker
+///
+/// Completes a TLS handshake with the target (`host`, `host:port` or an
+/// `https://` URL; port 443 by default) and reports the leaf certificate. The
+/// handshake succeeds even when the chain doesn't verify, so an untrusted or
+/// expired certificate is still reported rather than failing the check.
+pub struct CertExpiryChecker {
+    timeout_duration: Duration,
+    roots: Arc<RootCertStore>,
+    provider: Arc<CryptoProvider>,
+    /// Addresses to connect to instead of resolving the target
+    addrs: Option<Vec<SocketAddr>>,
+}
+
+impl CertExpiryChecker {
+    /// Verify chains against the Mozilla root program
+    pub fn new(timeout_seconds: u64) -> Self {
+        let roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        Self::with_roots(timeout_seconds, roots)
+    }
+
+    /// Verify chains against `roots`
+    pub fn with_roots(timeout_seconds: u64, roots: RootCertStore) -> Self {
+        Self {
+            timeout_duration: Duration::from_secs(timeout_seconds),
+            roots: Arc::new(roots),
+            provider: Arc::new(rustls::crypto::ring::default_provider()),
+            addrs: None,
+        }
+    }
+
+    /// Connect only to `addrs`, which the probe policy checked
+    pub fn with_addrs(mut self, addrs: &[SocketAddr]) -> Self {
+        self.addrs = Some(addrs.to_vec());
+        self
+    }
+
+    /// Handshake with the target, returning the handshake latency in
+    /// milliseconds and the certificate it presented
+    pub async fn inspect(&self, target: &str) -> Result<(u64, CertificateInfo)> {
+        let (host, port) = tls_target(target)?;
+        let server_name = ServerName::try_from(host.clone())
+            .map_err(|e| anyhow!("Invalid TLS server name {}: {}", host, e))?;
+
+        let verifier = Arc::new(RecordingVerifier {
+            inner: WebPkiServerVerifier::builder_with_provider(
+                self.roots.clone(),
+                self.provider.clone(),
+            )
+            .build()?,
+            chain_error: Mutex::new(None),
+        });
+        let config = rustls::ClientConfig::builder_with_provider(self.provider.clone())
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(verifier.clone())
+            .with_no_client_auth();
+
+        let start = Instant::now();
+        let handshake = async {
+            let stream = match &self.addrs {
+                Some(addrs) => tokio::net::TcpStream::connect(addrs.as_slice()).await?,
+                None => tokio::net::TcpStream::connect((host.as_str(), port)).await?,
+            };
+            TlsConnector::from(Arc::new(config)).connect(server_name, stream).await
+        };
+        let stream = timeout(self.timeout_duration, handshake)
+            .await
+            .map_err(|_| anyhow!("TLS handshake timeout"))?
+            .map_err(|e| anyhow!("TLS handshake failed: {}", e))?;
+        let latency = start.elapsed().as_millis() as u64;
+
+        let leaf = stream
+            .get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .ok_or_else(|| anyhow!("Server presented no certificate"))?;
+        let (_, cert) = X509Certificate::from_der(leaf.as_ref())
+            .map_err(|e| anyhow!("Failed to parse server certificate: {}", e))?;
+
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64;
+        let not_after = cert.validity().not_after.timestamp();
+        let chain_valid = verifier.chain_error.lock().unwrap().is_none();
+        Ok((
+            latency,
+            CertificateInfo {
+                days_until_expiry: (not_after - now).div_euclid(86_400),
+                not_after,
+                issuer: cert.issuer().to_string(),
+                chain_valid,
+            },
+        ))
+    }
+}
+
+/// Host and port a certificate expiry check connects to
+pub fn tls_target(target: &str) -> Result<(String, u16)> {
+    if target.contains("://") {
+        let url = Url::parse(target).map_err(|e| anyhow!("Invalid URL {}: {}", target, e))?;
+        let host = url.host_str().ok_or_else(|| anyhow!("URL {} has no host", target))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        return Ok((host.to_string(), url.port().unwrap_or(443)));
+    }
+
+    let (host, port) = match target.rsplit_once(':') {
+        // A bare IPv6 address has colons but no port
+        Some((host, port)) if !host.contains(':') || host.ends_with(']') => {
+            (host, port.parse().map_err(|_| anyhow!("Invalid port in {}", target))?)
+        }
+        _ => (target, 443),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        return Err(anyhow!("Target {} has no host", target));
+    }
+    Ok((host.to_string(), port))
+}
+
+/// Verifies the chain like rustls would, but records a failure instead of
+/// aborting the handshake
+#[derive(Debug)]
+struct RecordingVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    chain_error: Mutex<Option<rustls::Error>>,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        if let Err(e) = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        ) {
+            *self.chain_error.lock().unwrap() = Some(e);
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{CertificateParams, KeyPair, date_time_ymd};
+    use rustls::pki_types::PrivateKeyDer;
+    use tokio::net::TcpListener;
+    use tokio_rustls::TlsAcceptor;
+
+    fn certificate(days_until_expiry: i64, chain_valid: bool) -> CertificateInfo {
+        CertificateInfo {
+            days_until_expiry,
+            not_after: 0,
+            issuer: "CN=Test CA".to_string(),
+            chain_valid,
+        }
+    }
+
+    /// Serve one TLS handshake with a localhost certificate expiring at
+    /// `year`, returning the address and the certificate
+    async fn serve(year: i32) -> (String, CertificateDer<'static>) {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+        params.not_after = date_time_ymd(year, 1, 1);
+        let cert = params.self_signed(&key).unwrap().der().clone();
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![cert.clone()],
+                PrivateKeyDer::try_from(key.serialize_der()).unwrap(),
+            )
+            .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let _ = TlsAcceptor::from(Arc::new(config)).accept(stream).await;
+        });
+        (format!("localhost:{port}"), cert)
+    }
+
+    #[test]
+    fn test_cert_verdict_thresholds() {
+        let thresholds = CertExpiryConfig { warning_days: 14, critical_days: 3 };
+
+        assert_eq!(CertVerdict::of(&certificate(90, true), &thresholds), CertVerdict::Valid);
+        assert_eq!(CertVerdict::of(&certificate(14, true), &thresholds), CertVerdict::Expiring);
+        assert!(matches!(
+            CertVerdict::of(&certificate(3, true), &thresholds),
+            CertVerdict::Failing(_)
+        ));
+        assert!(matches!(
+            CertVerdict::of(&certificate(-1, true), &thresholds),
+            CertVerdict::Failing(_)
+        ));
+        assert!(matches!(
+            CertVerdict::of(&certificate(90, false), &thresholds),
+            CertVerdict::Failing(_)
+        ));
+    }
+
+    #[test]
+    fn test_tls_target() {
+        let target = |t: &str| tls_target(t).unwrap();
+        assert_eq!(target("example.com"), ("example.com".to_string(), 443));
+        assert_eq!(target("example.com:8443"), ("example.com".to_string(), 8443));
+        assert_eq!(target("https://example.com/health"), ("example.com".to_string(), 443));
+        assert_eq!(target("https://example.com:8443"), ("example.com".to_string(), 8443));
+        assert_eq!(target("2001:db8::1"), ("2001:db8::1".to_string(), 443));
+        assert_eq!(target("[2001:db8::1]:8443"), ("2001:db8::1".to_string(), 8443));
+
+        assert!(tls_target("example.com:abc").is_err());
+        assert!(tls_target(":443").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_inspect_reports_trusted_certificate() {
+        let (target, cert) = serve(4000).await;
+        let mut roots = RootCertStore::empty();
+        roots.add(cert).unwrap();
+
+        let (_, info) = CertExpiryChecker::with_roots(5, roots).inspect(&target).await.unwrap();
+        assert!(info.chain_valid);
+        assert!(info.days_until_expiry > 365);
+        assert!(info.issuer.contains("rcgen"));
+    }
+
+    #[tokio::test]
+    async fn test_inspect_reports_expired_untrusted_certificate() {
+        let (target, _) = serve(2000).await;
+
+        let (_, info) = CertExpiryChecker::new(5).inspect(&target).await.unwrap();
+        assert!(!info.chain_valid);
+        assert!(info.days_until_expiry < 0);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_http_check_does_not_follow_redirects() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Stands in for an internal service the probe policy refuses
+        let internal = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let internal_addr = internal.local_addr().unwrap();
+
+        let public = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let public_addr = public.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = public.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await;
+            let response = format!(
+                "HTTP/1.1 302 Found\r\nLocation: http://{internal_addr}/latest/meta-data/\r\n\
+                 Content-Length: 0\r\nConnection: close\r\n\r\n"
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        // The name never goes to DNS; the checked address is used instead
+        let checker = HttpChecker::pinned(5, "probe.example", &[public_addr]).unwrap();
+        let target = format!("http://probe.example:{}/", public_addr.port());
+        let (_, status) = checker.check(&target).await.unwrap();
+        assert_eq!(status, Some(302));
+
+        let followed = tokio::time::timeout(Duration::from_millis(200), internal.accept())
+            .await
+            .is_ok();
+        assert!(!followed, "the redirect to the internal address was followed");
+    }
+}
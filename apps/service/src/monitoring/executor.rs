@@ -1,59 +1,232 @@
 use anyhow::Result;
-use std::sync::Arc;
+use opentelemetry::KeyValue;
+use opentelemetry::global;
+use opentelemetry::metrics::Histogram;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use tokio::sync::Semaphore;
 use uuid::Uuid;
 
-use super::checker::{CheckType, Checker, HttpChecker, IcmpChecker, TcpChecker};
+use super::checker::{
+    CertExpiryChecker, CertVerdict, CheckClass, CheckType, Checker, HttpChecker, IcmpChecker,
+    TcpChecker,
+};
 use super::types::CheckResult;
+use crate::config::{CertExpiryConfig, CheckPoolsConfig};
+
+/// Checkers built for a specific timeout
+struct Checkers {
+    timeout_seconds: u64,
+    http: Arc<HttpChecker>,
+    tcp: Arc<TcpChecker>,
+    icmp: Arc<IcmpChecker>,
+    cert: Arc<CertExpiryChecker>,
+}
+
+impl Checkers {
+    fn new(timeout_seconds: u64) -> Result<Self> {
+        Ok(Self {
+            timeout_seconds,
+            http: Arc::new(HttpChecker::new(timeout_seconds)?),
+            tcp: Arc::new(TcpChecker::new(timeout_seconds)),
+            icmp: Arc::new(IcmpChecker::new(timeout_seconds)),
+            cert: Arc::new(CertExpiryChecker::new(timeout_seconds)),
+        })
+    }
+}
+
+/// Checker picked for a check
+enum Selected {
+    Probe(Arc<dyn Checker>),
+    Cert(Arc<CertExpiryChecker>),
+}
+
+/// Slots for running checks, one pool per class
+struct CheckPools {
+    light: Semaphore,
+    heavy: Semaphore,
+    wait: Histogram<f64>,
+}
+
+impl CheckPools {
+    fn new(config: &CheckPoolsConfig) -> Self {
+        Self {
+            light: Semaphore::new(config.light.max(1)),
+            heavy: Semaphore::new(config.heavy.max(1)),
+            wait: global::meter("uppe-service")
+                .f64_histogram("uppe.check.pool.wait")
+                .with_description("Time checks waited for a slot in their pool")
+                .with_unit("ms")
+                .build(),
+        }
+    }
+
+    fn get(&self, class: CheckClass) -> &Semaphore {
+        match class {
+            CheckClass::Light => &self.light,
+            CheckClass::Heavy => &self.heavy,
+        }
+    }
+}
 
 /// Monitoring executor - executes individual monitoring checks
+///
+/// Light and heavy checks run in separate pools (see [`CheckPoolsConfig`]).
 pub struct MonitoringExecutor {
-    http_checker: Arc<HttpChecker>,
-    tcp_checker: Arc<TcpChecker>,
-    icmp_checker: Arc<IcmpChecker>,
+    checkers: RwLock<Checkers>,
+    pools: CheckPools,
     peer_id: String,
-    degraded_threshold_ms: u64,
+    degraded_threshold_ms: AtomicU64,
+    cert_expiry: CertExpiryConfig,
 }
 
 impl MonitoringExecutor {
     /// Create a new monitoring executor
     pub fn new(peer_id: String, timeout_seconds: u64, degraded_threshold_ms: u64) -> Result<Self> {
         Ok(Self {
-            http_checker: Arc::new(HttpChecker::new(timeout_seconds)?),
-            tcp_checker: Arc::new(TcpChecker::new(timeout_seconds)),
-            icmp_checker: Arc::new(IcmpChecker::new(timeout_seconds)),
+            checkers: RwLock::new(Checkers::new(timeout_seconds)?),
+            pools: CheckPools::new(&CheckPoolsConfig::default()),
             peer_id,
-            degraded_threshold_ms,
+            degraded_threshold_ms: AtomicU64::new(degraded_threshold_ms),
+            cert_expiry: CertExpiryConfig::default(),
         })
     }
 
+    /// Limit the checks running at once per class
+    pub fn with_pools(mut self, config: &CheckPoolsConfig) -> Self {
+        self.pools = CheckPools::new(config);
+        self
+    }
+
+    /// Thresholds certificate expiry checks report degraded and down at
+    pub fn with_cert_expiry(mut self, config: &CertExpiryConfig) -> Self {
+        self.cert_expiry = *config;
+        self
+    }
+
+    /// Apply new timeout and degraded threshold to subsequent checks
+    ///
+    /// Checks already in flight keep the settings they started with.
+    pub fn update_settings(&self, timeout_seconds: u64, degraded_threshold_ms: u64) -> Result<()> {
+        self.degraded_threshold_ms.store(degraded_threshold_ms, Ordering::Relaxed);
+
+        let mut checkers = self.checkers.write().unwrap();
+        if checkers.timeout_seconds != timeout_seconds {
+            *checkers = Checkers::new(timeout_seconds)?;
+        }
+        Ok(())
+    }
+
     /// Execute a monitoring check
+    #[tracing::instrument(skip(self), fields(status))]
     pub async fn execute_check(
         &self,
         monitor_id: Uuid,
         target: String,
         check_type: CheckType,
     ) -> CheckResult {
-        let mut result = CheckResult::new(monitor_id, target.clone(), self.peer_id.clone());
-
-        let checker: &dyn Checker = match check_type {
-            CheckType::Http | CheckType::Https => self.http_checker.as_ref(),
-            CheckType::Tcp => self.tcp_checker.as_ref(),
-            CheckType::Icmp => self.icmp_checker.as_ref(),
+        let checker = {
+            let checkers = self.checkers.read().unwrap();
+            match check_type {
+                CheckType::Http | CheckType::Https => Selected::Probe(checkers.http.clone()),
+                CheckType::Tcp => Selected::Probe(checkers.tcp.clone()),
+                CheckType::Icmp => Selected::Probe(checkers.icmp.clone()),
+                CheckType::CertExpiry => Selected::Cert(checkers.cert.clone()),
+            }
         };
+        self.run(
+            CheckResult::new(monitor_id, target.clone(), self.peer_id.clone()),
+            target,
+            check_type,
+            checker,
+        )
+        .await
+    }
 
-        match checker.check(&target).await {
-            Ok((latency_ms, status_code)) => {
-                if latency_ms > self.degraded_threshold_ms {
-                    result = result.degraded(latency_ms, status_code);
-                } else {
-                    result = result.success(latency_ms, status_code);
+    /// Execute a probe a peer asked for, connecting only to `addrs`, which
+    /// the probe policy checked for `host`
+    ///
+    /// The target is not resolved again and HTTP redirects are not followed,
+    /// so the peer can't steer the probe to an address the policy refuses.
+    #[tracing::instrument(skip(self, addrs), fields(status))]
+    pub async fn execute_pinned_check(
+        &self,
+        target: String,
+        check_type: CheckType,
+        host: &str,
+        addrs: &[SocketAddr],
+    ) -> CheckResult {
+        let result = CheckResult::new(Uuid::nil(), target.clone(), self.peer_id.clone());
+        let timeout_seconds = self.checkers.read().unwrap().timeout_seconds;
+        let checker = match check_type {
+            CheckType::Http | CheckType::Https => {
+                match HttpChecker::pinned(timeout_seconds, host, addrs) {
+                    Ok(checker) => Selected::Probe(Arc::new(checker)),
+                    Err(e) => return result.failure(e.to_string()),
                 }
             }
-            Err(e) => {
-                result = result.failure(e.to_string());
+            CheckType::Tcp => {
+                Selected::Probe(Arc::new(TcpChecker::new(timeout_seconds).with_addrs(addrs)))
+            }
+            CheckType::Icmp => Selected::Probe(Arc::new(IcmpChecker::new(timeout_seconds))),
+            CheckType::CertExpiry => {
+                Selected::Cert(Arc::new(CertExpiryChecker::new(timeout_seconds).with_addrs(addrs)))
             }
+        };
+        self.run(result, target, check_type, checker).await
+    }
+
+    /// Run a check with `checker` once its pool has a free slot
+    async fn run(
+        &self,
+        mut result: CheckResult,
+        target: String,
+        check_type: CheckType,
+        checker: Selected,
+    ) -> CheckResult {
+        let degraded_threshold_ms = self.degraded_threshold_ms.load(Ordering::Relaxed);
+
+        let class = check_type.class();
+        let waited = Instant::now();
+        // The pools are never closed
+        let _permit = self.pools.get(class).acquire().await.ok();
+        self.pools.wait.record(
+            waited.elapsed().as_secs_f64() * 1000.0,
+            &[KeyValue::new("class", class.as_str())],
+        );
+
+        match checker {
+            Selected::Probe(checker) => match checker.check(&target).await {
+                Ok((latency_ms, status_code)) => {
+                    if latency_ms > degraded_threshold_ms {
+                        result = result.degraded(latency_ms, status_code);
+                    } else {
+                        result = result.success(latency_ms, status_code);
+                    }
+                }
+                Err(e) => {
+                    result = result.failure(e.to_string());
+                }
+            },
+            // Certificate checks are judged by expiry rather than latency
+            Selected::Cert(checker) => match checker.inspect(&target).await {
+                Ok((latency_ms, certificate)) => {
+                    result = match CertVerdict::of(&certificate, &self.cert_expiry) {
+                        CertVerdict::Valid => result.success(latency_ms, None),
+                        CertVerdict::Expiring => result.degraded(latency_ms, None),
+                        CertVerdict::Failing(reason) => result.failure(reason),
+                    }
+                    .with_certificate(certificate);
+                }
+                Err(e) => {
+                    result = result.failure(e.to_string());
+                }
+            },
         }
 
+        tracing::Span::current().record("status", result.status.to_string());
         result
     }
 }
@@ -62,6 +235,7 @@ impl MonitoringExecutor {
 mod tests {
     use super::*;
     use crate::monitoring::types::MonitorStatus;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_http_check() {
@@ -75,4 +249,26 @@ mod tests {
         assert!(matches!(result.status, MonitorStatus::Up | MonitorStatus::Degraded));
         assert!(result.latency_ms.is_some());
     }
+
+    #[tokio::test]
+    async fn test_busy_heavy_pool_does_not_hold_up_light_checks() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+        let executor = MonitoringExecutor::new("test-peer".to_string(), 5, 1000)
+            .unwrap()
+            .with_pools(&CheckPoolsConfig { light: 1, heavy: 1 });
+
+        // Every heavy slot is taken by a slow HTTPS check
+        let _busy = executor.pools.heavy.acquire().await.unwrap();
+
+        let heavy =
+            executor.execute_check(Uuid::new_v4(), format!("http://{target}"), CheckType::Http);
+        assert!(tokio::time::timeout(Duration::from_millis(100), heavy).await.is_err());
+
+        let light = executor.execute_check(Uuid::new_v4(), target, CheckType::Tcp);
+        let result = tokio::time::timeout(Duration::from_secs(2), light)
+            .await
+            .expect("light check waited for the heavy pool");
+        assert_eq!(result.status, MonitorStatus::Up);
+    }
 }
@@ -1,12 +1,18 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
-use tokio::time::interval;
+use tokio::sync::{mpsc, watch};
+use tokio::task::{AbortHandle, JoinHandle, JoinSet};
+use tokio::time::Instant;
 use uuid::Uuid;
 
 use super::checker::CheckType;
 use super::executor::MonitoringExecutor;
 use super::types::CheckResult;
+use crate::journal::{Journal, JournalEntry};
+use crate::queue::QueueSender;
 
 /// Monitor configuration for scheduling
 #[derive(Debug, Clone)]
@@ -18,76 +24,296 @@ pub struct MonitorConfig {
     pub enabled: bool,
 }
 
-/// Monitoring scheduler - coordinates execution of monitoring tasks
+/// What the scheduler loop last reported about its monitors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SchedulerStatus {
+    /// Monitors in the schedule
+    pub scheduled: usize,
+    /// Checks running now
+    pub in_flight: usize,
+    /// Checks still running a full interval after they started, which skip
+    /// their monitor's rounds
+    pub overdue: usize,
+    /// When the next check is due; the loop is stuck if this passes unnoticed
+    pub next_due: Option<Instant>,
+}
+
+/// Running scheduler, as returned by [`MonitoringScheduler::spawn`]
+pub struct SchedulerHandle {
+    /// Monitor sets are handed over on this; dropping it stops the scheduler
+    pub schedule_tx: mpsc::Sender<Vec<MonitorConfig>>,
+    /// Status published after every step of the loop
+    pub status: watch::Receiver<SchedulerStatus>,
+    pub task: JoinHandle<()>,
+}
+
+/// Monitoring scheduler - runs every monitor's checks from a single loop
+///
+/// Monitors wait in a priority queue ordered by their next due time. The loop
+/// sleeps until the earliest one is due, starts its check and queues the next
+/// one an interval later. Reloads hand over the full set of enabled monitors;
+/// only monitors that were added, changed or removed are touched, so the
+/// others keep their place in the queue. A check still running when its monitor
+/// is due again skips that round, even if the monitor was removed and added
+/// back meanwhile; removing a monitor aborts its running check. After every
+/// step the loop publishes a [`SchedulerStatus`] for the health endpoints.
 pub struct MonitoringScheduler {
     executor: Arc<MonitoringExecutor>,
-    result_tx: mpsc::Sender<CheckResult>,
+    result_tx: QueueSender<CheckResult>,
+    /// Journal results are recorded in until the pipeline stores them
+    journal: Option<Arc<Journal>>,
 }
 
 impl MonitoringScheduler {
     /// Create a new monitoring scheduler
-    pub fn new(executor: Arc<MonitoringExecutor>, result_tx: mpsc::Sender<CheckResult>) -> Self {
-        Self { executor, result_tx }
+    pub fn new(executor: Arc<MonitoringExecutor>, result_tx: QueueSender<CheckResult>) -> Self {
+        Self { executor, result_tx, journal: None }
     }
 
-    /// Schedule a single monitor for periodic checking
-    pub fn schedule_monitor(&self, config: MonitorConfig) -> tokio::task::JoinHandle<()> {
+    /// Record results in the journal before handing them to the pipeline
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Spawn the scheduler
+    ///
+    /// The task exits once the sender is dropped or the result channel closes,
+    /// aborting the checks still running.
+    pub fn spawn(self) -> SchedulerHandle {
+        let (tx, mut rx) = mpsc::channel::<Vec<MonitorConfig>>(4);
+        let (status_tx, status_rx) = watch::channel(SchedulerStatus::default());
+
+        let handle = tokio::spawn(async move {
+            let mut schedule = Schedule::default();
+            let mut checks = JoinSet::new();
+            // Monitor each running check task belongs to, and the other way round
+            let mut running = HashMap::new();
+            let mut aborts: HashMap<Uuid, AbortHandle> = HashMap::new();
+
+            loop {
+                let next_due = schedule.next_due();
+                tokio::select! {
+                    configs = rx.recv() => match configs {
+                        Some(configs) => {
+                            for id in schedule.sync(configs, Instant::now()) {
+                                // Its task is reaped below, which clears its running flag
+                                if let Some(abort) = aborts.remove(&id) {
+                                    abort.abort();
+                                }
+                            }
+                        }
+                        None => break,
+                    },
+                    Some(finished) = checks.join_next_with_id(), if !checks.is_empty() => {
+                        let (task_id, sent) = match finished {
+                            Ok((task_id, sent)) => (task_id, sent),
+                            Err(e) if e.is_cancelled() => (e.id(), true),
+                            Err(e) => {
+                                tracing::error!("Monitor check failed: {}", e);
+                                (e.id(), true)
+                            }
+                        };
+                        if let Some(id) = running.remove(&task_id) {
+                            aborts.remove(&id);
+                            schedule.finished(id);
+                        }
+                        if !sent {
+                            break;
+                        }
+                    }
+                    _ = sleep_until(next_due), if next_due.is_some() => {
+                        while let Some(config) = schedule.pop_due(Instant::now()) {
+                            let id = config.id;
+                            let task = checks.spawn(self.check(config));
+                            running.insert(task.id(), id);
+                            aborts.insert(id, task);
+                        }
+                    }
+                }
+                status_tx.send_replace(schedule.status(Instant::now()));
+            }
+
+            checks.shutdown().await;
+            tracing::debug!("Monitoring scheduler stopped");
+        });
+
+        SchedulerHandle { schedule_tx: tx, status: status_rx, task: handle }
+    }
+
+    /// Run one check and hand its result to the pipeline; false once the
+    /// result channel is closed
+    fn check(&self, config: MonitorConfig) -> impl Future<Output = bool> + Send + 'static {
         let executor = self.executor.clone();
         let result_tx = self.result_tx.clone();
+        let journal = self.journal.clone();
 
-        tokio::spawn(async move {
-            if !config.enabled {
-                return;
+        async move {
+            let result = executor.execute_check(config.id, config.target, config.check_type).await;
+
+            if let Some(journal) = &journal {
+                journal.begin(JournalEntry::Result(result.clone()));
             }
 
-            let mut timer = interval(Duration::from_secs(config.interval_seconds));
+            // Send result to the result channel
+            if let Err(e) = result_tx.send(result).await {
+                tracing::error!("Failed to send check result: {}", e);
+                return false;
+            }
+            true
+        }
+    }
+}
 
-            loop {
-                timer.tick().await;
+/// Sleep until `at`; `None` sleeps forever
+async fn sleep_until(at: Option<Instant>) {
+    match at {
+        Some(at) => tokio::time::sleep_until(at).await,
+        None => std::future::pending().await,
+    }
+}
 
-                let result = executor
-                    .execute_check(config.id, config.target.clone(), config.check_type)
-                    .await;
+#[derive(Debug)]
+struct Scheduled {
+    config: MonitorConfig,
+    /// Queue entries from before the monitor last changed carry an older value
+    generation: u64,
+}
 
-                // Send result to the result channel
-                if let Err(e) = result_tx.send(result).await {
-                    tracing::error!("Failed to send check result: {}", e);
-                    break;
-                }
+/// Enabled monitors and when each is next due
+#[derive(Debug, Default)]
+struct Schedule {
+    monitors: HashMap<Uuid, Scheduled>,
+    /// Due times, earliest first; entries of removed or changed monitors are
+    /// skipped when they come up
+    queue: BinaryHeap<Reverse<(Instant, u64, Uuid)>>,
+    next_generation: u64,
+    /// Monitors with a check running and when it started, kept while a
+    /// monitor is removed and added back so its checks never overlap
+    running: HashMap<Uuid, Instant>,
+}
+
+impl Schedule {
+    /// Replace the scheduled monitors with `configs`, checking new and changed
+    /// monitors right away; returns the removed monitors whose check is
+    /// still running
+    fn sync(&mut self, configs: Vec<MonitorConfig>, now: Instant) -> Vec<Uuid> {
+        let configs: HashMap<Uuid, MonitorConfig> = configs
+            .into_iter()
+            .filter(|config| config.enabled)
+            .map(|config| (config.id, config))
+            .collect();
+        self.monitors.retain(|id, _| configs.contains_key(id));
+        let removed = self.running.keys().filter(|id| !configs.contains_key(id)).copied().collect();
+        for config in configs.into_values() {
+            self.upsert(config, now);
+        }
+        removed
+    }
+
+    /// Add a monitor, or update it if its config changed
+    fn upsert(&mut self, config: MonitorConfig, now: Instant) {
+        if let Some(scheduled) = self.monitors.get(&config.id)
+            && same_schedule(&scheduled.config, &config)
+        {
+            return;
+        }
+        let generation = self.next_generation;
+        self.next_generation += 1;
+        self.queue.push(Reverse((now, generation, config.id)));
+        self.monitors.insert(config.id, Scheduled { config, generation });
+    }
+
+    /// Earliest due time in the queue
+    fn next_due(&self) -> Option<Instant> {
+        self.queue.peek().map(|Reverse((due, ..))| *due)
+    }
+
+    /// Next monitor due by `now` whose last check finished, queueing its
+    /// following check
+    fn pop_due(&mut self, now: Instant) -> Option<MonitorConfig> {
+        while let Some(Reverse((due, generation, id))) = self.queue.peek().copied() {
+            if due > now {
+                return None;
+            }
+            self.queue.pop();
+            let Some(scheduled) = self.monitors.get(&id) else {
+                continue;
+            };
+            if scheduled.generation != generation {
+                continue;
             }
-        })
+
+            // Missed rounds are skipped rather than run back to back
+            let interval = Duration::from_secs(scheduled.config.interval_seconds.max(1));
+            let mut next = due + interval;
+            if next <= now {
+                next = now + interval;
+            }
+            self.queue.push(Reverse((next, generation, id)));
+
+            if let Entry::Vacant(entry) = self.running.entry(id) {
+                entry.insert(now);
+                return Some(scheduled.config.clone());
+            }
+        }
+        None
     }
 
-    /// Schedule multiple monitors
-    pub fn schedule_monitors(
-        &self,
-        configs: Vec<MonitorConfig>,
-    ) -> Vec<tokio::task::JoinHandle<()>> {
-        configs.into_iter().map(|config| self.schedule_monitor(config)).collect()
+    /// Record that the check of a monitor finished or was aborted
+    fn finished(&mut self, id: Uuid) {
+        self.running.remove(&id);
     }
+
+    /// Scheduled monitors and their checks at `now`
+    fn status(&self, now: Instant) -> SchedulerStatus {
+        let overdue = self
+            .running
+            .iter()
+            .filter(|(id, started)| {
+                self.monitors.get(id).is_some_and(|scheduled| {
+                    let interval = Duration::from_secs(scheduled.config.interval_seconds.max(1));
+                    now.duration_since(**started) > interval
+                })
+            })
+            .count();
+        SchedulerStatus {
+            scheduled: self.monitors.len(),
+            in_flight: self.running.len(),
+            overdue,
+            next_due: self.next_due(),
+        }
+    }
+}
+
+fn same_schedule(a: &MonitorConfig, b: &MonitorConfig) -> bool {
+    a.target == b.target && a.check_type == b.check_type && a.interval_seconds == b.interval_seconds
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::QueueConfig;
+    use crate::queue::bounded;
 
-    #[tokio::test]
-    async fn test_scheduler() {
-        let executor =
-            Arc::new(MonitoringExecutor::new("test-peer".to_string(), 10, 1000).unwrap());
-
-        let (tx, mut rx) = mpsc::channel(10);
-        let scheduler = MonitoringScheduler::new(executor, tx);
-
-        let config = MonitorConfig {
+    fn config(interval_seconds: u64) -> MonitorConfig {
+        MonitorConfig {
             id: Uuid::new_v4(),
             target: "https://example.com".to_string(),
             check_type: CheckType::Https,
-            interval_seconds: 1,
+            interval_seconds,
             enabled: true,
-        };
+        }
+    }
 
-        let _handle = scheduler.schedule_monitor(config);
+    #[tokio::test]
+    async fn test_scheduler() {
+        let executor =
+            Arc::new(MonitoringExecutor::new("test-peer".to_string(), 10, 1000).unwrap());
+
+        let (tx, mut rx) = bounded("test", QueueConfig::default());
+        let schedule_tx = MonitoringScheduler::new(executor, tx).spawn().schedule_tx;
+        schedule_tx.send(vec![config(1)]).await.unwrap();
 
         // Wait for at least one result
         let result = tokio::time::timeout(Duration::from_secs(3), rx.recv())
@@ -97,4 +323,86 @@ mod tests {
 
         assert!(result.latency_ms.is_some());
     }
+
+    #[test]
+    fn test_schedule() {
+        let now = Instant::now();
+        let mut schedule = Schedule::default();
+        let a = config(10);
+        let b = config(30);
+        schedule.sync(vec![a.clone(), b.clone()], now);
+
+        // New monitors are checked right away, then every interval
+        let mut due: Vec<_> = std::iter::from_fn(|| schedule.pop_due(now)).map(|c| c.id).collect();
+        due.sort();
+        let mut expected = vec![a.id, b.id];
+        expected.sort();
+        assert_eq!(due, expected);
+        assert_eq!(schedule.next_due(), Some(now + Duration::from_secs(10)));
+
+        // A check still running skips its round
+        let later = now + Duration::from_secs(10);
+        assert!(schedule.pop_due(later).is_none());
+        schedule.finished(a.id);
+        schedule.finished(b.id);
+        let later = now + Duration::from_secs(20);
+        assert_eq!(schedule.pop_due(later).map(|c| c.id), Some(a.id));
+        schedule.finished(a.id);
+
+        // Unchanged monitors keep their place, changed ones run right away and
+        // removed ones are dropped
+        let mut changed = b.clone();
+        changed.target = "https://example.org".to_string();
+        schedule.sync(vec![a.clone(), changed], later);
+        assert_eq!(schedule.pop_due(later).map(|c| c.target), Some("https://example.org".into()));
+        assert!(schedule.pop_due(later).is_none());
+        schedule.sync(vec![b.clone()], later);
+        assert!(!schedule.monitors.contains_key(&a.id));
+        let much_later = later + Duration::from_secs(60);
+        assert_eq!(schedule.pop_due(much_later).map(|c| c.id), None, "b is still running");
+        schedule.finished(b.id);
+        assert_eq!(
+            schedule.pop_due(much_later + Duration::from_secs(30)).map(|c| c.id),
+            Some(b.id)
+        );
+    }
+
+    #[test]
+    fn test_running_check_survives_removal() {
+        let now = Instant::now();
+        let mut schedule = Schedule::default();
+        let a = config(10);
+        assert!(schedule.sync(vec![a.clone()], now).is_empty());
+        assert_eq!(schedule.pop_due(now).map(|c| c.id), Some(a.id));
+
+        // Removing the monitor reports its running check to be aborted
+        assert_eq!(schedule.sync(vec![], now), vec![a.id]);
+        assert_eq!(schedule.status(now).in_flight, 1);
+
+        // Added back before that check was reaped, it waits for it to end
+        schedule.sync(vec![a.clone()], now);
+        assert!(schedule.pop_due(now).is_none(), "checks of a monitor overlapped");
+        schedule.finished(a.id);
+        let later = now + Duration::from_secs(10);
+        assert_eq!(schedule.pop_due(later).map(|c| c.id), Some(a.id));
+    }
+
+    #[test]
+    fn test_status_counts_overdue_checks() {
+        let now = Instant::now();
+        let mut schedule = Schedule::default();
+        let (a, b) = (config(10), config(60));
+        schedule.sync(vec![a.clone(), b.clone()], now);
+        while schedule.pop_due(now).is_some() {}
+
+        let status = schedule.status(now + Duration::from_secs(5));
+        assert_eq!((status.scheduled, status.in_flight, status.overdue), (2, 2, 0));
+        assert_eq!(status.next_due, Some(now + Duration::from_secs(10)));
+
+        // a's check outlived its interval; b's still has time
+        let status = schedule.status(now + Duration::from_secs(11));
+        assert_eq!((status.in_flight, status.overdue), (2, 1));
+        schedule.finished(a.id);
+        assert_eq!(schedule.status(now + Duration::from_secs(11)).overdue, 0);
+    }
 }
@@ -9,6 +9,8 @@ pub enum MonitorStatus {
     Up,
     Down,
     Degraded,
+    /// Also what statuses added by newer versions read as
+    #[serde(other)]
     Unknown,
 }
 
@@ -23,6 +25,17 @@ impl std::fmt::Display for MonitorStatus {
     }
 }
 
+impl MonitorStatus {
+    /// Whether the status means the target was reachable; `None` when unknown
+    pub fn is_available(self) -> Option<bool> {
+        match self {
+            MonitorStatus::Up | MonitorStatus::Degraded => Some(true),
+            MonitorStatus::Down => Some(false),
+            MonitorStatus::Unknown => None,
+        }
+    }
+}
+
 /// Result of a monitoring check
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckResult {
@@ -52,6 +65,35 @@ pub struct CheckResult {
 
     /// Cryptographic signature of this result
     pub signature: Option<Vec<u8>>,
+
+    /// Certificate seen by a certificate expiry check
+    #[serde(default)]
+    pub certificate: Option<CertificateInfo>,
+
+    /// Whether the check ran during a maintenance window of the monitor
+    #[serde(default)]
+    pub maintenance: bool,
+
+    /// Whether an external agent checked this result; its `peer_id` and
+    /// `signature` are then the agent's, and it is never shared with peers
+    #[serde(default)]
+    pub agent: bool,
+}
+
+/// TLS certificate details recorded by a certificate expiry check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertificateInfo {
+    /// Whole days until the certificate expires; negative once it has
+    pub days_until_expiry: i64,
+
+    /// Expiry time (seconds since the Unix epoch)
+    pub not_after: i64,
+
+    /// Issuer distinguished name
+    pub issuer: String,
+
+    /// Whether the chain verified against the trusted roots for the host
+    pub chain_valid: bool,
 }
 
 impl CheckResult {
@@ -67,6 +109,9 @@ impl CheckResult {
             error_message: None,
             peer_id,
             signature: None,
+            certificate: None,
+            maintenance: false,
+            agent: false,
         }
     }
 
@@ -93,6 +138,12 @@ impl CheckResult {
         self
     }
 
+    /// Attach the certificate a certificate expiry check saw
+    pub fn with_certificate(mut self, certificate: CertificateInfo) -> Self {
+        self.certificate = Some(certificate);
+        self
+    }
+
     /// Add cryptographic signature to the result
     pub fn with_signature(mut self, signature: Vec<u8>) -> Self {
         self.signature = Some(signature);
@@ -0,0 +1,247 @@
+/// Agent results - takes results external agents checked and signed
+///
+/// `POST /api/v1/results` takes a result with `monitor_id`, `timestamp` (Unix
+/// seconds), `status`, `latency_ms`, `status_code`, `error_message`, the
+/// agent's public key as `peer_id` and a hex `signature`, made as nodes sign
+/// results but covering `error_message` too, over the monitor's target. The
+/// agent must be one of `[[agent_results.agents]]` and allowed to report on
+/// the monitor, and the result recent and new for that agent. Accepted
+/// results are stored under the agent's key and signature, and never shared
+/// with peers.
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, web};
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use super::api_tls::ApiTls;
+use super::http::{self, HandlerResult, Reply, error};
+use crate::config::{AgentConfig, ApiScope};
+use crate::crypto::verify_check_result;
+use crate::database::Database;
+use crate::database::models::Monitor;
+use crate::monitoring::CheckResult;
+use crate::monitoring::types::MonitorStatus;
+use crate::queue::QueueSender;
+
+/// Largest result body accepted
+const MAX_BODY_BYTES: usize = 16 * 1024;
+/// How old a result submitted by an agent may be
+const AGENT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+/// How far in the future a result submitted by an agent may be dated,
+/// allowing for clock skew
+const AGENT_MAX_SKEW: Duration = Duration::from_secs(5 * 60);
+
+/// A result an external agent submits
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AgentResultInput {
+    monitor_id: Uuid,
+    /// Unix seconds
+    timestamp: u64,
+    status: MonitorStatus,
+    #[serde(default)]
+    latency_ms: Option<u64>,
+    #[serde(default)]
+    status_code: Option<u16>,
+    #[serde(default)]
+    error_message: Option<String>,
+    /// Hex public key of the agent
+    peer_id: String,
+    /// Hex Ed25519 signature
+    signature: String,
+}
+
+/// Where results submitted by external agents go
+#[derive(Clone)]
+pub struct AgentResults {
+    agents: Arc<Vec<AgentConfig>>,
+    result_tx: QueueSender<CheckResult>,
+}
+
+impl AgentResults {
+    /// Hand results of `agents` to the pipeline through `result_tx`
+    pub fn new(agents: Vec<AgentConfig>, result_tx: QueueSender<CheckResult>) -> Self {
+        Self { agents: Arc::new(agents), result_tx }
+    }
+}
+
+/// Take agent results on `bind` until the task is aborted, over TLS when
+/// `tls` is given
+pub async fn serve(
+    bind: &str,
+    tls: Option<Arc<ApiTls>>,
+    database: Arc<dyn Database>,
+    agents: AgentResults,
+) -> Result<JoinHandle<()>> {
+    let state = web::Data::new((database, agents));
+    let api = http::Api::new("Agent results API", ApiScope::AgentResults, move |config| {
+        config
+            .app_data(state.clone())
+            .app_data(web::PayloadConfig::new(MAX_BODY_BYTES))
+            .service(http::resource("/api/v1/results").route(web::post().to(submit)));
+    });
+    http::serve(api, bind, tls).await
+}
+
+async fn submit(
+    state: web::Data<(Arc<dyn Database>, AgentResults)>,
+    body: web::Bytes,
+) -> HandlerResult {
+    let (database, agents) = state.as_ref();
+    Ok(agent_result(&body, database.as_ref(), agents).await?)
+}
+
+/// Accept a result an external agent checked and signed, passing it on to
+/// the result pipeline
+async fn agent_result(
+    body: &[u8],
+    database: &dyn Database,
+    agents: &AgentResults,
+) -> Result<Reply> {
+    let input: AgentResultInput = match serde_json::from_slice(body) {
+        Ok(input) => input,
+        Err(e) => return Ok(error(StatusCode::BAD_REQUEST, &format!("Invalid result: {e}"))),
+    };
+    let Some(agent) = agents
+        .agents
+        .iter()
+        .find(|agent| agent.public_key.eq_ignore_ascii_case(&input.peer_id))
+    else {
+        return Ok(error(StatusCode::FORBIDDEN, "Unknown agent"));
+    };
+    if !agent.monitors.is_empty() && !agent.monitors.contains(&input.monitor_id) {
+        return Ok(error(StatusCode::FORBIDDEN, "Agent may not report on this monitor"));
+    }
+    let monitor = database.get_monitor_by_uuid(input.monitor_id).await?;
+    let Some(monitor) = monitor.filter(|monitor| monitor.enabled) else {
+        return Ok(error(StatusCode::NOT_FOUND, "Monitor not found"));
+    };
+
+    let result = match agent_check_result(input, agent, &monitor, SystemTime::now()) {
+        Ok(result) => result,
+        Err(response) => return Ok(response),
+    };
+    if database
+        .has_result(result.monitor_id, &result.peer_id, result.timestamp)
+        .await?
+    {
+        return Ok(error(StatusCode::CONFLICT, "Result already submitted"));
+    }
+
+    debug!("Accepted a result for {} from agent '{}'", monitor.name, agent.name);
+    agents
+        .result_tx
+        .send(result)
+        .await
+        .map_err(|_| anyhow!("Result pipeline stopped"))?;
+    Ok(HttpResponse::Accepted().json(json!({ "ok": true })))
+}
+
+/// The result an agent submitted for `monitor`, if it is recent and signed by
+/// the agent
+fn agent_check_result(
+    input: AgentResultInput,
+    agent: &AgentConfig,
+    monitor: &Monitor,
+    now: SystemTime,
+) -> Result<CheckResult, Reply> {
+    let Some(timestamp) = UNIX_EPOCH
+        .checked_add(Duration::from_secs(input.timestamp))
+        .filter(|at| *at <= now + AGENT_MAX_SKEW && *at + AGENT_MAX_AGE >= now)
+    else {
+        return Err(error(StatusCode::BAD_REQUEST, "Result is too old or dated in the future"));
+    };
+    let public_key = hex::decode(&agent.public_key).ok().and_then(|key| key.try_into().ok());
+    let Some(public_key) = public_key else {
+        warn!("Agent '{}' has an invalid public key", agent.name);
+        return Err(error(StatusCode::FORBIDDEN, "Unknown agent"));
+    };
+    let Ok(signature) = hex::decode(&input.signature) else {
+        return Err(error(StatusCode::BAD_REQUEST, "Signature is not hex"));
+    };
+
+    let mut result = CheckResult::new(monitor.uuid, monitor.target.clone(), input.peer_id);
+    result.timestamp = timestamp;
+    result.status = input.status;
+    result.latency_ms = input.latency_ms;
+    result.status_code = input.status_code;
+    result.error_message = input.error_message;
+    result.signature = Some(signature);
+    result.agent = true;
+    match verify_check_result(&result, &public_key) {
+        Ok(true) => Ok(result),
+        _ => Err(error(StatusCode::FORBIDDEN, "Invalid signature")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_check_result() {
+        use crate::crypto::keys::generate_keypair;
+        use crate::crypto::signing::agent_result_bytes;
+        use ed25519_dalek::Signer;
+
+        let keypair = generate_keypair();
+        let agent = AgentConfig {
+            name: "tokyo".to_string(),
+            public_key: keypair.public_key_hex(),
+            monitors: Vec::new(),
+        };
+        let monitor =
+            Monitor::new("API".to_string(), "https://example.com".to_string(), "https".to_string());
+        let now = UNIX_EPOCH + Duration::from_secs(1_791_979_200);
+
+        // What the agent checked, signed over the monitor's target
+        let mut signed =
+            CheckResult::new(monitor.uuid, monitor.target.clone(), keypair.public_key_hex())
+                .success(120, Some(200));
+        signed.timestamp = now - Duration::from_secs(30);
+        let message = agent_result_bytes(&signed).unwrap();
+        let signature = hex::encode(keypair.signing_key.sign(&message).to_bytes());
+        let input = |timestamp: u64, signature: &str| AgentResultInput {
+            monitor_id: monitor.uuid,
+            timestamp,
+            status: MonitorStatus::Up,
+            latency_ms: Some(120),
+            status_code: Some(200),
+            error_message: None,
+            peer_id: keypair.public_key_hex(),
+            signature: signature.to_string(),
+        };
+        let timestamp = signed.timestamp.duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+        let result =
+            agent_check_result(input(timestamp, &signature), &agent, &monitor, now).unwrap();
+        assert_eq!(result.target, monitor.target);
+        assert_eq!(result.timestamp, signed.timestamp);
+        assert_eq!(result.latency_ms, Some(120));
+        assert_eq!(result.peer_id, keypair.public_key_hex());
+        assert!(result.agent);
+
+        // A changed timestamp breaks the signature, and stale results are refused
+        let rejected =
+            |input, now| agent_check_result(input, &agent, &monitor, now).unwrap_err().status();
+        assert_eq!(rejected(input(timestamp + 1, &signature), now), StatusCode::FORBIDDEN);
+        let tampered = AgentResultInput {
+            error_message: Some("connection refused".to_string()),
+            ..input(timestamp, &signature)
+        };
+        assert_eq!(rejected(tampered, now), StatusCode::FORBIDDEN);
+        assert_eq!(rejected(input(timestamp, "zz"), now), StatusCode::BAD_REQUEST);
+        let later = now + AGENT_MAX_AGE;
+        assert_eq!(rejected(input(timestamp, &signature), later), StatusCode::BAD_REQUEST);
+        let earlier = now - AGENT_MAX_SKEW - Duration::from_secs(60);
+        assert_eq!(rejected(input(timestamp, &signature), earlier), StatusCode::BAD_REQUEST);
+        // Checked before the signature, so it must not panic for any caller
+        assert_eq!(rejected(input(u64::MAX, &signature), now), StatusCode::BAD_REQUEST);
+    }
+}
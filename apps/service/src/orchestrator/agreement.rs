@@ -0,0 +1,182 @@
+/// Result agreement - how far verified peers confirm our own results
+///
+/// Results are grouped into fixed time buckets per monitor. For each bucket the
+/// aggregator compares our latest status with the latest status of every
+/// verified peer and stores the counts in `result_agreement`, where the API
+/// and TUI read them (e.g. "4/5 peers agree up"). Our results checked during
+/// maintenance are left out, so a bucket spent in maintenance gets no counts.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use super::runtime::RuntimeSettings;
+use crate::database::Database;
+use crate::database::models::{MonitorVisibility, ResultAgreement};
+use crate::journal::{Journal, JournalEntry};
+use crate::monitoring::types::MonitorStatus;
+
+/// Width of an agreement bucket
+const BUCKET: Duration = Duration::from_secs(5 * 60);
+
+/// Start of the bucket `time` falls into
+fn bucket_start(time: SystemTime) -> SystemTime {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    UNIX_EPOCH + Duration::from_secs(secs - secs % BUCKET.as_secs())
+}
+
+/// Our latest status in a bucket, the number of peers with a usable status and
+/// how many of them agree; `None` without local results
+pub fn compute_agreement(
+    local: &[(SystemTime, MonitorStatus)],
+    peers: &[(String, SystemTime, MonitorStatus)],
+) -> Option<(MonitorStatus, u32, u32)> {
+    let (_, local_status) = local.iter().max_by_key(|(timestamp, _)| *timestamp)?;
+
+    let mut latest: HashMap<&str, (SystemTime, MonitorStatus)> = HashMap::new();
+    for (peer_id, timestamp, status) in peers {
+        let entry = latest.entry(peer_id).or_insert((*timestamp, *status));
+        if *timestamp > entry.0 {
+            *entry = (*timestamp, *status);
+        }
+    }
+
+    let ours = local_status.is_available();
+    let mut total = 0;
+    let mut agreeing = 0;
+    for (_, status) in latest.values() {
+        let Some(theirs) = status.is_available() else {
+            continue;
+        };
+        total += 1;
+        if Some(theirs) == ours {
+            agreeing += 1;
+        }
+    }
+
+    Some((*local_status, total, agreeing))
+}
+
+/// Task recomputing agreement for recent buckets
+pub struct AgreementAggregator {
+    database: Arc<dyn Database>,
+    /// Journal buckets are recorded in until saved
+    journal: Option<Arc<Journal>>,
+}
+
+impl AgreementAggregator {
+    /// Create an aggregator for the given database
+    pub fn new(database: Arc<dyn Database>) -> Self {
+        Self { database, journal: None }
+    }
+
+    /// Record buckets in the journal until they are saved
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Spawn the aggregator; it runs once per bucket while results are shared
+    /// with peers, and stops once the settings channel closes
+    pub fn spawn(self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(BUCKET);
+
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => {}
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+
+                if !settings.borrow().p2p_sharing {
+                    continue;
+                }
+
+                // Peer results can arrive late, so the previous bucket is refreshed too
+                let current = bucket_start(SystemTime::now());
+                for start in [current - BUCKET, current] {
+                    if let Err(e) = self.aggregate(start).await {
+                        warn!("Failed to aggregate result agreement: {}", e);
+                    }
+                }
+            }
+        })
+    }
+
+    async fn aggregate(&self, start: SystemTime) -> anyhow::Result<()> {
+        let end = start + BUCKET;
+
+        for monitor in self.database.get_enabled_monitors().await? {
+            // Peers no longer check private monitors alongside us
+            if monitor.visibility == MonitorVisibility::Private {
+                continue;
+            }
+
+            let local = self.database.get_local_statuses(monitor.uuid, start, end).await?;
+            let peers = self.database.get_verified_peer_statuses(monitor.uuid, start, end).await?;
+            let Some((local_status, peers_total, peers_agreeing)) =
+                compute_agreement(&local, &peers)
+            else {
+                continue;
+            };
+
+            let agreement = ResultAgreement {
+                monitor_uuid: monitor.uuid,
+                bucket_start: start,
+                bucket_seconds: BUCKET.as_secs(),
+                local_status,
+                peers_total,
+                peers_agreeing,
+                updated_at: SystemTime::now(),
+            };
+            debug!("Monitor {}: {}", monitor.uuid, agreement.summary());
+            let journal_id = self
+                .journal
+                .as_ref()
+                .map(|journal| journal.begin(JournalEntry::Agreement(agreement.clone())));
+            self.database.save_result_agreement(&agreement).await?;
+            if let (Some(journal), Some(id)) = (&self.journal, journal_id) {
+                journal.done(id);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_agreement() {
+        let t0 = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let t1 = t0 + Duration::from_secs(30);
+        let local = [(t0, MonitorStatus::Down), (t1, MonitorStatus::Up)];
+        let peers = [
+            ("a".to_string(), t0, MonitorStatus::Up),
+            ("b".to_string(), t0, MonitorStatus::Down),
+            // Only a peer's latest status counts
+            ("b".to_string(), t1, MonitorStatus::Degraded),
+            ("c".to_string(), t0, MonitorStatus::Down),
+            ("d".to_string(), t0, MonitorStatus::Unknown),
+        ];
+
+        assert_eq!(compute_agreement(&local, &peers), Some((MonitorStatus::Up, 3, 2)));
+        assert_eq!(compute_agreement(&[], &peers), None);
+        assert_eq!(compute_agreement(&local, &[]), Some((MonitorStatus::Up, 0, 0)));
+    }
+
+    #[test]
+    fn test_bucket_start() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_000_123);
+        assert_eq!(bucket_start(time), UNIX_EPOCH + Duration::from_secs(1_000_000 - 100));
+    }
+}
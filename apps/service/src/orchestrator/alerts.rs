@@ -0,0 +1,265 @@
+/// Alerts - notifies a monitor's channels when its status changes
+///
+/// The pipeline hands every stored result to the notifier. When a monitor goes
+/// down, recovers or degrades, the channels its notification routes pick are
+/// alerted, each in its own task so a slow channel does not hold up the others.
+/// A monitor down for longer than its escalation policy allows is escalated to
+/// the policy's channels once, and they hear about the recovery too.
+/// Unknown results and results checked during maintenance leave the last
+/// status as it is, and the first result after start only sets it, so
+/// restarts do not alert. A monitor still down once its maintenance ends is
+/// alerted about then.
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::config::NotificationsConfig;
+use crate::database::Database;
+use crate::database::models::NotificationRoute;
+use crate::monitoring::CheckResult;
+use crate::monitoring::types::MonitorStatus;
+use crate::notifications::{self, Notification};
+
+/// A monitor that is down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Outage {
+    /// When the first down result was checked
+    since: SystemTime,
+    /// Whether the outage was escalated
+    escalated: bool,
+}
+
+/// How a result moves a monitor's outage along
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutageStep {
+    /// Still down for this long, and not escalated yet
+    Down(Duration),
+    /// Up again after an escalated outage
+    Recovered,
+}
+
+/// Task alerting notification channels about status changes
+pub struct AlertNotifier {
+    database: Arc<dyn Database>,
+    config: NotificationsConfig,
+    /// Last known status per monitor
+    last_status: HashMap<Uuid, MonitorStatus>,
+    /// Monitors currently down
+    outages: HashMap<Uuid, Outage>,
+}
+
+impl AlertNotifier {
+    /// Create a notifier if alerts are enabled
+    pub fn new(database: Arc<dyn Database>, config: &NotificationsConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            database,
+            config: *config,
+            last_status: HashMap::new(),
+            outages: HashMap::new(),
+        })
+    }
+
+    /// Spawn the notifier, returning the sender the pipeline feeds it through
+    ///
+    /// The task exits once the sender is dropped; alerts still being retried
+    /// are abandoned.
+    pub fn spawn(mut self) -> (mpsc::Sender<CheckResult>, JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::channel::<CheckResult>(256);
+
+        let handle = tokio::spawn(async move {
+            while let Some(result) = rx.recv().await {
+                let change = transition(&mut self.last_status, &result);
+                let step = outage_step(&mut self.outages, &result);
+                if change.is_none() && step.is_none() {
+                    continue;
+                }
+                if let Err(e) = self.alert(change, step, &result).await {
+                    warn!("Failed to alert about {}: {}", result.target, e);
+                }
+            }
+            debug!("Alert notifier stopped");
+        });
+
+        (tx, handle)
+    }
+
+    async fn alert(
+        &mut self,
+        change: Option<MonitorStatus>,
+        step: Option<OutageStep>,
+        result: &CheckResult,
+    ) -> Result<()> {
+        let Some(monitor) = self.database.get_monitor_by_uuid(result.monitor_id).await? else {
+            return Ok(());
+        };
+        let routes = self.database.get_notification_routes().await?;
+        let routing = NotificationRoute::resolve(&monitor, &routes);
+
+        let mut alerts = Vec::new();
+        if let Some(previous) = change {
+            let mut channels = routing.channels.clone();
+            if let (Some(OutageStep::Recovered), Some(policy)) = (step, &routing.escalation) {
+                for uuid in &policy.channels {
+                    if !channels.contains(uuid) {
+                        channels.push(*uuid);
+                    }
+                }
+            }
+            alerts.push((channels, Notification::status_change(&monitor.name, previous, result)));
+        }
+        if let (Some(OutageStep::Down(down_for)), Some(policy)) = (step, &routing.escalation)
+            && down_for >= Duration::from_secs(policy.after_secs)
+        {
+            if let Some(outage) = self.outages.get_mut(&result.monitor_id) {
+                outage.escalated = true;
+            }
+            alerts.push((
+                policy.channels.clone(),
+                Notification::escalation(&monitor.name, down_for, result),
+            ));
+        }
+        if alerts.iter().all(|(channels, _)| channels.is_empty()) {
+            return Ok(());
+        }
+
+        let channels = self.database.get_notification_channels().await?;
+        let retry_delay = Duration::from_secs(self.config.retry_delay_secs);
+        let max_attempts = self.config.max_attempts.max(1);
+        for (targets, notification) in alerts {
+            let notification = Arc::new(notification);
+            for channel in channels.iter().filter(|c| c.enabled && targets.contains(&c.uuid)) {
+                let channel = channel.clone();
+                let notification = notification.clone();
+                tokio::spawn(async move {
+                    match notifications::send_with_retry(
+                        &channel,
+                        &notification,
+                        max_attempts,
+                        retry_delay,
+                    )
+                    .await
+                    {
+                        Ok(()) => debug!("Sent '{}' to '{}'", notification.title, channel.name),
+                        Err(e) => warn!(
+                            "Giving up alerting '{}' about '{}': {}",
+                            channel.name, notification.title, e
+                        ),
+                    }
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Previous status if the result changes the monitor's known status
+fn transition(
+    last_status: &mut HashMap<Uuid, MonitorStatus>,
+    result: &CheckResult,
+) -> Option<MonitorStatus> {
+    if result.status == MonitorStatus::Unknown || result.maintenance {
+        return None;
+    }
+    let previous = last_status.insert(result.monitor_id, result.status)?;
+    (previous != result.status).then_some(previous)
+}
+
+/// Track a monitor's outage through a result
+///
+/// Unknown results and results checked during maintenance leave it as it is.
+fn outage_step(outages: &mut HashMap<Uuid, Outage>, result: &CheckResult) -> Option<OutageStep> {
+    if result.status == MonitorStatus::Unknown || result.maintenance {
+        return None;
+    }
+    if result.status != MonitorStatus::Down {
+        let outage = outages.remove(&result.monitor_id)?;
+        return outage.escalated.then_some(OutageStep::Recovered);
+    }
+    let outage = outages
+        .entry(result.monitor_id)
+        .or_insert(Outage { since: result.timestamp, escalated: false });
+    let down_for = result.timestamp.duration_since(outage.since).unwrap_or_default();
+    (!outage.escalated).then_some(OutageStep::Down(down_for))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transition() {
+        let mut last_status = HashMap::new();
+        let monitor_id = Uuid::new_v4();
+        let mut next = |status, maintenance| {
+            let mut result = CheckResult::new(
+                monitor_id,
+                "https://example.com".to_string(),
+                "local".to_string(),
+            );
+            result.status = status;
+            result.maintenance = maintenance;
+            transition(&mut last_status, &result)
+        };
+
+        // The first result after start only sets the status
+        assert_eq!(next(MonitorStatus::Up, false), None);
+        assert_eq!(next(MonitorStatus::Up, false), None);
+        assert_eq!(next(MonitorStatus::Down, false), Some(MonitorStatus::Up));
+        assert_eq!(next(MonitorStatus::Unknown, false), None);
+        assert_eq!(next(MonitorStatus::Degraded, false), Some(MonitorStatus::Down));
+        assert_eq!(next(MonitorStatus::Up, false), Some(MonitorStatus::Degraded));
+
+        // Outages during maintenance are not alerted, only one lasting past it
+        assert_eq!(next(MonitorStatus::Down, true), None);
+        assert_eq!(next(MonitorStatus::Up, false), None);
+        assert_eq!(next(MonitorStatus::Down, true), None);
+        assert_eq!(next(MonitorStatus::Down, false), Some(MonitorStatus::Up));
+    }
+
+    #[test]
+    fn test_outage_step() {
+        let mut outages = HashMap::new();
+        let monitor_id = Uuid::new_v4();
+        let start = SystemTime::now();
+        let at = |status, secs| {
+            let mut result = CheckResult::new(
+                monitor_id,
+                "https://example.com".to_string(),
+                "local".to_string(),
+            );
+            result.status = status;
+            result.timestamp = start + Duration::from_secs(secs);
+            result
+        };
+        let minute = Duration::from_secs(60);
+
+        assert_eq!(outage_step(&mut outages, &at(MonitorStatus::Up, 0)), None);
+        assert_eq!(
+            outage_step(&mut outages, &at(MonitorStatus::Down, 60)),
+            Some(OutageStep::Down(Duration::ZERO))
+        );
+        assert_eq!(outage_step(&mut outages, &at(MonitorStatus::Unknown, 90)), None);
+        assert_eq!(
+            outage_step(&mut outages, &at(MonitorStatus::Down, 120)),
+            Some(OutageStep::Down(minute))
+        );
+
+        // Recovering from an outage that was not escalated needs nothing extra
+        assert_eq!(outage_step(&mut outages, &at(MonitorStatus::Up, 180)), None);
+
+        outage_step(&mut outages, &at(MonitorStatus::Down, 240));
+        outages.get_mut(&monitor_id).unwrap().escalated = true;
+        assert_eq!(outage_step(&mut outages, &at(MonitorStatus::Down, 300)), None);
+        assert_eq!(
+            outage_step(&mut outages, &at(MonitorStatus::Degraded, 360)),
+            Some(OutageStep::Recovered)
+        );
+        assert!(outages.is_empty());
+    }
+}
@@ -0,0 +1,786 @@
+/// Management API - manages monitors, their notification routing and community
+/// domains, and reads incidents over REST
+///
+/// `GET /api/v1/monitors` lists the monitors and `GET /api/v1/monitors/<uuid>`
+/// returns one. `POST /api/v1/monitors` creates a monitor from a JSON body
+/// with at least `name`, `target` and `check_type`; `PUT
+/// /api/v1/monitors/<uuid>` changes the fields it is given, so `{"enabled":
+/// false}` pauses a monitor; `DELETE /api/v1/monitors/<uuid>` deletes it.
+/// `GET /api/v1/monitors/<uuid>/routing` tells where the monitor's alerts go,
+/// and `GET /api/v1/monitors/<uuid>/rollups/<hourly|daily>` returns its
+/// stored result rollups of the last 90 days, up to the last complete hour.
+///
+/// `GET /api/v1/routes` lists the notification routes; `GET`, `PUT` and
+/// `DELETE /api/v1/routes/<scope>` read, replace and delete the route of a
+/// scope, which is `default`, `tag:<tag>` or `monitor:<uuid>`. A route body
+/// has `channels` and an `escalation` of `{"after_secs", "channels"}`, or
+/// `null` to inherit it. Path segments are percent-decoded, so
+/// `/api/v1/routes/tag%3Aprod` is the route of `tag:prod`.
+///
+/// `POST /api/v1/heartbeat/<token>` pings the heartbeat monitor with that
+/// token. It needs no bearer token, as the token in the path is the secret;
+/// heartbeat monitors created without a target are given a random one.
+///
+/// `GET /api/v1/community` lists the community domains and who takes part;
+/// `POST /api/v1/community` with `{"target"}` joins monitoring one and
+/// `DELETE /api/v1/community/<host>` leaves it.
+///
+/// `GET /api/v1/incidents` lists the most recent incidents and `GET
+/// /api/v1/incidents/<uuid>` returns one with its timeline of `updates`.
+///
+/// Inputs are validated like the CLI's, changes are audited, and the
+/// scheduler is told to reload monitors after every monitor change. Requests need
+/// the configured bearer token; without one, the API is only served when
+/// clients must present a certificate.
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::http::header::{self, HeaderMap};
+use actix_web::middleware::{Next, from_fn};
+use actix_web::{HttpResponse, web};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use super::api_tls::ApiTls;
+use super::community;
+use super::http::{self, HandlerResult, Reply, error};
+use super::reload::ReloadRequest;
+use crate::config::ApiScope;
+use crate::database::Database;
+use crate::database::models::{
+    AuditEvent, CommunityDomain, EscalationPolicy, Incident, IncidentUpdate, Monitor,
+    MonitorVisibility, NotificationRoute, PublicMonitorGroup, ResultRollup, RollupPeriod,
+    RouteScope,
+};
+use crate::validation;
+
+/// Largest request body accepted
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Fields of a monitor a request sets; absent fields are left as they are
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MonitorInput {
+    name: Option<String>,
+    target: Option<String>,
+    check_type: Option<String>,
+    interval_seconds: Option<u64>,
+    timeout_seconds: Option<u64>,
+    grace_seconds: Option<u64>,
+    enabled: Option<bool>,
+    /// `null` falls back to the global retention
+    #[serde(default, deserialize_with = "present")]
+    retention_days: Option<Option<u64>>,
+    visibility: Option<MonitorVisibility>,
+    tags: Option<Vec<String>>,
+    expected_status_codes: Option<Vec<String>>,
+    headers: Option<BTreeMap<String, String>>,
+}
+
+/// A notification route a request sets, replacing the whole route
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RouteInput {
+    /// Empty inherits the channels
+    #[serde(default)]
+    channels: Vec<Uuid>,
+    /// `null` or absent inherits the escalation policy
+    #[serde(default)]
+    escalation: Option<EscalationPolicy>,
+}
+
+/// A community domain a request joins
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JoinInput {
+    /// Host or URL of the domain
+    target: String,
+}
+
+/// Deserialize a field that is present, telling `null` apart from absent
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+/// Serve the management API on `bind` until the task is aborted, over TLS
+/// when `tls` is given
+pub async fn serve(
+    bind: &str,
+    tls: Option<Arc<ApiTls>>,
+    token: Option<String>,
+    database: Arc<dyn Database>,
+    reload_tx: mpsc::Sender<ReloadRequest>,
+    heartbeats: mpsc::Sender<Uuid>,
+) -> Result<JoinHandle<()>> {
+    let state = ApiState { token: token.map(Arc::new), database, reload_tx, heartbeats };
+    http::serve(api(state), bind, tls).await
+}
+
+fn api(state: ApiState) -> http::Api {
+    let state = web::Data::new(state);
+    http::Api::new("Management API", ApiScope::Monitors, move |config| {
+        config
+            .app_data(state.clone())
+            .app_data(web::PayloadConfig::new(MAX_BODY_BYTES))
+            // Registered first, so the heartbeat route is matched outside the
+            // token-guarded scope
+            .service(http::resource("/api/v1/heartbeat/{token}").route(web::post().to(heartbeat)))
+            .service(
+                web::scope("/api/v1")
+                    .wrap(from_fn(require_token))
+                    .service(
+                        http::resource("/monitors")
+                            .route(web::get().to(list_monitors))
+                            .route(web::post().to(create_monitor)),
+                    )
+                    .service(
+                        http::resource("/monitors/{uuid}")
+                            .route(web::get().to(get_monitor))
+                            .route(web::put().to(update_monitor))
+                            .route(web::delete().to(delete_monitor)),
+                    )
+                    .service(http::resource("/monitors/{uuid}/routing").route(web::get().to(routing)))
+                    .service(
+                        http::resource("/monitors/{uuid}/rollups/{period}")
+                            .route(web::get().to(rollups)),
+                    )
+                    .service(http::resource("/routes").route(web::get().to(list_routes)))
+                    .service(
+                        http::resource("/routes/{scope}")
+                            .route(web::get().to(get_route))
+                            .route(web::put().to(set_route))
+                            .route(web::delete().to(delete_route)),
+                    )
+                    .service(
+                        http::resource("/community")
+                            .route(web::get().to(list_community))
+                            .route(web::post().to(join_community)),
+                    )
+                    .service(
+                        http::resource("/community/{host}").route(web::delete().to(leave_community)),
+                    )
+                    .service(http::resource("/incidents").route(web::get().to(list_incidents)))
+                    .service(http::resource("/incidents/{uuid}").route(web::get().to(get_incident))),
+            );
+    })
+}
+
+#[derive(Clone)]
+struct ApiState {
+    token: Option<Arc<String>>,
+    database: Arc<dyn Database>,
+    reload_tx: mpsc::Sender<ReloadRequest>,
+    heartbeats: mpsc::Sender<Uuid>,
+}
+
+impl ApiState {
+    /// Tell the scheduler monitors changed
+    async fn reload(&self) {
+        let _ = self.reload_tx.send(ReloadRequest::Monitors).await;
+    }
+}
+
+/// Refuse requests without the bearer token, when one is configured
+async fn require_token(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let token = request.app_data::<web::Data<ApiState>>().and_then(|state| state.token.clone());
+    if token.is_some_and(|token| !authorized(request.headers(), &token)) {
+        let response = error(StatusCode::UNAUTHORIZED, "Missing or wrong bearer token");
+        return Ok(request.into_response(response).map_into_right_body());
+    }
+    Ok(next.call(request).await?.map_into_left_body())
+}
+
+async fn list_monitors(state: web::Data<ApiState>) -> HandlerResult {
+    let monitors = state.database.get_monitors().await?;
+    Ok(ok(Value::Array(monitors.iter().map(monitor_body).collect())))
+}
+
+async fn get_monitor(state: web::Data<ApiState>, uuid: web::Path<String>) -> HandlerResult {
+    Ok(match find_monitor(state.database.as_ref(), &uuid).await? {
+        Some(monitor) => ok(monitor_body(&monitor)),
+        None => monitor_not_found(),
+    })
+}
+
+async fn create_monitor(state: web::Data<ApiState>, body: web::Bytes) -> HandlerResult {
+    let input = match parse_input(&body) {
+        Ok(input) => input,
+        Err(response) => return Ok(response),
+    };
+    let heartbeat = input
+        .check_type
+        .as_deref()
+        .is_some_and(|check_type| check_type.trim().eq_ignore_ascii_case(Monitor::HEARTBEAT));
+    let target = match &input.target {
+        Some(target) => Some(target.clone()),
+        None if heartbeat => Some(Monitor::heartbeat_token()),
+        None => None,
+    };
+    let (Some(name), Some(target), Some(check_type)) =
+        (input.name.clone(), target, input.check_type.clone())
+    else {
+        return Ok(error(StatusCode::BAD_REQUEST, "name, target and check_type are required"));
+    };
+
+    let mut monitor = Monitor::new(name, target, check_type);
+    if let Err(msg) = apply(&input, &mut monitor) {
+        return Ok(error(StatusCode::BAD_REQUEST, &msg));
+    }
+    monitor.id = Some(state.database.save_monitor(&monitor).await?);
+    state
+        .database
+        .append_audit_event(&AuditEvent::admin(format!(
+            "Added monitor '{}' ({}) via API",
+            monitor.name, monitor.uuid
+        )))
+        .await?;
+    state.reload().await;
+
+    Ok(HttpResponse::Created().json(monitor_body(&monitor)))
+}
+
+async fn update_monitor(
+    state: web::Data<ApiState>,
+    uuid: web::Path<String>,
+    body: web::Bytes,
+) -> HandlerResult {
+    let Some(mut monitor) = find_monitor(state.database.as_ref(), &uuid).await? else {
+        return Ok(monitor_not_found());
+    };
+    let input = match parse_input(&body) {
+        Ok(input) => input,
+        Err(response) => return Ok(response),
+    };
+    if let Err(msg) = apply(&input, &mut monitor) {
+        return Ok(error(StatusCode::BAD_REQUEST, &msg));
+    }
+    monitor.updated_at = SystemTime::now();
+    state.database.save_monitor(&monitor).await?;
+    state
+        .database
+        .append_audit_event(&AuditEvent::admin(format!(
+            "Updated monitor '{}' ({}) via API",
+            monitor.name, monitor.uuid
+        )))
+        .await?;
+    state.reload().await;
+
+    Ok(ok(monitor_body(&monitor)))
+}
+
+async fn delete_monitor(state: web::Data<ApiState>, uuid: web::Path<String>) -> HandlerResult {
+    let Some(monitor) = find_monitor(state.database.as_ref(), &uuid).await? else {
+        return Ok(monitor_not_found());
+    };
+    state.database.delete_monitor(monitor.uuid).await?;
+    state
+        .database
+        .append_audit_event(&AuditEvent::admin(format!(
+            "Deleted monitor '{}' ({}) via API",
+            monitor.name, monitor.uuid
+        )))
+        .await?;
+    state.reload().await;
+
+    Ok(ok(json!({ "ok": true })))
+}
+
+async fn list_routes(state: web::Data<ApiState>) -> HandlerResult {
+    let routes = state.database.get_notification_routes().await?;
+    Ok(ok(Value::Array(routes.iter().map(route_body).collect())))
+}
+
+async fn get_route(state: web::Data<ApiState>, scope: web::Path<String>) -> HandlerResult {
+    let scope = match scope.parse::<RouteScope>() {
+        Ok(scope) => scope,
+        Err(e) => return Ok(error(StatusCode::NOT_FOUND, &e.to_string())),
+    };
+    let routes = state.database.get_notification_routes().await?;
+    Ok(match routes.iter().find(|route| route.scope == scope) {
+        Some(route) => ok(route_body(route)),
+        None => ok(route_body(&NotificationRoute::new(scope))),
+    })
+}
+
+async fn set_route(
+    state: web::Data<ApiState>,
+    scope: web::Path<String>,
+    body: web::Bytes,
+) -> HandlerResult {
+    let scope = match scope.parse::<RouteScope>() {
+        Ok(scope) => scope,
+        Err(e) => return Ok(error(StatusCode::NOT_FOUND, &e.to_string())),
+    };
+    let input: RouteInput = match serde_json::from_slice(&body) {
+        Ok(input) => input,
+        Err(e) => {
+            return Ok(error(StatusCode::BAD_REQUEST, &format!("Invalid route JSON: {e}")));
+        }
+    };
+    let database = state.database.as_ref();
+    if let RouteScope::Monitor(uuid) = scope
+        && database.get_monitor_by_uuid(uuid).await?.is_none()
+    {
+        return Ok(monitor_not_found());
+    }
+    let known: Vec<Uuid> =
+        database.get_notification_channels().await?.iter().map(|c| c.uuid).collect();
+    let escalation = input.escalation.iter().flat_map(|policy| &policy.channels);
+    if let Some(unknown) =
+        input.channels.iter().chain(escalation).find(|uuid| !known.contains(uuid))
+    {
+        return Ok(error(StatusCode::BAD_REQUEST, &format!("Unknown channel {unknown}")));
+    }
+
+    let route = NotificationRoute {
+        channels: input.channels,
+        escalation: input.escalation,
+        ..NotificationRoute::new(scope)
+    };
+    database.save_notification_route(&route).await?;
+    database
+        .append_audit_event(&AuditEvent::admin(format!(
+            "Set notification route {} via API",
+            route.scope
+        )))
+        .await?;
+    Ok(ok(route_body(&route)))
+}
+
+async fn delete_route(state: web::Data<ApiState>, scope: web::Path<String>) -> HandlerResult {
+    let scope = match scope.parse::<RouteScope>() {
+        Ok(scope) => scope,
+        Err(e) => return Ok(error(StatusCode::NOT_FOUND, &e.to_string())),
+    };
+    if !state.database.delete_notification_route(&scope).await? {
+        return Ok(error(StatusCode::NOT_FOUND, "Route not found"));
+    }
+    state
+        .database
+        .append_audit_event(&AuditEvent::admin(format!(
+            "Deleted notification route {scope} via API"
+        )))
+        .await?;
+    Ok(ok(json!({ "ok": true })))
+}
+
+/// Answer a request for where a monitor's alerts go
+async fn routing(state: web::Data<ApiState>, uuid: web::Path<String>) -> HandlerResult {
+    let database = state.database.as_ref();
+    let Some(monitor) = find_monitor(database, &uuid).await? else {
+        return Ok(monitor_not_found());
+    };
+
+    let routing = NotificationRoute::resolve(&monitor, &database.get_notification_routes().await?);
+    Ok(ok(json!({
+        "channels": routing.channels,
+        "channels_from": routing.channels_from,
+        "escalation": routing.escalation,
+        "escalation_from": routing.escalation_from,
+    })))
+}
+
+/// Days of rollups returned
+const ROLLUP_DAYS: u64 = 90;
+
+async fn rollups(state: web::Data<ApiState>, path: web::Path<(String, String)>) -> HandlerResult {
+    let (uuid, period) = path.into_inner();
+    let Ok(period) = period.parse::<RollupPeriod>() else {
+        return Ok(error(StatusCode::NOT_FOUND, "Rollups are hourly or daily"));
+    };
+    let Some(monitor) = find_monitor(state.database.as_ref(), &uuid).await? else {
+        return Ok(monitor_not_found());
+    };
+
+    let start = SystemTime::now() - Duration::from_secs(ROLLUP_DAYS * 24 * 60 * 60);
+    let rollups = state.database.get_rollups(monitor.uuid, period, start).await?;
+    Ok(ok(Value::Array(rollups.iter().map(rollup_body).collect())))
+}
+
+/// The monitor with the given uuid, if the uuid is valid and it exists
+async fn find_monitor(database: &dyn Database, uuid: &str) -> Result<Option<Monitor>> {
+    match Uuid::parse_str(uuid) {
+        Ok(uuid) => database.get_monitor_by_uuid(uuid).await,
+        Err(_) => Ok(None),
+    }
+}
+
+fn parse_input(body: &[u8]) -> Result<MonitorInput, Reply> {
+    serde_json::from_slice(body)
+        .map_err(|e| error(StatusCode::BAD_REQUEST, &format!("Invalid monitor JSON: {e}")))
+}
+
+/// Apply the fields of a request to a monitor, validating the result
+fn apply(input: &MonitorInput, monitor: &mut Monitor) -> Result<(), String> {
+    if let Some(name) = &input.name {
+        monitor.name = name.trim().to_string();
+    }
+    if let Some(target) = &input.target {
+        monitor.target = target.trim().to_string();
+    }
+    if let Some(check_type) = &input.check_type {
+        monitor.check_type = check_type.trim().to_lowercase();
+    }
+    if let Some(interval) = input.interval_seconds {
+        monitor.interval_seconds = interval;
+    }
+    if let Some(timeout) = input.timeout_seconds {
+        monitor.timeout_seconds = timeout;
+    }
+    if let Some(grace) = input.grace_seconds {
+        monitor.grace_seconds = grace;
+    }
+    if let Some(enabled) = input.enabled {
+        monitor.enabled = enabled;
+    }
+    if let Some(retention_days) = input.retention_days {
+        monitor.retention_days = retention_days;
+    }
+    if let Some(visibility) = input.visibility {
+        monitor.visibility = visibility;
+    }
+    if let Some(tags) = &input.tags {
+        monitor.tags = Monitor::parse_tags(&tags.join(","));
+    }
+    if let Some(codes) = &input.expected_status_codes {
+        monitor.expected_status_codes = codes.iter().map(|code| code.trim().to_string()).collect();
+    }
+    if let Some(headers) = &input.headers {
+        monitor.headers = headers.clone();
+    }
+
+    let checks = [
+        validation::validate_monitor_name(&monitor.name),
+        validation::validate_monitor_target(&monitor.target, &monitor.check_type),
+        validation::validate_interval(monitor.interval_seconds),
+        validation::validate_timeout(monitor.timeout_seconds, monitor.interval_seconds),
+        validation::validate_grace(monitor.grace_seconds),
+        validation::validate_tags(&monitor.tags),
+        validation::validate_expected_status_codes(&monitor.expected_status_codes),
+        validation::validate_headers(&monitor.headers),
+    ];
+    match checks.into_iter().find(|check| !check.is_valid) {
+        Some(check) => Err(check.error.unwrap_or_else(|| "Invalid monitor".to_string())),
+        None => Ok(()),
+    }
+}
+
+fn monitor_body(monitor: &Monitor) -> Value {
+    json!({
+        "uuid": monitor.uuid,
+        "name": monitor.name,
+        "target": monitor.target,
+        "check_type": monitor.check_type,
+        "interval_seconds": monitor.interval_seconds,
+        "timeout_seconds": monitor.timeout_seconds,
+        "grace_seconds": monitor.grace_seconds,
+        "enabled": monitor.enabled,
+        "retention_days": monitor.retention_days,
+        "visibility": monitor.visibility,
+        "tags": monitor.tags,
+        "expected_status_codes": monitor.expected_status_codes,
+        "headers": monitor.headers,
+        "created_at": unix_secs(monitor.created_at),
+        "updated_at": unix_secs(monitor.updated_at),
+    })
+}
+
+/// Answer a heartbeat ping, passing it on to the heartbeat watcher
+async fn heartbeat(state: web::Data<ApiState>, token: web::Path<String>) -> HandlerResult {
+    // Compared as digests so the time taken does not leak tokens
+    let digest = Sha256::digest(token.as_bytes());
+    let monitor = state
+        .database
+        .get_enabled_monitors()
+        .await?
+        .into_iter()
+        .find(|monitor| monitor.is_heartbeat() && Sha256::digest(&monitor.target) == digest);
+    let Some(monitor) = monitor else {
+        return Ok(error(StatusCode::NOT_FOUND, "Unknown heartbeat token"));
+    };
+
+    state
+        .heartbeats
+        .send(monitor.uuid)
+        .await
+        .map_err(|_| anyhow!("Heartbeat watcher stopped"))?;
+    Ok(ok(json!({ "ok": true })))
+}
+
+async fn list_community(state: web::Data<ApiState>) -> HandlerResult {
+    let database = state.database.as_ref();
+    let listing = PublicMonitorGroup::listing(
+        &database.get_group_memberships().await?,
+        &database.get_community_domains().await?,
+        SystemTime::now(),
+    );
+    let body = listing
+        .into_iter()
+        .map(|(group, domain)| {
+            json!({
+                "host": group.host,
+                "participating_peers": group.participating_peers,
+                "joined": domain.as_ref().is_some_and(|domain| domain.joined),
+                "monitor_uuid": domain.and_then(|domain| domain.monitor_uuid),
+            })
+        })
+        .collect();
+    Ok(ok(Value::Array(body)))
+}
+
+async fn join_community(state: web::Data<ApiState>, body: web::Bytes) -> HandlerResult {
+    let input: JoinInput = match serde_json::from_slice(&body) {
+        Ok(input) => input,
+        Err(e) => return Ok(error(StatusCode::BAD_REQUEST, &format!("Invalid join JSON: {e}"))),
+    };
+    let database = state.database.as_ref();
+    let domain = match community::join(database, &input.target).await {
+        Ok(domain) => domain,
+        Err(e) => return Ok(error(StatusCode::BAD_REQUEST, &e.to_string())),
+    };
+    database
+        .append_audit_event(&AuditEvent::admin(format!(
+            "Joined monitoring {} via API",
+            domain.host
+        )))
+        .await?;
+    state.reload().await;
+    Ok(ok(domain_body(&domain)))
+}
+
+async fn leave_community(state: web::Data<ApiState>, host: web::Path<String>) -> HandlerResult {
+    let database = state.database.as_ref();
+    let Some(domain) = community::leave(database, &host).await? else {
+        return Ok(error(StatusCode::NOT_FOUND, "Not monitoring that domain"));
+    };
+    database
+        .append_audit_event(&AuditEvent::admin(format!("Left monitoring {} via API", domain.host)))
+        .await?;
+    state.reload().await;
+    Ok(ok(domain_body(&domain)))
+}
+
+/// Most recent incidents listed
+const INCIDENT_LIMIT: usize = 100;
+
+async fn list_incidents(state: web::Data<ApiState>) -> HandlerResult {
+    let incidents = state.database.get_incidents(INCIDENT_LIMIT).await?;
+    Ok(ok(Value::Array(incidents.iter().map(incident_body).collect())))
+}
+
+async fn get_incident(state: web::Data<ApiState>, uuid: web::Path<String>) -> HandlerResult {
+    let incident = match Uuid::parse_str(&uuid) {
+        Ok(uuid) => state.database.get_incident(uuid).await?,
+        Err(_) => None,
+    };
+    let Some(incident) = incident else {
+        return Ok(error(StatusCode::NOT_FOUND, "Incident not found"));
+    };
+    let updates = state.database.get_incident_updates(incident.uuid).await?;
+    let mut body = incident_body(&incident);
+    body["updates"] = updates.iter().map(incident_update_body).collect();
+    Ok(ok(body))
+}
+
+fn incident_body(incident: &Incident) -> Value {
+    json!({
+        "uuid": incident.uuid,
+        "title": incident.title,
+        "status": incident.status,
+        "severity": incident.severity,
+        "monitor_uuid": incident.monitor_uuid,
+        "started_at": unix_secs(incident.started_at),
+        "resolved_at": incident.resolved_at.map(unix_secs),
+    })
+}
+
+fn incident_update_body(update: &IncidentUpdate) -> Value {
+    json!({
+        "status": update.status,
+        "message": update.message,
+        "created_at": unix_secs(update.created_at),
+    })
+}
+
+fn rollup_body(rollup: &ResultRollup) -> Value {
+    json!({
+        "bucket_start": unix_secs(rollup.bucket_start),
+        "checks": rollup.checks,
+        "up": rollup.up,
+        "down": rollup.down,
+        "uptime_percent": (rollup.checks > 0)
+            .then(|| rollup.up as f64 * 100.0 / rollup.checks as f64),
+        "avg_latency_ms": rollup.avg_latency_ms,
+        "p95_latency_ms": rollup.p95_latency_ms,
+        "max_latency_ms": rollup.max_latency_ms,
+    })
+}
+
+fn domain_body(domain: &CommunityDomain) -> Value {
+    json!({
+        "host": domain.host,
+        "joined": domain.joined,
+        "monitor_uuid": domain.monitor_uuid,
+        "updated_at": unix_secs(domain.updated_at),
+    })
+}
+
+fn route_body(route: &NotificationRoute) -> Value {
+    json!({
+        "scope": route.scope,
+        "channels": route.channels,
+        "escalation": route.escalation,
+        "updated_at": unix_secs(route.updated_at),
+    })
+}
+
+fn ok(body: Value) -> Reply {
+    HttpResponse::Ok().json(body)
+}
+
+fn monitor_not_found() -> Reply {
+    error(StatusCode::NOT_FOUND, "Monitor not found")
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+}
+
+/// Whether the request carries the bearer token
+fn authorized(headers: &HeaderMap, token: &str) -> bool {
+    let given = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    // Compared as digests so the time taken does not leak the token
+    given.is_some_and(|given| Sha256::digest(given.trim()) == Sha256::digest(token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DatabaseConfig, DatabasePoolConfig};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    /// Send one request to the API and return the response status and body
+    async fn request(state: &ApiState, request: &str) -> (String, Value) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = http::serve_listener(api(state.clone()), listener, None).unwrap();
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        server.abort();
+
+        let status = response.lines().next().unwrap_or_default().to_string();
+        let body = response.split_once("\r\n\r\n").map(|(_, body)| body).unwrap_or_default();
+        (status, serde_json::from_str(body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_routes_decode_paths_and_need_the_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("uppe.db");
+        let db = libsql::Builder::new_local(path).build().await.unwrap();
+        let pool = crate::pool::build(db, &DatabasePoolConfig::default()).unwrap();
+        let database =
+            crate::database::open(&DatabaseConfig::default(), &DatabasePoolConfig::default(), pool)
+                .await
+                .unwrap();
+        let (reload_tx, _reload_rx) = mpsc::channel(8);
+        let (heartbeats, _heartbeats_rx) = mpsc::channel(8);
+        let state = ApiState {
+            token: Some(Arc::new("s3cret".to_string())),
+            database,
+            reload_tx,
+            heartbeats,
+        };
+
+        let put = |auth: &str| {
+            let body = r#"{"channels": []}"#;
+            format!(
+                "PUT /api/v1/routes/tag%3Aprod HTTP/1.1\r\nHost: x\r\n{auth}Content-Length: \
+                 {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        };
+        let (status, body) = request(&state, &put("Authorization: Bearer s3cret\r\n")).await;
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(body["scope"], "tag:prod");
+
+        let (status, _) = request(&state, &put("")).await;
+        assert_eq!(status, "HTTP/1.1 401 Unauthorized");
+
+        // Heartbeat pings carry their own secret and need no bearer token
+        let ping = "POST /api/v1/heartbeat/nope HTTP/1.1\r\nHost: x\r\nContent-Length: \
+                    0\r\nConnection: close\r\n\r\n";
+        let (status, body) = request(&state, ping).await;
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+        assert_eq!(body["msg"], "Unknown heartbeat token");
+    }
+
+    #[test]
+    fn test_apply_validates_and_leaves_absent_fields() {
+        let mut monitor =
+            Monitor::new("API".to_string(), "https://example.com".to_string(), "https".to_string());
+        monitor.retention_days = Some(7);
+
+        let input: MonitorInput =
+            serde_json::from_str(r#"{"enabled": false, "expected_status_codes": ["200-299"]}"#)
+                .unwrap();
+        apply(&input, &mut monitor).unwrap();
+        assert!(!monitor.enabled);
+        assert_eq!(monitor.expected_status_codes, ["200-299"]);
+        assert_eq!(monitor.retention_days, Some(7));
+
+        let input: MonitorInput = serde_json::from_str(r#"{"retention_days": null}"#).unwrap();
+        apply(&input, &mut monitor).unwrap();
+        assert_eq!(monitor.retention_days, None);
+
+        let input: MonitorInput = serde_json::from_str(r#"{"timeout_seconds": 60}"#).unwrap();
+        assert!(apply(&input, &mut monitor).is_err());
+        assert!(serde_json::from_str::<MonitorInput>(r#"{"colour": "red"}"#).is_err());
+    }
+
+    #[test]
+    fn test_apply_grace() {
+        let mut monitor = Monitor::new(
+            "Backup".to_string(),
+            Monitor::heartbeat_token(),
+            Monitor::HEARTBEAT.to_string(),
+        );
+        let input: MonitorInput = serde_json::from_str(r#"{"grace_seconds": 600}"#).unwrap();
+        apply(&input, &mut monitor).unwrap();
+        assert_eq!(monitor.grace_seconds, 600);
+
+        let input: MonitorInput = serde_json::from_str(r#"{"target": "a/b"}"#).unwrap();
+        assert!(apply(&input, &mut monitor).is_err());
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer s3cret".parse().unwrap());
+        assert!(authorized(&headers, "s3cret"));
+        assert!(!authorized(&headers, "other"));
+        assert!(!authorized(&HeaderMap::new(), "s3cret"));
+    }
+}
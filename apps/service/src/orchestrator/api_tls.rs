@@ -0,0 +1,286 @@
+/// TLS for the HTTP APIs, optionally requiring client certificates
+///
+/// With a certificate and key configured, the health endpoints, the status
+/// page API and the management API speak HTTPS. Adding a client CA turns on mutual TLS: a client
+/// without a certificate that CA issued fails the handshake. Listed clients,
+/// matched by subject common name or SHA-256 fingerprint, are limited to the
+/// APIs in their scopes; any other certificate is answered with 403.
+use anyhow::{Context, Result, anyhow};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig, ServerConnection};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use tracing::debug;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::config::{ApiClientConfig, ApiScope, ApiTlsConfig};
+
+/// Identity of a client certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ClientIdentity {
+    common_name: Option<String>,
+    /// SHA-256 of the DER certificate, lowercase hex
+    fingerprint: String,
+}
+
+impl ClientIdentity {
+    fn from_der(der: &[u8]) -> Self {
+        let common_name = X509Certificate::from_der(der).ok().and_then(|(_, cert)| {
+            cert.subject()
+                .iter_common_name()
+                .next()
+                .and_then(|cn| cn.as_str().ok())
+                .map(str::to_string)
+        });
+        Self { common_name, fingerprint: hex::encode(Sha256::digest(der)) }
+    }
+}
+
+/// TLS settings shared by the API listeners
+pub struct ApiTls {
+    server: Arc<ServerConfig>,
+    clients: Vec<ApiClientConfig>,
+    /// Whether clients must present a certificate
+    client_auth: bool,
+}
+
+impl ApiTls {
+    /// Load the configured certificates; `None` when TLS is off
+    pub fn from_config(config: &ApiTlsConfig) -> Result<Option<Self>> {
+        let Some(cert_path) = &config.cert_path else {
+            return Ok(None);
+        };
+        let key_path =
+            config.key_path.as_ref().ok_or_else(|| anyhow!("api_tls.key_path is not set"))?;
+        if !config.clients.is_empty() && config.client_ca_path.is_none() {
+            return Err(anyhow!("api_tls.clients needs api_tls.client_ca_path"));
+        }
+        for client in &config.clients {
+            if client.common_name.is_none() && client.fingerprint.is_none() {
+                return Err(anyhow!("api_tls.clients entries need a common_name or fingerprint"));
+            }
+        }
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?;
+        let builder = match &config.client_ca_path {
+            Some(ca_path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in load_certs(ca_path)? {
+                    roots.add(cert)?;
+                }
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .build()?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+        let server = builder.with_single_cert(load_certs(cert_path)?, load_key(key_path)?)?;
+
+        Ok(Some(Self {
+            server: Arc::new(server),
+            clients: config.clients.clone(),
+            client_auth: config.client_ca_path.is_some(),
+        }))
+    }
+
+    /// Whether clients must present a certificate the client CA issued
+    pub fn requires_client_certs(&self) -> bool {
+        self.client_auth
+    }
+
+    /// Server side TLS configuration of the listeners
+    pub(super) fn server_config(&self) -> ServerConfig {
+        ServerConfig::clone(&self.server)
+    }
+
+    /// Whether the client of a connection that completed the handshake may
+    /// use the API with `scope`
+    pub(super) fn allows_client(&self, connection: &ServerConnection, scope: ApiScope) -> bool {
+        let identity = connection
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .map(|cert| ClientIdentity::from_der(cert));
+        let allowed = identity.as_ref().is_none_or(|identity| self.allows(identity, scope));
+        if let Some(identity) = identity.filter(|_| !allowed) {
+            debug!(
+                "Refusing client certificate {:?} ({}) on the {} API",
+                identity.common_name,
+                identity.fingerprint,
+                scope.as_str()
+            );
+        }
+
+        allowed
+    }
+
+    /// Whether a verified client certificate may use the API with `scope`
+    fn allows(&self, identity: &ClientIdentity, scope: ApiScope) -> bool {
+        self.clients.is_empty()
+            || self
+                .clients
+                .iter()
+                .any(|client| matches(client, identity) && client.scopes.contains(&scope))
+    }
+}
+
+fn matches(client: &ApiClientConfig, identity: &ClientIdentity) -> bool {
+    let common_name = client
+        .common_name
+        .as_ref()
+        .is_none_or(|name| identity.common_name.as_deref() == Some(name.as_str()));
+    let fingerprint = client.fingerprint.as_ref().is_none_or(|fingerprint| {
+        fingerprint.replace(':', "").eq_ignore_ascii_case(&identity.fingerprint)
+    });
+    common_name && fingerprint
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {path}"))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Failed to read certificates from {path}"))?;
+    if certs.is_empty() {
+        return Err(anyhow!("No certificates in {path}"));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &str) -> Result<PrivateKeyDer<'static>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {path}"))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to read the private key from {path}"))?
+        .ok_or_else(|| anyhow!("No private key in {path}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::http::{Api, serve_listener};
+    use super::*;
+    use actix_web::web;
+    use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, KeyPair};
+    use rustls::pki_types::ServerName;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio_rustls::TlsConnector;
+
+    struct Issued {
+        cert: rcgen::Certificate,
+        key: KeyPair,
+    }
+
+    fn issue(name: &str, ca: Option<&Issued>) -> Issued {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(vec![name.to_string()]).unwrap();
+        params.distinguished_name.push(DnType::CommonName, name);
+        let cert = match ca {
+            Some(ca) => params.signed_by(&key, &ca.cert, &ca.key).unwrap(),
+            None => {
+                params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+                params.self_signed(&key).unwrap()
+            }
+        };
+        Issued { cert, key }
+    }
+
+    /// Connect with `client`'s certificate and return what the server answered
+    async fn connect(
+        tls: Arc<ApiTls>,
+        ca: &Issued,
+        client: Option<&Issued>,
+        scope: ApiScope,
+    ) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let api = Api::new("Test", scope, |config| {
+            config.route("/", web::get().to(|| async { "{}" }));
+        });
+        let server = serve_listener(api, listener, Some(tls)).unwrap();
+
+        let mut roots = RootCertStore::empty();
+        roots.add(ca.cert.der().clone()).unwrap();
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+        let config = match client {
+            Some(client) => builder
+                .with_client_auth_cert(
+                    vec![client.cert.der().clone()],
+                    PrivateKeyDer::try_from(client.key.serialize_der()).unwrap(),
+                )
+                .unwrap(),
+            None => builder.with_no_client_auth(),
+        };
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let name = ServerName::try_from("localhost").unwrap();
+        let mut response = String::new();
+        if let Ok(mut stream) = TlsConnector::from(Arc::new(config)).connect(name, stream).await {
+            let request = "GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+            if stream.write_all(request.as_bytes()).await.is_ok() {
+                let _ = stream.read_to_string(&mut response).await;
+            }
+        }
+        server.abort();
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_client_certificates_map_to_scopes() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, pem: String| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, pem).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+
+        let ca = issue("Uppe test CA", None);
+        let server = issue("localhost", Some(&ca));
+        let monitoring = issue("prometheus", Some(&ca));
+        let dashboard = issue("dashboard", Some(&ca));
+
+        let config = ApiTlsConfig {
+            cert_path: Some(write("server.pem", server.cert.pem())),
+            key_path: Some(write("server.key", server.key.serialize_pem())),
+            client_ca_path: Some(write("ca.pem", ca.cert.pem())),
+            clients: vec![
+                ApiClientConfig {
+                    common_name: Some("prometheus".to_string()),
+                    fingerprint: None,
+                    scopes: vec![ApiScope::Health],
+                },
+                ApiClientConfig {
+                    common_name: None,
+                    fingerprint: Some(hex::encode(Sha256::digest(dashboard.cert.der()))),
+                    scopes: vec![ApiScope::Health, ApiScope::StatusPage],
+                },
+            ],
+        };
+        let tls = Arc::new(ApiTls::from_config(&config).unwrap().unwrap());
+
+        let ok = "HTTP/1.1 200 OK";
+        let forbidden = "HTTP/1.1 403 Forbidden";
+        assert_eq!(connect(tls.clone(), &ca, Some(&monitoring), ApiScope::Health).await, ok);
+        assert_eq!(
+            connect(tls.clone(), &ca, Some(&monitoring), ApiScope::StatusPage).await,
+            forbidden
+        );
+        assert_eq!(connect(tls.clone(), &ca, Some(&dashboard), ApiScope::StatusPage).await, ok);
+        // No certificate, or one from another CA, fails the handshake
+        assert_eq!(connect(tls.clone(), &ca, None, ApiScope::Health).await, "");
+        let stranger = issue("prometheus", Some(&issue("Other CA", None)));
+        assert_eq!(connect(tls, &ca, Some(&stranger), ApiScope::Health).await, "");
+
+        // TLS stays off without a certificate, and half a setup is an error
+        assert!(ApiTls::from_config(&ApiTlsConfig::default()).unwrap().is_none());
+        let no_ca = ApiTlsConfig { client_ca_path: None, ..config };
+        assert!(ApiTls::from_config(&no_ca).is_err());
+    }
+}
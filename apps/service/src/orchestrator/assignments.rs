@@ -0,0 +1,250 @@
+/// Helper assignment state - owner-side assignments and accepted helper duties
+///
+/// Both maps are mirrored into the `helper_assignments` table so a restart does
+/// not silently drop who is helping us or whom we promised to help. Every read
+/// and write of assignments goes through the store, so the maps and the table
+/// never disagree.
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::database::models::{AssignmentRole, HelperAssignment};
+
+/// Outcome of restoring assignments from the database
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RestoreSummary {
+    pub owner_restored: usize,
+    pub helper_restored: usize,
+    pub dropped: usize,
+}
+
+/// Assignment store shared by the tasks accepting, running and checking
+/// assignments
+pub type SharedAssignments = Arc<Mutex<AssignmentStore>>;
+
+/// Persistent store for helper assignments
+pub struct AssignmentStore {
+    database: Arc<dyn Database>,
+    /// Owner-side: peers helping check our monitors, keyed by assignment ID
+    assignments: HashMap<Uuid, HelperAssignment>,
+    /// Helper-side: duties we accepted for other owners, keyed by assignment ID
+    helper_assignments: HashMap<Uuid, HelperAssignment>,
+}
+
+impl AssignmentStore {
+    /// Create an empty store backed by the given database
+    pub fn new(database: Arc<dyn Database>) -> Self {
+        Self { database, assignments: HashMap::new(), helper_assignments: HashMap::new() }
+    }
+
+    /// Load persisted assignments and drop the ones that are no longer valid
+    ///
+    /// Expired assignments are removed for both roles. Owner-side assignments
+    /// are also removed when the monitor they refer to no longer exists, and
+    /// helper-side duties are only kept once they were accepted.
+    pub async fn restore(&mut self) -> Result<RestoreSummary> {
+        let now = SystemTime::now();
+        let mut summary = RestoreSummary::default();
+
+        for assignment in self.database.get_helper_assignments(AssignmentRole::Owner).await? {
+            let monitor_exists =
+                self.database.get_monitor_by_uuid(assignment.monitor_uuid).await?.is_some();
+
+            if assignment.is_expired(now) || !monitor_exists {
+                self.database.delete_helper_assignment(assignment.assignment_id).await?;
+                summary.dropped += 1;
+                continue;
+            }
+
+            self.assignments.insert(assignment.assignment_id, assignment);
+            summary.owner_restored += 1;
+        }
+
+        for assignment in self.database.get_helper_assignments(AssignmentRole::Helper).await? {
+            if assignment.is_expired(now) || !assignment.is_accepted() {
+                self.database.delete_helper_assignment(assignment.assignment_id).await?;
+                summary.dropped += 1;
+                continue;
+            }
+
+            self.helper_assignments.insert(assignment.assignment_id, assignment);
+            summary.helper_restored += 1;
+        }
+
+        info!(
+            "Restored {} owner assignment(s) and {} helper duty(ies), dropped {} stale",
+            summary.owner_restored, summary.helper_restored, summary.dropped
+        );
+
+        Ok(summary)
+    }
+
+    /// Insert or update an assignment and persist it
+    pub async fn upsert(&mut self, assignment: HelperAssignment) -> Result<()> {
+        self.database.save_helper_assignment(&assignment).await?;
+
+        let map = match assignment.role {
+            AssignmentRole::Owner => &mut self.assignments,
+            AssignmentRole::Helper => &mut self.helper_assignments,
+        };
+        map.insert(assignment.assignment_id, assignment);
+        Ok(())
+    }
+
+    /// Remove an assignment from memory and the database
+    pub async fn remove(&mut self, assignment_id: Uuid) -> Result<()> {
+        let removed = self.assignments.remove(&assignment_id).is_some()
+            | self.helper_assignments.remove(&assignment_id).is_some();
+
+        if !removed {
+            warn!("Removing unknown helper assignment {}", assignment_id);
+        }
+
+        self.database.delete_helper_assignment(assignment_id).await
+    }
+
+    /// Record when a result last arrived for, or was sent under, an assignment
+    ///
+    /// The first result a helper sends confirms an owner-side assignment.
+    pub async fn record_result(&mut self, assignment_id: Uuid, at: SystemTime) -> Result<()> {
+        self.database.record_assignment_result(assignment_id, at).await?;
+
+        if let Some(assignment) = self
+            .assignments
+            .get_mut(&assignment_id)
+            .or_else(|| self.helper_assignments.get_mut(&assignment_id))
+        {
+            assignment.last_result_at = Some(at);
+        }
+        if let Some(assignment) = self.assignments.get_mut(&assignment_id)
+            && !assignment.is_accepted()
+        {
+            assignment.status = "accepted".to_string();
+            assignment.updated_at = at;
+            self.database.save_helper_assignment(assignment).await?;
+        }
+        Ok(())
+    }
+
+    /// Owner-side assignment (a peer helping us) with the given ID
+    pub fn owner_assignment(&self, assignment_id: Uuid) -> Option<&HelperAssignment> {
+        self.assignments.get(&assignment_id)
+    }
+
+    /// Owner-side assignments (peers helping us)
+    pub fn owner_assignments(&self) -> impl Iterator<Item = &HelperAssignment> {
+        self.assignments.values()
+    }
+
+    /// Helper-side duties (owners we are helping)
+    pub fn helper_duties(&self) -> impl Iterator<Item = &HelperAssignment> {
+        self.helper_assignments.values()
+    }
+
+    /// Accepted helper duties that have not expired, which count towards our
+    /// capacity
+    pub fn active_duties(&self, now: SystemTime) -> usize {
+        self.helper_duties()
+            .filter(|assignment| assignment.is_accepted() && !assignment.is_expired(now))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DatabaseConfig, DatabasePoolConfig};
+    use std::time::Duration;
+
+    fn assignment(expires_at: Option<SystemTime>) -> HelperAssignment {
+        let now = SystemTime::now();
+        HelperAssignment {
+            assignment_id: Uuid::new_v4(),
+            role: AssignmentRole::Helper,
+            monitor_uuid: Uuid::new_v4(),
+            owner_peer_id: "owner".to_string(),
+            helper_peer_id: "helper".to_string(),
+            target: "https://example.com".to_string(),
+            check_type: "https".to_string(),
+            interval_seconds: 60,
+            status: "accepted".to_string(),
+            created_at: now,
+            updated_at: now,
+            expires_at,
+            last_result_at: None,
+        }
+    }
+
+    #[test]
+    fn test_assignment_expiry() {
+        let now = SystemTime::now();
+
+        assert!(!assignment(None).is_expired(now));
+        assert!(!assignment(Some(now + Duration::from_secs(60))).is_expired(now));
+        assert!(assignment(Some(now - Duration::from_secs(60))).is_expired(now));
+    }
+
+    #[tokio::test]
+    async fn test_store_counts_and_drops_duties() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = libsql::Builder::new_local(dir.path().join("uppe.db")).build().await.unwrap();
+        let pool = crate::pool::build(db, &DatabasePoolConfig::default()).unwrap();
+        let database =
+            crate::database::open(&DatabaseConfig::default(), &DatabasePoolConfig::default(), pool)
+                .await
+                .unwrap();
+        let mut store = AssignmentStore::new(database.clone());
+        let now = SystemTime::now();
+
+        let accepted = assignment(None);
+        let mut pending = assignment(None);
+        pending.status = "pending".to_string();
+        let expired = assignment(Some(now - Duration::from_secs(60)));
+        for duty in [&accepted, &pending, &expired] {
+            store.upsert(duty.clone()).await.unwrap();
+        }
+        assert_eq!(store.active_duties(now), 1);
+
+        store.record_result(accepted.assignment_id, now).await.unwrap();
+        let duty = store.helper_duties().find(|duty| duty.assignment_id == accepted.assignment_id);
+        assert!(duty.unwrap().last_result_at.is_some());
+
+        // A helper's first result confirms the assignment on the owner side
+        let mut requested = assignment(None);
+        requested.role = AssignmentRole::Owner;
+        requested.status = "pending".to_string();
+        store.upsert(requested.clone()).await.unwrap();
+        store.record_result(requested.assignment_id, now).await.unwrap();
+        assert!(store.owner_assignment(requested.assignment_id).unwrap().is_accepted());
+        let saved = database.get_helper_assignments(AssignmentRole::Owner).await.unwrap();
+        assert!(saved[0].is_accepted() && saved[0].last_result_at.is_some());
+        store.remove(requested.assignment_id).await.unwrap();
+
+        // A restart keeps only the accepted, unexpired duty
+        let mut restarted = AssignmentStore::new(database.clone());
+        let summary = restarted.restore().await.unwrap();
+        assert_eq!((summary.helper_restored, summary.dropped), (1, 2));
+
+        restarted.remove(accepted.assignment_id).await.unwrap();
+        assert_eq!(restarted.active_duties(now), 0);
+        assert!(
+            database
+                .get_helper_assignments(AssignmentRole::Helper)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_assignment_role_roundtrip() {
+        assert_eq!("owner".parse::<AssignmentRole>().unwrap(), AssignmentRole::Owner);
+        assert_eq!(AssignmentRole::Helper.to_string(), "helper");
+        assert!("other".parse::<AssignmentRole>().is_err());
+    }
+}
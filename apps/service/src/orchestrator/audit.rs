@@ -0,0 +1,86 @@
+/// Audit log - persists security-relevant events
+///
+/// Tasks report events through an `AuditLog` handle; each event is logged under
+/// the `uppe::audit` target and appended to the `audit_events` table by the
+/// writer task. Old events are removed by the `RetentionSweeper`.
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::database::Database;
+use crate::database::models::{AuditEvent, AuditKind};
+
+/// Handle for recording audit events
+#[derive(Clone)]
+pub struct AuditLog {
+    tx: mpsc::Sender<AuditEvent>,
+}
+
+impl AuditLog {
+    /// Log an event and queue it for persistence
+    pub async fn record(&self, kind: AuditKind, peer_id: Option<&str>, message: String) {
+        warn!(
+            target: "uppe::audit",
+            kind = %kind,
+            peer_id = peer_id.unwrap_or("-"),
+            "{}",
+            message
+        );
+
+        let event = AuditEvent::new(kind, peer_id.map(str::to_string), message);
+        if self.tx.send(event).await.is_err() {
+            debug!("Audit writer stopped, event not persisted");
+        }
+    }
+}
+
+/// Task appending audit events to the database
+pub struct AuditWriter {
+    database: Arc<dyn Database>,
+}
+
+impl AuditWriter {
+    /// Create a writer for the given database
+    pub fn new(database: Arc<dyn Database>) -> Self {
+        Self { database }
+    }
+
+    /// Spawn the writer, returning the handle to record events with
+    ///
+    /// The task exits once every handle is dropped.
+    pub fn spawn(self) -> (AuditLog, JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::channel::<AuditEvent>(256);
+
+        let handle = tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                if let Err(e) = self.database.append_audit_event(&event).await {
+                    warn!("Failed to persist audit event: {}", e);
+                }
+            }
+
+            debug!("Audit writer stopped");
+        });
+
+        (AuditLog { tx }, handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_kind_roundtrip() {
+        for kind in [
+            AuditKind::SignatureFailure,
+            AuditKind::RateLimited,
+            AuditKind::PolicyRejected,
+            AuditKind::AdminAction,
+            AuditKind::PeerBanned,
+        ] {
+            assert_eq!(kind.to_string().parse::<AuditKind>().unwrap(), kind);
+        }
+        assert!("unknown".parse::<AuditKind>().is_err());
+    }
+}
@@ -0,0 +1,102 @@
+/// Bandwidth accounting - daily P2P traffic and the budget it is held to
+///
+/// The P2P node reports cumulative byte counts; the meter turns them into
+/// bytes used in the current UTC day. Once the `max_bandwidth_mb_per_day`
+/// budget is used up the budget flag is raised and low-priority publishing
+/// backs off until the day rolls over.
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Bytes used in the current UTC day
+#[derive(Debug, Default, Clone)]
+pub struct BandwidthMeter {
+    day: u64,
+    used_today: u64,
+    last_total: Option<u64>,
+}
+
+impl BandwidthMeter {
+    /// Record the node's cumulative byte count
+    ///
+    /// A total lower than the previous one means the node restarted, in which
+    /// case the whole new total counts as fresh traffic.
+    pub fn record_total(&mut self, total: u64, now: SystemTime) {
+        let day = utc_day(now);
+        if day != self.day {
+            self.day = day;
+            self.used_today = 0;
+        }
+
+        let delta = match self.last_total {
+            Some(last) if total >= last => total - last,
+            _ => total,
+        };
+        self.used_today += delta;
+        self.last_total = Some(total);
+    }
+
+    /// Megabytes used today, rounded down
+    pub fn used_mb(&self) -> u64 {
+        self.used_today / BYTES_PER_MB
+    }
+}
+
+fn utc_day(now: SystemTime) -> u64 {
+    now.duration_since(UNIX_EPOCH).map(|d| d.as_secs() / SECS_PER_DAY).unwrap_or(0)
+}
+
+/// Flag shared between the stats tracker and publishers
+#[derive(Debug, Clone, Default)]
+pub struct BandwidthBudget(Arc<AtomicBool>);
+
+impl BandwidthBudget {
+    /// Whether today's budget is used up
+    pub fn is_exhausted(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Update the flag from usage and the configured limit (0 = unlimited),
+    /// returning whether it changed
+    pub fn update(&self, used_mb: u64, limit_mb: u64) -> bool {
+        let exhausted = limit_mb > 0 && used_mb >= limit_mb;
+        self.0.swap(exhausted, Ordering::Relaxed) != exhausted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_bandwidth_meter_daily_usage() {
+        let day_one = UNIX_EPOCH + Duration::from_secs(10 * SECS_PER_DAY);
+        let mut meter = BandwidthMeter::default();
+
+        meter.record_total(BYTES_PER_MB, day_one);
+        meter.record_total(3 * BYTES_PER_MB, day_one);
+        assert_eq!(meter.used_mb(), 3);
+
+        // Restarted node reports a smaller total
+        meter.record_total(BYTES_PER_MB, day_one);
+        assert_eq!(meter.used_mb(), 4);
+
+        let day_two = day_one + Duration::from_secs(SECS_PER_DAY);
+        meter.record_total(2 * BYTES_PER_MB, day_two);
+        assert_eq!(meter.used_mb(), 1);
+    }
+
+    #[test]
+    fn test_bandwidth_budget() {
+        let budget = BandwidthBudget::default();
+        assert!(!budget.update(50, 100));
+        assert!(budget.update(100, 100));
+        assert!(budget.is_exhausted());
+        assert!(budget.update(500, 0));
+        assert!(!budget.is_exhausted());
+    }
+}
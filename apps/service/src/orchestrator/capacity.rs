@@ -0,0 +1,186 @@
+/// Helper capacity - how much work this node accepts on behalf of other owners
+///
+/// Limits start from the `[helper]` config section and are overridden by the
+/// matching rows in the `settings` table, which are re-read periodically so an
+/// operator can change them without restarting the service.
+use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::HelperConfig;
+use crate::database::Database;
+
+/// Settings key overriding `HelperConfig::max_assignments`
+const MAX_ASSIGNMENTS_SETTING: &str = "helper_max_assignments";
+/// Settings key overriding `HelperConfig::max_checks_per_owner_per_hour`
+const MAX_CHECKS_SETTING: &str = "helper_max_checks_per_owner_per_hour";
+
+/// Window the per-owner check limit applies to
+const CHECK_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Capacity limits for helper duties
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HelperLimits {
+    pub max_assignments: usize,
+    pub max_checks_per_owner_per_hour: u32,
+}
+
+impl HelperLimits {
+    /// Limits from the config file
+    pub fn from_config(config: &HelperConfig) -> Self {
+        Self {
+            max_assignments: config.max_assignments,
+            max_checks_per_owner_per_hour: config.max_checks_per_owner_per_hour,
+        }
+    }
+
+    /// Apply overrides from the settings table; unparsable values are ignored
+    pub async fn with_settings(mut self, database: &dyn Database) -> Result<Self> {
+        if let Some(value) = database.get_setting(MAX_ASSIGNMENTS_SETTING).await?
+            && let Ok(max) = value.trim().parse()
+        {
+            self.max_assignments = max;
+        }
+        if let Some(value) = database.get_setting(MAX_CHECKS_SETTING).await?
+            && let Ok(max) = value.trim().parse()
+        {
+            self.max_checks_per_owner_per_hour = max;
+        }
+
+        Ok(self)
+    }
+}
+
+/// Sliding one-hour window of checks run for each owner
+#[derive(Debug, Default)]
+pub struct PrivateMonitorRateLimiter {
+    checks: HashMap<String, VecDeque<Instant>>,
+}
+
+impl PrivateMonitorRateLimiter {
+    /// Record a check for `owner` if it is still under `limit` for the window
+    pub fn try_acquire(&mut self, owner: &str, limit: u32, now: Instant) -> bool {
+        let window = self.checks.entry(owner.to_string()).or_default();
+        prune(window, now);
+
+        if window.len() >= limit as usize {
+            return false;
+        }
+        window.push_back(now);
+        true
+    }
+
+    /// Checks run in the current window for the busiest owner
+    pub fn busiest(&mut self, now: Instant) -> usize {
+        self.checks.retain(|_, window| {
+            prune(window, now);
+            !window.is_empty()
+        });
+        self.checks.values().map(VecDeque::len).max().unwrap_or(0)
+    }
+}
+
+fn prune(window: &mut VecDeque<Instant>, now: Instant) {
+    while let Some(oldest) = window.front() {
+        if now.duration_since(*oldest) < CHECK_WINDOW {
+            break;
+        }
+        window.pop_front();
+    }
+}
+
+/// Current helper utilization against the configured limits
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HelperUtilization {
+    pub active_assignments: usize,
+    pub max_assignments: usize,
+    pub busiest_owner_checks: usize,
+    pub max_checks_per_owner_per_hour: u32,
+}
+
+/// Limits plus the state needed to enforce them
+#[derive(Debug)]
+pub struct HelperCapacity {
+    limits: HelperLimits,
+    rate_limiter: PrivateMonitorRateLimiter,
+}
+
+/// Capacity shared between the tasks that accept, run and reload helper work
+pub type SharedCapacity = Arc<Mutex<HelperCapacity>>;
+
+impl HelperCapacity {
+    /// Create capacity tracking with the given limits
+    pub fn new(limits: HelperLimits) -> Self {
+        Self { limits, rate_limiter: PrivateMonitorRateLimiter::default() }
+    }
+
+    /// Replace the limits, returning whether they changed
+    pub fn set_limits(&mut self, limits: HelperLimits) -> bool {
+        let changed = self.limits != limits;
+        self.limits = limits;
+        changed
+    }
+
+    /// Whether another assignment fits next to `active` accepted ones
+    pub fn has_room(&self, active: usize) -> bool {
+        active < self.limits.max_assignments
+    }
+
+    /// Record a check for `owner` unless they used up their hourly budget
+    pub fn try_record_check(&mut self, owner: &str, now: Instant) -> bool {
+        self.rate_limiter
+            .try_acquire(owner, self.limits.max_checks_per_owner_per_hour, now)
+    }
+
+    /// Utilization with `active` accepted assignments
+    pub fn utilization(&mut self, active: usize, now: Instant) -> HelperUtilization {
+        HelperUtilization {
+            active_assignments: active,
+            max_assignments: self.limits.max_assignments,
+            busiest_owner_checks: self.rate_limiter.busiest(now),
+            max_checks_per_owner_per_hour: self.limits.max_checks_per_owner_per_hour,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter_window() {
+        let mut limiter = PrivateMonitorRateLimiter::default();
+        let start = Instant::now();
+
+        assert!(limiter.try_acquire("owner", 2, start));
+        assert!(limiter.try_acquire("owner", 2, start));
+        assert!(!limiter.try_acquire("owner", 2, start));
+        assert!(limiter.try_acquire("other", 2, start));
+        assert_eq!(limiter.busiest(start), 2);
+
+        let later = start + CHECK_WINDOW;
+        assert!(limiter.try_acquire("owner", 2, later));
+        assert_eq!(limiter.busiest(later), 1);
+    }
+
+    #[test]
+    fn test_capacity_limits() {
+        let limits = HelperLimits { max_assignments: 2, max_checks_per_owner_per_hour: 1 };
+        let mut capacity = HelperCapacity::new(limits);
+        assert!(capacity.has_room(1));
+        assert!(!capacity.has_room(2));
+
+        let now = Instant::now();
+        assert!(capacity.try_record_check("owner", now));
+        assert!(!capacity.try_record_check("owner", now));
+
+        assert!(!capacity.set_limits(limits));
+        assert!(capacity.set_limits(HelperLimits { max_assignments: 5, ..limits }));
+        assert!(capacity.has_room(2));
+
+        let utilization = capacity.utilization(2, now);
+        assert_eq!(utilization.max_assignments, 5);
+        assert_eq!(utilization.busiest_owner_checks, 1);
+    }
+}
@@ -0,0 +1,113 @@
+/// Fault injection - deliberately breaks parts of the service, to check that
+/// alerting, retries and reassignment cope
+///
+/// Only builds with the `chaos` feature read the `chaos_*` settings; other
+/// builds never inject a fault. The settings apply at runtime like any other,
+/// and are never synced to cluster nodes:
+///
+/// - `chaos_gossip_drop_percent` drops that share of messages from peers
+/// - `chaos_db_write_delay_ms` delays every stored result by that long
+/// - `chaos_dht_failure_percent` fails that share of owner sync DHT lookups
+use anyhow::Result;
+use rand::Rng;
+use std::time::Duration;
+
+use crate::database::Database;
+
+/// Settings key for the share of gossip messages dropped
+pub const GOSSIP_DROP_SETTING: &str = "chaos_gossip_drop_percent";
+/// Settings key for the delay added to result writes
+pub const DB_WRITE_DELAY_SETTING: &str = "chaos_db_write_delay_ms";
+/// Settings key for the share of DHT lookups failed
+pub const DHT_FAILURE_SETTING: &str = "chaos_dht_failure_percent";
+
+/// Faults currently injected; none by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultSettings {
+    /// Share of gossip messages from peers dropped, 0 to 100
+    pub gossip_drop_percent: u8,
+    /// Delay added before each result is written
+    pub db_write_delay_ms: u64,
+    /// Share of DHT lookups reported as failed, 0 to 100
+    pub dht_failure_percent: u8,
+}
+
+impl FaultSettings {
+    /// Faults set in the settings table; always none without the `chaos`
+    /// feature
+    pub async fn from_settings(database: &dyn Database) -> Result<Self> {
+        if !cfg!(feature = "chaos") {
+            return Ok(Self::default());
+        }
+        Ok(Self::parse(
+            database.get_setting(GOSSIP_DROP_SETTING).await?.as_deref(),
+            database.get_setting(DB_WRITE_DELAY_SETTING).await?.as_deref(),
+            database.get_setting(DHT_FAILURE_SETTING).await?.as_deref(),
+        ))
+    }
+
+    /// Faults from settings values; unset or unparsable values inject nothing
+    /// and percentages are capped at 100
+    fn parse(
+        gossip_drop: Option<&str>,
+        db_write_delay: Option<&str>,
+        dht_failure: Option<&str>,
+    ) -> Self {
+        let percent = |value: Option<&str>| {
+            value.and_then(|v| v.trim().parse::<u64>().ok()).map_or(0, |p| p.min(100) as u8)
+        };
+        Self {
+            gossip_drop_percent: percent(gossip_drop),
+            db_write_delay_ms: db_write_delay.and_then(|v| v.trim().parse().ok()).unwrap_or(0),
+            dht_failure_percent: percent(dht_failure),
+        }
+    }
+
+    /// Whether any fault is injected
+    pub fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// Whether to drop the next gossip message
+    pub fn drop_gossip(&self) -> bool {
+        roll(self.gossip_drop_percent)
+    }
+
+    /// Whether to fail the next DHT lookup
+    pub fn fail_dht(&self) -> bool {
+        roll(self.dht_failure_percent)
+    }
+
+    /// Hold up a database write by the configured delay
+    pub async fn delay_db_write(&self) {
+        if self.db_write_delay_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(self.db_write_delay_ms)).await;
+        }
+    }
+}
+
+fn roll(percent: u8) -> bool {
+    percent > 0 && rand::thread_rng().gen_range(0..100) < percent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fault_settings_parse() {
+        assert_eq!(FaultSettings::parse(None, None, None), FaultSettings::default());
+        assert!(!FaultSettings::default().is_active());
+
+        let faults = FaultSettings::parse(Some(" 25 "), Some("500"), Some("250"));
+        assert_eq!(faults.gossip_drop_percent, 25);
+        assert_eq!(faults.db_write_delay_ms, 500);
+        assert_eq!(faults.dht_failure_percent, 100);
+        assert!(faults.is_active());
+
+        let faults = FaultSettings::parse(Some("100"), Some("soon"), Some("0"));
+        assert_eq!(faults.db_write_delay_ms, 0);
+        assert!((0..100).all(|_| faults.drop_gossip()));
+        assert!((0..100).all(|_| !faults.fail_dht()));
+    }
+}
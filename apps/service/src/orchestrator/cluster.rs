@@ -0,0 +1,421 @@
+/// Cluster sync - keeps monitors, settings and notification channels in step
+/// across an owner's own nodes
+///
+/// Nodes configured with the same `[cluster] key` publish their state on the
+/// cluster's topic every sync interval, sealed with the key, and merge what
+/// the others publish by last writer wins: a monitor is taken over when it is
+/// missing here or was changed more recently elsewhere, and deleted when
+/// another node deleted it after its last change here. Settings merge the
+/// same way, except node-local ones, and so do notification routes.
+/// Notification channels are only ever added; deleting a channel or a route is
+/// not propagated.
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::chaos;
+use super::dedup::SharedPublishSchedule;
+use super::reload::ReloadRequest;
+use super::runtime::RuntimeSettings;
+use crate::crypto::{ClusterKey, SealedClusterMessage};
+use crate::database::Database;
+use crate::database::models::{Monitor, NotificationRoute, RouteScope};
+use crate::p2p::{ClusterUpdate, P2PHandle, PayloadSchema};
+
+/// Settings that describe a single node rather than the cluster
+const LOCAL_SETTINGS: &[&str] = &[
+    "display_name",
+    chaos::GOSSIP_DROP_SETTING,
+    chaos::DB_WRITE_DELAY_SETTING,
+    chaos::DHT_FAILURE_SETTING,
+];
+/// Monitors per update, keeping updates well under the gossip message limit
+const MONITORS_PER_UPDATE: usize = 50;
+
+/// What to do with a monitor another node sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MonitorChange {
+    /// Not known here yet
+    Insert,
+    /// Changed more recently on the other node; replaces the row with this ID
+    Replace(Option<i64>),
+    /// Ours is as new, or it was deleted here after it last changed
+    Keep,
+}
+
+/// Task publishing our state to the cluster and merging the state of its
+/// other nodes
+pub struct ClusterSync {
+    database: Arc<dyn Database>,
+    p2p: P2PHandle,
+    key: Arc<ClusterKey>,
+    /// Our peer ID, sent with our updates
+    node_id: String,
+    interval: Duration,
+    messages: mpsc::Receiver<Vec<u8>>,
+    /// Told which nodes hold the cluster key
+    schedule: Option<SharedPublishSchedule>,
+}
+
+impl ClusterSync {
+    /// Create the cluster sync, returning the sender messages received on the
+    /// cluster topic are forwarded on
+    pub fn new(
+        database: Arc<dyn Database>,
+        p2p: P2PHandle,
+        key: Arc<ClusterKey>,
+        node_id: String,
+        interval: Duration,
+    ) -> (Self, mpsc::Sender<Vec<u8>>) {
+        let (tx, messages) = mpsc::channel(32);
+        (Self { database, p2p, key, node_id, interval, messages, schedule: None }, tx)
+    }
+
+    /// Admit the nodes whose updates open with our key to the publish schedule
+    pub fn with_publish_schedule(mut self, schedule: SharedPublishSchedule) -> Self {
+        self.schedule = Some(schedule);
+        self
+    }
+
+    /// Spawn the sync; monitors taken over from other nodes are rescheduled
+    /// through `reload_tx`. It stops once the settings channel or the message
+    /// sender closes
+    pub fn spawn(
+        mut self,
+        mut settings: watch::Receiver<RuntimeSettings>,
+        reload_tx: mpsc::Sender<ReloadRequest>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(self.interval);
+
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => {
+                        if let Err(e) = self.publish().await {
+                            warn!("Failed to publish cluster update: {}", e);
+                        }
+                    }
+                    message = self.messages.recv() => match message {
+                        Some(data) => match self.receive(&data).await {
+                            Ok(true) => {
+                                let _ = reload_tx.send(ReloadRequest::Monitors).await;
+                            }
+                            Ok(false) => {}
+                            Err(e) => debug!("Ignoring cluster message: {}", e),
+                        },
+                        None => break,
+                    },
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            debug!("Cluster sync stopped");
+        })
+    }
+
+    /// Publish our state, sealed, to the other nodes
+    async fn publish(&self) -> anyhow::Result<()> {
+        for update in split_update(self.snapshot().await?) {
+            let sealed = self.key.seal(&serde_json::to_vec(&update)?)?;
+            self.p2p.publish_cluster_update(sealed).await?;
+        }
+        Ok(())
+    }
+
+    /// Our monitors, tombstones, shared settings and notification channels
+    async fn snapshot(&self) -> anyhow::Result<ClusterUpdate> {
+        let mut monitors = self.database.get_monitors().await?;
+        for monitor in &mut monitors {
+            // Row IDs are local to each node
+            monitor.id = None;
+        }
+        let notification_routes = self.database.get_notification_routes().await?;
+        let monitor_channels = notification_routes
+            .iter()
+            .filter_map(|route| match route.scope {
+                RouteScope::Monitor(uuid) => Some((uuid, &route.channels)),
+                _ => None,
+            })
+            .flat_map(|(uuid, channels)| channels.iter().map(move |channel| (uuid, *channel)))
+            .collect();
+
+        let deleted_monitors = self
+            .database
+            .get_deleted_monitors()
+            .await?
+            .into_iter()
+            .map(|(uuid, deleted_at)| (uuid, unix_secs(deleted_at)))
+            .collect();
+        let settings = self
+            .database
+            .get_settings()
+            .await?
+            .into_iter()
+            .filter(|setting| !LOCAL_SETTINGS.contains(&setting.key.as_str()))
+            .collect();
+
+        Ok(ClusterUpdate {
+            node_id: self.node_id.clone(),
+            monitors,
+            deleted_monitors,
+            settings,
+            notification_channels: self.database.get_notification_channels().await?,
+            monitor_channels,
+            notification_routes,
+            schema: PayloadSchema::default(),
+        })
+    }
+
+    /// Open and merge an update from another node, returning whether any
+    /// monitor changed
+    async fn receive(&self, data: &[u8]) -> anyhow::Result<bool> {
+        let sealed: SealedClusterMessage = serde_json::from_slice(data)?;
+        let update: ClusterUpdate = serde_json::from_slice(&self.key.open(&sealed)?)?;
+        if update.node_id == self.node_id {
+            return Ok(false);
+        }
+        // Sealed with our key, so the node is one of ours
+        if let Some(schedule) = &self.schedule {
+            schedule.lock().unwrap().admit(&update.node_id, Instant::now());
+        }
+        self.merge(update).await
+    }
+
+    async fn merge(&self, update: ClusterUpdate) -> anyhow::Result<bool> {
+        let mut local: HashMap<Uuid, Monitor> = self
+            .database
+            .get_monitors()
+            .await?
+            .into_iter()
+            .map(|monitor| (monitor.uuid, monitor))
+            .collect();
+        let deleted: HashMap<Uuid, SystemTime> =
+            self.database.get_deleted_monitors().await?.into_iter().collect();
+
+        let mut changed = 0;
+        for mut monitor in update.monitors {
+            let change =
+                monitor_change(local.get(&monitor.uuid), deleted.get(&monitor.uuid), &monitor);
+            match change {
+                MonitorChange::Insert => monitor.id = None,
+                MonitorChange::Replace(id) => monitor.id = id,
+                MonitorChange::Keep => continue,
+            }
+            let id = self.database.save_monitor(&monitor).await?;
+            monitor.id = Some(id);
+            local.insert(monitor.uuid, monitor);
+            changed += 1;
+        }
+        for (uuid, deleted_at) in update.deleted_monitors {
+            let deleted_at = UNIX_EPOCH + Duration::from_secs(deleted_at);
+            if local.get(&uuid).is_some_and(|monitor| monitor.updated_at <= deleted_at) {
+                self.database.delete_monitor(uuid).await?;
+                local.remove(&uuid);
+                changed += 1;
+            }
+        }
+
+        let settings: HashMap<String, _> = self
+            .database
+            .get_settings()
+            .await?
+            .into_iter()
+            .map(|setting| (setting.key.clone(), setting))
+            .collect();
+        for setting in update.settings {
+            if LOCAL_SETTINGS.contains(&setting.key.as_str()) {
+                continue;
+            }
+            let newer = settings.get(&setting.key).is_none_or(|current| {
+                setting.updated_at > current.updated_at && setting.value != current.value
+            });
+            if newer {
+                self.database.save_setting(&setting).await?;
+            }
+        }
+
+        let mut channels: HashSet<Uuid> = self
+            .database
+            .get_notification_channels()
+            .await?
+            .into_iter()
+            .map(|channel| channel.uuid)
+            .collect();
+        for channel in update.notification_channels {
+            if !channels.contains(&channel.uuid) {
+                self.database.save_notification_channel(&channel).await?;
+                channels.insert(channel.uuid);
+            }
+        }
+        let mut routes: HashMap<RouteScope, NotificationRoute> = self
+            .database
+            .get_notification_routes()
+            .await?
+            .into_iter()
+            .map(|route| (route.scope.clone(), route))
+            .collect();
+        let known = |scope: &RouteScope| match scope {
+            RouteScope::Monitor(uuid) => local.contains_key(uuid),
+            _ => true,
+        };
+        // Nodes that predate routes only send the channels of monitors
+        let legacy = update.notification_routes.is_empty();
+        for mut route in update.notification_routes {
+            if !known(&route.scope) || !route_newer(routes.get(&route.scope), &route) {
+                continue;
+            }
+            route.channels.retain(|uuid| channels.contains(uuid));
+            if let Some(policy) = &mut route.escalation {
+                policy.channels.retain(|uuid| channels.contains(uuid));
+            }
+            self.database.save_notification_route(&route).await?;
+            routes.insert(route.scope.clone(), route);
+        }
+        if legacy {
+            for (monitor_uuid, channel_uuid) in update.monitor_channels {
+                let scope = RouteScope::Monitor(monitor_uuid);
+                if !known(&scope) || !channels.contains(&channel_uuid) {
+                    continue;
+                }
+                let route = match routes.entry(scope) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let scope = entry.key().clone();
+                        entry.insert(NotificationRoute::new(scope))
+                    }
+                };
+                if !route.channels.contains(&channel_uuid) {
+                    route.channels.push(channel_uuid);
+                    route.updated_at = SystemTime::now();
+                    self.database.save_notification_route(route).await?;
+                }
+            }
+        }
+
+        if changed > 0 {
+            info!("Took over {} monitor changes from cluster node {}", changed, update.node_id);
+        }
+        Ok(changed > 0)
+    }
+}
+
+/// Decide what to do with a monitor another node sent, by last writer wins
+fn monitor_change(
+    local: Option<&Monitor>,
+    deleted_at: Option<&SystemTime>,
+    remote: &Monitor,
+) -> MonitorChange {
+    match local {
+        Some(local) if remote.updated_at > local.updated_at => MonitorChange::Replace(local.id),
+        Some(_) => MonitorChange::Keep,
+        None if deleted_at.is_some_and(|at| *at >= remote.updated_at) => MonitorChange::Keep,
+        None => MonitorChange::Insert,
+    }
+}
+
+/// Whether a route another node sent should replace ours, by last writer wins
+fn route_newer(local: Option<&NotificationRoute>, remote: &NotificationRoute) -> bool {
+    local.is_none_or(|local| {
+        remote.updated_at > local.updated_at
+            && (remote.channels != local.channels || remote.escalation != local.escalation)
+    })
+}
+
+/// Split a state into updates of at most `MONITORS_PER_UPDATE` monitors; the
+/// first carries everything else
+fn split_update(mut update: ClusterUpdate) -> Vec<ClusterUpdate> {
+    let mut monitors = std::mem::take(&mut update.monitors);
+    let rest = monitors.split_off(monitors.len().min(MONITORS_PER_UPDATE));
+    update.monitors = monitors;
+
+    let node_id = update.node_id.clone();
+    let mut updates = vec![update];
+    for chunk in rest.chunks(MONITORS_PER_UPDATE) {
+        updates.push(ClusterUpdate {
+            node_id: node_id.clone(),
+            monitors: chunk.to_vec(),
+            ..ClusterUpdate::default()
+        });
+    }
+    updates
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor_at(updated_at: SystemTime) -> Monitor {
+        let mut monitor =
+            Monitor::new("API".to_string(), "https://example.com".to_string(), "http".to_string());
+        monitor.updated_at = updated_at;
+        monitor
+    }
+
+    #[test]
+    fn test_route_newer() {
+        let mut local = NotificationRoute::new(RouteScope::Default);
+        local.updated_at = UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut remote = local.clone();
+        remote.updated_at = UNIX_EPOCH + Duration::from_secs(2_000);
+
+        assert!(route_newer(None, &remote));
+        // A newer timestamp alone doesn't make it a change
+        assert!(!route_newer(Some(&local), &remote));
+        remote.channels = vec![Uuid::new_v4()];
+        assert!(route_newer(Some(&local), &remote));
+        assert!(!route_newer(Some(&remote), &local));
+    }
+
+    #[test]
+    fn test_monitor_change_last_writer_wins() {
+        let earlier = UNIX_EPOCH + Duration::from_secs(1_000);
+        let later = UNIX_EPOCH + Duration::from_secs(2_000);
+
+        let remote = monitor_at(later);
+        assert_eq!(monitor_change(None, None, &remote), MonitorChange::Insert);
+
+        let mut local = monitor_at(earlier);
+        local.id = Some(7);
+        assert_eq!(monitor_change(Some(&local), None, &remote), MonitorChange::Replace(Some(7)));
+        assert_eq!(monitor_change(Some(&remote), None, &local), MonitorChange::Keep);
+        assert_eq!(monitor_change(Some(&remote), None, &remote), MonitorChange::Keep);
+
+        // Deleted here after the other node last changed it
+        assert_eq!(monitor_change(None, Some(&later), &local), MonitorChange::Keep);
+        // Changed elsewhere after it was deleted here
+        assert_eq!(monitor_change(None, Some(&earlier), &remote), MonitorChange::Insert);
+    }
+
+    #[test]
+    fn test_split_update() {
+        let now = SystemTime::now();
+        let update = ClusterUpdate {
+            node_id: "node".to_string(),
+            monitors: (0..MONITORS_PER_UPDATE * 2 + 1).map(|_| monitor_at(now)).collect(),
+            deleted_monitors: vec![(Uuid::new_v4(), 1)],
+            ..ClusterUpdate::default()
+        };
+
+        let updates = split_update(update);
+        assert_eq!(updates.len(), 3);
+        assert_eq!(updates[0].monitors.len(), MONITORS_PER_UPDATE);
+        assert_eq!(updates[0].deleted_monitors.len(), 1);
+        assert_eq!(updates[2].monitors.len(), 1);
+        assert!(updates[2].deleted_monitors.is_empty());
+        assert!(updates.iter().all(|update| update.node_id == "node"));
+
+        assert_eq!(split_update(ClusterUpdate::default()).len(), 1);
+    }
+}
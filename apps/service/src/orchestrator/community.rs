@@ -0,0 +1,283 @@
+/// Community domains - monitoring public hosts together with other peers
+///
+/// Joining a domain adds a public monitor checking it, unless one already
+/// does, and has the manager announce this node as a participant; leaving
+/// pauses the monitor joining added and announces that. Announcements are
+/// signed, so a peer only speaks for itself and its latest announcement
+/// decides whether it takes part. They are repeated while they matter, so
+/// peers coming online learn who takes part and a join that stops being
+/// repeated expires. Verified results a participant shares for a domain count
+/// towards its contribution score.
+use anyhow::{Result, anyhow};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::runtime::RuntimeSettings;
+use crate::crypto::{KeyPair, sign_membership_announcement};
+use crate::database::Database;
+use crate::database::models::{
+    CommunityDomain, Follow, GroupMembership, Monitor, MonitorVisibility, PublicMonitorGroup,
+};
+use crate::p2p::P2PHandle;
+
+/// How often domain changes are picked up
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// How often memberships are announced again; well within their TTL
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Participating peers of each community domain, shared with the peer event
+/// handler
+pub type CommunityGroups = Arc<RwLock<HashMap<String, HashSet<String>>>>;
+
+/// Task announcing our memberships and keeping the groups up to date
+pub struct CommunityManager {
+    database: Arc<dyn Database>,
+    p2p: P2PHandle,
+    keypair: Arc<KeyPair>,
+    groups: CommunityGroups,
+}
+
+impl CommunityManager {
+    /// Create a manager updating the given groups
+    pub fn new(
+        database: Arc<dyn Database>,
+        p2p: P2PHandle,
+        keypair: Arc<KeyPair>,
+        groups: CommunityGroups,
+    ) -> Self {
+        Self { database, p2p, keypair, groups }
+    }
+
+    /// Spawn the manager; it stops once the settings channel closes
+    pub fn spawn(self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(POLL_INTERVAL);
+            // When each domain was last announced, and whether as joined
+            let mut announced: HashMap<String, (SystemTime, bool)> = HashMap::new();
+
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => {}
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+
+                // Announcements only go out while sharing with peers
+                if settings.borrow().p2p_sharing {
+                    match self.database.get_community_domains().await {
+                        Ok(domains) => {
+                            let now = SystemTime::now();
+                            for domain in domains {
+                                if !announce_due(&domain, announced.get(&domain.host), now) {
+                                    continue;
+                                }
+                                match self.announce(&domain, now).await {
+                                    Ok(()) => {
+                                        announced.insert(domain.host, (now, domain.joined));
+                                    }
+                                    Err(e) => {
+                                        debug!("Failed to announce {}: {}", domain.host, e)
+                                    }
+                                }
+                            }
+                        }
+                        Err(e) => warn!("Failed to load community domains: {}", e),
+                    }
+                }
+
+                match self.database.get_group_memberships().await {
+                    Ok(memberships) => {
+                        let groups =
+                            PublicMonitorGroup::from_memberships(&memberships, SystemTime::now())
+                                .into_iter()
+                                .map(|group| {
+                                    (group.host, group.participating_peers.into_iter().collect())
+                                })
+                                .collect();
+                        *self.groups.write().unwrap() = groups;
+                    }
+                    Err(e) => warn!("Failed to load group memberships: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Sign, store and publish our membership of a domain
+    async fn announce(&self, domain: &CommunityDomain, now: SystemTime) -> Result<()> {
+        let announcement =
+            sign_membership_announcement(&domain.host, domain.joined, &self.keypair, now)?;
+        // Gossip doesn't hand our own messages back, so store it here
+        self.database
+            .save_group_membership(&GroupMembership {
+                host: domain.host.clone(),
+                peer_id: announcement.peer_id.clone(),
+                joined: domain.joined,
+                announced_at: now,
+            })
+            .await?;
+        self.p2p.publish_membership(announcement).await?;
+        info!(
+            "Announced {} monitoring {}",
+            if domain.joined { "joining" } else { "leaving" },
+            domain.host
+        );
+        Ok(())
+    }
+}
+
+/// Whether a domain needs announcing: it changed since the last announcement
+/// or that is getting old. A leave stops being announced once the join it
+/// undoes would have expired anyway.
+fn announce_due(
+    domain: &CommunityDomain,
+    last: Option<&(SystemTime, bool)>,
+    now: SystemTime,
+) -> bool {
+    let left_for = now.duration_since(domain.updated_at).unwrap_or_default();
+    if !domain.joined && left_for > PublicMonitorGroup::MEMBERSHIP_TTL {
+        return false;
+    }
+    last.is_none_or(|&(at, joined)| {
+        joined != domain.joined
+            || domain.updated_at > at
+            || now.duration_since(at).unwrap_or_default() >= ANNOUNCE_INTERVAL
+    })
+}
+
+/// Community domain a target points at, if the peer takes part in it
+pub fn contributing_host(groups: &CommunityGroups, target: &str, peer_id: &str) -> Option<String> {
+    let host = Follow::host_of(target)?;
+    let groups = groups.read().unwrap();
+    groups.get(&host).is_some_and(|peers| peers.contains(peer_id)).then_some(host)
+}
+
+/// Join monitoring the domain a target points at
+///
+/// A public monitor checking the target is added unless an enabled one
+/// already checks the domain; one an earlier join added is resumed instead.
+/// The running service announces the join within `POLL_INTERVAL`.
+pub async fn join(database: &dyn Database, target: &str) -> Result<CommunityDomain> {
+    let host = Follow::host_of(target).ok_or_else(|| anyhow!("No host in '{target}'"))?;
+    let now = SystemTime::now();
+    let previous = database.get_community_domains().await?.into_iter().find(|d| d.host == host);
+    let monitors = database.get_monitors().await?;
+
+    let added = previous
+        .and_then(|domain| domain.monitor_uuid)
+        .and_then(|uuid| monitors.iter().find(|monitor| monitor.uuid == uuid));
+    let monitor_uuid = if let Some(monitor) = added {
+        if !monitor.enabled {
+            let mut monitor = monitor.clone();
+            monitor.enabled = true;
+            monitor.updated_at = now;
+            database.save_monitor(&monitor).await?;
+        }
+        Some(monitor.uuid)
+    } else if monitors.iter().any(|monitor| {
+        monitor.enabled
+            && monitor.visibility == MonitorVisibility::Public
+            && Follow::host_of(&monitor.target).as_ref() == Some(&host)
+    }) {
+        None
+    } else {
+        // A bare host is checked over HTTPS
+        let (target, check_type) = match url::Url::parse(target.trim()) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {
+                (target.trim().to_string(), url.scheme().to_string())
+            }
+            _ => (format!("https://{host}"), "https".to_string()),
+        };
+        let validation = crate::validation::validate_monitor_target(&target, &check_type);
+        if !validation.is_valid {
+            anyhow::bail!(validation.error.unwrap_or_else(|| "Invalid target".to_string()));
+        }
+        let monitor = Monitor::new(host.clone(), target, check_type);
+        database.save_monitor(&monitor).await?;
+        Some(monitor.uuid)
+    };
+
+    let domain = CommunityDomain { host, joined: true, monitor_uuid, updated_at: now };
+    database.save_community_domain(&domain).await?;
+    Ok(domain)
+}
+
+/// Leave monitoring a domain, pausing the monitor joining added; `None` when
+/// the domain wasn't joined
+pub async fn leave(database: &dyn Database, host: &str) -> Result<Option<CommunityDomain>> {
+    let host = Follow::host_of(host).ok_or_else(|| anyhow!("No host in '{host}'"))?;
+    let Some(mut domain) = database
+        .get_community_domains()
+        .await?
+        .into_iter()
+        .find(|domain| domain.host == host && domain.joined)
+    else {
+        return Ok(None);
+    };
+
+    let now = SystemTime::now();
+    if let Some(uuid) = domain.monitor_uuid
+        && let Some(mut monitor) = database.get_monitor_by_uuid(uuid).await?
+        && monitor.enabled
+    {
+        monitor.enabled = false;
+        monitor.updated_at = now;
+        database.save_monitor(&monitor).await?;
+    }
+
+    domain.joined = false;
+    domain.updated_at = now;
+    database.save_community_domain(&domain).await?;
+    Ok(Some(domain))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domain(joined: bool, updated_at: SystemTime) -> CommunityDomain {
+        CommunityDomain { host: "example.com".to_string(), joined, monitor_uuid: None, updated_at }
+    }
+
+    #[test]
+    fn test_announce_due() {
+        let now = SystemTime::now();
+        let joined = domain(true, now - Duration::from_secs(60));
+
+        assert!(announce_due(&joined, None, now));
+        assert!(!announce_due(&joined, Some(&(now - Duration::from_secs(30), true)), now));
+        // Repeated before it expires, and again after a change
+        assert!(announce_due(&joined, Some(&(now - ANNOUNCE_INTERVAL, true)), now));
+        assert!(announce_due(&joined, Some(&(now - Duration::from_secs(90), true)), now));
+        assert!(announce_due(&joined, Some(&(now - Duration::from_secs(30), false)), now));
+
+        // A leave is announced until the join it undoes has expired
+        let left = domain(false, now - Duration::from_secs(60));
+        assert!(announce_due(&left, Some(&(now - Duration::from_secs(30), true)), now));
+        let long_left = domain(false, now - PublicMonitorGroup::MEMBERSHIP_TTL * 2);
+        assert!(!announce_due(&long_left, None, now));
+    }
+
+    #[test]
+    fn test_contributing_host() {
+        let groups = CommunityGroups::default();
+        groups
+            .write()
+            .unwrap()
+            .insert("example.com".to_string(), HashSet::from(["peer-a".to_string()]));
+
+        assert_eq!(
+            contributing_host(&groups, "https://Example.com/health", "peer-a").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(contributing_host(&groups, "https://example.com", "peer-b"), None);
+        assert_eq!(contributing_host(&groups, "https://example.org", "peer-a"), None);
+    }
+}
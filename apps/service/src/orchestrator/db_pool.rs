@@ -0,0 +1,159 @@
+/// Database pool monitor - reports pool usage and sizes the pool to demand
+///
+/// Every minute the pool's size, idle and busy connections and waiting callers
+/// are recorded as `uppe.db.pool.*` gauges and summarized in the log, with a
+/// warning when callers waited long for a connection. The pool grows towards
+/// the configured maximum while callers wait, and shrinks back towards the
+/// minimum once it has been quiet for a few minutes.
+use opentelemetry::global;
+use opentelemetry::metrics::Gauge;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::runtime::RuntimeSettings;
+use crate::config::DatabasePoolConfig;
+use crate::pool::LibsqlPool;
+
+/// How often the pool is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// Quiet checks in a row before the pool shrinks
+const IDLE_POLLS: u32 = 5;
+
+/// Task watching the database pool
+pub struct PoolMonitor {
+    pool: LibsqlPool,
+    config: DatabasePoolConfig,
+    /// Checks in a row without waiting callers
+    idle_polls: u32,
+    size: Gauge<u64>,
+    max_size: Gauge<u64>,
+    available: Gauge<u64>,
+    in_use: Gauge<u64>,
+    waiting: Gauge<u64>,
+}
+
+impl PoolMonitor {
+    /// Create a monitor for the given pool
+    pub fn new(pool: LibsqlPool, config: DatabasePoolConfig) -> Self {
+        let meter = global::meter("uppe-service");
+        let gauge = |name: &'static str, description: &'static str| {
+            meter.u64_gauge(name).with_description(description).build()
+        };
+        Self {
+            pool,
+            config,
+            idle_polls: 0,
+            size: gauge("uppe.db.pool.size", "Open database connections"),
+            max_size: gauge("uppe.db.pool.max_size", "Database connections the pool allows"),
+            available: gauge("uppe.db.pool.available", "Idle database connections"),
+            in_use: gauge("uppe.db.pool.in_use", "Database connections in use"),
+            waiting: gauge("uppe.db.pool.waiting", "Callers waiting for a database connection"),
+        }
+    }
+
+    /// Spawn the monitor; it stops once the settings channel closes
+    pub fn spawn(mut self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(POLL_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => self.poll(),
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn poll(&mut self) {
+        let status = self.pool.status();
+        let waits = self.pool.manager().take_waits();
+        let in_use = status.size - status.available;
+
+        self.size.record(status.size as u64, &[]);
+        self.max_size.record(status.max_size as u64, &[]);
+        self.available.record(status.available as u64, &[]);
+        self.in_use.record(in_use as u64, &[]);
+        self.waiting.record(status.waiting as u64, &[]);
+
+        if waits.slow > 0 {
+            warn!(
+                "Database pool: {} of {} slow connection waits (longest {:?}), {} of {} \
+                 connections in use, {} waiting",
+                waits.slow, waits.count, waits.longest, in_use, status.max_size, status.waiting
+            );
+        } else {
+            debug!(
+                "Database pool: {} connection waits (average {:?}), {} of {} connections in use",
+                waits.count,
+                waits.average(),
+                in_use,
+                status.max_size
+            );
+        }
+
+        let busy = status.waiting > 0 || waits.slow > 0;
+        if let Some(size) = next_size(status.max_size, busy, &mut self.idle_polls, &self.config) {
+            self.pool.resize(size);
+            info!("Resized database pool from {} to {} connections", status.max_size, size);
+        }
+    }
+}
+
+/// Pool size after a check, `None` to keep `current`
+///
+/// The pool doubles while callers wait and gives up a quarter of its
+/// connections after `IDLE_POLLS` quiet checks in a row.
+fn next_size(
+    current: usize,
+    busy: bool,
+    idle_polls: &mut u32,
+    config: &DatabasePoolConfig,
+) -> Option<usize> {
+    let min = config.min_size.max(1);
+    let max = config.max_size.max(min);
+
+    let size = if busy {
+        *idle_polls = 0;
+        (current * 2).min(max)
+    } else {
+        *idle_polls += 1;
+        if *idle_polls < IDLE_POLLS {
+            return None;
+        }
+        *idle_polls = 0;
+        (current * 3 / 4).max(min)
+    };
+    (size != current).then_some(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_size() {
+        let config = DatabasePoolConfig { min_size: 4, max_size: 12, ..Default::default() };
+        let mut idle = 0;
+
+        assert_eq!(next_size(4, true, &mut idle, &config), Some(8));
+        assert_eq!(next_size(8, true, &mut idle, &config), Some(12));
+        assert_eq!(next_size(12, true, &mut idle, &config), None);
+
+        // Shrinks only after a quiet stretch, and not below the minimum
+        for _ in 1..IDLE_POLLS {
+            assert_eq!(next_size(12, false, &mut idle, &config), None);
+        }
+        assert_eq!(next_size(12, false, &mut idle, &config), Some(9));
+        for _ in 1..IDLE_POLLS {
+            next_size(5, false, &mut idle, &config);
+        }
+        assert_eq!(next_size(5, false, &mut idle, &config), Some(4));
+    }
+}
@@ -0,0 +1,236 @@
+/// Publish deduplication - one result per monitor and time bucket among an
+/// owner's nodes
+///
+/// An owner running a monitor on several nodes would otherwise publish each of
+/// their results. Results are grouped by owner, monitor and normalized target,
+/// and for each time bucket a rendezvous hash over the owner's nodes recently
+/// seen checking the monitor picks the one that publishes. Only our cluster's
+/// nodes, which proved they hold the cluster key, take part; other peers
+/// checking the same target do not hold back our results. Status changes are
+/// always published. Monitors nobody checked for a while are forgotten.
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::cache::ExpiringMap;
+use crate::monitoring::CheckResult;
+
+/// Width of a deduplication bucket
+const DEDUP_BUCKET: Duration = Duration::from_secs(60);
+/// How long a peer counts as checking a monitor after its last result for it
+const PEER_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// How long a node counts as a cluster member after its last sealed update
+const MEMBER_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+/// Monitors tracked at most
+const MAX_MONITORS: usize = 10_000;
+/// Cluster members tracked at most
+const MAX_MEMBERS: usize = 256;
+
+/// Publish schedule shared by the result pipeline, the peer event handler and
+/// the cluster sync
+pub type SharedPublishSchedule = Arc<Mutex<PublishSchedule>>;
+
+/// What results are deduplicated by: owner, monitor and normalized target
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PublishKey {
+    owner: String,
+    monitor_id: Uuid,
+    target: String,
+}
+
+/// Which node publishes each monitor's results, and what was published already
+#[derive(Debug)]
+pub struct PublishSchedule {
+    /// Owner our results are published for: our cluster, or our own peer ID
+    owner: String,
+    /// Nodes of our cluster, with when their last sealed update arrived
+    members: ExpiringMap<String, Instant>,
+    /// Members recently seen publishing each monitor, with when they were last seen
+    peers: ExpiringMap<PublishKey, HashMap<String, Instant>>,
+    /// Last bucket a result was published for, per monitor
+    published: ExpiringMap<PublishKey, u64>,
+}
+
+impl PublishSchedule {
+    /// Create a schedule for the results of `owner`
+    pub fn new(owner: impl Into<String>) -> Self {
+        Self {
+            owner: owner.into(),
+            members: ExpiringMap::new("publish_members", MAX_MEMBERS, MEMBER_WINDOW),
+            peers: ExpiringMap::new("publish_peers", MAX_MONITORS, PEER_WINDOW),
+            // Only the current bucket matters; keep one more for results near its edge
+            published: ExpiringMap::new("published_buckets", MAX_MONITORS, DEDUP_BUCKET * 2),
+        }
+    }
+
+    /// Create a shared schedule for the results of `owner`
+    pub fn shared(owner: impl Into<String>) -> SharedPublishSchedule {
+        Arc::new(Mutex::new(Self::new(owner)))
+    }
+
+    /// Note that `peer_id` is a node of our cluster
+    pub fn admit(&mut self, peer_id: &str, now: Instant) {
+        self.members.insert(peer_id.to_string(), now, now);
+    }
+
+    /// Note that a peer published a result for a monitor; only cluster
+    /// members are scheduled
+    pub fn observe(&mut self, monitor_id: Uuid, target: &str, peer_id: &str, now: Instant) {
+        if self.members.get(&peer_id.to_string(), now).is_none() {
+            return;
+        }
+        self.peers
+            .get_or_insert_with(self.key(monitor_id, target), now, HashMap::new)
+            .insert(peer_id.to_string(), now);
+    }
+
+    /// Whether we should publish `result`, recording it as published if so
+    ///
+    /// A result is held back if one for the same monitor and bucket went out
+    /// already, or if another of our nodes is scheduled to publish the bucket.
+    pub fn should_publish(
+        &mut self,
+        local_peer_id: &str,
+        result: &CheckResult,
+        status_changed: bool,
+        now: Instant,
+    ) -> bool {
+        let key = self.key(result.monitor_id, &result.target);
+        let bucket = bucket_index(result.timestamp);
+
+        if !status_changed {
+            if self.published.get(&key, now) == Some(&bucket) {
+                return false;
+            }
+            if self.publisher(&key, local_peer_id, bucket, now) != local_peer_id {
+                return false;
+            }
+        }
+
+        self.published.insert(key, bucket, now);
+        true
+    }
+
+    fn key(&self, monitor_id: Uuid, target: &str) -> PublishKey {
+        PublishKey { owner: self.owner.clone(), monitor_id, target: normalize_target(target) }
+    }
+
+    /// Node scheduled to publish a monitor's bucket, out of ourselves and the
+    /// members seen checking the monitor recently
+    fn publisher<'a>(
+        &'a mut self,
+        key: &PublishKey,
+        local_peer_id: &'a str,
+        bucket: u64,
+        now: Instant,
+    ) -> &'a str {
+        let Some(peers) = self.peers.get_mut(key, now) else {
+            return local_peer_id;
+        };
+        peers.retain(|_, seen| now.duration_since(*seen) < PEER_WINDOW);
+
+        peers
+            .keys()
+            .map(String::as_str)
+            .chain(std::iter::once(local_peer_id))
+            .min_by_key(|peer_id| rendezvous_score(peer_id, key, bucket))
+            .unwrap_or(local_peer_id)
+    }
+}
+
+/// Target in the form used in the deduplication key
+fn normalize_target(target: &str) -> String {
+    target.trim().trim_end_matches('/').to_lowercase()
+}
+
+fn bucket_index(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / DEDUP_BUCKET.as_secs()
+}
+
+/// Score every node computes the same way; the lowest one publishes
+fn rendezvous_score(peer_id: &str, key: &PublishKey, bucket: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(peer_id.as_bytes());
+    hasher.update([0]);
+    hasher.update(key.owner.as_bytes());
+    hasher.update([0]);
+    hasher.update(key.monitor_id.as_bytes());
+    hasher.update(key.target.as_bytes());
+    hasher.update(bucket.to_be_bytes());
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: &str = "https://example.com";
+
+    fn result(monitor_id: Uuid, target: &str, secs: u64) -> CheckResult {
+        let mut result = CheckResult::new(monitor_id, target.to_string(), "local".to_string());
+        result.timestamp = UNIX_EPOCH + Duration::from_secs(secs);
+        result
+    }
+
+    #[test]
+    fn test_same_bucket_published_once() {
+        let now = Instant::now();
+        let monitor = Uuid::new_v4();
+        let mut schedule = PublishSchedule::new("owner");
+
+        assert!(schedule.should_publish(
+            "local",
+            &result(monitor, "https://Example.com/", 600),
+            false,
+            now
+        ));
+        assert!(!schedule.should_publish("local", &result(monitor, TARGET, 630), false, now));
+        // Status changes always go out
+        assert!(schedule.should_publish("local", &result(monitor, TARGET, 640), true, now));
+        assert!(schedule.should_publish("local", &result(monitor, TARGET, 660), false, now));
+        // Another monitor of the same target has its own buckets
+        assert!(schedule.should_publish("local", &result(Uuid::new_v4(), TARGET, 670), false, now));
+    }
+
+    #[test]
+    fn test_schedule_picks_one_publisher() {
+        let now = Instant::now();
+        let monitor = Uuid::new_v4();
+        let mut a = PublishSchedule::new("cluster");
+        let mut b = PublishSchedule::new("cluster");
+        a.admit("b", now);
+        b.admit("a", now);
+        a.observe(monitor, TARGET, "b", now);
+        b.observe(monitor, TARGET, "a", now);
+
+        // Exactly one of the two nodes publishes each bucket
+        for secs in (0..6000).step_by(60) {
+            let published_a = a.should_publish("a", &result(monitor, TARGET, secs), false, now);
+            let published_b = b.should_publish("b", &result(monitor, TARGET, secs), false, now);
+            assert!(published_a ^ published_b);
+        }
+
+        // A peer that went quiet no longer holds its buckets
+        let later = now + PEER_WINDOW;
+        for secs in (6000..7200).step_by(60) {
+            assert!(a.should_publish("a", &result(monitor, TARGET, secs), false, later));
+        }
+    }
+
+    #[test]
+    fn test_outside_peers_do_not_hold_back_results() {
+        let now = Instant::now();
+        let monitor = Uuid::new_v4();
+        let mut schedule = PublishSchedule::new("cluster");
+        // Peers outside the cluster publishing the same monitor and target
+        for peer in 0..32 {
+            schedule.observe(monitor, TARGET, &format!("outsider-{peer}"), now);
+        }
+
+        for secs in (0..6000).step_by(60) {
+            assert!(schedule.should_publish("local", &result(monitor, TARGET, secs), false, now));
+        }
+    }
+}
@@ -0,0 +1,188 @@
+/// DHT debugging - lookups and publishes requested from the TUI
+///
+/// The TUI queues pending rows in `dht_operations`. This task picks them up,
+/// sends each to the node as a lookup or publish under the debug key
+/// namespace, and stores the outcome once the node reports it or the deadline
+/// passed.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::runtime::RuntimeSettings;
+use crate::database::Database;
+use crate::database::models::{DhtOperation, DhtOperationKind, ProbeState};
+use crate::p2p::P2PHandle;
+use crate::validation;
+
+/// How often queued operations are picked up
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Time the node gets to report an outcome
+const OPERATION_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Outcome of an operation, forwarded by the peer event handler
+#[derive(Debug)]
+pub struct DhtOutcome {
+    pub operation_id: Uuid,
+    pub records: Vec<Vec<u8>>,
+    pub error: Option<String>,
+}
+
+/// Task running queued DHT debug operations
+pub struct DhtDebug {
+    database: Arc<dyn Database>,
+    p2p: P2PHandle,
+    outcomes: mpsc::Receiver<DhtOutcome>,
+    /// Operations sent to the node, with when they time out
+    in_flight: HashMap<Uuid, (DhtOperation, Instant)>,
+}
+
+impl DhtDebug {
+    /// Create the task, returning the sender outcomes are forwarded on
+    pub fn new(database: Arc<dyn Database>, p2p: P2PHandle) -> (Self, mpsc::Sender<DhtOutcome>) {
+        let (tx, outcomes) = mpsc::channel(16);
+        let debug = Self { database, p2p, outcomes, in_flight: HashMap::new() };
+        (debug, tx)
+    }
+
+    /// Spawn the task; it stops once the settings channel or the outcome
+    /// sender closes
+    pub fn spawn(mut self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            self.fail_interrupted().await;
+            let mut timer = tokio::time::interval(POLL_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => {
+                        self.start_pending().await;
+                        self.fail_expired(Instant::now()).await;
+                    }
+                    outcome = self.outcomes.recv() => match outcome {
+                        Some(outcome) => self.finish(outcome).await,
+                        None => break,
+                    },
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Outcomes of operations sent before a restart are lost
+    async fn fail_interrupted(&self) {
+        match self.database.get_dht_operations_in_state(ProbeState::Running).await {
+            Ok(running) => {
+                for mut operation in running {
+                    fail(&mut operation, "interrupted by a restart");
+                    self.save(&operation).await;
+                }
+            }
+            Err(e) => warn!("Failed to load running DHT operations: {}", e),
+        }
+    }
+
+    async fn start_pending(&mut self) {
+        let pending = match self.database.get_dht_operations_in_state(ProbeState::Pending).await {
+            Ok(pending) => pending,
+            Err(e) => {
+                warn!("Failed to load pending DHT operations: {}", e);
+                return;
+            }
+        };
+
+        for mut operation in pending {
+            match self.start(&operation).await {
+                Ok(()) => {
+                    operation.state = ProbeState::Running;
+                    self.save(&operation).await;
+                    let deadline = Instant::now() + OPERATION_TIMEOUT;
+                    self.in_flight.insert(operation.uuid, (operation, deadline));
+                }
+                Err(e) => {
+                    fail(&mut operation, &e.to_string());
+                    self.save(&operation).await;
+                }
+            }
+        }
+    }
+
+    async fn start(&self, operation: &DhtOperation) -> anyhow::Result<()> {
+        let value = operation.value.as_deref().unwrap_or_default();
+        // Rows can be written by anything with database access
+        let check = validation::validate_dht_record(&operation.key, value);
+        if !check.is_valid {
+            anyhow::bail!(check.error.unwrap_or_else(|| "invalid record".to_string()));
+        }
+
+        match operation.kind {
+            DhtOperationKind::Get => {
+                self.p2p.get_debug_record(operation.uuid, operation.key.clone()).await
+            }
+            DhtOperationKind::Put => {
+                info!("Publishing DHT debug record {}", operation.key);
+                self.p2p
+                    .publish_debug_record(
+                        operation.uuid,
+                        operation.key.clone(),
+                        value.as_bytes().to_vec(),
+                    )
+                    .await
+            }
+        }
+    }
+
+    async fn finish(&mut self, outcome: DhtOutcome) {
+        let Some((mut operation, _)) = self.in_flight.remove(&outcome.operation_id) else {
+            return;
+        };
+
+        match outcome.error {
+            Some(error) => fail(&mut operation, &error),
+            None => {
+                operation.state = ProbeState::Complete;
+                operation.records = outcome
+                    .records
+                    .iter()
+                    .map(|value| String::from_utf8_lossy(value).into_owned())
+                    .collect();
+                operation.completed_at = Some(SystemTime::now());
+            }
+        }
+        self.save(&operation).await;
+    }
+
+    async fn fail_expired(&mut self, now: Instant) {
+        let expired: Vec<Uuid> = self
+            .in_flight
+            .iter()
+            .filter(|(_, (_, deadline))| *deadline <= now)
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in expired {
+            if let Some((mut operation, _)) = self.in_flight.remove(&id) {
+                fail(&mut operation, "no answer from the DHT");
+                self.save(&operation).await;
+            }
+        }
+    }
+
+    async fn save(&self, operation: &DhtOperation) {
+        if let Err(e) = self.database.save_dht_operation(operation).await {
+            warn!("Failed to save DHT operation {}: {}", operation.uuid, e);
+        }
+    }
+}
+
+fn fail(operation: &mut DhtOperation, error: &str) {
+    operation.state = ProbeState::Failed;
+    operation.error = Some(error.to_string());
+    operation.completed_at = Some(SystemTime::now());
+}
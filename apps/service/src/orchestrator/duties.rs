@@ -0,0 +1,283 @@
+/// Helper duties - checks we accepted to run for other owners
+///
+/// Accepted, unexpired duties are read from the assignment store and each runs
+/// at the interval its owner asked for, as long as the owner has checks left in
+/// the hourly budget `[helper]` allows each owner. Like a probe, the check connects only
+/// to the addresses our probe policy resolved for the target. The result is
+/// signed with our key, sealed so only the owner can read it and published on
+/// the encrypted results topic; nothing about it is stored here.
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use super::assignments::SharedAssignments;
+use super::audit::AuditLog;
+use super::capacity::SharedCapacity;
+use super::runtime::RuntimeSettings;
+use crate::crypto::{KeyPair, encrypt_result, sign_result};
+use crate::database::models::{AuditKind, HelperAssignment};
+use crate::monitoring::checker::CheckType;
+use crate::monitoring::{CheckResult, MonitoringExecutor};
+use crate::p2p::{EncryptedResultMessage, P2PHandle, PayloadSchema};
+use crate::policy::ProbePolicy;
+use crate::redact::ErrorRedactor;
+
+/// How often due duties are looked for
+const TICK: Duration = Duration::from_secs(5);
+/// Shortest interval a duty runs at, whatever its owner asked for
+const MIN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Task running the checks of accepted helper duties
+pub struct HelperDuties {
+    assignments: SharedAssignments,
+    capacity: SharedCapacity,
+    executor: Arc<MonitoringExecutor>,
+    policy: Arc<ProbePolicy>,
+    keypair: Arc<KeyPair>,
+    p2p: P2PHandle,
+    audit: AuditLog,
+    redactor: Arc<ErrorRedactor>,
+    /// When each duty runs next, by assignment ID
+    next_run: HashMap<Uuid, Instant>,
+}
+
+impl HelperDuties {
+    /// Create a task running the duties kept in `assignments`
+    pub fn new(
+        assignments: SharedAssignments,
+        capacity: SharedCapacity,
+        executor: Arc<MonitoringExecutor>,
+        policy: Arc<ProbePolicy>,
+        keypair: Arc<KeyPair>,
+        p2p: P2PHandle,
+        audit: AuditLog,
+    ) -> Self {
+        Self {
+            assignments,
+            capacity,
+            executor,
+            policy,
+            keypair,
+            p2p,
+            audit,
+            redactor: Arc::new(ErrorRedactor::default()),
+            next_run: HashMap::new(),
+        }
+    }
+
+    /// Redact error messages with the given redactor instead of the defaults
+    pub fn with_redactor(mut self, redactor: Arc<ErrorRedactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Spawn the task; it stops once the settings channel closes
+    pub fn spawn(mut self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(TICK);
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => {}
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+
+                // Results only reach owners while sharing with peers
+                if !settings.borrow().p2p_sharing {
+                    continue;
+                }
+                for duty in self.due(Instant::now()).await {
+                    self.run(duty).await;
+                }
+            }
+        })
+    }
+
+    /// Duties due to run at `now`, whose next run is scheduled
+    async fn due(&mut self, now: Instant) -> Vec<HelperAssignment> {
+        let wall = SystemTime::now();
+        let duties: Vec<HelperAssignment> = self
+            .assignments
+            .lock()
+            .await
+            .helper_duties()
+            .filter(|duty| duty.is_accepted() && !duty.is_expired(wall))
+            .cloned()
+            .collect();
+        self.next_run
+            .retain(|id, _| duties.iter().any(|duty| duty.assignment_id == *id));
+
+        duties
+            .into_iter()
+            .filter(|duty| {
+                // A newly accepted duty runs right away
+                let next = self.next_run.entry(duty.assignment_id).or_insert(now);
+                if *next > now {
+                    return false;
+                }
+                *next = now + interval(duty);
+                true
+            })
+            .collect()
+    }
+
+    /// Run a duty's check off the loop and send the sealed result to its owner
+    async fn run(&self, duty: HelperAssignment) {
+        let allowed = self
+            .capacity
+            .lock()
+            .unwrap()
+            .try_record_check(&duty.owner_peer_id, Instant::now());
+        if !allowed {
+            debug!(
+                "Skipped check for assignment {}: owner {} used up its hourly checks",
+                duty.assignment_id, duty.owner_peer_id
+            );
+            return;
+        }
+
+        let (host, addrs) = match self.policy.resolve(&duty.target, &duty.check_type).await {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                self.audit
+                    .record(
+                        AuditKind::PolicyRejected,
+                        Some(&duty.owner_peer_id),
+                        format!("Skipped check for assignment {}: {}", duty.assignment_id, e),
+                    )
+                    .await;
+                return;
+            }
+        };
+
+        let executor = self.executor.clone();
+        let keypair = self.keypair.clone();
+        let p2p = self.p2p.clone();
+        let redactor = self.redactor.clone();
+        let assignments = self.assignments.clone();
+        tokio::spawn(async move {
+            let mut result = executor
+                .execute_pinned_check(duty.target.clone(), check_type(&duty), &host, &addrs)
+                .await;
+            result.monitor_id = duty.monitor_uuid;
+            result.error_message = redactor.redact_opt(result.error_message.as_deref());
+
+            let message = match seal(&duty, result, &keypair) {
+                Ok(message) => message,
+                Err(e) => {
+                    warn!("Failed to seal result for assignment {}: {}", duty.assignment_id, e);
+                    return;
+                }
+            };
+            if let Err(e) = p2p.publish_encrypted_result(message).await {
+                debug!("Failed to send result for assignment {}: {}", duty.assignment_id, e);
+                return;
+            }
+            if let Err(e) = assignments
+                .lock()
+                .await
+                .record_result(duty.assignment_id, SystemTime::now())
+                .await
+            {
+                warn!("Failed to record result for assignment {}: {}", duty.assignment_id, e);
+            }
+        });
+    }
+}
+
+/// How often a duty runs
+fn interval(duty: &HelperAssignment) -> Duration {
+    Duration::from_secs(duty.interval_seconds).max(MIN_INTERVAL)
+}
+
+fn check_type(duty: &HelperAssignment) -> CheckType {
+    match duty.check_type.as_str() {
+        "tcp" => CheckType::Tcp,
+        "icmp" => CheckType::Icmp,
+        "cert_expiry" => CheckType::CertExpiry,
+        _ if duty.target.starts_with("https://") => CheckType::Https,
+        _ => CheckType::Http,
+    }
+}
+
+/// Sign a duty's result with our key and seal it for the duty's owner
+pub(super) fn seal(
+    duty: &HelperAssignment,
+    result: CheckResult,
+    keypair: &KeyPair,
+) -> Result<EncryptedResultMessage> {
+    let owner_key = hex::decode(&duty.owner_peer_id)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| anyhow!("Invalid owner peer ID {}", duty.owner_peer_id))?;
+
+    let signature = sign_result(&result, keypair)?;
+    let result = result.with_signature(signature);
+    Ok(EncryptedResultMessage {
+        assignment_id: duty.assignment_id,
+        monitor_id: duty.monitor_uuid,
+        owner_peer_id: duty.owner_peer_id.clone(),
+        helper_peer_id: keypair.public_key_hex(),
+        sealed: encrypt_result(&result, &owner_key)?,
+        schema: PayloadSchema::default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::decrypt_result;
+    use crate::crypto::keys::generate_keypair;
+    use crate::database::models::AssignmentRole;
+
+    #[test]
+    fn test_seal_for_owner() {
+        let owner = generate_keypair();
+        let helper = generate_keypair();
+        let now = SystemTime::now();
+        let duty = HelperAssignment {
+            assignment_id: Uuid::new_v4(),
+            role: AssignmentRole::Helper,
+            monitor_uuid: Uuid::new_v4(),
+            owner_peer_id: owner.public_key_hex(),
+            helper_peer_id: helper.public_key_hex(),
+            target: "https://example.com".to_string(),
+            check_type: "https".to_string(),
+            interval_seconds: 10,
+            status: "accepted".to_string(),
+            created_at: now,
+            updated_at: now,
+            expires_at: None,
+            last_result_at: None,
+        };
+        assert_eq!(interval(&duty), MIN_INTERVAL);
+        assert_eq!(check_type(&duty), CheckType::Https);
+
+        let mut result =
+            CheckResult::new(duty.monitor_uuid, duty.target.clone(), helper.public_key_hex());
+        result.latency_ms = Some(42);
+        let message = seal(&duty, result, &helper).unwrap();
+        assert_eq!(message.helper_peer_id, helper.public_key_hex());
+
+        // Only the owner can open it, and the helper signed what is inside
+        let opened = decrypt_result(&message.sealed, &owner).unwrap();
+        assert_eq!(opened.monitor_id, duty.monitor_uuid);
+        assert_eq!(opened.latency_ms, Some(42));
+        assert_eq!(opened.signature, Some(sign_result(&opened, &helper).unwrap()));
+        assert!(decrypt_result(&message.sealed, &helper).is_err());
+
+        let stranger = HelperAssignment { owner_peer_id: "not-a-key".to_string(), ..duty };
+        let result =
+            CheckResult::new(stranger.monitor_uuid, stranger.target.clone(), String::new());
+        assert!(seal(&stranger, result, &helper).is_err());
+    }
+}
@@ -0,0 +1,707 @@
+/// Multi-vantage probes - checks a public monitor from several peers at once
+///
+/// A probe is requested through the CLI or TUI, which queue a pending row in
+/// `multi_vantage_results`. The fan-out picks pending rows up, asks up to the
+/// requested number of online peers (optionally only those in a given country
+/// or region, or a single chosen peer) to probe the monitor's target over the
+/// probe protocol, and aggregates their answers into that row once every peer
+/// answered or the deadline passed. It also answers probe requests from other peers, subject
+/// to our probe policy and a limit on concurrent probes.
+///
+/// Answers also tell how far away a peer is: the lowest round trip seen to
+/// each peer, less the time it spent probing, is kept for a day. A peer whose
+/// signed location is further from us than that round trip allows is not
+/// picked for, or attributed to, the region it claims.
+use peerup::{ProbeRequest, ProbeResponse};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Semaphore, mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::audit::AuditLog;
+use super::runtime::RuntimeSettings;
+use crate::cache::ExpiringMap;
+use crate::database::Database;
+use crate::database::models::{
+    AuditKind, MonitorVisibility, MultiVantageResult, Peer, ProbeState, Vantage,
+};
+use crate::location::{self, Location};
+use crate::monitoring::MonitoringExecutor;
+use crate::monitoring::checker::CheckType;
+use crate::monitoring::types::MonitorStatus;
+use crate::p2p::P2PHandle;
+use crate::policy::ProbePolicy;
+use crate::redact::ErrorRedactor;
+
+/// How often queued probes are picked up
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Time peers get on top of the probe timeout to answer
+const ANSWER_GRACE: Duration = Duration::from_secs(10);
+/// Probe requests from peers we run at the same time
+const MAX_CONCURRENT_PROBES: usize = 4;
+/// Prefix of the error in a response to a probe we refused to run
+const REFUSED_PREFIX: &str = "refused: ";
+/// How long an observed round trip to a peer is remembered
+const RTT_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+/// Peers whose round trip is remembered at once
+const MAX_RTT_PEERS: usize = 10_000;
+
+/// Probe protocol events forwarded by the peer event handler
+#[derive(Debug)]
+pub enum ProbeEvent {
+    /// A peer asked us to probe a target
+    Requested { peer_id: String, inbound_id: u64, request: Box<ProbeRequest> },
+    /// A peer answered one of our probes
+    Answered { probe_id: Uuid, peer_id: String, response: Box<ProbeResponse> },
+    /// One of our probes got no answer from a peer
+    Failed { probe_id: Uuid, peer_id: String, error: String },
+}
+
+/// A fan-out waiting for answers
+struct InFlight {
+    result: MultiVantageResult,
+    /// Peers asked that did not answer yet, with where they check from
+    waiting: HashMap<String, Option<String>>,
+    sent_at: Instant,
+    deadline: Instant,
+}
+
+/// Task running multi-vantage probes and answering probes from peers
+pub struct ProbeFanout {
+    database: Arc<dyn Database>,
+    executor: Arc<MonitoringExecutor>,
+    policy: Arc<ProbePolicy>,
+    p2p: P2PHandle,
+    audit: AuditLog,
+    /// Our peer ID, reported in probes we answer
+    peer_id: String,
+    events: mpsc::Receiver<ProbeEvent>,
+    in_flight: HashMap<Uuid, InFlight>,
+    probes: Arc<Semaphore>,
+    /// Lowest round trip seen to each peer, in milliseconds
+    rtts: ExpiringMap<String, u64>,
+    /// Redacts error messages in our answers
+    redactor: Arc<ErrorRedactor>,
+}
+
+impl ProbeFanout {
+    /// Create the fan-out, returning the sender probe events are forwarded on
+    pub fn new(
+        database: Arc<dyn Database>,
+        executor: Arc<MonitoringExecutor>,
+        policy: Arc<ProbePolicy>,
+        p2p: P2PHandle,
+        audit: AuditLog,
+        peer_id: String,
+    ) -> (Self, mpsc::Sender<ProbeEvent>) {
+        let (tx, events) = mpsc::channel(100);
+        let fanout = Self {
+            database,
+            executor,
+            policy,
+            p2p,
+            audit,
+            peer_id,
+            events,
+            in_flight: HashMap::new(),
+            probes: Arc::new(Semaphore::new(MAX_CONCURRENT_PROBES)),
+            rtts: ExpiringMap::new("peer_rtts", MAX_RTT_PEERS, RTT_WINDOW),
+            redactor: Arc::new(ErrorRedactor::default()),
+        };
+        (fanout, tx)
+    }
+
+    /// Redact error messages in our answers with the operator's rules
+    pub fn with_redactor(mut self, redactor: Arc<ErrorRedactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Spawn the fan-out; it stops once the settings channel or the event
+    /// sender closes
+    pub fn spawn(mut self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            self.fail_interrupted().await;
+            let mut timer = tokio::time::interval(POLL_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => {
+                        if settings.borrow().p2p_sharing {
+                            self.start_pending().await;
+                        }
+                        self.finish_expired(Instant::now()).await;
+                    }
+                    event = self.events.recv() => match event {
+                        Some(event) => {
+                            let sharing = settings.borrow().p2p_sharing;
+                            self.handle(event, sharing).await;
+                        }
+                        None => break,
+                    },
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Answers to probes sent before a restart are lost, so those probes end here
+    async fn fail_interrupted(&self) {
+        let running =
+            match self.database.get_multi_vantage_results_in_state(ProbeState::Running).await {
+                Ok(running) => running,
+                Err(e) => {
+                    warn!("Failed to load running probes: {}", e);
+                    return;
+                }
+            };
+
+        for mut result in running {
+            fail(&mut result, "interrupted by a restart");
+            self.save(&result).await;
+        }
+    }
+
+    async fn start_pending(&mut self) {
+        let pending =
+            match self.database.get_multi_vantage_results_in_state(ProbeState::Pending).await {
+                Ok(pending) => pending,
+                Err(e) => {
+                    warn!("Failed to load pending probes: {}", e);
+                    return;
+                }
+            };
+
+        for mut result in pending {
+            if let Err(e) = self.start(&mut result).await {
+                fail(&mut result, &e.to_string());
+                self.save(&result).await;
+            }
+        }
+    }
+
+    async fn start(&mut self, result: &mut MultiVantageResult) -> anyhow::Result<()> {
+        let monitor = self
+            .database
+            .get_monitor_by_uuid(result.monitor_uuid)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("monitor not found"))?;
+        if monitor.visibility == MonitorVisibility::Private {
+            anyhow::bail!("monitor is private");
+        }
+        // There is nothing to probe, and the target is a secret token
+        if monitor.is_heartbeat() {
+            anyhow::bail!("heartbeat monitors are pinged, not probed");
+        }
+
+        let peers = self.database.get_online_peers().await?;
+        let now = Instant::now();
+        let plausible = |peer: &Peer| self.location_plausible(peer, now);
+        let selected = match &result.vantage_peer_id {
+            Some(peer_id) => peers.iter().filter(|peer| &peer.peer_id == peer_id).collect(),
+            None => {
+                select_peers(&peers, result.region.as_deref(), result.peers_requested, plausible)
+            }
+        };
+        if selected.is_empty() {
+            match (&result.vantage_peer_id, &result.region) {
+                (Some(peer_id), _) => anyhow::bail!("peer {peer_id} is not online"),
+                (None, Some(region)) => anyhow::bail!("no online peers in {region}"),
+                (None, None) => anyhow::bail!("no online peers"),
+            }
+        }
+
+        let timeout = Duration::from_secs(monitor.timeout_seconds);
+        let request = ProbeRequest {
+            target_url: monitor.target.clone(),
+            method: probe_method(&monitor.check_type).to_string(),
+            timeout: timeout.as_millis() as u64,
+            body: None,
+            headers: None,
+            requested_by: self.peer_id.clone(),
+        };
+        let peer_ids = selected.iter().map(|peer| peer.peer_id.clone()).collect();
+        self.p2p.request_probes(result.uuid, peer_ids, request).await?;
+
+        info!(
+            "Probing monitor {} from {} peer(s) (probe {})",
+            monitor.uuid,
+            selected.len(),
+            result.uuid
+        );
+        result.state = ProbeState::Running;
+        self.save(result).await;
+        self.in_flight.insert(
+            result.uuid,
+            InFlight {
+                result: result.clone(),
+                waiting: selected
+                    .iter()
+                    .map(|peer| {
+                        let location = plausible(peer).then(|| peer_location(peer)).flatten();
+                        (peer.peer_id.clone(), location)
+                    })
+                    .collect(),
+                sent_at: now,
+                deadline: Instant::now() + timeout + ANSWER_GRACE,
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn handle(&mut self, event: ProbeEvent, sharing: bool) {
+        match event {
+            ProbeEvent::Requested { peer_id, inbound_id, request } => {
+                self.answer(peer_id, inbound_id, *request, sharing).await;
+            }
+            ProbeEvent::Answered { probe_id, peer_id, response } => {
+                self.observe_rtt(probe_id, &peer_id, &response);
+                self.record(probe_id, &peer_id, |region| vantage(&peer_id, region, &response))
+                    .await;
+            }
+            ProbeEvent::Failed { probe_id, peer_id, error } => {
+                debug!("Peer {} did not answer probe {}: {}", peer_id, probe_id, error);
+                self.record(probe_id, &peer_id, |region| Vantage {
+                    peer_id: peer_id.clone(),
+                    status: MonitorStatus::Unknown,
+                    latency_ms: None,
+                    status_code: None,
+                    error: Some(error.clone()),
+                    region,
+                })
+                .await;
+            }
+        }
+    }
+
+    /// Remember the round trip to a peer that answered, less the time it spent probing
+    fn observe_rtt(&mut self, probe_id: Uuid, peer_id: &str, response: &ProbeResponse) {
+        let Some(in_flight) = self.in_flight.get(&probe_id) else {
+            return;
+        };
+        if !in_flight.waiting.contains_key(peer_id) {
+            return;
+        }
+
+        let now = Instant::now();
+        let rtt = (now.duration_since(in_flight.sent_at).as_millis() as u64)
+            .saturating_sub(response.duration);
+        let peer_id = peer_id.to_string();
+        let lowest = self.rtts.get(&peer_id, now).map_or(rtt, |lowest| rtt.min(*lowest));
+        self.rtts.insert(peer_id, lowest, now);
+    }
+
+    /// Whether a peer's claimed location fits the round trips seen to it
+    fn location_plausible(&self, peer: &Peer, now: Instant) -> bool {
+        let rtt = self.rtts.get(&peer.peer_id, now).copied();
+        let ours = location::get_location().region;
+        let plausible = plausible_location(rtt, ours.as_deref(), peer_region(peer).as_deref());
+        if !plausible {
+            debug!(
+                "Ignoring location of peer {}: {:?} answered in {:?} ms",
+                peer.peer_id,
+                peer_region(peer),
+                rtt
+            );
+        }
+        plausible
+    }
+
+    /// Store a peer's answer, finishing the probe once nobody is left to answer
+    async fn record(
+        &mut self,
+        probe_id: Uuid,
+        peer_id: &str,
+        vantage: impl FnOnce(Option<String>) -> Vantage,
+    ) {
+        let Some(in_flight) = self.in_flight.get_mut(&probe_id) else {
+            debug!("Ignoring late answer from {} to probe {}", peer_id, probe_id);
+            return;
+        };
+        let Some(region) = in_flight.waiting.remove(peer_id) else {
+            return;
+        };
+
+        in_flight.result.vantages.push(vantage(region));
+        if in_flight.waiting.is_empty()
+            && let Some(in_flight) = self.in_flight.remove(&probe_id)
+        {
+            self.finish(in_flight).await;
+        }
+    }
+
+    async fn finish_expired(&mut self, now: Instant) {
+        let expired: Vec<Uuid> = self
+            .in_flight
+            .iter()
+            .filter(|(_, in_flight)| in_flight.deadline <= now)
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in expired {
+            if let Some(in_flight) = self.in_flight.remove(&id) {
+                self.finish(in_flight).await;
+            }
+        }
+    }
+
+    async fn finish(&self, in_flight: InFlight) {
+        let mut result = in_flight.result;
+        aggregate(&mut result);
+        result.state = ProbeState::Complete;
+        result.completed_at = Some(SystemTime::now());
+        if !in_flight.waiting.is_empty() {
+            result.error =
+                Some(format!("{} peer(s) did not answer in time", in_flight.waiting.len()));
+        }
+
+        info!("Probe {} of monitor {}: {}", result.uuid, result.monitor_uuid, result.summary());
+        self.save(&result).await;
+    }
+
+    /// Run a probe a peer asked for, or refuse it, and send back the response
+    async fn answer(&self, peer_id: String, inbound_id: u64, request: ProbeRequest, sharing: bool) {
+        let check_type = check_type_for(&request);
+        if !sharing {
+            self.respond(inbound_id, self.refused("not sharing with peers")).await;
+            return;
+        }
+        let (host, addrs) =
+            match self.policy.resolve(&request.target_url, check_type_name(check_type)).await {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    self.audit
+                        .record(
+                            AuditKind::PolicyRejected,
+                            Some(&peer_id),
+                            format!("Refused probe of {}: {}", request.target_url, e),
+                        )
+                        .await;
+                    self.respond(inbound_id, self.refused(&e.to_string())).await;
+                    return;
+                }
+            };
+
+        let permit = match self.probes.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                self.audit
+                    .record(
+                        AuditKind::RateLimited,
+                        Some(&peer_id),
+                        format!("Refused probe of {}: too many probes", request.target_url),
+                    )
+                    .await;
+                self.respond(inbound_id, self.refused("too many probes")).await;
+                return;
+            }
+        };
+
+        // Probes take up to the check timeout, so they run off the event loop.
+        // They connect only to the addresses the policy checked, so the target
+        // can't be rebound or redirected to an internal one meanwhile
+        let executor = self.executor.clone();
+        let p2p = self.p2p.clone();
+        let local_peer_id = self.peer_id.clone();
+        let redactor = self.redactor.clone();
+        tokio::spawn(async move {
+            let result = executor
+                .execute_pinned_check(request.target_url, check_type, &host, &addrs)
+                .await;
+            drop(permit);
+
+            let response = ProbeResponse {
+                status: result.status_code,
+                duration: result.latency_ms.unwrap_or(0),
+                error: redactor.redact_opt(result.error_message.as_deref()),
+                probed_by: local_peer_id,
+                timestamp: unix_seconds(result.timestamp),
+                headers: None,
+                body: None,
+            };
+            if let Err(e) = p2p.respond_probe(inbound_id, response).await {
+                debug!("Failed to answer probe from {}: {}", peer_id, e);
+            }
+        });
+    }
+
+    fn refused(&self, reason: &str) -> ProbeResponse {
+        ProbeResponse {
+            status: None,
+            duration: 0,
+            error: Some(format!("{REFUSED_PREFIX}{reason}")),
+            probed_by: self.peer_id.clone(),
+            timestamp: unix_seconds(SystemTime::now()),
+            headers: None,
+            body: None,
+        }
+    }
+
+    async fn respond(&self, inbound_id: u64, response: ProbeResponse) {
+        if let Err(e) = self.p2p.respond_probe(inbound_id, response).await {
+            debug!("Failed to answer probe request: {}", e);
+        }
+    }
+
+    async fn save(&self, result: &MultiVantageResult) {
+        if let Err(e) = self.database.save_multi_vantage_result(result).await {
+            warn!("Failed to save probe {}: {}", result.uuid, e);
+        }
+    }
+}
+
+/// Online peers to ask, most recently seen first, limited to a country or
+/// region if given; only peers with a `plausible` location count as in one
+fn select_peers<'a>(
+    peers: &'a [Peer],
+    region: Option<&str>,
+    count: u32,
+    plausible: impl Fn(&Peer) -> bool,
+) -> Vec<&'a Peer> {
+    peers
+        .iter()
+        .filter(|peer| match region {
+            Some(region) => {
+                [&peer.location_country, &peer.location_region]
+                    .into_iter()
+                    .flatten()
+                    .any(|location| location.eq_ignore_ascii_case(region))
+                    && plausible(peer)
+            }
+            None => true,
+        })
+        .take(count as usize)
+        .collect()
+}
+
+/// Region a peer claims to be in, derived from its country if not given
+fn peer_region(peer: &Peer) -> Option<String> {
+    peer.location_region.clone().or_else(|| {
+        peer.location_country
+            .as_deref()
+            .map(|cc| Location::region_from_country(cc).to_string())
+    })
+}
+
+/// Whether a claimed region fits the lowest round trip seen to the peer;
+/// claims are taken as they are until there is a round trip to go by
+fn plausible_location(rtt_ms: Option<u64>, ours: Option<&str>, claimed: Option<&str>) -> bool {
+    match (rtt_ms, ours, claimed) {
+        (Some(rtt_ms), Some(ours), Some(claimed)) => rtt_ms >= location::min_rtt_ms(ours, claimed),
+        _ => true,
+    }
+}
+
+fn peer_location(peer: &Peer) -> Option<String> {
+    match (&peer.location_region, &peer.location_country) {
+        (Some(region), Some(country)) => Some(format!("{region}, {country}")),
+        (Some(location), None) | (None, Some(location)) => Some(location.clone()),
+        (None, None) => None,
+    }
+}
+
+/// Probe request method for a monitor's check type; HTTP checks send the
+/// HTTP method, other checks their type
+fn probe_method(check_type: &str) -> &'static str {
+    match check_type {
+        "tcp" => "TCP",
+        "icmp" => "ICMP",
+        "cert_expiry" => "CERT_EXPIRY",
+        _ => "GET",
+    }
+}
+
+/// Check to run for a probe request
+fn check_type_for(request: &ProbeRequest) -> CheckType {
+    match request.method.to_ascii_uppercase().as_str() {
+        "TCP" => CheckType::Tcp,
+        "ICMP" => CheckType::Icmp,
+        "CERT_EXPIRY" => CheckType::CertExpiry,
+        _ if request.target_url.starts_with("https://") => CheckType::Https,
+        _ => CheckType::Http,
+    }
+}
+
+fn check_type_name(check_type: CheckType) -> &'static str {
+    match check_type {
+        CheckType::Http => "http",
+        CheckType::Https => "https",
+        CheckType::Tcp => "tcp",
+        CheckType::Icmp => "icmp",
+        CheckType::CertExpiry => "cert_expiry",
+    }
+}
+
+/// A peer's answer as a vantage; refused probes count as unknown
+fn vantage(peer_id: &str, region: Option<String>, response: &ProbeResponse) -> Vantage {
+    let status = match &response.error {
+        Some(error) if error.starts_with(REFUSED_PREFIX) => MonitorStatus::Unknown,
+        Some(_) => MonitorStatus::Down,
+        None => MonitorStatus::Up,
+    };
+
+    Vantage {
+        peer_id: peer_id.to_string(),
+        status,
+        latency_ms: (status == MonitorStatus::Up).then_some(response.duration),
+        status_code: response.status,
+        error: response.error.clone(),
+        region,
+    }
+}
+
+/// Fill in the overall status and latency from the vantages
+fn aggregate(result: &mut MultiVantageResult) {
+    let answered: Vec<&Vantage> =
+        result.vantages.iter().filter(|v| v.status != MonitorStatus::Unknown).collect();
+    let up = answered.iter().filter(|v| v.status == MonitorStatus::Up).count();
+
+    result.peers_responded = answered.len() as u32;
+    result.peers_up = up as u32;
+    result.status = Some(match (up, answered.len()) {
+        (_, 0) => MonitorStatus::Unknown,
+        (0, _) => MonitorStatus::Down,
+        (up, total) if up == total => MonitorStatus::Up,
+        // Reachable from some places but not others
+        _ => MonitorStatus::Degraded,
+    });
+
+    let mut latencies: Vec<u64> = answered.iter().filter_map(|v| v.latency_ms).collect();
+    latencies.sort_unstable();
+    result.latency_min_ms = latencies.first().copied();
+    result.latency_max_ms = latencies.last().copied();
+    result.latency_median_ms = match latencies.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(latencies[n / 2]),
+        n => Some((latencies[n / 2 - 1] + latencies[n / 2]) / 2),
+    };
+}
+
+fn fail(result: &mut MultiVantageResult, error: &str) {
+    warn!("Probe {} of monitor {} failed: {}", result.uuid, result.monitor_uuid, error);
+    result.state = ProbeState::Failed;
+    result.error = Some(error.to_string());
+    result.completed_at = Some(SystemTime::now());
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(peer_id: &str, country: Option<&str>, region: Option<&str>) -> Peer {
+        let mut peer = Peer::new_online(peer_id.to_string(), SystemTime::now());
+        peer.location_country = country.map(str::to_string);
+        peer.location_region = region.map(str::to_string);
+        peer
+    }
+
+    fn answer(error: Option<&str>, duration: u64) -> ProbeResponse {
+        ProbeResponse {
+            status: error.is_none().then_some(200),
+            duration,
+            error: error.map(str::to_string),
+            probed_by: "peer".to_string(),
+            timestamp: 0,
+            headers: None,
+            body: None,
+        }
+    }
+
+    #[test]
+    fn test_select_peers_by_region() {
+        let peers = [
+            peer("a", Some("DE"), Some("Europe")),
+            peer("b", Some("US"), Some("North America")),
+            peer("c", Some("FR"), Some("Europe")),
+            peer("d", None, None),
+        ];
+        let ids = |selected: Vec<&Peer>| {
+            selected.into_iter().map(|peer| peer.peer_id.clone()).collect::<Vec<_>>()
+        };
+
+        let all = |_: &Peer| true;
+
+        assert_eq!(ids(select_peers(&peers, None, 3, all)), ["a", "b", "c"]);
+        assert_eq!(ids(select_peers(&peers, Some("europe"), 5, all)), ["a", "c"]);
+        assert_eq!(ids(select_peers(&peers, Some("us"), 5, all)), ["b"]);
+        assert!(select_peers(&peers, Some("Asia"), 5, all).is_empty());
+
+        // A peer whose claim is contradicted is not picked for its region
+        let not_c = |peer: &Peer| peer.peer_id != "c";
+        assert_eq!(ids(select_peers(&peers, Some("europe"), 5, not_c)), ["a"]);
+        assert_eq!(ids(select_peers(&peers, None, 3, not_c)), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_location_checked_against_rtt() {
+        // Europe is at least 50 ms from North America
+        assert!(plausible_location(Some(90), Some("North America"), Some("Europe")));
+        assert!(!plausible_location(Some(8), Some("North America"), Some("Europe")));
+        // Nothing to go by yet, or no floor between the regions
+        assert!(plausible_location(None, Some("North America"), Some("Europe")));
+        assert!(plausible_location(Some(8), Some("Unknown"), Some("Europe")));
+        assert!(plausible_location(Some(8), Some("Europe"), Some("Europe")));
+
+        assert_eq!(peer_region(&peer("a", Some("JP"), None)).as_deref(), Some("Asia"));
+    }
+
+    #[test]
+    fn test_aggregate_vantages() {
+        let mut result = MultiVantageResult::pending(Uuid::new_v4(), 5, None);
+        result.vantages = vec![
+            vantage("a", None, &answer(None, 100)),
+            vantage("b", None, &answer(None, 300)),
+            vantage("c", None, &answer(Some("connection refused"), 0)),
+            // Refused probes say nothing about the target
+            vantage("d", None, &answer(Some("refused: too many probes"), 0)),
+            vantage("e", None, &answer(None, 200)),
+        ];
+        aggregate(&mut result);
+
+        assert_eq!(result.status, Some(MonitorStatus::Degraded));
+        assert_eq!((result.peers_up, result.peers_responded), (3, 4));
+        assert_eq!(result.latency_min_ms, Some(100));
+        assert_eq!(result.latency_median_ms, Some(200));
+        assert_eq!(result.latency_max_ms, Some(300));
+
+        result.vantages.truncate(2);
+        aggregate(&mut result);
+        assert_eq!(result.status, Some(MonitorStatus::Up));
+        assert_eq!(result.latency_median_ms, Some(200));
+
+        result.vantages.clear();
+        aggregate(&mut result);
+        assert_eq!(result.status, Some(MonitorStatus::Unknown));
+        assert_eq!(result.latency_median_ms, None);
+    }
+
+    #[test]
+    fn test_probe_method_round_trip() {
+        let request = |check_type: &str, target: &str| ProbeRequest {
+            target_url: target.to_string(),
+            method: probe_method(check_type).to_string(),
+            timeout: 0,
+            body: None,
+            headers: None,
+            requested_by: "peer".to_string(),
+        };
+
+        assert_eq!(check_type_for(&request("https", "https://example.com")), CheckType::Https);
+        assert_eq!(check_type_for(&request("http", "http://example.com")), CheckType::Http);
+        assert_eq!(check_type_for(&request("tcp", "example.com:443")), CheckType::Tcp);
+        assert_eq!(check_type_for(&request("icmp", "example.com")), CheckType::Icmp);
+        assert_eq!(check_type_for(&request("cert_expiry", "example.com")), CheckType::CertExpiry);
+    }
+}
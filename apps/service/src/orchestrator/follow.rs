@@ -0,0 +1,112 @@
+/// Follow mode - subscribe to public targets without checking them
+///
+/// Followed hosts are never probed from here. Results peers share for them
+/// arrive over gossip like any other, and the manager also looks up the
+/// latest ones peers stored in the DHT under each host, so a new follow fills
+/// in without waiting for the next round of checks. The peer event handler
+/// records which peer monitors check a followed host, and the TUI and CLI
+/// summarise their results.
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use super::runtime::RuntimeSettings;
+use crate::database::Database;
+use crate::database::models::Follow;
+use crate::p2p::P2PHandle;
+
+/// How often follow changes are picked up
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// How often the DHT is asked for the latest results of each followed host
+const FETCH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Hosts currently followed, shared with the peer event handler
+pub type FollowedHosts = Arc<RwLock<HashSet<String>>>;
+
+/// Task keeping the followed hosts up to date and fetching their results
+pub struct FollowManager {
+    database: Arc<dyn Database>,
+    p2p: P2PHandle,
+    followed: FollowedHosts,
+}
+
+impl FollowManager {
+    /// Create a manager updating the given hosts
+    pub fn new(database: Arc<dyn Database>, p2p: P2PHandle, followed: FollowedHosts) -> Self {
+        Self { database, p2p, followed }
+    }
+
+    /// Spawn the manager; it stops once the settings channel closes
+    pub fn spawn(self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(POLL_INTERVAL);
+            let mut fetch_timer = tokio::time::interval(FETCH_INTERVAL);
+            // The first fetch covers every follow
+            fetch_timer.tick().await;
+            let mut fetch_all = true;
+
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => {}
+                    _ = fetch_timer.tick() => fetch_all = true,
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+
+                let hosts: HashSet<String> = match self.database.get_follows().await {
+                    Ok(follows) => follows.into_iter().map(|follow| follow.host).collect(),
+                    Err(e) => {
+                        warn!("Failed to load followed hosts: {}", e);
+                        continue;
+                    }
+                };
+                let previous =
+                    std::mem::replace(&mut *self.followed.write().unwrap(), hosts.clone());
+
+                // Results only come in while sharing with peers
+                if !settings.borrow().p2p_sharing {
+                    continue;
+                }
+                let due = hosts.into_iter().filter(|host| fetch_all || !previous.contains(host));
+                for host in due {
+                    if let Err(e) = self.p2p.fetch_target_results(host.clone()).await {
+                        debug!("Failed to look up results for {}: {}", host, e);
+                    }
+                }
+                fetch_all = false;
+            }
+        })
+    }
+}
+
+/// Followed host a target points at, if any
+pub fn followed_host(followed: &FollowedHosts, target: &str) -> Option<String> {
+    let host = Follow::host_of(target)?;
+    followed.read().unwrap().contains(&host).then_some(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_followed_host() {
+        let followed = FollowedHosts::default();
+        assert_eq!(followed_host(&followed, "https://example.com"), None);
+
+        followed.write().unwrap().insert("example.com".to_string());
+        assert_eq!(
+            followed_host(&followed, "https://EXAMPLE.com/health").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(followed_host(&followed, "example.com:443").as_deref(), Some("example.com"));
+        assert_eq!(followed_host(&followed, "https://api.example.com"), None);
+    }
+}
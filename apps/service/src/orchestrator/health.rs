@@ -0,0 +1,217 @@
+/// Health endpoints - liveness and readiness of the service over HTTP
+///
+/// A small listener answers `GET /healthz` and `GET /readyz` with a JSON
+/// report, and `GET /metrics` with the orchestrator's metrics for Prometheus
+/// to scrape. The service is live while the reload manager keeps reporting on the
+/// scheduled monitors; it is ready once the database answers queries, the
+/// scheduler holds every monitor with no check overdue and, when P2P is
+/// enabled, the node is up.
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, web};
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+use super::api_tls::ApiTls;
+use super::http;
+use super::metrics::ServiceMetrics;
+use crate::config::ApiScope;
+use crate::database::Database;
+
+/// How long the scheduler may go without reporting before it counts as stuck
+const SCHEDULER_STALE_AFTER: Duration = Duration::from_secs(90);
+/// Content type of the Prometheus text exposition format
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Last scheduler report from the reload manager
+#[derive(Debug, Clone, Copy)]
+struct SchedulerReport {
+    scheduled: usize,
+    running: usize,
+    in_flight: usize,
+    overdue: usize,
+    at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct HealthInner {
+    scheduler: Option<SchedulerReport>,
+    p2p_enabled: bool,
+    p2p_running: bool,
+}
+
+/// Health state reported by the orchestrator tasks
+#[derive(Debug, Clone, Default)]
+pub struct ServiceHealth(Arc<Mutex<HealthInner>>);
+
+impl ServiceHealth {
+    /// Health state for a service with P2P enabled or not
+    pub fn new(p2p_enabled: bool) -> Self {
+        Self(Arc::new(Mutex::new(HealthInner { p2p_enabled, ..HealthInner::default() })))
+    }
+
+    /// Record how many monitors were handed to the scheduler, how many it
+    /// checks on time, and how many checks are running and overdue
+    pub fn report_scheduler(
+        &self,
+        scheduled: usize,
+        running: usize,
+        in_flight: usize,
+        overdue: usize,
+    ) {
+        self.0.lock().unwrap().scheduler =
+            Some(SchedulerReport { scheduled, running, in_flight, overdue, at: Instant::now() });
+    }
+
+    /// Record whether the P2P node is running
+    pub fn set_p2p_running(&self, running: bool) {
+        self.0.lock().unwrap().p2p_running = running;
+    }
+
+    /// Current health, querying the database
+    pub async fn check(&self, database: &dyn Database) -> HealthReport {
+        let database = database.ping().await.map_err(|e| e.to_string());
+        let inner = self.0.lock().unwrap();
+        evaluate(&inner, database, Instant::now())
+    }
+}
+
+/// Snapshot served by the health endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    pub live: bool,
+    pub ready: bool,
+    pub database_ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_error: Option<String>,
+    /// Whether the scheduler reported recently; false until monitors are loaded
+    pub scheduler_alive: bool,
+    pub monitors_scheduled: usize,
+    /// Scheduled monitors checked on time
+    pub monitors_running: usize,
+    pub checks_in_flight: usize,
+    /// Checks still running a full interval after they started
+    pub checks_overdue: usize,
+    pub p2p_enabled: bool,
+    pub p2p_running: bool,
+}
+
+fn evaluate(inner: &HealthInner, database: Result<(), String>, now: Instant) -> HealthReport {
+    let scheduler = inner.scheduler;
+    let stale =
+        scheduler.is_some_and(|report| now.duration_since(report.at) > SCHEDULER_STALE_AFTER);
+    let scheduler_alive = scheduler.is_some() && !stale;
+    let monitors_scheduled = scheduler.map(|report| report.scheduled).unwrap_or(0);
+    let monitors_running = scheduler.map(|report| report.running).unwrap_or(0);
+    let checks_in_flight = scheduler.map(|report| report.in_flight).unwrap_or(0);
+    let checks_overdue = scheduler.map(|report| report.overdue).unwrap_or(0);
+
+    let database_ok = database.is_ok();
+    let ready = database_ok
+        && scheduler_alive
+        && monitors_running == monitors_scheduled
+        && checks_overdue == 0
+        && (!inner.p2p_enabled || inner.p2p_running);
+
+    HealthReport {
+        // Still live while starting up, before the first report
+        live: !stale,
+        ready,
+        database_ok,
+        database_error: database.err(),
+        scheduler_alive,
+        monitors_scheduled,
+        monitors_running,
+        checks_in_flight,
+        checks_overdue,
+        p2p_enabled: inner.p2p_enabled,
+        p2p_running: inner.p2p_running,
+    }
+}
+
+/// Serve the health and metrics endpoints on `bind` until the task is
+/// aborted, over TLS when `tls` is given
+pub async fn serve(
+    bind: &str,
+    tls: Option<Arc<ApiTls>>,
+    health: ServiceHealth,
+    metrics: ServiceMetrics,
+    database: Arc<dyn Database>,
+) -> Result<JoinHandle<()>> {
+    let state = web::Data::new(HealthState { health, metrics, database });
+    let api = http::Api::new("Health endpoints", ApiScope::Health, move |config| {
+        config
+            .app_data(state.clone())
+            .service(http::resource("/healthz").route(web::get().to(healthz)))
+            .service(http::resource("/readyz").route(web::get().to(readyz)))
+            .service(http::resource("/metrics").route(web::get().to(metrics_text)));
+    });
+    http::serve(api, bind, tls).await
+}
+
+struct HealthState {
+    health: ServiceHealth,
+    metrics: ServiceMetrics,
+    database: Arc<dyn Database>,
+}
+
+async fn healthz(state: web::Data<HealthState>) -> HttpResponse {
+    let report = state.health.check(state.database.as_ref()).await;
+    HttpResponse::build(status(report.live)).json(report)
+}
+
+async fn readyz(state: web::Data<HealthState>) -> HttpResponse {
+    let report = state.health.check(state.database.as_ref()).await;
+    HttpResponse::build(status(report.ready)).json(report)
+}
+
+async fn metrics_text(state: web::Data<HealthState>) -> HttpResponse {
+    http::text(METRICS_CONTENT_TYPE, state.metrics.render())
+}
+
+fn status(ok: bool) -> StatusCode {
+    if ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_evaluation() {
+        let now = Instant::now();
+        let mut inner = HealthInner { p2p_enabled: true, ..HealthInner::default() };
+
+        // Starting up: live, but not ready before the monitors are scheduled
+        let report = evaluate(&inner, Ok(()), now);
+        assert!(report.live);
+        assert!(!report.ready);
+
+        let scheduler = |scheduled, running, overdue| {
+            Some(SchedulerReport { scheduled, running, in_flight: 2, overdue, at: now })
+        };
+        inner.scheduler = scheduler(2, 2, 0);
+        assert!(!evaluate(&inner, Ok(()), now).ready, "P2P node is not running yet");
+        inner.p2p_running = true;
+        assert!(evaluate(&inner, Ok(()), now).ready);
+
+        let report = evaluate(&inner, Err("pool timed out".to_string()), now);
+        assert!(report.live);
+        assert!(!report.ready);
+        assert_eq!(report.database_error.as_deref(), Some("pool timed out"));
+
+        // A stuck check keeps the service from being ready
+        inner.scheduler = scheduler(2, 1, 1);
+        let health = evaluate(&inner, Ok(()), now);
+        assert!(!health.ready);
+        assert_eq!((health.checks_in_flight, health.checks_overdue), (2, 1));
+
+        // A scheduler that stopped reporting is neither live nor ready
+        let later = now + SCHEDULER_STALE_AFTER + Duration::from_secs(1);
+        let report = evaluate(&inner, Ok(()), later);
+        assert!(!report.live);
+        assert!(!report.ready);
+    }
+}
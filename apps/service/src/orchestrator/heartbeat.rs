@@ -0,0 +1,202 @@
+/// Heartbeat monitors - dead man's switches for systems that can't be probed
+///
+/// A heartbeat monitor's target is a secret token, and the monitored system,
+/// say a cron job or a backup script, POSTs to `/api/v1/heartbeat/<token>` on
+/// the management API whenever it runs. Every ping is stored as an up result.
+/// Once no ping arrived for the monitor's interval plus its grace period the
+/// watcher stores a down result, and another one every interval until pings
+/// resume. After a start, monitors get a full interval and grace period to
+/// ping before they count as missing.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use super::runtime::RuntimeSettings;
+use crate::database::Database;
+use crate::database::models::Monitor;
+use crate::monitoring::CheckResult;
+use crate::monitoring::types::MonitorStatus;
+use crate::queue::QueueSender;
+
+/// How often monitor changes are picked up and missing pings looked for
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A heartbeat monitor and when it was last heard from
+#[derive(Debug, Clone)]
+struct Watched {
+    interval: Duration,
+    grace: Duration,
+    /// Last ping, or when watching started
+    last_ping: SystemTime,
+    /// Last down result since that ping
+    last_down: Option<SystemTime>,
+}
+
+impl Watched {
+    fn new(monitor: &Monitor, now: SystemTime) -> Self {
+        let mut watched = Self {
+            interval: Duration::ZERO,
+            grace: Duration::ZERO,
+            last_ping: now,
+            last_down: None,
+        };
+        watched.update(monitor);
+        watched
+    }
+
+    fn update(&mut self, monitor: &Monitor) {
+        self.interval = Duration::from_secs(monitor.interval_seconds.max(1));
+        self.grace = Duration::from_secs(monitor.grace_seconds);
+    }
+
+    /// Whether a down result is due: the ping is overdue and the monitor
+    /// wasn't recorded down within the last interval
+    fn down_due(&self, now: SystemTime) -> bool {
+        now >= self.last_ping + self.interval + self.grace
+            && self.last_down.is_none_or(|at| now >= at + self.interval)
+    }
+}
+
+/// Task turning heartbeat pings and their absence into results
+pub struct HeartbeatWatcher {
+    database: Arc<dyn Database>,
+    result_tx: QueueSender<CheckResult>,
+    peer_id: String,
+}
+
+impl HeartbeatWatcher {
+    /// Create a watcher handing results to the pipeline as `peer_id`
+    pub fn new(
+        database: Arc<dyn Database>,
+        result_tx: QueueSender<CheckResult>,
+        peer_id: String,
+    ) -> Self {
+        Self { database, result_tx, peer_id }
+    }
+
+    /// Spawn the watcher, returning the sender pinged monitors are passed on
+    ///
+    /// The task stops once the settings channel or the result channel closes;
+    /// without the management API nothing pings, so monitors go down.
+    pub fn spawn(
+        self,
+        mut settings: watch::Receiver<RuntimeSettings>,
+    ) -> (mpsc::Sender<Uuid>, JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::channel::<Uuid>(64);
+
+        let handle = tokio::spawn(async move {
+            let mut timer = tokio::time::interval(POLL_INTERVAL);
+            let mut watched: HashMap<Uuid, Watched> = HashMap::new();
+
+            loop {
+                // Whether the pipeline still takes results
+                let sent = tokio::select! {
+                    _ = timer.tick() => {
+                        if let Err(e) = self.sync(&mut watched).await {
+                            warn!("Failed to load heartbeat monitors: {}", e);
+                        }
+                        let now = SystemTime::now();
+                        let mut sent_all = true;
+                        for (id, watched) in watched.iter_mut().filter(|(_, w)| w.down_due(now)) {
+                            let silent = now.duration_since(watched.last_ping).unwrap_or_default();
+                            watched.last_down = Some(now);
+                            let result = self.result(*id, now).failure(format!(
+                                "No heartbeat for {}s",
+                                silent.as_secs()
+                            ));
+                            sent_all &= self.result_tx.send(result).await.is_ok();
+                        }
+                        sent_all
+                    }
+                    Some(id) = rx.recv() => {
+                        let now = SystemTime::now();
+                        if let Some(watched) = watched.get_mut(&id) {
+                            watched.last_ping = now;
+                            watched.last_down = None;
+                        }
+                        let mut result = self.result(id, now);
+                        result.status = MonitorStatus::Up;
+                        self.result_tx.send(result).await.is_ok()
+                    }
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                if !sent {
+                    break;
+                }
+            }
+            debug!("Heartbeat watcher stopped");
+        });
+
+        (tx, handle)
+    }
+
+    /// Watch the enabled heartbeat monitors, keeping when each was last pinged
+    async fn sync(&self, watched: &mut HashMap<Uuid, Watched>) -> anyhow::Result<()> {
+        let monitors: Vec<Monitor> = self
+            .database
+            .get_enabled_monitors()
+            .await?
+            .into_iter()
+            .filter(Monitor::is_heartbeat)
+            .collect();
+
+        let now = SystemTime::now();
+        watched.retain(|id, _| monitors.iter().any(|monitor| monitor.uuid == *id));
+        for monitor in &monitors {
+            watched
+                .entry(monitor.uuid)
+                .and_modify(|watched| watched.update(monitor))
+                .or_insert_with(|| Watched::new(monitor, now));
+        }
+        Ok(())
+    }
+
+    /// A result for a heartbeat monitor; the target stands in for the token,
+    /// which must not be shared
+    fn result(&self, monitor_id: Uuid, now: SystemTime) -> CheckResult {
+        let mut result =
+            CheckResult::new(monitor_id, Monitor::HEARTBEAT.to_string(), self.peer_id.clone());
+        result.timestamp = now;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_down_due() {
+        let now = SystemTime::now();
+        let mut monitor =
+            Monitor::new("Backup".to_string(), "0123456789abcdef".to_string(), "heartbeat".into());
+        monitor.interval_seconds = 3600;
+        monitor.grace_seconds = 300;
+        let mut watched = Watched::new(&monitor, now);
+
+        // Down once interval and grace have passed without a ping
+        assert!(!watched.down_due(now + Duration::from_secs(3600)));
+        assert!(!watched.down_due(now + Duration::from_secs(3899)));
+        let overdue = now + Duration::from_secs(3900);
+        assert!(watched.down_due(overdue));
+
+        // Then again every interval
+        watched.last_down = Some(overdue);
+        assert!(!watched.down_due(overdue + Duration::from_secs(60)));
+        assert!(watched.down_due(overdue + Duration::from_secs(3600)));
+
+        // A ping starts the wait over
+        watched.last_ping = overdue;
+        watched.last_down = None;
+        assert!(!watched.down_due(overdue + Duration::from_secs(3600)));
+    }
+}
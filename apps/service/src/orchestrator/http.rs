@@ -0,0 +1,229 @@
+/// HTTP serving for the APIs - actix-web apps over plain or TLS listeners
+///
+/// Each API is an actix-web app on its own listener, the HTTP stack the
+/// server app uses, so requests are parsed by a maintained HTTP/1.1
+/// implementation with keep-alive and chunked bodies. Over TLS the listener
+/// runs the handshake and `api_tls` checks a client certificate may use the
+/// API; a refused client is answered 403 on every path.
+use actix_tls::accept::rustls_0_23::TlsStream;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServerHandle, ServiceRequest, ServiceResponse};
+use actix_web::http::{StatusCode, header};
+use actix_web::middleware::{Condition, DefaultHeaders, Next, from_fn};
+use actix_web::rt::net::TcpStream;
+use actix_web::{App, HttpResponse, HttpServer, Resource, ResponseError, web};
+use anyhow::Result;
+use serde_json::json;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::api_tls::ApiTls;
+use crate::config::ApiScope;
+
+/// How long a client may take to finish the TLS handshake and to send the
+/// head of a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A response
+pub(super) type Reply = HttpResponse;
+
+/// Routes of an API
+type Routes = Arc<dyn Fn(&mut web::ServiceConfig) + Send + Sync>;
+
+/// An API to serve: its name as logged, the scope a client certificate needs
+/// for it and its routes
+#[derive(Clone)]
+pub(super) struct Api {
+    name: &'static str,
+    scope: ApiScope,
+    routes: Routes,
+    any_origin: bool,
+}
+
+impl Api {
+    /// An API whose routes `routes` adds to each worker's app
+    pub(super) fn new(
+        name: &'static str,
+        scope: ApiScope,
+        routes: impl Fn(&mut web::ServiceConfig) + Send + Sync + 'static,
+    ) -> Self {
+        Self { name, scope, routes: Arc::new(routes), any_origin: false }
+    }
+
+    /// Let pages on any origin read the API from a browser
+    pub(super) fn allow_any_origin(mut self) -> Self {
+        self.any_origin = true;
+        self
+    }
+}
+
+/// Whether the client of a connection may use the API
+#[derive(Clone, Copy)]
+struct Access {
+    allowed: bool,
+}
+
+/// Serve `api` on `bind` until the task is aborted, over TLS when `tls` is
+/// given
+pub(super) async fn serve(
+    api: Api,
+    bind: &str,
+    tls: Option<Arc<ApiTls>>,
+) -> Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(bind).await?.into_std()?;
+    info!("{} listening on {}", api.name, listener.local_addr()?);
+    serve_listener(api, listener, tls)
+}
+
+/// Serve `api` on a bound listener until the task is aborted
+pub(super) fn serve_listener(
+    api: Api,
+    listener: std::net::TcpListener,
+    tls: Option<Arc<ApiTls>>,
+) -> Result<JoinHandle<()>> {
+    let Api { name, scope, routes, any_origin } = api;
+    let access = tls.clone();
+    let server = HttpServer::new(move || {
+        let routes = routes.clone();
+        let cors = DefaultHeaders::new().add((header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"));
+        App::new()
+            .configure(move |config| routes(config))
+            .default_service(web::to(|| async { error(StatusCode::NOT_FOUND, "Not found") }))
+            .wrap(Condition::new(any_origin, cors))
+            .wrap(from_fn(refuse_forbidden))
+    })
+    .on_connect(move |connection, extensions| {
+        let tls_stream = connection.downcast_ref::<TlsStream<TcpStream>>();
+        let allowed = match (&access, tls_stream) {
+            (Some(tls), Some(stream)) => tls.allows_client(stream.get_ref().1, scope),
+            _ => true,
+        };
+        extensions.insert(Access { allowed });
+    })
+    // Handlers wait on the database rather than the CPU, so one worker
+    // thread serves an API
+    .workers(1)
+    .disable_signals()
+    .client_request_timeout(REQUEST_TIMEOUT)
+    .tls_handshake_timeout(REQUEST_TIMEOUT);
+
+    let server = match tls {
+        Some(tls) => server.listen_rustls_0_23(listener, tls.server_config())?,
+        None => server.listen(listener)?,
+    }
+    .run();
+    let stop = StopOnDrop(server.handle());
+    let serving = tokio::spawn(server);
+    Ok(tokio::spawn(async move {
+        let _stop = stop;
+        match serving.await {
+            Ok(Err(e)) => warn!("{} stopped: {}", name, e),
+            Err(e) => warn!("{} stopped: {}", name, e),
+            Ok(Ok(())) => {}
+        }
+    }))
+}
+
+/// Stops a server once the task serving it ends or is aborted
+struct StopOnDrop(ServerHandle);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        // The stop command is sent before the returned future is polled
+        drop(self.0.stop(false));
+    }
+}
+
+/// Answer 403 on every path to clients whose certificate may not use the API
+async fn refuse_forbidden(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let refused = request.request().conn_data::<Access>().is_some_and(|access| !access.allowed);
+    if refused {
+        let response = error(StatusCode::FORBIDDEN, "Forbidden");
+        return Ok(request.into_response(response).map_into_right_body());
+    }
+    Ok(next.call(request).await?.map_into_left_body())
+}
+
+/// A resource answering the methods it has no route for with a JSON error,
+/// as the handlers do
+pub(super) fn resource(path: &str) -> Resource {
+    web::resource(path).default_service(web::to(|| async {
+        error(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed")
+    }))
+}
+
+/// A JSON error response
+pub(super) fn error(status: StatusCode, msg: &str) -> Reply {
+    HttpResponse::build(status).json(json!({ "ok": false, "msg": msg }))
+}
+
+/// A response of the given content type
+pub(super) fn text(content_type: &'static str, body: String) -> Reply {
+    HttpResponse::Ok().content_type(content_type).body(body)
+}
+
+/// An error a handler could not answer itself, logged and answered with 500
+#[derive(Debug)]
+pub(super) struct InternalError(anyhow::Error);
+
+impl<E: Into<anyhow::Error>> From<E> for InternalError {
+    fn from(e: E) -> Self {
+        Self(e.into())
+    }
+}
+
+impl fmt::Display for InternalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", self.0)
+    }
+}
+
+impl ResponseError for InternalError {
+    fn error_response(&self) -> HttpResponse {
+        debug!("API request failed: {}", self);
+        error(StatusCode::INTERNAL_SERVER_ERROR, "Internal error")
+    }
+}
+
+/// Result of a handler
+pub(super) type HandlerResult<T = Reply> = Result<T, InternalError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_chunked_bodies_over_keep_alive() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let api = Api::new("Echo", ApiScope::Monitors, |config| {
+            config.service(resource("/echo").route(web::post().to(|body: String| async { body })));
+        });
+        let server = serve_listener(api, listener, None).unwrap();
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        // The "é" of the body is split across two chunks
+        let chunked = b"POST /echo HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: chunked\r\n\r\n\
+                        4\r\ncaf\xc3\r\n2\r\n\xa9!\r\n0\r\n\r\n";
+        stream.write_all(chunked).await.unwrap();
+        let missing = "GET /missing HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n";
+        stream.write_all(missing.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        server.abort();
+        assert_eq!(response.matches("HTTP/1.1 ").count(), 2, "both answered on one connection");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("café!HTTP/1.1 404 Not Found"));
+        assert!(response.contains(r#"{"msg":"Not found","ok":false}"#));
+        assert!(!response.to_lowercase().contains("access-control-allow-origin"));
+    }
+}
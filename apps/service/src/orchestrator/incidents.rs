@@ -0,0 +1,257 @@
+/// Incidents - opens, updates and resolves incidents from check results
+///
+/// The pipeline hands every stored result to the manager. A monitor down for
+/// `open_after` consecutive results gets an incident, dated back to the first
+/// of them. While it is open, status changes are appended to its timeline, a
+/// degraded monitor moving it to "monitoring", and the first up result
+/// resolves it. Unknown results and results checked during maintenance neither
+/// count towards nor break a streak. Incidents left open by a previous run are
+/// picked up again at start.
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::IncidentsConfig;
+use crate::database::Database;
+use crate::database::models::{Incident, IncidentUpdate};
+use crate::monitoring::CheckResult;
+use crate::monitoring::types::MonitorStatus;
+
+/// What the manager knows about a monitor
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Tracked {
+    /// Consecutive down results
+    streak: u32,
+    /// When the first of them was checked
+    first_down: Option<SystemTime>,
+    /// Whether the monitor has an open incident
+    open: bool,
+    /// Last known status
+    last_status: Option<MonitorStatus>,
+}
+
+/// How a result moves a monitor's incident along
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IncidentStep {
+    /// Down long enough; open an incident that started at the first down result
+    Open(SystemTime),
+    /// The status of a monitor with an open incident changed
+    Update(MonitorStatus),
+    /// Up again; resolve the open incident
+    Resolve,
+}
+
+/// Track a monitor through a result
+fn step(tracked: &mut Tracked, result: &CheckResult, open_after: u32) -> Option<IncidentStep> {
+    if result.status == MonitorStatus::Unknown || result.maintenance {
+        return None;
+    }
+    let changed = tracked.last_status.is_some_and(|status| status != result.status);
+    tracked.last_status = Some(result.status);
+
+    if result.status != MonitorStatus::Down {
+        tracked.streak = 0;
+        tracked.first_down = None;
+        if !tracked.open {
+            return None;
+        }
+        if result.status == MonitorStatus::Up {
+            tracked.open = false;
+            return Some(IncidentStep::Resolve);
+        }
+        return changed.then_some(IncidentStep::Update(result.status));
+    }
+
+    tracked.streak = tracked.streak.saturating_add(1);
+    let first_down = *tracked.first_down.get_or_insert(result.timestamp);
+    if tracked.open {
+        changed.then_some(IncidentStep::Update(result.status))
+    } else if tracked.streak >= open_after.max(1) {
+        tracked.open = true;
+        Some(IncidentStep::Open(first_down))
+    } else {
+        None
+    }
+}
+
+/// Incident status while a monitor has the given status
+fn incident_status(status: MonitorStatus) -> &'static str {
+    match status {
+        MonitorStatus::Up => Incident::RESOLVED,
+        MonitorStatus::Degraded => Incident::MONITORING,
+        _ => Incident::INVESTIGATING,
+    }
+}
+
+/// Task keeping incidents in step with check results
+pub struct IncidentManager {
+    database: Arc<dyn Database>,
+    open_after: u32,
+    tracked: HashMap<Uuid, Tracked>,
+    /// Open incidents by monitor
+    incidents: HashMap<Uuid, Incident>,
+}
+
+impl IncidentManager {
+    /// Create a manager if incidents are enabled
+    pub fn new(database: Arc<dyn Database>, config: &IncidentsConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            database,
+            open_after: config.open_after,
+            tracked: HashMap::new(),
+            incidents: HashMap::new(),
+        })
+    }
+
+    /// Spawn the manager, returning the sender the pipeline feeds it through
+    ///
+    /// The task exits once the sender is dropped.
+    pub fn spawn(mut self) -> (mpsc::Sender<CheckResult>, JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::channel::<CheckResult>(256);
+
+        let handle = tokio::spawn(async move {
+            if let Err(e) = self.restore().await {
+                warn!("Failed to load open incidents: {}", e);
+            }
+            while let Some(result) = rx.recv().await {
+                let tracked = self.tracked.entry(result.monitor_id).or_default();
+                let Some(step) = step(tracked, &result, self.open_after) else {
+                    continue;
+                };
+                if let Err(e) = self.apply(step, &result).await {
+                    warn!("Failed to record incident for {}: {}", result.target, e);
+                }
+            }
+            debug!("Incident manager stopped");
+        });
+
+        (tx, handle)
+    }
+
+    /// Pick up incidents of monitors that are still open
+    async fn restore(&mut self) -> Result<()> {
+        for incident in self.database.get_open_incidents().await? {
+            let Some(monitor_uuid) = incident.monitor_uuid else {
+                continue;
+            };
+            let status = match incident.status.as_str() {
+                Incident::MONITORING => MonitorStatus::Degraded,
+                _ => MonitorStatus::Down,
+            };
+            self.tracked.insert(
+                monitor_uuid,
+                Tracked { open: true, last_status: Some(status), ..Tracked::default() },
+            );
+            self.incidents.insert(monitor_uuid, incident);
+        }
+        Ok(())
+    }
+
+    async fn apply(&mut self, step: IncidentStep, result: &CheckResult) -> Result<()> {
+        let detail = result.error_message.as_deref().unwrap_or("no error reported");
+        let (incident, message) = match step {
+            IncidentStep::Open(started_at) => {
+                let Some(monitor) = self.database.get_monitor_by_uuid(result.monitor_id).await?
+                else {
+                    self.tracked.remove(&result.monitor_id);
+                    return Ok(());
+                };
+                let incident = Incident::for_monitor(&monitor, started_at);
+                info!("Opened incident '{}'", incident.title);
+                let message =
+                    format!("Down for {} consecutive checks: {}", self.open_after.max(1), detail);
+                (
+                    self.incidents.entry(result.monitor_id).insert_entry(incident).into_mut(),
+                    message,
+                )
+            }
+            IncidentStep::Update(status) => {
+                let Some(incident) = self.incidents.get_mut(&result.monitor_id) else {
+                    return Ok(());
+                };
+                incident.status = incident_status(status).to_string();
+                (incident, format!("Now {status}: {detail}"))
+            }
+            IncidentStep::Resolve => {
+                let Some(incident) = self.incidents.get_mut(&result.monitor_id) else {
+                    return Ok(());
+                };
+                incident.status = Incident::RESOLVED.to_string();
+                incident.resolved_at = Some(result.timestamp);
+                info!("Resolved incident '{}'", incident.title);
+                (incident, "Recovered".to_string())
+            }
+        };
+
+        self.database.save_incident(incident).await?;
+        let update = IncidentUpdate {
+            incident_uuid: incident.uuid,
+            status: incident.status.clone(),
+            message,
+            created_at: result.timestamp,
+        };
+        if step == IncidentStep::Resolve {
+            self.incidents.remove(&result.monitor_id);
+        }
+        self.database.add_incident_update(&update).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_step() {
+        let mut tracked = Tracked::default();
+        let monitor_id = Uuid::new_v4();
+        let start = SystemTime::now();
+        let mut at = 0;
+        let mut next = |status, maintenance| {
+            let mut result = CheckResult::new(
+                monitor_id,
+                "https://example.com".to_string(),
+                "local".to_string(),
+            );
+            result.status = status;
+            result.maintenance = maintenance;
+            result.timestamp = start + Duration::from_secs(at);
+            at += 30;
+            step(&mut tracked, &result, 3)
+        };
+
+        // A streak broken before it's long enough opens nothing
+        assert_eq!(next(MonitorStatus::Down, false), None);
+        assert_eq!(next(MonitorStatus::Up, false), None);
+
+        // Opened on the third down result, dated back to the first
+        assert_eq!(next(MonitorStatus::Down, false), None);
+        assert_eq!(next(MonitorStatus::Unknown, false), None);
+        assert_eq!(next(MonitorStatus::Down, true), None);
+        assert_eq!(next(MonitorStatus::Down, false), None);
+        assert_eq!(
+            next(MonitorStatus::Down, false),
+            Some(IncidentStep::Open(start + Duration::from_secs(60)))
+        );
+        assert_eq!(next(MonitorStatus::Down, false), None);
+
+        // Status changes while open are updates, recovery resolves
+        assert_eq!(
+            next(MonitorStatus::Degraded, false),
+            Some(IncidentStep::Update(MonitorStatus::Degraded))
+        );
+        assert_eq!(
+            next(MonitorStatus::Down, false),
+            Some(IncidentStep::Update(MonitorStatus::Down))
+        );
+        assert_eq!(next(MonitorStatus::Up, false), Some(IncidentStep::Resolve));
+        assert_eq!(next(MonitorStatus::Up, false), None);
+        assert_eq!(next(MonitorStatus::Down, false), None);
+    }
+}
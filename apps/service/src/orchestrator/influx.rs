@@ -0,0 +1,252 @@
+/// InfluxDB sink - writes results as line protocol
+///
+/// Each `[[influxdb]]` section gets its own sink fed by the pipeline. Results
+/// of monitors carrying one of the section's `monitor_tags` are buffered and
+/// written every `flush_interval_secs` as one point per result, tagged with
+/// the monitor, its name, target and tags. A failed write is retried with the
+/// next flush; past `MAX_BUFFERED_RESULTS` the oldest results are dropped.
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::config::InfluxSinkConfig;
+use crate::database::Database;
+use crate::database::models::Monitor;
+use crate::monitoring::CheckResult;
+use crate::monitoring::types::MonitorStatus;
+
+/// How long a write may take
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Results kept while the endpoint is unreachable
+const MAX_BUFFERED_RESULTS: usize = 10_000;
+
+/// Task writing buffered results to one InfluxDB endpoint
+pub struct InfluxSink {
+    database: Arc<dyn Database>,
+    config: InfluxSinkConfig,
+    buffer: Vec<CheckResult>,
+}
+
+impl InfluxSink {
+    /// Create a sink for a configured endpoint
+    pub fn new(database: Arc<dyn Database>, config: InfluxSinkConfig) -> Self {
+        Self { database, config, buffer: Vec::new() }
+    }
+
+    /// Spawn the sink, returning the sender the pipeline feeds it through
+    ///
+    /// The task writes what is left and exits once the sender is dropped.
+    pub fn spawn(mut self) -> (mpsc::Sender<CheckResult>, JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::channel::<CheckResult>(256);
+
+        let handle = tokio::spawn(async move {
+            let client = match reqwest::Client::builder().timeout(WRITE_TIMEOUT).build() {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("InfluxDB sink disabled, failed to build HTTP client: {}", e);
+                    return;
+                }
+            };
+            let period = Duration::from_secs(self.config.flush_interval_secs.max(1));
+            let mut interval = tokio::time::interval(period);
+            interval.tick().await;
+
+            loop {
+                tokio::select! {
+                    result = rx.recv() => match result {
+                        Some(result) => self.buffer(result),
+                        None => break,
+                    },
+                    _ = interval.tick() => self.flush(&client).await,
+                }
+            }
+
+            self.flush(&client).await;
+            debug!("InfluxDB sink for {} stopped", self.config.url);
+        });
+
+        (tx, handle)
+    }
+
+    fn buffer(&mut self, result: CheckResult) {
+        if self.buffer.len() >= MAX_BUFFERED_RESULTS {
+            self.buffer.remove(0);
+        }
+        self.buffer.push(result);
+    }
+
+    /// Write the buffered results, keeping them on failure
+    async fn flush(&mut self, client: &reqwest::Client) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let body = match self.lines().await {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to load monitors for the InfluxDB sink: {}", e);
+                return;
+            }
+        };
+        if body.is_empty() {
+            self.buffer.clear();
+            return;
+        }
+
+        let mut request = client
+            .post(&self.config.url)
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .body(body);
+        if let Some(token) = &self.config.token {
+            request = request.header("Authorization", format!("Token {token}"));
+        }
+
+        match request.send().await.and_then(|response| response.error_for_status()) {
+            Ok(_) => {
+                debug!("Wrote {} results to {}", self.buffer.len(), self.config.url);
+                self.buffer.clear();
+            }
+            Err(e) => {
+                warn!("Failed to write to InfluxDB, {} results kept: {}", self.buffer.len(), e)
+            }
+        }
+    }
+
+    /// Line protocol for the buffered results of the selected monitors
+    async fn lines(&self) -> Result<String> {
+        let monitors: HashMap<Uuid, Monitor> =
+            self.database.get_monitors().await?.into_iter().map(|m| (m.uuid, m)).collect();
+
+        let mut body = String::new();
+        for result in &self.buffer {
+            // Results of deleted monitors have nothing to match the tags against
+            let Some(monitor) = monitors.get(&result.monitor_id) else {
+                continue;
+            };
+            if selected(&self.config.monitor_tags, monitor) {
+                body.push_str(&line(&self.config.measurement, monitor, result));
+                body.push('\n');
+            }
+        }
+        Ok(body)
+    }
+}
+
+/// Whether a monitor carries one of the sink's tags
+fn selected(monitor_tags: &[String], monitor: &Monitor) -> bool {
+    monitor_tags.is_empty() || monitor.tags.iter().any(|tag| monitor_tags.contains(tag))
+}
+
+/// One point for a result, with a nanosecond timestamp
+fn line(measurement: &str, monitor: &Monitor, result: &CheckResult) -> String {
+    let mut line = escape(measurement, &[',', ' ']);
+
+    let tags = [
+        ("monitor", monitor.uuid.to_string()),
+        ("name", monitor.name.clone()),
+        ("tags", monitor.tags.join(",")),
+        ("target", result.target.clone()),
+    ];
+    // Empty tag values are not allowed
+    for (key, value) in tags.iter().filter(|(_, value)| !value.is_empty()) {
+        line.push_str(&format!(",{key}={}", escape(value, &[',', '=', ' '])));
+    }
+
+    let up = matches!(result.status, MonitorStatus::Up | MonitorStatus::Degraded);
+    let mut fields = vec![format!("status=\"{}\"", result.status), format!("up={up}")];
+    if let Some(ms) = result.latency_ms {
+        fields.push(format!("latency_ms={ms}i"));
+    }
+    if let Some(code) = result.status_code {
+        fields.push(format!("status_code={code}i"));
+    }
+    if let Some(error) = &result.error_message {
+        // A newline would end the point
+        let error = error.replace('\n', " ");
+        fields.push(format!("error=\"{}\"", escape(&error, &['"'])));
+    }
+    line.push(' ');
+    line.push_str(&fields.join(","));
+
+    let nanos = result.timestamp.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    line.push_str(&format!(" {nanos}"));
+    line
+}
+
+/// Backslash-escape `special` characters, and backslashes themselves
+fn escape(value: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == '\\' || special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn monitor(tags: &[&str]) -> Monitor {
+        let mut monitor = Monitor::new(
+            "api, eu".to_string(),
+            "https://api.example.com".to_string(),
+            "http".to_string(),
+        );
+        monitor.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        monitor
+    }
+
+    #[test]
+    fn test_selected_by_monitor_tags() {
+        let prod = monitor(&["prod", "eu-west"]);
+        let untagged = monitor(&[]);
+
+        assert!(selected(&[], &prod));
+        assert!(selected(&[], &untagged));
+        assert!(selected(&["eu-west".to_string()], &prod));
+        assert!(!selected(&["staging".to_string()], &prod));
+        assert!(!selected(&["prod".to_string()], &untagged));
+    }
+
+    #[test]
+    fn test_line_protocol() {
+        let monitor = monitor(&["prod", "eu-west"]);
+        let mut result = CheckResult::new(
+            monitor.uuid,
+            "https://api.example.com".to_string(),
+            "peer".to_string(),
+        );
+        result.status = MonitorStatus::Down;
+        result.latency_ms = Some(120);
+        result.status_code = Some(503);
+        result.error_message = Some(r#"HTTP "503""#.to_string());
+        result.timestamp = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+
+        assert_eq!(
+            line("uppe check", &monitor, &result),
+            format!(
+                "uppe\\ check,monitor={},name=api\\,\\ eu,tags=prod\\,eu-west,\
+                 target=https://api.example.com \
+                 status=\"down\",up=false,latency_ms=120i,status_code=503i,\
+                 error=\"HTTP \\\"503\\\"\" 1700000000123000000",
+                monitor.uuid
+            )
+        );
+
+        result.status = MonitorStatus::Degraded;
+        result.error_message = None;
+        result.timestamp = SystemTime::UNIX_EPOCH;
+        assert!(
+            line("uppe_check", &monitor, &result)
+                .ends_with(" status=\"degraded\",up=true,latency_ms=120i,status_code=503i 0")
+        );
+    }
+}
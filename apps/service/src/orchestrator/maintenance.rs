@@ -0,0 +1,99 @@
+/// Maintenance windows - marks results checked while a monitor is expected down
+///
+/// The manager keeps the windows that have not ended yet in memory, where the
+/// pipeline looks up whether a result falls into one. Marked results are
+/// stored, but not shared with peers or alerted about, and uptime, rollups and
+/// agreement leave them out.
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::runtime::RuntimeSettings;
+use crate::database::Database;
+use crate::database::models::MaintenanceWindow;
+
+/// How often window changes are picked up
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Windows that have not ended yet, shared with the result pipeline
+pub type MaintenanceWindows = Arc<RwLock<Vec<MaintenanceWindow>>>;
+
+/// Task keeping the shared maintenance windows up to date
+pub struct MaintenanceManager {
+    database: Arc<dyn Database>,
+    windows: MaintenanceWindows,
+}
+
+impl MaintenanceManager {
+    /// Create a manager updating the given windows
+    pub fn new(database: Arc<dyn Database>, windows: MaintenanceWindows) -> Self {
+        Self { database, windows }
+    }
+
+    /// Spawn the manager; it stops once the settings channel closes
+    pub fn spawn(self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(POLL_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => {}
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+
+                match self.database.get_maintenance_windows(SystemTime::now()).await {
+                    Ok(windows) => *self.windows.write().unwrap() = windows,
+                    Err(e) => warn!("Failed to load maintenance windows: {}", e),
+                }
+            }
+        })
+    }
+}
+
+/// Whether a check of a monitor at a time falls into a maintenance window
+pub fn in_maintenance(windows: &MaintenanceWindows, monitor_id: Uuid, time: SystemTime) -> bool {
+    windows.read().unwrap().iter().any(|window| window.covers(monitor_id, time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_maintenance() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let end = start + Duration::from_secs(600);
+        let monitor_id = Uuid::new_v4();
+        let windows = MaintenanceWindows::default();
+        assert!(!in_maintenance(&windows, monitor_id, start));
+
+        windows.write().unwrap().push(MaintenanceWindow::new(
+            Some(monitor_id),
+            start,
+            end,
+            "Database upgrade".to_string(),
+        ));
+        assert!(in_maintenance(&windows, monitor_id, start));
+        assert!(in_maintenance(&windows, monitor_id, end - Duration::from_secs(1)));
+        assert!(!in_maintenance(&windows, monitor_id, end));
+        assert!(!in_maintenance(&windows, monitor_id, start - Duration::from_secs(1)));
+        assert!(!in_maintenance(&windows, Uuid::new_v4(), start));
+
+        // Windows without a monitor cover all of them
+        let later = end + Duration::from_secs(600);
+        windows
+            .write()
+            .unwrap()
+            .push(MaintenanceWindow::new(None, end, later, String::new()));
+        assert!(in_maintenance(&windows, Uuid::new_v4(), end));
+        assert!(!in_maintenance(&windows, Uuid::new_v4(), start));
+    }
+}
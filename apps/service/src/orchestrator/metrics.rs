@@ -0,0 +1,304 @@
+/// Prometheus metrics - what the orchestrator tasks report, rendered in the
+/// text exposition format for `GET /metrics`
+///
+/// The pipeline reports every stored result, the stats tracker its network
+/// counters and the peer event handler the DHT queries that finished. Only the
+/// latest state is kept; Prometheus derives rates from the scraped counters.
+/// Monitors stay listed until the service restarts.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::database::models::NetworkStats;
+use crate::monitoring::CheckResult;
+use crate::monitoring::types::MonitorStatus;
+
+/// Upper bounds of the check latency buckets, in seconds
+const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Cumulative latency histogram of one monitor
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MonitorMetrics {
+    target: String,
+    status: MonitorStatus,
+    latency: Histogram,
+}
+
+/// How a DHT query ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum DhtOutcome {
+    Found,
+    Empty,
+    Failed,
+}
+
+impl DhtOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            DhtOutcome::Found => "found",
+            DhtOutcome::Empty => "empty",
+            DhtOutcome::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    monitors: BTreeMap<Uuid, MonitorMetrics>,
+    network: Option<NetworkStats>,
+    routing_table_size: Option<usize>,
+    dht_queries: BTreeMap<DhtOutcome, u64>,
+    dht_records: u64,
+}
+
+/// Metrics registry shared by the orchestrator tasks and the health endpoints
+#[derive(Debug, Clone, Default)]
+pub struct ServiceMetrics(Arc<Mutex<MetricsInner>>);
+
+impl ServiceMetrics {
+    /// Record the status and latency of a stored local result
+    pub fn record_result(&self, result: &CheckResult) {
+        let mut inner = self.0.lock().unwrap();
+        let monitor = inner.monitors.entry(result.monitor_id).or_insert_with(|| MonitorMetrics {
+            target: result.target.clone(),
+            status: result.status,
+            latency: Histogram::default(),
+        });
+        monitor.target.clone_from(&result.target);
+        monitor.status = result.status;
+        if let Some(ms) = result.latency_ms {
+            monitor.latency.observe(ms as f64 / 1000.0);
+        }
+    }
+
+    /// Replace the network counters with the stats tracker's latest snapshot
+    pub fn set_network(&self, stats: NetworkStats) {
+        self.0.lock().unwrap().network = Some(stats);
+    }
+
+    /// Record the size of the node's Kademlia routing table
+    pub fn set_routing_table_size(&self, size: usize) {
+        self.0.lock().unwrap().routing_table_size = Some(size);
+    }
+
+    /// Record a finished DHT query and how many records it found
+    pub fn record_dht_query(&self, records: usize, failed: bool) {
+        let outcome = match (failed, records) {
+            (true, _) => DhtOutcome::Failed,
+            (false, 0) => DhtOutcome::Empty,
+            (false, _) => DhtOutcome::Found,
+        };
+        let mut inner = self.0.lock().unwrap();
+        *inner.dht_queries.entry(outcome).or_default() += 1;
+        inner.dht_records += records as u64;
+    }
+
+    /// Current metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        render(&self.0.lock().unwrap())
+    }
+}
+
+fn render(inner: &MetricsInner) -> String {
+    let mut out = String::new();
+
+    header(
+        &mut out,
+        "uppe_monitor_up",
+        "gauge",
+        "Whether the last local check was up (1) or down (0)",
+    );
+    for (uuid, monitor) in &inner.monitors {
+        // Degraded checks count as up, as in the rollups; unknown ones are left out
+        let up = match monitor.status {
+            MonitorStatus::Up | MonitorStatus::Degraded => 1,
+            MonitorStatus::Down => 0,
+            MonitorStatus::Unknown => continue,
+        };
+        let labels = monitor_labels(uuid, &monitor.target);
+        let _ = writeln!(out, "uppe_monitor_up{{{labels}}} {up}");
+    }
+
+    header(&mut out, "uppe_monitor_status", "gauge", "Status of the last local check");
+    for (uuid, monitor) in &inner.monitors {
+        let labels = monitor_labels(uuid, &monitor.target);
+        let _ = writeln!(out, "uppe_monitor_status{{{labels},status=\"{}\"}} 1", monitor.status);
+    }
+
+    header(&mut out, "uppe_check_latency_seconds", "histogram", "Latency of local checks");
+    for (uuid, monitor) in &inner.monitors {
+        let labels = monitor_labels(uuid, &monitor.target);
+        let latency = &monitor.latency;
+        for (count, bound) in latency.buckets.iter().zip(LATENCY_BUCKETS) {
+            let _ = writeln!(
+                out,
+                "uppe_check_latency_seconds_bucket{{{labels},le=\"{bound}\"}} {count}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "uppe_check_latency_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+            latency.count
+        );
+        let _ = writeln!(out, "uppe_check_latency_seconds_sum{{{labels}}} {}", latency.sum);
+        let _ = writeln!(out, "uppe_check_latency_seconds_count{{{labels}}} {}", latency.count);
+    }
+
+    if let Some(network) = &inner.network {
+        let samples = [
+            (
+                "uppe_peers_connected",
+                "gauge",
+                "Currently connected peers",
+                network.online_peers,
+            ),
+            ("uppe_peers_seen", "gauge", "Peers seen in the last day", network.total_peers),
+            (
+                "uppe_checks_performed_total",
+                "counter",
+                "Local checks performed since start",
+                network.checks_performed,
+            ),
+            (
+                "uppe_checks_received_total",
+                "counter",
+                "Results received from peers and stored since start",
+                network.checks_received,
+            ),
+        ];
+        for (name, kind, help, value) in samples {
+            header(&mut out, name, kind, help);
+            let _ = writeln!(out, "{name} {value}");
+        }
+    }
+
+    if let Some(size) = inner.routing_table_size {
+        header(
+            &mut out,
+            "uppe_dht_routing_table_size",
+            "gauge",
+            "Entries in the Kademlia routing table",
+        );
+        let _ = writeln!(out, "uppe_dht_routing_table_size {size}");
+    }
+
+    header(
+        &mut out,
+        "uppe_dht_queries_total",
+        "counter",
+        "DHT lookups and publishes finished, by outcome",
+    );
+    for (outcome, count) in &inner.dht_queries {
+        let _ = writeln!(out, "uppe_dht_queries_total{{outcome=\"{}\"}} {count}", outcome.as_str());
+    }
+    header(&mut out, "uppe_dht_records_total", "counter", "Records found by DHT lookups");
+    let _ = writeln!(out, "uppe_dht_records_total {}", inner.dht_records);
+
+    out
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn monitor_labels(uuid: &Uuid, target: &str) -> String {
+    format!("monitor=\"{uuid}\",target=\"{}\"", escape_label(target))
+}
+
+/// Escape a label value as the exposition format requires
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn result(monitor_id: Uuid, status: MonitorStatus, latency_ms: Option<u64>) -> CheckResult {
+        let mut result = CheckResult::new(
+            monitor_id,
+            "https://example.com/\"a\"".to_string(),
+            "local".to_string(),
+        );
+        result.status = status;
+        result.latency_ms = latency_ms;
+        result
+    }
+
+    #[test]
+    fn test_render() {
+        let metrics = ServiceMetrics::default();
+        let uuid = Uuid::nil();
+        metrics.record_result(&result(uuid, MonitorStatus::Up, Some(500)));
+        metrics.record_result(&result(uuid, MonitorStatus::Down, Some(3000)));
+        metrics.record_dht_query(2, false);
+        metrics.record_dht_query(0, true);
+        metrics.set_routing_table_size(7);
+
+        let rendered = metrics.render();
+        let labels = format!("monitor=\"{uuid}\",target=\"https://example.com/\\\"a\\\"\"");
+        assert!(rendered.contains(&format!("uppe_monitor_up{{{labels}}} 0\n")));
+        assert!(rendered.contains(&format!("uppe_monitor_status{{{labels},status=\"down\"}} 1\n")));
+        assert!(
+            rendered
+                .contains(&format!("uppe_check_latency_seconds_bucket{{{labels},le=\"0.5\"}} 1\n"))
+        );
+        assert!(
+            rendered
+                .contains(&format!("uppe_check_latency_seconds_bucket{{{labels},le=\"5\"}} 2\n"))
+        );
+        assert!(
+            rendered.contains(&format!(
+                "uppe_check_latency_seconds_bucket{{{labels},le=\"+Inf\"}} 2\n"
+            ))
+        );
+        assert!(rendered.contains(&format!("uppe_check_latency_seconds_sum{{{labels}}} 3.5\n")));
+        assert!(rendered.contains("uppe_dht_queries_total{outcome=\"found\"} 1\n"));
+        assert!(rendered.contains("uppe_dht_queries_total{outcome=\"failed\"} 1\n"));
+        assert!(rendered.contains("uppe_dht_records_total 2\n"));
+        assert!(rendered.contains("uppe_dht_routing_table_size 7\n"));
+        // Network counters appear once the stats tracker reported them
+        assert!(!rendered.contains("uppe_peers_connected"));
+
+        metrics.set_network(NetworkStats {
+            timestamp: SystemTime::now(),
+            total_peers: 5,
+            online_peers: 3,
+            checks_performed: 2,
+            checks_received: 9,
+            bandwidth_used_mb: 0,
+            helper_assignments: 0,
+            helper_max_assignments: 0,
+            helper_checks_last_hour: 0,
+            helper_max_checks_per_hour: 0,
+            seen_lookups: 0,
+            seen_hits: 0,
+        });
+        let rendered = metrics.render();
+        assert!(rendered.contains("# TYPE uppe_checks_received_total counter\n"));
+        assert!(rendered.contains("uppe_checks_received_total 9\n"));
+        assert!(rendered.contains("uppe_peers_connected 3\n"));
+    }
+}
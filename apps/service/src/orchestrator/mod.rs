@@ -4,72 +4,170 @@
 /// - Manages the lifecycle of all components
 /// - Coordinates between monitoring, database, crypto, and P2P layers
 /// - Handles results and distributes them appropriately
+mod agent_results;
+mod agreement;
+mod alerts;
+mod api;
+mod api_tls;
+mod assignments;
+mod audit;
+mod bandwidth;
+mod capacity;
+pub mod chaos;
+mod cluster;
+pub mod community;
+mod db_pool;
+mod dedup;
+mod dht_debug;
+mod duties;
+mod fanout;
+mod follow;
+mod health;
+mod heartbeat;
+mod http;
+mod incidents;
+mod influx;
+mod maintenance;
+mod metrics;
+mod mqtt;
+mod owner_sync;
+mod peer_events;
+mod pipeline;
+mod recruiter;
+mod region;
+mod reload;
+mod remote_write;
+mod reports;
+mod results_sync;
+mod retention;
+mod runtime;
+#[cfg(test)]
+mod simulation;
+mod stats;
+mod status_page;
+mod telemetry;
+mod visibility;
+
 use anyhow::Result;
-use std::collections::HashSet;
 use std::path::PathBuf;
-use std::sync::Arc;
-use std::time::{Duration, Instant, SystemTime};
-use tokio::sync::mpsc;
-use tracing::{debug, error, info, warn};
-
-use crate::config::Config;
-use crate::crypto::{KeyPair, load_or_generate_keypair, sign_result, verify_result};
-use crate::database::models::{NetworkStats, Peer};
-use crate::database::{Database, DatabaseImpl, initialize_database};
-use crate::monitoring::checker::CheckType;
-use crate::monitoring::scheduler::MonitorConfig;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::archive::ResultArchive;
+use crate::bus::BusPublisher;
+use crate::config::{Config, DatabaseBackend};
+use crate::crypto::{ClusterKey, KeyPair, keypair_path, load_or_generate_keypair};
+use crate::database::{self, Database};
+use crate::journal::{Journal, journal_path, recover};
 use crate::monitoring::{CheckResult, MonitoringExecutor, MonitoringScheduler};
 use crate::p2p::P2PNetwork;
+use crate::policy::ProbePolicy;
 use crate::pool::LibsqlPool;
+use crate::queue;
+use crate::redact::ErrorRedactor;
+
+use agent_results::AgentResults;
+use agreement::AgreementAggregator;
+use alerts::AlertNotifier;
+use api_tls::ApiTls;
+use assignments::{AssignmentStore, SharedAssignments};
+use audit::AuditWriter;
+use bandwidth::BandwidthBudget;
+use capacity::{HelperCapacity, HelperLimits};
+use cluster::ClusterSync;
+use community::{CommunityGroups, CommunityManager};
+use db_pool::PoolMonitor;
+use dedup::PublishSchedule;
+use dht_debug::DhtDebug;
+use duties::HelperDuties;
+use fanout::ProbeFanout;
+use follow::{FollowManager, FollowedHosts};
+use health::ServiceHealth;
+use heartbeat::HeartbeatWatcher;
+use incidents::IncidentManager;
+use influx::InfluxSink;
+use maintenance::{MaintenanceManager, MaintenanceWindows};
+use metrics::ServiceMetrics;
+use mqtt::MqttPublisher;
+use owner_sync::OwnerSync;
+use peer_events::PeerEventHandler;
+use pipeline::ResultPipeline;
+use recruiter::HelperRecruiter;
+use region::RegionEstimator;
+use reload::{ReloadManager, ReloadRequest};
+use remote_write::RemoteWriter;
+use reports::ReportScheduler;
+use results_sync::ResultsSync;
+use retention::RetentionSweeper;
+use runtime::{RuntimeConfigWatcher, spawn_executor_updates};
+use stats::StatsTracker;
+use visibility::{PrivateMonitors, VisibilityManager};
+
+/// How long each subsystem may take to wind down on shutdown
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Main orchestrator for the Uppe service
 pub struct Orchestrator {
-    #[allow(dead_code)] // Will be used for runtime configuration changes
     config: Arc<Config>,
+    /// Config file re-read for runtime changes, if known
+    config_path: Option<PathBuf>,
     database: Arc<dyn Database>,
+    /// Pool behind `database` on the libsql backend, watched and resized by `PoolMonitor`
+    pool: LibsqlPool,
     keypair: Arc<KeyPair>,
     executor: Arc<MonitoringExecutor>,
-    p2p_network: Arc<P2PNetwork>,
-    task_handles: Vec<tokio::task::JoinHandle<()>>,
+    p2p_network: P2PNetwork,
+    policy: Arc<ProbePolicy>,
+    redactor: Arc<ErrorRedactor>,
+    /// Key shared with our other nodes when `[cluster]` is configured
+    cluster_key: Option<Arc<ClusterKey>>,
+    assignments: SharedAssignments,
 }
 
 impl Orchestrator {
     /// Create and start a new orchestrator
     /// This is a convenience method that creates and immediately runs the orchestrator
-    pub async fn start(config: Config, pool: LibsqlPool) -> Result<()> {
-        let mut orchestrator = Self::new(config, pool).await?;
+    pub async fn start(
+        config: Config,
+        config_path: Option<PathBuf>,
+        pool: LibsqlPool,
+    ) -> Result<()> {
+        let mut orchestrator = Self::new(config, config_path, pool).await?;
         orchestrator.run().await
     }
 
     /// Create a new orchestrator instance
-    async fn new(config: Config, pool: LibsqlPool) -> Result<Self> {
+    async fn new(config: Config, config_path: Option<PathBuf>, pool: LibsqlPool) -> Result<Self> {
         let config = Arc::new(config);
 
-        // Get database connection for initialization
-        let conn = pool.get().await?;
-
-        // Initialize database schema
+        // Open the configured backend and bring its schema up to date
         info!("Initializing database schema...");
-        initialize_database(&conn).await?;
+        let database =
+            database::open(&config.database, &config.database_pool, pool.clone()).await?;
 
-        // Create database instance with pool
-        let database = Arc::new(DatabaseImpl::new_from_pool(pool));
+        // Restore helper assignments persisted before the last shutdown
+        let mut assignments = AssignmentStore::new(database.clone());
+        if let Err(e) = assignments.restore().await {
+            warn!("Failed to restore helper assignments: {}", e);
+        }
 
         // Load or generate cryptographic keypair
         info!("Loading cryptographic keypair...");
-        let keypair_path =
-            std::env::var("UPPE_KEYPAIR_PATH").unwrap_or_else(|_| "uppe_keypair.key".to_string());
-        let keypair_path = PathBuf::from(keypair_path);
-        let keypair = Arc::new(load_or_generate_keypair(&keypair_path)?);
+        let keypair = Arc::new(load_or_generate_keypair(&keypair_path())?);
         let peer_id = keypair.public_key_hex();
         info!("Peer ID (public key): {}", peer_id);
 
         // Create monitoring executor
-        let executor = Arc::new(MonitoringExecutor::new(
-            peer_id.clone(),
-            config.preferences.timeout_seconds.unwrap_or(10),
-            config.preferences.degraded_threshold_ms.unwrap_or(1000),
-        )?);
+        let executor = Arc::new(
+            MonitoringExecutor::new(
+                peer_id.clone(),
+                config.preferences.timeout_seconds.unwrap_or(10),
+                config.preferences.degraded_threshold_ms.unwrap_or(1000),
+            )?
+            .with_pools(&config.check_pools)
+            .with_cert_expiry(&config.cert_expiry),
+        );
 
         // Create P2P network with configuration
         let mut builder = peerup::node::NodeConfig::builder()
@@ -94,6 +192,20 @@ impl Orchestrator {
         } else {
             builder = builder.disable_relay();
         }
+        if config.peerup.enable_hole_punching {
+            builder = builder.enable_hole_punching();
+        } else {
+            builder = builder.disable_hole_punching();
+        }
+        if let Some(relay_server) = &config.peerup.relay_server {
+            info!("Hosting a relay for NATed peers");
+            builder = builder.relay_server(relay_server.limits());
+        }
+        builder = builder
+            .allow_peers(parse_peer_ids(&config.peerup.allowed_peers, "allowed"))
+            .block_peers(parse_peer_ids(&config.peerup.blocked_peers, "blocked"))
+            .gossip_rate_limit(config.peerup.gossip_rate_limit.limit())
+            .peer_rate_limit(config.peerup.peer_rate_limit.limit());
 
         let peerup_config = builder.build();
 
@@ -102,7 +214,26 @@ impl Orchestrator {
             config.preferences.use_peerup_layer,
             keypair.public_key_bytes(),
             peerup_config,
-        );
+        )
+        .with_queues(config.queues.p2p_events, config.queues.p2p_commands)
+        .with_compact_results(config.peerup.compact_results)
+        .with_offline_buffer(config.peerup.offline_buffer)
+        .with_identity_key(keypair.clone());
+
+        let cluster_key = config
+            .cluster
+            .key
+            .as_deref()
+            .map(ClusterKey::from_hex)
+            .transpose()?
+            .map(Arc::new);
+        if let Some(key) = &cluster_key {
+            info!("Cluster mode enabled on topic {}", key.topic());
+            p2p_network = p2p_network.with_cluster_topic(key.topic());
+        }
+
+        let policy = Arc::new(ProbePolicy::from_config(&config.helper)?);
+        let redactor = Arc::new(ErrorRedactor::from_config(&config.redaction)?);
 
         // Start P2P network if enabled
         if p2p_network.is_enabled() {
@@ -114,250 +245,579 @@ impl Orchestrator {
 
         Ok(Self {
             config,
+            config_path,
             database,
+            pool,
             keypair,
             executor,
-            p2p_network: Arc::new(p2p_network),
-            task_handles: Vec::new(),
+            p2p_network,
+            policy,
+            redactor,
+            cluster_key,
+            assignments: Arc::new(tokio::sync::Mutex::new(assignments)),
         })
     }
 
     /// Run the orchestrator
+    ///
+    /// Work is split across independent tasks that talk over channels:
+    /// - `ResultPipeline` signs, stores and shares local results
+    /// - `PeerEventHandler` verifies and stores what arrives from peers
+    /// - `ReloadManager` owns the scheduled monitors
+    /// - `StatsTracker` counts activity and persists network stats
+    /// - `AuditWriter` persists security-relevant events to the audit log
+    /// - `OwnerSync` backfills results for our monitors from the DHT
+    /// - `ResultsSync` pulls peer results missed while offline from peers
+    ///   that connect, and answers their requests for ours
+    /// - `ClusterSync` keeps monitors, settings and notification channels in
+    ///   step with our other nodes when `[cluster]` has a key
+    /// - `DhtDebug` runs DHT lookups and publishes queued from the TUI
+    /// - `AgreementAggregator` compares our results with verified peer results
+    /// - `ProbeFanout` runs queued multi-vantage probes and answers probes
+    ///   peers ask of us
+    /// - `HelperDuties` runs the checks we accepted for other owners and sends
+    ///   each result sealed to its owner
+    /// - `HelperRecruiter` asks the peers in `[helper] helpers` to check our
+    ///   private monitors
+    /// - `VisibilityManager` keeps private monitors unshared and retracts the
+    ///   results of monitors that turned private
+    /// - `MaintenanceManager` loads the maintenance windows the pipeline marks
+    ///   results by
+    /// - `FollowManager` loads the followed hosts and looks up their latest
+    ///   results in the DHT
+    /// - `RegionEstimator` infers our region from round trips while our
+    ///   location is unknown
+    /// - `CommunityManager` announces the community domains we joined or left
+    ///   and loads who takes part in each
+    /// - `health::serve` answers `/healthz`, `/readyz` and `/metrics` when
+    ///   configured, with the `ServiceMetrics` the pipeline, stats tracker and
+    ///   peer handler report
+    /// - `status_page::serve` serves status pages as Uptime Kuma JSON when
+    ///   configured
+    /// - `agent_results::serve` passes results signed by external agents to
+    ///   the pipeline when configured
+    /// - `api::serve` lets scripts and the frontend manage monitors over REST
+    ///   when configured, and takes heartbeat pings
+    /// - `HeartbeatWatcher` stores heartbeat pings as results and marks
+    ///   heartbeat monitors down when pings stop
+    /// - `BusPublisher` tells the TUI about stored results over ZeroMQ
+    /// - `RemoteWriter` pushes results to a Prometheus remote-write endpoint
+    ///   when configured
+    /// - `InfluxSink` writes results as line protocol, one per `[[influxdb]]`
+    /// - `MqttPublisher` publishes state changes and latency to an MQTT broker
+    ///   when configured
+    /// - `AlertNotifier` alerts a monitor's notification channels when its
+    ///   status changes
+    /// - `IncidentManager` opens an incident for a monitor that stays down,
+    ///   updates it on status changes and resolves it on recovery
+    /// - `ReportScheduler` delivers weekly and monthly uptime reports, one per
+    ///   `[[reports]]`, to notification channels
+    /// - `RetentionSweeper` rolls our results up hourly and daily, then deletes
+    ///   expired results and audit events, uploading our results to the
+    ///   `[archive]` bucket first when configured
+    /// - `PoolMonitor` reports libsql pool usage and resizes the pool
+    /// - `RuntimeConfigWatcher` publishes config and settings changes, which the
+    ///   executor, pipeline, peer handler and `RetentionSweeper` apply live
+    ///
+    /// In-flight results, accepted assignments and agreement buckets are
+    /// recorded in a `Journal`; what a crash left unfinished is recovered
+    /// before monitors are scheduled.
     async fn run(&mut self) -> Result<()> {
         info!("Starting Uppe orchestrator...");
 
         // P2P network was already started in new(), no need to start again
 
-        // Create channels for communication
-        let (result_tx, mut result_rx) = mpsc::channel::<CheckResult>(100);
-
-        // Create scheduler
-        let scheduler = MonitoringScheduler::new(self.executor.clone(), result_tx.clone());
-
-        // Load monitors from database and schedule them
-        info!("Loading monitors from database...");
-        let monitors = self.database.get_enabled_monitors().await?;
-        info!("Found {} enabled monitors", monitors.len());
-
-        // Convert database monitors to scheduler configs
-        let monitor_configs: Vec<MonitorConfig> = monitors
-            .into_iter()
-            .map(|m| {
-                let check_type = match m.check_type.as_str() {
-                    "http" => CheckType::Http,
-                    "https" => CheckType::Https,
-                    "tcp" => CheckType::Tcp,
-                    "icmp" => CheckType::Icmp,
-                    _ => CheckType::Http,
-                };
-
-                MonitorConfig {
-                    id: m.uuid,
-                    target: m.target,
-                    check_type,
-                    interval_seconds: m.interval_seconds,
-                    enabled: m.enabled,
-                }
-            })
-            .collect();
+        let (result_tx, result_rx) =
+            queue::bounded::<CheckResult>("results", self.config.queues.results);
 
-        // Schedule all monitors
-        info!("Scheduling monitors...");
-        self.task_handles = scheduler.schedule_monitors(monitor_configs);
-
-        // Process results in a loop
-        info!("Orchestrator started successfully - processing monitoring results");
-
-        // Track last location check
-        let mut last_location_check = Instant::now();
-        let location_check_interval = Duration::from_secs(60); // Check every minute if update is needed
-
-        // P2P/network stats tracking
-        let mut connected_peers: HashSet<String> = HashSet::new();
-        let mut total_peers_seen: HashSet<String> = HashSet::new();
-        let mut checks_performed: i64 = 0;
-        let mut checks_received: i64 = 0;
-        let mut last_stats_persist = Instant::now();
-        let stats_persist_interval = Duration::from_secs(5);
-
-        // Get mutable reference to p2p_network for event handling
-        let p2p_network = Arc::get_mut(&mut self.p2p_network)
-            .expect("P2P network should not have multiple references at this point");
-
-        loop {
-            tokio::select! {
-                // Handle monitoring results
-                Some(result) = result_rx.recv() => {
-                    // Periodically check if location needs updating (for mobile devices)
-                    if last_location_check.elapsed() >= location_check_interval {
-                        crate::location::check_and_update_location();
-                        last_location_check = Instant::now();
+        let path = journal_path();
+        let (journal, unfinished) = match Journal::open(&path) {
+            Ok((journal, unfinished)) => (Some(Arc::new(journal)), unfinished),
+            Err(e) => {
+                warn!("Failed to open journal {}, running without it: {}", path.display(), e);
+                (None, Vec::new())
+            }
+        };
+
+        let (settings_rx, watcher_task) = RuntimeConfigWatcher::new(
+            self.database.clone(),
+            &self.config,
+            self.config_path.clone(),
+        )
+        .spawn()
+        .await;
+
+        let budget = BandwidthBudget::default();
+        let metrics = ServiceMetrics::default();
+        let (stats_tx, stats_task) =
+            StatsTracker::new(self.database.clone(), settings_rx.clone(), budget.clone())
+                .with_config(self.config.network_stats)
+                .with_metrics(metrics.clone())
+                .spawn();
+        let executor_updates_task =
+            spawn_executor_updates(self.executor.clone(), settings_rx.clone());
+        let mut sweeper =
+            RetentionSweeper::new(self.database.clone(), self.config.retention.hourly_rollup_days);
+        match ResultArchive::from_config(&self.config.archive) {
+            Ok(Some(archive)) => sweeper = sweeper.with_archive(Arc::new(archive)),
+            Ok(None) => {}
+            Err(e) => warn!("Not archiving results: {:#}", e),
+        }
+        let retention_task = sweeper.spawn(settings_rx.clone());
+        // The postgres backend sizes its own pool; ours sits idle then
+        let pool_task = (self.config.database.backend == DatabaseBackend::Libsql).then(|| {
+            PoolMonitor::new(self.pool.clone(), self.config.database_pool)
+                .spawn(settings_rx.clone())
+        });
+        let mut aggregator = AgreementAggregator::new(self.database.clone());
+        if let Some(journal) = &journal {
+            aggregator = aggregator.with_journal(journal.clone());
+        }
+        let agreement_task = aggregator.spawn(settings_rx.clone());
+
+        let (audit, audit_task) = AuditWriter::new(self.database.clone()).spawn();
+
+        let private = PrivateMonitors::default();
+        let visibility_task = VisibilityManager::new(
+            self.database.clone(),
+            self.keypair.clone(),
+            self.p2p_network.handle(),
+            private.clone(),
+            audit.clone(),
+        )
+        .spawn(settings_rx.clone());
+        let maintenance = MaintenanceWindows::default();
+        let maintenance_task = MaintenanceManager::new(self.database.clone(), maintenance.clone())
+            .spawn(settings_rx.clone());
+        let followed = FollowedHosts::default();
+        let follow_task =
+            FollowManager::new(self.database.clone(), self.p2p_network.handle(), followed.clone())
+                .spawn(settings_rx.clone());
+        let region_task =
+            RegionEstimator::new(self.database.clone(), &self.config.preferences.region_anchors)
+                .spawn(settings_rx.clone());
+        let groups = CommunityGroups::default();
+        let community_task = CommunityManager::new(
+            self.database.clone(),
+            self.p2p_network.handle(),
+            self.keypair.clone(),
+            groups.clone(),
+        )
+        .spawn(settings_rx.clone());
+
+        let health = ServiceHealth::new(self.p2p_network.is_enabled());
+        // Badly configured TLS keeps the APIs off rather than serving them in the clear
+        let api_tls = match ApiTls::from_config(&self.config.api_tls) {
+            Ok(tls) => Some(tls.map(Arc::new)),
+            Err(e) => {
+                warn!("Not serving the HTTP APIs, their TLS settings are invalid: {:#}", e);
+                None
+            }
+        };
+        let health_task = match (&self.config.health.bind, &api_tls) {
+            (Some(bind), Some(tls)) => {
+                let serving = health::serve(
+                    bind,
+                    tls.clone(),
+                    health.clone(),
+                    metrics.clone(),
+                    self.database.clone(),
+                );
+                match serving.await {
+                    Ok(task) => Some(task),
+                    Err(e) => {
+                        warn!("Failed to serve health endpoints on {}: {}", bind, e);
+                        None
                     }
-
-                    // Sign the result
-                    let signature = sign_result(&result, &self.keypair)?;
-                    let signed_result = result.with_signature(signature);
-
-                    // Save to database
-                    if let Err(e) = self.database.save_result(&signed_result).await {
-                        error!("Failed to save result to database: {}", e);
+                }
+            }
+            _ => None,
+        };
+        let status_page_task = match (&self.config.status_page.bind, &api_tls) {
+            (Some(bind), Some(tls)) => {
+                match status_page::serve(
+                    bind,
+                    tls.clone(),
+                    self.database.clone(),
+                    self.redactor.clone(),
+                )
+                .await
+                {
+                    Ok(task) => Some(task),
+                    Err(e) => {
+                        warn!("Failed to serve the status page API on {}: {}", bind, e);
+                        None
                     }
-
-                    // Update stats for locally performed check
-                    checks_performed += 1;
-
-                    // Share with P2P network if enabled
-                    if p2p_network.is_enabled()
-                        && let Err(e) = p2p_network.share_result(&signed_result).await
-                    {
-                        error!("Failed to share result with P2P network: {}", e);
+                }
+            }
+            _ => None,
+        };
+        let agent_results_task = match (&self.config.agent_results.bind, &api_tls) {
+            (Some(bind), Some(tls)) => {
+                let agents =
+                    AgentResults::new(self.config.agent_results.agents.clone(), result_tx.clone());
+                match agent_results::serve(bind, tls.clone(), self.database.clone(), agents).await {
+                    Ok(task) => Some(task),
+                    Err(e) => {
+                        warn!("Failed to take agent results on {}: {}", bind, e);
+                        None
                     }
+                }
+            }
+            _ => None,
+        };
+
+        let default_limits = HelperLimits::from_config(&self.config.helper);
+        let capacity = Arc::new(Mutex::new(HelperCapacity::new(default_limits)));
+        let duties_task = HelperDuties::new(
+            self.assignments.clone(),
+            capacity.clone(),
+            self.executor.clone(),
+            self.policy.clone(),
+            self.keypair.clone(),
+            self.p2p_network.handle(),
+            audit.clone(),
+        )
+        .with_redactor(self.redactor.clone())
+        .spawn(settings_rx.clone());
+        let recruiter_task = HelperRecruiter::new(
+            self.database.clone(),
+            self.assignments.clone(),
+            self.keypair.clone(),
+            self.p2p_network.handle(),
+            &self.config.helper.helpers,
+        )
+        .spawn(settings_rx.clone());
+
+        // Our nodes share a cluster key; without one, this node publishes alone
+        let owner = match &self.cluster_key {
+            Some(key) => key.topic().to_string(),
+            None => self.keypair.public_key_hex(),
+        };
+        let schedule = PublishSchedule::shared(owner);
+        let mut pipeline = ResultPipeline::new(
+            self.database.clone(),
+            self.keypair.clone(),
+            self.p2p_network.handle(),
+            settings_rx.clone(),
+            budget,
+            stats_tx.clone(),
+            private,
+        )
+        .with_publish_schedule(schedule.clone())
+        .with_maintenance(maintenance)
+        .with_redactor(self.redactor.clone())
+        .with_metrics(metrics.clone());
+        if let Some(journal) = &journal {
+            pipeline = pipeline.with_journal(journal.clone());
+        }
+        let bus_task = match BusPublisher::bind(&self.config.zeromq) {
+            Ok(bus) => {
+                pipeline = pipeline.with_bus(bus.clone());
+                Some(bus.spawn_heartbeat())
+            }
+            Err(e) => {
+                warn!("Running without the TUI bus: {:#}", e);
+                None
+            }
+        };
+        let mut remote_write_task = None;
+        if let Some(writer) = RemoteWriter::new(self.database.clone(), &self.config.remote_write) {
+            let (sink, task) = writer.spawn();
+            pipeline = pipeline.with_sink(sink);
+            remote_write_task = Some(task);
+        }
+        let mut influx_tasks = Vec::new();
+        for sink_config in &self.config.influxdb {
+            let (sink, task) = InfluxSink::new(self.database.clone(), sink_config.clone()).spawn();
+            pipeline = pipeline.with_sink(sink);
+            influx_tasks.push(task);
+        }
+        let mqtt_task = match MqttPublisher::new(self.database.clone(), &self.config.mqtt) {
+            Ok(Some(publisher)) => {
+                let (sink, task) = publisher.spawn();
+                pipeline = pipeline.with_sink(sink);
+                Some(task)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Not publishing to MQTT: {:#}", e);
+                None
+            }
+        };
+        let mut alerts_task = None;
+        if let Some(notifier) =
+            AlertNotifier::new(self.database.clone(), &self.config.notifications)
+        {
+            let (sink, task) = notifier.spawn();
+            pipeline = pipeline.with_sink(sink);
+            alerts_task = Some(task);
+        }
+        let mut incidents_task = None;
+        if let Some(manager) = IncidentManager::new(self.database.clone(), &self.config.incidents) {
+            let (sink, task) = manager.spawn();
+            pipeline = pipeline.with_sink(sink);
+            incidents_task = Some(task);
+        }
+        let reports_task = ReportScheduler::new(
+            self.database.clone(),
+            &self.config.reports,
+            &self.config.notifications,
+        )
+        .map(ReportScheduler::spawn);
+        let mut pipeline_task = pipeline.spawn(result_rx);
+
+        let mut owner_sync_task = None;
+        let mut fanout_task = None;
+        let mut results_sync_task = None;
+        let mut dht_debug_task = None;
+        let mut cluster = None;
+        let peer_events_task = self.p2p_network.take_event_receiver().map(|rx| {
+            let (fanout, probes_tx) = ProbeFanout::new(
+                self.database.clone(),
+                self.executor.clone(),
+                self.policy.clone(),
+                self.p2p_network.handle(),
+                audit.clone(),
+                self.keypair.public_key_hex(),
+            );
+            let fanout = fanout.with_redactor(self.redactor.clone());
+            fanout_task = Some(fanout.spawn(settings_rx.clone()));
+
+            let (results_sync, sync_tx) = ResultsSync::new(
+                self.database.clone(),
+                self.p2p_network.handle(),
+                self.keypair.public_key_hex(),
+            );
+            results_sync_task = Some(results_sync.spawn(settings_rx.clone()));
+
+            let mut handler = PeerEventHandler::new(
+                self.database.clone(),
+                self.keypair.clone(),
+                self.policy.clone(),
+                capacity.clone(),
+                settings_rx.clone(),
+                stats_tx.clone(),
+                audit,
+            )
+            .with_assignments(self.assignments.clone())
+            .with_health(health.clone())
+            .with_metrics(metrics)
+            .with_publish_schedule(schedule.clone())
+            .with_probe_fanout(probes_tx)
+            .with_results_sync(sync_tx)
+            .with_follows(followed)
+            .with_community(groups)
+            .with_p2p(self.p2p_network.handle());
+            if let Some(journal) = &journal {
+                handler = handler.with_journal(journal.clone());
+            }
+            if let Some(key) = &self.cluster_key {
+                let (cluster_sync, cluster_tx) = ClusterSync::new(
+                    self.database.clone(),
+                    self.p2p_network.handle(),
+                    key.clone(),
+                    self.keypair.public_key_hex(),
+                    Duration::from_secs(self.config.cluster.sync_interval_secs.max(1)),
+                );
+                cluster = Some(cluster_sync.with_publish_schedule(schedule));
+                handler = handler.with_cluster(cluster_tx);
+            }
+            if self.config.peerup.enable_kademlia {
+                let (owner_sync, outcomes_tx) =
+                    OwnerSync::new(self.database.clone(), self.p2p_network.handle());
+                owner_sync_task = Some(owner_sync.spawn());
+                handler = handler.with_owner_sync(outcomes_tx);
+
+                let (dht_debug, dht_outcomes_tx) =
+                    DhtDebug::new(self.database.clone(), self.p2p_network.handle());
+                dht_debug_task = Some(dht_debug.spawn(settings_rx.clone()));
+                handler = handler.with_dht_debug(dht_outcomes_tx);
+            }
+            handler.spawn(rx)
+        });
+
+        if let Some(journal) = &journal {
+            let recovered = recover(journal, unfinished, self.database.as_ref(), &result_tx).await;
+            // Assignments the journal put back were not in the database when
+            // the store was restored
+            if recovered.assignments_restored > 0
+                && let Err(e) = self.assignments.lock().await.restore().await
+            {
+                warn!("Failed to restore helper assignments: {}", e);
+            }
+        }
 
-                    if last_stats_persist.elapsed() >= stats_persist_interval {
-                        let snapshot = NetworkStats {
-                            timestamp: SystemTime::now(),
-                            total_peers: total_peers_seen.len() as i64,
-                            online_peers: connected_peers.len() as i64,
-                            checks_performed,
-                            checks_received,
-                            bandwidth_used_mb: 0,
-                        };
-
-                        if let Err(e) = self.database.insert_network_stats(&snapshot).await {
-                            warn!("Failed to persist network stats: {}", e);
+        let (heartbeats_tx, heartbeat_task) = HeartbeatWatcher::new(
+            self.database.clone(),
+            result_tx.clone(),
+            self.keypair.public_key_hex(),
+        )
+        .spawn(settings_rx.clone());
+        let mut scheduler = MonitoringScheduler::new(self.executor.clone(), result_tx);
+        if let Some(journal) = journal {
+            scheduler = scheduler.with_journal(journal);
+        }
+        let (reload_tx, reload_task) = ReloadManager::new(
+            self.database.clone(),
+            scheduler.spawn(),
+            default_limits,
+            capacity,
+            self.assignments.clone(),
+            stats_tx,
+            health,
+        )
+        .spawn();
+        reload_tx.send(ReloadRequest::Monitors).await?;
+        let cluster_task =
+            cluster.map(|cluster| cluster.spawn(settings_rx.clone(), reload_tx.clone()));
+        let api_task = match (&self.config.api.bind, &api_tls) {
+            (Some(bind), Some(tls)) => {
+                let token = self.config.api.token.clone();
+                // The API changes monitors, so it is never served without authentication
+                if token.is_none() && !tls.as_ref().is_some_and(|tls| tls.requires_client_certs()) {
+                    warn!(
+                        "Not serving the management API on {}: set [api] token or [api_tls] \
+                         client_ca_path",
+                        bind
+                    );
+                    None
+                } else {
+                    let database = self.database.clone();
+                    let serving = api::serve(
+                        bind,
+                        tls.clone(),
+                        token,
+                        database,
+                        reload_tx.clone(),
+                        heartbeats_tx,
+                    );
+                    match serving.await {
+                        Ok(task) => Some(task),
+                        Err(e) => {
+                            warn!("Failed to serve the management API on {}: {}", bind, e);
+                            None
                         }
-
-                        last_stats_persist = Instant::now();
                     }
-
-                    // Log the result
-                    info!(
-                        "Monitor {} - {} - Status: {} - Latency: {:?}ms",
-                        signed_result.monitor_id,
-                        signed_result.target,
-                        signed_result.status,
-                        signed_result.latency_ms
-                    );
                 }
+            }
+            _ => None,
+        };
 
-                // Handle P2P events
-                Some(p2p_event) = p2p_network.next_event() => {
-                    use crate::p2p::P2PEvent;
-                    match p2p_event {
-                        P2PEvent::ResultReceived { peer_id, result } => {
-                            info!("Received monitoring result from peer {}", peer_id);
-
-                            total_peers_seen.insert(peer_id.clone());
-
-                            // Convert P2P result to database model
-                            if let Some(mut db_result) = crate::database::models::PeerResult::from_p2p_result(&result) {
-                                // Verify signature if public key is available
-                                let verified = if let Some(public_key_vec) = &result.public_key {
-                                    if public_key_vec.len() == 32 {
-                                        let mut public_key_bytes = [0u8; 32];
-                                        public_key_bytes.copy_from_slice(&public_key_vec[..32]);
-
-                                        // Verify the signature
-                                        match verify_result(&db_result, &public_key_bytes, &result.result.target) {
-                                            Ok(true) => {
-                                                info!("Successfully verified signature from peer {}", peer_id);
-                                                true
-                                            }
-                                            Ok(false) => {
-                                                warn!("Invalid signature from peer {}", peer_id);
-                                                false
-                                            }
-                                            Err(e) => {
-                                                error!("Signature verification error from peer {}: {}", peer_id, e);
-                                                false
-                                            }
-                                        }
-                                    } else {
-                                        warn!("Invalid public key length from peer {}: {} bytes", peer_id, public_key_vec.len());
-                                        false
-                                    }
-                                } else {
-                                    warn!("Received peer result without public key from {}", peer_id);
-                                    false
-                                };
-
-                                db_result.verified = verified;
-
-                                // Keep peer record fresh when results arrive
-                                let peer_model = Peer::new_online(peer_id.clone(), SystemTime::now());
-                                if let Err(e) = self.database.upsert_peer(&peer_model).await {
-                                    warn!("Failed to upsert peer {} on result: {}", peer_id, e);
-                                }
-
-                                if let Err(e) = self.database.save_peer_result(&db_result).await {
-                                    error!("Failed to save peer result: {}", e);
-                                } else {
-                                    let status = if verified { "verified" } else { "unverified" };
-                                    debug!("Successfully saved {} peer result from {}", status, peer_id);
-                                }
-
-                                // Update stats for received results
-                                checks_received += 1;
-                            } else {
-                                warn!("Received peer result without signature from {}", peer_id);
-                            }
-                        }
-                        P2PEvent::PeerConnected(peer_id) => {
-                            info!("Peer connected: {}", peer_id);
-
-                            let now = SystemTime::now();
-                            connected_peers.insert(peer_id.clone());
-                            total_peers_seen.insert(peer_id.clone());
+        info!("Orchestrator started successfully - processing monitoring results");
 
-                            let peer_model = Peer::new_online(peer_id.clone(), now);
-                            if let Err(e) = self.database.upsert_peer(&peer_model).await {
-                                warn!("Failed to upsert peer {}: {}", peer_id, e);
-                            }
-                        }
-                        P2PEvent::PeerDisconnected(peer_id) => {
-                            info!("Peer disconnected: {}", peer_id);
+        let pipeline_finished = tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                info!("Shutdown signal received, stopping orchestrator");
+                false
+            }
+            _ = &mut pipeline_task => {
+                info!("All channels closed, shutting down orchestrator");
+                true
+            }
+        };
 
-                            connected_peers.remove(&peer_id);
-                            if let Err(e) = self.database.mark_peer_offline(&peer_id, SystemTime::now()).await {
-                                warn!("Failed to mark peer offline {}: {}", peer_id, e);
-                            }
-                        }
-                        P2PEvent::Started { peer_id } => {
-                            info!("P2P network started with peer ID: {}", peer_id);
-                        }
-                        P2PEvent::Error(err) => {
-                            error!("P2P error: {}", err);
-                        }
-                        _ => {
-                            tracing::trace!("P2P event: {:?}", p2p_event);
-                        }
-                    }
+        // No more monitor changes; the API holds a sender of the reload channel
+        if let Some(task) = api_task {
+            task.abort();
+            let _ = task.await;
+        }
 
-                    if last_stats_persist.elapsed() >= stats_persist_interval {
-                        let snapshot = NetworkStats {
-                            timestamp: SystemTime::now(),
-                            total_peers: total_peers_seen.len() as i64,
-                            online_peers: connected_peers.len() as i64,
-                            checks_performed,
-                            checks_received,
-                            bandwidth_used_mb: 0,
-                        };
-
-                        if let Err(e) = self.database.insert_network_stats(&snapshot).await {
-                            warn!("Failed to persist network stats: {}", e);
-                        }
+        // Dropping the settings sender ends the tasks that follow it
+        watcher_task.abort();
+        let _ = watcher_task.await;
+        let _ = executor_updates_task.await;
+        let _ = retention_task.await;
+        if let Some(task) = pool_task {
+            let _ = task.await;
+        }
+        let _ = agreement_task.await;
+        let _ = visibility_task.await;
+        let _ = maintenance_task.await;
+        let _ = follow_task.await;
+        let _ = region_task.await;
+        let _ = community_task.await;
+        let _ = heartbeat_task.await;
+        let _ = duties_task.await;
+        let _ = recruiter_task.await;
+        if let Some(task) = fanout_task {
+            let _ = task.await;
+        }
+        if let Some(task) = results_sync_task {
+            let _ = task.await;
+        }
+        if let Some(task) = dht_debug_task {
+            let _ = task.await;
+        }
+        if let Some(task) = cluster_task {
+            let _ = task.await;
+        }
 
-                        last_stats_persist = Instant::now();
-                    }
-                }
+        // Stopping the scheduler and agents closes the result channel, which
+        // ends the pipeline
+        if let Some(task) = agent_results_task {
+            task.abort();
+        }
+        drop(reload_tx);
+        let _ = reload_task.await;
+        if !pipeline_finished {
+            let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, pipeline_task).await;
+        }
 
-                else => {
-                    info!("All channels closed, shutting down orchestrator");
-                    break;
-                }
-            }
+        // A sync round can be interrupted at any point; it reruns on the next start
+        if let Some(task) = owner_sync_task {
+            task.abort();
+        }
+        if let Err(e) = self.p2p_network.shutdown().await {
+            warn!("Failed to shut down P2P network: {}", e);
+        }
+        if let Some(task) = peer_events_task {
+            let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, task).await;
+        }
+        let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, audit_task).await;
+        let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, stats_task).await;
+        if let Some(task) = remote_write_task {
+            let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, task).await;
+        }
+        for task in influx_tasks {
+            let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, task).await;
+        }
+        if let Some(task) = mqtt_task {
+            let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, task).await;
+        }
+        if let Some(task) = alerts_task {
+            let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, task).await;
+        }
+        if let Some(task) = incidents_task {
+            let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, task).await;
+        }
+        if let Some(task) = health_task {
+            task.abort();
+        }
+        if let Some(task) = status_page_task {
+            task.abort();
+        }
+        if let Some(task) = reports_task {
+            task.abort();
+        }
+        if let Some(task) = bus_task {
+            task.abort();
         }
 
         Ok(())
     }
 }
+
+/// Parse the peer IDs of an allow or block list, skipping invalid ones
+fn parse_peer_ids(peer_ids: &[String], list: &str) -> Vec<peerup::PeerId> {
+    peer_ids
+        .iter()
+        .filter_map(|peer_id| match peer_id.parse() {
+            Ok(peer) => Some(peer),
+            Err(e) => {
+                warn!("Ignoring invalid {} peer {}: {}", list, peer_id, e);
+                None
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,230 @@
+/// MQTT - publishes monitor state changes and latency to a broker
+///
+/// The pipeline hands every stored result to the publisher. When a monitor's
+/// status differs from the last one seen, including the first result after
+/// start, a retained JSON state goes to its state topic, so subscribers such
+/// as Home Assistant see the current state as soon as they connect. Every
+/// result's latency goes to the latency topic. A last will flips the
+/// availability topic to "offline" when the service disappears.
+use anyhow::{Context, Result};
+use rumqttc::{AsyncClient, EventLoop, LastWill, MqttOptions, QoS};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::config::MqttConfig;
+use crate::database::Database;
+use crate::database::models::Monitor;
+use crate::monitoring::CheckResult;
+use crate::monitoring::types::MonitorStatus;
+
+const DEFAULT_PORT: u16 = 1883;
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+/// Wait before reconnecting after the connection to the broker failed
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Retained payload of a state topic
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct StatePayload {
+    monitor: Uuid,
+    name: String,
+    target: String,
+    status: MonitorStatus,
+    /// Status before the change; `None` for the first result after start
+    previous: Option<MonitorStatus>,
+    latency_ms: Option<u64>,
+    error: Option<String>,
+    /// Unix seconds of the result
+    at: i64,
+}
+
+/// Task publishing results to the MQTT broker
+pub struct MqttPublisher {
+    database: Arc<dyn Database>,
+    config: MqttConfig,
+    client: AsyncClient,
+    /// Taken by the connection task on spawn
+    event_loop: Option<EventLoop>,
+    /// Last status seen per monitor
+    last_status: HashMap<Uuid, MonitorStatus>,
+}
+
+impl MqttPublisher {
+    /// Create a publisher if a broker is configured
+    pub fn new(database: Arc<dyn Database>, config: &MqttConfig) -> Result<Option<Self>> {
+        let Some(broker) = &config.broker else {
+            return Ok(None);
+        };
+        let (host, port) = parse_broker(broker)?;
+
+        let mut options = MqttOptions::new(config.client_id.clone(), host, port);
+        options.set_keep_alive(KEEP_ALIVE);
+        if let Some(username) = &config.username {
+            options.set_credentials(username, config.password.clone().unwrap_or_default());
+        }
+        if !config.availability_topic.is_empty() {
+            options.set_last_will(LastWill::new(
+                &config.availability_topic,
+                "offline",
+                QoS::AtLeastOnce,
+                true,
+            ));
+        }
+        let (client, event_loop) = AsyncClient::new(options, 64);
+
+        Ok(Some(Self {
+            database,
+            config: config.clone(),
+            client,
+            event_loop: Some(event_loop),
+            last_status: HashMap::new(),
+        }))
+    }
+
+    /// Spawn the publisher, returning the sender the pipeline feeds it through
+    ///
+    /// The task marks the service offline and exits once the sender is dropped.
+    pub fn spawn(mut self) -> (mpsc::Sender<CheckResult>, JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::channel::<CheckResult>(256);
+
+        let mut event_loop = self.event_loop.take().expect("publisher spawned once");
+        let availability_topic = self.config.availability_topic.clone();
+        let announcer = self.client.clone();
+        let mut connection = tokio::spawn(async move {
+            loop {
+                match event_loop.poll().await {
+                    // Announce again after every (re)connect, the will may have fired
+                    Ok(rumqttc::Event::Incoming(rumqttc::Packet::ConnAck(_))) => {
+                        debug!("Connected to MQTT broker");
+                        if !availability_topic.is_empty() {
+                            let _ = announcer.try_publish(
+                                &availability_topic,
+                                QoS::AtLeastOnce,
+                                true,
+                                "online",
+                            );
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        warn!("MQTT connection failed, retrying: {}", e);
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                }
+            }
+        });
+
+        let handle = tokio::spawn(async move {
+            while let Some(result) = rx.recv().await {
+                if let Err(e) = self.publish(&result).await {
+                    debug!("Failed to publish result for {} to MQTT: {}", result.target, e);
+                }
+            }
+
+            if !self.config.availability_topic.is_empty() {
+                let _ = self
+                    .client
+                    .publish(&self.config.availability_topic, QoS::AtLeastOnce, true, "offline")
+                    .await;
+            }
+            let _ = self.client.disconnect().await;
+            // Give the event loop a moment to send what is queued
+            let _ = tokio::time::timeout(Duration::from_secs(2), &mut connection).await;
+            connection.abort();
+            debug!("MQTT publisher stopped");
+        });
+
+        (tx, handle)
+    }
+
+    async fn publish(&mut self, result: &CheckResult) -> Result<()> {
+        let previous = self.last_status.insert(result.monitor_id, result.status);
+
+        if previous != Some(result.status) && !self.config.state_topic.is_empty() {
+            let monitor = self.database.get_monitor_by_uuid(result.monitor_id).await?;
+            let payload = StatePayload {
+                monitor: result.monitor_id,
+                name: monitor.map(|m| m.name).unwrap_or_default(),
+                target: result.target.clone(),
+                status: result.status,
+                previous,
+                latency_ms: result.latency_ms,
+                error: result.error_message.clone(),
+                at: Monitor::timestamp_to_i64(result.timestamp),
+            };
+            let topic = topic(&self.config.state_topic, result.monitor_id);
+            self.client
+                .publish(topic, QoS::AtLeastOnce, true, serde_json::to_vec(&payload)?)
+                .await?;
+        }
+
+        if let Some(ms) = result.latency_ms
+            && !self.config.latency_topic.is_empty()
+        {
+            let topic = topic(&self.config.latency_topic, result.monitor_id);
+            self.client.publish(topic, QoS::AtMostOnce, false, ms.to_string()).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Host and port of a broker given as "host", "host:port" or "mqtt://host:port"
+fn parse_broker(broker: &str) -> Result<(String, u16)> {
+    let address = broker.strip_prefix("mqtt://").unwrap_or(broker);
+    match address.rsplit_once(':') {
+        Some((host, port)) => {
+            let port =
+                port.parse().with_context(|| format!("Invalid MQTT broker port in {broker}"))?;
+            Ok((host.to_string(), port))
+        }
+        None => Ok((address.to_string(), DEFAULT_PORT)),
+    }
+}
+
+/// Topic for a monitor from a configured template
+fn topic(template: &str, monitor: Uuid) -> String {
+    template.replace("{monitor}", &monitor.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_broker() {
+        assert_eq!(parse_broker("localhost").unwrap(), ("localhost".to_string(), 1883));
+        assert_eq!(parse_broker("10.0.0.5:8883").unwrap(), ("10.0.0.5".to_string(), 8883));
+        assert_eq!(
+            parse_broker("mqtt://broker.lan:1884").unwrap(),
+            ("broker.lan".to_string(), 1884)
+        );
+        assert!(parse_broker("broker.lan:mqtt").is_err());
+    }
+
+    #[test]
+    fn test_topic_and_payload() {
+        let uuid = Uuid::new_v4();
+        assert_eq!(topic("home/uppe/{monitor}/state", uuid), format!("home/uppe/{uuid}/state"));
+
+        let payload = StatePayload {
+            monitor: uuid,
+            name: "API".to_string(),
+            target: "https://api.example.com".to_string(),
+            status: MonitorStatus::Down,
+            previous: Some(MonitorStatus::Up),
+            latency_ms: None,
+            error: Some("timeout".to_string()),
+            at: 1_700_000_000,
+        };
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["status"], "down");
+        assert_eq!(json["previous"], "up");
+        assert_eq!(json["monitor"], uuid.to_string());
+    }
+}
@@ -0,0 +1,288 @@
+/// Owner sync - backfills results for our monitors from the DHT
+///
+/// Peers store the latest result they shared for each monitor in the DHT.
+/// Every 12 hours the sync looks those records up for all of our monitors so
+/// results checked while this node was offline still end up in the database.
+/// Up to `MAX_IN_FLIGHT` lookups run at a time. A round that fails is retried
+/// with exponential backoff. Progress is written to the `owner_sync_state`
+/// table, where the TUI picks it up.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::database::models::{MonitorVisibility, OwnerSyncState};
+use crate::p2p::P2PHandle;
+
+/// Minimum time between successful sync rounds
+const OWNER_SYNC_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+/// Delay before retrying after the first failed round
+const INITIAL_BACKOFF: Duration = Duration::from_secs(60);
+/// Longest delay between retries
+const MAX_BACKOFF: Duration = Duration::from_secs(2 * 60 * 60);
+/// Time given to the node to find peers before the first round
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+/// How long a single monitor's lookup may take
+const FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+/// Lookups running at the same time
+const MAX_IN_FLIGHT: usize = 8;
+
+/// Outcome of one monitor's DHT lookup
+#[derive(Debug, Clone)]
+pub struct FetchOutcome {
+    pub monitor_id: Uuid,
+    pub records: usize,
+    pub error: Option<String>,
+}
+
+/// Whether enough time passed since the last successful sync to run another
+pub fn should_sync_owner_results(last_sync: Option<SystemTime>, now: SystemTime) -> bool {
+    match last_sync {
+        Some(last) => now.duration_since(last).map(|d| d >= OWNER_SYNC_INTERVAL).unwrap_or(false),
+        None => true,
+    }
+}
+
+/// Delay before the next attempt after `failures` consecutive failed rounds
+fn backoff_delay(failures: u32) -> Duration {
+    let exponent = failures.saturating_sub(1).min(16);
+    INITIAL_BACKOFF.saturating_mul(1 << exponent).min(MAX_BACKOFF)
+}
+
+/// Task running owner sync rounds
+pub struct OwnerSync {
+    database: Arc<dyn Database>,
+    p2p: P2PHandle,
+    outcomes: mpsc::Receiver<FetchOutcome>,
+}
+
+impl OwnerSync {
+    /// Create the sync task, returning the sender lookup outcomes are reported on
+    pub fn new(database: Arc<dyn Database>, p2p: P2PHandle) -> (Self, mpsc::Sender<FetchOutcome>) {
+        let (tx, outcomes) = mpsc::channel(100);
+        (Self { database, p2p, outcomes }, tx)
+    }
+
+    /// Spawn the sync; it stops once the outcome sender is dropped
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut state = match self.database.get_owner_sync_state().await {
+                Ok(state) => state.unwrap_or_default(),
+                Err(e) => {
+                    warn!("Failed to load owner sync state: {}", e);
+                    OwnerSyncState::default()
+                }
+            };
+            // A round interrupted by a shutdown is simply run again
+            if state.running {
+                state.running = false;
+                self.save(&state).await;
+            }
+
+            let mut first_round = true;
+            loop {
+                let mut delay = next_attempt(&state, SystemTime::now());
+                if first_round {
+                    delay = delay.max(STARTUP_DELAY);
+                    first_round = false;
+                }
+                if !delay.is_zero() {
+                    debug!("Next owner sync in {}s", delay.as_secs());
+                }
+
+                let sleep = tokio::time::sleep(delay);
+                tokio::pin!(sleep);
+                loop {
+                    tokio::select! {
+                        _ = &mut sleep => break,
+                        outcome = self.outcomes.recv() => match outcome {
+                            // Late outcome from a lookup that already timed out
+                            Some(outcome) => debug!("Ignoring stale owner sync outcome for {}", outcome.monitor_id),
+                            None => return,
+                        },
+                    }
+                }
+
+                if !self.run_round(&mut state).await {
+                    return;
+                }
+            }
+        })
+    }
+
+    /// Run one sync round, returning false if the task should stop
+    async fn run_round(&mut self, state: &mut OwnerSyncState) -> bool {
+        // Results of private monitors are not stored in the DHT
+        let monitors = match self.database.get_enabled_monitors().await {
+            Ok(monitors) => monitors
+                .into_iter()
+                .filter(|monitor| monitor.visibility == MonitorVisibility::Public)
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                self.finish(state, Some(format!("Failed to load monitors: {e}"))).await;
+                return true;
+            }
+        };
+
+        info!("Syncing results for {} monitors from the DHT", monitors.len());
+        state.running = true;
+        state.monitors_total = monitors.len() as u64;
+        state.monitors_synced = 0;
+        state.records_found = 0;
+        state.last_started_at = Some(SystemTime::now());
+        state.next_attempt_at = None;
+        self.save(state).await;
+
+        // Once a lookup fails no new ones start, but those running finish
+        let mut error = None;
+        let mut queue = monitors.into_iter().map(|monitor| monitor.uuid);
+        let mut in_flight: HashMap<Uuid, Instant> = HashMap::new();
+        loop {
+            while error.is_none() && in_flight.len() < MAX_IN_FLIGHT {
+                let Some(monitor_id) = queue.next() else {
+                    break;
+                };
+                match self.p2p.fetch_owner_results(monitor_id).await {
+                    Ok(()) => {
+                        in_flight.insert(monitor_id, Instant::now() + FETCH_TIMEOUT);
+                    }
+                    Err(e) => error = Some(format!("Monitor {monitor_id}: {e}")),
+                }
+            }
+            let Some(deadline) = in_flight.values().min().copied() else {
+                break;
+            };
+
+            tokio::select! {
+                outcome = self.outcomes.recv() => {
+                    let Some(outcome) = outcome else {
+                        return false;
+                    };
+                    if in_flight.remove(&outcome.monitor_id).is_none() {
+                        debug!("Ignoring stale owner sync outcome for {}", outcome.monitor_id);
+                        continue;
+                    }
+                    match outcome.error {
+                        Some(e) => {
+                            error.get_or_insert(format!("Monitor {}: {}", outcome.monitor_id, e));
+                        }
+                        None => {
+                            state.monitors_synced += 1;
+                            state.records_found += outcome.records as u64;
+                            self.save(state).await;
+                        }
+                    }
+                }
+                _ = tokio::time::sleep_until(deadline) => {
+                    let now = Instant::now();
+                    in_flight.retain(|monitor_id, deadline| {
+                        if *deadline > now {
+                            return true;
+                        }
+                        error.get_or_insert(format!("Monitor {monitor_id}: DHT lookup timed out"));
+                        false
+                    });
+                }
+            }
+        }
+
+        self.finish(state, error).await;
+        true
+    }
+
+    /// Record the end of a round and schedule the next one
+    async fn finish(&self, state: &mut OwnerSyncState, error: Option<String>) {
+        let now = SystemTime::now();
+        state.running = false;
+
+        match &error {
+            Some(e) => {
+                state.failures += 1;
+                let delay = backoff_delay(state.failures);
+                warn!(
+                    "Owner sync failed ({} in a row), retrying in {}s: {}",
+                    state.failures,
+                    delay.as_secs(),
+                    e
+                );
+                state.next_attempt_at = Some(now + delay);
+            }
+            None => {
+                info!(
+                    "Owner sync finished: {} results found for {} monitors",
+                    state.records_found, state.monitors_synced
+                );
+                state.failures = 0;
+                state.last_completed_at = Some(now);
+                state.next_attempt_at = Some(now + OWNER_SYNC_INTERVAL);
+            }
+        }
+        state.last_error = error;
+        self.save(state).await;
+    }
+
+    async fn save(&self, state: &OwnerSyncState) {
+        if let Err(e) = self.database.save_owner_sync_state(state).await {
+            warn!("Failed to save owner sync state: {}", e);
+        }
+    }
+}
+
+/// Time to wait before the next round
+fn next_attempt(state: &OwnerSyncState, now: SystemTime) -> Duration {
+    if state.failures > 0 {
+        return state
+            .next_attempt_at
+            .and_then(|at| at.duration_since(now).ok())
+            .unwrap_or_default();
+    }
+    if should_sync_owner_results(state.last_completed_at, now) {
+        return Duration::ZERO;
+    }
+
+    state
+        .last_completed_at
+        .map(|last| last + OWNER_SYNC_INTERVAL)
+        .and_then(|at| at.duration_since(now).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owner_sync_rate_limit() {
+        let now = SystemTime::now();
+        assert!(should_sync_owner_results(None, now));
+        assert!(!should_sync_owner_results(Some(now - Duration::from_secs(3600)), now));
+        assert!(should_sync_owner_results(Some(now - OWNER_SYNC_INTERVAL), now));
+
+        let state = OwnerSyncState {
+            last_completed_at: Some(now - Duration::from_secs(2 * 60 * 60)),
+            ..OwnerSyncState::default()
+        };
+        assert_eq!(next_attempt(&state, now), Duration::from_secs(10 * 60 * 60));
+    }
+
+    #[test]
+    fn test_owner_sync_backoff() {
+        assert_eq!(backoff_delay(1), INITIAL_BACKOFF);
+        assert_eq!(backoff_delay(2), INITIAL_BACKOFF * 2);
+        assert_eq!(backoff_delay(4), INITIAL_BACKOFF * 8);
+        assert_eq!(backoff_delay(50), MAX_BACKOFF);
+
+        let now = SystemTime::now();
+        let state = OwnerSyncState {
+            failures: 2,
+            next_attempt_at: Some(now + Duration::from_secs(120)),
+            ..OwnerSyncState::default()
+        };
+        assert_eq!(next_attempt(&state, now), Duration::from_secs(120));
+    }
+}
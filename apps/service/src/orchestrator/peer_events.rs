@@ -0,0 +1,962 @@
+/// Peer event handler - verifies and stores results and peer state from the P2P layer
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use super::assignments::SharedAssignments;
+use super::audit::AuditLog;
+use super::capacity::SharedCapacity;
+use super::community::{CommunityGroups, contributing_host};
+use super::dedup::SharedPublishSchedule;
+use super::dht_debug::DhtOutcome;
+use super::fanout::ProbeEvent;
+use super::follow::{FollowedHosts, followed_host};
+use super::health::ServiceHealth;
+use super::metrics::ServiceMetrics;
+use super::owner_sync::FetchOutcome;
+use super::results_sync::SyncEvent;
+use super::runtime::RuntimeSettings;
+use super::stats::StatsEvent;
+use crate::crypto::{
+    KeyPair, decrypt_result, verify_assignment_request, verify_location_claim,
+    verify_membership_announcement, verify_monitor_retraction, verify_peer_result, verify_result,
+};
+use crate::database::Database;
+use crate::database::models::{AuditKind, Follow, GroupMembership, Peer};
+use crate::journal::{Journal, JournalEntry};
+use crate::location::Location;
+use crate::p2p::{
+    EncryptedResultMessage, HelperAssignmentRequest, MembershipAnnouncement, MonitorRetraction,
+    P2PEvent, P2PHandle, PeerResult,
+};
+use crate::policy::ProbePolicy;
+use crate::queue::QueueReceiver;
+
+/// How long the list of banned peers is cached before it is reloaded
+const BAN_REFRESH: Duration = Duration::from_secs(30);
+/// How far in the future a membership announcement may be dated, allowing
+/// for clock skew
+const MEMBERSHIP_MAX_SKEW: Duration = Duration::from_secs(5 * 60);
+
+/// Peers an operator banned, as last loaded from the database
+#[derive(Default)]
+struct BannedPeers {
+    peer_ids: HashSet<String>,
+    loaded_at: Option<Instant>,
+}
+
+/// Task consuming events emitted by the P2P node
+pub struct PeerEventHandler {
+    database: Arc<dyn Database>,
+    keypair: Arc<KeyPair>,
+    policy: Arc<ProbePolicy>,
+    capacity: SharedCapacity,
+    settings: watch::Receiver<RuntimeSettings>,
+    stats_tx: mpsc::Sender<StatsEvent>,
+    audit: AuditLog,
+    /// Store accepted assignments are kept in; without one assignment
+    /// requests and helper results are ignored
+    assignments: Option<SharedAssignments>,
+    /// Where DHT lookup outcomes for the owner sync are forwarded
+    owner_sync: Option<mpsc::Sender<FetchOutcome>>,
+    /// Where the node's running state is reported
+    health: Option<ServiceHealth>,
+    /// Publish schedule told which peers check which monitors
+    schedule: Option<SharedPublishSchedule>,
+    /// Journal accepted assignments are recorded in until stored
+    journal: Option<Arc<Journal>>,
+    /// Where probe requests and answers are forwarded
+    probes: Option<mpsc::Sender<ProbeEvent>>,
+    /// Results sync task, told about connected peers and sync requests
+    results_sync: Option<mpsc::Sender<SyncEvent>>,
+    /// Where outcomes of DHT debug operations are forwarded
+    dht_debug: Option<mpsc::Sender<DhtOutcome>>,
+    /// Where sealed messages from our cluster's topic are forwarded
+    cluster: Option<mpsc::Sender<Vec<u8>>>,
+    /// Metrics the DHT queries and routing table are reported to
+    metrics: Option<ServiceMetrics>,
+    /// Followed hosts, whose peer monitors are recorded as results arrive
+    follows: Option<FollowedHosts>,
+    /// Participants of community domains, whose results count towards their
+    /// contribution score
+    community: Option<CommunityGroups>,
+    /// Node told to block banned peers at the swarm
+    p2p: Option<P2PHandle>,
+    banned: Mutex<BannedPeers>,
+}
+
+impl PeerEventHandler {
+    /// Create a new peer event handler
+    pub fn new(
+        database: Arc<dyn Database>,
+        keypair: Arc<KeyPair>,
+        policy: Arc<ProbePolicy>,
+        capacity: SharedCapacity,
+        settings: watch::Receiver<RuntimeSettings>,
+        stats_tx: mpsc::Sender<StatsEvent>,
+        audit: AuditLog,
+    ) -> Self {
+        Self {
+            database,
+            keypair,
+            policy,
+            capacity,
+            settings,
+            stats_tx,
+            audit,
+            assignments: None,
+            owner_sync: None,
+            health: None,
+            schedule: None,
+            journal: None,
+            probes: None,
+            results_sync: None,
+            dht_debug: None,
+            cluster: None,
+            metrics: None,
+            follows: None,
+            community: None,
+            p2p: None,
+            banned: Mutex::default(),
+        }
+    }
+
+    /// Keep accepted assignments in the given store and check helper results
+    /// against it
+    pub fn with_assignments(mut self, assignments: SharedAssignments) -> Self {
+        self.assignments = Some(assignments);
+        self
+    }
+
+    /// Forward DHT lookup outcomes to the owner sync task
+    pub fn with_owner_sync(mut self, owner_sync: mpsc::Sender<FetchOutcome>) -> Self {
+        self.owner_sync = Some(owner_sync);
+        self
+    }
+
+    /// Report the node's running state to the health endpoints
+    pub fn with_health(mut self, health: ServiceHealth) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// Tell the publish schedule about our cluster's nodes checking the same
+    /// monitors as us
+    pub fn with_publish_schedule(mut self, schedule: SharedPublishSchedule) -> Self {
+        self.schedule = Some(schedule);
+        self
+    }
+
+    /// Record accepted assignments in the journal until they are stored
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Forward probe requests and answers to the probe fan-out
+    pub fn with_probe_fanout(mut self, probes: mpsc::Sender<ProbeEvent>) -> Self {
+        self.probes = Some(probes);
+        self
+    }
+
+    /// Forward connected peers and results sync requests and pages to the
+    /// results sync
+    pub fn with_results_sync(mut self, results_sync: mpsc::Sender<SyncEvent>) -> Self {
+        self.results_sync = Some(results_sync);
+        self
+    }
+
+    /// Forward outcomes of DHT debug operations to the task running them
+    pub fn with_dht_debug(mut self, dht_debug: mpsc::Sender<DhtOutcome>) -> Self {
+        self.dht_debug = Some(dht_debug);
+        self
+    }
+
+    /// Forward messages from our cluster's topic to the cluster sync
+    pub fn with_cluster(mut self, cluster: mpsc::Sender<Vec<u8>>) -> Self {
+        self.cluster = Some(cluster);
+        self
+    }
+
+    /// Report DHT queries and the routing table size to the metrics endpoint
+    pub fn with_metrics(mut self, metrics: ServiceMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Record which peer monitors check the followed hosts
+    pub fn with_follows(mut self, follows: FollowedHosts) -> Self {
+        self.follows = Some(follows);
+        self
+    }
+
+    /// Track community domain memberships and credit participants' results
+    pub fn with_community(mut self, groups: CommunityGroups) -> Self {
+        self.community = Some(groups);
+        self
+    }
+
+    /// Block banned peers at the swarm as well, so their connections are
+    /// refused instead of only their messages dropped
+    pub fn with_p2p(mut self, p2p: P2PHandle) -> Self {
+        self.p2p = Some(p2p);
+        self
+    }
+
+    /// Spawn the handler; it stops once the P2P event channel closes
+    pub fn spawn(self, mut event_rx: QueueReceiver<P2PEvent>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(event) = event_rx.recv().await {
+                self.handle(event).await;
+            }
+
+            // The channel closes once the node's event loop has stopped
+            if let Some(health) = &self.health {
+                health.set_p2p_running(false);
+            }
+
+            debug!("Peer event handler stopped");
+        })
+    }
+
+    async fn handle(&self, event: P2PEvent) {
+        if let Some(peer_id) = sender(&event)
+            && self.is_banned(peer_id).await
+        {
+            debug!("Dropping event from banned peer {}", peer_id);
+            return;
+        }
+        if is_gossip(&event) && self.settings.borrow().faults.drop_gossip() {
+            debug!("Dropping gossip message (injected fault)");
+            return;
+        }
+
+        match event {
+            P2PEvent::ResultReceived { peer_id, result } => {
+                if self.settings.borrow().p2p_sharing {
+                    self.handle_result(peer_id, &result).await;
+                }
+            }
+            P2PEvent::ResultBackfilled(result) => {
+                if self.settings.borrow().p2p_sharing {
+                    self.handle_backfilled_result(&result).await;
+                }
+            }
+            P2PEvent::OwnerResultsFetched { monitor_id, records, mut error } => {
+                if self.settings.borrow().faults.fail_dht() {
+                    error = Some("Injected DHT failure".to_string());
+                }
+                if let Some(metrics) = &self.metrics {
+                    metrics.record_dht_query(records, error.is_some());
+                }
+                if let Some(owner_sync) = &self.owner_sync {
+                    let _ = owner_sync.send(FetchOutcome { monitor_id, records, error }).await;
+                }
+            }
+            P2PEvent::EncryptedResultReceived { peer_id, message } => {
+                if message.owner_peer_id == self.keypair.public_key_hex() {
+                    self.handle_encrypted_result(peer_id, &message).await;
+                } else {
+                    tracing::trace!(
+                        "Ignoring encrypted result for owner {}",
+                        message.owner_peer_id
+                    );
+                }
+            }
+            P2PEvent::HelperAssignmentRequested { peer_id, request, owner_bound } => {
+                if request.helper_peer_id == self.keypair.public_key_hex() {
+                    self.handle_assignment_request(peer_id, &request, owner_bound).await;
+                }
+            }
+            P2PEvent::MonitorRetracted { peer_id, retraction } => {
+                self.handle_retraction(peer_id, &retraction).await;
+            }
+            P2PEvent::MembershipAnnounced { peer_id, announcement } => {
+                self.handle_membership(peer_id, &announcement).await;
+            }
+            P2PEvent::ProbeRequested { peer_id, inbound_id, request } => {
+                self.forward_probe(ProbeEvent::Requested { peer_id, inbound_id, request }).await;
+            }
+            P2PEvent::ProbeAnswered { probe_id, peer_id, response } => {
+                self.forward_probe(ProbeEvent::Answered { probe_id, peer_id, response }).await;
+            }
+            P2PEvent::ProbeFailed { probe_id, peer_id, error } => {
+                self.forward_probe(ProbeEvent::Failed { probe_id, peer_id, error }).await;
+            }
+            P2PEvent::ResultsSyncRequested { peer_id, inbound_id, request } => {
+                self.forward_sync(SyncEvent::Requested { peer_id, inbound_id, request }).await;
+            }
+            P2PEvent::ResultsSyncAnswered { sync_id, peer_id, response } => {
+                self.forward_sync(SyncEvent::Answered { sync_id, peer_id, response }).await;
+            }
+            P2PEvent::ResultsSyncFailed { sync_id, peer_id, error } => {
+                self.forward_sync(SyncEvent::Failed { sync_id, peer_id, error }).await;
+            }
+            P2PEvent::DhtOperationFinished { operation_id, records, error } => {
+                if let Some(metrics) = &self.metrics {
+                    metrics.record_dht_query(records.len(), error.is_some());
+                }
+                if let Some(dht_debug) = &self.dht_debug {
+                    let _ = dht_debug.send(DhtOutcome { operation_id, records, error }).await;
+                }
+            }
+            P2PEvent::ClusterMessageReceived { peer_id, data } => match &self.cluster {
+                Some(cluster) => {
+                    let _ = cluster.send(data).await;
+                }
+                None => debug!("Ignoring cluster message relayed by {}", peer_id),
+            },
+            P2PEvent::PeerConnected { peer_id, address } => {
+                info!("Peer connected: {}", peer_id);
+
+                let peer_model = Peer::new_online(peer_id.clone(), SystemTime::now());
+                if let Err(e) = self.database.upsert_peer(&peer_model).await {
+                    warn!("Failed to upsert peer {}: {}", peer_id, e);
+                }
+                if let Some(address) = address
+                    && let Err(e) = self.database.record_peer_address(&peer_id, &address).await
+                {
+                    warn!("Failed to record address of peer {}: {}", peer_id, e);
+                }
+                self.forward_sync(SyncEvent::PeerConnected(peer_id.clone())).await;
+                let _ = self.stats_tx.send(StatsEvent::PeerConnected(peer_id)).await;
+            }
+            P2PEvent::PeerGreylisted { peer_id, duration, offences } => {
+                // The penalty also records a publisher we never connected to
+                if let Err(e) =
+                    self.database.add_peer_contribution(&peer_id, -Peer::GREYLIST_PENALTY).await
+                {
+                    warn!("Failed to lower the score of peer {}: {}", peer_id, e);
+                }
+                if let Err(e) =
+                    self.database.record_peer_greylisted(&peer_id, SystemTime::now()).await
+                {
+                    warn!("Failed to record greylisting of peer {}: {}", peer_id, e);
+                }
+                self.audit
+                    .record(
+                        AuditKind::RateLimited,
+                        Some(&peer_id),
+                        format!(
+                            "Greylisted peer for {}s after repeatedly exceeding its message rate \
+                             (offence {})",
+                            duration.as_secs(),
+                            offences
+                        ),
+                    )
+                    .await;
+            }
+            P2PEvent::PeerDisconnected(peer_id) => {
+                info!("Peer disconnected: {}", peer_id);
+
+                if let Err(e) = self.database.mark_peer_offline(&peer_id, SystemTime::now()).await {
+                    warn!("Failed to mark peer offline {}: {}", peer_id, e);
+                }
+                let _ = self.stats_tx.send(StatsEvent::PeerDisconnected(peer_id)).await;
+            }
+            P2PEvent::Started { peer_id } => {
+                info!("P2P network started with peer ID: {}", peer_id);
+                if let Some(health) = &self.health {
+                    health.set_p2p_running(true);
+                }
+            }
+            P2PEvent::NodeMetrics(metrics) => {
+                let total_bytes = metrics.total_bytes();
+                let _ = self.stats_tx.send(StatsEvent::Traffic { total_bytes }).await;
+                if let Some(service_metrics) = &self.metrics {
+                    service_metrics.set_routing_table_size(metrics.routing_table_size);
+                }
+
+                debug!(
+                    "P2P metrics: {} connected, {} routing entries, {} pending queries, {} B out \
+                     / {} B in",
+                    metrics.connected_peers,
+                    metrics.routing_table_size,
+                    metrics.pending_queries,
+                    metrics.bytes_published,
+                    metrics.bytes_received
+                );
+            }
+            P2PEvent::SeenMessages { lookups, hits } => {
+                let _ = self.stats_tx.send(StatsEvent::SeenMessages { lookups, hits }).await;
+            }
+            P2PEvent::Error(err) => {
+                error!("P2P error: {}", err);
+            }
+            other => {
+                tracing::trace!("P2P event: {:?}", other);
+            }
+        }
+    }
+
+    /// Whether an operator banned a peer; the ban list is reloaded every
+    /// `BAN_REFRESH` so bans set through the CLI or TUI take effect, and
+    /// changes to it are passed on to the node
+    async fn is_banned(&self, peer_id: &str) -> bool {
+        let stale = self
+            .banned
+            .lock()
+            .unwrap()
+            .loaded_at
+            .is_none_or(|at| at.elapsed() >= BAN_REFRESH);
+        if stale {
+            let loaded = self.database.get_banned_peer_ids().await;
+            let mut changes = Vec::new();
+            {
+                let mut banned = self.banned.lock().unwrap();
+                match loaded {
+                    Ok(peer_ids) => {
+                        let peer_ids: HashSet<String> = peer_ids.into_iter().collect();
+                        changes.extend(
+                            peer_ids.difference(&banned.peer_ids).map(|p| (p.clone(), true)),
+                        );
+                        changes.extend(
+                            banned.peer_ids.difference(&peer_ids).map(|p| (p.clone(), false)),
+                        );
+                        banned.peer_ids = peer_ids;
+                    }
+                    Err(e) => warn!("Failed to load banned peers: {}", e),
+                }
+                banned.loaded_at = Some(Instant::now());
+            }
+
+            if let Some(p2p) = &self.p2p {
+                for (peer, blocked) in changes {
+                    if let Err(e) = p2p.block_peer(peer.clone(), blocked).await {
+                        warn!("Failed to update the block on peer {}: {}", peer, e);
+                    }
+                }
+            }
+        }
+
+        self.banned.lock().unwrap().peer_ids.contains(peer_id)
+    }
+
+    async fn forward_probe(&self, event: ProbeEvent) {
+        match &self.probes {
+            Some(probes) => {
+                let _ = probes.send(event).await;
+            }
+            None => tracing::trace!("Ignoring probe event: {:?}", event),
+        }
+    }
+
+    async fn forward_sync(&self, event: SyncEvent) {
+        match &self.results_sync {
+            Some(results_sync) => {
+                let _ = results_sync.send(event).await;
+            }
+            None => tracing::trace!("Ignoring results sync event: {:?}", event),
+        }
+    }
+
+    /// Verify and store a result received from a peer
+    async fn handle_result(&self, peer_id: String, result: &PeerResult) {
+        info!("Received monitoring result from peer {}", peer_id);
+
+        // Convert P2P result to database model
+        let Some(mut db_result) = crate::database::models::PeerResult::from_p2p_result(result)
+        else {
+            warn!("Received peer result without signature from {}", peer_id);
+            return;
+        };
+
+        // Gossip results were checked by the decoder, backfilled ones are
+        // checked here
+        let outcome = match result.signature_valid {
+            Some(valid) => Ok(valid),
+            None => verify_peer_result(result),
+        };
+        // A result published by a node bound to another key is not attributed
+        // to the key it names, however it was signed
+        let outcome = match (outcome, result.identity_bound) {
+            (Ok(true), Some(false)) => {
+                self.audit
+                    .record(
+                        AuditKind::SignatureFailure,
+                        Some(&peer_id),
+                        format!(
+                            "Result for monitor {} published by a node not bound to {}",
+                            db_result.monitor_uuid, result.peer_id
+                        ),
+                    )
+                    .await;
+                Ok(None)
+            }
+            (outcome, _) => outcome.map(Some),
+        };
+        let verified = match outcome {
+            Ok(Some(true)) => {
+                info!("Successfully verified signature from peer {}", peer_id);
+                true
+            }
+            Ok(None) => false,
+            Ok(Some(false)) => {
+                self.audit
+                    .record(
+                        AuditKind::SignatureFailure,
+                        Some(&peer_id),
+                        format!("Invalid result signature for monitor {}", db_result.monitor_uuid),
+                    )
+                    .await;
+                false
+            }
+            Err(e) => {
+                warn!("Could not verify result from peer {}: {}", peer_id, e);
+                false
+            }
+        };
+
+        db_result.verified = verified;
+        db_result.source_peer_id = Some(peer_id.clone());
+
+        // Only signed results can hold a publishing slot
+        if verified && let Some(schedule) = &self.schedule {
+            schedule.lock().unwrap().observe(
+                result.result.monitor_id,
+                &result.result.target,
+                &db_result.peer_id,
+                Instant::now(),
+            );
+        }
+
+        // Only trust a location the result's signer vouched for
+        let location = if verified { self.claimed_location(&peer_id, result).await } else { None };
+        if let Some(location) = &location {
+            db_result.city = location.city.clone();
+            db_result.country = location.country.clone();
+            db_result.region = location.region.clone();
+        }
+
+        // Keep peer record fresh when results arrive
+        let mut peer_model = Peer::new_online(peer_id.clone(), SystemTime::now());
+        if let Some(location) = location {
+            peer_model.location_city = location.city;
+            peer_model.location_country = location.country;
+            peer_model.location_region = location.region;
+        }
+        if let Err(e) = self.database.upsert_peer(&peer_model).await {
+            warn!("Failed to upsert peer {} on result: {}", peer_id, e);
+        }
+
+        let faults = self.settings.borrow().faults;
+        faults.delay_db_write().await;
+        if let Err(e) = self.database.save_peer_result(&db_result).await {
+            error!("Failed to save peer result: {}", e);
+        } else {
+            let status = if verified { "verified" } else { "unverified" };
+            debug!("Successfully saved {} peer result from {}", status, peer_id);
+        }
+
+        // Only signed results can tie a peer monitor to a followed host
+        if verified
+            && let Some(follows) = &self.follows
+            && let Some(host) = followed_host(follows, &result.result.target)
+            && let Err(e) = self.database.add_follow_monitor(&host, db_result.monitor_uuid).await
+        {
+            warn!("Failed to record monitor checking followed host {}: {}", host, e);
+        }
+
+        // Signed results of a community domain count towards their signer's
+        // contribution while it takes part
+        if verified
+            && let Some(groups) = &self.community
+            && let Some(host) = contributing_host(groups, &result.result.target, &result.peer_id)
+            && let Err(e) = self
+                .database
+                .add_peer_contribution(&result.peer_id, Peer::CONTRIBUTION_PER_CHECK)
+                .await
+        {
+            warn!("Failed to credit {} for checking {}: {}", result.peer_id, host, e);
+        }
+
+        let _ = self.stats_tx.send(StatsEvent::CheckReceived { peer_id }).await;
+    }
+
+    /// Store a result found in the DHT unless it already arrived over gossip
+    async fn handle_backfilled_result(&self, result: &PeerResult) {
+        match self
+            .database
+            .has_peer_result(result.result.monitor_id, &result.peer_id, result.result.timestamp)
+            .await
+        {
+            Ok(true) => {
+                debug!("Backfilled result for {} already stored", result.result.monitor_id);
+            }
+            Ok(false) => self.handle_result(result.peer_id.clone(), result).await,
+            Err(e) => warn!("Failed to look up backfilled result: {}", e),
+        }
+    }
+
+    /// The location a peer signed for its result, if the claim checks out
+    async fn claimed_location(&self, peer_id: &str, result: &PeerResult) -> Option<Location> {
+        let claim = result.location.as_ref()?;
+        let public_key = <[u8; 32]>::try_from(result.public_key.as_deref()?).ok()?;
+
+        match verify_location_claim(claim, &public_key) {
+            Ok(true) if claim.peer_id == result.peer_id => Some(claim.location.clone()),
+            Ok(_) => {
+                self.audit
+                    .record(
+                        AuditKind::SignatureFailure,
+                        Some(peer_id),
+                        "Invalid or stale location claim".to_string(),
+                    )
+                    .await;
+                None
+            }
+            Err(e) => {
+                warn!("Location claim verification error from peer {}: {}", peer_id, e);
+                None
+            }
+        }
+    }
+
+    /// Decrypt a result a helper sealed for us and store it with our own results
+    ///
+    /// Only results under an assignment we made, from the helper and for the
+    /// monitor it names, are taken.
+    async fn handle_encrypted_result(&self, peer_id: String, message: &EncryptedResultMessage) {
+        let Some(assignments) = &self.assignments else {
+            return;
+        };
+        let assigned =
+            assignments.lock().await.owner_assignment(message.assignment_id).is_some_and(
+                |assignment| {
+                    assignment.helper_peer_id == message.helper_peer_id
+                        && assignment.monitor_uuid == message.monitor_id
+                },
+            );
+        if !assigned {
+            debug!(
+                "Ignoring encrypted result from {} for unknown assignment {}",
+                message.helper_peer_id, message.assignment_id
+            );
+            return;
+        }
+
+        let result = match decrypt_result(&message.sealed, &self.keypair) {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Failed to decrypt result from helper {}: {}", message.helper_peer_id, e);
+                return;
+            }
+        };
+
+        if result.monitor_id != message.monitor_id || result.peer_id != message.helper_peer_id {
+            warn!("Encrypted result from {} does not match its envelope", message.helper_peer_id);
+            return;
+        }
+
+        // The helper signs the result before sealing it
+        let helper_key = hex::decode(&message.helper_peer_id)
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok());
+        let p2p_result = PeerResult {
+            result: result.clone(),
+            signature: result.signature.clone(),
+            public_key: helper_key.map(|key| key.to_vec()),
+            peer_id: result.peer_id.clone(),
+            location: None,
+            received_at: SystemTime::now(),
+            signature_valid: None,
+            identity_bound: None,
+        };
+        let verified =
+            match (helper_key, crate::database::models::PeerResult::from_p2p_result(&p2p_result)) {
+                (Some(key), Some(db_result)) => {
+                    verify_result(&db_result, &key, &result.target).unwrap_or(false)
+                }
+                _ => false,
+            };
+
+        if !verified {
+            self.audit
+                .record(
+                    AuditKind::SignatureFailure,
+                    Some(&message.helper_peer_id),
+                    format!(
+                        "Dropped encrypted result with invalid signature for monitor {}",
+                        message.monitor_id
+                    ),
+                )
+                .await;
+            return;
+        }
+
+        if let Err(e) = self.database.save_result(&result).await {
+            error!("Failed to save helper result for monitor {}: {}", result.monitor_id, e);
+            return;
+        }
+
+        if let Err(e) = assignments
+            .lock()
+            .await
+            .record_result(message.assignment_id, SystemTime::now())
+            .await
+        {
+            warn!("Failed to record result for assignment {}: {}", message.assignment_id, e);
+        }
+
+        debug!(
+            "Stored helper result for monitor {} from {} (assignment {})",
+            result.monitor_id, message.helper_peer_id, message.assignment_id
+        );
+        let _ = self.stats_tx.send(StatsEvent::CheckReceived { peer_id }).await;
+    }
+
+    /// Drop helper duties for a monitor its owner made private and mark the
+    /// results the owner shared for it as retracted
+    async fn handle_retraction(&self, peer_id: String, retraction: &MonitorRetraction) {
+        match verify_monitor_retraction(retraction) {
+            Ok(true) => {}
+            Ok(false) => {
+                self.audit
+                    .record(
+                        AuditKind::SignatureFailure,
+                        Some(&peer_id),
+                        format!(
+                            "Ignored retraction of monitor {}: not signed by owner {}",
+                            retraction.monitor_id, retraction.owner_peer_id
+                        ),
+                    )
+                    .await;
+                return;
+            }
+            Err(e) => {
+                warn!("Ignoring malformed retraction of {}: {}", retraction.monitor_id, e);
+                return;
+            }
+        }
+
+        if let Some(assignments) = &self.assignments {
+            let mut assignments = assignments.lock().await;
+            let retracted: Vec<Uuid> = assignments
+                .helper_duties()
+                .filter(|assignment| {
+                    assignment.monitor_uuid == retraction.monitor_id
+                        && assignment.owner_peer_id == retraction.owner_peer_id
+                })
+                .map(|assignment| assignment.assignment_id)
+                .collect();
+            for assignment_id in retracted {
+                if let Err(e) = assignments.remove(assignment_id).await {
+                    warn!("Failed to drop helper assignment {}: {}", assignment_id, e);
+                }
+            }
+        }
+
+        match self
+            .database
+            .retract_peer_results(retraction.monitor_id, &retraction.owner_peer_id)
+            .await
+        {
+            Ok(retracted) => info!(
+                "Owner {} made monitor {} private, retracted {} result(s)",
+                retraction.owner_peer_id, retraction.monitor_id, retracted
+            ),
+            Err(e) => warn!("Failed to retract results of {}: {}", retraction.monitor_id, e),
+        }
+    }
+
+    /// Record a peer joining or leaving a community domain, if the peer
+    /// signed the announcement itself and no later one is stored
+    async fn handle_membership(&self, peer_id: String, announcement: &MembershipAnnouncement) {
+        match verify_membership_announcement(announcement) {
+            Ok(true) => {}
+            Ok(false) => {
+                self.audit
+                    .record(
+                        AuditKind::SignatureFailure,
+                        Some(&peer_id),
+                        format!(
+                            "Ignored membership announcement for {}: not signed by {}",
+                            announcement.host, announcement.peer_id
+                        ),
+                    )
+                    .await;
+                return;
+            }
+            Err(e) => {
+                warn!(
+                    "Ignoring malformed membership announcement for {}: {}",
+                    announcement.host, e
+                );
+                return;
+            }
+        }
+
+        // A host that isn't normalized would split the group, and an
+        // announcement dated ahead would outlive the peer's later ones; one
+        // dated past what a clock holds is no date at all
+        let normalized =
+            Follow::host_of(&announcement.host).as_deref() == Some(announcement.host.as_str());
+        let Some(announced_at) = SystemTime::UNIX_EPOCH
+            .checked_add(Duration::from_secs(announcement.announced_at))
+            .filter(|at| normalized && *at <= SystemTime::now() + MEMBERSHIP_MAX_SKEW)
+        else {
+            debug!("Ignoring membership announcement for {} from {}", announcement.host, peer_id);
+            return;
+        };
+
+        let membership = GroupMembership {
+            host: announcement.host.clone(),
+            peer_id: announcement.peer_id.clone(),
+            joined: announcement.joined,
+            announced_at,
+        };
+        match self.database.save_group_membership(&membership).await {
+            Ok(true) => {
+                if let Some(groups) = &self.community {
+                    let mut groups = groups.write().unwrap();
+                    let peers = groups.entry(membership.host.clone()).or_default();
+                    if membership.joined {
+                        peers.insert(membership.peer_id.clone());
+                    } else {
+                        peers.remove(&membership.peer_id);
+                    }
+                }
+                info!(
+                    "Peer {} {} monitoring {}",
+                    membership.peer_id,
+                    if membership.joined { "joined" } else { "left" },
+                    membership.host
+                );
+            }
+            Ok(false) => debug!("Ignoring stale membership announcement for {}", membership.host),
+            Err(e) => warn!("Failed to store membership for {}: {}", membership.host, e),
+        }
+    }
+
+    /// Accept an assignment request only if the claimed owner signed it and
+    /// published it from their own node, the target passes our probe policy
+    /// and we have capacity left
+    async fn handle_assignment_request(
+        &self,
+        peer_id: String,
+        request: &HelperAssignmentRequest,
+        owner_bound: bool,
+    ) {
+        if !owner_bound {
+            self.audit
+                .record(
+                    AuditKind::SignatureFailure,
+                    Some(&peer_id),
+                    format!(
+                        "Rejected assignment {}: not published by a node bound to owner {}",
+                        request.assignment_id, request.owner_peer_id
+                    ),
+                )
+                .await;
+            return;
+        }
+
+        match verify_assignment_request(request) {
+            Ok(true) => {}
+            Ok(false) => {
+                self.audit
+                    .record(
+                        AuditKind::SignatureFailure,
+                        Some(&peer_id),
+                        format!(
+                            "Rejected assignment {}: not signed by owner {}",
+                            request.assignment_id, request.owner_peer_id
+                        ),
+                    )
+                    .await;
+                return;
+            }
+            Err(e) => {
+                warn!("Rejecting malformed assignment {}: {}", request.assignment_id, e);
+                return;
+            }
+        }
+
+        let Some(assignment) = request.to_helper_assignment(SystemTime::now()) else {
+            warn!("Rejecting assignment {} with an invalid expiry", request.assignment_id);
+            return;
+        };
+
+        if let Err(e) = self.policy.check(&request.target, &request.check_type).await {
+            self.audit
+                .record(
+                    AuditKind::PolicyRejected,
+                    Some(&request.owner_peer_id),
+                    format!("Rejected assignment {}: {}", request.assignment_id, e),
+                )
+                .await;
+            return;
+        }
+
+        let Some(assignments) = &self.assignments else {
+            return;
+        };
+        let mut assignments = assignments.lock().await;
+        let active = assignments.active_duties(SystemTime::now());
+        let has_room = self.capacity.lock().unwrap().has_room(active);
+        if !has_room {
+            self.audit
+                .record(
+                    AuditKind::RateLimited,
+                    Some(&request.owner_peer_id),
+                    format!(
+                        "Rejected assignment {}: at capacity ({} active)",
+                        request.assignment_id, active
+                    ),
+                )
+                .await;
+            return;
+        }
+
+        let journal_id = self
+            .journal
+            .as_ref()
+            .map(|journal| journal.begin(JournalEntry::Assignment(assignment.clone())));
+        if let Err(e) = assignments.upsert(assignment).await {
+            error!("Failed to store helper assignment {}: {}", request.assignment_id, e);
+            return;
+        }
+        if let (Some(journal), Some(id)) = (&self.journal, journal_id) {
+            journal.done(id);
+        }
+
+        let utilization = self.capacity.lock().unwrap().utilization(active + 1, Instant::now());
+        let _ = self.stats_tx.send(StatsEvent::HelperUtilization(utilization)).await;
+
+        info!(
+            "Accepted helper assignment {} for monitor {} from owner {}",
+            request.assignment_id, request.monitor_id, request.owner_peer_id
+        );
+    }
+}
+
+/// Peer that sent work or results we act on, for events dropped from banned peers
+fn sender(event: &P2PEvent) -> Option<&str> {
+    match event {
+        P2PEvent::ResultReceived { peer_id, .. }
+        | P2PEvent::EncryptedResultReceived { peer_id, .. }
+        | P2PEvent::HelperAssignmentRequested { peer_id, .. }
+        | P2PEvent::MembershipAnnounced { peer_id, .. }
+        | P2PEvent::ProbeRequested { peer_id, .. }
+        | P2PEvent::ResultsSyncRequested { peer_id, .. }
+        | P2PEvent::ResultsSyncAnswered { peer_id, .. } => Some(peer_id),
+        _ => None,
+    }
+}
+
+/// Messages peers gossip to us, which fault injection may drop
+fn is_gossip(event: &P2PEvent) -> bool {
+    matches!(
+        event,
+        P2PEvent::ResultReceived { .. }
+            | P2PEvent::EncryptedResultReceived { .. }
+            | P2PEvent::HelperAssignmentRequested { .. }
+            | P2PEvent::MonitorRetracted { .. }
+            | P2PEvent::MembershipAnnounced { .. }
+            | P2PEvent::ClusterMessageReceived { .. }
+    )
+}
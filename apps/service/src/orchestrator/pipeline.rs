@@ -0,0 +1,271 @@
+/// Result pipeline - signs, stores and shares local monitoring results
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use super::bandwidth::BandwidthBudget;
+use super::dedup::{PublishSchedule, SharedPublishSchedule};
+use super::maintenance::{MaintenanceWindows, in_maintenance};
+use super::metrics::ServiceMetrics;
+use super::runtime::RuntimeSettings;
+use super::stats::StatsEvent;
+use super::visibility::{PrivateMonitors, is_shareable};
+use crate::bus::{BusEvent, BusPublisher};
+use crate::crypto::{KeyPair, sign_location_claim, sign_result};
+use crate::database::Database;
+use crate::journal::Journal;
+use crate::location::LocationClaim;
+use crate::monitoring::CheckResult;
+use crate::monitoring::types::MonitorStatus;
+use crate::p2p::P2PHandle;
+use crate::queue::QueueReceiver;
+use crate::redact::ErrorRedactor;
+
+/// How often the pipeline checks whether the location needs updating
+const LOCATION_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Task processing results produced by the local scheduler
+pub struct ResultPipeline {
+    database: Arc<dyn Database>,
+    keypair: Arc<KeyPair>,
+    p2p: P2PHandle,
+    settings: watch::Receiver<RuntimeSettings>,
+    budget: BandwidthBudget,
+    stats_tx: mpsc::Sender<StatsEvent>,
+    private: PrivateMonitors,
+    /// Maintenance windows results are checked against
+    maintenance: MaintenanceWindows,
+    schedule: SharedPublishSchedule,
+    /// Journal the scheduler recorded results in
+    journal: Option<Arc<Journal>>,
+    /// Bus the TUI listens on for stored results
+    bus: Option<Arc<BusPublisher>>,
+    /// Exporters fed with every stored result
+    sinks: Vec<mpsc::Sender<CheckResult>>,
+    /// Metrics served to Prometheus
+    metrics: Option<ServiceMetrics>,
+    /// Last status shared per monitor, so status changes still go out when throttled
+    last_status: HashMap<Uuid, MonitorStatus>,
+    /// Redacts error messages in shared results
+    redactor: Arc<ErrorRedactor>,
+}
+
+impl ResultPipeline {
+    /// Create a new result pipeline
+    pub fn new(
+        database: Arc<dyn Database>,
+        keypair: Arc<KeyPair>,
+        p2p: P2PHandle,
+        settings: watch::Receiver<RuntimeSettings>,
+        budget: BandwidthBudget,
+        stats_tx: mpsc::Sender<StatsEvent>,
+        private: PrivateMonitors,
+    ) -> Self {
+        let schedule = PublishSchedule::shared(keypair.public_key_hex());
+        Self {
+            database,
+            keypair,
+            p2p,
+            settings,
+            budget,
+            stats_tx,
+            private,
+            maintenance: MaintenanceWindows::default(),
+            schedule,
+            journal: None,
+            bus: None,
+            sinks: Vec::new(),
+            metrics: None,
+            last_status: HashMap::new(),
+            redactor: Arc::new(ErrorRedactor::default()),
+        }
+    }
+
+    /// Share the publish schedule with the peer event handler, which tells it
+    /// about our cluster's nodes checking the same monitors
+    pub fn with_publish_schedule(mut self, schedule: SharedPublishSchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// Mark results checked during the given maintenance windows
+    pub fn with_maintenance(mut self, maintenance: MaintenanceWindows) -> Self {
+        self.maintenance = maintenance;
+        self
+    }
+
+    /// Redact error messages in shared results with the operator's rules
+    pub fn with_redactor(mut self, redactor: Arc<ErrorRedactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Mark results in the journal once they are stored
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Announce stored results on the bus
+    pub fn with_bus(mut self, bus: Arc<BusPublisher>) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Hand stored results to an exporter; results are dropped while it is behind
+    pub fn with_sink(mut self, sink: mpsc::Sender<CheckResult>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Report the status and latency of stored results to the metrics endpoint
+    pub fn with_metrics(mut self, metrics: ServiceMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Spawn the pipeline; it stops once every result sender is dropped
+    pub fn spawn(mut self, mut result_rx: QueueReceiver<CheckResult>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut last_location_check = Instant::now();
+
+            while let Some(result) = result_rx.recv().await {
+                // Periodically check if location needs updating (for mobile devices)
+                if last_location_check.elapsed() >= LOCATION_CHECK_INTERVAL {
+                    crate::location::check_and_update_location();
+                    last_location_check = Instant::now();
+                }
+
+                self.process(result).await;
+            }
+
+            debug!("Result pipeline stopped");
+        })
+    }
+
+    /// Sign, store and share a single result
+    async fn process(&mut self, mut result: CheckResult) {
+        result.maintenance = in_maintenance(&self.maintenance, result.monitor_id, result.timestamp);
+        // Agents sign their results themselves
+        let signed_result = if result.agent {
+            result
+        } else {
+            match sign_result(&result, &self.keypair) {
+                Ok(signature) => result.with_signature(signature),
+                Err(e) => {
+                    error!("Failed to sign result for monitor {}: {}", result.monitor_id, e);
+                    return;
+                }
+            }
+        };
+
+        let faults = self.settings.borrow().faults;
+        faults.delay_db_write().await;
+        match self.database.save_result(&signed_result).await {
+            Ok(_) => {
+                if let Some(journal) = &self.journal {
+                    journal.result_done(signed_result.monitor_id, signed_result.timestamp);
+                }
+                if let Some(bus) = &self.bus {
+                    bus.publish(&BusEvent::ResultStored {
+                        monitor_uuid: signed_result.monitor_id,
+                        status: signed_result.status,
+                    });
+                }
+                if let Some(metrics) = &self.metrics {
+                    metrics.record_result(&signed_result);
+                }
+                for sink in &self.sinks {
+                    if sink.try_send(signed_result.clone()).is_err() {
+                        debug!("Exporter behind, dropping result for {}", signed_result.target);
+                    }
+                }
+            }
+            // Left in the journal, so the next start tries again
+            Err(e) => error!("Failed to save result to database: {}", e),
+        }
+
+        let _ = self
+            .stats_tx
+            .send(StatsEvent::CheckPerformed {
+                status: signed_result.status,
+                latency_ms: signed_result.latency_ms,
+            })
+            .await;
+
+        if self.should_share(&signed_result) {
+            // The error message is not signed, so the shared copy is redacted
+            let mut shared = signed_result.clone();
+            shared.error_message = self.redactor.redact_opt(shared.error_message.as_deref());
+            match self.p2p.share_result(&shared, self.location_claim()).await {
+                Ok(()) => {
+                    let _ = self.stats_tx.send(StatsEvent::ResultShared).await;
+                }
+                Err(e) => error!("Failed to share result with P2P network: {}", e),
+            }
+        }
+
+        info!(
+            "Monitor {} - {} - Status: {} - Latency: {:?}ms",
+            signed_result.monitor_id,
+            signed_result.target,
+            signed_result.status,
+            signed_result.latency_ms
+        );
+    }
+
+    /// Signed claim of where this node checks from, if its location is known
+    fn location_claim(&self) -> Option<LocationClaim> {
+        let location = crate::location::get_location();
+        if !location.is_known() {
+            return None;
+        }
+
+        sign_location_claim(&location, &self.keypair, SystemTime::now())
+            .inspect_err(|e| warn!("Failed to sign location claim: {}", e))
+            .ok()
+    }
+
+    /// Whether a result should be published to peers
+    ///
+    /// Results of private monitors or checked during maintenance are never
+    /// shared, as peers would count them as outages, nor are results agents
+    /// submitted, which are not our checks to vouch for. Routine results are only
+    /// shared when the publish schedule picks us for their monitor and time
+    /// bucket, and not at all once the daily bandwidth budget is used up;
+    /// status changes are always shared.
+    fn should_share(&mut self, result: &CheckResult) -> bool {
+        if !self.settings.borrow().p2p_sharing
+            || result.agent
+            || !is_shareable(&self.private, result.monitor_id)
+        {
+            return false;
+        }
+        if result.maintenance {
+            debug!("Monitor {} in maintenance, not sharing result", result.monitor_id);
+            return false;
+        }
+
+        let previous = self.last_status.insert(result.monitor_id, result.status);
+        let status_changed = previous != Some(result.status);
+        if self.budget.is_exhausted() && !status_changed {
+            debug!("Bandwidth budget used, not sharing result for monitor {}", result.monitor_id);
+            return false;
+        }
+
+        let scheduled = self.schedule.lock().unwrap().should_publish(
+            &self.keypair.public_key_hex(),
+            result,
+            status_changed,
+            Instant::now(),
+        );
+        if !scheduled {
+            debug!("Result for {} already published this bucket, not sharing", result.target);
+        }
+        scheduled
+    }
+}
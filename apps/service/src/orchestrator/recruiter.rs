@@ -0,0 +1,257 @@
+/// Helper recruiting - asks the peers listed in `[helper] helpers` to check our
+/// private monitors
+///
+/// Every enabled private monitor gets one assignment per listed helper. Each
+/// assignment lapses after `ASSIGNMENT_TTL` and a fresh one is requested before
+/// then, so a helper that went away stops counting once its last assignment
+/// lapses. Requests are signed with our key, which helpers check before taking
+/// the duty on, and are sent again every round until the helper's first result
+/// confirms them. Assignments for monitors that were deleted, disabled, made
+/// public or changed, and for peers no longer listed, are dropped.
+use anyhow::Result;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::assignments::SharedAssignments;
+use super::runtime::RuntimeSettings;
+use crate::crypto::{KeyPair, sign_assignment_request};
+use crate::database::Database;
+use crate::database::models::{AssignmentRole, HelperAssignment, Monitor, MonitorVisibility};
+use crate::p2p::P2PHandle;
+
+/// How often assignments are requested, renewed and dropped
+pub(super) const ROUND: Duration = Duration::from_secs(5 * 60);
+/// How long an assignment lasts
+pub(super) const ASSIGNMENT_TTL: Duration = Duration::from_secs(60 * 60);
+/// How long before an assignment lapses its replacement is requested
+const RENEW_BEFORE: Duration = Duration::from_secs(20 * 60);
+
+/// Task keeping our private monitors assigned to the configured helpers
+pub struct HelperRecruiter {
+    database: Arc<dyn Database>,
+    assignments: SharedAssignments,
+    keypair: Arc<KeyPair>,
+    p2p: P2PHandle,
+    /// Public keys (hex) of the peers asked to help
+    helpers: Vec<String>,
+}
+
+impl HelperRecruiter {
+    /// Create a recruiter asking `helpers`; entries that are not public keys,
+    /// and our own key, are skipped
+    pub fn new(
+        database: Arc<dyn Database>,
+        assignments: SharedAssignments,
+        keypair: Arc<KeyPair>,
+        p2p: P2PHandle,
+        helpers: &[String],
+    ) -> Self {
+        let own = keypair.public_key_hex();
+        let mut listed = HashSet::new();
+        let helpers = helpers
+            .iter()
+            .map(|helper| helper.trim().to_lowercase())
+            .filter(|helper| {
+                let is_key = hex::decode(helper).is_ok_and(|bytes| bytes.len() == 32);
+                if !is_key {
+                    warn!("Ignoring helper {}: not an Ed25519 public key in hex", helper);
+                }
+                is_key && *helper != own && listed.insert(helper.clone())
+            })
+            .collect();
+        Self { database, assignments, keypair, p2p, helpers }
+    }
+
+    /// Spawn the task; it stops once the settings channel closes
+    pub fn spawn(self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(ROUND);
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => {}
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+
+                // Requests only reach helpers while sharing with peers
+                if !settings.borrow().p2p_sharing {
+                    continue;
+                }
+                if let Err(e) = self.recruit().await {
+                    warn!("Failed to update helper assignments: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Drop stale assignments and request the missing and unconfirmed ones
+    async fn recruit(&self) -> Result<()> {
+        let monitors: Vec<Monitor> = self
+            .database
+            .get_monitors()
+            .await?
+            .into_iter()
+            .filter(|monitor| monitor.enabled && monitor.visibility == MonitorVisibility::Private)
+            .collect();
+
+        let mut assignments = self.assignments.lock().await;
+        let now = SystemTime::now();
+        let round = plan(
+            &monitors,
+            &self.helpers,
+            assignments.owner_assignments(),
+            &self.keypair.public_key_hex(),
+            now,
+        );
+
+        for assignment_id in &round.stale {
+            assignments.remove(*assignment_id).await?;
+        }
+        let mut requested = 0;
+        for assignment in round.requests {
+            let request = sign_assignment_request(&assignment, &self.keypair)?;
+            if assignments.owner_assignment(assignment.assignment_id).is_none() {
+                assignments.upsert(assignment).await?;
+                requested += 1;
+            }
+            self.p2p.publish_assignment_request(request).await?;
+        }
+
+        if requested > 0 || !round.stale.is_empty() {
+            info!(
+                "Requested {} helper assignment(s), dropped {} stale",
+                requested,
+                round.stale.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// What a round changes about our owner-side assignments
+#[derive(Debug, Default)]
+pub(super) struct Round {
+    /// Assignments to drop
+    pub(super) stale: Vec<Uuid>,
+    /// Assignments to request: new ones, and known ones not yet confirmed
+    pub(super) requests: Vec<HelperAssignment>,
+}
+
+/// Compare the assignments we hold with the ones `monitors` and `helpers` call for
+pub(super) fn plan<'a>(
+    monitors: &[Monitor],
+    helpers: &[String],
+    held: impl Iterator<Item = &'a HelperAssignment>,
+    owner_peer_id: &str,
+    now: SystemTime,
+) -> Round {
+    let mut round = Round::default();
+    let mut covered = HashSet::new();
+
+    for assignment in held {
+        let wanted = monitors.iter().any(|monitor| {
+            monitor.uuid == assignment.monitor_uuid
+                && monitor.target == assignment.target
+                && monitor.check_type == assignment.check_type
+                && monitor.interval_seconds == assignment.interval_seconds
+        }) && helpers.contains(&assignment.helper_peer_id);
+        if !wanted || assignment.is_expired(now) {
+            round.stale.push(assignment.assignment_id);
+            continue;
+        }
+
+        // Close to lapsing, it stays valid while its replacement is requested
+        if assignment.is_expired(now + RENEW_BEFORE) {
+            continue;
+        }
+        covered.insert((assignment.monitor_uuid, assignment.helper_peer_id.as_str()));
+        if !assignment.is_accepted() {
+            round.requests.push(assignment.clone());
+        }
+    }
+
+    for monitor in monitors {
+        for helper in helpers {
+            if covered.contains(&(monitor.uuid, helper.as_str())) {
+                continue;
+            }
+            round.requests.push(HelperAssignment {
+                assignment_id: Uuid::new_v4(),
+                role: AssignmentRole::Owner,
+                monitor_uuid: monitor.uuid,
+                owner_peer_id: owner_peer_id.to_string(),
+                helper_peer_id: helper.clone(),
+                target: monitor.target.clone(),
+                check_type: monitor.check_type.clone(),
+                interval_seconds: monitor.interval_seconds,
+                status: "pending".to_string(),
+                created_at: now,
+                updated_at: now,
+                expires_at: Some(now + ASSIGNMENT_TTL),
+                last_result_at: None,
+            });
+        }
+    }
+    round
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn private_monitor() -> Monitor {
+        let mut monitor =
+            Monitor::new("Intranet".to_string(), "https://example.com".to_string(), "https".into());
+        monitor.visibility = MonitorVisibility::Private;
+        monitor
+    }
+
+    #[test]
+    fn test_plan_requests_renews_and_drops() {
+        let now = SystemTime::now();
+        let monitors = [private_monitor()];
+        let helpers = vec!["a".to_string(), "b".to_string()];
+
+        // Nothing held yet: one request per helper
+        let first = plan(&monitors, &helpers, std::iter::empty(), "owner", now);
+        assert!(first.stale.is_empty());
+        assert_eq!(first.requests.len(), 2);
+        assert!(first.requests.iter().all(|a| a.role == AssignmentRole::Owner));
+
+        // Unconfirmed ones are sent again, confirmed ones are left alone
+        let mut held = first.requests.clone();
+        held[0].status = "accepted".to_string();
+        let again = plan(&monitors, &helpers, held.iter(), "owner", now);
+        assert!(again.stale.is_empty());
+        assert_eq!(again.requests.len(), 1);
+        assert_eq!(again.requests[0].assignment_id, held[1].assignment_id);
+
+        // A confirmed assignment close to lapsing gets a replacement
+        let later = now + ASSIGNMENT_TTL - RENEW_BEFORE / 2;
+        let renewed = plan(&monitors, &helpers[..1], held[..1].iter(), "owner", later);
+        assert!(renewed.stale.is_empty());
+        assert_eq!(renewed.requests.len(), 1);
+        assert_ne!(renewed.requests[0].assignment_id, held[0].assignment_id);
+
+        // Lapsed, delisted or changed assignments are dropped
+        let lapsed = plan(&monitors, &helpers, held.iter(), "owner", now + ASSIGNMENT_TTL);
+        assert_eq!(lapsed.stale.len(), 2);
+        let delisted = plan(&monitors, &helpers[..1], held.iter(), "owner", now);
+        assert_eq!(delisted.stale, vec![held[1].assignment_id]);
+        let mut changed = monitors[0].clone();
+        changed.target = "https://example.org".to_string();
+        let moved = plan(&[changed], &helpers, held.iter(), "owner", now);
+        assert_eq!(moved.stale.len(), 2);
+        assert_eq!(moved.requests.len(), 2);
+        assert!(plan(&[], &helpers, held.iter(), "owner", now).requests.is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+/// Region estimator - infers our region from round trips when nothing else
+/// tells it
+///
+/// While neither GeoIP nor the IP API resolved our location and none was
+/// configured, the estimator times TCP connects to anchors in known regions
+/// and to peers whose signed location claims we verified, and tags results
+/// with the region `location::infer_region` picks. A location looked up later
+/// takes over again.
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, info, warn};
+
+use super::runtime::RuntimeSettings;
+use crate::config::RegionAnchor;
+use crate::database::Database;
+use crate::location;
+
+/// How often the region is estimated again
+const ESTIMATE_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// How long a connect may take before the reference point is left out
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Connects timed per reference point, keeping the fastest
+const ATTEMPTS: usize = 3;
+/// Most peers timed per estimate
+const MAX_PEERS: usize = 16;
+
+/// Task keeping the inferred region up to date
+pub struct RegionEstimator {
+    database: Arc<dyn Database>,
+    /// `(host:port, region)` of the anchors
+    anchors: Vec<(String, String)>,
+}
+
+impl RegionEstimator {
+    /// Create an estimator timing the given anchors, or the built-in ones
+    /// when there are none
+    pub fn new(database: Arc<dyn Database>, anchors: &[RegionAnchor]) -> Self {
+        let anchors = if anchors.is_empty() {
+            location::DEFAULT_REGION_ANCHORS
+                .iter()
+                .map(|(target, region)| (target.to_string(), region.to_string()))
+                .collect()
+        } else {
+            anchors
+                .iter()
+                .map(|anchor| (anchor.target.clone(), anchor.region.clone()))
+                .collect()
+        };
+        Self { database, anchors }
+    }
+
+    /// Spawn the estimator; it stops once the settings channel closes
+    pub fn spawn(self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(ESTIMATE_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => {}
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+
+                if !location::needs_inferred_region() {
+                    continue;
+                }
+                let region = location::infer_region(&self.measure().await);
+                match &region {
+                    Some(region) => info!("Inferred region from round trips: {}", region),
+                    None => debug!("Round trips don't tell our region"),
+                }
+                location::set_inferred_region(region);
+            }
+        })
+    }
+
+    /// Time the reference points, returning `(region, rtt_ms)` of those that
+    /// answered
+    async fn measure(&self) -> Vec<(String, u64)> {
+        let mut targets = self.anchors.clone();
+        match self.database.get_online_peers().await {
+            Ok(peers) => targets.extend(
+                peers
+                    .into_iter()
+                    .filter_map(|peer| {
+                        let region = peer.location_region?;
+                        let addr = peer.addresses.iter().find_map(|addr| tcp_socket_addr(addr))?;
+                        Some((addr.to_string(), region))
+                    })
+                    .take(MAX_PEERS),
+            ),
+            Err(e) => warn!("Failed to load peers for region estimate: {}", e),
+        }
+
+        let mut timings = JoinSet::new();
+        for (target, region) in targets {
+            timings.spawn(async move { connect_rtt_ms(&target).await.map(|rtt| (region, rtt)) });
+        }
+        let mut samples = Vec::new();
+        while let Some(timing) = timings.join_next().await {
+            if let Ok(Some(sample)) = timing {
+                samples.push(sample);
+            }
+        }
+        samples
+    }
+}
+
+/// Fastest of `ATTEMPTS` TCP connects to `target`, in milliseconds
+async fn connect_rtt_ms(target: &str) -> Option<u64> {
+    let mut fastest = None;
+    for _ in 0..ATTEMPTS {
+        let start = Instant::now();
+        if let Ok(Ok(_)) = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(target)).await {
+            let rtt = start.elapsed().as_millis() as u64;
+            fastest = Some(fastest.map_or(rtt, |fastest: u64| fastest.min(rtt)));
+        }
+    }
+    fastest
+}
+
+/// Socket address of an `/ip4/<ip>/tcp/<port>` or `/ip6/<ip>/tcp/<port>`
+/// multiaddr; relayed addresses time the relay, not the peer
+fn tcp_socket_addr(addr: &str) -> Option<SocketAddr> {
+    if addr.contains("/p2p-circuit") {
+        return None;
+    }
+    let mut parts = addr.trim_start_matches('/').split('/');
+    let ip = match (parts.next()?, parts.next()?) {
+        ("ip4" | "ip6", ip) => ip.parse().ok()?,
+        _ => return None,
+    };
+    let port = match (parts.next()?, parts.next()?) {
+        ("tcp", port) => port.parse().ok()?,
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tcp_socket_addr() {
+        assert_eq!(
+            tcp_socket_addr("/ip4/203.0.113.7/tcp/4001/p2p/12D3KooW"),
+            Some("203.0.113.7:4001".parse().unwrap())
+        );
+        assert_eq!(
+            tcp_socket_addr("/ip6/2001:db8::1/tcp/4001"),
+            Some("[2001:db8::1]:4001".parse().unwrap())
+        );
+        assert_eq!(tcp_socket_addr("/ip4/203.0.113.7/udp/4001/quic-v1"), None);
+        assert_eq!(tcp_socket_addr("/dns4/example.com/tcp/4001"), None);
+        assert_eq!(tcp_socket_addr("/ip4/203.0.113.7/tcp/4001/p2p/12D3KooW/p2p-circuit"), None);
+    }
+}
@@ -0,0 +1,195 @@
+/// Reload manager - (re)loads monitors and helper limits from the database
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+
+use super::assignments::SharedAssignments;
+use super::capacity::{HelperLimits, SharedCapacity};
+use super::health::ServiceHealth;
+use super::stats::StatsEvent;
+use crate::database::Database;
+use crate::database::models::Monitor;
+use crate::monitoring::checker::CheckType;
+use crate::monitoring::scheduler::{MonitorConfig, SchedulerHandle, SchedulerStatus};
+
+/// Requests handled by the reload manager
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadRequest {
+    /// Reload enabled monitors from the database and hand them to the scheduler
+    Monitors,
+    /// Re-read helper capacity limits from the settings table
+    HelperLimits,
+}
+
+/// How often helper limits are re-read, and utilization and scheduler health reported
+const HELPER_LIMITS_INTERVAL: Duration = Duration::from_secs(30);
+/// How long past its next due time the scheduler loop may go without
+/// reporting before all its monitors count as overdue
+const SCHEDULER_STALL_AFTER: Duration = Duration::from_secs(60);
+
+/// Task owning the monitoring scheduler
+pub struct ReloadManager {
+    database: Arc<dyn Database>,
+    schedule_tx: mpsc::Sender<Vec<MonitorConfig>>,
+    scheduler_task: JoinHandle<()>,
+    scheduler_status: watch::Receiver<SchedulerStatus>,
+    /// Monitors last handed to the scheduler
+    scheduled: usize,
+    /// Limits from the config file, before settings overrides
+    default_limits: HelperLimits,
+    capacity: SharedCapacity,
+    assignments: SharedAssignments,
+    stats_tx: mpsc::Sender<StatsEvent>,
+    health: ServiceHealth,
+}
+
+impl ReloadManager {
+    /// Create a reload manager handing monitors to a spawned scheduler
+    pub fn new(
+        database: Arc<dyn Database>,
+        scheduler: SchedulerHandle,
+        default_limits: HelperLimits,
+        capacity: SharedCapacity,
+        assignments: SharedAssignments,
+        stats_tx: mpsc::Sender<StatsEvent>,
+        health: ServiceHealth,
+    ) -> Self {
+        Self {
+            database,
+            schedule_tx: scheduler.schedule_tx,
+            scheduler_task: scheduler.task,
+            scheduler_status: scheduler.status,
+            scheduled: 0,
+            default_limits,
+            capacity,
+            assignments,
+            stats_tx,
+            health,
+        }
+    }
+
+    /// Spawn the manager, returning the channel to request reloads on
+    ///
+    /// Helper limits are also reloaded on a fixed interval so settings changes
+    /// apply without a restart. When every sender is dropped the scheduler is
+    /// stopped and the task exits, which in turn closes the result channel.
+    pub fn spawn(mut self) -> (mpsc::Sender<ReloadRequest>, JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::channel::<ReloadRequest>(8);
+
+        let handle = tokio::spawn(async move {
+            let mut limits_timer = tokio::time::interval(HELPER_LIMITS_INTERVAL);
+
+            loop {
+                let request = tokio::select! {
+                    request = rx.recv() => match request {
+                        Some(request) => request,
+                        None => break,
+                    },
+                    _ = limits_timer.tick() => ReloadRequest::HelperLimits,
+                };
+
+                match request {
+                    ReloadRequest::Monitors => {
+                        if let Err(e) = self.reload_monitors().await {
+                            error!("Failed to reload monitors: {}", e);
+                        }
+                    }
+                    ReloadRequest::HelperLimits => {
+                        if let Err(e) = self.reload_helper_limits().await {
+                            error!("Failed to reload helper limits: {}", e);
+                        }
+                    }
+                }
+                self.report_health();
+            }
+
+            let Self { schedule_tx, scheduler_task, .. } = self;
+            drop(schedule_tx);
+            let _ = scheduler_task.await;
+            debug!("Reload manager stopped");
+        });
+
+        (tx, handle)
+    }
+
+    /// Hand the enabled monitors from the database to the scheduler, which
+    /// only reschedules the ones that changed
+    async fn reload_monitors(&mut self) -> anyhow::Result<()> {
+        info!("Loading monitors from database...");
+        let monitors = self.database.get_enabled_monitors().await?;
+        info!("Found {} enabled monitors", monitors.len());
+
+        // Heartbeat monitors are pinged rather than checked
+        let monitor_configs: Vec<MonitorConfig> = monitors
+            .into_iter()
+            .filter(|monitor| !monitor.is_heartbeat())
+            .map(monitor_config)
+            .collect();
+
+        let scheduled = monitor_configs.len();
+        self.schedule_tx
+            .send(monitor_configs)
+            .await
+            .map_err(|_| anyhow::anyhow!("Monitoring scheduler stopped"))?;
+        self.scheduled = scheduled;
+        Ok(())
+    }
+
+    /// Apply the current helper limits and report utilization
+    async fn reload_helper_limits(&mut self) -> anyhow::Result<()> {
+        let limits = self.default_limits.with_settings(self.database.as_ref()).await?;
+        let active = self.assignments.lock().await.active_duties(SystemTime::now());
+
+        let (changed, utilization) = {
+            let mut capacity = self.capacity.lock().unwrap();
+            (capacity.set_limits(limits), capacity.utilization(active, Instant::now()))
+        };
+        if changed {
+            info!(
+                "Helper limits: {} assignments, {} checks per owner per hour",
+                limits.max_assignments, limits.max_checks_per_owner_per_hour
+            );
+        }
+
+        let _ = self.stats_tx.send(StatsEvent::HelperUtilization(utilization)).await;
+        Ok(())
+    }
+
+    /// Report how many monitors are scheduled, none of which run once the
+    /// scheduler stopped
+    fn report_health(&self) {
+        let status = *self.scheduler_status.borrow();
+        let stalled = status.next_due.is_some_and(|due| {
+            tokio::time::Instant::now().saturating_duration_since(due) > SCHEDULER_STALL_AFTER
+        });
+        let overdue = if stalled { status.scheduled } else { status.overdue };
+        let running = if self.scheduler_task.is_finished() {
+            0
+        } else {
+            status.scheduled.saturating_sub(overdue)
+        };
+        self.health.report_scheduler(self.scheduled, running, status.in_flight, overdue);
+    }
+}
+
+/// Convert a database monitor to a scheduler config
+fn monitor_config(monitor: Monitor) -> MonitorConfig {
+    let check_type = match monitor.check_type.as_str() {
+        "http" => CheckType::Http,
+        "https" => CheckType::Https,
+        "tcp" => CheckType::Tcp,
+        "icmp" => CheckType::Icmp,
+        "cert_expiry" => CheckType::CertExpiry,
+        _ => CheckType::Http,
+    };
+
+    MonitorConfig {
+        id: monitor.uuid,
+        target: monitor.target,
+        check_type,
+        interval_seconds: monitor.interval_seconds,
+        enabled: monitor.enabled,
+    }
+}
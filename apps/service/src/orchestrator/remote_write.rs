@@ -0,0 +1,332 @@
+/// Remote write - pushes results and uptime gauges to a Prometheus-compatible TSDB
+///
+/// The pipeline hands every stored result to the exporter, which buffers them
+/// and pushes a remote-write 1.0 request (protobuf, snappy compressed) every
+/// `push_interval_secs`. Each push also carries every enabled monitor's
+/// uptime over the last day, taken from the result rollups. A failed push is
+/// retried with the next one; past `MAX_BUFFERED_RESULTS` the oldest results
+/// are dropped.
+use anyhow::Result;
+use prost::Message;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::config::RemoteWriteConfig;
+use crate::database::Database;
+use crate::monitoring::CheckResult;
+use crate::monitoring::types::MonitorStatus;
+
+/// How long a push may take
+const PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Results kept while the endpoint is unreachable
+const MAX_BUFFERED_RESULTS: usize = 10_000;
+/// Window of the uptime gauge
+const UPTIME_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+/// Rollup bucket the uptime gauge is summed from
+const UPTIME_BUCKET_SECONDS: u64 = 60 * 60;
+
+/// Label set identifying a series, sorted by name as remote write requires
+type Labels = Vec<(String, String)>;
+
+/// Samples of one series as (value, Unix milliseconds)
+type Samples = Vec<(f64, i64)>;
+
+/// Task pushing buffered results to the remote-write endpoint
+pub struct RemoteWriter {
+    database: Arc<dyn Database>,
+    config: RemoteWriteConfig,
+    url: String,
+    buffer: VecDeque<CheckResult>,
+}
+
+impl RemoteWriter {
+    /// Create an exporter if a remote-write URL is configured
+    pub fn new(database: Arc<dyn Database>, config: &RemoteWriteConfig) -> Option<Self> {
+        let url = config.url.clone()?;
+        Some(Self { database, config: config.clone(), url, buffer: VecDeque::new() })
+    }
+
+    /// Spawn the exporter, returning the sender the pipeline feeds it through
+    ///
+    /// The task pushes what is left and exits once the sender is dropped.
+    pub fn spawn(mut self) -> (mpsc::Sender<CheckResult>, JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::channel::<CheckResult>(256);
+
+        let handle = tokio::spawn(async move {
+            let client = match reqwest::Client::builder().timeout(PUSH_TIMEOUT).build() {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("Remote write disabled, failed to build HTTP client: {}", e);
+                    return;
+                }
+            };
+            let period = Duration::from_secs(self.config.push_interval_secs.max(1));
+            let mut interval = tokio::time::interval(period);
+            interval.tick().await;
+
+            loop {
+                tokio::select! {
+                    result = rx.recv() => match result {
+                        Some(result) => self.buffer(result),
+                        None => break,
+                    },
+                    _ = interval.tick() => self.push(&client).await,
+                }
+            }
+
+            self.push(&client).await;
+            debug!("Remote writer stopped");
+        });
+
+        (tx, handle)
+    }
+
+    fn buffer(&mut self, result: CheckResult) {
+        if self.buffer.len() >= MAX_BUFFERED_RESULTS {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back(result);
+    }
+
+    /// Push the buffered results and current uptime, keeping the results on failure
+    async fn push(&mut self, client: &reqwest::Client) {
+        let series = match self.collect().await {
+            Ok(series) => series,
+            Err(e) => {
+                warn!("Failed to collect samples for remote write: {}", e);
+                return;
+            }
+        };
+        if series.is_empty() {
+            return;
+        }
+
+        let body = match snap::raw::Encoder::new().compress_vec(&encode_write_request(&series)) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to compress remote write request: {}", e);
+                return;
+            }
+        };
+        let mut request = client
+            .post(&self.url)
+            .header("Content-Type", "application/x-protobuf")
+            .header("Content-Encoding", "snappy")
+            .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+            .body(body);
+        if let Some(token) = &self.config.bearer_token {
+            request = request.bearer_auth(token);
+        }
+
+        match request.send().await.and_then(|response| response.error_for_status()) {
+            Ok(_) => {
+                debug!("Pushed {} series to {}", series.len(), self.url);
+                self.buffer.clear();
+            }
+            Err(e) => warn!(
+                "Failed to push to remote write endpoint, {} results kept: {}",
+                self.buffer.len(),
+                e
+            ),
+        }
+    }
+
+    /// Series for the buffered results plus the uptime gauges
+    async fn collect(&self) -> Result<BTreeMap<Labels, Samples>> {
+        let monitors = self.database.get_monitors().await?;
+        let names: HashMap<Uuid, &str> =
+            monitors.iter().map(|m| (m.uuid, m.name.as_str())).collect();
+        let name_of = |uuid: &Uuid| names.get(uuid).copied().unwrap_or_default();
+
+        let mut series = result_series(&self.buffer, name_of);
+
+        let now = SystemTime::now();
+        let start = now - UPTIME_WINDOW;
+        for monitor in monitors.iter().filter(|m| m.enabled) {
+            let rollups = self
+                .database
+                .get_result_rollups(monitor.uuid, start, UPTIME_BUCKET_SECONDS)
+                .await?;
+            let checks: u64 = rollups.iter().map(|r| r.checks).sum();
+            if checks == 0 {
+                continue;
+            }
+            let up: u64 = rollups.iter().map(|r| r.up).sum();
+            let labels =
+                labels("uppe_monitor_uptime_ratio", monitor.uuid, &monitor.name, &monitor.target);
+            series
+                .entry(labels)
+                .or_default()
+                .push((up as f64 / checks as f64, unix_millis(now)));
+        }
+
+        Ok(series)
+    }
+}
+
+/// `uppe_check_up` and `uppe_check_latency_seconds` samples of stored results
+///
+/// Degraded checks count as up, as in the rollups; unknown results only
+/// report their latency.
+fn result_series<'a, 'r>(
+    results: impl IntoIterator<Item = &'r CheckResult>,
+    name_of: impl Fn(&Uuid) -> &'a str,
+) -> BTreeMap<Labels, Samples> {
+    let mut series: BTreeMap<Labels, Samples> = BTreeMap::new();
+    for result in results {
+        let name = name_of(&result.monitor_id);
+        let at = unix_millis(result.timestamp);
+
+        let up = match result.status {
+            MonitorStatus::Up | MonitorStatus::Degraded => Some(1.0),
+            MonitorStatus::Down => Some(0.0),
+            MonitorStatus::Unknown => None,
+        };
+        if let Some(up) = up {
+            let labels = labels("uppe_check_up", result.monitor_id, name, &result.target);
+            series.entry(labels).or_default().push((up, at));
+        }
+        if let Some(ms) = result.latency_ms {
+            let labels =
+                labels("uppe_check_latency_seconds", result.monitor_id, name, &result.target);
+            series.entry(labels).or_default().push((ms as f64 / 1000.0, at));
+        }
+    }
+
+    // Samples of a series must be sent oldest first
+    for samples in series.values_mut() {
+        samples.sort_by_key(|(_, at)| *at);
+    }
+    series
+}
+
+/// Labels of a monitor's series; `name` is left out for deleted monitors
+fn labels(metric: &str, monitor: Uuid, name: &str, target: &str) -> Labels {
+    let mut labels = vec![
+        ("__name__".to_string(), metric.to_string()),
+        ("monitor".to_string(), monitor.to_string()),
+    ];
+    if !name.is_empty() {
+        labels.push(("name".to_string(), name.to_string()));
+    }
+    labels.push(("target".to_string(), target.to_string()));
+    labels
+}
+
+fn unix_millis(at: SystemTime) -> i64 {
+    at.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
+
+/// `prometheus.WriteRequest` of remote write 1.0
+#[derive(Clone, PartialEq, Message)]
+struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    timeseries: Vec<TimeSeries>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct TimeSeries {
+    #[prost(message, repeated, tag = "1")]
+    labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    samples: Vec<Sample>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Label {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, tag = "2")]
+    value: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Sample {
+    #[prost(double, tag = "1")]
+    value: f64,
+    #[prost(int64, tag = "2")]
+    timestamp: i64,
+}
+
+/// Protobuf `prometheus.WriteRequest` holding the given series
+fn encode_write_request(series: &BTreeMap<Labels, Samples>) -> Vec<u8> {
+    let timeseries = series
+        .iter()
+        .map(|(labels, samples)| TimeSeries {
+            labels: labels
+                .iter()
+                .map(|(name, value)| Label { name: name.clone(), value: value.clone() })
+                .collect(),
+            samples: samples
+                .iter()
+                .map(|&(value, timestamp)| Sample { value, timestamp })
+                .collect(),
+        })
+        .collect();
+    WriteRequest { timeseries }.encode_to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(monitor_id: Uuid, secs: u64, status: MonitorStatus) -> CheckResult {
+        let mut result =
+            CheckResult::new(monitor_id, "https://example.com".to_string(), "peer".to_string());
+        result.status = status;
+        result.timestamp = UNIX_EPOCH + Duration::from_secs(secs);
+        result.latency_ms = Some(250);
+        result
+    }
+
+    #[test]
+    fn test_result_series() {
+        let uuid = Uuid::new_v4();
+        let results = [
+            result(uuid, 20, MonitorStatus::Down),
+            result(uuid, 10, MonitorStatus::Degraded),
+            result(uuid, 30, MonitorStatus::Unknown),
+        ];
+        let series = result_series(&results, |_| "api");
+
+        let up = &series[&labels("uppe_check_up", uuid, "api", "https://example.com")];
+        assert_eq!(up, &vec![(1.0, 10_000), (0.0, 20_000)]);
+        let latency =
+            &series[&labels("uppe_check_latency_seconds", uuid, "api", "https://example.com")];
+        assert_eq!(latency, &vec![(0.25, 10_000), (0.25, 20_000), (0.25, 30_000)]);
+    }
+
+    #[test]
+    fn test_encode_write_request() {
+        let mut series = BTreeMap::new();
+        series.insert(vec![("a".to_string(), "b".to_string())], vec![(1.0, 5)]);
+
+        let mut expected = vec![0x0a, 0x15]; // timeseries, 21 bytes
+        expected.extend([0x0a, 0x06, 0x0a, 0x01, b'a', 0x12, 0x01, b'b']); // label
+        expected.extend([0x12, 0x0b, 0x09]); // sample, value
+        expected.extend(1.0f64.to_le_bytes());
+        expected.extend([0x10, 0x05]); // timestamp
+        assert_eq!(encode_write_request(&series), expected);
+    }
+
+    #[test]
+    fn test_compressed_request_round_trip() {
+        let mut series = BTreeMap::new();
+        let labels = labels("uppe_check_up", Uuid::new_v4(), "api", "https://example.com");
+        series.insert(labels, (0..500).map(|i| (1.0, i * 1000)).collect());
+        let request = encode_write_request(&series);
+
+        let body = snap::raw::Encoder::new().compress_vec(&request).unwrap();
+        assert!(body.len() < request.len());
+        let decoded = snap::raw::Decoder::new().decompress_vec(&body).unwrap();
+        let decoded = WriteRequest::decode(decoded.as_slice()).unwrap();
+        assert_eq!(decoded.timeseries.len(), 1);
+        assert_eq!(decoded.timeseries[0].labels[0].value, "uppe_check_up");
+        assert_eq!(decoded.timeseries[0].samples[499].timestamp, 499_000);
+    }
+}
@@ -0,0 +1,239 @@
+/// Reports - renders scheduled uptime reports and delivers them to notification channels
+///
+/// Every `[[reports]]` entry is delivered once per week or month, a little
+/// after the period ended so its last hour is rolled up. Templates are read
+/// from disk for every report, so they can be edited without a restart. The
+/// end of the last delivered period is kept in the `report_sent:<name>`
+/// setting: a restart neither repeats nor skips a report, and the nodes of a
+/// cluster, which share settings, leave it to whichever gets there first.
+///
+/// Webhooks get the report in the `event` of their body, PDF reports base64
+/// encoded; chat apps and push services get its text.
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::config::{NotificationsConfig, ReportConfig, ReportFormat};
+use crate::database::Database;
+use crate::database::models::{Monitor, Setting};
+use crate::notifications::{self, Notification};
+use crate::report::{self, Report};
+
+/// How often reports are checked for a period that is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// Wait after a period ends, giving the hourly rollup time to cover it
+const ROLLUP_DELAY: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// Task delivering scheduled reports
+pub struct ReportScheduler {
+    database: Arc<dyn Database>,
+    reports: Vec<ReportConfig>,
+    notifications: NotificationsConfig,
+}
+
+impl ReportScheduler {
+    /// Create a scheduler if any report is configured
+    pub fn new(
+        database: Arc<dyn Database>,
+        reports: &[ReportConfig],
+        notifications: &NotificationsConfig,
+    ) -> Option<Self> {
+        (!reports.is_empty()).then(|| Self {
+            database,
+            reports: reports.to_vec(),
+            notifications: *notifications,
+        })
+    }
+
+    /// Spawn the scheduler; it runs until the task is aborted
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                timer.tick().await;
+                for config in &self.reports {
+                    if let Err(e) = self.deliver_due(config).await {
+                        warn!("Failed to deliver report '{}': {:#}", config.name, e);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Render and deliver the report of the last period unless it was
+    /// delivered already
+    async fn deliver_due(&self, config: &ReportConfig) -> Result<()> {
+        let now = SystemTime::now();
+        let (start, end) = report::last_period(config.period, now);
+        if now < end + ROLLUP_DELAY {
+            return Ok(());
+        }
+        let key = sent_key(&config.name);
+        let end_secs = Monitor::timestamp_to_i64(end);
+        let sent = self.database.get_setting(&key).await?.and_then(|v| v.parse::<i64>().ok());
+        if sent.is_some_and(|sent| sent >= end_secs) {
+            return Ok(());
+        }
+
+        let report = Report::load(self.database.as_ref(), config, start, end).await?;
+        let template = match &config.template {
+            Some(path) => Some(
+                tokio::fs::read_to_string(path)
+                    .await
+                    .map_err(|e| anyhow!("Failed to read template {}: {e}", path.display()))?,
+            ),
+            None => None,
+        };
+        let document = report.render(config.format, template.as_deref())?;
+
+        if let Some(dir) = &config.output_dir {
+            let path = dir.join(file_name(config, &report));
+            tokio::fs::create_dir_all(dir).await?;
+            tokio::fs::write(&path, document.bytes()).await?;
+            info!("Wrote report '{}' to {}", config.name, path.display());
+        }
+        self.send(config, &report, &document).await?;
+
+        let setting = Setting { key, value: end_secs.to_string(), updated_at: now };
+        self.database.save_setting(&setting).await
+    }
+
+    /// Send a report to its channels, failing only if none of them got it
+    async fn send(
+        &self,
+        config: &ReportConfig,
+        report: &Report,
+        document: &report::Document,
+    ) -> Result<()> {
+        let channels = self.database.get_notification_channels().await?;
+        let targets: Vec<_> = config
+            .channels
+            .iter()
+            .filter_map(|wanted| {
+                let channel =
+                    channels.iter().find(|c| c.name == *wanted || c.uuid.to_string() == *wanted);
+                if channel.is_none() {
+                    warn!("Report '{}' names unknown channel '{}'", config.name, wanted);
+                }
+                channel.filter(|c| c.enabled)
+            })
+            .collect();
+        if targets.is_empty() {
+            debug!("Report '{}' has no enabled channels", config.name);
+            return Ok(());
+        }
+
+        let notification = notification(config, report, document);
+        let retry_delay = Duration::from_secs(self.notifications.retry_delay_secs);
+        let max_attempts = self.notifications.max_attempts.max(1);
+        let mut delivered = 0;
+        for channel in &targets {
+            match notifications::send_with_retry(channel, &notification, max_attempts, retry_delay)
+                .await
+            {
+                Ok(()) => delivered += 1,
+                Err(e) => warn!("Failed to send report to '{}': {}", channel.name, e),
+            }
+        }
+        if delivered == 0 {
+            return Err(anyhow!("No channel accepted the report"));
+        }
+
+        info!("Delivered report '{}' to {} channel(s)", config.name, delivered);
+        Ok(())
+    }
+}
+
+/// Setting holding the end of the last period a report was delivered for
+fn sent_key(name: &str) -> String {
+    format!("report_sent:{name}")
+}
+
+/// File a report is written to, e.g. "weekly-2026-10-05.md"
+fn file_name(config: &ReportConfig, report: &Report) -> String {
+    let extension = match config.format {
+        ReportFormat::Markdown => "md",
+        ReportFormat::Html => "html",
+        ReportFormat::Pdf => "pdf",
+    };
+    let name: String = config
+        .name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    format!("{name}-{}.{extension}", report::format_date(report.start))
+}
+
+fn notification(
+    config: &ReportConfig,
+    report: &Report,
+    document: &report::Document,
+) -> Notification {
+    let (content_type, content) = match (config.format, &document.pdf) {
+        (ReportFormat::Pdf, Some(pdf)) => ("application/pdf", BASE64.encode(pdf)),
+        (ReportFormat::Html, _) => ("text/html", document.text.clone()),
+        _ => ("text/markdown", document.text.clone()),
+    };
+
+    Notification {
+        title: format!("{} report: {}", config.name, report.period()),
+        body: document.text.clone(),
+        event: Some(json!({
+            "type": "report",
+            "name": config.name,
+            "start": Monitor::timestamp_to_i64(report.start),
+            "end": Monitor::timestamp_to_i64(report.end),
+            "uptime_percent": report.uptime_percent(),
+            "monitors": report.monitors.len(),
+            "incidents": report.incidents.len(),
+            "content_type": content_type,
+            "content": content,
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ReportPeriod;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_report_notification() {
+        let config = ReportConfig {
+            name: "Weekly API".to_string(),
+            period: ReportPeriod::Weekly,
+            format: ReportFormat::Pdf,
+            status_page: None,
+            tag: Some("api".to_string()),
+            channels: vec!["ops".to_string()],
+            template: None,
+            output_dir: None,
+        };
+        let start = UNIX_EPOCH + Duration::from_secs(1_791_158_400);
+        let report = Report {
+            title: config.name.clone(),
+            start,
+            end: start + Duration::from_secs(7 * 86_400),
+            generated_at: start,
+            monitors: Vec::new(),
+            incidents: Vec::new(),
+        };
+        assert_eq!(file_name(&config, &report), "weekly-api-2026-10-05.pdf");
+
+        let document = report.render(config.format, None).unwrap();
+        let notification = notification(&config, &report, &document);
+        assert_eq!(notification.title, "Weekly API report: 2026-10-05 to 2026-10-11");
+        assert_eq!(notification.body, document.text);
+        let event = notification.event.unwrap();
+        assert_eq!(event["type"], "report");
+        assert_eq!(event["content_type"], "application/pdf");
+        assert_eq!(event["content"], BASE64.encode(document.pdf.as_ref().unwrap()));
+        assert!(event["uptime_percent"].is_null());
+    }
+}
@@ -0,0 +1,417 @@
+/// Results sync - pulls peer results missed while offline from other peers
+///
+/// Peers keep the verified results they received over gossip. When a peer
+/// connects and no sync ran for `SYNC_INTERVAL`, that peer is asked for the
+/// results it stored for each of our public monitors since the last sync, a
+/// page at a time. Signatures are checked against our own monitor's target,
+/// as stored results do not carry it, and results already stored are skipped.
+/// Requests from peers are answered from our peer results the same way;
+/// private monitors are never served.
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use peerup::{ResultsSyncRequest, ResultsSyncResponse};
+
+use super::runtime::RuntimeSettings;
+use crate::crypto::verify_result;
+use crate::database::Database;
+use crate::database::models::MonitorVisibility;
+use crate::p2p::{P2PHandle, SyncedResult};
+
+/// Minimum time between sync rounds
+const SYNC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// How far back the first round after start looks
+const MAX_LOOKBACK: Duration = Duration::from_secs(24 * 60 * 60);
+/// Overlap with the previous round, for results that arrived late at peers
+const LOOKBACK_MARGIN: Duration = Duration::from_secs(5 * 60);
+/// Results per page, asked for and served
+const PAGE_SIZE: u32 = 200;
+/// Pages fetched per monitor in a round
+const MAX_PAGES: u32 = 50;
+/// Monitors synced at the same time
+const MAX_IN_FLIGHT: usize = 4;
+
+/// Results sync protocol events forwarded by the peer event handler
+#[derive(Debug)]
+pub enum SyncEvent {
+    /// A peer connected
+    PeerConnected(String),
+    /// A peer asked for results we stored
+    Requested { peer_id: String, inbound_id: u64, request: Box<ResultsSyncRequest> },
+    /// A peer answered one of our requests with a page
+    Answered { sync_id: Uuid, peer_id: String, response: Box<ResultsSyncResponse> },
+    /// One of our requests got no answer
+    Failed { sync_id: Uuid, peer_id: String, error: String },
+}
+
+/// A monitor whose results are being pulled
+#[derive(Debug, Clone)]
+struct MonitorSync {
+    monitor_id: Uuid,
+    target: String,
+    pages: u32,
+}
+
+/// A sync round pulling from one peer
+struct Round {
+    peer_id: String,
+    since: SystemTime,
+    queue: VecDeque<MonitorSync>,
+    in_flight: HashMap<Uuid, MonitorSync>,
+    stored: u64,
+}
+
+/// Task pulling missed results from peers and answering their requests
+pub struct ResultsSync {
+    database: Arc<dyn Database>,
+    p2p: P2PHandle,
+    /// Our peer ID; results we signed ourselves are not pulled back
+    peer_id: String,
+    events: mpsc::Receiver<SyncEvent>,
+    round: Option<Round>,
+    /// When the last round started, by the clock and the wall clock
+    last_round: Option<(Instant, SystemTime)>,
+}
+
+impl ResultsSync {
+    /// Create the sync task, returning the sender sync events are forwarded on
+    pub fn new(
+        database: Arc<dyn Database>,
+        p2p: P2PHandle,
+        peer_id: String,
+    ) -> (Self, mpsc::Sender<SyncEvent>) {
+        let (tx, events) = mpsc::channel(100);
+        (Self { database, p2p, peer_id, events, round: None, last_round: None }, tx)
+    }
+
+    /// Spawn the sync; it stops once the settings channel or the event sender
+    /// closes
+    pub fn spawn(mut self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    event = self.events.recv() => match event {
+                        Some(event) => {
+                            let sharing = settings.borrow().p2p_sharing;
+                            self.handle(event, sharing).await;
+                        }
+                        None => break,
+                    },
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            debug!("Results sync stopped");
+        })
+    }
+
+    async fn handle(&mut self, event: SyncEvent, sharing: bool) {
+        match event {
+            SyncEvent::PeerConnected(peer_id) => {
+                if sharing && self.round.is_none() && round_due(self.last_round, Instant::now()) {
+                    self.start_round(peer_id).await;
+                }
+            }
+            SyncEvent::Requested { peer_id, inbound_id, request } => {
+                let response = if sharing {
+                    self.answer(&peer_id, &request).await
+                } else {
+                    ResultsSyncResponse { results: Vec::new(), next_cursor: None }
+                };
+                if let Err(e) = self.p2p.respond_results_sync(inbound_id, response).await {
+                    debug!("Failed to answer results sync from {}: {}", peer_id, e);
+                }
+            }
+            SyncEvent::Answered { sync_id, peer_id, response } => {
+                self.receive_page(sync_id, &peer_id, *response).await;
+            }
+            SyncEvent::Failed { sync_id, peer_id, error } => {
+                if let Some(round) = &mut self.round
+                    && let Some(monitor) = round.in_flight.remove(&sync_id)
+                {
+                    debug!(
+                        "Results sync of {} from {} failed: {}",
+                        monitor.monitor_id, peer_id, error
+                    );
+                }
+                self.continue_round().await;
+            }
+        }
+    }
+
+    /// Start pulling results for our public monitors from a peer
+    async fn start_round(&mut self, peer_id: String) {
+        let monitors = match self.database.get_enabled_monitors().await {
+            Ok(monitors) => monitors,
+            Err(e) => {
+                warn!("Failed to load monitors for results sync: {}", e);
+                return;
+            }
+        };
+        let queue: VecDeque<_> = monitors
+            .into_iter()
+            // Only we hear a heartbeat monitor's pings, so peers have no results
+            .filter(|monitor| monitor.visibility == MonitorVisibility::Public)
+            .filter(|monitor| !monitor.is_heartbeat())
+            .map(|monitor| MonitorSync {
+                monitor_id: monitor.uuid,
+                target: monitor.target,
+                pages: 0,
+            })
+            .collect();
+
+        let now = SystemTime::now();
+        let since = sync_since(self.last_round.map(|(_, since)| since), now);
+        self.last_round = Some((Instant::now(), now));
+        if queue.is_empty() {
+            return;
+        }
+
+        info!("Syncing results for {} monitors from peer {}", queue.len(), peer_id);
+        self.round = Some(Round { peer_id, since, queue, in_flight: HashMap::new(), stored: 0 });
+        self.continue_round().await;
+    }
+
+    /// Request the next monitors of the round, ending it once all are done
+    async fn continue_round(&mut self) {
+        let Some(round) = &mut self.round else {
+            return;
+        };
+        while round.in_flight.len() < MAX_IN_FLIGHT {
+            let Some(monitor) = round.queue.pop_front() else {
+                break;
+            };
+            request_page(&self.p2p, round, monitor, None).await;
+        }
+
+        if round.in_flight.is_empty() && round.queue.is_empty() {
+            info!("Synced {} results from peer {}", round.stored, round.peer_id);
+            self.round = None;
+        }
+    }
+
+    /// Store the results of a page and ask for the next one
+    async fn receive_page(&mut self, sync_id: Uuid, peer_id: &str, response: ResultsSyncResponse) {
+        let Some(mut monitor) =
+            self.round.as_mut().and_then(|round| round.in_flight.remove(&sync_id))
+        else {
+            debug!("Ignoring stale results sync page from {}", peer_id);
+            return;
+        };
+        monitor.pages += 1;
+
+        let mut stored = 0;
+        for value in response.results.into_iter().take(PAGE_SIZE as usize) {
+            let Ok(result) = serde_json::from_value::<SyncedResult>(value) else {
+                continue;
+            };
+            if result.peer_id == self.peer_id {
+                continue;
+            }
+            let Some(mut result) = result.to_stored(monitor.monitor_id, peer_id, SystemTime::now())
+            else {
+                debug!("Dropping synced result with an invalid timestamp from {}", peer_id);
+                continue;
+            };
+            if !signed_by_peer(&result, &monitor.target) {
+                debug!("Dropping synced result with a bad signature from {}", peer_id);
+                continue;
+            }
+            match self
+                .database
+                .has_peer_result(result.monitor_uuid, &result.peer_id, result.timestamp)
+                .await
+            {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => {
+                    warn!("Failed to look up synced result: {}", e);
+                    continue;
+                }
+            }
+            result.verified = true;
+            match self.database.save_peer_result(&result).await {
+                Ok(_) => stored += 1,
+                Err(e) => warn!("Failed to save synced result: {}", e),
+            }
+        }
+
+        let Some(round) = &mut self.round else {
+            return;
+        };
+        round.stored += stored;
+        match response.next_cursor {
+            Some(cursor) if monitor.pages < MAX_PAGES => {
+                request_page(&self.p2p, round, monitor, Some(cursor)).await;
+            }
+            _ => debug!("Synced results for {} from {}", monitor.monitor_id, peer_id),
+        }
+        self.continue_round().await;
+    }
+
+    /// A page of the verified results we stored for a peer's request
+    async fn answer(&self, peer_id: &str, request: &ResultsSyncRequest) -> ResultsSyncResponse {
+        let empty = ResultsSyncResponse { results: Vec::new(), next_cursor: None };
+        let Ok(monitor_id) = request.monitor_id.parse::<Uuid>() else {
+            return empty;
+        };
+        // Results of our private monitors stay with us
+        match self.database.get_monitor_by_uuid(monitor_id).await {
+            Ok(Some(monitor)) if monitor.visibility != MonitorVisibility::Public => return empty,
+            Ok(_) => {}
+            Err(e) => {
+                warn!("Failed to look up monitor for results sync: {}", e);
+                return empty;
+            }
+        }
+
+        let limit = request.limit.clamp(1, PAGE_SIZE);
+        let Some(since) = UNIX_EPOCH.checked_add(Duration::from_secs(request.since)) else {
+            return empty;
+        };
+        let after_id = request.cursor.map_or(0, |cursor| cursor.min(i64::MAX as u64) as i64);
+        let results = match self
+            .database
+            .get_peer_results_page(monitor_id, since, after_id, limit as usize)
+            .await
+        {
+            Ok(results) => results,
+            Err(e) => {
+                warn!("Failed to load results for sync request from {}: {}", peer_id, e);
+                return empty;
+            }
+        };
+
+        // A full page may have more after it
+        let next_cursor = (results.len() == limit as usize)
+            .then(|| results.last().and_then(|result| result.id))
+            .flatten()
+            .map(|id| id as u64);
+        let results = results
+            .iter()
+            .filter_map(|result| serde_json::to_value(SyncedResult::from_stored(result)).ok())
+            .collect();
+        ResultsSyncResponse { results, next_cursor }
+    }
+}
+
+/// Ask the round's peer for a page of a monitor's results
+async fn request_page(
+    p2p: &P2PHandle,
+    round: &mut Round,
+    monitor: MonitorSync,
+    cursor: Option<u64>,
+) {
+    let sync_id = Uuid::new_v4();
+    let request = ResultsSyncRequest {
+        monitor_id: monitor.monitor_id.to_string(),
+        since: unix_seconds(round.since),
+        cursor,
+        limit: PAGE_SIZE,
+    };
+    match p2p.request_results_sync(sync_id, round.peer_id.clone(), request).await {
+        Ok(()) => {
+            round.in_flight.insert(sync_id, monitor);
+        }
+        Err(e) => debug!("Failed to request results sync: {}", e),
+    }
+}
+
+/// Whether enough time passed since the last round started to run another
+fn round_due(last_round: Option<(Instant, SystemTime)>, now: Instant) -> bool {
+    last_round.is_none_or(|(started, _)| now.duration_since(started) >= SYNC_INTERVAL)
+}
+
+/// Time to sync from: shortly before the last round, at most a day back
+pub(super) fn sync_since(last_round: Option<SystemTime>, now: SystemTime) -> SystemTime {
+    let earliest = now.checked_sub(MAX_LOOKBACK).unwrap_or(UNIX_EPOCH);
+    match last_round.and_then(|last| last.checked_sub(LOOKBACK_MARGIN)) {
+        Some(since) => since.max(earliest),
+        None => earliest,
+    }
+}
+
+/// Whether a synced result was signed by the key its peer ID names
+pub(super) fn signed_by_peer(result: &crate::database::models::PeerResult, target: &str) -> bool {
+    let Some(key) = hex::decode(&result.peer_id)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+    else {
+        return false;
+    };
+    verify_result(result, &key, target).unwrap_or(false)
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::keys::generate_keypair;
+    use crate::crypto::sign_result;
+    use crate::monitoring::CheckResult;
+    use crate::monitoring::types::MonitorStatus;
+
+    #[test]
+    fn test_synced_result_verifies_with_own_target() {
+        let keypair = generate_keypair();
+        let mut check = CheckResult::new(
+            Uuid::new_v4(),
+            "https://example.com".to_string(),
+            keypair.public_key_hex(),
+        );
+        check.status = MonitorStatus::Up;
+        check.latency_ms = Some(42);
+        check.timestamp = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut stored =
+            crate::database::models::PeerResult::from_p2p_result(&crate::p2p::PeerResult {
+                result: check.clone(),
+                signature: Some(sign_result(&check, &keypair).unwrap()),
+                public_key: None,
+                peer_id: check.peer_id.clone(),
+                location: None,
+                received_at: SystemTime::now(),
+                signature_valid: None,
+                identity_bound: None,
+            })
+            .unwrap();
+        stored.id = Some(7);
+
+        // Round trip through the wire form, as a page carries it
+        let value = serde_json::to_value(SyncedResult::from_stored(&stored)).unwrap();
+        let synced: SyncedResult = serde_json::from_value(value).unwrap();
+        let received = synced.to_stored(check.monitor_id, "12D3KooWA", SystemTime::now()).unwrap();
+        assert_eq!(received.timestamp, check.timestamp);
+        assert_eq!(received.source_peer_id.as_deref(), Some("12D3KooWA"));
+        assert!(signed_by_peer(&received, "https://example.com"));
+        assert!(!signed_by_peer(&received, "https://example.org"));
+
+        // A timestamp no clock can hold is dropped rather than panicking
+        let far = SyncedResult { timestamp: u64::MAX, ..synced };
+        assert!(far.to_stored(check.monitor_id, "12D3KooWA", SystemTime::now()).is_none());
+    }
+
+    #[test]
+    fn test_sync_since() {
+        let now = UNIX_EPOCH + Duration::from_secs(10 * 24 * 60 * 60);
+        assert_eq!(sync_since(None, now), now - MAX_LOOKBACK);
+        let last = now - Duration::from_secs(60 * 60);
+        assert_eq!(sync_since(Some(last), now), last - LOOKBACK_MARGIN);
+        assert_eq!(sync_since(Some(now - MAX_LOOKBACK * 3), now), now - MAX_LOOKBACK);
+
+        let started = Instant::now();
+        assert!(round_due(None, started));
+        assert!(!round_due(Some((started, now)), started + Duration::from_secs(60)));
+        assert!(round_due(Some((started, now)), started + SYNC_INTERVAL));
+    }
+}
@@ -0,0 +1,151 @@
+/// Retention - removes results and audit events older than their configured retention
+///
+/// Results use their monitor's own retention when it has one and the global
+/// `result_retention_days` otherwise. With an archive configured, our own
+/// expired results are uploaded before they are deleted; peer results,
+/// agreements and vantage results are only deleted. Every sweep first rolls
+/// our new results up into hourly and daily rollups, which outlive them, and
+/// drops hourly rollups past their own retention.
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::runtime::RuntimeSettings;
+use crate::archive::ResultArchive;
+use crate::database::Database;
+use crate::database::models::RollupPeriod;
+
+/// How often old results are swept
+const SWEEP_INTERVAL: Duration = Duration::from_secs(3600);
+/// Results per archive file
+const ARCHIVE_BATCH: usize = 50_000;
+
+/// Task deleting results and audit events that fell out of their retention window
+pub struct RetentionSweeper {
+    database: Arc<dyn Database>,
+    archive: Option<Arc<ResultArchive>>,
+    hourly_rollup_days: u64,
+}
+
+impl RetentionSweeper {
+    /// Create a sweeper for the given database
+    pub fn new(database: Arc<dyn Database>, hourly_rollup_days: u64) -> Self {
+        Self { database, archive: None, hourly_rollup_days }
+    }
+
+    /// Archive expired results before deleting them
+    pub fn with_archive(mut self, archive: Arc<ResultArchive>) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    /// Spawn the sweeper; it sweeps hourly and whenever a retention changes,
+    /// and stops once the settings channel closes
+    pub fn spawn(self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(SWEEP_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => {}
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                }
+
+                let (result_days, audit_days) = {
+                    let current = settings.borrow_and_update();
+                    (current.result_retention_days, current.audit_retention_days)
+                };
+                self.rollup_results().await;
+                self.sweep_results(result_days).await;
+                self.sweep_audit_events(audit_days).await;
+                self.sweep_hourly_rollups().await;
+            }
+        })
+    }
+
+    /// Roll results up before any of them expire
+    async fn rollup_results(&self) {
+        match self.database.rollup_results(SystemTime::now()).await {
+            Ok(0) => {}
+            Ok(hours) => debug!("Rolled up {} hours of results", hours),
+            Err(e) => warn!("Failed to roll up results: {}", e),
+        }
+    }
+
+    /// Runs even when the global retention keeps everything, since monitors
+    /// may still have a retention of their own
+    async fn sweep_results(&self, default_days: u64) {
+        if let Some(archive) = &self.archive
+            && let Err(e) = self.archive_results(archive, default_days).await
+        {
+            // Keep the results until they made it into the archive
+            warn!("Failed to archive old results, not removing them: {}", e);
+            return;
+        }
+
+        match self.database.delete_expired_results(SystemTime::now(), default_days).await {
+            Ok(0) => {}
+            Ok(removed) => info!("Removed {} results past their retention", removed),
+            Err(e) => warn!("Failed to remove old results: {}", e),
+        }
+    }
+
+    /// Upload and delete our expired results, one batch at a time
+    async fn archive_results(
+        &self,
+        archive: &ResultArchive,
+        default_days: u64,
+    ) -> anyhow::Result<()> {
+        loop {
+            let results = self
+                .database
+                .get_expired_results(SystemTime::now(), default_days, ARCHIVE_BATCH)
+                .await?;
+            if results.is_empty() {
+                return Ok(());
+            }
+
+            archive.archive(&results).await?;
+            let ids: Vec<i64> = results.iter().filter_map(|r| r.id).collect();
+            let removed = self.database.delete_results(&ids).await?;
+            info!("Archived {} results past their retention", removed);
+
+            if results.len() < ARCHIVE_BATCH {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn sweep_audit_events(&self, days: u64) {
+        let Some(cutoff) = cutoff(days) else {
+            return;
+        };
+        match self.database.delete_audit_events_before(cutoff).await {
+            Ok(0) => {}
+            Ok(removed) => info!("Removed {} audit events older than {} days", removed, days),
+            Err(e) => warn!("Failed to remove old audit events: {}", e),
+        }
+    }
+
+    async fn sweep_hourly_rollups(&self) {
+        let Some(cutoff) = cutoff(self.hourly_rollup_days) else {
+            return;
+        };
+        match self.database.delete_rollups_before(RollupPeriod::Hourly, cutoff).await {
+            Ok(0) => {}
+            Ok(removed) => info!("Removed {} hourly result rollups", removed),
+            Err(e) => warn!("Failed to remove old hourly result rollups: {}", e),
+        }
+    }
+}
+
+/// Oldest timestamp kept for a retention of `days`; `None` keeps everything
+fn cutoff(days: u64) -> Option<SystemTime> {
+    (days > 0).then(|| SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60))
+}
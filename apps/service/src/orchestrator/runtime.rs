@@ -0,0 +1,260 @@
+/// Runtime settings - config and settings values that apply without a restart
+///
+/// The watcher re-reads the config file when it changes on disk and merges in
+/// the `settings` table, publishing the result on a watch channel. Tasks that
+/// care about a value subscribe and apply it when it changes.
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::chaos::FaultSettings;
+use crate::config::{Config, RetentionConfig};
+use crate::database::Database;
+use crate::monitoring::MonitoringExecutor;
+
+/// Settings key for how long results are kept
+const RESULT_RETENTION_SETTING: &str = "result_retention_days";
+/// Settings key for how long audit events are kept
+const AUDIT_RETENTION_SETTING: &str = "audit_retention_days";
+/// Settings key for the daily P2P bandwidth budget
+const MAX_BANDWIDTH_SETTING: &str = "max_bandwidth_mb_per_day";
+
+/// Retentions used when neither the config file nor the settings table sets one
+const DEFAULT_RESULT_RETENTION_DAYS: u64 = 30;
+const DEFAULT_AUDIT_RETENTION_DAYS: u64 = 90;
+
+/// How often the config file and settings are re-read
+const WATCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Values that can change while the service runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeSettings {
+    /// Check timeout in seconds
+    pub timeout_seconds: u64,
+    /// Latency above which a check counts as degraded
+    pub degraded_threshold_ms: u64,
+    /// Days of results to keep for monitors without their own retention;
+    /// 0 keeps everything
+    pub result_retention_days: u64,
+    /// Days of audit events to keep; 0 keeps everything
+    pub audit_retention_days: u64,
+    /// Whether results are shared with and accepted from peers
+    pub p2p_sharing: bool,
+    /// Daily P2P traffic budget in megabytes; 0 is unlimited
+    pub max_bandwidth_mb_per_day: u64,
+    /// P2P transport toggles; these only take effect on restart
+    pub enable_mdns: bool,
+    pub enable_kademlia: bool,
+    pub enable_relay: bool,
+    /// Faults injected to test failure handling; only set in `chaos` builds
+    pub faults: FaultSettings,
+    /// Retentions pinned by the config file, which the settings table must not override
+    pinned_retention: RetentionConfig,
+}
+
+impl RuntimeSettings {
+    /// Settings from the config file, with the default bandwidth and any
+    /// retention the file leaves unset
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            timeout_seconds: config.preferences.timeout_seconds.unwrap_or(10),
+            degraded_threshold_ms: config.preferences.degraded_threshold_ms.unwrap_or(1000),
+            result_retention_days: config
+                .retention
+                .result_days
+                .unwrap_or(DEFAULT_RESULT_RETENTION_DAYS),
+            audit_retention_days: config
+                .retention
+                .audit_days
+                .unwrap_or(DEFAULT_AUDIT_RETENTION_DAYS),
+            p2p_sharing: config.preferences.use_peerup_layer,
+            max_bandwidth_mb_per_day: 100,
+            enable_mdns: config.peerup.enable_mdns,
+            enable_kademlia: config.peerup.enable_kademlia,
+            enable_relay: config.peerup.enable_relay,
+            faults: FaultSettings::default(),
+            pinned_retention: config.retention,
+        }
+    }
+
+    /// Apply overrides from the settings table; unparsable values and
+    /// retentions pinned by the config file are ignored
+    pub async fn with_settings(mut self, database: &dyn Database) -> Result<Self> {
+        if self.pinned_retention.result_days.is_none()
+            && let Some(value) = database.get_setting(RESULT_RETENTION_SETTING).await?
+            && let Ok(days) = value.trim().parse()
+        {
+            self.result_retention_days = days;
+        }
+        if self.pinned_retention.audit_days.is_none()
+            && let Some(value) = database.get_setting(AUDIT_RETENTION_SETTING).await?
+            && let Ok(days) = value.trim().parse()
+        {
+            self.audit_retention_days = days;
+        }
+        if let Some(value) = database.get_setting(MAX_BANDWIDTH_SETTING).await?
+            && let Ok(mb) = value.trim().parse()
+        {
+            self.max_bandwidth_mb_per_day = mb;
+        }
+        self.faults = FaultSettings::from_settings(database).await?;
+
+        Ok(self)
+    }
+
+    /// Whether a change requires restarting the P2P node
+    fn transport_changed(&self, other: &Self) -> bool {
+        self.enable_mdns != other.enable_mdns
+            || self.enable_kademlia != other.enable_kademlia
+            || self.enable_relay != other.enable_relay
+    }
+}
+
+/// Task watching the config file and settings table for changes
+pub struct RuntimeConfigWatcher {
+    database: Arc<dyn Database>,
+    config_path: Option<PathBuf>,
+    last_modified: Option<SystemTime>,
+    file_settings: RuntimeSettings,
+}
+
+impl RuntimeConfigWatcher {
+    /// Create a watcher starting from the config the service was started with
+    pub fn new(database: Arc<dyn Database>, config: &Config, config_path: Option<PathBuf>) -> Self {
+        let last_modified = config_path.as_deref().and_then(modified_at);
+        Self {
+            database,
+            config_path,
+            last_modified,
+            file_settings: RuntimeSettings::from_config(config),
+        }
+    }
+
+    /// Spawn the watcher, returning the channel current settings are published on
+    ///
+    /// The task runs until aborted.
+    pub async fn spawn(mut self) -> (watch::Receiver<RuntimeSettings>, JoinHandle<()>) {
+        let initial = self.current().await;
+        let (tx, rx) = watch::channel(initial);
+
+        let handle = tokio::spawn(async move {
+            let mut timer = tokio::time::interval(WATCH_INTERVAL);
+            timer.tick().await;
+
+            loop {
+                timer.tick().await;
+                self.reload_file();
+
+                let settings = self.current().await;
+                let previous = tx.borrow().clone();
+                if settings == previous {
+                    continue;
+                }
+
+                info!("Applying runtime settings: {:?}", settings);
+                if settings.transport_changed(&previous) {
+                    warn!("P2P transport changes (mDNS, Kademlia, relay) apply after a restart");
+                }
+                if settings.faults != previous.faults && settings.faults.is_active() {
+                    warn!("Injecting faults: {:?}", settings.faults);
+                }
+                let _ = tx.send(settings);
+            }
+        });
+
+        (rx, handle)
+    }
+
+    /// Re-read the config file if it changed on disk
+    fn reload_file(&mut self) {
+        let Some(path) = &self.config_path else {
+            return;
+        };
+        let modified = modified_at(path);
+        if modified == self.last_modified {
+            return;
+        }
+
+        match Config::read_config(path) {
+            Ok(config) => {
+                debug!("Config file {} changed, reloading", path.display());
+                self.file_settings = RuntimeSettings::from_config(&config);
+                self.last_modified = modified;
+            }
+            Err(e) => warn!("Ignoring unreadable config file {}: {:?}", path.display(), e),
+        }
+    }
+
+    /// File settings merged with the settings table
+    async fn current(&self) -> RuntimeSettings {
+        match self.file_settings.clone().with_settings(self.database.as_ref()).await {
+            Ok(settings) => settings,
+            Err(e) => {
+                warn!("Failed to read runtime settings: {}", e);
+                self.file_settings.clone()
+            }
+        }
+    }
+}
+
+fn modified_at(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Keep the executor's timeout and degraded threshold in sync with the settings
+pub fn spawn_executor_updates(
+    executor: Arc<MonitoringExecutor>,
+    mut settings: watch::Receiver<RuntimeSettings>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while settings.changed().await.is_ok() {
+            let current = settings.borrow_and_update().clone();
+            if let Err(e) =
+                executor.update_settings(current.timeout_seconds, current.degraded_threshold_ms)
+            {
+                warn!("Failed to apply check timeout {}s: {}", current.timeout_seconds, e);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_settings_from_config() {
+        let mut config = Config::default();
+        config.preferences.timeout_seconds = None;
+        config.preferences.degraded_threshold_ms = Some(250);
+
+        let settings = RuntimeSettings::from_config(&config);
+        assert_eq!(settings.timeout_seconds, 10);
+        assert_eq!(settings.degraded_threshold_ms, 250);
+        assert_eq!(settings.p2p_sharing, config.preferences.use_peerup_layer);
+
+        let mut toggled = settings.clone();
+        toggled.p2p_sharing = !toggled.p2p_sharing;
+        assert!(!settings.transport_changed(&toggled));
+        toggled.enable_relay = !toggled.enable_relay;
+        assert!(settings.transport_changed(&toggled));
+    }
+
+    #[test]
+    fn test_runtime_settings_retention_from_config() {
+        let mut config = Config::default();
+        let settings = RuntimeSettings::from_config(&config);
+        assert_eq!(settings.result_retention_days, DEFAULT_RESULT_RETENTION_DAYS);
+        assert_eq!(settings.audit_retention_days, DEFAULT_AUDIT_RETENTION_DAYS);
+
+        config.retention.result_days = Some(365);
+        config.retention.audit_days = Some(0);
+        let settings = RuntimeSettings::from_config(&config);
+        assert_eq!(settings.result_retention_days, 365);
+        assert_eq!(settings.audit_retention_days, 0);
+    }
+}
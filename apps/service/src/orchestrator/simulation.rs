@@ -0,0 +1,496 @@
+/// Multi-node scenarios run on peerup's network simulation
+///
+/// Each scenario drives the service's own logic across peers in virtual time:
+/// result agreement over signed results, the recruiter handing a private
+/// monitor to helpers and reassigning it, and results sync catching up a node
+/// that was down. Nodes here only carry messages between those functions the
+/// way the event loop does; what they decide comes from the real code.
+use std::collections::BTreeSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use peerup::testing::{
+    Action, Context, LinkConfig, NodeId, Scenario, SimNode, Simulation, Topology,
+};
+use uuid::Uuid;
+
+use super::agreement::compute_agreement;
+use super::duties::seal;
+use super::recruiter::{ASSIGNMENT_TTL, ROUND, plan};
+use super::results_sync::{signed_by_peer, sync_since};
+use crate::crypto::keys::generate_keypair;
+use crate::crypto::{
+    KeyPair, decrypt_result, sign_assignment_request, sign_result, verify_assignment_request,
+    verify_peer_result,
+};
+use crate::database::models::{self, HelperAssignment, Monitor, MonitorVisibility};
+use crate::monitoring::types::{CheckResult, MonitorStatus};
+use crate::p2p::{EncryptedResultMessage, HelperAssignmentRequest, PeerResult, SyncedResult};
+
+const TARGET: &str = "https://example.com";
+/// How often nodes check the monitor
+const CHECK: Duration = Duration::from_secs(60);
+
+/// Wall clock time at a point of virtual time
+fn wall(now: Duration) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(1_800_000_000) + now
+}
+
+/// Our result of a check at `now`, signed and as published
+fn check(keypair: &KeyPair, monitor: Uuid, status: MonitorStatus, now: Duration) -> PeerResult {
+    let mut result = CheckResult::new(monitor, TARGET.to_string(), keypair.public_key_hex());
+    result = match status {
+        MonitorStatus::Down => result.failure("connection refused".to_string()),
+        _ => result.success(120, Some(200)),
+    };
+    result.timestamp = wall(now);
+    PeerResult {
+        signature: Some(sign_result(&result, keypair).unwrap()),
+        result,
+        public_key: Some(keypair.public_key_bytes().to_vec()),
+        peer_id: keypair.public_key_hex(),
+        location: None,
+        received_at: wall(now),
+        signature_valid: None,
+        identity_bound: None,
+    }
+}
+
+/// Checks a monitor, publishes its signed results and keeps the ones from
+/// peers that verify
+struct Checker {
+    keypair: KeyPair,
+    monitor: Uuid,
+    /// Status this node's checks see
+    sees: MonitorStatus,
+    /// Changes its results after signing them
+    forger: bool,
+    own: Vec<(SystemTime, MonitorStatus)>,
+    peers: Vec<(String, SystemTime, MonitorStatus)>,
+    rejected: u32,
+}
+
+impl Checker {
+    fn new(monitor: Uuid, sees: MonitorStatus) -> Self {
+        Self {
+            keypair: generate_keypair(),
+            monitor,
+            sees,
+            forger: false,
+            own: Vec::new(),
+            peers: Vec::new(),
+            rejected: 0,
+        }
+    }
+
+    /// Agreement of our results between `from` and `until`
+    fn agreement(&self, from: Duration, until: Duration) -> Option<(MonitorStatus, u32, u32)> {
+        let window = wall(from)..wall(until);
+        let own: Vec<_> = self.own.iter().filter(|(at, _)| window.contains(at)).copied().collect();
+        let peers: Vec<_> =
+            self.peers.iter().filter(|(_, at, _)| window.contains(at)).cloned().collect();
+        compute_agreement(&own, &peers)
+    }
+}
+
+impl SimNode for Checker {
+    type Message = PeerResult;
+
+    fn on_start(&mut self, ctx: &mut Context<PeerResult>) {
+        ctx.set_timer(CHECK, 0);
+    }
+
+    fn on_message(&mut self, _ctx: &mut Context<PeerResult>, _from: NodeId, result: PeerResult) {
+        if verify_peer_result(&result).unwrap_or(false) {
+            let result = result.result;
+            self.peers.push((result.peer_id, result.timestamp, result.status));
+        } else {
+            self.rejected += 1;
+        }
+    }
+
+    fn on_timer(&mut self, ctx: &mut Context<PeerResult>, timer: u64) {
+        let mut result = check(&self.keypair, self.monitor, self.sees, ctx.now());
+        self.own.push((result.result.timestamp, result.result.status));
+        if self.forger {
+            result.result.status = MonitorStatus::Down;
+        }
+        ctx.broadcast(result);
+        ctx.set_timer(CHECK, timer);
+    }
+}
+
+#[test]
+fn test_agreement_across_peers() {
+    let monitor = Uuid::new_v4();
+    let mut sim = Simulation::new(7);
+    for node in 0..5 {
+        // Node 3's path to the target is broken; node 4 forges its results
+        let sees = if node == 3 { MonitorStatus::Down } else { MonitorStatus::Up };
+        let mut checker = Checker::new(monitor, sees);
+        checker.forger = node == 4;
+        sim.add_node(checker);
+    }
+    let link = LinkConfig::new(Duration::from_millis(40)).with_jitter(Duration::from_millis(20));
+    sim.apply(&Topology::full_mesh(5, link));
+    sim.start_all();
+    sim.schedule(Scenario::new().partition(
+        Duration::from_secs(5 * 60),
+        Duration::from_secs(10 * 60),
+        vec![vec![0, 3]],
+    ));
+    sim.run_until(Duration::from_secs(15 * 60));
+
+    let minutes = |m: u64| Duration::from_secs(m * 60);
+    let node = sim.node(0);
+    // Peers 1 and 2 agree, 3 does not and the forged results never count
+    assert_eq!(node.agreement(minutes(0), minutes(5)), Some((MonitorStatus::Up, 3, 2)));
+    assert!(
+        node.peers
+            .iter()
+            .all(|(peer, ..)| *peer != sim.node(4).keypair.public_key_hex())
+    );
+    assert!(node.rejected > 0);
+    // While partitioned only node 3 is heard from
+    assert_eq!(node.agreement(minutes(6), minutes(10)), Some((MonitorStatus::Up, 1, 0)));
+    assert_eq!(node.agreement(minutes(11), minutes(15)), Some((MonitorStatus::Up, 3, 2)));
+    // Node 3 sees every honest peer disagree with it
+    assert_eq!(sim.node(3).agreement(minutes(0), minutes(5)), Some((MonitorStatus::Down, 3, 0)));
+}
+
+/// Messages between a monitor's owner and its helpers
+#[derive(Clone)]
+enum Duty {
+    Request(Box<HelperAssignmentRequest>),
+    Result(Box<EncryptedResultMessage>),
+}
+
+/// Owns a private monitor and recruits the listed helpers for it, or helps
+/// whichever owner asks it to
+struct Peer {
+    keypair: KeyPair,
+    /// Private monitors, when this node owns any
+    monitors: Vec<Monitor>,
+    /// Helpers the owner lists
+    helpers: Vec<String>,
+    /// Owner-side assignments
+    held: Vec<HelperAssignment>,
+    /// Helper-side assignments
+    duties: Vec<HelperAssignment>,
+}
+
+impl Peer {
+    fn new() -> Self {
+        Self {
+            keypair: generate_keypair(),
+            monitors: Vec::new(),
+            helpers: Vec::new(),
+            held: Vec::new(),
+            duties: Vec::new(),
+        }
+    }
+
+    fn key(&self) -> String {
+        self.keypair.public_key_hex()
+    }
+
+    /// Owner-side assignments to a helper that are still valid at `now`
+    fn held_by(&self, helper: &str, now: Duration) -> Vec<&HelperAssignment> {
+        self.held
+            .iter()
+            .filter(|a| a.helper_peer_id == helper && !a.is_expired(wall(now)))
+            .collect()
+    }
+
+    fn recruit(&mut self, ctx: &mut Context<Duty>) {
+        let now = wall(ctx.now());
+        let round = plan(&self.monitors, &self.helpers, self.held.iter(), &self.key(), now);
+        self.held.retain(|a| !round.stale.contains(&a.assignment_id));
+        for assignment in round.requests {
+            let request = sign_assignment_request(&assignment, &self.keypair).unwrap();
+            if !self.held.iter().any(|a| a.assignment_id == assignment.assignment_id) {
+                self.held.push(assignment);
+            }
+            ctx.broadcast(Duty::Request(Box::new(request)));
+        }
+    }
+
+    fn accept(&mut self, ctx: &mut Context<Duty>, request: &HelperAssignmentRequest) {
+        if request.helper_peer_id != self.key() || !verify_assignment_request(request).unwrap() {
+            return;
+        }
+        let Some(duty) = request.to_helper_assignment(wall(ctx.now())) else {
+            return;
+        };
+        self.duties.retain(|d| d.assignment_id != duty.assignment_id);
+        self.duties.push(duty);
+    }
+
+    fn run_duties(&mut self, ctx: &mut Context<Duty>) {
+        let now = wall(ctx.now());
+        self.duties.retain(|duty| !duty.is_expired(now));
+        for duty in &self.duties {
+            let mut result = CheckResult::new(duty.monitor_uuid, duty.target.clone(), self.key());
+            result = result.success(80, Some(200));
+            result.timestamp = now;
+            let sealed = seal(duty, result, &self.keypair).unwrap();
+            ctx.broadcast(Duty::Result(Box::new(sealed)));
+        }
+    }
+
+    /// Confirm an assignment with the first result its helper sends
+    fn confirm(&mut self, ctx: &mut Context<Duty>, message: &EncryptedResultMessage) {
+        let Some(assignment) = self.held.iter_mut().find(|a| {
+            a.assignment_id == message.assignment_id && a.helper_peer_id == message.helper_peer_id
+        }) else {
+            return;
+        };
+        let Ok(result) = decrypt_result(&message.sealed, &self.keypair) else {
+            return;
+        };
+        let p2p_result = PeerResult {
+            signature: result.signature.clone(),
+            public_key: hex::decode(&message.helper_peer_id).ok(),
+            peer_id: result.peer_id.clone(),
+            result,
+            location: None,
+            received_at: wall(ctx.now()),
+            signature_valid: None,
+            identity_bound: None,
+        };
+        if verify_peer_result(&p2p_result).unwrap_or(false) {
+            assignment.status = "accepted".to_string();
+            assignment.last_result_at = Some(wall(ctx.now()));
+        }
+    }
+}
+
+/// Timers of a `Peer`
+const RECRUIT: u64 = 0;
+const RUN_DUTIES: u64 = 1;
+
+impl SimNode for Peer {
+    type Message = Duty;
+
+    fn on_start(&mut self, ctx: &mut Context<Duty>) {
+        ctx.set_timer(Duration::from_secs(1), RECRUIT);
+        ctx.set_timer(CHECK, RUN_DUTIES);
+    }
+
+    fn on_message(&mut self, ctx: &mut Context<Duty>, _from: NodeId, message: Duty) {
+        match message {
+            Duty::Request(request) => self.accept(ctx, &request),
+            Duty::Result(message) => self.confirm(ctx, &message),
+        }
+    }
+
+    fn on_timer(&mut self, ctx: &mut Context<Duty>, timer: u64) {
+        if timer == RECRUIT {
+            if !self.monitors.is_empty() {
+                self.recruit(ctx);
+            }
+            ctx.set_timer(ROUND, RECRUIT);
+        } else {
+            self.run_duties(ctx);
+            ctx.set_timer(CHECK, RUN_DUTIES);
+        }
+    }
+}
+
+#[test]
+fn test_helpers_are_recruited_and_reassigned() {
+    let mut sim = Simulation::new(11);
+    for _ in 0..4 {
+        sim.add_node(Peer::new());
+    }
+    let helper = |sim: &Simulation<Peer>, id| sim.node(id).key();
+    let (h1, h2, h3) = (helper(&sim, 1), helper(&sim, 2), helper(&sim, 3));
+
+    let mut monitor = Monitor::new("Intranet".to_string(), TARGET.to_string(), "https".to_string());
+    monitor.visibility = MonitorVisibility::Private;
+    let owner = sim.node_mut(0);
+    owner.monitors = vec![monitor];
+    owner.helpers = vec![h1.clone(), h2.clone()];
+
+    sim.apply(&Topology::full_mesh(4, LinkConfig::new(Duration::from_millis(30))));
+    sim.start_all();
+    sim.schedule(Scenario::new().at(Duration::from_secs(10 * 60), Action::Stop(2)));
+
+    // Both listed helpers take the monitor on and confirm it
+    sim.run_until(Duration::from_secs(10 * 60));
+    let now = sim.now();
+    for (id, key) in [(1, &h1), (2, &h2)] {
+        let held = sim.node(0).held_by(key, now);
+        assert_eq!(held.len(), 1);
+        assert!(held[0].is_accepted());
+        assert_eq!(sim.node(id).duties.len(), 1);
+        assert_eq!(sim.node(id).duties[0].assignment_id, held[0].assignment_id);
+    }
+    assert!(sim.node(3).duties.is_empty(), "Unlisted peers are not asked");
+
+    // Helper 1 is renewed before its assignment lapses; the lapsed assignment
+    // of helper 2, which went away, is replaced by one it never confirms
+    let later = Duration::from_secs(10 * 60) + 2 * ASSIGNMENT_TTL;
+    sim.run_until(later);
+    let owner = sim.node(0);
+    assert!(owner.held_by(&h1, later).iter().any(|a| a.is_accepted()));
+    let h2_held = owner.held_by(&h2, later);
+    assert!(!h2_held.is_empty() && h2_held.iter().all(|a| !a.is_accepted()));
+    assert!(owner.held.iter().all(|a| !a.is_expired(wall(later))), "Lapsed ones are dropped");
+
+    // Listing helper 3 instead moves the monitor to it
+    sim.node_mut(0).helpers = vec![h1.clone(), h3.clone()];
+    sim.start(2);
+    sim.run_for(2 * ROUND);
+    let now = sim.now();
+    let owner = sim.node(0);
+    assert!(owner.held.iter().all(|a| a.helper_peer_id != h2));
+    assert!(owner.held_by(&h3, now).iter().any(|a| a.is_accepted()));
+    assert_eq!(sim.node(3).duties.len(), 1);
+    assert!(sim.node(2).duties.is_empty(), "Helper 2's duty lapsed while it was down");
+}
+
+/// Results nodes share, and sync after missing them
+#[derive(Clone)]
+enum Sync {
+    Share(Box<PeerResult>),
+    Request { since: u64 },
+    Page(Vec<SyncedResult>),
+}
+
+/// Checks a monitor, shares its results and syncs the ones it missed from
+/// its peers whenever it starts
+struct Syncer {
+    keypair: KeyPair,
+    monitor: Uuid,
+    /// Changes the results it serves to syncing peers
+    forger: bool,
+    stored: Vec<models::PeerResult>,
+    last_round: Option<SystemTime>,
+    rejected: u32,
+}
+
+impl Syncer {
+    fn new(monitor: Uuid) -> Self {
+        Self {
+            keypair: generate_keypair(),
+            monitor,
+            forger: false,
+            stored: Vec::new(),
+            last_round: None,
+            rejected: 0,
+        }
+    }
+
+    fn store(&mut self, mut result: models::PeerResult) {
+        let known = self
+            .stored
+            .iter()
+            .any(|r| r.peer_id == result.peer_id && r.timestamp == result.timestamp);
+        if !known {
+            result.verified = true;
+            self.stored.push(result);
+        }
+    }
+
+    /// Signers and times of the stored results
+    fn held(&self) -> BTreeSet<(String, SystemTime)> {
+        self.stored.iter().map(|r| (r.peer_id.clone(), r.timestamp)).collect()
+    }
+}
+
+impl SimNode for Syncer {
+    type Message = Sync;
+
+    fn on_start(&mut self, ctx: &mut Context<Sync>) {
+        let now = wall(ctx.now());
+        let since = sync_since(self.last_round, now);
+        self.last_round = Some(now);
+        let since = since.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        ctx.broadcast(Sync::Request { since });
+        ctx.set_timer(CHECK, 0);
+    }
+
+    fn on_message(&mut self, ctx: &mut Context<Sync>, from: NodeId, message: Sync) {
+        match message {
+            Sync::Share(result) => {
+                if verify_peer_result(&result).unwrap_or(false) {
+                    self.store(models::PeerResult::from_p2p_result(&result).unwrap());
+                } else {
+                    self.rejected += 1;
+                }
+            }
+            Sync::Request { since } => {
+                let since = UNIX_EPOCH + Duration::from_secs(since);
+                let page = self
+                    .stored
+                    .iter()
+                    .filter(|r| r.verified && r.timestamp >= since)
+                    .map(|r| {
+                        let mut synced = SyncedResult::from_stored(r);
+                        if self.forger {
+                            synced.status = MonitorStatus::Down;
+                        }
+                        synced
+                    })
+                    .collect();
+                ctx.send(from, Sync::Page(page));
+            }
+            Sync::Page(page) => {
+                for synced in page {
+                    let stored = synced.to_stored(self.monitor, "peer", wall(ctx.now()));
+                    match stored {
+                        Some(result) if signed_by_peer(&result, TARGET) => self.store(result),
+                        _ => self.rejected += 1,
+                    }
+                }
+            }
+        }
+    }
+
+    fn on_timer(&mut self, ctx: &mut Context<Sync>, timer: u64) {
+        let result = check(&self.keypair, self.monitor, MonitorStatus::Up, ctx.now());
+        self.store(models::PeerResult::from_p2p_result(&result).unwrap());
+        ctx.broadcast(Sync::Share(Box::new(result)));
+        ctx.set_timer(CHECK, timer);
+    }
+}
+
+#[test]
+fn test_results_sync_catches_up_after_downtime() {
+    let monitor = Uuid::new_v4();
+    let mut sim = Simulation::new(13);
+    for node in 0..4 {
+        let mut syncer = Syncer::new(monitor);
+        syncer.forger = node == 2;
+        sim.add_node(syncer);
+    }
+    let link = LinkConfig::new(Duration::from_millis(25)).with_jitter(Duration::from_millis(50));
+    sim.apply(&Topology::full_mesh(4, link));
+    sim.start_all();
+    sim.schedule(Scenario::new().churn(
+        3,
+        Duration::from_secs(2 * 60 + 30),
+        Duration::from_secs(30 * 60),
+        Duration::from_secs(10 * 60),
+        Duration::from_secs(30 * 60),
+    ));
+
+    // Down from 2.5 to 12.5 minutes, node 3 misses what was shared meanwhile
+    sim.run_until(Duration::from_secs(12 * 60));
+    let missed = sim.node(0).held().difference(&sim.node(3).held()).count();
+    assert!(missed >= 3 * 9);
+
+    // On its restart it syncs; what the forger serves does not verify, but
+    // the honest peers serve the same results
+    sim.run_until(Duration::from_secs(13 * 60));
+    let node = sim.node(3);
+    let shared_before_restart: BTreeSet<_> = sim
+        .node(0)
+        .held()
+        .into_iter()
+        .filter(|(_, at)| *at <= wall(Duration::from_secs(12 * 60 + 30)))
+        .collect();
+    assert!(node.held().is_superset(&shared_before_restart));
+    assert!(node.rejected > 0);
+    assert!(node.stored.iter().all(|r| r.status == MonitorStatus::Up));
+}
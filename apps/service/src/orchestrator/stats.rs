@@ -0,0 +1,301 @@
+/// Network statistics tracker - counts checks and peers and persists snapshots
+///
+/// Other orchestrator tasks report what happened over a channel; the tracker
+/// owns the counters and writes a `NetworkStats` row once per configured
+/// interval when something changed. Every hour, rows older than the
+/// full-resolution window are rolled into hourly aggregates. The total peer
+/// count covers the peers seen in the last day.
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::bandwidth::{BandwidthBudget, BandwidthMeter};
+use super::capacity::HelperUtilization;
+use super::metrics::ServiceMetrics;
+use super::runtime::RuntimeSettings;
+use super::telemetry::TelemetryMetrics;
+use crate::cache::ExpiringMap;
+use crate::config::NetworkStatsConfig;
+use crate::database::Database;
+use crate::database::models::NetworkStats;
+use crate::monitoring::types::MonitorStatus;
+
+/// How often old snapshots are rolled up
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// How long a peer counts towards the total after it was last seen
+const PEERS_SEEN_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+/// Peers counted towards the total at most
+const PEERS_SEEN_CAPACITY: usize = 10_000;
+
+/// Something the stats tracker should count
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatsEvent {
+    /// A local check finished
+    CheckPerformed { status: MonitorStatus, latency_ms: Option<u64> },
+    /// A local result was published to peers
+    ResultShared,
+    /// A result from a peer was stored
+    CheckReceived { peer_id: String },
+    /// A peer connected
+    PeerConnected(String),
+    /// A peer disconnected
+    PeerDisconnected(String),
+    /// Helper capacity usage changed
+    HelperUtilization(HelperUtilization),
+    /// Cumulative bytes the P2P node sent and received
+    Traffic { total_bytes: u64 },
+    /// Cumulative lookups and hits of the seen-message cache
+    SeenMessages { lookups: u64, hits: u64 },
+}
+
+/// Running network counters
+#[derive(Debug, Clone)]
+pub struct NetworkCounters {
+    connected_peers: HashSet<String>,
+    peers_seen: ExpiringMap<String, ()>,
+    checks_performed: i64,
+    checks_received: i64,
+    helper: HelperUtilization,
+    bandwidth: BandwidthMeter,
+    seen_lookups: u64,
+    seen_hits: u64,
+}
+
+impl Default for NetworkCounters {
+    fn default() -> Self {
+        Self {
+            connected_peers: HashSet::new(),
+            peers_seen: ExpiringMap::new("peers_seen", PEERS_SEEN_CAPACITY, PEERS_SEEN_WINDOW),
+            checks_performed: 0,
+            checks_received: 0,
+            helper: HelperUtilization::default(),
+            bandwidth: BandwidthMeter::default(),
+            seen_lookups: 0,
+            seen_hits: 0,
+        }
+    }
+}
+
+impl NetworkCounters {
+    /// Apply a stats event to the counters
+    pub fn apply(&mut self, event: StatsEvent) {
+        match event {
+            StatsEvent::CheckPerformed { .. } => self.checks_performed += 1,
+            StatsEvent::ResultShared => {}
+            StatsEvent::CheckReceived { peer_id } => {
+                self.checks_received += 1;
+                self.peers_seen.insert(peer_id, (), Instant::now());
+            }
+            StatsEvent::PeerConnected(peer_id) => {
+                self.connected_peers.insert(peer_id.clone());
+                self.peers_seen.insert(peer_id, (), Instant::now());
+            }
+            StatsEvent::PeerDisconnected(peer_id) => {
+                self.connected_peers.remove(&peer_id);
+            }
+            StatsEvent::HelperUtilization(utilization) => self.helper = utilization,
+            StatsEvent::Traffic { total_bytes } => {
+                self.bandwidth.record_total(total_bytes, SystemTime::now());
+            }
+            StatsEvent::SeenMessages { lookups, hits } => {
+                self.seen_lookups = lookups;
+                self.seen_hits = hits;
+            }
+        }
+    }
+
+    /// Forget peers not seen within the window
+    pub fn expire(&mut self, now: Instant) {
+        self.peers_seen.expire(now);
+    }
+
+    /// Build a stats row for the current counters
+    pub fn snapshot(&self, timestamp: SystemTime) -> NetworkStats {
+        NetworkStats {
+            timestamp,
+            total_peers: self.peers_seen.len() as i64,
+            online_peers: self.connected_peers.len() as i64,
+            checks_performed: self.checks_performed,
+            checks_received: self.checks_received,
+            bandwidth_used_mb: self.bandwidth.used_mb() as i64,
+            helper_assignments: self.helper.active_assignments as i64,
+            helper_max_assignments: self.helper.max_assignments as i64,
+            helper_checks_last_hour: self.helper.busiest_owner_checks as i64,
+            helper_max_checks_per_hour: self.helper.max_checks_per_owner_per_hour as i64,
+            seen_lookups: self.seen_lookups as i64,
+            seen_hits: self.seen_hits as i64,
+        }
+    }
+}
+
+/// Task owning the network counters
+pub struct StatsTracker {
+    database: Arc<dyn Database>,
+    counters: NetworkCounters,
+    settings: watch::Receiver<RuntimeSettings>,
+    budget: BandwidthBudget,
+    metrics: TelemetryMetrics,
+    /// Metrics served to Prometheus, given every counter change
+    prometheus: Option<ServiceMetrics>,
+    config: NetworkStatsConfig,
+}
+
+impl StatsTracker {
+    /// Create a tracker persisting into the given database
+    ///
+    /// The tracker raises `budget` once today's traffic exceeds the configured
+    /// bandwidth limit.
+    pub fn new(
+        database: Arc<dyn Database>,
+        settings: watch::Receiver<RuntimeSettings>,
+        budget: BandwidthBudget,
+    ) -> Self {
+        Self {
+            database,
+            counters: NetworkCounters::default(),
+            settings,
+            budget,
+            metrics: TelemetryMetrics::new(),
+            prometheus: None,
+            config: NetworkStatsConfig::default(),
+        }
+    }
+
+    /// Use the given snapshot interval and full-resolution window
+    pub fn with_config(mut self, config: NetworkStatsConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Report the counters to the metrics endpoint
+    pub fn with_metrics(mut self, metrics: ServiceMetrics) -> Self {
+        self.prometheus = Some(metrics);
+        self
+    }
+
+    /// Spawn the tracker, returning the channel to report events on
+    ///
+    /// The task persists a final snapshot and exits once every sender is dropped.
+    pub fn spawn(mut self) -> (mpsc::Sender<StatsEvent>, JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::channel::<StatsEvent>(256);
+
+        let handle = tokio::spawn(async move {
+            let mut timer = tokio::time::interval(Duration::from_secs(
+                self.config.persist_interval_secs.max(1),
+            ));
+            let mut rollup_timer = tokio::time::interval(ROLLUP_INTERVAL);
+            let mut dirty = false;
+
+            loop {
+                tokio::select! {
+                    event = rx.recv() => match event {
+                        Some(event) => {
+                            let traffic = matches!(event, StatsEvent::Traffic { .. });
+                            self.counters.apply(event.clone());
+                            self.metrics.record(&event, self.counters.connected_peers.len());
+                            if let Some(prometheus) = &self.prometheus {
+                                prometheus.set_network(self.counters.snapshot(SystemTime::now()));
+                            }
+                            if traffic {
+                                self.update_budget();
+                            }
+                            dirty = true;
+                        }
+                        None => break,
+                    },
+                    _ = timer.tick() => {
+                        if dirty {
+                            self.counters.expire(Instant::now());
+                            self.persist().await;
+                            dirty = false;
+                        }
+                    }
+                    _ = rollup_timer.tick() => self.rollup().await,
+                }
+            }
+
+            if dirty {
+                self.persist().await;
+            }
+            debug!("Stats tracker stopped");
+        });
+
+        (tx, handle)
+    }
+
+    fn update_budget(&self) {
+        let used_mb = self.counters.bandwidth.used_mb();
+        let limit_mb = self.settings.borrow().max_bandwidth_mb_per_day;
+        if self.budget.update(used_mb, limit_mb) {
+            if self.budget.is_exhausted() {
+                info!("Daily bandwidth budget of {} MB used, throttling result sharing", limit_mb);
+            } else {
+                info!("Bandwidth budget available again, resuming result sharing");
+            }
+        }
+    }
+
+    async fn rollup(&self) {
+        let Some(before) = rollup_cutoff(SystemTime::now(), self.config.keep_hours) else {
+            return;
+        };
+        match self.database.rollup_network_stats(before).await {
+            Ok(0) => {}
+            Ok(rolled) => debug!("Rolled {} network stats snapshots into hourly stats", rolled),
+            Err(e) => warn!("Failed to roll up network stats: {}", e),
+        }
+    }
+
+    async fn persist(&self) {
+        let snapshot = self.counters.snapshot(SystemTime::now());
+        if let Err(e) = self.database.insert_network_stats(&snapshot).await {
+            warn!("Failed to persist network stats: {}", e);
+        }
+    }
+}
+
+/// Start of the hour `keep_hours` before `now`; snapshots before it are
+/// rolled up, so each hour is rolled up whole
+fn rollup_cutoff(now: SystemTime, keep_hours: u64) -> Option<SystemTime> {
+    let keep = Duration::from_secs(keep_hours.max(1) * 60 * 60);
+    let secs = now.checked_sub(keep)?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(UNIX_EPOCH + Duration::from_secs(secs / 3600 * 3600))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollup_cutoff() {
+        let now = UNIX_EPOCH + Duration::from_secs(100 * 3600 + 1234);
+        assert_eq!(rollup_cutoff(now, 24), Some(UNIX_EPOCH + Duration::from_secs(76 * 3600)));
+        // Less than a full hour is always kept
+        assert_eq!(rollup_cutoff(now, 0), Some(UNIX_EPOCH + Duration::from_secs(99 * 3600)));
+        assert_eq!(rollup_cutoff(UNIX_EPOCH, 1), None);
+    }
+
+    #[test]
+    fn test_network_counters() {
+        let mut counters = NetworkCounters::default();
+        counters
+            .apply(StatsEvent::CheckPerformed { status: MonitorStatus::Up, latency_ms: Some(12) });
+        counters.apply(StatsEvent::PeerConnected("a".to_string()));
+        counters.apply(StatsEvent::PeerConnected("b".to_string()));
+        counters.apply(StatsEvent::PeerDisconnected("a".to_string()));
+        counters.apply(StatsEvent::CheckReceived { peer_id: "c".to_string() });
+
+        let snapshot = counters.snapshot(SystemTime::now());
+        assert_eq!(snapshot.checks_performed, 1);
+        assert_eq!(snapshot.checks_received, 1);
+        assert_eq!(snapshot.online_peers, 1);
+        assert_eq!(snapshot.total_peers, 3);
+
+        // Peers drop out of the total a day after they were last seen
+        counters.expire(Instant::now() + PEERS_SEEN_WINDOW);
+        assert_eq!(counters.snapshot(SystemTime::now()).total_peers, 0);
+    }
+}
@@ -0,0 +1,304 @@
+/// Status page API - serves status pages in Uptime Kuma's JSON shape
+///
+/// `GET /api/status-page/<slug>` returns the page config, its open incident
+/// and one group listing the page's monitors; `GET
+/// /api/status-page/heartbeat/<slug>` returns their recent results and
+/// 24-hour uptime. Widgets, apps and scripts written against Uptime Kuma can
+/// read Uppe pages unchanged. Monitors are identified by their numeric id, as
+/// in Kuma, and times are UTC. Error messages in the heartbeat list are
+/// redacted like those shared with peers, as anyone may read a page.
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, web};
+use anyhow::Result;
+use serde_json::{Map, Value, json};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+use super::api_tls::ApiTls;
+use super::http::{self, HandlerResult, Reply};
+use crate::civil::civil_from_days;
+use crate::config::ApiScope;
+use crate::database::Database;
+use crate::database::models::{Incident, Monitor, MonitorResult, StatusPage};
+use crate::monitoring::types::MonitorStatus;
+use crate::redact::ErrorRedactor;
+
+/// Results per monitor in the heartbeat list, as in Kuma
+const HEARTBEATS: usize = 100;
+/// Window of the uptime in the heartbeat list, in hours
+const UPTIME_HOURS: u64 = 24;
+
+/// State of the status page handlers
+struct PageState {
+    database: Arc<dyn Database>,
+    redactor: Arc<ErrorRedactor>,
+}
+
+/// Serve the status page API on `bind` until the task is aborted, over TLS
+/// when `tls` is given
+///
+/// Status pages are public, so any origin may read them from a browser.
+pub async fn serve(
+    bind: &str,
+    tls: Option<Arc<ApiTls>>,
+    database: Arc<dyn Database>,
+    redactor: Arc<ErrorRedactor>,
+) -> Result<JoinHandle<()>> {
+    let state = web::Data::new(PageState { database, redactor });
+    let api = http::Api::new("Status page API", ApiScope::StatusPage, move |config| {
+        config
+            .app_data(state.clone())
+            .service(http::resource("/api/status-page/{slug}").route(web::get().to(page_handler)))
+            .service(
+                http::resource("/api/status-page/heartbeat/{slug}")
+                    .route(web::get().to(heartbeat_handler)),
+            );
+    });
+    http::serve(api.allow_any_origin(), bind, tls).await
+}
+
+async fn page_handler(state: web::Data<PageState>, slug: web::Path<String>) -> HandlerResult {
+    Ok(found(page(state.database.as_ref(), &slug).await?))
+}
+
+async fn heartbeat_handler(state: web::Data<PageState>, slug: web::Path<String>) -> HandlerResult {
+    Ok(found(heartbeat(state.database.as_ref(), &state.redactor, &slug).await?))
+}
+
+fn found(body: Option<Value>) -> Reply {
+    match body {
+        Some(body) => HttpResponse::Ok().json(body),
+        None => http::error(StatusCode::NOT_FOUND, "Status page not found"),
+    }
+}
+
+/// Active page with the given slug
+async fn find_page(database: &dyn Database, slug: &str) -> Result<Option<StatusPage>> {
+    Ok(database.get_status_page(slug).await?.filter(|page| page.is_active))
+}
+
+/// Body of `/api/status-page/<slug>`
+async fn page(database: &dyn Database, slug: &str) -> Result<Option<Value>> {
+    let Some(page) = find_page(database, slug).await? else {
+        return Ok(None);
+    };
+    let monitors = database.get_status_page_monitors(page.uuid).await?;
+    // The newest open incident of a monitor on the page is pinned
+    let incident = database.get_open_incidents().await?.into_iter().rev().find(|incident| {
+        incident
+            .monitor_uuid
+            .is_some_and(|uuid| monitors.iter().any(|m| m.uuid == uuid))
+    });
+
+    Ok(Some(page_body(&page, &monitors, incident.as_ref())))
+}
+
+/// Body of `/api/status-page/heartbeat/<slug>`
+async fn heartbeat(
+    database: &dyn Database,
+    redactor: &ErrorRedactor,
+    slug: &str,
+) -> Result<Option<Value>> {
+    let Some(page) = find_page(database, slug).await? else {
+        return Ok(None);
+    };
+
+    let mut heartbeats = Map::new();
+    let mut uptimes = Map::new();
+    let start = SystemTime::now() - Duration::from_secs(UPTIME_HOURS * 60 * 60);
+    for monitor in database.get_status_page_monitors(page.uuid).await? {
+        let id = monitor.id.unwrap_or_default();
+
+        let mut results = database.get_recent_results(monitor.uuid, HEARTBEATS).await?;
+        results.reverse();
+        let entries = results.iter().map(|result| heartbeat_entry(result, redactor)).collect();
+        heartbeats.insert(id.to_string(), entries);
+
+        let rollups = database.get_result_rollups(monitor.uuid, start, 60 * 60).await?;
+        let checks: u64 = rollups.iter().map(|r| r.checks).sum();
+        if checks > 0 {
+            let up: u64 = rollups.iter().map(|r| r.up).sum();
+            uptimes.insert(format!("{id}_{UPTIME_HOURS}"), json!(up as f64 / checks as f64));
+        }
+    }
+
+    Ok(Some(json!({ "heartbeatList": heartbeats, "uptimeList": uptimes })))
+}
+
+fn page_body(page: &StatusPage, monitors: &[Monitor], incident: Option<&Incident>) -> Value {
+    let monitor_list: Vec<Value> = monitors
+        .iter()
+        .map(|m| {
+            json!({
+                "id": m.id.unwrap_or_default(),
+                "name": m.name,
+                "sendUrl": 0,
+                "type": monitor_type(&m.check_type),
+            })
+        })
+        .collect();
+
+    json!({
+        "config": {
+            "slug": page.slug,
+            "title": page.title,
+            "description": page.description,
+            "icon": "/icon.svg",
+            "theme": "auto",
+            "published": page.is_active,
+            "showTags": false,
+            "domainNameList": [],
+            "customCSS": "",
+            "footerText": null,
+            "showPoweredBy": false,
+            "googleAnalyticsId": null,
+            "showCertificateExpiry": false,
+            "autoRefreshInterval": 300,
+        },
+        "incident": incident.map(|incident| json!({
+            "style": incident_style(&incident.severity),
+            "title": incident.title,
+            "content": format!("Status: {}", incident.status),
+            "pin": true,
+            "createdDate": kuma_time(incident.started_at),
+            "lastUpdatedDate": null,
+        })),
+        "publicGroupList": [{
+            "id": 1,
+            "name": "Services",
+            "weight": 1,
+            "monitorList": monitor_list,
+        }],
+        "maintenanceList": [],
+    })
+}
+
+fn heartbeat_entry(result: &MonitorResult, redactor: &ErrorRedactor) -> Value {
+    json!({
+        "status": kuma_status(result.status),
+        "time": kuma_time(result.timestamp),
+        "msg": redactor.redact_opt(result.error_message.as_deref()).unwrap_or_default(),
+        "ping": result.latency_ms,
+    })
+}
+
+/// Kuma's heartbeat status: 0 down, 1 up, 2 pending
+fn kuma_status(status: MonitorStatus) -> u8 {
+    match status {
+        MonitorStatus::Down => 0,
+        MonitorStatus::Up | MonitorStatus::Degraded => 1,
+        MonitorStatus::Unknown => 2,
+    }
+}
+
+/// Kuma's name for a check type
+fn monitor_type(check_type: &str) -> &str {
+    match check_type {
+        "http" | "https" => "http",
+        "tcp" => "port",
+        "icmp" => "ping",
+        "heartbeat" => "push",
+        other => other,
+    }
+}
+
+/// Kuma's incident style for a severity
+fn incident_style(severity: &str) -> &'static str {
+    match severity {
+        "critical" => "danger",
+        "major" => "warning",
+        _ => "info",
+    }
+}
+
+/// UTC time as Kuma formats it, e.g. "2024-01-31 12:00:00.000"
+fn kuma_time(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let secs_of_day = secs % 86_400;
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}.{:03}",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kuma_time() {
+        assert_eq!(kuma_time(UNIX_EPOCH), "1970-01-01 00:00:00.000");
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_208_000_250);
+        assert_eq!(kuma_time(time), "2024-02-29 12:00:00.250");
+        let time = UNIX_EPOCH + Duration::from_secs(951_868_799);
+        assert_eq!(kuma_time(time), "2000-02-29 23:59:59.000");
+    }
+
+    #[test]
+    fn test_page_body() {
+        let page = StatusPage::new("Acme".to_string(), "acme".to_string(), String::new());
+        let mut monitor =
+            Monitor::new("API".to_string(), "api.example.com:443".to_string(), "tcp".to_string());
+        monitor.id = Some(7);
+        let incident = Incident {
+            uuid: uuid::Uuid::new_v4(),
+            title: "API outage".to_string(),
+            status: "investigating".to_string(),
+            severity: "major".to_string(),
+            monitor_uuid: Some(monitor.uuid),
+            started_at: UNIX_EPOCH,
+            resolved_at: None,
+        };
+
+        let body = page_body(&page, &[monitor], Some(&incident));
+        assert_eq!(body["config"]["slug"], "acme");
+        assert_eq!(body["config"]["published"], true);
+        assert_eq!(body["incident"]["style"], "warning");
+        assert_eq!(body["incident"]["createdDate"], "1970-01-01 00:00:00.000");
+        let monitor = &body["publicGroupList"][0]["monitorList"][0];
+        assert_eq!(monitor["id"], 7);
+        assert_eq!(monitor["type"], "port");
+
+        let body = page_body(&page, &[], None);
+        assert!(body["incident"].is_null());
+        assert_eq!(body["publicGroupList"][0]["monitorList"], json!([]));
+    }
+
+    #[test]
+    fn test_heartbeat_entry_redacts_errors() {
+        let result = MonitorResult {
+            id: None,
+            monitor_uuid: uuid::Uuid::new_v4(),
+            timestamp: UNIX_EPOCH,
+            status: MonitorStatus::Down,
+            latency_ms: None,
+            status_code: None,
+            error_message: Some("connection refused (os error 111) to 10.0.3.7:8080".to_string()),
+            peer_id: String::new(),
+            signature: None,
+            created_at: UNIX_EPOCH,
+            city: None,
+            country: None,
+            region: None,
+            maintenance: false,
+        };
+
+        let entry = heartbeat_entry(&result, &ErrorRedactor::default());
+        assert_eq!(entry["status"], 0);
+        assert_eq!(entry["msg"], "connection refused (os error 111) to [internal-ip]:8080");
+    }
+
+    #[test]
+    fn test_kuma_status() {
+        assert_eq!(kuma_status(MonitorStatus::Down), 0);
+        assert_eq!(kuma_status(MonitorStatus::Degraded), 1);
+        assert_eq!(kuma_status(MonitorStatus::Unknown), 2);
+    }
+}
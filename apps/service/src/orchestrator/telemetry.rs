@@ -0,0 +1,83 @@
+/// Telemetry metrics - mirrors the stats tracker's counters as OpenTelemetry
+/// instruments
+///
+/// Instruments come from the global meter provider, which only exports when
+/// OTLP was configured at startup; otherwise recording is a no-op.
+use opentelemetry::KeyValue;
+use opentelemetry::global;
+use opentelemetry::metrics::{Counter, Gauge, Histogram};
+
+use super::stats::StatsEvent;
+
+/// Instruments recorded by the stats tracker
+#[derive(Debug, Clone)]
+pub struct TelemetryMetrics {
+    checks: Counter<u64>,
+    check_latency: Histogram<u64>,
+    results_shared: Counter<u64>,
+    peer_results: Counter<u64>,
+    connected_peers: Gauge<u64>,
+    p2p_traffic: Gauge<u64>,
+    helper_assignments: Gauge<u64>,
+}
+
+impl TelemetryMetrics {
+    /// Create the instruments on the global meter
+    pub fn new() -> Self {
+        let meter = global::meter("uppe-service");
+        Self {
+            checks: meter
+                .u64_counter("uppe.checks")
+                .with_description("Local checks performed")
+                .build(),
+            check_latency: meter
+                .u64_histogram("uppe.check.latency")
+                .with_description("Latency of successful local checks")
+                .with_unit("ms")
+                .build(),
+            results_shared: meter
+                .u64_counter("uppe.results.shared")
+                .with_description("Results published to the P2P network")
+                .build(),
+            peer_results: meter
+                .u64_counter("uppe.results.received")
+                .with_description("Results received from peers and stored")
+                .build(),
+            connected_peers: meter
+                .u64_gauge("uppe.peers.connected")
+                .with_description("Currently connected peers")
+                .build(),
+            p2p_traffic: meter
+                .u64_gauge("uppe.p2p.traffic")
+                .with_description("Bytes sent and received by the P2P node since it started")
+                .with_unit("By")
+                .build(),
+            helper_assignments: meter
+                .u64_gauge("uppe.helper.assignments")
+                .with_description("Monitors checked on behalf of other owners")
+                .build(),
+        }
+    }
+
+    /// Record an event; `connected_peers` is the count after applying it
+    pub fn record(&self, event: &StatsEvent, connected_peers: usize) {
+        match event {
+            StatsEvent::CheckPerformed { status, latency_ms } => {
+                self.checks.add(1, &[KeyValue::new("status", status.to_string())]);
+                if let Some(latency_ms) = latency_ms {
+                    self.check_latency.record(*latency_ms, &[]);
+                }
+            }
+            StatsEvent::ResultShared => self.results_shared.add(1, &[]),
+            StatsEvent::CheckReceived { .. } => self.peer_results.add(1, &[]),
+            StatsEvent::PeerConnected(_) | StatsEvent::PeerDisconnected(_) => {
+                self.connected_peers.record(connected_peers as u64, &[]);
+            }
+            StatsEvent::HelperUtilization(utilization) => {
+                self.helper_assignments.record(utilization.active_assignments as u64, &[]);
+            }
+            StatsEvent::Traffic { total_bytes } => self.p2p_traffic.record(*total_bytes, &[]),
+            StatsEvent::SeenMessages { .. } => {}
+        }
+    }
+}
@@ -0,0 +1,139 @@
+/// Monitor visibility - keeps private monitors off the public network
+///
+/// The manager tracks which monitors are private so the pipeline stops sharing
+/// their results. When a public monitor turns private it retracts what was
+/// shared before: peers get a signed retraction on the results topic, which
+/// also replaces the monitor's DHT record and makes helpers drop their duties
+/// for it, and our earlier results are marked as retracted. Our own
+/// assignments are kept in step with visibility by the helper recruiter.
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::audit::AuditLog;
+use super::runtime::RuntimeSettings;
+use crate::crypto::{KeyPair, sign_monitor_retraction};
+use crate::database::Database;
+use crate::database::models::{AuditKind, Monitor};
+use crate::p2p::P2PHandle;
+
+/// How often visibility changes are picked up
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Monitors whose results must not be shared, shared with the result pipeline
+pub type PrivateMonitors = Arc<RwLock<HashSet<Uuid>>>;
+
+/// Task applying monitor visibility changes
+pub struct VisibilityManager {
+    database: Arc<dyn Database>,
+    keypair: Arc<KeyPair>,
+    p2p: P2PHandle,
+    private: PrivateMonitors,
+    audit: AuditLog,
+}
+
+impl VisibilityManager {
+    /// Create a manager updating the given private monitor set
+    pub fn new(
+        database: Arc<dyn Database>,
+        keypair: Arc<KeyPair>,
+        p2p: P2PHandle,
+        private: PrivateMonitors,
+        audit: AuditLog,
+    ) -> Self {
+        Self { database, keypair, p2p, private, audit }
+    }
+
+    /// Spawn the manager; it stops once the settings channel closes
+    pub fn spawn(self, mut settings: watch::Receiver<RuntimeSettings>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(POLL_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = timer.tick() => {}
+                    changed = settings.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+
+                self.refresh_private().await;
+                // Retractions are published while sharing is on, so peers
+                // that saw our results also see them withdrawn
+                if settings.borrow().p2p_sharing {
+                    self.retract_pending().await;
+                }
+            }
+        })
+    }
+
+    async fn refresh_private(&self) {
+        match self.database.get_private_monitor_uuids().await {
+            Ok(uuids) => *self.private.write().unwrap() = uuids.into_iter().collect(),
+            Err(e) => warn!("Failed to load private monitors: {}", e),
+        }
+    }
+
+    async fn retract_pending(&self) {
+        let monitors = match self.database.get_monitors_to_retract().await {
+            Ok(monitors) => monitors,
+            Err(e) => {
+                warn!("Failed to load monitors to retract: {}", e);
+                return;
+            }
+        };
+
+        for monitor in monitors {
+            if let Err(e) = self.retract(&monitor).await {
+                // Left pending, so the next poll tries again
+                warn!("Failed to retract results of monitor {}: {}", monitor.uuid, e);
+            }
+        }
+    }
+
+    async fn retract(&self, monitor: &Monitor) -> anyhow::Result<()> {
+        let now = SystemTime::now();
+        let retraction = sign_monitor_retraction(monitor.uuid, &self.keypair, now)?;
+        self.p2p.publish_retraction(retraction).await?;
+
+        let retracted = self.database.retract_monitor_results(monitor.uuid, now).await?;
+        info!("Monitor {} turned private: retracted {} result(s)", monitor.uuid, retracted);
+        self.audit
+            .record(
+                AuditKind::AdminAction,
+                None,
+                format!("Retracted {} shared result(s) of monitor {}", retracted, monitor.uuid),
+            )
+            .await;
+
+        Ok(())
+    }
+}
+
+/// Whether results of a monitor may be shared
+pub fn is_shareable(private: &PrivateMonitors, monitor_id: Uuid) -> bool {
+    !private.read().unwrap().contains(&monitor_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_private_monitors_are_not_shareable() {
+        let private = PrivateMonitors::default();
+        let monitor_id = Uuid::new_v4();
+        assert!(is_shareable(&private, monitor_id));
+
+        private.write().unwrap().insert(monitor_id);
+        assert!(!is_shareable(&private, monitor_id));
+        assert!(is_shareable(&private, Uuid::new_v4()));
+    }
+}
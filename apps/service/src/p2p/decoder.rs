@@ -0,0 +1,450 @@
+/// Gossip decoder - parses and verifies gossip messages off the P2P event loop
+///
+/// The event loop only hands the raw message over; JSON parsing and result
+/// signature checks run on blocking workers, at most `GOSSIP_WORKERS` at a
+/// time. Decoded events leave in the order the messages arrived, so the
+/// handler sees the same sequence as before. The intake channel is bounded:
+/// once every worker is busy and the queue is full, the event loop waits.
+/// Results already in the seen-message cache are dropped before their
+/// signature is checked. Identity bindings are recorded here, and results and
+/// assignment requests are marked with whether their publisher bound the key
+/// they claim.
+use futures::StreamExt;
+use futures::stream::FuturesOrdered;
+use peerup::ControlMessage;
+use std::time::{Instant, SystemTime};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use super::identity::SharedIdentities;
+use super::messages::{
+    EncryptedResultMessage, HelperAssignmentRequest, IdentityBinding, MembershipAnnouncement,
+    MonitorRetraction, P2PEvent, PayloadSchema, SCHEMA_VERSION, SignedMessage,
+};
+use super::seen::{SeenKey, SharedSeenMessages};
+use crate::crypto::verify_peer_result;
+use crate::queue::QueueSender;
+
+/// Messages decoded at the same time
+pub const GOSSIP_WORKERS: usize = 4;
+/// Messages waiting for a worker
+const QUEUE_SIZE: usize = 256;
+
+/// A gossip message as it came off the swarm
+pub struct RawGossip {
+    /// libp2p peer that forwarded the message
+    pub peer_id: String,
+    /// libp2p peer that published the message, as its gossip signature proves
+    pub source: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// Spawn the decoder, returning the sender the event loop feeds it through
+///
+/// The task decodes what is queued and exits once the sender is dropped.
+pub fn spawn(
+    event_tx: QueueSender<P2PEvent>,
+    seen: SharedSeenMessages,
+    identities: SharedIdentities,
+    workers: usize,
+) -> (mpsc::Sender<RawGossip>, JoinHandle<()>) {
+    let (tx, mut rx) = mpsc::channel::<RawGossip>(QUEUE_SIZE);
+    let workers = workers.max(1);
+
+    let handle = tokio::spawn(async move {
+        let mut pending = FuturesOrdered::new();
+        let mut open = true;
+
+        while open || !pending.is_empty() {
+            tokio::select! {
+                // Hand finished messages on first so the queue keeps moving
+                biased;
+                Some(decoded) = pending.next(), if !pending.is_empty() => {
+                    if let Ok(Some(event)) = decoded
+                        && event_tx.send(event).await.is_err()
+                    {
+                        break;
+                    }
+                }
+                message = rx.recv(), if open && pending.len() < workers => match message {
+                    Some(message) => {
+                        let seen = seen.clone();
+                        let identities = identities.clone();
+                        pending.push_back(tokio::task::spawn_blocking(move || {
+                            decode(message, &seen, &identities)
+                        }));
+                    }
+                    None => open = false,
+                },
+            }
+        }
+        tracing::debug!("Gossip decoder stopped");
+    });
+
+    (tx, handle)
+}
+
+/// Event for a gossip message, `None` if it is none of ours
+fn decode(
+    message: RawGossip,
+    seen: &SharedSeenMessages,
+    identities: &SharedIdentities,
+) -> Option<P2PEvent> {
+    let RawGossip { peer_id, source, data } = message;
+    let binding_of = |app_peer_id: &str| {
+        let source = source.as_deref()?;
+        identities.lock().unwrap().check(source, app_peer_id, Instant::now())
+    };
+
+    if let Some(ControlMessage::Goodbye { peer_id }) = ControlMessage::parse(&data) {
+        tracing::info!("Peer {} is shutting down", peer_id);
+        Some(P2PEvent::PeerDisconnected(peer_id))
+    } else if let Ok(binding) = serde_json::from_slice::<IdentityBinding>(&data) {
+        note_schema("identity binding", &peer_id, &binding.schema);
+        let accepted = source.as_deref().is_some_and(|source| {
+            identities
+                .lock()
+                .unwrap()
+                .accept(&binding, source, SystemTime::now(), Instant::now())
+        });
+        if accepted {
+            tracing::debug!("Peer {} is bound to {}", binding.libp2p_peer_id, binding.app_peer_id);
+        } else {
+            tracing::warn!(
+                "Rejected identity binding of {} to {} from {:?}",
+                binding.libp2p_peer_id,
+                binding.app_peer_id,
+                source
+            );
+        }
+        None
+    } else if let Ok(encrypted) = serde_json::from_slice::<EncryptedResultMessage>(&data) {
+        note_schema("encrypted result", &peer_id, &encrypted.schema);
+        Some(P2PEvent::EncryptedResultReceived { peer_id, message: Box::new(encrypted) })
+    } else if let Ok(retraction) = serde_json::from_slice::<MonitorRetraction>(&data) {
+        note_schema("retraction", &peer_id, &retraction.schema);
+        Some(P2PEvent::MonitorRetracted { peer_id, retraction: Box::new(retraction) })
+    } else if let Ok(announcement) = serde_json::from_slice::<MembershipAnnouncement>(&data) {
+        note_schema("membership announcement", &peer_id, &announcement.schema);
+        Some(P2PEvent::MembershipAnnounced { peer_id, announcement: Box::new(announcement) })
+    } else if let Ok(request) = serde_json::from_slice::<HelperAssignmentRequest>(&data) {
+        note_schema("assignment request", &peer_id, &request.schema);
+        let owner_bound = binding_of(&request.owner_peer_id) == Some(true);
+        Some(P2PEvent::HelperAssignmentRequested {
+            peer_id,
+            request: Box::new(request),
+            owner_bound,
+        })
+    } else if let Some(signed_msg) = SignedMessage::parse(&data) {
+        note_schema("result", &peer_id, &signed_msg.schema);
+        let key = SeenKey::of(&signed_msg.result);
+        if let Some(key) = &key
+            && seen.lock().unwrap().check(key)
+        {
+            return None;
+        }
+
+        let mut result = super::network::peer_result(signed_msg);
+        // A result that cannot be checked at all is left to the handler to report
+        result.signature_valid = verify_peer_result(&result).ok();
+        result.identity_bound = binding_of(&result.peer_id);
+        // Only genuine results are remembered, so a forged copy cannot shadow one
+        if result.signature_valid == Some(true)
+            && let Some(key) = key
+        {
+            seen.lock().unwrap().insert(key);
+        }
+        Some(P2PEvent::ResultReceived { peer_id, result: Box::new(result) })
+    } else {
+        tracing::debug!("Dropping gossip message from {} that none of our payloads match", peer_id);
+        None
+    }
+}
+
+/// Log payloads written by newer nodes, which may carry fields we don't know
+fn note_schema(payload: &str, peer_id: &str, schema: &PayloadSchema) {
+    if schema.is_newer() {
+        tracing::debug!(
+            "Read {} from {} written with schema version {} (ours is {}), keeping {} unknown \
+             fields",
+            payload,
+            peer_id,
+            schema.schema_version,
+            SCHEMA_VERSION,
+            schema.unknown.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::QueueConfig;
+    use crate::crypto::keys::{KeyPair, generate_keypair};
+    use crate::crypto::sign_result;
+    use crate::monitoring::types::{CertificateInfo, CheckResult, MonitorStatus};
+    use crate::p2p::messages::{SIGNED_RESULT_KIND, SignedFields};
+    use crate::queue::bounded;
+
+    fn signed_result(keypair: &KeyPair, latency_ms: u64) -> Vec<u8> {
+        let mut result = CheckResult::new(
+            uuid::Uuid::new_v4(),
+            "https://example.com".to_string(),
+            "test-peer".to_string(),
+        );
+        result.status = MonitorStatus::Up;
+        result.latency_ms = Some(latency_ms);
+        result.signature = Some(sign_result(&result, keypair).unwrap());
+        let message = SignedMessage::new(result, keypair.public_key_bytes(), None);
+        serde_json::to_vec(&message).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_decodes_in_order_and_verifies() {
+        let keypair = generate_keypair();
+        let (event_tx, mut event_rx) = bounded("test", QueueConfig::default());
+        let (tx, handle) =
+            spawn(event_tx, SharedSeenMessages::default(), SharedIdentities::default(), 3);
+
+        for latency in 0..20 {
+            let mut data = signed_result(&keypair, latency);
+            // Every fifth result is tampered with after signing
+            if latency % 5 == 0 {
+                let text = String::from_utf8(data).unwrap();
+                data = text.replace("\"up\"", "\"down\"").into_bytes();
+            }
+            tx.send(RawGossip { peer_id: "peer".to_string(), source: None, data })
+                .await
+                .unwrap();
+        }
+        tx.send(RawGossip { peer_id: "peer".to_string(), source: None, data: b"noise".to_vec() })
+            .await
+            .unwrap();
+        drop(tx);
+        handle.await.unwrap();
+
+        for latency in 0..20 {
+            match event_rx.recv().await {
+                Some(P2PEvent::ResultReceived { result, .. }) => {
+                    assert_eq!(result.result.latency_ms, Some(latency));
+                    assert_eq!(result.signature_valid, Some(latency % 5 != 0));
+                }
+                other => panic!("Unexpected event: {other:?}"),
+            }
+        }
+        assert!(event_rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_decodes_binary_results() {
+        let keypair = generate_keypair();
+        let (event_tx, mut event_rx) = bounded("test", QueueConfig::default());
+        let (tx, handle) =
+            spawn(event_tx, SharedSeenMessages::default(), SharedIdentities::default(), 1);
+
+        let json = signed_result(&keypair, 25);
+        let message: SignedMessage = serde_json::from_slice(&json).unwrap();
+        let data = message.encode(&mut peerup::WireEncoder::new()).unwrap();
+        assert!(data.len() < json.len());
+        tx.send(RawGossip { peer_id: "peer".to_string(), source: None, data })
+            .await
+            .unwrap();
+        drop(tx);
+        handle.await.unwrap();
+
+        match event_rx.recv().await {
+            Some(P2PEvent::ResultReceived { result, .. }) => {
+                assert_eq!(result.result.latency_ms, Some(25));
+                assert_eq!(result.signature_valid, Some(true));
+            }
+            other => panic!("Unexpected event: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_reads_other_schema_versions() {
+        let keypair = generate_keypair();
+        let message: SignedMessage = serde_json::from_slice(&signed_result(&keypair, 5)).unwrap();
+        assert_eq!(message.schema.schema_version, SCHEMA_VERSION);
+
+        // Binary results of nodes that predate versioning end after the location
+        let r = &message.result;
+        let result = (
+            &r.monitor_id,
+            &r.target,
+            &r.timestamp,
+            &r.status,
+            &r.latency_ms,
+            &r.status_code,
+            &r.error_message,
+            &r.peer_id,
+            &r.signature,
+        );
+        let old = (result, &message.public_key, &message.location);
+        let data = peerup::WireEncoder::new().encode(SIGNED_RESULT_KIND, &old).unwrap().to_vec();
+        let parsed = SignedMessage::parse(&data).unwrap();
+        assert_eq!(parsed.schema.schema_version, 0);
+        assert_eq!(parsed.result.latency_ms, Some(5));
+
+        // Fields a newer node appended are kept and encoded back
+        let mut newer = message.clone();
+        newer.schema.schema_version = SCHEMA_VERSION + 1;
+        newer.unknown_wire = vec![1, 2, 3];
+        let data = newer.encode(&mut peerup::WireEncoder::new()).unwrap();
+        let parsed = SignedMessage::parse(&data).unwrap();
+        assert!(parsed.schema.is_newer());
+        assert_eq!(parsed.unknown_wire, [1, 2, 3]);
+        assert_eq!(parsed.encode(&mut peerup::WireEncoder::new()).unwrap(), data);
+
+        // And so are unknown JSON fields
+        let mut json: serde_json::Value = serde_json::to_value(&message).unwrap();
+        json["schema_version"] = (SCHEMA_VERSION + 1).into();
+        json["attestation"] = "tpm".into();
+        let parsed: SignedMessage = serde_json::from_value(json).unwrap();
+        assert!(parsed.schema.is_newer());
+        assert_eq!(parsed.schema.unknown["attestation"], "tpm");
+        assert_eq!(serde_json::to_value(&parsed).unwrap()["attestation"], "tpm");
+        assert!(verify_peer_result(&crate::p2p::network::peer_result(parsed)).unwrap());
+    }
+
+    #[test]
+    fn test_binary_result_layout() {
+        let mut result = CheckResult::new(
+            uuid::Uuid::from_u128(1),
+            "https://a.example".to_string(),
+            "p".to_string(),
+        );
+        result.timestamp = std::time::UNIX_EPOCH + std::time::Duration::from_secs(300);
+        result.status = MonitorStatus::Up;
+        result.latency_ms = Some(5);
+        result.signature = Some(vec![7]);
+        result.certificate = Some(CertificateInfo {
+            days_until_expiry: 3,
+            not_after: 4,
+            issuer: "i".to_string(),
+            chain_valid: true,
+        });
+        result.maintenance = true;
+        let message = SignedMessage::new(result, [9; 32], None);
+
+        // The fields of version 0, the schema version, then the fields added since
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            183, SIGNED_RESULT_KIND,
+            16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+            17, b'h', b't', b't', b'p', b's', b':', b'/', b'/',
+            b'a', b'.', b'e', b'x', b'a', b'm', b'p', b'l', b'e',
+            172, 2, 0,
+            0,
+            1, 5,
+            0,
+            0,
+            1, b'p',
+            1, 1, 7,
+            9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9,
+            9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9,
+            0,
+            2,
+            1, 6, 8, 1, b'i', 1,
+            1,
+            0,
+        ];
+        let data = message.encode(&mut peerup::WireEncoder::new()).unwrap();
+        assert_eq!(data, expected);
+
+        let parsed = SignedMessage::parse(expected).unwrap();
+        assert_eq!(parsed.schema.schema_version, 2);
+        assert!(parsed.unknown_wire.is_empty());
+        assert_eq!(
+            serde_json::to_value(&parsed.result).unwrap(),
+            serde_json::to_value(&message.result).unwrap()
+        );
+        assert_eq!(parsed.encode(&mut peerup::WireEncoder::new()).unwrap(), expected);
+
+        // A version 1 node stops after the schema version, keeping the rest
+        let (_, rest) = peerup::protocol::wire::decode_prefix::<(SignedFields, u32)>(
+            SIGNED_RESULT_KIND,
+            expected,
+        )
+        .unwrap();
+        assert_eq!(rest, [1, 6, 8, 1, b'i', 1, 1, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_skips_seen_results() {
+        let keypair = generate_keypair();
+        let (event_tx, mut event_rx) = bounded("test", QueueConfig::default());
+        let seen = SharedSeenMessages::default();
+        // One worker, so the copy is decoded after the original was remembered
+        let (tx, handle) = spawn(event_tx, seen.clone(), SharedIdentities::default(), 1);
+
+        let data = signed_result(&keypair, 10);
+        tx.send(RawGossip { peer_id: "peer".to_string(), source: None, data: data.clone() })
+            .await
+            .unwrap();
+        tx.send(RawGossip { peer_id: "other".to_string(), source: None, data })
+            .await
+            .unwrap();
+        drop(tx);
+        handle.await.unwrap();
+
+        assert!(matches!(event_rx.recv().await, Some(P2PEvent::ResultReceived { .. })));
+        assert!(event_rx.recv().await.is_none());
+        assert_eq!(seen.lock().unwrap().counts(), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_checks_identity_bindings() {
+        let keypair = generate_keypair();
+        let (event_tx, mut event_rx) = bounded("test", QueueConfig::default());
+        let (tx, handle) =
+            spawn(event_tx, SharedSeenMessages::default(), SharedIdentities::default(), 1);
+        let signed = |latency_ms| {
+            let mut result = CheckResult::new(
+                uuid::Uuid::new_v4(),
+                "https://example.com".to_string(),
+                keypair.public_key_hex(),
+            );
+            result.latency_ms = Some(latency_ms);
+            result.signature = Some(sign_result(&result, &keypair).unwrap());
+            let public_key = keypair.public_key_bytes();
+            serde_json::to_vec(&SignedMessage::new(result, public_key, None)).unwrap()
+        };
+        let gossip = |source: &str, data| RawGossip {
+            peer_id: "relay".to_string(),
+            source: Some(source.to_string()),
+            data,
+        };
+
+        // The binding is only taken from the peer it names
+        let binding =
+            crate::crypto::sign_identity_binding("12D3KooWA", &keypair, SystemTime::now()).unwrap();
+        let binding = serde_json::to_vec(&binding).unwrap();
+        tx.send(gossip("12D3KooWB", binding.clone())).await.unwrap();
+        tx.send(gossip("12D3KooWA", binding)).await.unwrap();
+        // Results claiming the key, published by the bound peer, a peer bound
+        // to another key and one that bound none
+        let other = generate_keypair();
+        let other_binding =
+            crate::crypto::sign_identity_binding("12D3KooWB", &other, SystemTime::now()).unwrap();
+        tx.send(gossip("12D3KooWB", serde_json::to_vec(&other_binding).unwrap()))
+            .await
+            .unwrap();
+        tx.send(gossip("12D3KooWA", signed(10))).await.unwrap();
+        tx.send(gossip("12D3KooWB", signed(20))).await.unwrap();
+        tx.send(gossip("12D3KooWC", signed(30))).await.unwrap();
+        drop(tx);
+        handle.await.unwrap();
+
+        for bound in [Some(true), Some(false), None] {
+            match event_rx.recv().await {
+                Some(P2PEvent::ResultReceived { result, .. }) => {
+                    assert_eq!(result.signature_valid, Some(true));
+                    assert_eq!(result.identity_bound, bound);
+                }
+                other => panic!("Unexpected event: {other:?}"),
+            }
+        }
+        assert!(event_rx.recv().await.is_none());
+    }
+}
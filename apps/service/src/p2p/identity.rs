@@ -0,0 +1,101 @@
+/// Peer identities - which app key each libp2p peer proved to hold
+///
+/// Results and helper assignments name their author by Ed25519 app key, while
+/// gossip is authenticated with the libp2p key of the node that published it.
+/// Every node signs an `IdentityBinding` of its libp2p peer ID with its app key
+/// and publishes it whenever a peer connects. The decoder accepts a binding
+/// only from the libp2p peer it names, and checks the claimed authors of later
+/// results and assignment requests against the bindings it accepted.
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::messages::IdentityBinding;
+use crate::cache::ExpiringMap;
+use crate::crypto::verify_identity_binding;
+
+/// How long an accepted binding is trusted; nodes republish theirs on connect
+const BINDING_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Oldest binding accepted, so a stale one cannot be replayed for long
+const BINDING_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// How far in the future a binding may be dated, allowing for clock skew
+const BINDING_MAX_SKEW: Duration = Duration::from_secs(5 * 60);
+/// Bindings remembered
+const BINDINGS_CAPACITY: usize = 10_000;
+
+/// Bindings shared by the decoder's workers
+pub type SharedIdentities = Arc<Mutex<PeerIdentities>>;
+
+/// App peer ID each libp2p peer proved to hold
+#[derive(Debug)]
+pub struct PeerIdentities {
+    bound: ExpiringMap<String, String>,
+}
+
+impl Default for PeerIdentities {
+    fn default() -> Self {
+        Self { bound: ExpiringMap::new("identity_bindings", BINDINGS_CAPACITY, BINDING_TTL) }
+    }
+}
+
+impl PeerIdentities {
+    /// Accept a binding published by `source`; returns whether it checked out
+    ///
+    /// The binding must name `source` as its libp2p peer, be signed by the app
+    /// key it names and have been issued within the last day.
+    pub fn accept(
+        &mut self,
+        binding: &IdentityBinding,
+        source: &str,
+        now: SystemTime,
+        at: Instant,
+    ) -> bool {
+        let now_secs = now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let fresh = binding.issued_at <= now_secs + BINDING_MAX_SKEW.as_secs()
+            && now_secs.saturating_sub(binding.issued_at) <= BINDING_MAX_AGE.as_secs();
+        let valid = binding.libp2p_peer_id == source
+            && fresh
+            && verify_identity_binding(binding).unwrap_or(false);
+        if valid {
+            self.bound.insert(source.to_string(), binding.app_peer_id.to_lowercase(), at);
+        }
+        valid
+    }
+
+    /// Whether the libp2p peer proved it holds the key of `app_peer_id`;
+    /// `None` if it has not bound any key
+    pub fn check(&self, libp2p_peer_id: &str, app_peer_id: &str, at: Instant) -> Option<bool> {
+        self.bound
+            .get(&libp2p_peer_id.to_string(), at)
+            .map(|bound| *bound == app_peer_id.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::keys::generate_keypair;
+    use crate::crypto::sign_identity_binding;
+
+    #[test]
+    fn test_accept_binding() {
+        let keypair = generate_keypair();
+        let app_peer_id = keypair.public_key_hex();
+        let now = SystemTime::now();
+        let at = Instant::now();
+        let binding = sign_identity_binding("12D3KooWA", &keypair, now).unwrap();
+        let mut identities = PeerIdentities::default();
+
+        // Only the libp2p peer a binding names may publish it
+        assert!(!identities.accept(&binding, "12D3KooWB", now, at));
+        assert_eq!(identities.check("12D3KooWB", &app_peer_id, at), None);
+
+        assert!(identities.accept(&binding, "12D3KooWA", now, at));
+        assert_eq!(identities.check("12D3KooWA", &app_peer_id, at), Some(true));
+        let other = generate_keypair().public_key_hex();
+        assert_eq!(identities.check("12D3KooWA", &other, at), Some(false));
+
+        // Stale bindings are refused, and accepted ones run out
+        assert!(!identities.accept(&binding, "12D3KooWA", now + BINDING_MAX_AGE * 2, at));
+        assert_eq!(identities.check("12D3KooWA", &app_peer_id, at + BINDING_TTL), None);
+    }
+}
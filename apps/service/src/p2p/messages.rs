@@ -1,7 +1,80 @@
 /// P2P messaging types for communication between the node and service
 use serde::{Deserialize, Serialize};
 
-use crate::monitoring::types::CheckResult;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use peerup::protocol::{WireEncoder, wire};
+use peerup::{ProbeRequest, ProbeResponse, ResultsSyncRequest, ResultsSyncResponse};
+
+use crate::crypto::SealedResult;
+use crate::database::models::{
+    AssignmentRole, HelperAssignment, Monitor, NotificationChannel, NotificationRoute, Setting,
+};
+use crate::location::LocationClaim;
+use crate::monitoring::types::{CertificateInfo, CheckResult, MonitorStatus};
+
+/// DHT key prefix under which the latest shared result of each monitor is stored
+pub const OWNER_RESULTS_KEY_PREFIX: &str = "uppe/results/";
+
+/// DHT key holding the latest shared result for a monitor
+pub fn owner_results_key(monitor_id: &Uuid) -> String {
+    format!("{OWNER_RESULTS_KEY_PREFIX}{monitor_id}")
+}
+
+/// DHT key prefix under which the latest shared result for each target host
+/// is stored, for nodes following the host without checking it
+pub const TARGET_RESULTS_KEY_PREFIX: &str = "uppe/targets/";
+
+/// DHT key holding the latest shared result for a target host
+pub fn target_results_key(host: &str) -> String {
+    format!("{TARGET_RESULTS_KEY_PREFIX}{host}")
+}
+
+/// Gossip topic prefix of clusters; each cluster's topic is derived from its key
+pub const CLUSTER_TOPIC_PREFIX: &str = "uppe/cluster/";
+
+/// DHT key prefix records published for debugging are namespaced under, so
+/// they cannot overwrite result records
+pub const DEBUG_KEY_PREFIX: &str = "uppe/debug/";
+
+/// DHT key a debug record is stored under
+pub fn debug_key(key: &str) -> String {
+    format!("{DEBUG_KEY_PREFIX}{key}")
+}
+
+/// Schema version of the payloads this node gossips and stores in the DHT
+///
+/// Raise it when a payload gains fields. Payloads of any version are read:
+/// fields this node doesn't know are kept in `PayloadSchema::unknown` rather
+/// than failing the payload, so mixed networks keep hearing each other.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Schema version and unknown fields of a gossiped or DHT-stored payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PayloadSchema {
+    /// Version the sender wrote; 0 from nodes that predate versioning
+    #[serde(default)]
+    pub schema_version: u32,
+    /// Fields of a newer schema, kept so the payload encodes back intact
+    #[serde(flatten)]
+    pub unknown: BTreeMap<String, serde_json::Value>,
+}
+
+impl PayloadSchema {
+    /// Whether the payload was written by a newer node than this one
+    pub fn is_newer(&self) -> bool {
+        self.schema_version > SCHEMA_VERSION
+    }
+}
+
+impl Default for PayloadSchema {
+    /// The schema of payloads this node writes
+    fn default() -> Self {
+        Self { schema_version: SCHEMA_VERSION, unknown: BTreeMap::new() }
+    }
+}
 
 /// Signed message published to the P2P network
 /// This wraps a CheckResult with signature and public key for verification
@@ -11,21 +84,410 @@ pub struct SignedMessage {
     pub result: CheckResult,
     /// Ed25519 public key of the sender (32 bytes)
     pub public_key: [u8; 32],
+    /// Sender's signed location, if it has one to share
+    #[serde(default)]
+    pub location: Option<LocationClaim>,
+    #[serde(flatten)]
+    pub schema: PayloadSchema,
+    /// Bytes a newer binary layout appended after the schema version
+    #[serde(skip)]
+    pub unknown_wire: Vec<u8>,
+}
+
+/// Payload kind of a `SignedMessage` in the binary wire format
+///
+/// The binary format encodes fields and enum variants by position, so the
+/// layout of `SignedMessage` and `CheckResult` is part of the protocol. The
+/// schema version follows the fields of version 0, and fields added later
+/// go after it; older nodes stop reading before them.
+pub const SIGNED_RESULT_KIND: u8 = 1;
+
+/// Fields of a binary `CheckResult` in schema version 0
+type ResultFields = (
+    Uuid,
+    String,
+    SystemTime,
+    MonitorStatus,
+    Option<u64>,
+    Option<u16>,
+    Option<String>,
+    String,
+    Option<Vec<u8>>,
+);
+
+/// Fields of a binary `SignedMessage` in schema version 0
+pub(super) type SignedFields = (ResultFields, [u8; 32], Option<LocationClaim>);
+
+/// `certificate`, `maintenance` and `agent` of a binary `CheckResult`,
+/// appended after the schema version from `RESULT_EXTRAS_VERSION` on
+type ResultExtras = (Option<CertificateInfo>, bool, bool);
+
+/// First schema version whose binary results carry `ResultExtras`
+const RESULT_EXTRAS_VERSION: u32 = 2;
+
+impl SignedMessage {
+    /// A signed result in the current schema
+    pub fn new(result: CheckResult, public_key: [u8; 32], location: Option<LocationClaim>) -> Self {
+        Self {
+            result,
+            public_key,
+            location,
+            schema: PayloadSchema::default(),
+            unknown_wire: Vec::new(),
+        }
+    }
+
+    /// Parse a signed result published as binary or JSON
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if wire::wire_kind(data).is_none() {
+            return serde_json::from_slice(data).ok();
+        }
+        let ((fields, public_key, location), rest) =
+            wire::decode_prefix::<SignedFields>(SIGNED_RESULT_KIND, data).ok()?;
+        let (schema_version, rest) = match rest {
+            [] => (0, rest),
+            rest => wire::decode_appended::<u32>(rest).ok()?,
+        };
+        let ((certificate, maintenance, agent), unknown) =
+            if schema_version >= RESULT_EXTRAS_VERSION {
+                wire::decode_appended::<ResultExtras>(rest).ok()?
+            } else {
+                ((None, false, false), rest)
+            };
+
+        let (
+            monitor_id,
+            target,
+            timestamp,
+            status,
+            latency_ms,
+            status_code,
+            error_message,
+            peer_id,
+            signature,
+        ) = fields;
+        let result = CheckResult {
+            monitor_id,
+            target,
+            timestamp,
+            status,
+            latency_ms,
+            status_code,
+            error_message,
+            peer_id,
+            signature,
+            certificate,
+            maintenance,
+            agent,
+        };
+        Some(Self {
+            result,
+            public_key,
+            location,
+            schema: PayloadSchema { schema_version, unknown: BTreeMap::new() },
+            unknown_wire: unknown.to_vec(),
+        })
+    }
+
+    /// Encode in the binary wire format
+    pub fn encode(&self, encoder: &mut WireEncoder) -> anyhow::Result<Vec<u8>> {
+        let r = &self.result;
+        let result = (
+            &r.monitor_id,
+            &r.target,
+            &r.timestamp,
+            &r.status,
+            &r.latency_ms,
+            &r.status_code,
+            &r.error_message,
+            &r.peer_id,
+            &r.signature,
+        );
+        let version = self.schema.schema_version;
+        let head = (result, &self.public_key, &self.location, version);
+        let mut payload = if version >= RESULT_EXTRAS_VERSION {
+            let extras = (&r.certificate, r.maintenance, r.agent);
+            encoder.encode(SIGNED_RESULT_KIND, &(head, extras))?.to_vec()
+        } else {
+            encoder.encode(SIGNED_RESULT_KIND, &head)?.to_vec()
+        };
+        payload.extend_from_slice(&self.unknown_wire);
+        Ok(payload)
+    }
+}
+
+/// Result of a private monitor check, encrypted by a helper for the owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedResultMessage {
+    /// Helper assignment the check was performed for
+    pub assignment_id: Uuid,
+    /// Monitor the result belongs to
+    pub monitor_id: Uuid,
+    /// Owner the result is encrypted for (Ed25519 public key, hex)
+    pub owner_peer_id: String,
+    /// Helper that performed the check (Ed25519 public key, hex)
+    pub helper_peer_id: String,
+    /// Signed `CheckResult`, sealed for the owner
+    pub sealed: SealedResult,
+    #[serde(flatten)]
+    pub schema: PayloadSchema,
+}
+
+/// Request from a monitor owner asking a helper to check a private monitor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelperAssignmentRequest {
+    /// Unique assignment identifier chosen by the owner
+    pub assignment_id: Uuid,
+    /// Monitor the helper should check
+    pub monitor_id: Uuid,
+    /// Owner peer ID (Ed25519 public key, hex)
+    pub owner_peer_id: String,
+    /// Owner Ed25519 public key; must be the key `owner_peer_id` encodes
+    pub owner_public_key: [u8; 32],
+    /// Helper the request is addressed to
+    pub helper_peer_id: String,
+    /// Target to check
+    pub target: String,
+    /// Check type (http, https, tcp, icmp)
+    pub check_type: String,
+    /// Interval between checks in seconds
+    pub interval_seconds: u64,
+    /// Unix timestamp (seconds) after which the assignment lapses
+    pub expires_at: Option<u64>,
+    /// Owner's Ed25519 signature over all fields above
+    pub signature: Vec<u8>,
+    #[serde(flatten)]
+    pub schema: PayloadSchema,
+}
+
+impl HelperAssignmentRequest {
+    /// Helper-side assignment record for an accepted request, or `None` when
+    /// its expiry is out of range
+    pub fn to_helper_assignment(&self, now: SystemTime) -> Option<HelperAssignment> {
+        let expires_at = match self.expires_at {
+            Some(secs) => Some(UNIX_EPOCH.checked_add(Duration::from_secs(secs))?),
+            None => None,
+        };
+        Some(HelperAssignment {
+            assignment_id: self.assignment_id,
+            role: AssignmentRole::Helper,
+            monitor_uuid: self.monitor_id,
+            owner_peer_id: self.owner_peer_id.clone(),
+            helper_peer_id: self.helper_peer_id.clone(),
+            target: self.target.clone(),
+            check_type: self.check_type.clone(),
+            interval_seconds: self.interval_seconds,
+            status: "accepted".to_string(),
+            created_at: now,
+            updated_at: now,
+            expires_at,
+            last_result_at: None,
+        })
+    }
+}
+
+/// Owner's notice that a monitor turned private
+///
+/// Peers drop their helper duties for the monitor and mark the results the
+/// owner shared for it as retracted. The notice also replaces the monitor's
+/// DHT record, so later lookups no longer find a shared result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorRetraction {
+    /// Monitor whose results are retracted
+    pub monitor_id: Uuid,
+    /// Owner peer ID (Ed25519 public key, hex)
+    pub owner_peer_id: String,
+    /// Owner Ed25519 public key; must be the key `owner_peer_id` encodes
+    pub owner_public_key: [u8; 32],
+    /// Unix timestamp (seconds) the monitor turned private
+    pub retracted_at: u64,
+    /// Owner's Ed25519 signature over all fields above
+    pub signature: Vec<u8>,
+    #[serde(flatten)]
+    pub schema: PayloadSchema,
+}
+
+/// A peer joining or leaving the monitoring of a community domain
+///
+/// Signed with the peer's Ed25519 key, so a peer only speaks for itself; the
+/// latest announcement of each peer decides whether it takes part. Peers
+/// repeat their announcements, and a join that is not repeated expires.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MembershipAnnouncement {
+    /// Lowercase host name of the domain
+    pub host: String,
+    /// Announcing peer ID (Ed25519 public key, hex)
+    pub peer_id: String,
+    /// Ed25519 public key; must be the key `peer_id` encodes
+    pub public_key: [u8; 32],
+    /// Whether the peer joined or left
+    pub joined: bool,
+    /// Unix timestamp (seconds) of the announcement
+    pub announced_at: u64,
+    /// Peer's Ed25519 signature over all fields above
+    pub signature: Vec<u8>,
+    #[serde(flatten)]
+    pub schema: PayloadSchema,
+}
+
+/// A node's proof that its libp2p peer ID and its Ed25519 app key belong together
+///
+/// Signed with the app key and published by the libp2p peer it names; gossip
+/// signs the message carrying it with that peer's libp2p key, so a binding
+/// arriving from its own `libp2p_peer_id` shows one node holds both keys.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityBinding {
+    /// libp2p peer ID of the node
+    pub libp2p_peer_id: String,
+    /// App peer ID (Ed25519 public key, hex)
+    pub app_peer_id: String,
+    /// App Ed25519 public key; must be the key `app_peer_id` encodes
+    pub public_key: [u8; 32],
+    /// Unix timestamp (seconds) the binding was signed
+    pub issued_at: u64,
+    /// App key's Ed25519 signature over all fields above
+    pub signature: Vec<u8>,
+    #[serde(flatten)]
+    pub schema: PayloadSchema,
+}
+
+/// A stored peer result as sent in a results sync page
+///
+/// Peer results are stored without their target, which the signature covers,
+/// so the requester checks the signature against its own monitor's target.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncedResult {
+    /// Peer ID that signed the result (Ed25519 public key, hex)
+    pub peer_id: String,
+    /// Unix timestamp (seconds) the check ran
+    pub timestamp: u64,
+    pub status: MonitorStatus,
+    pub latency_ms: Option<u64>,
+    pub status_code: Option<u16>,
+    pub error_message: Option<String>,
+    /// Signer's Ed25519 signature over the result
+    pub signature: Vec<u8>,
+    /// Certificate details reported with the result; covered by the signature
+    #[serde(default)]
+    pub certificate: Option<CertificateInfo>,
+}
+
+impl SyncedResult {
+    /// Wire form of a stored peer result
+    pub fn from_stored(result: &crate::database::models::PeerResult) -> Self {
+        Self {
+            peer_id: result.peer_id.clone(),
+            timestamp: result
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            status: result.status,
+            latency_ms: result.latency_ms,
+            status_code: result.status_code,
+            error_message: result.error_message.clone(),
+            signature: result.signature.clone(),
+            certificate: result.certificate.clone(),
+        }
+    }
+
+    /// Unverified peer result for a monitor, as delivered by `source_peer_id`,
+    /// or `None` when its timestamp is out of range
+    pub fn to_stored(
+        &self,
+        monitor_uuid: Uuid,
+        source_peer_id: &str,
+        now: SystemTime,
+    ) -> Option<crate::database::models::PeerResult> {
+        Some(crate::database::models::PeerResult {
+            id: None,
+            monitor_uuid,
+            timestamp: UNIX_EPOCH.checked_add(Duration::from_secs(self.timestamp))?,
+            status: self.status,
+            latency_ms: self.latency_ms,
+            status_code: self.status_code,
+            error_message: self.error_message.clone(),
+            peer_id: self.peer_id.clone(),
+            signature: self.signature.clone(),
+            verified: false,
+            created_at: now,
+            city: None,
+            country: None,
+            region: None,
+            source_peer_id: Some(source_peer_id.to_string()),
+            certificate: self.certificate.clone(),
+        })
+    }
+}
+
+/// State a node of a cluster shares with the other nodes
+///
+/// Published sealed with the cluster key on the cluster's topic; large states
+/// are split over several updates.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClusterUpdate {
+    /// App peer ID of the node that sent the update
+    pub node_id: String,
+    pub monitors: Vec<Monitor>,
+    /// Deleted monitors and when they were deleted (Unix seconds)
+    pub deleted_monitors: Vec<(Uuid, u64)>,
+    pub settings: Vec<Setting>,
+    pub notification_channels: Vec<NotificationChannel>,
+    /// Channels routed to monitors, as (monitor, channel); kept for nodes
+    /// that predate notification routes
+    pub monitor_channels: Vec<(Uuid, Uuid)>,
+    #[serde(default)]
+    pub notification_routes: Vec<NotificationRoute>,
+    #[serde(flatten)]
+    pub schema: PayloadSchema,
 }
 
 /// Commands sent to the P2P node
 #[derive(Debug, Clone)]
 pub enum P2PCommand {
-    /// Publish a monitoring result to the network
-    PublishResult(CheckResult),
+    /// Publish a monitoring result, with the node's location claim, to the network
+    PublishResult(CheckResult, Option<Box<LocationClaim>>),
     /// Subscribe to monitoring results
     #[allow(dead_code)] // Future API
     Subscribe,
     /// Unsubscribe from monitoring results
     #[allow(dead_code)] // Future API
     Unsubscribe,
+    /// Publish a result encrypted for a monitor owner
+    PublishEncryptedResult(Box<EncryptedResultMessage>),
+    /// Ask a helper to check one of our private monitors
+    PublishAssignmentRequest(Box<HelperAssignmentRequest>),
+    /// Look up results stored in the DHT for one of our monitors
+    FetchOwnerResults(Uuid),
+    /// Look up results stored in the DHT for a followed target host
+    FetchTargetResults(String),
+    /// Tell peers a monitor turned private and replace its DHT record
+    PublishRetraction(Box<MonitorRetraction>),
+    /// Tell peers we joined or left monitoring a community domain
+    PublishMembership(Box<MembershipAnnouncement>),
+    /// Ask each of the given peers to probe a target once; answers arrive as
+    /// `P2PEvent::ProbeAnswered` or `P2PEvent::ProbeFailed` tagged with `probe_id`
+    RequestProbes { probe_id: Uuid, peers: Vec<String>, request: Box<ProbeRequest> },
+    /// Answer a probe request received as `P2PEvent::ProbeRequested`
+    RespondProbe { inbound_id: u64, response: Box<ProbeResponse> },
+    /// Ask a peer for a page of the results it stored for a monitor; the page
+    /// arrives as `P2PEvent::ResultsSyncAnswered` or `P2PEvent::ResultsSyncFailed`
+    /// tagged with `sync_id`
+    RequestResultsSync { sync_id: Uuid, peer_id: String, request: Box<ResultsSyncRequest> },
+    /// Answer a results sync request received as `P2PEvent::ResultsSyncRequested`
+    RespondResultsSync { inbound_id: u64, response: Box<ResultsSyncResponse> },
+    /// Block a peer at the swarm, closing its connections and refusing new
+    /// ones, or lift the block with `blocked: false`
+    BlockPeer { peer_id: String, blocked: bool },
+    /// Publish a sealed update on the cluster topic
+    PublishClusterUpdate(Box<crate::crypto::SealedClusterMessage>),
+    /// Look up the records under a debug key; the outcome arrives as
+    /// `P2PEvent::DhtOperationFinished` tagged with `operation_id`
+    GetDHTRecord { operation_id: Uuid, key: String },
+    /// Store a record under a debug key; the outcome arrives as
+    /// `P2PEvent::DhtOperationFinished` tagged with `operation_id`
+    PublishDHTRecord { operation_id: Uuid, key: String, value: Vec<u8> },
     /// Shutdown the P2P node
-    #[allow(dead_code)] // Future API
     Shutdown,
 }
 
@@ -34,16 +496,57 @@ pub enum P2PCommand {
 pub enum P2PEvent {
     /// A monitoring result was received from a peer
     ResultReceived { peer_id: String, result: Box<PeerResult> },
+    /// A result for one of our monitors was found in the DHT
+    ResultBackfilled(Box<PeerResult>),
+    /// A DHT lookup started with `P2PCommand::FetchOwnerResults` finished
+    OwnerResultsFetched { monitor_id: Uuid, records: usize, error: Option<String> },
+    /// An encrypted result from a helper was received
+    EncryptedResultReceived { peer_id: String, message: Box<EncryptedResultMessage> },
+    /// An owner asked a helper to check a monitor; `owner_bound` tells whether
+    /// the libp2p peer that published the request proved it holds the owner key
+    HelperAssignmentRequested {
+        peer_id: String,
+        request: Box<HelperAssignmentRequest>,
+        owner_bound: bool,
+    },
+    /// An owner retracted the results of a monitor that turned private
+    MonitorRetracted { peer_id: String, retraction: Box<MonitorRetraction> },
+    /// A peer joined or left monitoring a community domain
+    MembershipAnnounced { peer_id: String, announcement: Box<MembershipAnnouncement> },
+    /// A peer asked us to probe a target; answer with `P2PCommand::RespondProbe`
+    ProbeRequested { peer_id: String, inbound_id: u64, request: Box<ProbeRequest> },
+    /// A peer answered a probe sent with `P2PCommand::RequestProbes`
+    ProbeAnswered { probe_id: Uuid, peer_id: String, response: Box<ProbeResponse> },
+    /// A probe sent with `P2PCommand::RequestProbes` got no answer
+    ProbeFailed { probe_id: Uuid, peer_id: String, error: String },
+    /// A peer asked for results we stored; answer with `P2PCommand::RespondResultsSync`
+    ResultsSyncRequested { peer_id: String, inbound_id: u64, request: Box<ResultsSyncRequest> },
+    /// A peer answered a request sent with `P2PCommand::RequestResultsSync`
+    ResultsSyncAnswered { sync_id: Uuid, peer_id: String, response: Box<ResultsSyncResponse> },
+    /// A request sent with `P2PCommand::RequestResultsSync` got no answer
+    ResultsSyncFailed { sync_id: Uuid, peer_id: String, error: String },
+    /// A message arrived on the cluster topic, not yet opened
+    ClusterMessageReceived { peer_id: String, data: Vec<u8> },
     /// Successfully subscribed to results
     Subscribed,
     /// Successfully unsubscribed from results
     Unsubscribed,
-    /// A peer connected
-    PeerConnected(String),
+    /// A debug lookup or publish finished, with the values found by a lookup
+    DhtOperationFinished { operation_id: Uuid, records: Vec<Vec<u8>>, error: Option<String> },
+    /// A peer connected, with the address it was reached on if known
+    PeerConnected { peer_id: String, address: Option<String> },
     /// A peer disconnected
     PeerDisconnected(String),
+    /// A peer flooded us and everything it sends is dropped for `duration`;
+    /// `offences` counts how often the node greylisted it since starting
+    PeerGreylisted { peer_id: String, duration: Duration, offences: u32 },
     /// Node started successfully
     Started { peer_id: String },
+    /// Periodic metrics snapshot from the node
+    NodeMetrics(Box<peerup::NodeMetrics>),
+    /// Lookups and hits of the seen-message cache since start, sent with
+    /// the node metrics
+    SeenMessages { lookups: u64, hits: u64 },
     /// Node encountered an error
     Error(String),
 }
@@ -59,6 +562,16 @@ pub struct PeerResult {
     pub public_key: Option<Vec<u8>>,
     /// Peer ID that sent this result
     pub peer_id: String,
+    /// Location the sender claimed, not yet verified
+    pub location: Option<LocationClaim>,
     /// Timestamp when received
     pub received_at: std::time::SystemTime,
+    /// Outcome of the signature check done when the result came in over
+    /// gossip; `None` if it was not checked yet
+    #[serde(skip)]
+    pub signature_valid: Option<bool>,
+    /// Whether the libp2p peer that published the result proved it holds the
+    /// key of the result's `peer_id`; `None` if it did not come over gossip
+    #[serde(skip)]
+    pub identity_bound: Option<bool>,
 }
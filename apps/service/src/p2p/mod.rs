@@ -5,11 +5,19 @@
 /// - Sharing monitoring results with peers
 /// - Receiving results from other peers
 /// - Peer discovery and coordination
+pub mod decoder;
+pub mod identity;
 pub mod messages;
 pub mod network;
+pub mod outbox;
 pub mod receiving;
+pub mod seen;
 pub mod sharing;
 
 #[allow(unused_imports)]
-pub use messages::{P2PCommand, P2PEvent, PeerResult};
-pub use network::P2PNetwork;
+pub use messages::{
+    ClusterUpdate, EncryptedResultMessage, HelperAssignmentRequest, IdentityBinding,
+    MembershipAnnouncement, MonitorRetraction, P2PCommand, P2PEvent, PayloadSchema, PeerResult,
+    SCHEMA_VERSION, SyncedResult,
+};
+pub use network::{P2PHandle, P2PNetwork};
@@ -1,9 +1,39 @@
-use futures::StreamExt;
-use peerup::{PeerNode, node::NodeConfig};
-use tokio::sync::mpsc;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
-use super::messages::{P2PCommand, P2PEvent, PeerResult, SignedMessage};
+use peerup::{
+    EventFilter, MONITORING_RESULTS_TOPIC, PeerNode, ProbeRequest, ProbeResponse,
+    ResultsSyncRequest, ResultsSyncResponse, WireEncoder, node::NodeConfig,
+};
+use uuid::Uuid;
+
+use super::decoder::{self, GOSSIP_WORKERS, RawGossip};
+use super::identity::SharedIdentities;
+use super::messages::{
+    CLUSTER_TOPIC_PREFIX, DEBUG_KEY_PREFIX, EncryptedResultMessage, HelperAssignmentRequest,
+    MembershipAnnouncement, MonitorRetraction, OWNER_RESULTS_KEY_PREFIX, P2PCommand, P2PEvent,
+    PeerResult, SignedMessage, TARGET_RESULTS_KEY_PREFIX, debug_key, owner_results_key,
+    target_results_key,
+};
+use super::outbox::Outbox;
+use super::seen::SharedSeenMessages;
+use crate::config::QueueConfig;
+use crate::crypto::{KeyPair, sign_identity_binding};
+use crate::database::models::Follow;
+use crate::location::LocationClaim;
 use crate::monitoring::types::CheckResult;
+use crate::queue::{self, QueueReceiver, QueueSender};
+
+/// How long the node may spend draining in-flight requests on shutdown
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Results held while offline unless configured otherwise
+const DEFAULT_OFFLINE_BUFFER: usize = 1000;
+/// How often the identity binding is republished without new connections,
+/// well before peers stop trusting it
+const BINDING_REFRESH: Duration = Duration::from_secs(6 * 60 * 60);
 
 /// P2P network manager
 pub struct P2PNetwork {
@@ -11,12 +41,25 @@ pub struct P2PNetwork {
     enabled: bool,
     /// Ed25519 public key (32 bytes) for signing messages
     public_key: Option<[u8; 32]>,
+    /// App key the node's libp2p peer ID is bound to for peers
+    identity_key: Option<Arc<KeyPair>>,
     /// Configuration for the P2P node
     config: NodeConfig,
     /// Channel to send commands to the P2P node
-    command_tx: Option<mpsc::Sender<P2PCommand>>,
+    command_tx: Option<QueueSender<P2PCommand>>,
     /// Channel to receive events from the P2P node
-    event_rx: Option<mpsc::Receiver<P2PEvent>>,
+    event_rx: Option<QueueReceiver<P2PEvent>>,
+    /// Sizes and overflow policies of the event and command channels
+    event_queue: QueueConfig,
+    command_queue: QueueConfig,
+    /// Extra gossip topics to (re)subscribe whenever the node starts
+    topics: Vec<String>,
+    /// Publish results in the binary wire format instead of JSON
+    compact_results: bool,
+    /// Results held for replay while no peer would receive them
+    offline_buffer: usize,
+    /// Topic of the cluster this node belongs to, if any
+    cluster_topic: Option<String>,
 }
 
 impl P2PNetwork {
@@ -31,7 +74,21 @@ impl P2PNetwork {
             .disable_relay()
             .build();
 
-        Self { peer_id, enabled, public_key: None, config, command_tx: None, event_rx: None }
+        Self {
+            peer_id,
+            enabled,
+            public_key: None,
+            identity_key: None,
+            config,
+            command_tx: None,
+            event_rx: None,
+            event_queue: QueueConfig::default(),
+            command_queue: QueueConfig::default(),
+            topics: Vec::new(),
+            compact_results: true,
+            offline_buffer: DEFAULT_OFFLINE_BUFFER,
+            cluster_topic: None,
+        }
     }
 
     /// Create a new P2P network manager with custom config
@@ -45,12 +102,59 @@ impl P2PNetwork {
             peer_id,
             enabled,
             public_key: Some(public_key),
+            identity_key: None,
             config,
             command_tx: None,
             event_rx: None,
+            event_queue: QueueConfig::default(),
+            command_queue: QueueConfig::default(),
+            topics: Vec::new(),
+            compact_results: true,
+            offline_buffer: DEFAULT_OFFLINE_BUFFER,
+            cluster_topic: None,
         }
     }
 
+    /// Use the given sizes and overflow policies for the event and command
+    /// channels once started
+    pub fn with_queues(mut self, events: QueueConfig, commands: QueueConfig) -> Self {
+        self.event_queue = events;
+        self.command_queue = commands;
+        self
+    }
+
+    /// Whether results are published in the binary wire format; JSON is
+    /// for networks that still have nodes only reading JSON
+    pub fn with_compact_results(mut self, compact: bool) -> Self {
+        self.compact_results = compact;
+        self
+    }
+
+    /// Hold up to `capacity` results while no peer would receive them, to
+    /// replay once one subscribes again; 0 drops them instead
+    pub fn with_offline_buffer(mut self, capacity: usize) -> Self {
+        self.offline_buffer = capacity;
+        self
+    }
+
+    /// Join a cluster's topic; its messages arrive as
+    /// `P2PEvent::ClusterMessageReceived` instead of going to the decoder
+    pub fn with_cluster_topic(mut self, topic: impl Into<String>) -> Self {
+        let topic = topic.into();
+        self.topics.push(topic.clone());
+        self.cluster_topic = Some(topic);
+        self
+    }
+
+    /// Prove to peers that the node's libp2p peer ID belongs to this app key
+    ///
+    /// A signed binding is published once a peer connects, so peers can check
+    /// that results and assignment requests come from the key they name.
+    pub fn with_identity_key(mut self, keypair: Arc<KeyPair>) -> Self {
+        self.identity_key = Some(keypair);
+        self
+    }
+
     /// Initialize and join the P2P network
     pub async fn start(&mut self) -> anyhow::Result<()> {
         if !self.enabled {
@@ -61,8 +165,9 @@ impl P2PNetwork {
         tracing::info!("Starting P2P network with peer ID: {}", self.peer_id);
 
         // Create channels for communication
-        let (command_tx, mut command_rx) = mpsc::channel::<P2PCommand>(100);
-        let (event_tx, event_rx) = mpsc::channel::<P2PEvent>(100);
+        let (command_tx, mut command_rx) =
+            queue::bounded::<P2PCommand>("p2p_commands", self.command_queue);
+        let (event_tx, event_rx) = queue::bounded::<P2PEvent>("p2p_events", self.event_queue);
 
         // Store the command sender and event receiver
         self.command_tx = Some(command_tx);
@@ -99,30 +204,144 @@ impl P2PNetwork {
             }
         }
 
-        // Subscribe to monitoring results topic
+        // Subscribe to monitoring results topic and any topics registered before a restart
         node.subscribe_to_results()?;
+        for topic in &self.topics {
+            node.subscribe_topic(topic)?;
+        }
+
+        // Only surface the events the loop below acts on
+        node.set_event_filter(
+            EventFilter::new()
+                .gossip_topic_prefix(MONITORING_RESULTS_TOPIC)
+                .gossip_topic_prefix(CLUSTER_TOPIC_PREFIX)
+                .dht_key_prefix(OWNER_RESULTS_KEY_PREFIX)
+                .dht_key_prefix(TARGET_RESULTS_KEY_PREFIX)
+                .dht_key_prefix(DEBUG_KEY_PREFIX)
+                .connections()
+                .probes()
+                .results_sync(),
+        );
+        let store_records = node.config().enable_kademlia;
+        let compact_results = self.compact_results;
+        // Peers take this much gossip from us; replays leave half of it to
+        // the results we publish as they come in
+        let replay_limit = node.config().gossip_rate_limit.unwrap_or_default();
+        let mut outbox = Outbox::new(self.offline_buffer)
+            .with_rate_limit((replay_limit.max_messages as usize / 2).max(1), replay_limit.window);
+        let identity_key = self.identity_key.clone();
+        let local_peer_id = libp2p_peer_id.to_string();
+        let cluster_topic = self.cluster_topic.clone();
 
         // Send started event
         let _ = event_tx.send(P2PEvent::Started { peer_id: libp2p_peer_id.to_string() }).await;
 
-        // Spawn background task to run the node's event loop
-        tokio::task::spawn_local(async move {
+        let seen = SharedSeenMessages::default();
+        let (gossip_tx, _) = decoder::spawn(
+            event_tx.clone(),
+            seen.clone(),
+            SharedIdentities::default(),
+            GOSSIP_WORKERS,
+        );
+
+        let mut metrics_timer = tokio::time::interval(node.config().metrics_interval);
+        metrics_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        // Spawn background task to run the node's event loop on the shared runtime
+        tokio::spawn(async move {
             tracing::info!("P2P event loop started");
 
+            // Owner result lookups in flight and what each found so far
+            let mut pending_fetches: HashMap<peerup::kad::QueryId, OwnerFetch> = HashMap::new();
+            // Followed target lookups in flight, by query, and the host each is for
+            let mut pending_target_fetches: HashMap<peerup::kad::QueryId, String> = HashMap::new();
+            // Debug lookups and publishes in flight, by query, and the operation each belongs to
+            let mut pending_debug: HashMap<peerup::kad::QueryId, Uuid> = HashMap::new();
+            // Probes we sent, by request ID, and the fan-out each belongs to
+            let mut pending_probes: HashMap<u64, Uuid> = HashMap::new();
+            // Probe requests from peers waiting for the service to answer
+            let mut inbound_probes = HashMap::new();
+            // Results sync requests we sent, by request ID, and the sync each belongs to
+            let mut pending_syncs: HashMap<u64, Uuid> = HashMap::new();
+            // Results sync requests from peers waiting for the service to answer
+            let mut inbound_syncs = HashMap::new();
+            let mut next_inbound_id: u64 = 0;
+            // Reused for every published result
+            let mut encoder = WireEncoder::new();
+            // The binding goes out on the next tick after a peer connects
+            let mut binding_due = false;
+            let mut binding_published: Option<Instant> = None;
+
             loop {
                 tokio::select! {
+                    // Emit a periodic metrics snapshot
+                    _ = metrics_timer.tick() => {
+                        let metrics = node.metrics();
+                        let _ = event_tx.send(P2PEvent::NodeMetrics(Box::new(metrics))).await;
+                        let (lookups, hits) = seen.lock().unwrap().counts();
+                        let _ = event_tx.send(P2PEvent::SeenMessages { lookups, hits }).await;
+
+                        replay_outbox(&mut node, &mut outbox);
+
+                        let refresh = binding_published.is_none_or(|at| at.elapsed() >= BINDING_REFRESH);
+                        if let Some(keypair) = &identity_key
+                            && (binding_due || refresh)
+                        {
+                            let published = sign_identity_binding(&local_peer_id, keypair, SystemTime::now())
+                                .and_then(|binding| Ok(serde_json::to_string(&binding)?))
+                                .and_then(|json| node.publish_result(json));
+                            match published {
+                                Ok(()) => {
+                                    binding_due = false;
+                                    binding_published = Some(Instant::now());
+                                }
+                                // Retried on the next tick, e.g. while no peer is in the mesh yet
+                                Err(e) => tracing::debug!("Failed to publish identity binding: {}", e),
+                            }
+                        }
+                    }
+
                     // Handle commands from the service
                     Some(cmd) = command_rx.recv() => {
                         match cmd {
-                            P2PCommand::PublishResult(result) => {
+                            P2PCommand::PublishResult(result, location) => {
                                 // Wrap result with public key in SignedMessage
-                                let signed_msg = SignedMessage {
-                                    result: result.clone(),
-                                    public_key: public_key.unwrap_or([0u8; 32]),
+                                let signed_msg = SignedMessage::new(
+                                    result,
+                                    public_key.unwrap_or([0u8; 32]),
+                                    location.map(|claim| *claim),
+                                );
+                                let payload = if compact_results {
+                                    signed_msg.encode(&mut encoder)
+                                } else {
+                                    serde_json::to_vec(&signed_msg).map_err(anyhow::Error::from)
                                 };
 
-                                if let Ok(json) = serde_json::to_string(&signed_msg) {
-                                    match node.publish_result(json) {
+                                if let Ok(payload) = payload {
+                                    // Keep the latest result in the DHT so the owner can
+                                    // backfill what it missed while offline
+                                    if store_records
+                                        && let Err(e) = node.put_record(
+                                            owner_results_key(&signed_msg.result.monitor_id),
+                                            payload.clone(),
+                                        )
+                                    {
+                                        tracing::debug!("Failed to store result in the DHT: {}", e);
+                                    }
+                                    // And under its target, for nodes following the host
+                                    if store_records
+                                        && let Some(host) = Follow::host_of(&signed_msg.result.target)
+                                        && let Err(e) = node.put_record(target_results_key(&host), payload.clone())
+                                    {
+                                        tracing::debug!("Failed to store target result in the DHT: {}", e);
+                                    }
+
+                                    let span = tracing::info_span!(
+                                        "gossip_publish",
+                                        monitor_id = %signed_msg.result.monitor_id,
+                                        bytes = payload.len()
+                                    );
+                                    match span.in_scope(|| publish_or_buffer(&mut node, &mut outbox, "result", payload)) {
                                         Ok(_) => {
                                             tracing::debug!("Published monitoring result to P2P network");
                                         }
@@ -134,6 +353,119 @@ impl P2PNetwork {
                                     }
                                 }
                             }
+                            P2PCommand::PublishEncryptedResult(message) => {
+                                if let Ok(json) = serde_json::to_vec(&message)
+                                    && let Err(e) = publish_or_buffer(&mut node, &mut outbox, "encrypted result", json)
+                                {
+                                    tracing::error!("Failed to publish encrypted result: {}", e);
+                                    let _ = event_tx.send(P2PEvent::Error(e.to_string())).await;
+                                }
+                            }
+                            P2PCommand::PublishAssignmentRequest(request) => {
+                                if let Ok(json) = serde_json::to_string(&request)
+                                    && let Err(e) = node.publish_result(json)
+                                {
+                                    tracing::error!("Failed to publish assignment request: {}", e);
+                                    let _ = event_tx.send(P2PEvent::Error(e.to_string())).await;
+                                }
+                            }
+                            P2PCommand::PublishRetraction(retraction) => {
+                                if let Ok(json) = serde_json::to_string(&retraction) {
+                                    // Records cannot be deleted from other peers, so the
+                                    // notice overwrites the last shared result instead
+                                    if store_records
+                                        && let Err(e) = node.put_record(
+                                            owner_results_key(&retraction.monitor_id),
+                                            json.clone().into_bytes(),
+                                        )
+                                    {
+                                        tracing::debug!("Failed to replace DHT record: {}", e);
+                                    }
+                                    if let Err(e) = node.publish_result(json) {
+                                        tracing::error!("Failed to publish retraction: {}", e);
+                                        let _ = event_tx.send(P2PEvent::Error(e.to_string())).await;
+                                    }
+                                }
+                            }
+                            P2PCommand::PublishMembership(announcement) => {
+                                if let Ok(json) = serde_json::to_string(&announcement)
+                                    && let Err(e) = node.publish_result(json)
+                                {
+                                    tracing::error!("Failed to publish membership: {}", e);
+                                    let _ = event_tx.send(P2PEvent::Error(e.to_string())).await;
+                                }
+                            }
+                            P2PCommand::PublishClusterUpdate(sealed) => {
+                                let Some(topic) = &cluster_topic else {
+                                    tracing::warn!("Dropping cluster update: not in a cluster");
+                                    continue;
+                                };
+                                if let Ok(json) = serde_json::to_vec(&sealed)
+                                    && let Err(e) = node.publish_to_topic(topic, json)
+                                {
+                                    tracing::error!("Failed to publish cluster update: {}", e);
+                                    let _ = event_tx.send(P2PEvent::Error(e.to_string())).await;
+                                }
+                            }
+                            P2PCommand::RequestProbes { probe_id, peers, request } => {
+                                for peer_id in peers {
+                                    match peer_id.parse::<peerup::PeerId>() {
+                                        Ok(peer) => {
+                                            let request_id = node.send_probe(&peer, (*request).clone());
+                                            pending_probes.insert(request_id, probe_id);
+                                        }
+                                        Err(e) => {
+                                            let _ = event_tx.send(P2PEvent::ProbeFailed {
+                                                probe_id,
+                                                peer_id,
+                                                error: format!("Invalid peer ID: {e}"),
+                                            }).await;
+                                        }
+                                    }
+                                }
+                            }
+                            P2PCommand::RespondProbe { inbound_id, response } => {
+                                if let Some(channel) = inbound_probes.remove(&inbound_id)
+                                    && let Err(e) = node.respond_probe(channel, *response)
+                                {
+                                    tracing::debug!("Failed to answer probe request: {}", e);
+                                }
+                            }
+                            P2PCommand::RequestResultsSync { sync_id, peer_id, request } => {
+                                match peer_id.parse::<peerup::PeerId>() {
+                                    Ok(peer) => {
+                                        let request_id = node.send_results_sync(&peer, *request);
+                                        pending_syncs.insert(request_id, sync_id);
+                                    }
+                                    Err(e) => {
+                                        let _ = event_tx.send(P2PEvent::ResultsSyncFailed {
+                                            sync_id,
+                                            peer_id,
+                                            error: format!("Invalid peer ID: {e}"),
+                                        }).await;
+                                    }
+                                }
+                            }
+                            P2PCommand::BlockPeer { peer_id, blocked } => {
+                                match peer_id.parse::<peerup::PeerId>() {
+                                    Ok(peer) if blocked => {
+                                        node.block_peer(peer);
+                                    }
+                                    Ok(peer) => {
+                                        node.unblock_peer(peer);
+                                    }
+                                    Err(e) => {
+                                        tracing::warn!("Invalid peer ID {}: {}", peer_id, e);
+                                    }
+                                }
+                            }
+                            P2PCommand::RespondResultsSync { inbound_id, response } => {
+                                if let Some(channel) = inbound_syncs.remove(&inbound_id)
+                                    && let Err(e) = node.respond_results_sync(channel, *response)
+                                {
+                                    tracing::debug!("Failed to answer results sync request: {}", e);
+                                }
+                            }
                             P2PCommand::Subscribe => {
                                 if let Err(e) = node.subscribe_to_results() {
                                     tracing::error!("Failed to subscribe: {}", e);
@@ -148,6 +480,58 @@ impl P2PNetwork {
                                     let _ = event_tx.send(P2PEvent::Unsubscribed).await;
                                 }
                             }
+                            P2PCommand::FetchOwnerResults(monitor_id) => {
+                                match node.get_record(owner_results_key(&monitor_id)) {
+                                    Ok(query_id) => {
+                                        pending_fetches.insert(query_id, OwnerFetch::new(monitor_id));
+                                    }
+                                    Err(e) => {
+                                        let _ = event_tx.send(P2PEvent::OwnerResultsFetched {
+                                            monitor_id,
+                                            records: 0,
+                                            error: Some(e.to_string()),
+                                        }).await;
+                                    }
+                                }
+                            }
+                            P2PCommand::FetchTargetResults(host) => {
+                                match node.get_record(target_results_key(&host)) {
+                                    Ok(query_id) => {
+                                        pending_target_fetches.insert(query_id, host);
+                                    }
+                                    Err(e) => {
+                                        tracing::debug!("Failed to look up results for {}: {}", host, e);
+                                    }
+                                }
+                            }
+                            P2PCommand::GetDHTRecord { operation_id, key } => {
+                                match node.get_record(debug_key(&key)) {
+                                    Ok(query_id) => {
+                                        pending_debug.insert(query_id, operation_id);
+                                    }
+                                    Err(e) => {
+                                        let _ = event_tx.send(P2PEvent::DhtOperationFinished {
+                                            operation_id,
+                                            records: Vec::new(),
+                                            error: Some(e.to_string()),
+                                        }).await;
+                                    }
+                                }
+                            }
+                            P2PCommand::PublishDHTRecord { operation_id, key, value } => {
+                                match node.put_record(debug_key(&key), value) {
+                                    Ok(query_id) => {
+                                        pending_debug.insert(query_id, operation_id);
+                                    }
+                                    Err(e) => {
+                                        let _ = event_tx.send(P2PEvent::DhtOperationFinished {
+                                            operation_id,
+                                            records: Vec::new(),
+                                            error: Some(e.to_string()),
+                                        }).await;
+                                    }
+                                }
+                            }
                             P2PCommand::Shutdown => {
                                 tracing::info!("Shutting down P2P node");
                                 break;
@@ -156,32 +540,206 @@ impl P2PNetwork {
                     }
 
                     // Handle events from the swarm
-                    event = node.swarm.select_next_some() => {
-                        use peerup::{swarm::SwarmEvent, PeerUPEvent};
+                    event = node.next_event() => {
+                        use peerup::{kad, swarm::SwarmEvent, PeerUPEvent};
 
                         match event {
+                            // Parsing and signature checks run on the decoder's workers
                             SwarmEvent::Behaviour(PeerUPEvent::GossipsubMessage { peer, message, .. }) => {
-                                // Decode signed message
-                                if let Ok(msg_str) = String::from_utf8(message.data.clone())
-                                    && let Ok(signed_msg) = serde_json::from_str::<SignedMessage>(&msg_str)
-                                {
-                                    let peer_result = PeerResult {
-                                        result: signed_msg.result.clone(),
-                                        signature: signed_msg.result.signature.clone(),
-                                        public_key: Some(signed_msg.public_key.to_vec()),
-                                        // Use the signer-declared peer_id (matches signature) rather than libp2p ID
-                                        peer_id: signed_msg.result.peer_id.clone(),
-                                        received_at: std::time::SystemTime::now(),
+                                // Cluster updates are sealed; the cluster task opens them
+                                if cluster_topic.as_deref() == Some(message.topic.as_str()) {
+                                    let _ = event_tx.send(P2PEvent::ClusterMessageReceived {
+                                        peer_id: peer.to_string(),
+                                        data: message.data,
+                                    }).await;
+                                    continue;
+                                }
+                                let _ = gossip_tx.send(RawGossip {
+                                    peer_id: peer.to_string(),
+                                    source: message.source.map(|source| source.to_string()),
+                                    data: message.data,
+                                }).await;
+                            }
+                            SwarmEvent::Behaviour(PeerUPEvent::Kademlia(kad::Event::OutboundQueryProgressed {
+                                id,
+                                result: kad::QueryResult::GetRecord(result),
+                                step,
+                                ..
+                            })) => {
+                                // A debug lookup ends at the first record found
+                                if let Some(operation_id) = pending_debug.remove(&id) {
+                                    let (records, error) = match result {
+                                        Ok(kad::GetRecordOk::FoundRecord(found)) => (vec![found.record.value], None),
+                                        Ok(kad::GetRecordOk::FinishedWithNoAdditionalRecord { .. })
+                                        | Err(kad::GetRecordError::NotFound { .. }) => (Vec::new(), None),
+                                        Err(e) => (Vec::new(), Some(e.to_string())),
                                     };
-                                    let _ = event_tx.send(P2PEvent::ResultReceived {
+                                    let _ = event_tx.send(P2PEvent::DhtOperationFinished {
+                                        operation_id,
+                                        records,
+                                        error,
+                                    }).await;
+                                    continue;
+                                }
+
+                                if let Some(host) = pending_target_fetches.get(&id) {
+                                    let finished = match result {
+                                        Ok(kad::GetRecordOk::FoundRecord(found)) => {
+                                            if let Some(signed_msg) = SignedMessage::parse(&found.record.value)
+                                                && Follow::host_of(&signed_msg.result.target).as_ref() == Some(host)
+                                            {
+                                                let _ = event_tx.send(P2PEvent::ResultBackfilled(
+                                                    Box::new(peer_result(signed_msg)),
+                                                )).await;
+                                            }
+                                            step.last
+                                        }
+                                        Ok(kad::GetRecordOk::FinishedWithNoAdditionalRecord { .. }) => true,
+                                        Err(e) => {
+                                            tracing::debug!("Target lookup for {} ended: {}", host, e);
+                                            true
+                                        }
+                                    };
+                                    if finished {
+                                        pending_target_fetches.remove(&id);
+                                    }
+                                    continue;
+                                }
+
+                                let Some(fetch) = pending_fetches.get_mut(&id) else {
+                                    continue;
+                                };
+
+                                let (finished, error) = match result {
+                                    Ok(kad::GetRecordOk::FoundRecord(found)) => {
+                                        if fetch.is_new(&found.record.value)
+                                            && let Some(signed_msg) = SignedMessage::parse(&found.record.value)
+                                            && signed_msg.result.monitor_id == fetch.monitor_id
+                                        {
+                                            fetch.records += 1;
+                                            let _ = event_tx.send(P2PEvent::ResultBackfilled(
+                                                Box::new(peer_result(signed_msg)),
+                                            )).await;
+                                        }
+                                        // Stop once the closest peers only repeat what we have
+                                        if !step.last && fetch.exhausted() {
+                                            let _ = node.finish_query(id);
+                                            (true, None)
+                                        } else {
+                                            (step.last, None)
+                                        }
+                                    }
+                                    Ok(kad::GetRecordOk::FinishedWithNoAdditionalRecord { .. }) => (true, None),
+                                    // Nobody shared a result for the monitor yet
+                                    Err(kad::GetRecordError::NotFound { .. }) => (true, None),
+                                    Err(e) => (true, Some(e.to_string())),
+                                };
+
+                                if finished && let Some(fetch) = pending_fetches.remove(&id) {
+                                    let _ = event_tx.send(P2PEvent::OwnerResultsFetched {
+                                        monitor_id: fetch.monitor_id,
+                                        records: fetch.records,
+                                        error,
+                                    }).await;
+                                }
+                            }
+                            SwarmEvent::Behaviour(PeerUPEvent::Kademlia(kad::Event::OutboundQueryProgressed {
+                                id,
+                                result: kad::QueryResult::PutRecord(result),
+                                ..
+                            })) => {
+                                if let Some(operation_id) = pending_debug.remove(&id) {
+                                    let _ = event_tx.send(P2PEvent::DhtOperationFinished {
+                                        operation_id,
+                                        records: Vec::new(),
+                                        error: result.err().map(|e| e.to_string()),
+                                    }).await;
+                                }
+                            }
+                            SwarmEvent::Behaviour(PeerUPEvent::ProbeRequestReceived { peer, request, channel }) => {
+                                let inbound_id = next_inbound_id;
+                                next_inbound_id += 1;
+                                inbound_probes.insert(inbound_id, channel);
+                                let _ = event_tx.send(P2PEvent::ProbeRequested {
+                                    peer_id: peer.to_string(),
+                                    inbound_id,
+                                    request: Box::new(request),
+                                }).await;
+                            }
+                            SwarmEvent::Behaviour(PeerUPEvent::InboundProbeFailure { peer, error, .. }) => {
+                                tracing::debug!("Probe request from {} failed: {}", peer, error);
+                            }
+                            SwarmEvent::Behaviour(PeerUPEvent::ProbeResponseReceived { peer, request_id, response }) => {
+                                if let Some(probe_id) = pending_probes.remove(&request_id) {
+                                    let _ = event_tx.send(P2PEvent::ProbeAnswered {
+                                        probe_id,
+                                        peer_id: peer.to_string(),
+                                        response: Box::new(response),
+                                    }).await;
+                                }
+                            }
+                            SwarmEvent::Behaviour(PeerUPEvent::OutboundProbeFailure { peer, request_id, error }) => {
+                                if let Some(probe_id) = pending_probes.remove(&request_id) {
+                                    let _ = event_tx.send(P2PEvent::ProbeFailed {
+                                        probe_id,
                                         peer_id: peer.to_string(),
-                                        result: Box::new(peer_result),
+                                        error: error.to_string(),
                                     }).await;
                                 }
                             }
-                            SwarmEvent::Behaviour(PeerUPEvent::PeerDiscovered(peer)) |
-                            SwarmEvent::ConnectionEstablished { peer_id: peer, .. } => {
-                                let _ = event_tx.send(P2PEvent::PeerConnected(peer.to_string())).await;
+                            SwarmEvent::Behaviour(PeerUPEvent::ResultsSyncRequestReceived { peer, request, channel }) => {
+                                let inbound_id = next_inbound_id;
+                                next_inbound_id += 1;
+                                inbound_syncs.insert(inbound_id, channel);
+                                let _ = event_tx.send(P2PEvent::ResultsSyncRequested {
+                                    peer_id: peer.to_string(),
+                                    inbound_id,
+                                    request: Box::new(request),
+                                }).await;
+                            }
+                            SwarmEvent::Behaviour(PeerUPEvent::InboundResultsSyncFailure { peer, error, .. }) => {
+                                tracing::debug!("Results sync request from {} failed: {}", peer, error);
+                            }
+                            SwarmEvent::Behaviour(PeerUPEvent::ResultsSyncResponseReceived { peer, request_id, response }) => {
+                                if let Some(sync_id) = pending_syncs.remove(&request_id) {
+                                    let _ = event_tx.send(P2PEvent::ResultsSyncAnswered {
+                                        sync_id,
+                                        peer_id: peer.to_string(),
+                                        response: Box::new(response),
+                                    }).await;
+                                }
+                            }
+                            SwarmEvent::Behaviour(PeerUPEvent::OutboundResultsSyncFailure { peer, request_id, error }) => {
+                                if let Some(sync_id) = pending_syncs.remove(&request_id) {
+                                    let _ = event_tx.send(P2PEvent::ResultsSyncFailed {
+                                        sync_id,
+                                        peer_id: peer.to_string(),
+                                        error: error.to_string(),
+                                    }).await;
+                                }
+                            }
+                            SwarmEvent::Behaviour(PeerUPEvent::PeerGreylisted { peer, duration, offences }) => {
+                                let _ = event_tx.send(P2PEvent::PeerGreylisted {
+                                    peer_id: peer.to_string(),
+                                    duration,
+                                    offences,
+                                }).await;
+                            }
+                            SwarmEvent::Behaviour(PeerUPEvent::GossipsubSubscribed { .. }) => {
+                                replay_outbox(&mut node, &mut outbox);
+                            }
+                            SwarmEvent::Behaviour(PeerUPEvent::PeerDiscovered(peer)) => {
+                                let _ = event_tx.send(P2PEvent::PeerConnected {
+                                    peer_id: peer.to_string(),
+                                    address: None,
+                                }).await;
+                            }
+                            SwarmEvent::ConnectionEstablished { peer_id: peer, endpoint, .. } => {
+                                binding_due = true;
+                                let _ = event_tx.send(P2PEvent::PeerConnected {
+                                    peer_id: peer.to_string(),
+                                    address: Some(endpoint.get_remote_address().to_string()),
+                                }).await;
                             }
                             SwarmEvent::Behaviour(PeerUPEvent::PeerRemoved(peer)) |
                             SwarmEvent::ConnectionClosed { peer_id: peer, .. } => {
@@ -195,31 +753,50 @@ impl P2PNetwork {
                 }
             }
 
+            match node.shutdown(SHUTDOWN_DRAIN_TIMEOUT).await {
+                Ok(summary) => tracing::debug!("P2P node shutdown: {:?}", summary),
+                Err(e) => tracing::warn!("P2P node did not shut down cleanly: {}", e),
+            }
+
             tracing::info!("P2P event loop stopped");
         });
 
         Ok(())
     }
 
-    /// Share a monitoring result with the network
-    pub async fn share_result(&self, result: &CheckResult) -> anyhow::Result<()> {
-        if !self.enabled {
+    /// Cloneable handle for sending commands to the running node
+    pub fn handle(&self) -> P2PHandle {
+        P2PHandle { enabled: self.enabled, command_tx: self.command_tx.clone() }
+    }
+
+    /// Take the event receiver so a dedicated task can consume node events
+    pub fn take_event_receiver(&mut self) -> Option<QueueReceiver<P2PEvent>> {
+        self.event_rx.take()
+    }
+
+    /// Gracefully stop the P2P node and wait for its event loop to finish
+    pub async fn shutdown(&mut self) -> anyhow::Result<()> {
+        let Some(tx) = self.command_tx.take() else {
             return Ok(());
-        }
+        };
 
-        if let Some(tx) = &self.command_tx {
-            tx.send(P2PCommand::PublishResult(result.clone()))
-                .await
-                .map_err(|e| anyhow::anyhow!("Failed to send publish command: {}", e))?;
-            tracing::debug!("Sent publish command for monitor {}", result.monitor_id);
-        } else {
-            tracing::warn!("P2P node not started, cannot share result");
+        tx.send(P2PCommand::Shutdown)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send shutdown command: {}", e))?;
+
+        // The event channel closes once the node task has finished shutting down
+        if let Some(mut rx) = self.event_rx.take() {
+            let drained = async { while rx.recv().await.is_some() {} };
+            if tokio::time::timeout(SHUTDOWN_DRAIN_TIMEOUT * 2, drained).await.is_err() {
+                tracing::warn!("Timed out waiting for the P2P node to shut down");
+            }
         }
 
         Ok(())
     }
 
     /// Get the next event from the P2P network
+    #[allow(dead_code)] // Public API
     pub async fn next_event(&mut self) -> Option<P2PEvent> {
         if let Some(rx) = &mut self.event_rx { rx.recv().await } else { None }
     }
@@ -249,6 +826,290 @@ impl P2PNetwork {
     }
 }
 
+/// Cloneable sender side of a running P2P node
+#[derive(Clone)]
+pub struct P2PHandle {
+    enabled: bool,
+    command_tx: Option<QueueSender<P2PCommand>>,
+}
+
+impl P2PHandle {
+    /// Look up results peers stored in the DHT for one of our monitors
+    ///
+    /// The outcome arrives as `P2PEvent::OwnerResultsFetched`.
+    pub async fn fetch_owner_results(&self, monitor_id: Uuid) -> anyhow::Result<()> {
+        let tx = self
+            .command_tx
+            .as_ref()
+            .filter(|_| self.enabled)
+            .ok_or_else(|| anyhow::anyhow!("P2P node not started"))?;
+
+        tx.send(P2PCommand::FetchOwnerResults(monitor_id))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send fetch command: {}", e))
+    }
+
+    /// Look up results peers stored in the DHT for a followed target host
+    ///
+    /// Found results arrive as `P2PEvent::ResultBackfilled`.
+    pub async fn fetch_target_results(&self, host: String) -> anyhow::Result<()> {
+        let tx = self
+            .command_tx
+            .as_ref()
+            .filter(|_| self.enabled)
+            .ok_or_else(|| anyhow::anyhow!("P2P node not started"))?;
+
+        tx.send(P2PCommand::FetchTargetResults(host))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send fetch command: {}", e))
+    }
+
+    /// Tell the network a monitor turned private
+    pub async fn publish_retraction(&self, retraction: MonitorRetraction) -> anyhow::Result<()> {
+        let tx = self
+            .command_tx
+            .as_ref()
+            .filter(|_| self.enabled)
+            .ok_or_else(|| anyhow::anyhow!("P2P node not started"))?;
+
+        tx.send(P2PCommand::PublishRetraction(Box::new(retraction)))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send retraction command: {}", e))
+    }
+
+    /// Tell the network we joined or left monitoring a community domain
+    pub async fn publish_membership(
+        &self,
+        announcement: MembershipAnnouncement,
+    ) -> anyhow::Result<()> {
+        let tx = self
+            .command_tx
+            .as_ref()
+            .filter(|_| self.enabled)
+            .ok_or_else(|| anyhow::anyhow!("P2P node not started"))?;
+
+        tx.send(P2PCommand::PublishMembership(Box::new(announcement)))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send membership command: {}", e))
+    }
+
+    /// Look up the records under a debug key
+    ///
+    /// The outcome arrives as `P2PEvent::DhtOperationFinished`.
+    pub async fn get_debug_record(&self, operation_id: Uuid, key: String) -> anyhow::Result<()> {
+        self.send(P2PCommand::GetDHTRecord { operation_id, key }).await
+    }
+
+    /// Store a record under a debug key
+    ///
+    /// The outcome arrives as `P2PEvent::DhtOperationFinished`.
+    pub async fn publish_debug_record(
+        &self,
+        operation_id: Uuid,
+        key: String,
+        value: Vec<u8>,
+    ) -> anyhow::Result<()> {
+        self.send(P2PCommand::PublishDHTRecord { operation_id, key, value }).await
+    }
+
+    /// Ask peers to probe a target once on behalf of the fan-out `probe_id`
+    pub async fn request_probes(
+        &self,
+        probe_id: Uuid,
+        peers: Vec<String>,
+        request: ProbeRequest,
+    ) -> anyhow::Result<()> {
+        self.send(P2PCommand::RequestProbes { probe_id, peers, request: Box::new(request) })
+            .await
+    }
+
+    /// Answer a probe request a peer sent us
+    pub async fn respond_probe(
+        &self,
+        inbound_id: u64,
+        response: ProbeResponse,
+    ) -> anyhow::Result<()> {
+        self.send(P2PCommand::RespondProbe { inbound_id, response: Box::new(response) })
+            .await
+    }
+
+    /// Ask a peer for a page of the results it stored, on behalf of the sync `sync_id`
+    pub async fn request_results_sync(
+        &self,
+        sync_id: Uuid,
+        peer_id: String,
+        request: ResultsSyncRequest,
+    ) -> anyhow::Result<()> {
+        self.send(P2PCommand::RequestResultsSync { sync_id, peer_id, request: Box::new(request) })
+            .await
+    }
+
+    /// Answer a results sync request a peer sent us
+    pub async fn respond_results_sync(
+        &self,
+        inbound_id: u64,
+        response: ResultsSyncResponse,
+    ) -> anyhow::Result<()> {
+        self.send(P2PCommand::RespondResultsSync { inbound_id, response: Box::new(response) })
+            .await
+    }
+
+    /// Block a peer at the swarm, or lift the block
+    pub async fn block_peer(&self, peer_id: String, blocked: bool) -> anyhow::Result<()> {
+        self.send(P2PCommand::BlockPeer { peer_id, blocked }).await
+    }
+
+    /// Send the result of a helper duty, sealed for its owner
+    pub async fn publish_encrypted_result(
+        &self,
+        message: EncryptedResultMessage,
+    ) -> anyhow::Result<()> {
+        self.send(P2PCommand::PublishEncryptedResult(Box::new(message))).await
+    }
+
+    /// Ask a helper to check one of our private monitors
+    pub async fn publish_assignment_request(
+        &self,
+        request: HelperAssignmentRequest,
+    ) -> anyhow::Result<()> {
+        self.send(P2PCommand::PublishAssignmentRequest(Box::new(request))).await
+    }
+
+    /// Publish a sealed update to the other nodes of our cluster
+    pub async fn publish_cluster_update(
+        &self,
+        sealed: crate::crypto::SealedClusterMessage,
+    ) -> anyhow::Result<()> {
+        self.send(P2PCommand::PublishClusterUpdate(Box::new(sealed))).await
+    }
+
+    async fn send(&self, command: P2PCommand) -> anyhow::Result<()> {
+        let tx = self
+            .command_tx
+            .as_ref()
+            .filter(|_| self.enabled)
+            .ok_or_else(|| anyhow::anyhow!("P2P node not started"))?;
+
+        tx.send(command)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send command: {}", e))
+    }
+
+    /// Share a monitoring result, and optionally where it was checked from, with the network
+    pub async fn share_result(
+        &self,
+        result: &CheckResult,
+        location: Option<LocationClaim>,
+    ) -> anyhow::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        if let Some(tx) = &self.command_tx {
+            tx.send(P2PCommand::PublishResult(result.clone(), location.map(Box::new)))
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to send publish command: {}", e))?;
+            tracing::debug!("Sent publish command for monitor {}", result.monitor_id);
+        } else {
+            tracing::warn!("P2P node not started, cannot share result");
+        }
+
+        Ok(())
+    }
+}
+
+/// Repeated records after which an owner result lookup stops early
+const OWNER_FETCH_MAX_REPEATS: usize = 3;
+
+/// Progress of an owner result lookup
+///
+/// Records are replicated to the peers closest to the key, so a lookup keeps
+/// running into copies it already has. It stops once
+/// `OWNER_FETCH_MAX_REPEATS` copies came in, while one that keeps finding
+/// new records runs to the end.
+struct OwnerFetch {
+    monitor_id: Uuid,
+    /// Distinct records for the monitor found so far
+    records: usize,
+    /// Hashes of the records found so far
+    seen: HashSet<u64>,
+    repeats: usize,
+}
+
+impl OwnerFetch {
+    fn new(monitor_id: Uuid) -> Self {
+        Self { monitor_id, records: 0, seen: HashSet::new(), repeats: 0 }
+    }
+
+    /// Whether a record is new to this lookup, counting it as a repeat if not
+    fn is_new(&mut self, value: &[u8]) -> bool {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let new = self.seen.insert(hasher.finish());
+        if !new {
+            self.repeats += 1;
+        }
+        new
+    }
+
+    fn exhausted(&self) -> bool {
+        self.repeats >= OWNER_FETCH_MAX_REPEATS
+    }
+}
+
+/// Publish a payload on the results topic, or buffer it for replay while no
+/// peer would receive it or publishing fails
+fn publish_or_buffer(
+    node: &mut PeerNode,
+    outbox: &mut Outbox,
+    kind: &'static str,
+    payload: Vec<u8>,
+) -> anyhow::Result<()> {
+    // Buffered results go out first, as many as the replay rate allows
+    replay_outbox(node, outbox);
+    if !node.has_result_peers() {
+        tracing::debug!("No peers to receive {}, buffering it", kind);
+        outbox.push(kind, payload, Instant::now());
+        return Ok(());
+    }
+    node.publish_result(payload.clone())
+        .inspect_err(|_| outbox.push(kind, payload, Instant::now()))
+}
+
+/// Publish the buffered results once a peer on the results topic can receive
+/// them, as many as the replay rate allows
+fn replay_outbox(node: &mut PeerNode, outbox: &mut Outbox) {
+    if outbox.is_empty() || !node.has_result_peers() {
+        return;
+    }
+    let buffered = outbox.len();
+    let published = outbox.replay(Instant::now(), |payload| node.publish_result(payload));
+    if published == 0 {
+        return;
+    }
+    tracing::info!(
+        "Replayed {} of {} results buffered while offline ({} dropped so far)",
+        published,
+        buffered,
+        outbox.dropped()
+    );
+}
+
+/// Result received from a peer, attributed to the signer-declared peer ID
+/// (which matches the signature) rather than the libp2p ID it arrived from
+pub(super) fn peer_result(signed_msg: SignedMessage) -> PeerResult {
+    PeerResult {
+        signature: signed_msg.result.signature.clone(),
+        public_key: Some(signed_msg.public_key.to_vec()),
+        peer_id: signed_msg.result.peer_id.clone(),
+        location: signed_msg.location,
+        received_at: std::time::SystemTime::now(),
+        signature_valid: None,
+        identity_bound: None,
+        result: signed_msg.result,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -262,6 +1123,18 @@ mod tests {
         assert!(!network.is_enabled());
     }
 
+    #[test]
+    fn test_owner_fetch_stops_on_repeats() {
+        let mut fetch = OwnerFetch::new(Uuid::new_v4());
+        assert!(fetch.is_new(b"a"));
+        assert!(fetch.is_new(b"b"));
+        assert!(!fetch.is_new(b"a"));
+        assert!(!fetch.is_new(b"b"));
+        assert!(!fetch.exhausted());
+        assert!(!fetch.is_new(b"a"));
+        assert!(fetch.exhausted());
+    }
+
     #[tokio::test]
     async fn test_p2p_network_enabled() {
         let network = P2PNetwork::new("test-peer".to_string(), true);
@@ -0,0 +1,201 @@
+/// Outbox - results held back while no peer would receive them
+///
+/// Signed results and encrypted helper results are queued instead of
+/// published while no connected peer is subscribed to the results topic, and
+/// when publishing fails. Once a peer subscribes again they are replayed in
+/// order, no faster than the rate limit allows: peers drop gossip over their
+/// per-publisher limit, so a backlog goes out over several windows, and a
+/// payload that fails to publish waits for the next replay. The payloads are
+/// kept as encoded, so peers see the original timestamps and signatures. The
+/// oldest make room when the outbox is full, and anything older than a day is
+/// dropped.
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long a result is held before it is no longer worth replaying
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug)]
+struct Pending {
+    /// What the payload is, for logging
+    kind: &'static str,
+    payload: Vec<u8>,
+    queued_at: Instant,
+}
+
+/// Payloads waiting to be published, oldest first
+#[derive(Debug)]
+pub struct Outbox {
+    capacity: usize,
+    pending: VecDeque<Pending>,
+    /// Payloads dropped because the outbox was full or they got too old
+    dropped: u64,
+    /// Payloads replayed per window
+    max_replays: usize,
+    window: Duration,
+    /// Start of the current window and the payloads replayed in it
+    window_started: Option<Instant>,
+    replayed: usize,
+}
+
+impl Outbox {
+    /// Outbox holding up to `capacity` payloads; 0 holds none
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            pending: VecDeque::new(),
+            dropped: 0,
+            max_replays: usize::MAX,
+            window: Duration::from_secs(60),
+            window_started: None,
+            replayed: 0,
+        }
+    }
+
+    /// Replay at most `max_replays` payloads per `window`
+    pub fn with_rate_limit(mut self, max_replays: usize, window: Duration) -> Self {
+        self.max_replays = max_replays;
+        self.window = window;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Payloads dropped so far
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Queue a payload, dropping the oldest if the outbox is full
+    pub fn push(&mut self, kind: &'static str, payload: Vec<u8>, now: Instant) {
+        if self.capacity == 0 {
+            self.dropped += 1;
+            return;
+        }
+        if self.pending.len() >= self.capacity {
+            self.pending.pop_front();
+            self.dropped += 1;
+        }
+        self.pending.push_back(Pending { kind, payload, queued_at: now });
+    }
+
+    /// Publish the queued payloads in order, as many as the rate limit
+    /// allows, returning how many went out
+    ///
+    /// A payload that fails to publish goes back to the front of the outbox
+    /// and ends the replay; it is tried again on the next one.
+    pub fn replay(
+        &mut self,
+        now: Instant,
+        mut publish: impl FnMut(Vec<u8>) -> Result<()>,
+    ) -> usize {
+        if self
+            .window_started
+            .is_none_or(|started| now.duration_since(started) >= self.window)
+        {
+            self.window_started = Some(now);
+            self.replayed = 0;
+        }
+
+        let mut published = 0;
+        while self.replayed < self.max_replays {
+            let Some(pending) = self.pending.pop_front() else {
+                break;
+            };
+            if now.duration_since(pending.queued_at) > MAX_AGE {
+                self.dropped += 1;
+                continue;
+            }
+            // The payload is needed again if publishing fails
+            match publish(pending.payload.clone()) {
+                Ok(()) => {
+                    published += 1;
+                    self.replayed += 1;
+                }
+                Err(e) => {
+                    tracing::debug!("Failed to replay buffered {}, requeued: {}", pending.kind, e);
+                    self.pending.push_front(pending);
+                    break;
+                }
+            }
+        }
+        published
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_in_order() {
+        let now = Instant::now();
+        let mut outbox = Outbox::new(2);
+        outbox.push("result", b"a".to_vec(), now);
+        outbox.push("result", b"b".to_vec(), now);
+        outbox.push("encrypted result", b"c".to_vec(), now + Duration::from_secs(1));
+        assert_eq!(outbox.len(), 2);
+        assert_eq!(outbox.dropped(), 1);
+
+        let mut sent = Vec::new();
+        let published = outbox.replay(now + Duration::from_secs(2), |payload| {
+            sent.push(payload);
+            Ok(())
+        });
+        assert_eq!(published, 2);
+        assert_eq!(sent, vec![b"b".to_vec(), b"c".to_vec()]);
+        assert!(outbox.is_empty());
+
+        // Stale payloads are not replayed
+        outbox.push("result", b"d".to_vec(), now);
+        assert_eq!(outbox.replay(now + MAX_AGE * 2, |_| Ok(())), 0);
+        assert_eq!(outbox.dropped(), 2);
+    }
+
+    #[test]
+    fn test_replay_is_rate_limited_and_requeues_failures() {
+        let now = Instant::now();
+        let window = Duration::from_secs(60);
+        let mut outbox = Outbox::new(10).with_rate_limit(2, window);
+        for payload in [b"a", b"b", b"c", b"d", b"e"] {
+            outbox.push("result", payload.to_vec(), now);
+        }
+        let mut sent = Vec::new();
+
+        // Only two go out per window, however often the outbox is replayed
+        let published = outbox.replay(now, |payload| {
+            sent.push(payload);
+            Ok(())
+        });
+        assert_eq!(published, 2);
+        assert_eq!(outbox.replay(now + Duration::from_secs(30), |_| Ok(())), 0);
+        assert_eq!(outbox.len(), 3);
+
+        // "c" goes out, "d" fails and waits at the front for the next replay
+        let published = outbox.replay(now + window, |payload| {
+            if payload == b"d" {
+                anyhow::bail!("insufficient peers");
+            }
+            sent.push(payload);
+            Ok(())
+        });
+        assert_eq!(published, 1);
+        assert_eq!(outbox.len(), 2);
+        assert_eq!(outbox.dropped(), 0);
+
+        let published = outbox.replay(now + window * 2, |payload| {
+            sent.push(payload);
+            Ok(())
+        });
+        assert_eq!(published, 2);
+        assert!(outbox.is_empty());
+        assert_eq!(sent, [b"a", b"b", b"c", b"d", b"e"].map(|p| p.to_vec()));
+    }
+}
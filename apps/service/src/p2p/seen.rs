@@ -0,0 +1,138 @@
+/// Seen messages - recently verified gossip results, to skip duplicates
+///
+/// Gossipsub delivers the same message once per mesh it travels through. A
+/// result is remembered by its signature, monitor and timestamp once its
+/// signature checked out; a copy arriving later is dropped by the decoder
+/// before it is verified or stored again. The least recently seen entries
+/// make room when the cache is full.
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::database::models::Monitor;
+use crate::monitoring::CheckResult;
+
+/// Results remembered
+pub const SEEN_CAPACITY: usize = 10_000;
+
+/// Seen cache shared by the decoder's workers
+pub type SharedSeenMessages = Arc<Mutex<SeenMessages>>;
+
+/// What identifies a result on the network
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SeenKey {
+    signature: Vec<u8>,
+    monitor: Uuid,
+    timestamp: i64,
+}
+
+impl SeenKey {
+    /// Key of a signed result; `None` for an unsigned one
+    pub fn of(result: &CheckResult) -> Option<Self> {
+        Some(Self {
+            signature: result.signature.clone()?,
+            monitor: result.monitor_id,
+            timestamp: Monitor::timestamp_to_i64(result.timestamp),
+        })
+    }
+}
+
+/// Least-recently-used set of seen results, with lookup counters
+#[derive(Debug)]
+pub struct SeenMessages {
+    capacity: usize,
+    /// Last use of each key
+    entries: HashMap<SeenKey, u64>,
+    /// Keys by last use, oldest first
+    order: BTreeMap<u64, SeenKey>,
+    tick: u64,
+    lookups: u64,
+    hits: u64,
+}
+
+impl SeenMessages {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            lookups: 0,
+            hits: 0,
+        }
+    }
+
+    /// Whether the key was seen, counting the lookup and refreshing a hit
+    pub fn check(&mut self, key: &SeenKey) -> bool {
+        self.lookups += 1;
+        let hit = self.entries.contains_key(key);
+        if hit {
+            self.hits += 1;
+            self.touch(key.clone());
+        }
+        hit
+    }
+
+    /// Remember a key, evicting the least recently seen one when full
+    pub fn insert(&mut self, key: SeenKey) {
+        if !self.entries.contains_key(&key)
+            && self.entries.len() >= self.capacity
+            && let Some((_, oldest)) = self.order.pop_first()
+        {
+            self.entries.remove(&oldest);
+        }
+        self.touch(key);
+    }
+
+    /// Lookups and hits since start
+    pub fn counts(&self) -> (u64, u64) {
+        (self.lookups, self.hits)
+    }
+
+    fn touch(&mut self, key: SeenKey) {
+        self.tick += 1;
+        if let Some(previous) = self.entries.insert(key.clone(), self.tick) {
+            self.order.remove(&previous);
+        }
+        self.order.insert(self.tick, key);
+    }
+}
+
+impl Default for SeenMessages {
+    fn default() -> Self {
+        Self::new(SEEN_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(n: u8) -> SeenKey {
+        SeenKey { signature: vec![n; 64], monitor: Uuid::nil(), timestamp: n as i64 }
+    }
+
+    #[test]
+    fn test_evicts_least_recently_seen() {
+        let mut seen = SeenMessages::new(2);
+        seen.insert(key(1));
+        seen.insert(key(2));
+        // Seeing 1 again makes 2 the oldest
+        assert!(seen.check(&key(1)));
+        seen.insert(key(3));
+
+        assert!(seen.check(&key(1)));
+        assert!(!seen.check(&key(2)));
+        assert!(seen.check(&key(3)));
+        assert_eq!(seen.counts(), (4, 3));
+    }
+
+    #[test]
+    fn test_key_needs_signature() {
+        let mut result =
+            CheckResult::new(Uuid::new_v4(), "https://example.com".to_string(), "peer".to_string());
+        assert!(SeenKey::of(&result).is_none());
+        result.signature = Some(vec![7; 64]);
+        assert!(SeenKey::of(&result).is_some());
+    }
+}
@@ -0,0 +1,203 @@
+/// Probe target policy for helper assignments
+///
+/// Helpers run checks on behalf of other peers, so a request must never make
+/// them probe internal networks or pile onto a victim the operator excluded.
+/// Targets are rejected when they point at loopback, private, link-local or
+/// otherwise non-public addresses, or at a denied CIDR or domain. Hostnames are
+/// resolved and every address they resolve to must pass; probes run for peers
+/// then connect only to those addresses, so the name can't be rebound to an
+/// internal one in between.
+use anyhow::{Result, anyhow};
+use peerup::handlers::target::{Cidr, is_public};
+use std::net::{IpAddr, SocketAddr};
+use url::Url;
+
+use crate::config::HelperConfig;
+use crate::monitoring::checker::tls_target;
+
+/// Helper-side policy deciding which targets may be probed
+#[derive(Debug, Clone, Default)]
+pub struct ProbePolicy {
+    denied_cidrs: Vec<Cidr>,
+    denied_domains: Vec<String>,
+}
+
+impl ProbePolicy {
+    /// Build the policy from the operator's helper settings
+    ///
+    /// Malformed CIDRs are an error rather than silently allowing everything.
+    pub fn from_config(config: &HelperConfig) -> Result<Self> {
+        let denied_cidrs = config
+            .denied_cidrs
+            .iter()
+            .map(|cidr| Cidr::parse(cidr))
+            .collect::<Result<_>>()?;
+        let denied_domains = config
+            .denied_domains
+            .iter()
+            .map(|domain| domain.trim().trim_start_matches('.').to_lowercase())
+            .filter(|domain| !domain.is_empty())
+            .collect();
+
+        Ok(Self { denied_cidrs, denied_domains })
+    }
+
+    /// Check a target, resolving hostnames to make sure none maps to a denied address
+    pub async fn check(&self, target: &str, check_type: &str) -> Result<()> {
+        self.resolve(target, check_type).await.map(|_| ())
+    }
+
+    /// Check a target like [`Self::check`], returning its host and the
+    /// addresses a probe should be pinned to
+    pub async fn resolve(
+        &self,
+        target: &str,
+        check_type: &str,
+    ) -> Result<(String, Vec<SocketAddr>)> {
+        let (host, port) = target_host(target, check_type)?;
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_string();
+        self.check_host(&host)?;
+
+        let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => tokio::net::lookup_host((host.as_str(), port))
+                .await
+                .map_err(|e| anyhow!("Failed to resolve {host}: {e}"))?
+                .collect(),
+        };
+        if addrs.is_empty() {
+            return Err(anyhow!("{host} did not resolve to any address"));
+        }
+        for addr in &addrs {
+            self.check_ip(&addr.ip())?;
+        }
+
+        Ok((host, addrs))
+    }
+
+    /// Check a host without resolving it
+    pub fn check_host(&self, host: &str) -> Result<()> {
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_lowercase();
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return self.check_ip(&ip);
+        }
+
+        let host = host.trim_end_matches('.');
+        if host == "localhost" || host.ends_with(".localhost") {
+            return Err(anyhow!("Target {host} is a loopback name"));
+        }
+        if let Some(domain) = self
+            .denied_domains
+            .iter()
+            .find(|domain| host == domain.as_str() || host.ends_with(&format!(".{domain}")))
+        {
+            return Err(anyhow!("Target {host} is in denied domain {domain}"));
+        }
+
+        Ok(())
+    }
+
+    /// Check a single address
+    pub fn check_ip(&self, ip: &IpAddr) -> Result<()> {
+        if !is_public(ip) {
+            return Err(anyhow!("Target address {ip} is not publicly routable"));
+        }
+        if self.denied_cidrs.iter().any(|cidr| cidr.contains(ip)) {
+            return Err(anyhow!("Target address {ip} is in a denied range"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Extract the host and port a check would connect to
+fn target_host(target: &str, check_type: &str) -> Result<(String, u16)> {
+    match check_type.to_lowercase().as_str() {
+        "http" | "https" => {
+            let url = Url::parse(target).map_err(|e| anyhow!("Invalid URL {target}: {e}"))?;
+            let host = url.host_str().ok_or_else(|| anyhow!("URL {target} has no host"))?;
+            let port = url.port_or_known_default().unwrap_or(80);
+            Ok((host.to_string(), port))
+        }
+        "tcp" => {
+            let (host, port) = target
+                .rsplit_once(':')
+                .ok_or_else(|| anyhow!("TCP target must be in format 'host:port'"))?;
+            let port = port.parse().map_err(|_| anyhow!("Invalid port in {target}"))?;
+            Ok((host.trim_start_matches('[').trim_end_matches(']').to_string(), port))
+        }
+        "icmp" => Ok((target.to_string(), 0)),
+        "cert_expiry" => tls_target(target),
+        other => Err(anyhow!("Unknown check type: {other}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(cidrs: &[&str], domains: &[&str]) -> ProbePolicy {
+        ProbePolicy::from_config(&HelperConfig {
+            denied_cidrs: cidrs.iter().map(|s| s.to_string()).collect(),
+            denied_domains: domains.iter().map(|s| s.to_string()).collect(),
+            ..HelperConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rejects_non_public_addresses() {
+        let policy = ProbePolicy::default();
+        for host in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "[fe80::1]",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+            "localhost",
+            "api.localhost",
+        ] {
+            assert!(policy.check_host(host).is_err(), "{host} should be rejected");
+        }
+
+        assert!(policy.check_host("1.1.1.1").is_ok());
+        assert!(policy.check_host("2606:4700:4700::1111").is_ok());
+        assert!(policy.check_host("example.com").is_ok());
+    }
+
+    #[test]
+    fn test_denied_cidrs_and_domains() {
+        let policy =
+            policy(&["203.0.114.0/24", "2606:4700::/32"], &["victim.example", ".other.org"]);
+
+        assert!(policy.check_host("203.0.114.7").is_err());
+        assert!(policy.check_host("203.0.115.7").is_ok());
+        assert!(policy.check_host("2606:4700:4700::1111").is_err());
+        assert!(policy.check_host("victim.example").is_err());
+        assert!(policy.check_host("www.victim.example").is_err());
+        assert!(policy.check_host("notvictim.example").is_ok());
+        assert!(policy.check_host("a.other.org").is_err());
+
+        assert!(Cidr::parse("10.0.0.0/33").is_err());
+        assert!(Cidr::parse("not-an-ip/8").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_parses_targets() {
+        let policy = ProbePolicy::default();
+        assert!(policy.check("http://127.0.0.1:8080/admin", "http").await.is_err());
+        assert!(policy.check("[::1]:22", "tcp").await.is_err());
+        assert!(policy.check("192.168.0.1", "icmp").await.is_err());
+        assert!(policy.check("https://1.1.1.1", "https").await.is_ok());
+
+        let (host, addrs) = policy.resolve("https://1.1.1.1:8443/status", "https").await.unwrap();
+        assert_eq!(host, "1.1.1.1");
+        assert_eq!(addrs, ["1.1.1.1:8443".parse::<SocketAddr>().unwrap()]);
+    }
+}
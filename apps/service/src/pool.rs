@@ -1,16 +1,95 @@
-use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
 
+use anyhow::Result;
+use deadpool::Runtime;
 use deadpool::managed::{self, Pool, RecycleResult};
 use libsql::{Connection, Database, Error as LibsqlError, params};
+use opentelemetry::global;
+use opentelemetry::metrics::Histogram;
+
+use crate::config::DatabasePoolConfig;
 
 pub struct LibsqlManager {
     database: Database,
     recycle_count: AtomicUsize,
+    /// Waits at least this long count as slow
+    slow_wait: Duration,
+    waits: WaitStats,
+    wait_histogram: Histogram<f64>,
 }
 
 impl LibsqlManager {
     pub fn new(database: Database) -> Self {
-        Self { database, recycle_count: AtomicUsize::new(0) }
+        Self {
+            database,
+            recycle_count: AtomicUsize::new(0),
+            slow_wait: Duration::from_millis(DatabasePoolConfig::default().slow_wait_ms),
+            waits: WaitStats::default(),
+            wait_histogram: global::meter("uppe-service")
+                .f64_histogram("uppe.db.pool.wait")
+                .with_description("Time spent waiting for a database connection")
+                .with_unit("ms")
+                .build(),
+        }
+    }
+
+    /// Record how long a caller waited for a connection
+    pub fn record_wait(&self, waited: Duration) {
+        self.wait_histogram.record(waited.as_secs_f64() * 1000.0, &[]);
+        self.waits.record(waited, waited >= self.slow_wait);
+    }
+
+    /// Waits recorded since the last call
+    pub fn take_waits(&self) -> PoolWaits {
+        self.waits.take()
+    }
+}
+
+/// Connection waits over some period
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PoolWaits {
+    pub count: u64,
+    /// Waits at or over the configured slow threshold
+    pub slow: u64,
+    pub total: Duration,
+    pub longest: Duration,
+}
+
+impl PoolWaits {
+    /// Average wait, zero without waits
+    pub fn average(&self) -> Duration {
+        if self.count == 0 { Duration::ZERO } else { self.total / self.count as u32 }
+    }
+}
+
+/// Running wait counters, reset whenever they are taken
+#[derive(Debug, Default)]
+struct WaitStats {
+    count: AtomicU64,
+    slow: AtomicU64,
+    total_micros: AtomicU64,
+    longest_micros: AtomicU64,
+}
+
+impl WaitStats {
+    fn record(&self, waited: Duration, slow: bool) {
+        let micros = waited.as_micros() as u64;
+        self.count.fetch_add(1, Ordering::Relaxed);
+        if slow {
+            self.slow.fetch_add(1, Ordering::Relaxed);
+        }
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.longest_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    fn take(&self) -> PoolWaits {
+        PoolWaits {
+            count: self.count.swap(0, Ordering::Relaxed),
+            slow: self.slow.swap(0, Ordering::Relaxed),
+            total: Duration::from_micros(self.total_micros.swap(0, Ordering::Relaxed)),
+            longest: Duration::from_micros(self.longest_micros.swap(0, Ordering::Relaxed)),
+        }
     }
 }
 
@@ -27,7 +106,7 @@ impl managed::Manager for LibsqlManager {
         conn: &mut Self::Type,
         _: &managed::Metrics,
     ) -> RecycleResult<Self::Error> {
-        let recycle_count = self.recycle_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let recycle_count = self.recycle_count.fetch_add(1, Ordering::Relaxed);
         let row = conn
             .query("SELECT ?1", params![recycle_count as u64])
             .await?
@@ -40,3 +119,16 @@ impl managed::Manager for LibsqlManager {
 }
 
 pub type LibsqlPool = Pool<LibsqlManager>;
+
+/// Build the connection pool, starting at the configured minimum size
+pub fn build(database: Database, config: &DatabasePoolConfig) -> Result<LibsqlPool> {
+    let manager = LibsqlManager {
+        slow_wait: Duration::from_millis(config.slow_wait_ms),
+        ..LibsqlManager::new(database)
+    };
+    Ok(Pool::builder(manager)
+        .max_size(config.min_size.max(1))
+        .wait_timeout(Some(Duration::from_secs(config.wait_timeout_secs.max(1))))
+        .runtime(Runtime::Tokio1)
+        .build()?)
+}
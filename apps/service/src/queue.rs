@@ -0,0 +1,223 @@
+/// Queue - bounded channels with a configurable overflow policy
+///
+/// Used for the result channel and the P2P command and event channels in
+/// place of a plain `mpsc` channel. When a queue is full, `block` makes the
+/// sender wait for room, as `mpsc` does; `drop-oldest` discards the oldest
+/// queued item instead so the sender never stalls, counting what it drops.
+/// Depth and drops are recorded as `uppe.queue.depth` and
+/// `uppe.queue.dropped`, labelled with the queue's name.
+use opentelemetry::KeyValue;
+use opentelemetry::global;
+use opentelemetry::metrics::{Counter, Gauge};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use tokio::sync::Notify;
+
+use crate::config::{OverflowPolicy, QueueConfig};
+
+/// Error of a send to a queue whose receiver is gone, holding the item
+#[derive(Debug)]
+pub struct QueueClosed<T>(pub T);
+
+impl<T> fmt::Display for QueueClosed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "queue closed")
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for QueueClosed<T> {}
+
+struct Shared<T> {
+    items: Mutex<VecDeque<T>>,
+    config: QueueConfig,
+    /// Signalled when an item is queued or the last sender is dropped
+    available: Notify,
+    /// Signalled when an item is taken or the receiver is dropped
+    space: Notify,
+    senders: AtomicUsize,
+    receiver_dropped: AtomicBool,
+    dropped: AtomicU64,
+    labels: [KeyValue; 1],
+    depth_gauge: Gauge<u64>,
+    dropped_counter: Counter<u64>,
+}
+
+impl<T> Shared<T> {
+    fn record_depth(&self, depth: usize) {
+        self.depth_gauge.record(depth as u64, &self.labels);
+    }
+}
+
+/// Sending half of a queue
+pub struct QueueSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// Receiving half of a queue
+pub struct QueueReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// Create a queue; `name` labels its metrics
+pub fn bounded<T>(name: &'static str, config: QueueConfig) -> (QueueSender<T>, QueueReceiver<T>) {
+    let meter = global::meter("uppe-service");
+    let config = QueueConfig { capacity: config.capacity.max(1), ..config };
+    let shared = Arc::new(Shared {
+        items: Mutex::new(VecDeque::with_capacity(config.capacity)),
+        config,
+        available: Notify::new(),
+        space: Notify::new(),
+        senders: AtomicUsize::new(1),
+        receiver_dropped: AtomicBool::new(false),
+        dropped: AtomicU64::new(0),
+        labels: [KeyValue::new("queue", name)],
+        depth_gauge: meter
+            .u64_gauge("uppe.queue.depth")
+            .with_description("Items waiting in an internal queue")
+            .build(),
+        dropped_counter: meter
+            .u64_counter("uppe.queue.dropped")
+            .with_description("Items a full drop-oldest queue discarded")
+            .build(),
+    });
+
+    (QueueSender { shared: shared.clone() }, QueueReceiver { shared })
+}
+
+impl<T> QueueSender<T> {
+    /// Queue an item, waiting for room or dropping the oldest item when full
+    pub async fn send(&self, item: T) -> Result<(), QueueClosed<T>> {
+        let shared = &self.shared;
+        loop {
+            let space = shared.space.notified();
+            tokio::pin!(space);
+            space.as_mut().enable();
+
+            if shared.receiver_dropped.load(Ordering::Acquire) {
+                return Err(QueueClosed(item));
+            }
+            {
+                let mut items = shared.items.lock().unwrap();
+                if items.len() >= shared.config.capacity {
+                    match shared.config.overflow {
+                        OverflowPolicy::Block => {}
+                        OverflowPolicy::DropOldest => {
+                            items.pop_front();
+                            shared.dropped.fetch_add(1, Ordering::Relaxed);
+                            shared.dropped_counter.add(1, &shared.labels);
+                        }
+                    }
+                }
+                if items.len() < shared.config.capacity {
+                    items.push_back(item);
+                    shared.record_depth(items.len());
+                    drop(items);
+                    shared.available.notify_one();
+                    return Ok(());
+                }
+            }
+
+            space.await;
+        }
+    }
+
+    /// Items discarded since the queue was created
+    #[allow(dead_code)] // Public API
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> Clone for QueueSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::AcqRel);
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for QueueSender<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.available.notify_one();
+        }
+    }
+}
+
+impl<T> QueueReceiver<T> {
+    /// Next item; `None` once every sender is gone and the queue is empty
+    pub async fn recv(&mut self) -> Option<T> {
+        let shared = &self.shared;
+        loop {
+            // The single receiver may rely on the stored permit of `notify_one`
+            let available = shared.available.notified();
+            {
+                let mut items = shared.items.lock().unwrap();
+                if let Some(item) = items.pop_front() {
+                    shared.record_depth(items.len());
+                    drop(items);
+                    shared.space.notify_one();
+                    return Some(item);
+                }
+            }
+            if shared.senders.load(Ordering::Acquire) == 0 {
+                return None;
+            }
+            available.await;
+        }
+    }
+}
+
+impl<T> Drop for QueueReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.receiver_dropped.store(true, Ordering::Release);
+        self.shared.space.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_drop_oldest_keeps_newest() {
+        let config = QueueConfig { capacity: 3, overflow: OverflowPolicy::DropOldest };
+        let (tx, mut rx) = bounded("test", config);
+        for i in 0..5 {
+            tx.send(i).await.unwrap();
+        }
+        assert_eq!(tx.dropped(), 2);
+        drop(tx);
+
+        let mut received = Vec::new();
+        while let Some(i) = rx.recv().await {
+            received.push(i);
+        }
+        assert_eq!(received, vec![2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_block_waits_for_room() {
+        let config = QueueConfig { capacity: 2, overflow: OverflowPolicy::Block };
+        let (tx, mut rx) = bounded("test", config);
+        tx.send(1).await.unwrap();
+        tx.send(2).await.unwrap();
+        assert!(tokio::time::timeout(Duration::from_millis(50), tx.send(3)).await.is_err());
+
+        let sender = tokio::spawn({
+            let tx = tx.clone();
+            async move { tx.send(3).await.unwrap() }
+        });
+        assert_eq!(rx.recv().await, Some(1));
+        sender.await.unwrap();
+        assert_eq!(rx.recv().await, Some(2));
+        assert_eq!(rx.recv().await, Some(3));
+        assert_eq!(tx.dropped(), 0);
+
+        drop(rx);
+        assert!(tx.send(4).await.is_err());
+    }
+}
@@ -0,0 +1,559 @@
+/// Reports - weekly and monthly uptime and incident summaries
+///
+/// A report covers the monitors of a status page or tag, or every monitor,
+/// over the last full week or calendar month in UTC. Uptime and latency come
+/// from the daily result rollups, so a report can be rendered long after the
+/// raw results expired; incidents are those open at any time in the period.
+///
+/// Reports are rendered from a template in their format: Markdown, HTML, or
+/// Markdown laid out as a plain PDF. Templates are text with placeholders,
+/// replaced in one pass: `{title}`, `{period}`, `{start}` and `{end}` (the
+/// first and last day), `{generated}`, `{uptime}`, `{checks}`,
+/// `{monitor_count}`, `{incident_count}`, and `{monitors}` and `{incidents}`,
+/// which are tables. Anything else in braces is left as it is.
+use anyhow::{Result, anyhow};
+use lopdf::content::{Content, Operation};
+use lopdf::{Object, Stream, dictionary};
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::civil::{civil_from_days, days_from_civil};
+use crate::config::{ReportConfig, ReportFormat, ReportPeriod};
+use crate::database::Database;
+use crate::database::models::{Incident, ResultRollup, RollupPeriod};
+
+/// Incidents looked through for the ones in a period, newest first
+const INCIDENT_LIMIT: usize = 1000;
+/// Characters per line of a PDF page, in 9pt Courier
+const PDF_COLUMNS: usize = 90;
+/// Lines per PDF page
+const PDF_LINES: usize = 64;
+
+const MARKDOWN_TEMPLATE: &str = "\
+# {title}
+
+{period}
+
+**{uptime}** uptime over {checks} checks of {monitor_count} monitors; {incident_count} incidents.
+
+## Monitors
+
+{monitors}
+
+## Incidents
+
+{incidents}
+
+_Generated {generated}_
+";
+
+const HTML_TEMPLATE: &str = "\
+<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{title}</title>
+<style>
+body { font-family: sans-serif; max-width: 60em; margin: 2em auto; color: #222; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.4em; text-align: left; }
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<p>{period}</p>
+<p><strong>{uptime}</strong> uptime over {checks} checks of {monitor_count} monitors;
+{incident_count} incidents.</p>
+<h2>Monitors</h2>
+{monitors}
+<h2>Incidents</h2>
+{incidents}
+<p><small>Generated {generated}</small></p>
+</body>
+</html>
+";
+
+/// How one monitor did over the period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorSummary {
+    pub uuid: Uuid,
+    pub name: String,
+    pub target: String,
+    pub rollup: ResultRollup,
+}
+
+/// A report's figures, ready to render
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub title: String,
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub generated_at: SystemTime,
+    pub monitors: Vec<MonitorSummary>,
+    pub incidents: Vec<Incident>,
+}
+
+/// A rendered report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    /// Markdown or HTML, or for PDF reports the Markdown laid out in `pdf`
+    pub text: String,
+    pub pdf: Option<Vec<u8>>,
+}
+
+impl Document {
+    /// Contents of the report's file
+    pub fn bytes(&self) -> &[u8] {
+        self.pdf.as_deref().unwrap_or(self.text.as_bytes())
+    }
+}
+
+impl Report {
+    /// Gather the figures of a report over `start..end` from the database
+    pub async fn load(
+        db: &dyn Database,
+        config: &ReportConfig,
+        start: SystemTime,
+        end: SystemTime,
+    ) -> Result<Self> {
+        let mut monitors = match &config.status_page {
+            Some(slug) => {
+                let page = db
+                    .get_status_page(slug)
+                    .await?
+                    .ok_or_else(|| anyhow!("No status page '{slug}'"))?;
+                db.get_status_page_monitors(page.uuid).await?
+            }
+            None => db.get_monitors().await?,
+        };
+        if let Some(tag) = &config.tag {
+            monitors.retain(|m| m.tags.contains(tag));
+        }
+
+        let mut summaries = Vec::with_capacity(monitors.len());
+        for monitor in &monitors {
+            let mut days = db.get_rollups(monitor.uuid, RollupPeriod::Daily, start).await?;
+            days.retain(|day| day.bucket_start < end);
+            summaries.push(MonitorSummary {
+                uuid: monitor.uuid,
+                name: monitor.name.clone(),
+                target: monitor.target.clone(),
+                rollup: ResultRollup::merge(start, &days),
+            });
+        }
+
+        // Incidents without a monitor only belong in reports on every monitor
+        let scoped = config.status_page.is_some() || config.tag.is_some();
+        let covered: HashSet<Uuid> = monitors.iter().map(|m| m.uuid).collect();
+        let mut incidents: Vec<Incident> = db
+            .get_incidents(INCIDENT_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|incident| {
+                incident.started_at < end
+                    && incident.resolved_at.is_none_or(|resolved| resolved >= start)
+                    && incident.monitor_uuid.map_or(!scoped, |uuid| covered.contains(&uuid))
+            })
+            .collect();
+        incidents.reverse();
+
+        Ok(Self {
+            title: config.name.clone(),
+            start,
+            end,
+            generated_at: SystemTime::now(),
+            monitors: summaries,
+            incidents,
+        })
+    }
+
+    /// Uptime of all covered monitors together, as a percentage
+    pub fn uptime_percent(&self) -> Option<f64> {
+        let rollups: Vec<ResultRollup> = self.monitors.iter().map(|m| m.rollup.clone()).collect();
+        uptime_percent(&ResultRollup::merge(self.start, &rollups))
+    }
+
+    /// Human-readable span of the report, e.g. "2026-10-05 to 2026-10-11"
+    pub fn period(&self) -> String {
+        format!("{} to {}", format_date(self.start), format_date(self.last_day()))
+    }
+
+    /// Start of the last day covered
+    fn last_day(&self) -> SystemTime {
+        self.end - Duration::from_secs(RollupPeriod::Daily.seconds())
+    }
+
+    /// Render the report from `template`, or the built-in template of its format
+    pub fn render(&self, format: ReportFormat, template: Option<&str>) -> Result<Document> {
+        Ok(match format {
+            ReportFormat::Markdown => Document {
+                text: self.fill(template.unwrap_or(MARKDOWN_TEMPLATE), false),
+                pdf: None,
+            },
+            ReportFormat::Html => {
+                Document { text: self.fill(template.unwrap_or(HTML_TEMPLATE), true), pdf: None }
+            }
+            ReportFormat::Pdf => {
+                let text = self.fill(template.unwrap_or(MARKDOWN_TEMPLATE), false);
+                let pdf = pdf(&text)?;
+                Document { text, pdf: Some(pdf) }
+            }
+        })
+    }
+
+    fn fill(&self, template: &str, html: bool) -> String {
+        let text = |value: String| if html { escape_html(&value) } else { value };
+        let checks: u64 = self.monitors.iter().map(|m| m.rollup.checks).sum();
+        let values = [
+            ("title", text(self.title.clone())),
+            ("period", text(self.period())),
+            ("start", format_date(self.start)),
+            ("end", format_date(self.last_day())),
+            ("generated", format_time(self.generated_at)),
+            ("uptime", format_percent(self.uptime_percent())),
+            ("checks", checks.to_string()),
+            ("monitor_count", self.monitors.len().to_string()),
+            ("incident_count", self.incidents.len().to_string()),
+            ("monitors", self.monitor_table(html)),
+            ("incidents", self.incident_table(html)),
+        ];
+
+        fill_template(template, &values)
+    }
+
+    fn monitor_table(&self, html: bool) -> String {
+        if self.monitors.is_empty() {
+            return "No monitors.".to_string();
+        }
+
+        let rows = self.monitors.iter().map(|m| {
+            let latency = |ms: Option<u64>| ms.map(|ms| format!("{ms}ms")).unwrap_or("-".into());
+            vec![
+                m.name.clone(),
+                m.target.clone(),
+                format_percent(uptime_percent(&m.rollup)),
+                m.rollup.checks.to_string(),
+                m.rollup.down.to_string(),
+                latency(m.rollup.avg_latency_ms),
+                latency(m.rollup.p95_latency_ms),
+            ]
+        });
+        let header =
+            ["Monitor", "Target", "Uptime", "Checks", "Down", "Avg latency", "p95 latency"];
+        table(&header, rows, html)
+    }
+
+    fn incident_table(&self, html: bool) -> String {
+        if self.incidents.is_empty() {
+            return "No incidents.".to_string();
+        }
+
+        let rows = self.incidents.iter().map(|incident| {
+            let (resolved, duration) = match incident.resolved_at {
+                Some(resolved) => (
+                    format_time(resolved),
+                    format_duration(
+                        resolved.duration_since(incident.started_at).unwrap_or_default(),
+                    ),
+                ),
+                None => ("Open".to_string(), "-".to_string()),
+            };
+            vec![
+                incident.title.clone(),
+                incident.severity.clone(),
+                format_time(incident.started_at),
+                resolved,
+                duration,
+            ]
+        });
+        table(&["Incident", "Severity", "Started", "Resolved", "Duration"], rows, html)
+    }
+}
+
+/// The last full period before `now`, as `(start, end)`
+pub fn last_period(period: ReportPeriod, now: SystemTime) -> (SystemTime, SystemTime) {
+    let days = (now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / 86_400) as i64;
+    let (start, end) = match period {
+        ReportPeriod::Weekly => {
+            // The epoch fell on a Thursday
+            let monday = days - (days + 3).rem_euclid(7);
+            (monday - 7, monday)
+        }
+        ReportPeriod::Monthly => {
+            let (year, month, _) = civil_from_days(days);
+            let (prev_year, prev_month) =
+                if month == 1 { (year - 1, 12) } else { (year, month - 1) };
+            (days_from_civil(prev_year, prev_month, 1), days_from_civil(year, month, 1))
+        }
+    };
+
+    let at = |days: i64| UNIX_EPOCH + Duration::from_secs(days.max(0) as u64 * 86_400);
+    (at(start), at(end))
+}
+
+/// Share of checks that reached the target
+fn uptime_percent(rollup: &ResultRollup) -> Option<f64> {
+    (rollup.checks > 0).then(|| rollup.up as f64 * 100.0 / rollup.checks as f64)
+}
+
+/// Replace `{key}` placeholders with their values in one pass
+fn fill_template(template: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let value = rest.find('}').and_then(|close| {
+            let key = &rest[1..close];
+            values.iter().find(|(k, _)| *k == key).map(|(_, value)| (value, close))
+        });
+        match value {
+            Some((value, close)) => {
+                out.push_str(value);
+                rest = &rest[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// A Markdown or HTML table
+fn table(header: &[&str], rows: impl Iterator<Item = Vec<String>>, html: bool) -> String {
+    if html {
+        let cells = |cells: &[String], tag: &str| -> String {
+            cells
+                .iter()
+                .map(|cell| format!("<{tag}>{}</{tag}>", escape_html(cell)))
+                .collect()
+        };
+        let header: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+        let mut out = format!("<table>\n<tr>{}</tr>\n", cells(&header, "th"));
+        for row in rows {
+            out.push_str(&format!("<tr>{}</tr>\n", cells(&row, "td")));
+        }
+        out.push_str("</table>");
+        out
+    } else {
+        let mut out = format!("| {} |\n|{}\n", header.join(" | "), "---|".repeat(header.len()));
+        for row in rows {
+            let row: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+            out.push_str(&format!("| {} |\n", row.join(" | ")));
+        }
+        out.pop();
+        out
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn format_percent(percent: Option<f64>) -> String {
+    percent.map(|p| format!("{p:.3}%")).unwrap_or("n/a".to_string())
+}
+
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes}m"),
+        (hours, minutes) => format!("{hours}h {minutes}m"),
+    }
+}
+
+/// Format SystemTime as YYYY-MM-DD in UTC
+pub fn format_date(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Format SystemTime as YYYY-MM-DD HH:MM UTC
+fn format_time(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (hours, minutes) = ((secs % 86_400) / 3600, (secs % 3600) / 60);
+    format!("{} {hours:02}:{minutes:02} UTC", format_date(time))
+}
+
+/// Lay text out as a PDF in A4 pages of Courier, wrapping long lines
+///
+/// Characters outside ASCII are replaced with `?`, as the standard fonts
+/// carry no other encoding we could rely on.
+fn pdf(text: &str) -> Result<Vec<u8>> {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let chars: Vec<char> = line
+            .chars()
+            .map(|c| if c.is_ascii() && !c.is_control() { c } else { '?' })
+            .collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(chars.chunks(PDF_COLUMNS).map(|chunk| chunk.iter().collect::<String>()));
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+
+    let mut doc = lopdf::Document::with_version("1.4");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Courier",
+    });
+    let resources_id = doc.add_object(dictionary! { "Font" => dictionary! { "F1" => font_id } });
+
+    let mut kids = Vec::new();
+    for page in lines.chunks(PDF_LINES) {
+        let mut operations = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 9.into()]),
+            Operation::new("TL", vec![11.into()]),
+            Operation::new("Td", vec![50.into(), 806.into()]),
+        ];
+        operations.extend(
+            page.iter()
+                .map(|line| Operation::new("'", vec![Object::string_literal(line.as_str())])),
+        );
+        operations.push(Operation::new("ET", vec![]));
+
+        let content = Content { operations }.encode()?;
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        });
+        kids.push(Object::from(page_id));
+    }
+    let pages = dictionary! {
+        "Type" => "Pages",
+        "Count" => kids.len() as i64,
+        "Kids" => kids,
+        "Resources" => resources_id,
+        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+    };
+    doc.objects.insert(pages_id, Object::Dictionary(pages));
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog_id);
+    doc.compress();
+
+    let mut out = Vec::new();
+    doc.save_to(&mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn report() -> Report {
+        let rollup = |checks, up, latency| ResultRollup {
+            bucket_start: at(1_791_158_400),
+            checks,
+            up,
+            down: checks - up,
+            avg_latency_ms: Some(latency),
+            p95_latency_ms: Some(latency * 2),
+            max_latency_ms: Some(latency * 3),
+        };
+
+        Report {
+            title: "Weekly <ops>".to_string(),
+            // 2026-10-05 to 2026-10-12
+            start: at(1_791_158_400),
+            end: at(1_791_763_200),
+            generated_at: at(1_791_770_400),
+            monitors: vec![
+                MonitorSummary {
+                    uuid: Uuid::new_v4(),
+                    name: "api".to_string(),
+                    target: "https://api.example.com".to_string(),
+                    rollup: rollup(1000, 990, 40),
+                },
+                MonitorSummary {
+                    uuid: Uuid::new_v4(),
+                    name: "web|www".to_string(),
+                    target: "https://example.com".to_string(),
+                    rollup: rollup(1000, 1000, 20),
+                },
+            ],
+            incidents: vec![Incident {
+                uuid: Uuid::new_v4(),
+                title: "api is down".to_string(),
+                status: Incident::RESOLVED.to_string(),
+                severity: "major".to_string(),
+                monitor_uuid: None,
+                started_at: at(1_791_195_180),
+                resolved_at: Some(at(1_791_200_580)),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_last_period() {
+        // Wednesday 2026-10-14 12:00 UTC
+        let now = at(1_791_979_200);
+        assert_eq!(last_period(ReportPeriod::Weekly, now), (at(1_791_158_400), at(1_791_763_200)));
+        // September 2026
+        assert_eq!(last_period(ReportPeriod::Monthly, now), (at(1_788_220_800), at(1_790_812_800)));
+        // January rolls back to December
+        let (start, end) = last_period(ReportPeriod::Monthly, at(1_799_020_800));
+        assert_eq!(
+            (format_date(start), format_date(end)),
+            ("2026-12-01".into(), "2027-01-01".into())
+        );
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let report = report();
+        assert_eq!(report.period(), "2026-10-05 to 2026-10-11");
+        assert_eq!(report.uptime_percent(), Some(99.5));
+
+        let text = report.render(ReportFormat::Markdown, None).unwrap().text;
+        assert!(text.starts_with("# Weekly <ops>\n\n2026-10-05 to 2026-10-11\n"));
+        assert!(text.contains("**99.500%** uptime over 2000 checks of 2 monitors; 1 incidents."));
+        assert!(
+            text.contains("| api | https://api.example.com | 99.000% | 1000 | 10 | 40ms | 80ms |")
+        );
+        assert!(text.contains("| web\\|www |"));
+        assert!(text.contains(
+            "| api is down | major | 2026-10-05 10:13 UTC | 2026-10-05 11:43 UTC | 1h 30m |"
+        ));
+
+        let custom = report
+            .render(ReportFormat::Markdown, Some("{title}: {uptime} {unknown} {"))
+            .unwrap();
+        assert_eq!(custom.text, "Weekly <ops>: 99.500% {unknown} {");
+    }
+
+    #[test]
+    fn test_render_html_and_pdf() {
+        let html = report().render(ReportFormat::Html, None).unwrap();
+        assert!(html.text.contains("<h1>Weekly &lt;ops&gt;</h1>"));
+        assert!(html.text.contains("<td>web|www</td>"));
+        assert_eq!(html.bytes(), html.text.as_bytes());
+
+        let pdf = report().render(ReportFormat::Pdf, None).unwrap();
+        let doc = lopdf::Document::load_mem(pdf.bytes()).unwrap();
+        let pages = doc.get_pages();
+        assert_eq!(pages.len(), 1);
+        let content = Content::decode(&doc.get_page_content(pages[&1]).unwrap()).unwrap();
+        let first = &content.operations.iter().find(|op| op.operator == "'").unwrap().operands[0];
+        assert_eq!(first.as_str().unwrap(), b"# Weekly <ops>");
+    }
+}
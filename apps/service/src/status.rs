@@ -0,0 +1,235 @@
+/// Status - a one-shot summary of monitors, peers and open incidents
+///
+/// `uppe status` prints the summary as JSON or plain text for scripts and
+/// MOTD banners, and exits with `DOWN_EXIT_CODE` while an enabled monitor is
+/// down. Timestamps are Unix seconds, as in exports.
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::database::models::Monitor;
+use crate::monitoring::types::MonitorStatus;
+
+/// Exit code of `uppe status` while an enabled monitor is down
+pub const DOWN_EXIT_CODE: i32 = 2;
+
+/// Current state of one monitor
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorLine {
+    pub uuid: Uuid,
+    pub name: String,
+    pub target: String,
+    pub enabled: bool,
+    /// Status of the latest result; `None` until the monitor was checked
+    pub status: Option<MonitorStatus>,
+    pub latency_ms: Option<u64>,
+    pub checked_at: Option<i64>,
+}
+
+impl MonitorLine {
+    fn is_down(&self) -> bool {
+        self.enabled && self.status == Some(MonitorStatus::Down)
+    }
+}
+
+/// Peers in the latest network stats
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PeerCounts {
+    pub online: i64,
+    pub total: i64,
+}
+
+/// Incident that is not resolved yet
+#[derive(Debug, Clone, Serialize)]
+pub struct IncidentLine {
+    pub uuid: Uuid,
+    pub title: String,
+    pub status: String,
+    pub severity: String,
+    pub monitor_uuid: Option<Uuid>,
+    pub started_at: i64,
+}
+
+/// Summary printed by `uppe status`
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusReport {
+    pub monitors: Vec<MonitorLine>,
+    pub peers: PeerCounts,
+    pub incidents: Vec<IncidentLine>,
+}
+
+impl StatusReport {
+    /// Gather the summary from the database
+    pub async fn load(db: &dyn Database) -> Result<Self> {
+        let latest: HashMap<Uuid, _> = db
+            .get_latest_results()
+            .await?
+            .into_iter()
+            .map(|result| (result.monitor_uuid, result))
+            .collect();
+
+        let monitors = db
+            .get_monitors()
+            .await?
+            .into_iter()
+            .map(|m| {
+                let result = latest.get(&m.uuid);
+                MonitorLine {
+                    uuid: m.uuid,
+                    name: m.name,
+                    target: m.target,
+                    enabled: m.enabled,
+                    status: result.map(|r| r.status),
+                    latency_ms: result.and_then(|r| r.latency_ms),
+                    checked_at: result.map(|r| Monitor::timestamp_to_i64(r.timestamp)),
+                }
+            })
+            .collect();
+
+        let peers = db
+            .get_latest_network_stats()
+            .await?
+            .map(|stats| PeerCounts { online: stats.online_peers, total: stats.total_peers })
+            .unwrap_or_default();
+
+        let incidents = db
+            .get_open_incidents()
+            .await?
+            .into_iter()
+            .map(|incident| IncidentLine {
+                uuid: incident.uuid,
+                title: incident.title,
+                status: incident.status,
+                severity: incident.severity,
+                monitor_uuid: incident.monitor_uuid,
+                started_at: Monitor::timestamp_to_i64(incident.started_at),
+            })
+            .collect();
+
+        Ok(Self { monitors, peers, incidents })
+    }
+
+    /// Whether any enabled monitor is down
+    pub fn any_down(&self) -> bool {
+        self.monitors.iter().any(MonitorLine::is_down)
+    }
+
+    /// Plain text summary, one line per monitor and incident
+    pub fn plain(&self) -> String {
+        let count = |status: MonitorStatus| {
+            self.monitors.iter().filter(|m| m.enabled && m.status == Some(status)).count()
+        };
+        let paused = self.monitors.iter().filter(|m| !m.enabled).count();
+        let unchecked = self.monitors.iter().filter(|m| m.enabled && m.status.is_none()).count();
+
+        let mut out = format!(
+            "Monitors: {} up, {} degraded, {} down, {} unknown",
+            count(MonitorStatus::Up),
+            count(MonitorStatus::Degraded),
+            count(MonitorStatus::Down),
+            count(MonitorStatus::Unknown) + unchecked,
+        );
+        if paused > 0 {
+            out.push_str(&format!(", {paused} paused"));
+        }
+        out.push('\n');
+
+        for m in &self.monitors {
+            let state = match (m.enabled, m.status) {
+                (false, _) => "PAUSED".to_string(),
+                (true, Some(status)) => status.to_string().to_uppercase(),
+                (true, None) => "UNCHECKED".to_string(),
+            };
+            let latency = m.latency_ms.map(|ms| format!(" ({ms}ms)")).unwrap_or_default();
+            out.push_str(&format!("  {state:<9} {} -> {}{latency}\n", m.name, m.target));
+        }
+
+        out.push_str(&format!("Peers: {} online of {}\n", self.peers.online, self.peers.total));
+
+        if self.incidents.is_empty() {
+            out.push_str("Incidents: none\n");
+        } else {
+            out.push_str(&format!("Incidents: {} open\n", self.incidents.len()));
+            for incident in &self.incidents {
+                out.push_str(&format!(
+                    "  [{}] {} ({})\n",
+                    incident.severity, incident.title, incident.status
+                ));
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(name: &str, enabled: bool, status: Option<MonitorStatus>) -> MonitorLine {
+        MonitorLine {
+            uuid: Uuid::new_v4(),
+            name: name.to_string(),
+            target: format!("https://{name}.example.com"),
+            enabled,
+            status,
+            latency_ms: status.map(|_| 42),
+            checked_at: status.map(|_| 1_700_000_000),
+        }
+    }
+
+    #[test]
+    fn test_any_down_ignores_paused_monitors() {
+        let mut report = StatusReport {
+            monitors: vec![
+                line("api", true, Some(MonitorStatus::Up)),
+                line("old", false, Some(MonitorStatus::Down)),
+            ],
+            peers: PeerCounts::default(),
+            incidents: Vec::new(),
+        };
+        assert!(!report.any_down());
+
+        report.monitors.push(line("web", true, Some(MonitorStatus::Down)));
+        assert!(report.any_down());
+    }
+
+    #[test]
+    fn test_plain_summary() {
+        let report = StatusReport {
+            monitors: vec![
+                line("api", true, Some(MonitorStatus::Up)),
+                line("web", true, Some(MonitorStatus::Down)),
+                line("new", true, None),
+                line("old", false, Some(MonitorStatus::Up)),
+            ],
+            peers: PeerCounts { online: 3, total: 5 },
+            incidents: vec![IncidentLine {
+                uuid: Uuid::new_v4(),
+                title: "Web outage".to_string(),
+                status: "investigating".to_string(),
+                severity: "major".to_string(),
+                monitor_uuid: None,
+                started_at: 1_700_000_000,
+            }],
+        };
+
+        let plain = report.plain();
+        let mut lines = plain.lines();
+        assert_eq!(lines.next(), Some("Monitors: 1 up, 0 degraded, 1 down, 1 unknown, 1 paused"));
+        assert_eq!(lines.next(), Some("  UP        api -> https://api.example.com (42ms)"));
+        assert_eq!(lines.next(), Some("  DOWN      web -> https://web.example.com (42ms)"));
+        assert_eq!(lines.next(), Some("  UNCHECKED new -> https://new.example.com"));
+        assert_eq!(lines.next(), Some("  PAUSED    old -> https://old.example.com (42ms)"));
+        assert_eq!(lines.next(), Some("Peers: 3 online of 5"));
+        assert_eq!(lines.next(), Some("Incidents: 1 open"));
+        assert_eq!(lines.next(), Some("  [major] Web outage (investigating)"));
+        assert_eq!(lines.next(), None);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["monitors"][1]["status"], "down");
+        assert_eq!(json["peers"]["online"], 3);
+    }
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+use crossterm::event::KeyCode;
+use std::time::{Instant, SystemTime};
+
+use crate::database::Database;
+use crate::database::models::AuditEvent;
+use crate::tui::state::AppState;
+use crate::tui::types::BulkAction;
+
+/// Handle keyboard events in the bulk operation confirmation
+pub async fn handle_bulk_confirm(
+    state: &mut AppState,
+    key_code: KeyCode,
+    db: &dyn Database,
+) -> Result<()> {
+    let Some(action) = state.bulk_confirm.clone() else {
+        return Ok(());
+    };
+
+    match key_code {
+        KeyCode::Char('y') => {
+            let monitors: Vec<_> = state.marked_monitors().into_iter().cloned().collect();
+            for mut m in monitors {
+                let event = match &action {
+                    BulkAction::Pause | BulkAction::Enable => {
+                        let enabled = action == BulkAction::Enable;
+                        if m.enabled == enabled {
+                            continue;
+                        }
+                        m.enabled = enabled;
+                        db.save_monitor(&m).await?;
+                        let verb = if enabled { "Enabled" } else { "Disabled" };
+                        format!("{verb} monitor '{}' ({}) via TUI bulk action", m.name, m.uuid)
+                    }
+                    BulkAction::Delete => {
+                        db.delete_monitor(m.uuid).await?;
+                        format!("Deleted monitor '{}' ({}) via TUI bulk action", m.name, m.uuid)
+                    }
+                    BulkAction::Tag(tags) => {
+                        m.tags = tags.clone();
+                        m.updated_at = SystemTime::now();
+                        db.save_monitor(&m).await?;
+                        format!(
+                            "Set tags of monitor '{}' ({}) to [{}] via TUI bulk action",
+                            m.name,
+                            m.uuid,
+                            m.tags.join(", ")
+                        )
+                    }
+                };
+                db.append_audit_event(&AuditEvent::admin(event)).await?;
+            }
+
+            let done = match action {
+                BulkAction::Pause => "Paused",
+                BulkAction::Enable => "Enabled",
+                BulkAction::Delete => "Deleted",
+                BulkAction::Tag(_) => "Re-tagged",
+            };
+            state.status_message =
+                Some((format!("{done} {} monitors", state.marked.len()), Instant::now()));
+            state.marked.clear();
+            state.bulk_confirm = None;
+            state.refresh_monitors_and_results(db).await?;
+            state.last_refresh = Instant::now();
+        }
+        KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('q') => {
+            state.bulk_confirm = None;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use crossterm::event::KeyCode;
+
+use crate::database::Database;
+use crate::database::models::AuditEvent;
+use crate::orchestrator::community;
+use crate::tui::state::AppState;
+
+/// Handle keyboard events in the community domains popup
+pub async fn handle_community_popup(
+    state: &mut AppState,
+    key_code: KeyCode,
+    db: &dyn Database,
+) -> Result<()> {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('M') => {
+            state.show_community = false;
+        }
+        KeyCode::Char('j') | KeyCode::Down => {
+            if state.selected_community + 1 < state.community.len() {
+                state.selected_community += 1;
+            }
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            state.selected_community = state.selected_community.saturating_sub(1);
+        }
+        KeyCode::Char('r') => state.load_community(db).await?,
+
+        // Join the selected domain, or leave it
+        KeyCode::Char(' ') | KeyCode::Enter => {
+            let Some((group, domain)) = state.community.get(state.selected_community) else {
+                return Ok(());
+            };
+            let host = group.host.clone();
+            if domain.as_ref().is_some_and(|domain| domain.joined) {
+                leave(state, &host, db).await?;
+            } else {
+                join(state, &host, db).await?;
+            }
+        }
+
+        // Join the domain the selected monitor checks
+        KeyCode::Char('+') => {
+            if let Some(target) = state.monitors.get(state.selected).map(|m| m.target.clone()) {
+                join(state, &target, db).await?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+async fn join(state: &mut AppState, target: &str, db: &dyn Database) -> Result<()> {
+    state.community_status = Some(match community::join(db, target).await {
+        Ok(domain) => {
+            db.append_audit_event(&AuditEvent::admin(format!(
+                "Joined monitoring {} via TUI",
+                domain.host
+            )))
+            .await?;
+            format!("Joined {}; the service announces it shortly", domain.host)
+        }
+        Err(e) => format!("Failed to join: {e}"),
+    });
+    state.load_community(db).await?;
+    state.reload_monitors(db).await
+}
+
+async fn leave(state: &mut AppState, host: &str, db: &dyn Database) -> Result<()> {
+    state.community_status = Some(match community::leave(db, host).await? {
+        Some(domain) => {
+            db.append_audit_event(&AuditEvent::admin(format!(
+                "Left monitoring {} via TUI",
+                domain.host
+            )))
+            .await?;
+            format!("Left {}", domain.host)
+        }
+        None => format!("Not monitoring {host}"),
+    });
+    state.load_community(db).await?;
+    state.reload_monitors(db).await
+}
@@ -0,0 +1,82 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::database::Database;
+use crate::database::models::{AuditEvent, DhtOperation, DhtOperationKind};
+use crate::p2p::messages::debug_key;
+use crate::tui::state::AppState;
+use crate::validation;
+
+/// Handle keyboard events in the DHT debug popup
+pub async fn handle_dht_popup(
+    state: &mut AppState,
+    key: KeyEvent,
+    db: &dyn Database,
+) -> Result<()> {
+    let Some(form) = state.dht_form.as_mut() else {
+        return Ok(());
+    };
+
+    // A publish waits for an explicit yes
+    if form.confirm_put {
+        match key.code {
+            KeyCode::Char('y') => {
+                form.confirm_put = false;
+                let operation = DhtOperation::pending(
+                    DhtOperationKind::Put,
+                    form.key.clone(),
+                    Some(form.value.clone()),
+                );
+                db.save_dht_operation(&operation).await?;
+                db.append_audit_event(&AuditEvent::admin(format!(
+                    "Published DHT record '{}' ({} bytes) via TUI",
+                    debug_key(&operation.key),
+                    form.value.len()
+                )))
+                .await?;
+                state.dht_operations = db.get_dht_operations(20).await?;
+            }
+            KeyCode::Char('n') | KeyCode::Esc => form.confirm_put = false,
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    let field = if form.editing_value { &mut form.value } else { &mut form.key };
+    match key.code {
+        KeyCode::Esc => {
+            state.dht_form = None;
+            state.validation_error = None;
+        }
+        KeyCode::Tab | KeyCode::BackTab => {
+            form.editing_value = !form.editing_value;
+        }
+        KeyCode::Backspace => {
+            field.pop();
+            state.validation_error = None;
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            field.push(c);
+            state.validation_error = None;
+        }
+
+        // Enter looks the key up from the key field and publishes from the value field
+        KeyCode::Enter => {
+            let result = validation::validate_dht_record(&form.key, &form.value);
+            if !result.is_valid {
+                state.validation_error = result.error;
+            } else if form.editing_value {
+                form.confirm_put = true;
+            } else {
+                let operation =
+                    DhtOperation::pending(DhtOperationKind::Get, form.key.clone(), None);
+                db.save_dht_operation(&operation).await?;
+                state.dht_operations = db.get_dht_operations(20).await?;
+            }
+        }
+
+        _ => {}
+    }
+
+    Ok(())
+}
@@ -1,14 +1,15 @@
 use anyhow::Result;
 use crossterm::event::KeyCode;
 
-use crate::database::{Database, DatabaseImpl};
+use crate::database::Database;
+use crate::database::models::AuditEvent;
 use crate::tui::state::AppState;
 
 /// Handle keyboard events in edit popup
 pub async fn handle_edit_popup(
     state: &mut AppState,
     key_code: KeyCode,
-    db: &DatabaseImpl,
+    db: &dyn Database,
 ) -> Result<()> {
     match key_code {
         KeyCode::Esc => {
@@ -109,6 +110,12 @@ pub async fn handle_edit_popup(
                     && let Some(m) = state.edit_monitor.take()
                 {
                     db.save_monitor(&m).await?;
+                    let action = if state.is_add_form { "Added" } else { "Edited" };
+                    db.append_audit_event(&AuditEvent::admin(format!(
+                        "{action} monitor '{}' ({}) via TUI",
+                        m.name, m.uuid
+                    )))
+                    .await?;
                     state.close_edit();
                     state.refresh_monitors_and_results(db).await?;
                 }
@@ -134,6 +141,8 @@ pub async fn handle_edit_popup(
                                 "http" => "https".into(),
                                 "https" => "tcp".into(),
                                 "tcp" => "icmp".into(),
+                                "icmp" => "cert_expiry".into(),
+                                "cert_expiry" => "heartbeat".into(),
                                 _ => "http".into(),
                             };
                         }
@@ -164,7 +173,9 @@ pub async fn handle_edit_popup(
                                 "https" => "http".into(),
                                 "tcp" => "https".into(),
                                 "icmp" => "tcp".into(),
-                                _ => "icmp".into(),
+                                "cert_expiry" => "icmp".into(),
+                                "heartbeat" => "cert_expiry".into(),
+                                _ => "heartbeat".into(),
                             };
                         }
                         3 => {
@@ -210,6 +221,8 @@ pub async fn handle_edit_popup(
                                 "http" => "https".into(),
                                 "https" => "tcp".into(),
                                 "tcp" => "icmp".into(),
+                                "icmp" => "cert_expiry".into(),
+                                "cert_expiry" => "heartbeat".into(),
                                 _ => "http".into(),
                             };
                         }
@@ -223,7 +236,9 @@ pub async fn handle_edit_popup(
                                         "https" => "http".into(),
                                         "tcp" => "https".into(),
                                         "icmp" => "tcp".into(),
-                                        _ => "icmp".into(),
+                                        "cert_expiry" => "icmp".into(),
+                                        "heartbeat" => "cert_expiry".into(),
+                                        _ => "heartbeat".into(),
                                     };
                                 }
                                 3 => {
@@ -246,6 +261,8 @@ pub async fn handle_edit_popup(
                                         "http" => "https".into(),
                                         "https" => "tcp".into(),
                                         "tcp" => "icmp".into(),
+                                        "icmp" => "cert_expiry".into(),
+                                        "cert_expiry" => "heartbeat".into(),
                                         _ => "http".into(),
                                     };
                                 }
@@ -0,0 +1,45 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::path::Path;
+use std::time::Instant;
+
+use crate::export;
+use crate::tui::state::AppState;
+
+/// Handle keyboard events in the export prompt
+pub fn handle_export_input(state: &mut AppState, key: KeyEvent) -> Result<()> {
+    let Some(path) = state.export_path.as_mut() else {
+        return Ok(());
+    };
+
+    match key.code {
+        KeyCode::Esc => {
+            state.export_path = None;
+            state.validation_error = None;
+        }
+        KeyCode::Backspace => {
+            path.pop();
+            state.validation_error = None;
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            path.push(c);
+            state.validation_error = None;
+        }
+        KeyCode::Enter => {
+            let path = path.trim().to_string();
+            let data = state.export_data();
+            let (name, count) = (data.name(), data.len());
+            if let Err(e) = export::write(Path::new(&path), &data) {
+                state.validation_error = Some(e.to_string());
+                return Ok(());
+            }
+
+            state.status_message =
+                Some((format!("Exported {count} {name} to {path}"), Instant::now()));
+            state.export_path = None;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
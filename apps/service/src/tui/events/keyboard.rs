@@ -1,18 +1,111 @@
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use crate::database::models::Monitor;
-use crate::database::{Database, DatabaseImpl};
+use crate::database::Database;
+use crate::database::models::{AuditEvent, Monitor};
 use crate::tui::state::AppState;
-use crate::tui::types::Focus;
+use crate::tui::types::{BulkAction, Focus};
+use crate::validation;
+
+/// Handle keyboard events while typing a monitor search; the list is filtered
+/// as the query changes
+pub async fn handle_search_input(
+    state: &mut AppState,
+    key: KeyEvent,
+    db: &dyn Database,
+) -> Result<()> {
+    match key.code {
+        // Keep the filter and go back to navigating
+        KeyCode::Enter => state.search_active = false,
+        KeyCode::Esc => {
+            state.search_active = false;
+            state.search_query.clear();
+        }
+        KeyCode::Backspace => {
+            state.search_query.pop();
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            state.search_query.push(c);
+        }
+        _ => return Ok(()),
+    }
+
+    state.apply_monitor_view();
+    state.load_selected_results(db).await
+}
+
+/// Handle keyboard events in the tag editor of the selected monitor
+pub async fn handle_tag_input(
+    state: &mut AppState,
+    key: KeyEvent,
+    db: &dyn Database,
+) -> Result<()> {
+    let Some(input) = state.tag_input.as_mut() else {
+        return Ok(());
+    };
+
+    match key.code {
+        KeyCode::Esc => {
+            state.tag_input = None;
+            state.validation_error = None;
+        }
+        KeyCode::Backspace => {
+            input.pop();
+            state.validation_error = None;
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            input.push(c);
+            state.validation_error = None;
+        }
+        KeyCode::Enter => {
+            let tags = Monitor::parse_tags(input);
+            let result = validation::validate_tags(&tags);
+            if !result.is_valid {
+                state.validation_error = result.error;
+                return Ok(());
+            }
+
+            // With monitors marked, the tags replace theirs once confirmed
+            if !state.marked.is_empty() {
+                state.bulk_confirm = Some(BulkAction::Tag(tags));
+            } else if let Some(mut m) = state.monitors.get(state.selected).cloned() {
+                m.tags = tags;
+                m.updated_at = std::time::SystemTime::now();
+                db.save_monitor(&m).await?;
+                db.append_audit_event(&AuditEvent::admin(format!(
+                    "Set tags of monitor '{}' ({}) to [{}] via TUI",
+                    m.name,
+                    m.uuid,
+                    m.tags.join(", ")
+                )))
+                .await?;
+                state.reload_monitors(db).await?;
+            }
+            state.tag_input = None;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
 
 /// Handle keyboard events in main view (no popups open)
 pub async fn handle_main_view(
     state: &mut AppState,
     key: KeyEvent,
-    db: &DatabaseImpl,
+    db: &dyn Database,
 ) -> Result<bool> {
     match key.code {
+        // Clear the marks, then the monitor search, before quitting
+        KeyCode::Esc if !state.marked.is_empty() => {
+            state.marked.clear();
+        }
+        KeyCode::Esc if !state.search_query.is_empty() => {
+            state.search_query.clear();
+            state.apply_monitor_view();
+            state.load_selected_results(db).await?;
+        }
+
         // Quit
         KeyCode::Char('q') | KeyCode::Esc if key.modifiers.is_empty() => {
             return Ok(true); // Signal to quit
@@ -60,7 +153,10 @@ pub async fn handle_main_view(
             Focus::Results => {
                 state.next_result();
             }
-            Focus::Stats | Focus::Network => {}
+            Focus::Network => {
+                state.next_peer();
+            }
+            Focus::Stats => {}
         },
 
         // Navigation - Up (k, Up arrow)
@@ -74,7 +170,10 @@ pub async fn handle_main_view(
             Focus::Results => {
                 state.prev_result();
             }
-            Focus::Stats | Focus::Network => {}
+            Focus::Network => {
+                state.prev_peer();
+            }
+            Focus::Stats => {}
         },
 
         // Jump to first (g, Home)
@@ -109,11 +208,57 @@ pub async fn handle_main_view(
             }
         }
 
-        // View result detail
-        KeyCode::Enter if key.modifiers.is_empty() => {
-            if state.focus == Focus::Results && !state.results.is_empty() {
+        // View result or peer detail
+        KeyCode::Enter if key.modifiers.is_empty() => match state.focus {
+            Focus::Results if !state.results.is_empty() => {
+                state.load_result_confirmations(db).await?;
                 state.show_result_detail = true;
             }
+            Focus::Network => {
+                if let Some(peer) = state.peers.get(state.selected_peer).cloned() {
+                    state.peer_status = None;
+                    state.load_peer_detail(db, peer).await?;
+                }
+            }
+            _ => {}
+        },
+
+        // Search monitors by name or target
+        KeyCode::Char('/') if key.modifiers.is_empty() => {
+            state.search_active = true;
+            state.focus = Focus::Monitors;
+        }
+
+        // Edit the selected monitor's tags, or set the tags of all marked ones
+        KeyCode::Char('#') => {
+            if !state.marked.is_empty() {
+                state.tag_input = Some(String::new());
+                state.validation_error = None;
+            } else if let Some(m) = state.monitors.get(state.selected) {
+                state.tag_input = Some(m.tags.join(", "));
+                state.validation_error = None;
+            }
+        }
+
+        // Cycle the monitors sort order
+        KeyCode::Char('s') if key.modifiers.is_empty() => {
+            state.monitor_sort = state.monitor_sort.next();
+            state.apply_monitor_view();
+        }
+
+        // Show only the focused pane, or the whole grid again
+        KeyCode::Char('z') if key.modifiers.is_empty() => {
+            state.zoomed = !state.zoomed;
+        }
+
+        // Cycle the layout presets
+        KeyCode::Char('v') if key.modifiers.is_empty() => {
+            state.next_layout();
+        }
+
+        // Zoom the history charts (1h → 24h → 7d)
+        KeyCode::Char('Z') => {
+            state.history_range = state.history_range.next();
         }
 
         // Toggle auto-refresh
@@ -122,14 +267,42 @@ pub async fn handle_main_view(
             state.last_refresh = std::time::Instant::now();
         }
 
+        // Mark the selected monitor for a bulk operation
+        KeyCode::Char(' ') if key.modifiers.is_empty() && state.focus == Focus::Monitors => {
+            state.toggle_mark();
+            if state.selected + 1 < state.monitors.len() {
+                state.next_monitor();
+                state.load_selected_results(db).await?;
+            }
+        }
+
+        // Mark every listed monitor, or clear the marks
+        KeyCode::Char('*') => {
+            state.toggle_mark_all();
+        }
+
+        // Pause or enable the marked monitors
+        KeyCode::Char('p') if key.modifiers.is_empty() && !state.marked.is_empty() => {
+            state.bulk_confirm = Some(BulkAction::Pause);
+        }
+        KeyCode::Char('u') if key.modifiers.is_empty() && !state.marked.is_empty() => {
+            state.bulk_confirm = Some(BulkAction::Enable);
+        }
+
         // Toggle enabled status
-        KeyCode::Char(' ') | KeyCode::Char('t') if key.modifiers.is_empty() => {
+        KeyCode::Char('t') if key.modifiers.is_empty() => {
             if state.focus == Focus::Monitors
                 && let Some(mo) = state.monitors.get(state.selected).cloned()
             {
                 let mut nm = mo;
                 nm.enabled = !nm.enabled;
                 db.save_monitor(&nm).await?;
+                let action = if nm.enabled { "Enabled" } else { "Disabled" };
+                db.append_audit_event(&AuditEvent::admin(format!(
+                    "{action} monitor '{}' ({}) via TUI",
+                    nm.name, nm.uuid
+                )))
+                .await?;
                 state.refresh_monitors_and_results(db).await?;
                 state.last_refresh = std::time::Instant::now();
             }
@@ -137,13 +310,14 @@ pub async fn handle_main_view(
 
         // Refresh data
         KeyCode::Char('r') if key.modifiers.is_empty() => {
-            state.monitors = db.get_enabled_monitors().await?;
+            state.reload_monitors(db).await?;
             if !state.monitors.is_empty() {
                 let uuid = state.monitors[state.selected].uuid;
                 state.results = db.get_recent_results(uuid, 50).await?;
             } else {
                 state.results.clear();
             }
+            state.rollups_for = None;
         }
 
         // Add monitor
@@ -169,9 +343,79 @@ pub async fn handle_main_view(
             }
         }
 
-        // Delete monitor
+        // Audit log
+        KeyCode::Char('L') => {
+            state.audit_events = db.get_audit_events(None, 100).await?;
+            state.selected_audit = 0;
+            state.show_audit = true;
+        }
+
+        // DHT debugging
+        KeyCode::Char('K') => {
+            state.dht_operations = db.get_dht_operations(20).await?;
+            state.dht_form = Some(Default::default());
+            state.validation_error = None;
+        }
+
+        // Export the focused pane's data
+        KeyCode::Char('E') => {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            state.export_path = Some(format!("uppe-{}-{now}.csv", state.export_data().name()));
+            state.validation_error = None;
+        }
+
+        // Distributed view
+        KeyCode::Char('C') => {
+            state.load_distributed(db).await?;
+            state.show_distributed = true;
+        }
+
+        // Acknowledge what the alert banner shows
+        KeyCode::Char('A') => {
+            let alerts = state.acknowledge_alerts();
+            if !alerts.is_empty() {
+                db.append_audit_event(&AuditEvent::admin(format!(
+                    "Acknowledged {} via TUI",
+                    alerts.join(", ")
+                )))
+                .await?;
+            }
+        }
+
+        // Helper assignments
+        KeyCode::Char('H') => {
+            state.load_assignments(db).await?;
+            state.show_assignments = true;
+        }
+
+        // Followed hosts
+        KeyCode::Char('F') => {
+            state.load_follows(db).await?;
+            state.show_following = true;
+        }
+
+        // Community domains
+        KeyCode::Char('M') => {
+            state.load_community(db).await?;
+            state.community_status = None;
+            state.show_community = true;
+        }
+
+        // Notification channels
+        KeyCode::Char('N') => {
+            state.refresh_notification_channels(db).await?;
+            state.notification_status = None;
+            state.show_notifications = true;
+        }
+
+        // Delete the marked monitors, or the selected one
         KeyCode::Char('d') if key.modifiers.is_empty() => {
-            if state.monitors.get(state.selected).is_some() {
+            if !state.marked.is_empty() {
+                state.bulk_confirm = Some(BulkAction::Delete);
+            } else if state.monitors.get(state.selected).is_some() {
                 state.show_delete_confirm = true;
             }
         }
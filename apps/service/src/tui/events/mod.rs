@@ -1,15 +1,22 @@
+pub mod bulk;
+pub mod community;
+pub mod dht;
 pub mod edit;
+pub mod export;
 pub mod keyboard;
 pub mod mouse;
+pub mod notifications;
+pub mod peers;
 
 use anyhow::Result;
 use crossterm::event::{Event, KeyCode, KeyEventKind};
 
-use crate::database::{Database, DatabaseImpl};
+use crate::database::Database;
+use crate::database::models::AuditEvent;
 use crate::tui::state::AppState;
 
 /// Handle all events and return true if should quit
-pub async fn handle_event(state: &mut AppState, event: Event, db: &DatabaseImpl) -> Result<bool> {
+pub async fn handle_event(state: &mut AppState, event: Event, db: &dyn Database) -> Result<bool> {
     match event {
         Event::Key(k) => {
             // Only process key press events, ignore releases and repeats
@@ -33,8 +40,13 @@ pub async fn handle_event(state: &mut AppState, event: Event, db: &DatabaseImpl)
                     KeyCode::Char('y') => {
                         if let Some(m) = state.monitors.get(state.selected) {
                             db.delete_monitor(m.uuid).await?;
+                            db.append_audit_event(&AuditEvent::admin(format!(
+                                "Deleted monitor '{}' ({}) via TUI",
+                                m.name, m.uuid
+                            )))
+                            .await?;
                             state.show_delete_confirm = false;
-                            state.monitors = db.get_enabled_monitors().await?;
+                            state.reload_monitors(db).await?;
                             if state.selected >= state.monitors.len() {
                                 state.selected = state.monitors.len().saturating_sub(1);
                             }
@@ -54,11 +66,98 @@ pub async fn handle_event(state: &mut AppState, event: Event, db: &DatabaseImpl)
                 return Ok(false);
             }
 
+            if state.bulk_confirm.is_some() {
+                bulk::handle_bulk_confirm(state, k.code, db).await?;
+                return Ok(false);
+            }
+
             if state.show_edit {
                 edit::handle_edit_popup(state, k.code, db).await?;
                 return Ok(false);
             }
 
+            if state.show_audit {
+                match k.code {
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('L') => {
+                        state.show_audit = false;
+                    }
+                    KeyCode::Char('j') | KeyCode::Down => state.next_audit_event(),
+                    KeyCode::Char('k') | KeyCode::Up => state.prev_audit_event(),
+                    _ => {}
+                }
+                return Ok(false);
+            }
+
+            if state.show_distributed {
+                match k.code {
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('C') => {
+                        state.show_distributed = false;
+                    }
+                    KeyCode::Char('j') | KeyCode::Down => state.next_domain(),
+                    KeyCode::Char('k') | KeyCode::Up => state.prev_domain(),
+                    KeyCode::Char('r') => state.load_distributed(db).await?,
+                    _ => {}
+                }
+                return Ok(false);
+            }
+
+            if state.show_assignments {
+                match k.code {
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('H') => {
+                        state.show_assignments = false;
+                    }
+                    KeyCode::Char('r') => state.load_assignments(db).await?,
+                    _ => {}
+                }
+                return Ok(false);
+            }
+
+            if state.show_following {
+                match k.code {
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('F') => {
+                        state.show_following = false;
+                    }
+                    KeyCode::Char('r') => state.load_follows(db).await?,
+                    _ => {}
+                }
+                return Ok(false);
+            }
+
+            if state.show_community {
+                community::handle_community_popup(state, k.code, db).await?;
+                return Ok(false);
+            }
+
+            if state.show_notifications {
+                notifications::handle_notifications_popup(state, k.code, db).await?;
+                return Ok(false);
+            }
+
+            if state.dht_form.is_some() {
+                dht::handle_dht_popup(state, k, db).await?;
+                return Ok(false);
+            }
+
+            if state.peer_detail.is_some() {
+                peers::handle_peer_detail_popup(state, k.code, db).await?;
+                return Ok(false);
+            }
+
+            if state.export_path.is_some() {
+                export::handle_export_input(state, k)?;
+                return Ok(false);
+            }
+
+            if state.tag_input.is_some() {
+                keyboard::handle_tag_input(state, k, db).await?;
+                return Ok(false);
+            }
+
+            if state.search_active {
+                keyboard::handle_search_input(state, k, db).await?;
+                return Ok(false);
+            }
+
             if state.show_result_detail {
                 match k.code {
                     KeyCode::Esc | KeyCode::Char('q') => {
@@ -75,15 +174,7 @@ pub async fn handle_event(state: &mut AppState, event: Event, db: &DatabaseImpl)
 
         Event::Mouse(m) => {
             // Only handle mouse events if no popup is blocking
-            if !state.show_help
-                && !state.show_edit
-                && !state.show_delete_confirm
-                && !state.show_result_detail
-            {
-                mouse::handle_mouse(state, m, db).await
-            } else {
-                Ok(false)
-            }
+            if !state.overlay_open() { mouse::handle_mouse(state, m, db).await } else { Ok(false) }
         }
 
         _ => Ok(false),
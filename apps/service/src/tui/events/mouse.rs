@@ -1,18 +1,47 @@
 use anyhow::Result;
 use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
 
+use crate::database::Database;
 use crate::database::models::Monitor;
-use crate::database::{Database, DatabaseImpl};
 use crate::tui::state::AppState;
-use crate::tui::types::Focus;
+use crate::tui::types::{Focus, FrameAreas, PaneBorder};
+use crate::tui::ui::results::HISTORY_HEIGHT;
 
 /// Handle mouse events
 pub async fn handle_mouse(
     state: &mut AppState,
     mouse: MouseEvent,
-    db: &DatabaseImpl,
+    db: &dyn Database,
 ) -> Result<bool> {
-    if let Some(areas) = &state.areas
+    // Resize panes by dragging the borders between them
+    match (mouse.kind, state.dragging, state.areas.as_ref()) {
+        (MouseEventKind::Drag(MouseButton::Left), Some(border), Some(areas)) => {
+            let grid = areas.grid;
+            let percent = match border {
+                PaneBorder::Column => {
+                    mouse.column.saturating_sub(grid.x) as u32 * 100 / grid.width.max(1) as u32
+                }
+                PaneBorder::Row => {
+                    mouse.row.saturating_sub(grid.y) as u32 * 100 / grid.height.max(1) as u32
+                }
+            };
+            state.move_border(border, percent as u16);
+            return Ok(false);
+        }
+        (MouseEventKind::Up(MouseButton::Left), Some(_), _) => {
+            state.dragging = None;
+            return Ok(false);
+        }
+        (MouseEventKind::Down(MouseButton::Left), _, Some(areas)) if !state.zoomed => {
+            if let Some(border) = border_at(areas, mouse.column, mouse.row) {
+                state.dragging = Some(border);
+                return Ok(false);
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(areas) = state.areas.clone()
         && let MouseEventKind::Down(MouseButton::Left) = mouse.kind
     {
         let x = mouse.column;
@@ -47,7 +76,7 @@ pub async fn handle_mouse(
                         }
                     }
                     "Refresh" => {
-                        state.monitors = db.get_enabled_monitors().await?;
+                        state.reload_monitors(db).await?;
                         if let Some(mo) = state.monitors.get(state.selected) {
                             state.results = db.get_recent_results(mo.uuid, 50).await?;
                         } else {
@@ -86,8 +115,11 @@ pub async fn handle_mouse(
         let rrect = areas.results;
         if is_in_rect(x, y, &rrect) {
             state.focus = Focus::Results;
-            let inner_y = y.saturating_sub(rrect.y + 2); // header row
-            if inner_y < rrect.height.saturating_sub(3) {
+            // History charts, then border and header row
+            let inner_y = y.saturating_sub(rrect.y + HISTORY_HEIGHT + 2);
+            if y >= rrect.y + HISTORY_HEIGHT + 2
+                && inner_y < rrect.height.saturating_sub(HISTORY_HEIGHT + 3)
+            {
                 let idx = inner_y as usize;
                 if idx < state.results.len() {
                     state.selected_result = idx;
@@ -99,6 +131,25 @@ pub async fn handle_mouse(
     Ok(false) // Don't quit
 }
 
+/// Pane border under the cursor; the edges of the two panes meeting there
+/// both count, as each draws its own border line
+fn border_at(areas: &FrameAreas, x: u16, y: u16) -> Option<PaneBorder> {
+    if !is_in_rect(x, y, &areas.grid) {
+        return None;
+    }
+    // The results pane starts right of the column border, the monitors pane
+    // ends above the row border
+    let column = areas.results.x;
+    let row = areas.monitors.y + areas.monitors.height;
+    if x + 1 == column || x == column {
+        Some(PaneBorder::Column)
+    } else if y + 1 == row || y == row {
+        Some(PaneBorder::Row)
+    } else {
+        None
+    }
+}
+
 fn is_in_rect(x: u16, y: u16, rect: &ratatui::layout::Rect) -> bool {
     x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height
 }
@@ -0,0 +1,139 @@
+use anyhow::Result;
+use crossterm::event::KeyCode;
+
+use crate::database::Database;
+use crate::database::models::{AuditEvent, EscalationPolicy, NotificationRoute};
+use crate::notifications;
+use crate::tui::state::AppState;
+
+/// Escalation delay a new policy starts with, and the step `+`/`-` change it by
+const ESCALATION_STEP_SECS: u64 = 5 * 60;
+
+/// Handle keyboard events in the notification channels popup
+pub async fn handle_notifications_popup(
+    state: &mut AppState,
+    key_code: KeyCode,
+    db: &dyn Database,
+) -> Result<()> {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('N') => {
+            state.show_notifications = false;
+        }
+        KeyCode::Char('j') | KeyCode::Down => state.next_channel(),
+        KeyCode::Char('k') | KeyCode::Up => state.prev_channel(),
+
+        // Test-fire the selected channel
+        KeyCode::Char('t') | KeyCode::Enter => {
+            if let Some(channel) = state.notification_channels.get(state.selected_channel).cloned()
+            {
+                state.notification_status =
+                    Some(match notifications::test_fire(db, &channel).await {
+                        Ok(()) => format!("Test notification sent to '{}'", channel.name),
+                        Err(e) => format!("Test notification to '{}' failed: {e}", channel.name),
+                    });
+                state.refresh_notification_channels(db).await?;
+            }
+        }
+
+        // Route the selected channel to the selected monitor, or stop routing it
+        KeyCode::Char(' ') | KeyCode::Char('a') => {
+            let channel = state.notification_channels.get(state.selected_channel).cloned();
+            if let (Some(channel), Some(mut route)) = (channel, state.monitor_route.clone()) {
+                let attach = !route.channels.contains(&channel.uuid);
+                if attach {
+                    route.channels.push(channel.uuid);
+                } else {
+                    route.channels.retain(|uuid| *uuid != channel.uuid);
+                }
+                let action = if attach { "Routed" } else { "Unrouted" };
+                let message = format!("{action} '{}'", channel.name);
+                save_route(state, db, route, &message).await?;
+            }
+        }
+
+        // Escalate to the selected channel, or stop escalating to it
+        KeyCode::Char('e') => {
+            let channel = state.notification_channels.get(state.selected_channel).cloned();
+            if let (Some(channel), Some(mut route)) = (channel, state.monitor_route.clone()) {
+                let policy = route
+                    .escalation
+                    .get_or_insert_with(|| inherited_escalation(state, ESCALATION_STEP_SECS));
+                let escalate = !policy.channels.contains(&channel.uuid);
+                if escalate {
+                    policy.channels.push(channel.uuid);
+                } else {
+                    policy.channels.retain(|uuid| *uuid != channel.uuid);
+                }
+                let action = if escalate { "Escalating to" } else { "Not escalating to" };
+                let message = format!("{action} '{}'", channel.name);
+                save_route(state, db, route, &message).await?;
+            }
+        }
+
+        // Escalate sooner or later; below the first step escalation is off
+        KeyCode::Char('+') | KeyCode::Char('-') => {
+            if let Some(mut route) = state.monitor_route.clone() {
+                let policy = route.escalation.get_or_insert_with(|| inherited_escalation(state, 0));
+                policy.after_secs = if key_code == KeyCode::Char('+') {
+                    policy.after_secs + ESCALATION_STEP_SECS
+                } else {
+                    policy.after_secs.saturating_sub(ESCALATION_STEP_SECS)
+                };
+                let message = match policy.after_secs {
+                    0 => "Not escalating".to_string(),
+                    secs => format!("Escalating after {} minutes", secs / 60),
+                };
+                save_route(state, db, route, &message).await?;
+            }
+        }
+
+        // Inherit everything from the monitor's tags and the default route
+        KeyCode::Char('i') => {
+            if let Some(route) = state.monitor_route.clone() {
+                let route = NotificationRoute::new(route.scope);
+                save_route(state, db, route, "Inheriting routing").await?;
+            }
+        }
+
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// The escalation policy the selected monitor inherits, to start its own from;
+/// `after_secs` when it inherits none
+fn inherited_escalation(state: &AppState, after_secs: u64) -> EscalationPolicy {
+    state
+        .routing
+        .escalation
+        .clone()
+        .unwrap_or(EscalationPolicy { after_secs, channels: Vec::new() })
+}
+
+/// Store the selected monitor's route, dropping it when it sets nothing
+async fn save_route(
+    state: &mut AppState,
+    db: &dyn Database,
+    mut route: NotificationRoute,
+    message: &str,
+) -> Result<()> {
+    let Some(monitor) = state.monitors.get(state.selected).cloned() else {
+        return Ok(());
+    };
+    route.updated_at = std::time::SystemTime::now();
+    if route.is_empty() {
+        db.delete_notification_route(&route.scope).await?;
+    } else {
+        db.save_notification_route(&route).await?;
+    }
+    db.append_audit_event(&AuditEvent::admin(format!(
+        "{message} for monitor '{}' ({}) via TUI",
+        monitor.name, monitor.uuid
+    )))
+    .await?;
+    state.notification_status = Some(format!("{message} for '{}'", monitor.name));
+    state.refresh_notification_channels(db).await?;
+
+    Ok(())
+}
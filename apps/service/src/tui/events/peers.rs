@@ -0,0 +1,76 @@
+use anyhow::Result;
+use crossterm::event::KeyCode;
+use std::time::SystemTime;
+
+use crate::database::Database;
+use crate::database::models::{AuditEvent, AuditKind, MonitorVisibility, MultiVantageResult};
+use crate::tui::state::AppState;
+
+/// Handle keyboard events in the peer detail popup
+pub async fn handle_peer_detail_popup(
+    state: &mut AppState,
+    key_code: KeyCode,
+    db: &dyn Database,
+) -> Result<()> {
+    let Some(detail) = &state.peer_detail else {
+        return Ok(());
+    };
+    let peer = detail.peer.clone();
+
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            state.peer_detail = None;
+        }
+
+        // Ban the peer, or lift its ban; the service picks bans up within 30 seconds
+        KeyCode::Char('b') => {
+            let banned_at = if peer.banned_at.is_some() { None } else { Some(SystemTime::now()) };
+            db.set_peer_banned(&peer.peer_id, banned_at).await?;
+            let event = match banned_at {
+                Some(_) => AuditEvent::new(
+                    AuditKind::PeerBanned,
+                    Some(peer.peer_id.clone()),
+                    "Banned peer via TUI",
+                ),
+                None => AuditEvent::admin(format!("Lifted ban on peer {} via TUI", peer.peer_id)),
+            };
+            db.append_audit_event(&event).await?;
+
+            state.peer_status = Some(match banned_at {
+                Some(_) => "Peer banned - its connections are refused".to_string(),
+                None => "Ban lifted".to_string(),
+            });
+            state.refresh_peers(db).await?;
+            let peer = state.peers.iter().find(|p| p.peer_id == peer.peer_id).cloned();
+            if let Some(peer) = peer {
+                state.load_peer_detail(db, peer).await?;
+            }
+        }
+
+        // Ask the peer to probe the selected monitor
+        KeyCode::Char('p') => {
+            let Some(monitor) = state.monitors.get(state.selected).cloned() else {
+                state.peer_status = Some("Select a monitor to probe first".to_string());
+                return Ok(());
+            };
+
+            state.peer_status = Some(if monitor.visibility == MonitorVisibility::Private {
+                format!("'{}' is private and cannot be probed by peers", monitor.name)
+            } else if peer.banned_at.is_some() {
+                "Lift the ban before asking this peer to probe".to_string()
+            } else {
+                let mut probe = MultiVantageResult::pending(monitor.uuid, 1, None);
+                probe.vantage_peer_id = Some(peer.peer_id.clone());
+                db.save_multi_vantage_result(&probe).await?;
+                format!(
+                    "Queued probe of '{}' - see `uppe monitor probes {}`",
+                    monitor.name, monitor.uuid
+                )
+            });
+        }
+
+        _ => {}
+    }
+
+    Ok(())
+}
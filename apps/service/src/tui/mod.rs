@@ -1,5 +1,6 @@
 mod events;
 mod state;
+pub mod theme;
 mod types;
 mod ui;
 
@@ -14,23 +15,27 @@ use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 use std::time::Duration;
 
-use crate::database::{Database, DatabaseImpl};
-use crate::pool::LibsqlPool;
+use crate::bus::{BusEvent, BusSubscriber};
+use crate::database::Database;
+use crate::database::models::RollupPeriod;
 
 use state::AppState;
+use theme::Theme;
+use ui::stats::{DAY_SECONDS, UPTIME_DAYS};
 
 /// Run TUI with P2P information
-pub async fn run_tui_with_p2p(pool: LibsqlPool, peer_id: String, p2p_enabled: bool) -> Result<()> {
-    // Prepare DB
-    let conn = pool.get().await?;
-    crate::database::initialize_database(&conn).await?;
-    drop(conn);
-    let db = DatabaseImpl::new_from_pool(pool);
-
+pub async fn run_tui_with_p2p(
+    db: &dyn Database,
+    peer_id: String,
+    p2p_enabled: bool,
+    theme: Theme,
+    mut bus: Option<BusSubscriber>,
+) -> Result<()> {
     // Load initial data
     let mut state = AppState::new();
+    state.theme = theme;
     state.set_peer_info(peer_id, p2p_enabled);
-    state.monitors = db.get_enabled_monitors().await?;
+    state.reload_monitors(db).await?;
     if !state.monitors.is_empty() {
         let uuid = state.monitors[state.selected].uuid;
         state.results = db.get_recent_results(uuid, 50).await?;
@@ -43,7 +48,10 @@ pub async fn run_tui_with_p2p(pool: LibsqlPool, peer_id: String, p2p_enabled: bo
             stats.checks_performed as usize,
             stats.checks_received as usize,
         );
+        state.update_helper_stats(&stats);
     }
+    state.owner_sync = db.get_owner_sync_state().await.ok().flatten();
+    state.refresh_peers(db).await?;
 
     // Init terminal in alternate screen
     enable_raw_mode()?;
@@ -54,19 +62,48 @@ pub async fn run_tui_with_p2p(pool: LibsqlPool, peer_id: String, p2p_enabled: bo
     terminal.clear()?;
 
     loop {
-        // Periodic auto-refresh if enabled and no overlay is active
+        // Results the service stored since the last frame; left queued while
+        // an overlay is open
+        if state.auto_refresh
+            && !state.overlay_open()
+            && let Some(bus) = bus.as_mut()
+        {
+            let stored: Vec<_> = bus
+                .drain()
+                .into_iter()
+                .filter_map(|event| match event {
+                    BusEvent::ResultStored { monitor_uuid, .. } => Some(monitor_uuid),
+                    BusEvent::Heartbeat => None,
+                })
+                .collect();
+            state.bus_live = bus.is_live();
+
+            if !stored.is_empty() {
+                state.reload_monitors(db).await?;
+                if let Some(m) = state.monitors.get(state.selected)
+                    && stored.contains(&m.uuid)
+                {
+                    state.results = db.get_recent_results(m.uuid, 50).await?;
+                    state.agreement_for = None;
+                    state.rollups_for = None;
+                    state.daily_rollups_for = None;
+                }
+            }
+        }
+
+        // Periodic auto-refresh if enabled and no overlay is active; results
+        // are only polled while the bus is quiet
         if state.auto_refresh
             && state.last_refresh.elapsed() >= Duration::from_secs(state.refresh_interval_secs)
-            && !state.show_help
-            && !state.show_edit
-            && !state.show_delete_confirm
-            && !state.show_result_detail
+            && !state.overlay_open()
         {
-            state.monitors = db.get_enabled_monitors().await?;
-            if let Some(m) = state.monitors.get(state.selected) {
-                state.results = db.get_recent_results(m.uuid, 50).await?;
-            } else {
-                state.results.clear();
+            if !state.bus_live {
+                state.reload_monitors(db).await?;
+                if let Some(m) = state.monitors.get(state.selected) {
+                    state.results = db.get_recent_results(m.uuid, 50).await?;
+                } else {
+                    state.results.clear();
+                }
             }
 
             if let Ok(Some(stats)) = db.get_latest_network_stats().await {
@@ -76,10 +113,67 @@ pub async fn run_tui_with_p2p(pool: LibsqlPool, peer_id: String, p2p_enabled: bo
                     stats.checks_performed as usize,
                     stats.checks_received as usize,
                 );
+                state.update_helper_stats(&stats);
             }
+            state.owner_sync = db.get_owner_sync_state().await.ok().flatten();
+            state.refresh_peers(db).await?;
+            state.agreement_for = None;
+            state.rollups_for = None;
+            state.daily_rollups_for = None;
+            state.last_refresh = std::time::Instant::now();
+        }
+
+        // Keep the DHT debug popup's operations current while it is open
+        if state.dht_form.is_some()
+            && state.last_refresh.elapsed() >= Duration::from_secs(state.refresh_interval_secs)
+        {
+            state.dht_operations = db.get_dht_operations(20).await?;
             state.last_refresh = std::time::Instant::now();
         }
 
+        // Load the peer agreement whenever the selected monitor changes
+        let selected_uuid = state.monitors.get(state.selected).map(|m| m.uuid);
+        if selected_uuid != state.agreement_for {
+            state.agreement = match selected_uuid {
+                Some(uuid) => db.get_result_agreements(uuid, 1).await?.pop(),
+                None => None,
+            };
+            state.agreement_for = selected_uuid;
+        }
+
+        // Load the history rollups whenever the monitor or range changes
+        let history_key = selected_uuid.map(|uuid| (uuid, state.history_range));
+        if history_key != state.rollups_for {
+            state.rollups = match history_key {
+                Some((uuid, range)) => {
+                    let start = std::time::SystemTime::now()
+                        - Duration::from_secs(range.seconds() - range.bucket_seconds());
+                    db.get_result_rollups(uuid, start, range.bucket_seconds()).await?
+                }
+                None => Vec::new(),
+            };
+            state.rollups_for = history_key;
+        }
+
+        // Load the daily rollups behind the uptime bar whenever the monitor
+        // changes; the stored ones reach past result retention, and the last
+        // of them is redone from the results, which also cover the hour the
+        // retention sweeper hasn't rolled up yet
+        if selected_uuid != state.daily_rollups_for {
+            state.daily_rollups = match selected_uuid {
+                Some(uuid) => {
+                    let start = std::time::SystemTime::now()
+                        - Duration::from_secs((UPTIME_DAYS - 1) * DAY_SECONDS);
+                    let mut rollups = db.get_rollups(uuid, RollupPeriod::Daily, start).await?;
+                    let recent_start = rollups.pop().map_or(start, |last| last.bucket_start);
+                    rollups.extend(db.get_result_rollups(uuid, recent_start, DAY_SECONDS).await?);
+                    rollups
+                }
+                None => Vec::new(),
+            };
+            state.daily_rollups_for = selected_uuid;
+        }
+
         // Render UI
         terminal.draw(|f| {
             ui::render(f, &mut state);
@@ -88,7 +182,7 @@ pub async fn run_tui_with_p2p(pool: LibsqlPool, peer_id: String, p2p_enabled: bo
         // Poll for events
         if event::poll(Duration::from_millis(250))? {
             let ev = event::read()?;
-            let should_quit = events::handle_event(&mut state, ev, &db).await?;
+            let should_quit = events::handle_event(&mut state, ev, db).await?;
 
             if should_quit {
                 break;
@@ -106,6 +200,6 @@ pub async fn run_tui_with_p2p(pool: LibsqlPool, peer_id: String, p2p_enabled: bo
 
 /// Backward compatible wrapper
 #[allow(dead_code)] // Backward compatibility API
-pub async fn run_tui(pool: LibsqlPool) -> Result<()> {
-    run_tui_with_p2p(pool, "unknown".into(), false).await
+pub async fn run_tui(db: &dyn Database) -> Result<()> {
+    run_tui_with_p2p(db, "unknown".into(), false, Theme::default(), None).await
 }
@@ -1,16 +1,71 @@
-use super::types::{Focus, FrameAreas};
-use crate::database::models::{Monitor, MonitorResult};
+use super::theme::Theme;
+use super::types::{
+    BulkAction, DhtForm, DomainConsensus, Focus, FrameAreas, HistoryRange, LayoutPreset,
+    MonitorSort, PaneBorder, PeerDetail,
+};
+use crate::database::models::{
+    AssignmentRole, AuditEvent, CommunityDomain, DhtOperation, Follow, FollowSummary,
+    HelperAssignment, Incident, Monitor, MonitorResult, MonitorVisibility, NetworkStats,
+    NotificationChannel, NotificationRoute, OwnerSyncState, Peer, PeerResult, PublicMonitorGroup,
+    ResultAgreement, ResultRollup, RouteScope, Routing,
+};
 use crate::monitoring::types::MonitorStatus;
 use crate::validation;
+use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
 /// Application state
 pub struct AppState {
+    /// Colors to draw with
+    pub theme: Theme,
+    /// Monitors matching `search_query`, in `monitor_sort` order
     pub monitors: Vec<Monitor>,
     pub selected: usize,
+    /// Every monitor, enabled or paused, in the order added
+    pub all_monitors: Vec<Monitor>,
+    /// Monitors marked for a bulk operation, by UUID
+    pub marked: HashSet<uuid::Uuid>,
+    /// Bulk operation awaiting confirmation
+    pub bulk_confirm: Option<BulkAction>,
+    /// Latest result of each monitor, by monitor UUID
+    pub latest_results: HashMap<uuid::Uuid, MonitorResult>,
+    /// Incidents that are not resolved yet
+    pub incidents: Vec<Incident>,
+    /// Down monitors and open incidents silenced from the banner, by UUID;
+    /// a monitor's entry is dropped once it recovers
+    pub acknowledged: HashSet<uuid::Uuid>,
+    pub monitor_sort: MonitorSort,
+    /// Case-insensitive filter on monitor names and targets
+    pub search_query: String,
+    /// Whether keys are typed into `search_query`
+    pub search_active: bool,
+    /// Tags being typed for the selected monitor, while the tag editor is open
+    pub tag_input: Option<String>,
     pub results: Vec<MonitorResult>,
+    /// Latest peer agreement for the selected monitor
+    pub agreement: Option<ResultAgreement>,
+    /// Monitor `agreement` was loaded for; cleared to force a reload
+    pub agreement_for: Option<uuid::Uuid>,
+    /// Range of the history charts
+    pub history_range: HistoryRange,
+    /// Result rollups over `history_range` for the selected monitor
+    pub rollups: Vec<ResultRollup>,
+    /// Monitor and range `rollups` were loaded for; cleared to force a reload
+    pub rollups_for: Option<(uuid::Uuid, HistoryRange)>,
+    /// Daily rollups for the selected monitor's uptime bar
+    pub daily_rollups: Vec<ResultRollup>,
+    /// Monitor `daily_rollups` were loaded for; cleared to force a reload
+    pub daily_rollups_for: Option<uuid::Uuid>,
     pub show_help: bool,
     pub focus: Focus,
+    pub layout_preset: LayoutPreset,
+    /// Width of the left pane column and height of the top pane row, in percent
+    pub split_x: u16,
+    pub split_y: u16,
+    /// Whether only the focused pane is shown, filling the grid
+    pub zoomed: bool,
+    /// Border the mouse is dragging
+    pub dragging: Option<PaneBorder>,
     pub selected_result: usize,
 
     // Edit & delete
@@ -18,6 +73,11 @@ pub struct AppState {
     pub edit_monitor: Option<Monitor>,
     pub show_delete_confirm: bool,
     pub show_result_detail: bool,
+    /// Peer results checked around the result shown in the detail popup,
+    /// one per peer
+    pub result_confirmations: Vec<PeerResult>,
+    pub show_audit: bool,
+    pub show_notifications: bool,
     pub areas: Option<FrameAreas>,
 
     // Editing state
@@ -33,6 +93,9 @@ pub struct AppState {
     pub auto_refresh: bool,
     pub last_refresh: Instant,
     pub refresh_interval_secs: u64,
+    /// Whether the service is heard on the bus, so results arrive as they are
+    /// stored rather than by polling
+    pub bus_live: bool,
 
     // P2P status
     pub peer_id: String,
@@ -42,6 +105,80 @@ pub struct AppState {
     pub results_shared: usize,
     pub results_received: usize,
     pub last_peer_event: Option<String>,
+    /// Every peer seen so far, online ones first
+    pub peers: Vec<Peer>,
+    pub selected_peer: usize,
+    /// The peer shown in the detail popup, while it is open
+    pub peer_detail: Option<PeerDetail>,
+    /// Outcome of the last action in the peer detail popup
+    pub peer_status: Option<String>,
+
+    // DHT debugging
+    /// The DHT debug popup's fields, while it is open
+    pub dht_form: Option<DhtForm>,
+    /// Recent DHT lookups and publishes, newest first
+    pub dht_operations: Vec<DhtOperation>,
+
+    // Distributed view
+    pub show_distributed: bool,
+    /// Public monitors with their schedule, agreement and votes
+    pub distributed: Vec<DomainConsensus>,
+    pub selected_domain: usize,
+    /// Accepted, unexpired duties we run for other owners
+    pub helper_duties: Vec<HelperAssignment>,
+
+    // Helper assignments
+    pub show_assignments: bool,
+    /// Unexpired duties other owners gave us, pending or accepted
+    pub inbound_assignments: Vec<HelperAssignment>,
+    /// Unexpired assignments of peers helping check our monitors
+    pub outbound_assignments: Vec<HelperAssignment>,
+
+    // Followed hosts
+    pub show_following: bool,
+    /// Hosts we follow without checking them, with what peers see of each
+    pub follows: Vec<(Follow, FollowSummary)>,
+
+    // Community domains
+    pub show_community: bool,
+    /// Domains peers monitor together, with our own record of each
+    pub community: Vec<(PublicMonitorGroup, Option<CommunityDomain>)>,
+    pub selected_community: usize,
+    /// Outcome of the last join or leave
+    pub community_status: Option<String>,
+
+    // Helper capacity
+    pub helper_assignments: usize,
+    pub helper_max_assignments: usize,
+    pub helper_checks_last_hour: usize,
+    pub helper_max_checks_per_hour: usize,
+    /// Share of gossip results that were duplicates, and how many arrived
+    pub seen_hit_rate: Option<f64>,
+    pub seen_lookups: usize,
+
+    // DHT backfill of our monitors' results
+    pub owner_sync: Option<OwnerSyncState>,
+
+    // Audit log
+    pub audit_events: Vec<AuditEvent>,
+    pub selected_audit: usize,
+
+    // Notification channels
+    pub notification_channels: Vec<NotificationChannel>,
+    pub selected_channel: usize,
+    /// Notification route of the selected monitor itself
+    pub monitor_route: Option<NotificationRoute>,
+    /// Where the selected monitor's alerts go, inherited routes included
+    pub routing: Routing,
+    /// Outcome of the last action in the notifications popup
+    pub notification_status: Option<String>,
+
+    // Export
+    /// Path typed into the export prompt, while it is open
+    pub export_path: Option<String>,
+
+    /// Short-lived message shown in the header, with when it was set
+    pub status_message: Option<(String, Instant)>,
 
     // Validation
     pub validation_error: Option<String>,
@@ -50,16 +187,42 @@ pub struct AppState {
 impl AppState {
     pub fn new() -> Self {
         Self {
+            theme: Theme::default(),
             monitors: Vec::new(),
             selected: 0,
+            all_monitors: Vec::new(),
+            marked: HashSet::new(),
+            bulk_confirm: None,
+            latest_results: HashMap::new(),
+            incidents: Vec::new(),
+            acknowledged: HashSet::new(),
+            monitor_sort: MonitorSort::default(),
+            search_query: String::new(),
+            search_active: false,
+            tag_input: None,
             results: Vec::new(),
+            agreement: None,
+            agreement_for: None,
+            history_range: HistoryRange::default(),
+            rollups: Vec::new(),
+            rollups_for: None,
+            daily_rollups: Vec::new(),
+            daily_rollups_for: None,
             show_help: false,
             focus: Focus::Monitors,
+            layout_preset: LayoutPreset::default(),
+            split_x: 50,
+            split_y: 50,
+            zoomed: false,
+            dragging: None,
             selected_result: 0,
             show_edit: false,
             edit_monitor: None,
             show_delete_confirm: false,
             show_result_detail: false,
+            result_confirmations: Vec::new(),
+            show_audit: false,
+            show_notifications: false,
             areas: None,
             is_add_form: false,
             edit_field_index: 0,
@@ -68,6 +231,7 @@ impl AppState {
             auto_refresh: true,
             last_refresh: Instant::now(),
             refresh_interval_secs: 5,
+            bus_live: false,
             peer_id: String::new(),
             p2p_enabled: false,
             connected_peers: 0,
@@ -75,10 +239,64 @@ impl AppState {
             results_shared: 0,
             results_received: 0,
             last_peer_event: None,
+            peers: Vec::new(),
+            selected_peer: 0,
+            peer_detail: None,
+            peer_status: None,
+            dht_form: None,
+            dht_operations: Vec::new(),
+            show_distributed: false,
+            distributed: Vec::new(),
+            selected_domain: 0,
+            helper_duties: Vec::new(),
+            show_assignments: false,
+            inbound_assignments: Vec::new(),
+            outbound_assignments: Vec::new(),
+            show_following: false,
+            follows: Vec::new(),
+            show_community: false,
+            community: Vec::new(),
+            selected_community: 0,
+            community_status: None,
+            helper_assignments: 0,
+            helper_max_assignments: 0,
+            helper_checks_last_hour: 0,
+            helper_max_checks_per_hour: 0,
+            seen_hit_rate: None,
+            seen_lookups: 0,
+            owner_sync: None,
+            audit_events: Vec::new(),
+            selected_audit: 0,
+            notification_channels: Vec::new(),
+            selected_channel: 0,
+            monitor_route: None,
+            routing: Routing::default(),
+            notification_status: None,
+            export_path: None,
+            status_message: None,
             validation_error: None,
         }
     }
 
+    /// Whether a popup or prompt covers the main view
+    pub fn overlay_open(&self) -> bool {
+        self.show_help
+            || self.show_edit
+            || self.show_delete_confirm
+            || self.bulk_confirm.is_some()
+            || self.show_result_detail
+            || self.show_audit
+            || self.show_notifications
+            || self.show_distributed
+            || self.show_assignments
+            || self.show_following
+            || self.show_community
+            || self.peer_detail.is_some()
+            || self.dht_form.is_some()
+            || self.tag_input.is_some()
+            || self.export_path.is_some()
+    }
+
     pub fn update_peer_stats(
         &mut self,
         connected: usize,
@@ -92,6 +310,15 @@ impl AppState {
         self.results_received = received;
     }
 
+    pub fn update_helper_stats(&mut self, stats: &NetworkStats) {
+        self.helper_assignments = stats.helper_assignments as usize;
+        self.helper_max_assignments = stats.helper_max_assignments as usize;
+        self.helper_checks_last_hour = stats.helper_checks_last_hour as usize;
+        self.helper_max_checks_per_hour = stats.helper_max_checks_per_hour as usize;
+        self.seen_hit_rate = stats.seen_hit_rate();
+        self.seen_lookups = stats.seen_lookups as usize;
+    }
+
     #[allow(dead_code)] // TUI API
     pub fn record_peer_event(&mut self, event: String) {
         self.last_peer_event = Some(event);
@@ -202,6 +429,252 @@ impl AppState {
         }
     }
 
+    /// Navigate to next audit event (without wrapping)
+    pub fn next_audit_event(&mut self) {
+        if self.selected_audit + 1 < self.audit_events.len() {
+            self.selected_audit += 1;
+        }
+    }
+
+    /// Navigate to previous audit event (without wrapping)
+    pub fn prev_audit_event(&mut self) {
+        self.selected_audit = self.selected_audit.saturating_sub(1);
+    }
+
+    /// Navigate to next notification channel (without wrapping)
+    pub fn next_channel(&mut self) {
+        if self.selected_channel + 1 < self.notification_channels.len() {
+            self.selected_channel += 1;
+        }
+    }
+
+    /// Navigate to previous notification channel (without wrapping)
+    pub fn prev_channel(&mut self) {
+        self.selected_channel = self.selected_channel.saturating_sub(1);
+    }
+
+    /// Reload notification channels and the routing of the selected monitor
+    pub async fn refresh_notification_channels(
+        &mut self,
+        db: &dyn crate::database::Database,
+    ) -> anyhow::Result<()> {
+        self.notification_channels = db.get_notification_channels().await?;
+        let routes = db.get_notification_routes().await?;
+        (self.monitor_route, self.routing) = match self.monitors.get(self.selected) {
+            Some(monitor) => {
+                let scope = RouteScope::Monitor(monitor.uuid);
+                let route = routes.iter().find(|route| route.scope == scope).cloned();
+                let routing = NotificationRoute::resolve(monitor, &routes);
+                (Some(route.unwrap_or_else(|| NotificationRoute::new(scope))), routing)
+            }
+            None => (None, Routing::default()),
+        };
+        if self.selected_channel >= self.notification_channels.len() {
+            self.selected_channel = self.notification_channels.len().saturating_sub(1);
+        }
+        Ok(())
+    }
+
+    /// Navigate to next peer (without wrapping)
+    pub fn next_peer(&mut self) {
+        if self.selected_peer + 1 < self.peers.len() {
+            self.selected_peer += 1;
+        }
+    }
+
+    /// Navigate to previous peer (without wrapping)
+    pub fn prev_peer(&mut self) {
+        self.selected_peer = self.selected_peer.saturating_sub(1);
+    }
+
+    /// Reload the known peers, keeping the selection in range
+    pub async fn refresh_peers(
+        &mut self,
+        db: &dyn crate::database::Database,
+    ) -> anyhow::Result<()> {
+        self.peers = db.get_peers().await?;
+        if self.selected_peer >= self.peers.len() {
+            self.selected_peer = self.peers.len().saturating_sub(1);
+        }
+        Ok(())
+    }
+
+    /// Load the detail popup for a peer, with the results it delivered
+    pub async fn load_peer_detail(
+        &mut self,
+        db: &dyn crate::database::Database,
+        peer: Peer,
+    ) -> anyhow::Result<()> {
+        let results = db.get_results_from_peer(&peer.peer_id, 20).await?;
+        let (results_total, results_verified) = db.count_results_from_peer(&peer.peer_id).await?;
+        self.peer_detail = Some(PeerDetail { peer, results, results_total, results_verified });
+        Ok(())
+    }
+
+    /// Load the peer results confirming the selected result: each peer's check
+    /// closest to it, within one check interval on either side
+    pub async fn load_result_confirmations(
+        &mut self,
+        db: &dyn crate::database::Database,
+    ) -> anyhow::Result<()> {
+        self.result_confirmations.clear();
+        let Some(result) = self.results.get(self.selected_result) else {
+            return Ok(());
+        };
+        let interval = self
+            .all_monitors
+            .iter()
+            .find(|monitor| monitor.uuid == result.monitor_uuid)
+            .map_or(60, |monitor| monitor.interval_seconds);
+        let window = std::time::Duration::from_secs(interval.max(1));
+        let at = result.timestamp;
+
+        let distance = |peer_result: &PeerResult| match peer_result.timestamp.duration_since(at) {
+            Ok(after) => after,
+            Err(before) => before.duration(),
+        };
+        let mut closest: Vec<PeerResult> = Vec::new();
+        for peer_result in db
+            .get_peer_results_between(result.monitor_uuid, at - window, at + window)
+            .await?
+        {
+            match closest.iter_mut().find(|kept| kept.peer_id == peer_result.peer_id) {
+                Some(kept) if distance(&peer_result) < distance(kept) => *kept = peer_result,
+                Some(_) => {}
+                None => closest.push(peer_result),
+            }
+        }
+
+        self.result_confirmations = closest;
+        Ok(())
+    }
+
+    /// Data of the focused pane, as the export writes it
+    pub fn export_data(&self) -> crate::export::ExportData<'_> {
+        use crate::export::ExportData;
+        match self.focus {
+            Focus::Monitors => ExportData::Monitors(&self.monitors),
+            Focus::Results | Focus::Stats => ExportData::Results(&self.results),
+            Focus::Network => ExportData::Peers(&self.peers),
+        }
+    }
+
+    /// Navigate to next public monitor in the distributed view (without wrapping)
+    pub fn next_domain(&mut self) {
+        if self.selected_domain + 1 < self.distributed.len() {
+            self.selected_domain += 1;
+        }
+    }
+
+    /// Navigate to previous public monitor in the distributed view (without wrapping)
+    pub fn prev_domain(&mut self) {
+        self.selected_domain = self.selected_domain.saturating_sub(1);
+    }
+
+    /// Load the distributed view: every public monitor's schedule, latest
+    /// agreement and peer votes, plus the duties we run for other owners
+    pub async fn load_distributed(
+        &mut self,
+        db: &dyn crate::database::Database,
+    ) -> anyhow::Result<()> {
+        let now = std::time::SystemTime::now();
+        let assignments = db.get_helper_assignments(AssignmentRole::Owner).await?;
+
+        let mut distributed = Vec::new();
+        for monitor in &self.all_monitors {
+            if monitor.visibility != MonitorVisibility::Public {
+                continue;
+            }
+
+            let schedule = assignments
+                .iter()
+                .filter(|a| a.monitor_uuid == monitor.uuid && !a.is_expired(now))
+                .cloned()
+                .collect();
+            let agreement = db.get_result_agreements(monitor.uuid, 1).await?.into_iter().next();
+
+            // Results come newest first, so the first one per peer is its vote
+            let mut votes: Vec<PeerResult> = Vec::new();
+            for result in db.get_peer_results(monitor.uuid, 100).await? {
+                if !votes.iter().any(|vote| vote.peer_id == result.peer_id) {
+                    votes.push(result);
+                }
+            }
+
+            distributed.push(DomainConsensus {
+                monitor: monitor.clone(),
+                schedule,
+                agreement,
+                votes,
+            });
+        }
+
+        self.distributed = distributed;
+        if self.selected_domain >= self.distributed.len() {
+            self.selected_domain = self.distributed.len().saturating_sub(1);
+        }
+        self.helper_duties = db
+            .get_helper_assignments(AssignmentRole::Helper)
+            .await?
+            .into_iter()
+            .filter(|duty| duty.is_accepted() && !duty.is_expired(now))
+            .collect();
+        Ok(())
+    }
+
+    /// Load the helper assignments in both directions
+    pub async fn load_assignments(
+        &mut self,
+        db: &dyn crate::database::Database,
+    ) -> anyhow::Result<()> {
+        let now = std::time::SystemTime::now();
+        self.inbound_assignments = db
+            .get_helper_assignments(AssignmentRole::Helper)
+            .await?
+            .into_iter()
+            .filter(|a| !a.is_expired(now))
+            .collect();
+        self.outbound_assignments = db
+            .get_helper_assignments(AssignmentRole::Owner)
+            .await?
+            .into_iter()
+            .filter(|a| !a.is_expired(now))
+            .collect();
+        Ok(())
+    }
+
+    /// Load the followed hosts and summarise recent peer results for each
+    pub async fn load_follows(&mut self, db: &dyn crate::database::Database) -> anyhow::Result<()> {
+        let since = std::time::SystemTime::now() - FollowSummary::WINDOW;
+        let mut follows = Vec::new();
+        for follow in db.get_follows().await? {
+            let mut results = Vec::new();
+            for monitor_uuid in db.get_follow_monitors(&follow.host).await? {
+                results.extend(
+                    db.get_peer_results(monitor_uuid, FollowSummary::RESULTS_PER_MONITOR).await?,
+                );
+            }
+            follows.push((follow, FollowSummary::from_results(&results, since)));
+        }
+        self.follows = follows;
+        Ok(())
+    }
+
+    /// Load the community domains and who takes part in each
+    pub async fn load_community(
+        &mut self,
+        db: &dyn crate::database::Database,
+    ) -> anyhow::Result<()> {
+        self.community = PublicMonitorGroup::listing(
+            &db.get_group_memberships().await?,
+            &db.get_community_domains().await?,
+            std::time::SystemTime::now(),
+        );
+        self.selected_community =
+            self.selected_community.min(self.community.len().saturating_sub(1));
+        Ok(())
+    }
+
     /// Jump to first monitor
     pub fn first_monitor(&mut self) {
         if !self.monitors.is_empty() {
@@ -230,7 +703,8 @@ impl AppState {
         }
     }
 
-    /// Calculate stats for current monitor
+    /// Calculate stats for current monitor; uptime leaves out results checked
+    /// during maintenance
     pub fn get_current_monitor_stats(&self) -> (f64, u64, u64, u64) {
         if self.monitors.is_empty() || self.selected >= self.monitors.len() {
             return (0.0, 0, 0, 0);
@@ -240,9 +714,9 @@ impl AppState {
             return (0.0, 0, 0, 0);
         }
 
-        let total = self.results.len() as u64;
-        let successful =
-            self.results.iter().filter(|r| r.status == MonitorStatus::Up).count() as u64;
+        let counted = || self.results.iter().filter(|r| !r.maintenance);
+        let total = counted().count() as u64;
+        let successful = counted().filter(|r| r.status == MonitorStatus::Up).count() as u64;
         let uptime = if total > 0 { (successful as f64 / total as f64) * 100.0 } else { 0.0 };
         let (latency_sum, latency_count) = self
             .results
@@ -267,13 +741,200 @@ impl AppState {
         (total_monitors, online, avg_uptime)
     }
 
+    /// Reload monitors, their latest results and open incidents, keeping the
+    /// selected monitor selected if it is still listed
+    pub async fn reload_monitors(
+        &mut self,
+        db: &dyn crate::database::Database,
+    ) -> anyhow::Result<()> {
+        self.all_monitors = db.get_monitors().await?;
+        let all = &self.all_monitors;
+        self.marked.retain(|uuid| all.iter().any(|m| m.uuid == *uuid));
+        self.latest_results = db
+            .get_latest_results()
+            .await?
+            .into_iter()
+            .map(|result| (result.monitor_uuid, result))
+            .collect();
+        self.incidents = db.get_open_incidents().await?;
+
+        // Forget acknowledgements of recovered monitors and closed incidents,
+        // so the next outage shows again
+        let down: HashSet<uuid::Uuid> = self.down_monitors().iter().map(|m| m.uuid).collect();
+        let incidents = &self.incidents;
+        self.acknowledged
+            .retain(|uuid| down.contains(uuid) || incidents.iter().any(|i| i.uuid == *uuid));
+        self.apply_monitor_view();
+        Ok(())
+    }
+
+    /// Rebuild `monitors` from `all_monitors` with the current search and
+    /// sort, keeping the selected monitor selected if it is still listed
+    pub fn apply_monitor_view(&mut self) {
+        let selected_uuid = self.monitors.get(self.selected).map(|m| m.uuid);
+        let query = self.search_query.to_lowercase();
+
+        // "#tag" matches tags starting with "tag" only
+        let mut monitors: Vec<Monitor> = self
+            .all_monitors
+            .iter()
+            .filter(|m| match query.strip_prefix('#') {
+                Some(tag) => m.tags.iter().any(|t| t.starts_with(tag)),
+                None => {
+                    query.is_empty()
+                        || m.name.to_lowercase().contains(&query)
+                        || m.target.to_lowercase().contains(&query)
+                        || m.tags.iter().any(|t| t.contains(&query))
+                }
+            })
+            .cloned()
+            .collect();
+
+        let latest = &self.latest_results;
+        match self.monitor_sort {
+            MonitorSort::Added => {}
+            MonitorSort::Name => monitors.sort_by_key(|m| m.name.to_lowercase()),
+            MonitorSort::Status => monitors.sort_by_key(|m| {
+                let rank = match latest.get(&m.uuid).map(|r| r.status) {
+                    Some(MonitorStatus::Down) => 0,
+                    Some(MonitorStatus::Degraded) => 1,
+                    Some(MonitorStatus::Unknown) | None => 2,
+                    Some(MonitorStatus::Up) => 3,
+                };
+                (rank, m.name.to_lowercase())
+            }),
+            MonitorSort::Latency => monitors.sort_by_key(|m| {
+                let latency = latest.get(&m.uuid).and_then(|r| r.latency_ms);
+                (std::cmp::Reverse(latency), m.name.to_lowercase())
+            }),
+            MonitorSort::LastCheck => {
+                monitors.sort_by_key(|m| (latest.get(&m.uuid).map(|r| r.timestamp), m.uuid))
+            }
+        }
+
+        self.selected = selected_uuid
+            .and_then(|uuid| monitors.iter().position(|m| m.uuid == uuid))
+            .unwrap_or_else(|| self.selected.min(monitors.len().saturating_sub(1)));
+        self.monitors = monitors;
+    }
+
+    /// Switch to the next layout preset
+    pub fn next_layout(&mut self) {
+        self.layout_preset = self.layout_preset.next();
+        if let Some((x, y)) = self.layout_preset.splits() {
+            self.split_x = x;
+            self.split_y = y;
+        }
+    }
+
+    /// Move a pane border to `percent` of the grid, keeping every pane usable
+    pub fn move_border(&mut self, border: PaneBorder, percent: u16) {
+        let percent = percent.clamp(LayoutPreset::MIN_SPLIT, 100 - LayoutPreset::MIN_SPLIT);
+        match border {
+            PaneBorder::Column => self.split_x = percent,
+            PaneBorder::Row => self.split_y = percent,
+        }
+        self.layout_preset = LayoutPreset::Custom;
+    }
+
+    /// Enabled monitors whose latest result is down outside maintenance
+    pub fn down_monitors(&self) -> Vec<&Monitor> {
+        self.all_monitors
+            .iter()
+            .filter(|m| {
+                m.enabled
+                    && self
+                        .latest_results
+                        .get(&m.uuid)
+                        .is_some_and(|r| r.status == MonitorStatus::Down && !r.maintenance)
+            })
+            .collect()
+    }
+
+    /// Silence every down monitor and open incident in the banner, returning
+    /// what was newly acknowledged
+    pub fn acknowledge_alerts(&mut self) -> Vec<String> {
+        let alerts: Vec<(uuid::Uuid, String)> = self
+            .down_monitors()
+            .iter()
+            .map(|m| (m.uuid, format!("monitor '{}'", m.name)))
+            .chain(self.incidents.iter().map(|i| (i.uuid, format!("incident '{}'", i.title))))
+            .collect();
+        alerts
+            .into_iter()
+            .filter(|(uuid, _)| self.acknowledged.insert(*uuid))
+            .map(|(_, alert)| alert)
+            .collect()
+    }
+
+    /// Mark the selected monitor for a bulk operation, or unmark it
+    pub fn toggle_mark(&mut self) {
+        if let Some(m) = self.monitors.get(self.selected)
+            && !self.marked.remove(&m.uuid)
+        {
+            self.marked.insert(m.uuid);
+        }
+    }
+
+    /// Mark every listed monitor, or clear the marks if all are marked
+    pub fn toggle_mark_all(&mut self) {
+        if self.monitors.iter().all(|m| self.marked.contains(&m.uuid)) {
+            self.marked.clear();
+        } else {
+            self.marked.extend(self.monitors.iter().map(|m| m.uuid));
+        }
+    }
+
+    /// Marked monitors, in the order added
+    pub fn marked_monitors(&self) -> Vec<&Monitor> {
+        self.all_monitors.iter().filter(|m| self.marked.contains(&m.uuid)).collect()
+    }
+
+    /// Per tag, how many monitors are up (or degraded) out of how many carry
+    /// the tag, by tag name
+    pub fn tag_groups(&self) -> Vec<(String, usize, usize)> {
+        let mut groups: std::collections::BTreeMap<&str, (usize, usize)> = Default::default();
+        for monitor in &self.all_monitors {
+            let up = matches!(
+                self.latest_results.get(&monitor.uuid).map(|r| r.status),
+                Some(MonitorStatus::Up | MonitorStatus::Degraded)
+            );
+            for tag in &monitor.tags {
+                let group = groups.entry(tag).or_default();
+                group.0 += usize::from(up);
+                group.1 += 1;
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(tag, (up, total))| (tag.to_string(), up, total))
+            .collect()
+    }
+
+    /// Load the recent results of the selected monitor, if it changed
+    pub async fn load_selected_results(
+        &mut self,
+        db: &dyn crate::database::Database,
+    ) -> anyhow::Result<()> {
+        match self.monitors.get(self.selected) {
+            Some(m) if self.results.first().is_none_or(|r| r.monitor_uuid != m.uuid) => {
+                self.results = db.get_recent_results(m.uuid, 50).await?;
+                self.selected_result = 0;
+            }
+            Some(_) => {}
+            None => self.results.clear(),
+        }
+        Ok(())
+    }
+
     /// Refresh monitors list and update results for the currently selected monitor.
     /// This helper method eliminates duplicate code across event handlers.
     pub async fn refresh_monitors_and_results(
         &mut self,
-        db: &impl crate::database::Database,
+        db: &dyn crate::database::Database,
     ) -> anyhow::Result<()> {
-        self.monitors = db.get_enabled_monitors().await?;
+        self.reload_monitors(db).await?;
         if let Some(m) = self.monitors.get(self.selected) {
             self.results = db.get_recent_results(m.uuid, 50).await?;
         } else {
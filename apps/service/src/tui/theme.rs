@@ -0,0 +1,135 @@
+use anyhow::{Result, anyhow};
+use ratatui::style::Color;
+
+use crate::config::{ThemeColors, ThemeName, TuiConfig};
+
+/// Colors the TUI draws with, by what they mean
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Titles, labels and table headers
+    pub accent: Color,
+    /// Regular text where a color is set explicitly
+    pub text: Color,
+    /// Field labels and secondary text
+    pub label: Color,
+    /// Hints and missing values
+    pub muted: Color,
+    pub success: Color,
+    /// Mostly good, e.g. a day with a little downtime
+    pub success_dim: Color,
+    /// Pending, degraded and input being typed
+    pub warning: Color,
+    /// Mostly bad, e.g. a day with a lot of downtime
+    pub error_dim: Color,
+    pub error: Color,
+    /// Monitor tags
+    pub tag: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// The original palette, for dark terminals
+    pub fn dark() -> Self {
+        Self {
+            accent: Color::Cyan,
+            text: Color::White,
+            label: Color::Gray,
+            muted: Color::DarkGray,
+            success: Color::Green,
+            success_dim: Color::LightGreen,
+            warning: Color::Yellow,
+            error_dim: Color::LightRed,
+            error: Color::Red,
+            tag: Color::Magenta,
+        }
+    }
+
+    /// Darker shades that stay readable on light backgrounds
+    pub fn light() -> Self {
+        Self {
+            accent: Color::Blue,
+            text: Color::Black,
+            label: Color::DarkGray,
+            muted: Color::Gray,
+            success: Color::Rgb(0, 120, 0),
+            success_dim: Color::Rgb(60, 150, 60),
+            warning: Color::Rgb(170, 100, 0),
+            error_dim: Color::Rgb(200, 70, 70),
+            error: Color::Rgb(170, 0, 0),
+            tag: Color::Magenta,
+        }
+    }
+
+    /// Bright, fully saturated colors
+    pub fn high_contrast() -> Self {
+        Self {
+            accent: Color::LightCyan,
+            text: Color::White,
+            label: Color::White,
+            muted: Color::Gray,
+            success: Color::LightGreen,
+            success_dim: Color::LightGreen,
+            warning: Color::LightYellow,
+            error_dim: Color::LightRed,
+            error: Color::LightRed,
+            tag: Color::LightMagenta,
+        }
+    }
+
+    /// Base palette named in the config with its hex overrides applied
+    pub fn from_config(config: &TuiConfig) -> Result<Self> {
+        let mut theme = match config.theme {
+            ThemeName::Dark => Self::dark(),
+            ThemeName::Light => Self::light(),
+            ThemeName::HighContrast => Self::high_contrast(),
+        };
+
+        let ThemeColors {
+            accent,
+            text,
+            label,
+            muted,
+            success,
+            success_dim,
+            warning,
+            error_dim,
+            error,
+            tag,
+        } = &config.colors;
+        for (hex, color) in [
+            (accent, &mut theme.accent),
+            (text, &mut theme.text),
+            (label, &mut theme.label),
+            (muted, &mut theme.muted),
+            (success, &mut theme.success),
+            (success_dim, &mut theme.success_dim),
+            (warning, &mut theme.warning),
+            (error_dim, &mut theme.error_dim),
+            (error, &mut theme.error),
+            (tag, &mut theme.tag),
+        ] {
+            if let Some(hex) = hex {
+                *color = parse_hex(hex)?;
+            }
+        }
+
+        Ok(theme)
+    }
+}
+
+/// Parse a "#rrggbb" color
+fn parse_hex(hex: &str) -> Result<Color> {
+    let digits = hex.trim().trim_start_matches('#');
+    let invalid = || anyhow!("Invalid theme color '{hex}', expected #rrggbb");
+    if digits.len() != 6 || !digits.is_ascii() {
+        return Err(invalid());
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| invalid());
+    Ok(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
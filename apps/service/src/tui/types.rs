@@ -1,9 +1,15 @@
 use ratatui::layout::Rect;
+use std::time::UNIX_EPOCH;
+
+use crate::database::models::{HelperAssignment, Monitor, Peer, PeerResult, ResultAgreement};
 
 /// Frame areas for mouse hit-testing
+#[derive(Clone)]
 pub struct FrameAreas {
     #[allow(dead_code)] // May be used for future header interactions
     pub header: Rect,
+    /// Area of the pane grid
+    pub grid: Rect,
     pub monitors: Rect,
     pub results: Rect,
     #[allow(dead_code)] // May be used for future footer interactions
@@ -19,3 +25,247 @@ pub enum Focus {
     Stats,
     Network,
 }
+
+/// Time range shown by the history charts in the results pane
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HistoryRange {
+    #[default]
+    Hour,
+    Day,
+    Week,
+}
+
+impl HistoryRange {
+    /// Buckets each range is rolled up into
+    pub const BUCKETS: u64 = 60;
+
+    pub fn seconds(self) -> u64 {
+        match self {
+            HistoryRange::Hour => 60 * 60,
+            HistoryRange::Day => 24 * 60 * 60,
+            HistoryRange::Week => 7 * 24 * 60 * 60,
+        }
+    }
+
+    pub fn bucket_seconds(self) -> u64 {
+        self.seconds() / Self::BUCKETS
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HistoryRange::Hour => "1h",
+            HistoryRange::Day => "24h",
+            HistoryRange::Week => "7d",
+        }
+    }
+
+    /// Next range when zooming out, wrapping back to the shortest
+    pub fn next(self) -> Self {
+        match self {
+            HistoryRange::Hour => HistoryRange::Day,
+            HistoryRange::Day => HistoryRange::Week,
+            HistoryRange::Week => HistoryRange::Hour,
+        }
+    }
+}
+
+/// Order of the monitors list
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MonitorSort {
+    /// Order the monitors were added in
+    #[default]
+    Added,
+    Name,
+    /// Down first, then degraded, unknown and up
+    Status,
+    /// Slowest first
+    Latency,
+    /// Least recently checked first, so stale monitors stand out
+    LastCheck,
+}
+
+impl MonitorSort {
+    pub fn label(self) -> &'static str {
+        match self {
+            MonitorSort::Added => "added",
+            MonitorSort::Name => "name",
+            MonitorSort::Status => "status",
+            MonitorSort::Latency => "latency",
+            MonitorSort::LastCheck => "last check",
+        }
+    }
+
+    /// Next sort order, wrapping back to the order added
+    pub fn next(self) -> Self {
+        match self {
+            MonitorSort::Added => MonitorSort::Name,
+            MonitorSort::Name => MonitorSort::Status,
+            MonitorSort::Status => MonitorSort::Latency,
+            MonitorSort::Latency => MonitorSort::LastCheck,
+            MonitorSort::LastCheck => MonitorSort::Added,
+        }
+    }
+}
+
+/// Arrangement of the dashboard's 2x2 pane grid
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LayoutPreset {
+    #[default]
+    Grid,
+    /// Large monitors pane
+    Monitors,
+    /// Large network pane
+    Network,
+    /// Borders dragged with the mouse
+    Custom,
+}
+
+impl LayoutPreset {
+    /// Narrowest share of the grid, in percent, a pane row or column keeps
+    pub const MIN_SPLIT: u16 = 20;
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LayoutPreset::Grid => "grid",
+            LayoutPreset::Monitors => "monitors",
+            LayoutPreset::Network => "network",
+            LayoutPreset::Custom => "custom",
+        }
+    }
+
+    /// Next preset, wrapping back to the even grid; custom moves to the grid
+    pub fn next(self) -> Self {
+        match self {
+            LayoutPreset::Grid => LayoutPreset::Monitors,
+            LayoutPreset::Monitors => LayoutPreset::Network,
+            LayoutPreset::Network | LayoutPreset::Custom => LayoutPreset::Grid,
+        }
+    }
+
+    /// Width of the left column and height of the top row, in percent
+    pub fn splits(self) -> Option<(u16, u16)> {
+        match self {
+            LayoutPreset::Grid => Some((50, 50)),
+            LayoutPreset::Monitors => Some((65, 70)),
+            LayoutPreset::Network => Some((35, 30)),
+            LayoutPreset::Custom => None,
+        }
+    }
+}
+
+/// Pane border being dragged with the mouse
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaneBorder {
+    /// Between the left and right columns
+    Column,
+    /// Between the top and bottom rows
+    Row,
+}
+
+/// Operation applied to every marked monitor at once, once confirmed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BulkAction {
+    Pause,
+    Enable,
+    Delete,
+    /// Replace the tags of each monitor
+    Tag(Vec<String>),
+}
+
+impl BulkAction {
+    /// Verb shown in the confirmation, e.g. "Pause 3 monitors"
+    pub fn verb(&self) -> &'static str {
+        match self {
+            BulkAction::Pause => "Pause",
+            BulkAction::Enable => "Enable",
+            BulkAction::Delete => "Delete",
+            BulkAction::Tag(_) => "Re-tag",
+        }
+    }
+}
+
+/// Trust points a peer loses each time it is greylisted for flooding
+const GREYLIST_PENALTY: f64 = 10.0;
+
+/// What the peer detail popup shows about one peer
+pub struct PeerDetail {
+    pub peer: Peer,
+    /// Most recent results the peer delivered, newest first
+    pub results: Vec<PeerResult>,
+    /// Results the peer delivered overall, and how many of them were verified
+    pub results_total: u64,
+    pub results_verified: u64,
+}
+
+impl PeerDetail {
+    /// Share of the peer's results with a valid signature, in percent, less
+    /// `GREYLIST_PENALTY` points for every time it was greylisted for flooding
+    pub fn trust_score(&self) -> Option<f64> {
+        (self.results_total > 0).then(|| {
+            let verified = self.results_verified as f64 * 100.0 / self.results_total as f64;
+            (verified - self.peer.greylist_count as f64 * GREYLIST_PENALTY).max(0.0)
+        })
+    }
+
+    /// Median seconds between when the peer says it checked and when its
+    /// result arrived; this includes delivery time, so only a large or negative
+    /// value points at a skewed clock
+    pub fn clock_skew_secs(&self) -> Option<i64> {
+        let secs = |time: std::time::SystemTime| match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            Err(before) => -(before.duration().as_secs() as i64),
+        };
+        let mut skews: Vec<i64> = self
+            .results
+            .iter()
+            .map(|result| secs(result.created_at) - secs(result.timestamp))
+            .collect();
+        skews.sort_unstable();
+
+        skews.get(skews.len() / 2).copied()
+    }
+}
+
+/// What the distributed view shows about one public monitor
+pub struct DomainConsensus {
+    pub monitor: Monitor,
+    /// Peers we assigned to check the monitor; accepted ones make up the
+    /// agreed schedule, the rest still wait for the helper to confirm
+    pub schedule: Vec<HelperAssignment>,
+    /// Latest agreement between our results and those of verified peers
+    pub agreement: Option<ResultAgreement>,
+    /// Latest signed result of each peer, newest first
+    pub votes: Vec<PeerResult>,
+}
+
+impl DomainConsensus {
+    /// Peers taking part, scheduled helpers first
+    pub fn participants(&self) -> Vec<&str> {
+        let mut peers: Vec<&str> = Vec::new();
+        let scheduled = self.schedule.iter().map(|a| a.helper_peer_id.as_str());
+        for peer_id in scheduled.chain(self.votes.iter().map(|v| v.peer_id.as_str())) {
+            if !peers.contains(&peer_id) {
+                peers.push(peer_id);
+            }
+        }
+        peers
+    }
+
+    /// Whether a vote arrived after the agreement was last computed
+    pub fn is_pending(&self, vote: &PeerResult) -> bool {
+        self.agreement
+            .as_ref()
+            .is_none_or(|agreement| vote.created_at > agreement.updated_at)
+    }
+}
+
+/// Fields of the DHT debug popup
+#[derive(Default)]
+pub struct DhtForm {
+    pub key: String,
+    pub value: String,
+    /// Whether keys are typed into `value` rather than `key`
+    pub editing_value: bool,
+    /// Whether a publish is waiting for confirmation
+    pub confirm_put: bool,
+}
@@ -0,0 +1,55 @@
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+
+use crate::tui::state::AppState;
+
+/// Whether the banner has anything to show
+pub fn is_visible(state: &AppState) -> bool {
+    !state.incidents.is_empty() || !state.down_monitors().is_empty()
+}
+
+pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let down = state.down_monitors();
+
+    let mut alerts: Vec<String> = down
+        .iter()
+        .filter(|m| !state.acknowledged.contains(&m.uuid))
+        .map(|m| format!("{} is DOWN", m.name))
+        .collect();
+    alerts.extend(
+        state
+            .incidents
+            .iter()
+            .filter(|i| !state.acknowledged.contains(&i.uuid))
+            .map(|i| format!("[{}] {}", i.severity, i.title)),
+    );
+    let silenced = down.len() + state.incidents.len() - alerts.len();
+
+    let line = if alerts.is_empty() {
+        Line::from(Span::styled(
+            format!("✓ {silenced} acknowledged alert(s)"),
+            Style::default().fg(theme.muted),
+        ))
+    } else {
+        let mut spans = vec![Span::styled(
+            format!(" ⚠ {} ", alerts.join("  •  ")),
+            Style::default()
+                .fg(theme.error)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        )];
+        if silenced > 0 {
+            spans.push(Span::styled(
+                format!("  +{silenced} acknowledged"),
+                Style::default().fg(theme.muted),
+            ));
+        }
+        spans.push(Span::styled("  Shift-A: Acknowledge", Style::default().fg(theme.muted)));
+        Line::from(spans)
+    };
+
+    f.render_widget(Paragraph::new(line), area);
+}
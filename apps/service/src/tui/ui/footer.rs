@@ -1,10 +1,11 @@
+use crate::tui::theme::Theme;
 use ratatui::Frame;
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Clear, Paragraph};
 
-pub fn render(f: &mut Frame, area: Rect, show_help: bool) -> Vec<(String, Rect)> {
+pub fn render(f: &mut Frame, area: Rect, show_help: bool, theme: &Theme) -> Vec<(String, Rect)> {
     let mut action_buttons: Vec<(String, Rect)> = Vec::new();
 
     if !show_help {
@@ -30,7 +31,7 @@ pub fn render(f: &mut Frame, area: Rect, show_help: bool) -> Vec<(String, Rect)>
             let text = format!("{key}: {label}");
             let btn = Paragraph::new(Line::from(Span::styled(
                 text,
-                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
             )))
             .alignment(Alignment::Center);
 
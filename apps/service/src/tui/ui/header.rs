@@ -1,12 +1,16 @@
 use ratatui::Frame;
 use ratatui::layout::Rect;
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Clear, Paragraph};
 
 use crate::tui::state::AppState;
 
+/// How long a status message stays in the header
+const STATUS_MESSAGE_SECS: u64 = 10;
+
 pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
+    let theme = &state.theme;
     let p2p_status = if state.p2p_enabled {
         format!("P2P: Connected ({})", &state.peer_id[..state.peer_id.len().min(8)])
     } else {
@@ -14,23 +18,35 @@ pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
     };
 
     let status = format!(
-        "Auto-refresh: {}  Monitors: {}  Results: {}  Last: {}s  {}",
-        if state.auto_refresh { "On" } else { "Off" },
+        "Auto-refresh: {}  Monitors: {}  Results: {}  Last: {}s  Layout: {}  {}",
+        match (state.auto_refresh, state.bus_live) {
+            (false, _) => "Off",
+            (true, true) => "Live",
+            (true, false) => "Polling",
+        },
         state.monitors.len(),
         state.results.len(),
         state.last_refresh.elapsed().as_secs(),
+        if state.zoomed { "zoom" } else { state.layout_preset.label() },
         p2p_status
     );
 
+    let subtitle = match &state.status_message {
+        Some((message, at)) if at.elapsed().as_secs() < STATUS_MESSAGE_SECS => {
+            Span::styled(format!("— {message}"), Style::default().fg(theme.warning))
+        }
+        _ => Span::raw("— 4-Pane View (Monitors | Results | Stats | Network)"),
+    };
+
     let header = Paragraph::new(vec![
         Line::from(vec![
             Span::styled(
                 "Uppe. Dashboard ",
-                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
             ),
-            Span::raw("— 4-Pane View (Monitors | Results | Stats | Network)"),
+            subtitle,
         ]),
-        Line::from(Span::styled(status, Style::default().fg(Color::Gray))),
+        Line::from(Span::styled(status, Style::default().fg(theme.label))),
     ]);
 
     f.render_widget(Clear, area);
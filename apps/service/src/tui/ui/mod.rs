@@ -1,3 +1,4 @@
+pub mod banner;
 pub mod footer;
 pub mod header;
 pub mod monitors;
@@ -7,61 +8,94 @@ pub mod results;
 pub mod stats;
 
 use ratatui::Frame;
-use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
 
 use crate::tui::state::AppState;
-use crate::tui::types::FrameAreas;
+use crate::tui::types::{Focus, FrameAreas};
+
+/// Areas of the four panes within `grid`, split as the layout says; when
+/// zoomed the focused pane fills the grid and the others get no area
+fn pane_areas(grid: Rect, state: &AppState) -> [Rect; 4] {
+    if state.zoomed {
+        let mut panes = [Rect::default(); 4];
+        let index = match state.focus {
+            Focus::Monitors => 0,
+            Focus::Results => 1,
+            Focus::Stats => 2,
+            Focus::Network => 3,
+        };
+        panes[index] = grid;
+        return panes;
+    }
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(state.split_y), Constraint::Min(0)])
+        .split(grid);
+    let columns = |row: Rect| {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(state.split_x), Constraint::Min(0)])
+            .split(row)
+    };
+    let (top, bottom) = (columns(rows[0]), columns(rows[1]));
+    [top[0], top[1], bottom[0], bottom[1]]
+}
 
 /// Render the entire UI
 pub fn render(f: &mut Frame, state: &mut AppState) {
     let size = f.size();
 
+    // The alert banner only takes a row while something is down or open
+    let banner_height = u16::from(banner::is_visible(state));
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
-        .constraints([Constraint::Length(2), Constraint::Min(1), Constraint::Length(2)])
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Length(banner_height),
+            Constraint::Min(1),
+            Constraint::Length(2),
+        ])
         .split(size);
 
-    // Render header
+    // Render header and alert banner
     header::render(f, chunks[0], state);
+    if banner_height > 0 {
+        banner::render(f, chunks[1], state);
+    }
 
-    // Create 2x2 grid layout for main content
-    let grid = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-        .split(chunks[1]);
-
-    let top_panes = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-        .split(grid[0]);
-
-    let bottom_panes = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-        .split(grid[1]);
-
-    // Render all 4 panes
-    monitors::render(f, top_panes[0], state);
-    results::render(f, top_panes[1], state);
-    stats::render(f, bottom_panes[0], state);
-    network::render(f, bottom_panes[1], state);
+    // Panes in grid order: monitors, results, stats, network
+    let panes = pane_areas(chunks[2], state);
+    if panes[0].area() > 0 {
+        monitors::render(f, panes[0], state);
+    }
+    if panes[1].area() > 0 {
+        results::render(f, panes[1], state);
+    }
+    if panes[2].area() > 0 {
+        stats::render(f, panes[2], state);
+    }
+    if panes[3].area() > 0 {
+        network::render(f, panes[3], state);
+    }
 
     // Render footer
-    let action_buttons = footer::render(f, chunks[2], state.show_help);
+    let action_buttons = footer::render(f, chunks[3], state.show_help, &state.theme);
 
     // Store frame areas for mouse hit-testing
     state.areas = Some(FrameAreas {
         header: chunks[0],
-        monitors: top_panes[0],
-        results: top_panes[1],
-        footer: chunks[2],
+        grid: chunks[2],
+        monitors: panes[0],
+        results: panes[1],
+        footer: chunks[3],
         action_buttons,
     });
 
     // Render popups (overlays)
     if state.show_help {
-        popups::help::render(f, size);
+        popups::help::render(f, size, &state.theme);
     }
 
     if state.show_edit {
@@ -72,7 +106,51 @@ pub fn render(f: &mut Frame, state: &mut AppState) {
         popups::delete::render(f, size, state);
     }
 
+    if state.bulk_confirm.is_some() {
+        popups::bulk::render(f, size, state);
+    }
+
     if state.show_result_detail {
         popups::result_detail::render(f, size, state);
     }
+
+    if state.show_audit {
+        popups::audit::render(f, size, state);
+    }
+
+    if state.show_notifications {
+        popups::notifications::render(f, size, state);
+    }
+
+    if state.show_distributed {
+        popups::distributed::render(f, size, state);
+    }
+
+    if state.show_assignments {
+        popups::assignments::render(f, size, state);
+    }
+
+    if state.show_following {
+        popups::following::render(f, size, state);
+    }
+
+    if state.show_community {
+        popups::community::render(f, size, state);
+    }
+
+    if state.dht_form.is_some() {
+        popups::dht::render(f, size, state);
+    }
+
+    if state.peer_detail.is_some() {
+        popups::peer_detail::render(f, size, state);
+    }
+
+    if state.tag_input.is_some() {
+        popups::tags::render(f, size, state);
+    }
+
+    if state.export_path.is_some() {
+        popups::export::render(f, size, state);
+    }
 }
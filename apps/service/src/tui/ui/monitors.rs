@@ -1,13 +1,15 @@
 use ratatui::Frame;
 use ratatui::layout::Rect;
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, List, ListItem};
 
+use crate::monitoring::types::MonitorStatus;
 use crate::tui::state::AppState;
 use crate::tui::types::Focus;
 
 pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
+    let theme = &state.theme;
     let items: Vec<ListItem> = state
         .monitors
         .iter()
@@ -15,25 +17,64 @@ pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
         .map(|(i, m)| {
             let selected = i == state.selected;
             let style = if selected {
-                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                Style::default().fg(theme.warning).add_modifier(Modifier::BOLD)
             } else {
                 Style::default()
             };
 
+            let latest = state.latest_results.get(&m.uuid);
+            let in_maintenance = latest.is_some_and(|r| r.maintenance);
+            let status_color = match latest.map(|r| r.status) {
+                _ if in_maintenance => theme.accent,
+                Some(MonitorStatus::Up) => theme.success,
+                Some(MonitorStatus::Degraded) => theme.warning,
+                Some(MonitorStatus::Down) => theme.error,
+                Some(MonitorStatus::Unknown) | None => theme.muted,
+            };
+            let latency = latest
+                .and_then(|r| r.latency_ms)
+                .map(|ms| format!("  {ms}ms"))
+                .unwrap_or_default();
+
+            let mark = if state.marked.contains(&m.uuid) { "▶ " } else { "  " };
+
             ListItem::new(Line::from(vec![
+                Span::styled(mark, Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                Span::styled("● ", Style::default().fg(status_color)),
                 Span::styled(m.name.to_string(), style),
                 Span::styled(
                     if m.enabled { "  ✓ " } else { "  ✗ " },
-                    Style::default().fg(if m.enabled { Color::Green } else { Color::Red }),
+                    Style::default().fg(if m.enabled { theme.success } else { theme.error }),
                 ),
                 Span::raw(format!(" [{}]", m.check_type)),
                 Span::raw(format!("  -> {}", m.target)),
+                Span::styled(
+                    m.tags.iter().map(|tag| format!(" #{tag}")).collect::<String>(),
+                    Style::default().fg(theme.tag),
+                ),
+                Span::styled(latency, Style::default().fg(theme.muted)),
+                Span::styled(
+                    if in_maintenance { "  maintenance" } else { "" },
+                    Style::default().fg(theme.accent),
+                ),
             ]))
         })
         .collect();
 
-    let monitors_title =
-        if state.focus == Focus::Monitors { "Monitors (focused)" } else { "Monitors" };
+    let mut monitors_title =
+        if state.focus == Focus::Monitors { "Monitors (focused)" } else { "Monitors" }.to_string();
+    if state.monitors.len() != state.all_monitors.len() {
+        monitors_title.push_str(&format!(" {}/{}", state.monitors.len(), state.all_monitors.len()));
+    }
+    monitors_title.push_str(&format!(" - sort: {}", state.monitor_sort.label()));
+    if !state.marked.is_empty() {
+        monitors_title.push_str(&format!(" - {} marked (P/U/D/#, Esc clears)", state.marked.len()));
+    }
+    if state.search_active {
+        monitors_title.push_str(&format!(" - /{}_", state.search_query));
+    } else if !state.search_query.is_empty() {
+        monitors_title.push_str(&format!(" - /{} (Esc clears)", state.search_query));
+    }
 
     let monitors_list =
         List::new(items).block(Block::default().borders(Borders::ALL).title(monitors_title));
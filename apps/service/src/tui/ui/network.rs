@@ -1,16 +1,31 @@
 use ratatui::Frame;
 use ratatui::layout::Rect;
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph};
+use std::time::{Duration, SystemTime};
 
 use crate::tui::state::AppState;
 
+/// Peers listed at once in the pane
+const PEER_ROWS: usize = 5;
+
+/// Format a duration as its two largest units, e.g. "3h 12m"
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
+
 pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
+    let theme = &state.theme;
     let focus_style = if state.focus == crate::tui::types::Focus::Network {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        Style::default().fg(theme.warning).add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::Cyan)
+        Style::default().fg(theme.accent)
     };
 
     let title = if state.focus == crate::tui::types::Focus::Network {
@@ -24,16 +39,16 @@ pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
     let node_status = if state.p2p_enabled {
         Span::styled(
             format!("Node ID: {}...", state.peer_id.chars().take(16).collect::<String>()),
-            Style::default().fg(Color::Green),
+            Style::default().fg(theme.success),
         )
     } else {
-        Span::styled("P2P: Disabled", Style::default().fg(Color::Red))
+        Span::styled("P2P: Disabled", Style::default().fg(theme.error))
     };
 
     lines.push(Line::from(node_status));
 
     let status_text = if state.p2p_enabled { "✓ Connected" } else { "✗ Offline" };
-    let status_color = if state.p2p_enabled { Color::Green } else { Color::Red };
+    let status_color = if state.p2p_enabled { theme.success } else { theme.error };
 
     lines.push(Line::from(vec![
         Span::raw("Status:  "),
@@ -42,7 +57,7 @@ pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
 
     if state.p2p_enabled {
         lines.push(Line::from(""));
-        lines.push(Line::from(Span::styled("Peers", Style::default().fg(Color::Yellow))));
+        lines.push(Line::from(Span::styled("Peers", Style::default().fg(theme.warning))));
         lines.push(Line::from(format!("  Connected: {}", state.connected_peers)));
         lines.push(Line::from(format!("  Total Seen: {}", state.total_peers_seen)));
 
@@ -52,11 +67,11 @@ pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
             0
         };
         let health_color = if health_pct > 80 {
-            Color::Green
+            theme.success
         } else if health_pct > 50 {
-            Color::Yellow
+            theme.warning
         } else {
-            Color::Red
+            theme.error
         };
         lines.push(Line::from(vec![
             Span::raw("  Health:    "),
@@ -64,14 +79,103 @@ pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
         ]));
 
         lines.push(Line::from(""));
-        lines.push(Line::from(Span::styled("Activity", Style::default().fg(Color::Yellow))));
+        lines.push(Line::from(Span::styled("Activity", Style::default().fg(theme.warning))));
         lines.push(Line::from(format!("  Shared:    {} results", state.results_shared)));
         lines.push(Line::from(format!("  Received:  {} results", state.results_received)));
+        if let Some(rate) = state.seen_hit_rate {
+            lines.push(Line::from(format!(
+                "  Duplicate: {:.1}% of {} gossiped",
+                rate * 100.0,
+                state.seen_lookups
+            )));
+        }
+
+        if state.helper_max_assignments > 0 {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled("Helping", Style::default().fg(theme.warning))));
+            lines.push(Line::from(format!(
+                "  Assigned:  {}/{}",
+                state.helper_assignments, state.helper_max_assignments
+            )));
+            lines.push(Line::from(format!(
+                "  Checks/h:  {}/{} (busiest owner)",
+                state.helper_checks_last_hour, state.helper_max_checks_per_hour
+            )));
+        }
+
+        if let Some(sync) = &state.owner_sync {
+            let now = SystemTime::now();
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled("DHT Sync", Style::default().fg(theme.warning))));
+
+            if sync.running {
+                lines.push(Line::from(vec![
+                    Span::raw("  Syncing:   "),
+                    Span::styled(
+                        format!("{}/{} monitors", sync.monitors_synced, sync.monitors_total),
+                        Style::default().fg(theme.accent),
+                    ),
+                ]));
+            } else if let Some(last) = sync.last_completed_at {
+                let ago = now.duration_since(last).unwrap_or_default();
+                lines.push(Line::from(format!(
+                    "  Last:      {} ago ({} results)",
+                    format_duration(ago),
+                    sync.records_found
+                )));
+            }
+
+            if sync.failures > 0 {
+                lines.push(Line::from(vec![
+                    Span::raw("  Failed:    "),
+                    Span::styled(
+                        format!("{} in a row", sync.failures),
+                        Style::default().fg(theme.error),
+                    ),
+                ]));
+            }
+            if !sync.running
+                && let Some(next) = sync.next_attempt_at
+            {
+                let wait = next.duration_since(now).unwrap_or_default();
+                lines.push(Line::from(format!("  Next:      in {}", format_duration(wait))));
+            }
+        }
+
+        if !state.peers.is_empty() {
+            let focused = state.focus == crate::tui::types::Focus::Network;
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                if focused { "Known Peers (Enter: details)" } else { "Known Peers" },
+                Style::default().fg(theme.warning),
+            )));
+
+            // Keep the selected peer in view
+            let start = (state.selected_peer + 1).saturating_sub(PEER_ROWS);
+            for (i, peer) in state.peers.iter().enumerate().skip(start).take(PEER_ROWS) {
+                let (status, color) = match peer.banned_at {
+                    Some(_) => ("banned", theme.error),
+                    None if peer.status == "online" => ("online", theme.success),
+                    None => ("offline", theme.muted),
+                };
+                let mut line = Line::from(vec![
+                    Span::raw(format!(
+                        "  {}... ",
+                        peer.peer_id.chars().take(12).collect::<String>()
+                    )),
+                    Span::styled(status, Style::default().fg(color)),
+                ]);
+                if focused && i == state.selected_peer {
+                    line = line.style(Style::default().add_modifier(Modifier::REVERSED));
+                }
+                lines.push(line);
+            }
+        }
 
         if let Some(ref event) = state.last_peer_event {
             lines.push(Line::from(""));
             lines.push(Line::from(vec![
-                Span::styled("Last Event: ", Style::default().fg(Color::Cyan)),
+                Span::styled("Last Event: ", Style::default().fg(theme.accent)),
                 Span::raw(event),
             ]));
         }
@@ -79,7 +183,7 @@ pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
         lines.push(Line::from(""));
         lines.push(Line::from(Span::styled(
             "P2P networking is disabled",
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.muted),
         )));
         lines.push(Line::from(Span::raw("Enable in config to share")));
         lines.push(Line::from(Span::raw("and receive monitoring data")));
@@ -0,0 +1,146 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, Cell, Clear, Row, Table};
+use std::time::{Duration, SystemTime};
+
+use crate::database::models::HelperAssignment;
+use crate::tui::state::AppState;
+use crate::tui::theme::Theme;
+use crate::tui::ui::network::format_duration;
+use crate::tui::ui::popups::distributed::short_peer;
+
+/// Intervals that may pass without a result before an assignment looks stalled
+const STALLED_INTERVALS: u32 = 3;
+
+fn header(theme: &Theme, cells: &[&'static str]) -> Row<'static> {
+    Row::new(cells.iter().map(|cell| Cell::from(*cell)).collect::<Vec<_>>())
+        .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))
+}
+
+fn confirmation(theme: &Theme, assignment: &HelperAssignment) -> Cell<'static> {
+    if assignment.is_accepted() {
+        Cell::from("confirmed").style(Style::default().fg(theme.success))
+    } else {
+        Cell::from("awaiting").style(Style::default().fg(theme.warning))
+    }
+}
+
+/// Time since the last result, flagged once the assignment looks stalled
+fn last_result(theme: &Theme, assignment: &HelperAssignment, now: SystemTime) -> Cell<'static> {
+    let stalled_after = Duration::from_secs(assignment.interval_seconds) * STALLED_INTERVALS;
+    let (text, color): (String, Color) = match assignment.last_result_at {
+        Some(at) => {
+            let ago = now.duration_since(at).unwrap_or_default();
+            let color = if ago > stalled_after { theme.warning } else { theme.text };
+            (format!("{} ago", format_duration(ago)), color)
+        }
+        None => ("never".to_string(), theme.muted),
+    };
+    Cell::from(text).style(Style::default().fg(color))
+}
+
+pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(10),
+            Constraint::Percentage(80),
+            Constraint::Percentage(10),
+        ])
+        .split(size);
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(10),
+            Constraint::Percentage(80),
+            Constraint::Percentage(10),
+        ])
+        .split(vchunks[1]);
+
+    let area = hchunks[1];
+    let now = SystemTime::now();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Helper Assignments - R: Refresh  Esc/Q: Close");
+    let inner = block.inner(area);
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(inner);
+
+    // Duties we run for other owners
+    let inbound: Vec<Row> = state
+        .inbound_assignments
+        .iter()
+        .map(|duty| {
+            Row::new(vec![
+                Cell::from(short_peer(&duty.owner_peer_id)),
+                Cell::from(duty.target_hash()),
+                Cell::from(format!("every {}s", duty.interval_seconds)),
+                confirmation(theme, duty),
+                last_result(theme, duty, now),
+            ])
+        })
+        .collect();
+    let title = format!("We Help ({}) - last result sent", inbound.len());
+    f.render_widget(
+        Table::new(
+            inbound,
+            [
+                Constraint::Length(14),
+                Constraint::Length(14),
+                Constraint::Length(12),
+                Constraint::Length(11),
+                Constraint::Min(10),
+            ],
+        )
+        .header(header(theme, &["Owner", "Target Hash", "Interval", "State", "Last Sent"]))
+        .block(Block::default().borders(Borders::BOTTOM).title(title)),
+        chunks[0],
+    );
+
+    // Peers checking our monitors
+    let outbound: Vec<Row> = state
+        .outbound_assignments
+        .iter()
+        .map(|assignment| {
+            let monitor = state
+                .all_monitors
+                .iter()
+                .find(|m| m.uuid == assignment.monitor_uuid)
+                .map(|m| m.name.clone())
+                .unwrap_or_else(|| assignment.target.clone());
+            Row::new(vec![
+                Cell::from(monitor),
+                Cell::from(short_peer(&assignment.helper_peer_id)),
+                Cell::from(format!("every {}s", assignment.interval_seconds)),
+                confirmation(theme, assignment),
+                last_result(theme, assignment, now),
+            ])
+        })
+        .collect();
+    let title = format!("Helping Us ({}) - last result received", outbound.len());
+    f.render_widget(
+        Table::new(
+            outbound,
+            [
+                Constraint::Min(20),
+                Constraint::Length(14),
+                Constraint::Length(12),
+                Constraint::Length(11),
+                Constraint::Length(14),
+            ],
+        )
+        .header(header(theme, &["Monitor", "Helper", "Interval", "State", "Last Received"]))
+        .block(Block::default().title(title)),
+        chunks[1],
+    );
+}
@@ -0,0 +1,97 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, Cell, Clear, Row, Table};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::civil::civil_from_days;
+use crate::database::models::AuditKind;
+use crate::tui::state::AppState;
+use crate::tui::theme::Theme;
+
+/// Format SystemTime as YYYY-MM-DD HH:MM in UTC.
+fn format_timestamp(time: SystemTime) -> String {
+    let total_secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (year, month, day) = civil_from_days((total_secs / 86400) as i64);
+    let hours = (total_secs % 86400) / 3600;
+    let minutes = (total_secs % 3600) / 60;
+
+    format!("{year:04}-{month:02}-{day:02} {hours:02}:{minutes:02}")
+}
+
+fn kind_color(theme: &Theme, kind: AuditKind) -> Color {
+    match kind {
+        AuditKind::SignatureFailure | AuditKind::PeerBanned => theme.error,
+        AuditKind::RateLimited | AuditKind::PolicyRejected => theme.warning,
+        AuditKind::AdminAction => theme.accent,
+    }
+}
+
+pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(10),
+            Constraint::Percentage(80),
+            Constraint::Percentage(10),
+        ])
+        .split(size);
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(5),
+            Constraint::Percentage(90),
+            Constraint::Percentage(5),
+        ])
+        .split(vchunks[1]);
+
+    let area = hchunks[1];
+
+    let rows: Vec<Row> = state
+        .audit_events
+        .iter()
+        .enumerate()
+        .map(|(i, event)| {
+            let peer = event.peer_id.as_deref().map(|p| p.chars().take(12).collect::<String>());
+
+            let mut row = Row::new(vec![
+                Cell::from(format_timestamp(event.timestamp)),
+                Cell::from(event.kind.to_string())
+                    .style(Style::default().fg(kind_color(theme, event.kind))),
+                Cell::from(peer.unwrap_or_else(|| "-".into())),
+                Cell::from(event.message.clone()),
+            ]);
+
+            if i == state.selected_audit {
+                row = row.style(Style::default().add_modifier(Modifier::REVERSED));
+            }
+
+            row
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(17),
+        Constraint::Length(18),
+        Constraint::Length(13),
+        Constraint::Min(20),
+    ];
+
+    let title = format!("Audit Log ({} events) - Up/Down: Scroll  Esc/Q: Close", rows.len());
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec![
+                Cell::from("Time (UTC)"),
+                Cell::from("Kind"),
+                Cell::from("Peer"),
+                Cell::from("Message"),
+            ])
+            .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::default().borders(Borders::ALL).title(title));
+
+    f.render_widget(Clear, area);
+    f.render_widget(table, area);
+}
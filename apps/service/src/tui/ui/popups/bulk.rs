@@ -0,0 +1,96 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+
+use crate::tui::state::AppState;
+use crate::tui::types::BulkAction;
+
+/// Names listed in the confirmation before the rest are summarised
+const MAX_LISTED: usize = 8;
+
+pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let Some(action) = &state.bulk_confirm else {
+        return;
+    };
+
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(25),
+            Constraint::Percentage(50),
+            Constraint::Percentage(25),
+        ])
+        .split(size);
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(20),
+            Constraint::Percentage(60),
+            Constraint::Percentage(20),
+        ])
+        .split(vchunks[1]);
+
+    let area = hchunks[1];
+
+    let monitors = state.marked_monitors();
+    let title_color = if *action == BulkAction::Delete { theme.error } else { theme.warning };
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("{} {} monitors", action.verb(), monitors.len()),
+            Style::default().fg(title_color).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    if let BulkAction::Tag(tags) = action {
+        let tags = if tags.is_empty() {
+            "no tags".to_string()
+        } else {
+            tags.iter().map(|tag| format!("#{tag}")).collect::<Vec<_>>().join(" ")
+        };
+        lines.push(Line::from(vec![
+            Span::styled("New tags: ", Style::default().fg(theme.label)),
+            Span::styled(tags, Style::default().fg(theme.tag)),
+        ]));
+        lines.push(Line::from(""));
+    }
+    for m in monitors.iter().take(MAX_LISTED) {
+        let unchanged = match action {
+            BulkAction::Pause => !m.enabled,
+            BulkAction::Enable => m.enabled,
+            BulkAction::Delete | BulkAction::Tag(_) => false,
+        };
+        let style = if unchanged {
+            Style::default().fg(theme.muted)
+        } else {
+            Style::default().fg(theme.text)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {}", m.name), style),
+            Span::styled(format!("  -> {}", m.target), Style::default().fg(theme.muted)),
+            Span::styled(
+                if unchanged { "  (unchanged)" } else { "" },
+                Style::default().fg(theme.muted),
+            ),
+        ]));
+    }
+    if monitors.len() > MAX_LISTED {
+        lines.push(Line::from(Span::styled(
+            format!("  … and {} more", monitors.len() - MAX_LISTED),
+            Style::default().fg(theme.muted),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from("Y: Yes    N/Esc: No"));
+
+    let popup = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Confirm Bulk Action"));
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
@@ -0,0 +1,101 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table};
+
+use crate::tui::state::AppState;
+
+/// Characters of a peer ID shown in the participant list
+const PEER_ID_CHARS: usize = 8;
+
+pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(15),
+            Constraint::Percentage(70),
+            Constraint::Percentage(15),
+        ])
+        .split(size);
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(15),
+            Constraint::Percentage(70),
+            Constraint::Percentage(15),
+        ])
+        .split(vchunks[1]);
+
+    let area = hchunks[1];
+    let block = Block::default().borders(Borders::ALL).title(
+        "Community Domains - Space: Join/Leave  +: Join selected monitor's host  R: Refresh  \
+         Esc/Q: Close",
+    );
+    let inner = block.inner(area);
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    let rows: Vec<Row> = state
+        .community
+        .iter()
+        .enumerate()
+        .map(|(i, (group, domain))| {
+            let joined = domain.as_ref().is_some_and(|domain| domain.joined);
+            let participants = group
+                .participating_peers
+                .iter()
+                .map(|peer_id| peer_id.chars().take(PEER_ID_CHARS).collect::<String>())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let mut row = Row::new(vec![
+                Cell::from(if joined { "[x]" } else { "[ ]" }),
+                Cell::from(group.host.clone()),
+                Cell::from(group.participating_peers.len().to_string()),
+                Cell::from(participants),
+            ]);
+            if !joined {
+                row = row.style(Style::default().fg(theme.muted));
+            }
+            if i == state.selected_community {
+                row = row.style(Style::default().add_modifier(Modifier::REVERSED));
+            }
+            row
+        })
+        .collect();
+
+    f.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Min(24),
+                Constraint::Length(6),
+                Constraint::Min(20),
+            ],
+        )
+        .header(
+            Row::new(vec!["Joined", "Host", "Peers", "Participants"])
+                .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        ),
+        chunks[0],
+    );
+
+    let status = match &state.community_status {
+        Some(status) => Paragraph::new(status.clone()).style(Style::default().fg(theme.warning)),
+        None if state.community.is_empty() => {
+            Paragraph::new("No community domains yet - press + to join the selected monitor's host")
+                .style(Style::default().fg(theme.muted))
+        }
+        None => Paragraph::new(""),
+    };
+    f.render_widget(status, chunks[1]);
+}
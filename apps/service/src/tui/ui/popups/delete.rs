@@ -1,12 +1,13 @@
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 
 use crate::tui::state::AppState;
 
 pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
     let vchunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -32,7 +33,7 @@ pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
     let popup = Paragraph::new(vec![
         Line::from(Span::styled(
             "Delete Monitor",
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
         )),
         Line::from(""),
         Line::from(format!("Are you sure you want to delete '{name}' ?")),
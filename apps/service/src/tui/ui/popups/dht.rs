@@ -0,0 +1,145 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, Wrap};
+
+use crate::database::models::{DhtOperationKind, ProbeState};
+use crate::p2p::messages::{DEBUG_KEY_PREFIX, debug_key};
+use crate::tui::state::AppState;
+use crate::tui::ui::results::format_time;
+use crate::validation::MAX_DHT_VALUE_BYTES;
+
+pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let Some(form) = &state.dht_form else {
+        return;
+    };
+
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(15),
+            Constraint::Percentage(70),
+            Constraint::Percentage(15),
+        ])
+        .split(size);
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(10),
+            Constraint::Percentage(80),
+            Constraint::Percentage(10),
+        ])
+        .split(vchunks[1]);
+
+    let area = hchunks[1];
+
+    let field_style = |active: bool| {
+        if active { Style::default().fg(theme.warning) } else { Style::default().fg(theme.text) }
+    };
+    let cursor = |active: bool| if active { "|" } else { "" };
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Key:   ", Style::default().fg(theme.label)),
+            Span::styled(DEBUG_KEY_PREFIX, Style::default().fg(theme.muted)),
+            Span::styled(
+                format!("{}{}", form.key, cursor(!form.editing_value)),
+                field_style(!form.editing_value),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Value: ", Style::default().fg(theme.label)),
+            Span::styled(
+                format!("{}{}", form.value, cursor(form.editing_value)),
+                field_style(form.editing_value),
+            ),
+        ]),
+        Line::from(Span::styled(
+            format!("       {}/{MAX_DHT_VALUE_BYTES} bytes", form.value.len()),
+            Style::default().fg(if form.value.len() > MAX_DHT_VALUE_BYTES {
+                theme.error
+            } else {
+                theme.muted
+            }),
+        )),
+    ];
+
+    if form.confirm_put {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "Publish {} bytes under '{}' to the DHT? Peers keep the record. (y/n)",
+                form.value.len(),
+                debug_key(&form.key)
+            ),
+            Style::default().fg(theme.warning).add_modifier(Modifier::BOLD),
+        )));
+    } else if let Some(err) = &state.validation_error {
+        lines.push(Line::from(Span::styled(
+            format!("⚠ {err}"),
+            Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
+        )));
+    } else {
+        lines.push(Line::from(""));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("DHT Debug - Tab: Switch field  Enter: Get (key) / Publish (value)  Esc: Close");
+    let inner = block.inner(area);
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(lines.len() as u16 + 1), Constraint::Min(1)])
+        .split(inner);
+
+    f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), chunks[0]);
+
+    let rows: Vec<Row> = state
+        .dht_operations
+        .iter()
+        .map(|operation| {
+            let color = match operation.state {
+                ProbeState::Pending | ProbeState::Running => theme.warning,
+                ProbeState::Complete => theme.success,
+                ProbeState::Failed => theme.error,
+            };
+            let kind = match operation.kind {
+                DhtOperationKind::Get => "GET",
+                DhtOperationKind::Put => "PUT",
+            };
+
+            Row::new(vec![
+                Cell::from(format_time(operation.requested_at)),
+                Cell::from(kind),
+                Cell::from(operation.key.clone()),
+                Cell::from(operation.summary()).style(Style::default().fg(color)),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Length(4),
+        Constraint::Length(24),
+        Constraint::Min(20),
+    ];
+    let header = Row::new(vec![
+        Cell::from("Time"),
+        Cell::from(""),
+        Cell::from("Key"),
+        Cell::from("Outcome"),
+    ])
+    .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD));
+    f.render_widget(
+        Table::new(rows, widths)
+            .header(header)
+            .block(Block::default().borders(Borders::TOP).title("Recent Operations")),
+        chunks[1],
+    );
+}
@@ -0,0 +1,262 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Clear, List, ListItem, Paragraph, Row, Table};
+use std::time::SystemTime;
+
+use crate::monitoring::types::MonitorStatus;
+use crate::tui::state::AppState;
+use crate::tui::theme::Theme;
+use crate::tui::ui::network::format_duration;
+use crate::tui::ui::results::format_time;
+
+/// Peer IDs share a long common prefix, so show their distinctive tail
+pub fn short_peer(peer_id: &str) -> String {
+    let chars: Vec<char> = peer_id.chars().collect();
+    if chars.len() <= 12 {
+        return peer_id.to_string();
+    }
+    format!("…{}", chars[chars.len() - 12..].iter().collect::<String>())
+}
+
+fn header(theme: &Theme, cells: &[&'static str]) -> Row<'static> {
+    Row::new(cells.iter().map(|cell| Cell::from(*cell)).collect::<Vec<_>>())
+        .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))
+}
+
+pub fn status_color(theme: &Theme, status: MonitorStatus) -> Color {
+    match status {
+        MonitorStatus::Up => theme.success,
+        MonitorStatus::Down => theme.error,
+        MonitorStatus::Degraded => theme.warning,
+        MonitorStatus::Unknown => theme.muted,
+    }
+}
+
+pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(5),
+            Constraint::Percentage(90),
+            Constraint::Percentage(5),
+        ])
+        .split(size);
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(5),
+            Constraint::Percentage(90),
+            Constraint::Percentage(5),
+        ])
+        .split(vchunks[1]);
+
+    let area = hchunks[1];
+    let now = SystemTime::now();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Distributed - J/K: Select monitor  R: Refresh  Esc/Q: Close");
+    let inner = block.inner(area);
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(10), Constraint::Length(8)])
+        .split(inner);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(25), Constraint::Percentage(75)])
+        .split(rows[0]);
+
+    // Public monitors
+    let items: Vec<ListItem> = state
+        .distributed
+        .iter()
+        .enumerate()
+        .map(|(i, domain)| {
+            let mut style = Style::default();
+            if i == state.selected_domain {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            ListItem::new(format!("{} ({})", domain.monitor.name, domain.participants().len()))
+                .style(style)
+        })
+        .collect();
+    let list = if items.is_empty() {
+        List::new(vec![ListItem::new(Span::styled(
+            "No public monitors",
+            Style::default().fg(theme.muted),
+        ))])
+    } else {
+        List::new(items)
+    };
+    f.render_widget(
+        list.block(Block::default().borders(Borders::RIGHT).title("Public Monitors (peers)")),
+        columns[0],
+    );
+
+    if let Some(domain) = state.distributed.get(state.selected_domain) {
+        let details = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Percentage(40),
+                Constraint::Percentage(60),
+            ])
+            .split(columns[1]);
+
+        let agreement = match &domain.agreement {
+            Some(agreement) => Span::styled(
+                format!(
+                    "{} (bucket from {})",
+                    agreement.summary(),
+                    format_time(agreement.bucket_start)
+                ),
+                Style::default().fg(status_color(theme, agreement.local_status)),
+            ),
+            None => Span::styled("Nothing agreed yet", Style::default().fg(theme.muted)),
+        };
+        let participants = domain.participants();
+        let lines = vec![
+            Line::from(vec![
+                Span::styled("Target:       ", Style::default().fg(theme.accent)),
+                Span::raw(domain.monitor.target.clone()),
+            ]),
+            Line::from(vec![
+                Span::styled("Agreement:    ", Style::default().fg(theme.accent)),
+                agreement,
+            ]),
+            Line::from(vec![
+                Span::styled("Participants: ", Style::default().fg(theme.accent)),
+                Span::raw(if participants.is_empty() {
+                    "None".to_string()
+                } else {
+                    participants.iter().map(|peer| short_peer(peer)).collect::<Vec<_>>().join(", ")
+                }),
+            ]),
+        ];
+        f.render_widget(Paragraph::new(lines), details[0]);
+
+        // Schedule: accepted assignments are agreed, the rest await the helper
+        let schedule: Vec<Row> = domain
+            .schedule
+            .iter()
+            .map(|assignment| {
+                let (label, color) = if assignment.is_accepted() {
+                    ("agreed", theme.success)
+                } else {
+                    ("awaiting helper", theme.warning)
+                };
+                let expires = match assignment.expires_at {
+                    Some(at) => format!(
+                        "in {}",
+                        format_duration(at.duration_since(now).unwrap_or_default())
+                    ),
+                    None => "never".to_string(),
+                };
+                Row::new(vec![
+                    Cell::from(short_peer(&assignment.helper_peer_id)),
+                    Cell::from(format!("{}s", assignment.interval_seconds)),
+                    Cell::from(label).style(Style::default().fg(color)),
+                    Cell::from(expires),
+                ])
+            })
+            .collect();
+        f.render_widget(
+            Table::new(
+                schedule,
+                [
+                    Constraint::Length(14),
+                    Constraint::Length(7),
+                    Constraint::Length(16),
+                    Constraint::Min(10),
+                ],
+            )
+            .header(header(theme, &["Helper", "Every", "State", "Expires"]))
+            .block(Block::default().borders(Borders::TOP).title("Schedule")),
+            details[1],
+        );
+
+        // Votes: each peer's latest signed status, pending until the next
+        // agreement bucket counts it
+        let votes: Vec<Row> = domain
+            .votes
+            .iter()
+            .map(|vote| {
+                let signature =
+                    hex::encode(vote.signature.iter().take(8).copied().collect::<Vec<_>>());
+                let (counted, counted_color) = if !vote.verified {
+                    ("ignored", theme.error)
+                } else if domain.is_pending(vote) {
+                    ("pending", theme.warning)
+                } else {
+                    ("counted", theme.success)
+                };
+                Row::new(vec![
+                    Cell::from(short_peer(&vote.peer_id)),
+                    Cell::from(vote.status.to_string())
+                        .style(Style::default().fg(status_color(theme, vote.status))),
+                    Cell::from(format_time(vote.timestamp)),
+                    Cell::from(format!("{signature}…")),
+                    Cell::from(if vote.verified { "✓" } else { "✗" }),
+                    Cell::from(counted).style(Style::default().fg(counted_color)),
+                ])
+            })
+            .collect();
+        f.render_widget(
+            Table::new(
+                votes,
+                [
+                    Constraint::Length(14),
+                    Constraint::Length(9),
+                    Constraint::Length(10),
+                    Constraint::Length(18),
+                    Constraint::Length(8),
+                    Constraint::Min(8),
+                ],
+            )
+            .header(header(theme, &["Peer", "Status", "Checked", "Signature", "Verified", "Vote"]))
+            .block(Block::default().borders(Borders::TOP).title("Votes")),
+            details[2],
+        );
+    }
+
+    // Duties we run for other owners
+    let duties: Vec<Row> = state
+        .helper_duties
+        .iter()
+        .map(|duty| {
+            let until = match duty.expires_at {
+                Some(at) => format_time(at),
+                None => "-".to_string(),
+            };
+            Row::new(vec![
+                Cell::from(short_peer(&duty.owner_peer_id)),
+                Cell::from(duty.target.clone()),
+                Cell::from(format!("every {}s", duty.interval_seconds)),
+                Cell::from(until),
+            ])
+        })
+        .collect();
+    let title = if duties.is_empty() { "Our Time Slots - none assigned" } else { "Our Time Slots" };
+    f.render_widget(
+        Table::new(
+            duties,
+            [
+                Constraint::Length(14),
+                Constraint::Min(20),
+                Constraint::Length(12),
+                Constraint::Length(10),
+            ],
+        )
+        .header(header(theme, &["Owner", "Target", "Slot", "Until"]))
+        .block(Block::default().borders(Borders::TOP).title(title)),
+        rows[1],
+    );
+}
@@ -1,12 +1,13 @@
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 
 use crate::tui::state::AppState;
 
 pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
     if let Some(m) = &state.edit_monitor {
         let vchunks = Layout::default()
             .direction(Direction::Vertical)
@@ -43,14 +44,14 @@ pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
         let mut lines: Vec<Line> = Vec::new();
         lines.push(Line::from(Span::styled(
             title,
-            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
         )));
         lines.push(Line::from(""));
 
         for (i, (label, value)) in labels.iter().zip(values.iter()).enumerate() {
             let prefix = if i == state.edit_field_index { "> " } else { "  " };
             let field_style = if i == state.edit_field_index {
-                Style::default().fg(Color::Yellow)
+                Style::default().fg(theme.warning)
             } else {
                 Style::default()
             };
@@ -68,7 +69,7 @@ pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
 
             lines.push(Line::from(vec![
                 Span::raw(prefix),
-                Span::styled(format!("{label}: "), Style::default().fg(Color::Gray)),
+                Span::styled(format!("{label}: "), Style::default().fg(theme.label)),
                 Span::styled(display_value, field_style),
             ]));
         }
@@ -79,18 +80,18 @@ pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
         if let Some(err) = &state.validation_error {
             lines.push(Line::from(Span::styled(
                 format!("⚠ {err}"),
-                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
             )));
             lines.push(Line::from(""));
         }
 
-        lines.push(Line::from(Span::styled("Navigation:", Style::default().fg(Color::Gray))));
+        lines.push(Line::from(Span::styled("Navigation:", Style::default().fg(theme.label))));
         lines.push(Line::from(
             "  Tab/Shift-Tab/j/k: Next/Prev field  Left/Right/h/l: Move cursor/adjust",
         ));
         lines.push(Line::from("  Home/End: Jump to start/end of text"));
         lines.push(Line::from(""));
-        lines.push(Line::from(Span::styled("Edit:", Style::default().fg(Color::Gray))));
+        lines.push(Line::from(Span::styled("Edit:", Style::default().fg(theme.label))));
         lines.push(Line::from(
             "  Type: edit text  +/- [ ]: adjust numbers  C: cycle type  Space: toggle",
         ));
@@ -0,0 +1,60 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use crate::tui::state::AppState;
+
+pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(35),
+            Constraint::Percentage(30),
+            Constraint::Percentage(35),
+        ])
+        .split(size);
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(20),
+            Constraint::Percentage(60),
+            Constraint::Percentage(20),
+        ])
+        .split(vchunks[1]);
+
+    let area = hchunks[1];
+
+    let data = state.export_data();
+    let input = state.export_path.as_deref().unwrap_or_default();
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("Export {} {}", data.len(), data.name()),
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Path: ", Style::default().fg(theme.label)),
+            Span::styled(format!("{input}|"), Style::default().fg(theme.warning)),
+        ]),
+        Line::from(""),
+    ];
+    if let Some(err) = &state.validation_error {
+        lines.push(Line::from(Span::styled(
+            format!("⚠ {err}"),
+            Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from("The extension picks the format: .csv or .json"));
+    lines.push(Line::from("Enter: Export  Esc: Cancel"));
+
+    let popup = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Export"));
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
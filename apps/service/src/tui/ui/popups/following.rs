@@ -0,0 +1,96 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table};
+use std::time::SystemTime;
+
+use crate::tui::state::AppState;
+use crate::tui::ui::network::format_duration;
+use crate::tui::ui::popups::distributed::status_color;
+
+pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(15),
+            Constraint::Percentage(70),
+            Constraint::Percentage(15),
+        ])
+        .split(size);
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(15),
+            Constraint::Percentage(70),
+            Constraint::Percentage(15),
+        ])
+        .split(vchunks[1]);
+
+    let area = hchunks[1];
+    let now = SystemTime::now();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Following - peer results, last 15m - R: Refresh  Esc/Q: Close");
+    let inner = block.inner(area);
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    if state.follows.is_empty() {
+        f.render_widget(
+            Paragraph::new(
+                "Not following any hosts. Add one with `uppe-service follow add --host`.",
+            )
+            .style(Style::default().fg(theme.muted)),
+            inner,
+        );
+        return;
+    }
+
+    let rows: Vec<Row> = state
+        .follows
+        .iter()
+        .map(|(follow, summary)| {
+            let status = summary.status();
+            let latency = summary
+                .avg_latency_ms
+                .map(|ms| format!("{ms}ms"))
+                .unwrap_or_else(|| "-".to_string());
+            let last = summary
+                .last_result_at
+                .map(|at| {
+                    format!("{} ago", format_duration(now.duration_since(at).unwrap_or_default()))
+                })
+                .unwrap_or_else(|| "never".to_string());
+            Row::new(vec![
+                Cell::from(follow.host.clone()),
+                Cell::from(status.to_string())
+                    .style(Style::default().fg(status_color(theme, status))),
+                Cell::from(summary.summary()),
+                Cell::from(latency),
+                Cell::from(last),
+            ])
+        })
+        .collect();
+
+    f.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Min(24),
+                Constraint::Length(10),
+                Constraint::Length(24),
+                Constraint::Length(10),
+                Constraint::Length(14),
+            ],
+        )
+        .header(
+            Row::new(vec!["Host", "Status", "Peers", "Latency", "Last Result"])
+                .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        ),
+        inner,
+    );
+}
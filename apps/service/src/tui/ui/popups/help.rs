@@ -1,10 +1,11 @@
+use crate::tui::theme::Theme;
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 
-pub fn render(f: &mut Frame, size: Rect) {
+pub fn render(f: &mut Frame, size: Rect, theme: &Theme) {
     let vchunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -28,41 +29,66 @@ pub fn render(f: &mut Frame, size: Rect) {
     let help_lines = vec![
         Line::from(Span::styled(
             "Keybinds",
-            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
         )),
         Line::from(""),
-        Line::from(Span::styled("Navigation:", Style::default().fg(Color::Yellow))),
+        Line::from(Span::styled("Navigation:", Style::default().fg(theme.warning))),
         Line::from("  Up/Down, k/j      - Navigate in focused pane"),
         Line::from("  Tab / Shift-Tab   - Cycle focus (Monitors → Results → Stats → Network)"),
         Line::from("  Left/Right, h/l   - Jump focus (Monitors ↔ Results)"),
         Line::from("  g/Home            - Jump to first"),
         Line::from("  G/End             - Jump to last"),
+        Line::from(
+            "  /                 - Search monitors, #tag for a tag (Enter keeps, Esc clears)",
+        ),
+        Line::from("  S                 - Sort monitors (added/name/status/latency/last check)"),
         Line::from(""),
-        Line::from(Span::styled("Actions:", Style::default().fg(Color::Yellow))),
+        Line::from(Span::styled("Actions:", Style::default().fg(theme.warning))),
         Line::from("  A                 - Add monitor"),
         Line::from("  E                 - Edit selected monitor"),
         Line::from("  D                 - Delete selected monitor"),
-        Line::from("  Space/T           - Toggle enabled (Monitors list)"),
-        Line::from("  Enter             - View result details (Results list)"),
+        Line::from("  T                 - Toggle enabled (Monitors list)"),
+        Line::from("  #                 - Edit tags of selected monitor"),
+        Line::from("  Space / *         - Mark selected monitor / mark all listed"),
+        Line::from("  P / U / D / #     - Pause / enable / delete / re-tag marked monitors"),
+        Line::from("  Enter             - View result details (Results) or peer details (Network)"),
+        Line::from(
+            "  B / P             - Ban peer / probe selected monitor from it (peer details)",
+        ),
+        Line::from("  Shift-A           - Acknowledge down monitors and open incidents (banner)"),
         Line::from("  R                 - Refresh data"),
-        Line::from("  F                 - Toggle auto-refresh"),
+        Line::from("  F                 - Toggle auto-refresh (live while the service runs)"),
+        Line::from("  Shift-Z           - Zoom history charts (1h / 24h / 7d)"),
+        Line::from("  Z                 - Zoom focused pane to full grid (again to restore)"),
+        Line::from("  V                 - Layout presets (grid / monitors / network)"),
+        Line::from("  Shift-L           - View audit log"),
+        Line::from("  Shift-N           - Notification routing (channels, escalation, test)"),
+        Line::from("  Shift-C           - Distributed view (schedules, votes, our duties)"),
+        Line::from("  Shift-H           - Helper assignments (who we help / who helps us)"),
+        Line::from("  Shift-F           - Followed hosts (what peers see, without checking)"),
+        Line::from("  Shift-M           - Community domains (join / leave, who takes part)"),
+        Line::from(
+            "  Shift-E           - Export focused pane (monitors/results/peers) to .csv or .json",
+        ),
+        Line::from("  Shift-K           - DHT debugging (get / publish records)"),
         Line::from(""),
-        Line::from(Span::styled("Panes:", Style::default().fg(Color::Yellow))),
+        Line::from(Span::styled("Panes:", Style::default().fg(theme.warning))),
         Line::from("  Top-Left    - Monitors list"),
-        Line::from("  Top-Right   - Recent results with latency & location"),
-        Line::from("  Bottom-Left - Statistics (uptime, success, latency)"),
-        Line::from("  Bottom-Right- Network & P2P (peers, bandwidth, score)"),
+        Line::from("  Top-Right   - Latency & up/down history, recent results"),
+        Line::from("  Bottom-Left - Statistics (uptime, daily uptime bar, tag groups)"),
+        Line::from("  Bottom-Right- Network & P2P (stats, known peers)"),
+        Line::from("  Drag the borders between panes with the mouse to resize them"),
         Line::from(""),
-        Line::from(Span::styled("General:", Style::default().fg(Color::Yellow))),
+        Line::from(Span::styled("General:", Style::default().fg(theme.warning))),
         Line::from("  ?                 - Toggle help"),
         Line::from("  Q / Esc           - Quit"),
         Line::from(""),
-        Line::from(Span::styled("Edit Form:", Style::default().fg(Color::Gray))),
+        Line::from(Span::styled("Edit Form:", Style::default().fg(theme.label))),
         Line::from("  Tab/↑/↓           - Navigate fields"),
         Line::from("  Enter             - Save monitor"),
         Line::from("  Esc/C             - Cancel"),
         Line::from(""),
-        Line::from(Span::styled("Tips:", Style::default().fg(Color::Gray))),
+        Line::from(Span::styled("Tips:", Style::default().fg(theme.label))),
         Line::from("  - All 4 panes visible at once"),
         Line::from("  - Mouse clicks supported"),
         Line::from("  - Use Tab to cycle through panes"),
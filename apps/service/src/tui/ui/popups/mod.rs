@@ -1,4 +1,15 @@
+pub mod assignments;
+pub mod audit;
+pub mod bulk;
+pub mod community;
 pub mod delete;
+pub mod dht;
+pub mod distributed;
 pub mod edit;
+pub mod export;
+pub mod following;
 pub mod help;
+pub mod notifications;
+pub mod peer_detail;
 pub mod result_detail;
+pub mod tags;
@@ -0,0 +1,163 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table};
+
+use crate::database::models::RouteScope;
+use crate::tui::state::AppState;
+
+pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(15),
+            Constraint::Percentage(70),
+            Constraint::Percentage(15),
+        ])
+        .split(size);
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(10),
+            Constraint::Percentage(80),
+            Constraint::Percentage(10),
+        ])
+        .split(vchunks[1]);
+
+    let area = hchunks[1];
+    let monitor = state.monitors.get(state.selected);
+
+    let rows: Vec<Row> = state
+        .notification_channels
+        .iter()
+        .enumerate()
+        .map(|(i, channel)| {
+            let own = state.monitor_route.as_ref();
+            let alerted = mark(
+                own.is_some_and(|route| route.channels.contains(&channel.uuid)),
+                state.routing.channels.contains(&channel.uuid),
+            );
+            let escalated = mark(
+                own.and_then(|route| route.escalation.as_ref())
+                    .is_some_and(|policy| policy.channels.contains(&channel.uuid)),
+                state
+                    .routing
+                    .escalation
+                    .as_ref()
+                    .is_some_and(|policy| policy.channels.contains(&channel.uuid)),
+            );
+            let test = channel.test_summary();
+            let test_color = match (&channel.last_test_at, &channel.last_test_error) {
+                (None, _) => theme.muted,
+                (Some(_), None) => theme.success,
+                (Some(_), Some(_)) => theme.error,
+            };
+
+            let mut row = Row::new(vec![
+                Cell::from(alerted),
+                Cell::from(escalated),
+                Cell::from(channel.name.clone()),
+                Cell::from(channel.kind.to_string()),
+                Cell::from(channel.target.clone()),
+                Cell::from(test).style(Style::default().fg(test_color)),
+            ]);
+            if !channel.enabled {
+                row = row.style(Style::default().fg(theme.muted));
+            }
+
+            if i == state.selected_channel {
+                row = row.style(Style::default().add_modifier(Modifier::REVERSED));
+            }
+
+            row
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(5),
+        Constraint::Length(8),
+        Constraint::Length(20),
+        Constraint::Length(8),
+        Constraint::Min(20),
+        Constraint::Length(24),
+    ];
+
+    let title = match monitor {
+        Some(monitor) => format!(
+            "Notification Routing for '{}' - Space: Alert  E: Escalate  +/-: Delay  I: Inherit  \
+             T: Test  Esc/Q: Close",
+            monitor.name
+        ),
+        None => "Notification Channels - T: Test  Esc/Q: Close".to_string(),
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    let header = Row::new(vec![
+        Cell::from("Alert"),
+        Cell::from("Escalate"),
+        Cell::from("Name"),
+        Cell::from("Kind"),
+        Cell::from("Target"),
+        Cell::from("Last test"),
+    ])
+    .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD));
+    f.render_widget(Table::new(rows, widths).header(header), chunks[0]);
+
+    let status = match &state.notification_status {
+        Some(status) => Paragraph::new(status.clone()).style(Style::default().fg(theme.warning)),
+        None if state.notification_channels.is_empty() => Paragraph::new(
+            "No channels yet - add one with `uppe notify add --name <name> --url <url>`",
+        )
+        .style(Style::default().fg(theme.muted)),
+        None if monitor.is_some() => {
+            Paragraph::new(routing_summary(state)).style(Style::default().fg(theme.muted))
+        }
+        None => Paragraph::new(""),
+    };
+    f.render_widget(status, chunks[1]);
+}
+
+/// `[x]` for the monitor's own choice, `(x)` for one inherited from a tag or
+/// the default route
+fn mark(own: bool, applies: bool) -> &'static str {
+    match (own, applies) {
+        (true, _) => "[x]",
+        (false, true) => "(x)",
+        (false, false) => "[ ]",
+    }
+}
+
+/// Where the selected monitor's channels and escalation policy come from
+fn routing_summary(state: &AppState) -> String {
+    let from = |scope: &Option<RouteScope>| match scope {
+        Some(RouteScope::Monitor(_)) => "this monitor".to_string(),
+        Some(RouteScope::Tag(tag)) => format!("tag '{tag}'"),
+        Some(RouteScope::Default) => "the default route".to_string(),
+        None => "nowhere".to_string(),
+    };
+    let routing = &state.routing;
+    let channels = match &routing.channels_from {
+        Some(_) => format!("Channels from {}", from(&routing.channels_from)),
+        None => "No channels routed".to_string(),
+    };
+    let escalation = match &routing.escalation {
+        Some(policy) => format!(
+            "escalates after {} minutes (from {})",
+            policy.after_secs / 60,
+            from(&routing.escalation_from)
+        ),
+        None => "no escalation".to_string(),
+    };
+    format!("{channels}; {escalation}. [x] own  (x) inherited")
+}
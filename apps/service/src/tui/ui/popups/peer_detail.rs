@@ -0,0 +1,212 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table};
+use std::time::SystemTime;
+
+use crate::monitoring::types::MonitorStatus;
+use crate::tui::state::AppState;
+use crate::tui::theme::Theme;
+use crate::tui::ui::network::format_duration;
+use crate::tui::ui::results::format_time;
+
+/// Clock skew beyond which the peer's clock is likely off, in seconds
+const SKEW_WARNING_SECS: i64 = 30;
+
+fn label<'a>(theme: &Theme, text: &'a str) -> Span<'a> {
+    Span::styled(format!("{text:<14}"), Style::default().fg(theme.accent))
+}
+
+pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let Some(detail) = &state.peer_detail else {
+        return;
+    };
+    let peer = &detail.peer;
+
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(10),
+            Constraint::Percentage(80),
+            Constraint::Percentage(10),
+        ])
+        .split(size);
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(10),
+            Constraint::Percentage(80),
+            Constraint::Percentage(10),
+        ])
+        .split(vchunks[1]);
+
+    let area = hchunks[1];
+    let now = SystemTime::now();
+
+    let status = match peer.banned_at {
+        Some(_) => Span::styled("banned", Style::default().fg(theme.error)),
+        None if peer.status == "online" => {
+            Span::styled("online", Style::default().fg(theme.success))
+        }
+        None => Span::styled(peer.status.clone(), Style::default().fg(theme.muted)),
+    };
+    let seen_ago = format_duration(now.duration_since(peer.last_seen).unwrap_or_default());
+    let location = [&peer.location_city, &peer.location_region, &peer.location_country]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut lines = vec![
+        Line::from(vec![label(theme, "Peer ID:"), Span::raw(peer.peer_id.clone())]),
+        Line::from(vec![
+            label(theme, "Status:"),
+            status,
+            Span::raw(format!("  (seen {seen_ago} ago)")),
+        ]),
+        Line::from(vec![
+            label(theme, "Location:"),
+            Span::raw(if location.is_empty() {
+                "Unknown".to_string()
+            } else {
+                location.join(", ")
+            }),
+        ]),
+        Line::from(vec![
+            label(theme, "Addresses:"),
+            Span::raw(if peer.addresses.is_empty() {
+                "None recorded".to_string()
+            } else {
+                peer.addresses.join("  ")
+            }),
+        ]),
+    ];
+
+    let trust = match detail.trust_score() {
+        Some(score) => {
+            let color = if score >= 95.0 {
+                theme.success
+            } else if score >= 75.0 {
+                theme.warning
+            } else {
+                theme.error
+            };
+            Span::styled(
+                format!(
+                    "{score:.0}% ({}/{} results verified)",
+                    detail.results_verified, detail.results_total
+                ),
+                Style::default().fg(color),
+            )
+        }
+        None => Span::styled("No results yet", Style::default().fg(theme.muted)),
+    };
+    lines.push(Line::from(vec![label(theme, "Trust:"), trust]));
+    if let Some(greylisted_at) = peer.greylisted_at {
+        let ago = format_duration(now.duration_since(greylisted_at).unwrap_or_default());
+        lines.push(Line::from(vec![
+            label(theme, "Greylisted:"),
+            Span::styled(
+                format!("{} times for flooding, last {ago} ago", peer.greylist_count),
+                Style::default().fg(theme.warning),
+            ),
+        ]));
+    }
+    lines.push(Line::from(vec![
+        label(theme, "Contribution:"),
+        Span::raw(format!(
+            "score {:.2}, {} checks/day, {:.1}% uptime",
+            peer.contribution_score, peer.checks_per_day, peer.uptime_percentage
+        )),
+    ]));
+
+    let skew = match detail.clock_skew_secs() {
+        Some(secs) if secs.abs() > SKEW_WARNING_SECS => {
+            Span::styled(format!("{secs:+}s (clock likely off)"), Style::default().fg(theme.error))
+        }
+        Some(secs) => Span::raw(format!("{secs:+}s (incl. delivery)")),
+        None => Span::styled("Unknown", Style::default().fg(theme.muted)),
+    };
+    lines.push(Line::from(vec![label(theme, "Clock skew:"), skew]));
+
+    let title = format!(
+        "Peer Details - B: {}  P: Probe selected monitor  Esc/Q: Close",
+        if peer.banned_at.is_some() { "Unban" } else { "Ban" }
+    );
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(lines.len() as u16 + 1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    f.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let monitor_name = |uuid: uuid::Uuid| {
+        state
+            .all_monitors
+            .iter()
+            .find(|monitor| monitor.uuid == uuid)
+            .map(|monitor| monitor.name.clone())
+            .unwrap_or_else(|| uuid.to_string().chars().take(8).collect())
+    };
+    let rows: Vec<Row> = detail
+        .results
+        .iter()
+        .map(|result| {
+            let color = match result.status {
+                MonitorStatus::Up => theme.success,
+                MonitorStatus::Down => theme.error,
+                MonitorStatus::Degraded => theme.warning,
+                MonitorStatus::Unknown => theme.muted,
+            };
+
+            Row::new(vec![
+                Cell::from(format_time(result.timestamp)),
+                Cell::from(monitor_name(result.monitor_uuid)),
+                Cell::from(result.status.to_string()).style(Style::default().fg(color)),
+                Cell::from(result.latency_ms.map(|ms| format!("{ms}ms")).unwrap_or_default()),
+                Cell::from(if result.verified { "✓" } else { "✗" }),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Min(16),
+        Constraint::Length(9),
+        Constraint::Length(8),
+        Constraint::Length(8),
+    ];
+    let header = Row::new(vec![
+        Cell::from("Time"),
+        Cell::from("Monitor"),
+        Cell::from("Status"),
+        Cell::from("Latency"),
+        Cell::from("Verified"),
+    ])
+    .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD));
+    f.render_widget(
+        Table::new(rows, widths)
+            .header(header)
+            .block(Block::default().borders(Borders::TOP).title("Recent Results")),
+        chunks[1],
+    );
+
+    let status = match &state.peer_status {
+        Some(status) => Paragraph::new(status.clone()).style(Style::default().fg(theme.warning)),
+        None => Paragraph::new(""),
+    };
+    f.render_widget(status, chunks[2]);
+}
@@ -1,10 +1,11 @@
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table};
 
 use crate::tui::state::AppState;
+use crate::tui::ui::network::format_duration;
 
 /// Format location from city, country, and region.
 fn format_location(
@@ -32,21 +33,22 @@ fn format_location(
 }
 
 pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
     let vchunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Percentage(15),
-            Constraint::Percentage(70),
-            Constraint::Percentage(15),
+            Constraint::Percentage(10),
+            Constraint::Percentage(80),
+            Constraint::Percentage(10),
         ])
         .split(size);
 
     let hchunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Percentage(15),
-            Constraint::Percentage(70),
-            Constraint::Percentage(15),
+            Constraint::Percentage(10),
+            Constraint::Percentage(80),
+            Constraint::Percentage(10),
         ])
         .split(vchunks[1]);
 
@@ -55,10 +57,10 @@ pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
     if let Some(r) = state.results.get(state.selected_result) {
         let location = format_location(&r.city, &r.country, &r.region);
 
-        let lines = vec![
+        let mut lines = vec![
             Line::from(Span::styled(
                 "Result Details",
-                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
             )),
             Line::from(""),
             Line::from(format!("Status: {}", r.status)),
@@ -73,14 +75,97 @@ pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
             Line::from(format!("Location: {location}")),
             Line::from(format!("Error: {}", r.error_message.clone().unwrap_or_default())),
             Line::from(format!("Peer: {}", r.peer_id)),
-            Line::from(""),
-            Line::from("Esc/Q: Close"),
         ];
 
-        let popup =
-            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Details"));
+        let ours = r.status.is_available();
+        let disagrees = |status: crate::monitoring::types::MonitorStatus| matches!((ours, status.is_available()), (Some(a), Some(b)) if a != b);
+        let verified: Vec<_> = state.result_confirmations.iter().filter(|p| p.verified).collect();
+        let disagreeing = verified.iter().filter(|p| disagrees(p.status)).count();
+        let agreeing = verified
+            .iter()
+            .filter(|p| ours.is_some() && p.status.is_available() == ours)
+            .count();
+        let summary = if state.result_confirmations.is_empty() {
+            Span::styled("No peer results around this check", Style::default().fg(theme.muted))
+        } else if disagreeing > 0 {
+            Span::styled(
+                format!("{disagreeing} of {} verified peers disagree", verified.len()),
+                Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::styled(
+                format!("{agreeing} of {} verified peers agree", verified.len()),
+                Style::default().fg(theme.success),
+            )
+        };
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![Span::raw("Peer confirmations: "), summary]));
+
+        let block = Block::default().borders(Borders::ALL).title("Details - Esc/Q: Close");
+        let inner = block.inner(area);
 
         f.render_widget(Clear, area);
-        f.render_widget(popup, area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(lines.len() as u16 + 1), Constraint::Min(1)])
+            .split(inner);
+
+        f.render_widget(Paragraph::new(lines), chunks[0]);
+
+        let rows: Vec<Row> = state
+            .result_confirmations
+            .iter()
+            .map(|p| {
+                let offset = match p.timestamp.duration_since(r.timestamp) {
+                    Ok(after) => format!("+{}", format_duration(after)),
+                    Err(before) => format!("-{}", format_duration(before.duration())),
+                };
+                let style = if !p.verified {
+                    Style::default().fg(theme.muted)
+                } else if disagrees(p.status) {
+                    Style::default().fg(theme.error)
+                } else {
+                    Style::default()
+                };
+
+                Row::new(vec![
+                    Cell::from(p.peer_id.chars().take(16).collect::<String>()),
+                    Cell::from(format_location(&p.city, &p.country, &p.region)),
+                    Cell::from(p.status.to_string()),
+                    Cell::from(
+                        p.latency_ms.map(|ms| format!("{ms}ms")).unwrap_or_else(|| "-".into()),
+                    ),
+                    Cell::from(if p.verified { "✓" } else { "✗" }),
+                    Cell::from(offset),
+                ])
+                .style(style)
+            })
+            .collect();
+
+        let widths = [
+            Constraint::Length(17),
+            Constraint::Min(16),
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ];
+        let header = Row::new(vec![
+            Cell::from("Peer"),
+            Cell::from("Location"),
+            Cell::from("Status"),
+            Cell::from("Latency"),
+            Cell::from("Verified"),
+            Cell::from("Offset"),
+        ])
+        .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD));
+        f.render_widget(
+            Table::new(rows, widths)
+                .header(header)
+                .block(Block::default().borders(Borders::TOP).title("Peer Results")),
+            chunks[1],
+        );
     }
 }
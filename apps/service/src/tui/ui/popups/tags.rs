@@ -0,0 +1,65 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use crate::tui::state::AppState;
+
+pub fn render(f: &mut Frame, size: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(35),
+            Constraint::Percentage(30),
+            Constraint::Percentage(35),
+        ])
+        .split(size);
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(25),
+            Constraint::Percentage(50),
+            Constraint::Percentage(25),
+        ])
+        .split(vchunks[1]);
+
+    let area = hchunks[1];
+
+    let title = if state.marked.is_empty() {
+        let name = state.monitors.get(state.selected).map(|m| m.name.clone()).unwrap_or_default();
+        format!("Tags of '{name}'")
+    } else {
+        format!("Replace tags of {} marked monitors", state.marked.len())
+    };
+    let input = state.tag_input.as_deref().unwrap_or_default();
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            title,
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Tags: ", Style::default().fg(theme.label)),
+            Span::styled(format!("{input}|"), Style::default().fg(theme.warning)),
+        ]),
+        Line::from(""),
+    ];
+    if let Some(err) = &state.validation_error {
+        lines.push(Line::from(Span::styled(
+            format!("⚠ {err}"),
+            Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from("Comma or space separated, e.g. prod, eu-west"));
+    lines.push(Line::from("Enter: Save  Esc: Cancel"));
+
+    let popup = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Tags"));
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
@@ -1,17 +1,21 @@
 use ratatui::Frame;
-use ratatui::layout::{Constraint, Rect};
-use ratatui::style::{Color, Modifier, Style};
-use ratatui::widgets::{Block, Borders, Cell, Clear, Row, Table};
-use std::time::SystemTime;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Sparkline, Table};
+use std::ops::Range;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::database::models::ResultRollup;
 use crate::tui::state::AppState;
-use crate::tui::types::Focus;
+use crate::tui::types::{Focus, HistoryRange};
+
+/// Rows taken by the history charts, including borders
+pub const HISTORY_HEIGHT: u16 = 5;
 
 /// Format SystemTime as HH:MM:SS in UTC timezone.
 /// Returns time in UTC (Coordinated Universal Time) format.
-fn format_time(time: SystemTime) -> String {
-    use std::time::UNIX_EPOCH;
-
+pub fn format_time(time: SystemTime) -> String {
     let duration = time.duration_since(UNIX_EPOCH).unwrap_or_default();
 
     let total_secs = duration.as_secs();
@@ -44,7 +48,112 @@ fn format_location(
     }
 }
 
+/// Rollups placed in their bucket of the range ending now, oldest first
+fn history_slots(rollups: &[ResultRollup], range: HistoryRange) -> Vec<Option<&ResultRollup>> {
+    let bucket = range.bucket_seconds();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let first = (now / bucket + 1).saturating_sub(HistoryRange::BUCKETS) * bucket;
+
+    let mut slots = vec![None; HistoryRange::BUCKETS as usize];
+    for rollup in rollups {
+        let start = rollup.bucket_start.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        if let Some(slot) = start
+            .checked_sub(first)
+            .and_then(|offset| slots.get_mut((offset / bucket) as usize))
+        {
+            *slot = Some(rollup);
+        }
+    }
+    slots
+}
+
+/// Slots each of `width` columns covers; columns repeat slots when wider
+/// than the range and merge them when narrower
+fn column_slots(slots: usize, width: usize) -> Vec<Range<usize>> {
+    (0..width)
+        .map(|column| {
+            let start = column * slots / width;
+            let end = ((column + 1) * slots / width).max(start + 1);
+            start..end
+        })
+        .collect()
+}
+
+/// Latency sparkline and up/down timeline for the selected monitor
+fn render_history(f: &mut Frame, area: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let slots = history_slots(&state.rollups, state.history_range);
+    let rollups = || slots.iter().flatten();
+
+    let mut title = format!("History {}", state.history_range.label());
+    let (latency_sum, latency_checks) = rollups()
+        .filter_map(|r| r.avg_latency_ms.map(|avg| (avg * r.checks, r.checks)))
+        .fold((0, 0), |(sum, checks), (s, c)| (sum + s, checks + c));
+    if latency_checks > 0 {
+        title.push_str(&format!(" - avg {}ms", latency_sum / latency_checks));
+    }
+    if let Some(max) = rollups().filter_map(|r| r.max_latency_ms).max() {
+        title.push_str(&format!(", max {max}ms"));
+    }
+    title.push_str(" (z: zoom)");
+
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    if inner.height < 2 || inner.width == 0 {
+        return;
+    }
+
+    let columns = column_slots(slots.len(), inner.width as usize);
+    // Each column shows the highest average latency of its buckets
+    let latency: Vec<u64> = columns
+        .iter()
+        .map(|range| {
+            slots[range.clone()]
+                .iter()
+                .flatten()
+                .filter_map(|r| r.avg_latency_ms)
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let timeline: Vec<Span> = columns
+        .iter()
+        .map(|range| {
+            let (up, down) = slots[range.clone()]
+                .iter()
+                .flatten()
+                .fold((0, 0), |(up, down), r| (up + r.up, down + r.down));
+            match (up, down) {
+                (0, 0) => Span::styled("·", Style::default().fg(theme.muted)),
+                (_, 0) => Span::styled("█", Style::default().fg(theme.success)),
+                (0, _) => Span::styled("█", Style::default().fg(theme.error)),
+                _ => Span::styled("█", Style::default().fg(theme.warning)),
+            }
+        })
+        .collect();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+    f.render_widget(
+        Sparkline::default().data(&latency).style(Style::default().fg(theme.accent)),
+        chunks[0],
+    );
+    f.render_widget(Paragraph::new(Line::from(timeline)), chunks[1]);
+}
+
 pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
+    let theme = &state.theme;
+    f.render_widget(Clear, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(HISTORY_HEIGHT), Constraint::Min(3)])
+        .split(area);
+    render_history(f, chunks[0], state);
+
     let rows: Vec<Row> = state
         .results
         .iter()
@@ -52,9 +161,14 @@ pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
         .map(|(i, r)| {
             let location = format_location(&r.city, &r.country, &r.region);
 
+            let status = if r.maintenance {
+                Cell::from(format!("{} (m)", r.status)).style(Style::default().fg(theme.accent))
+            } else {
+                Cell::from(format!("{}", r.status))
+            };
             let mut row = Row::new(vec![
                 Cell::from(format_time(r.timestamp)),
-                Cell::from(format!("{}", r.status)),
+                status,
                 Cell::from(r.latency_ms.map(|v| v.to_string()).unwrap_or_else(|| "-".into())),
                 Cell::from(r.status_code.map(|v| v.to_string()).unwrap_or_else(|| "-".into())),
                 Cell::from(location),
@@ -62,7 +176,7 @@ pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
             ]);
 
             if i == state.selected_result {
-                row = row.style(Style::default().fg(Color::Yellow));
+                row = row.style(Style::default().fg(theme.warning));
             }
 
             row
@@ -71,15 +185,24 @@ pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
 
     let widths = [
         Constraint::Length(10),
-        Constraint::Length(10),
+        Constraint::Length(12),
         Constraint::Length(12),
         Constraint::Length(6),
         Constraint::Length(15),
         Constraint::Min(10),
     ];
 
-    let results_title =
-        if state.focus == Focus::Results { "Recent Results (focused)" } else { "Recent Results" };
+    let mut results_title = if state.focus == Focus::Results {
+        "Recent Results (focused)".to_string()
+    } else {
+        "Recent Results".to_string()
+    };
+    if state.results.iter().any(|r| r.maintenance) {
+        results_title.push_str(" - (m) maintenance");
+    }
+    if let Some(agreement) = &state.agreement {
+        results_title.push_str(&format!(" - {}", agreement.summary()));
+    }
 
     let table = Table::new(rows, widths)
         .header(
@@ -91,10 +214,9 @@ pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
                 Cell::from("Location"),
                 Cell::from("Error"),
             ])
-            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
         )
         .block(Block::default().borders(Borders::ALL).title(results_title));
 
-    f.render_widget(Clear, area);
-    f.render_widget(table, area);
+    f.render_widget(table, chunks[1]);
 }
@@ -4,13 +4,74 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph};
 
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::database::models::ResultRollup;
 use crate::tui::state::AppState;
+use crate::tui::theme::Theme;
+
+/// Days covered by the uptime bar
+pub const UPTIME_DAYS: u64 = 90;
+/// Bucket width of the daily rollups
+pub const DAY_SECONDS: u64 = 24 * 60 * 60;
+
+/// Color of a day in the uptime bar
+fn uptime_color(theme: &Theme, uptime: f64) -> Color {
+    match uptime {
+        u if u >= 99.9 => theme.success,
+        u if u >= 99.0 => theme.success_dim,
+        u if u >= 95.0 => theme.warning,
+        u if u >= 80.0 => theme.error_dim,
+        _ => theme.error,
+    }
+}
+
+/// Status-page style bar with one cell per day, today last; days that don't
+/// fit in `width` are dropped from the front
+fn uptime_bar(theme: &Theme, rollups: &[ResultRollup], width: usize) -> Vec<Line<'static>> {
+    let today =
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / DAY_SECONDS;
+    let days = (UPTIME_DAYS as usize).min(width).max(1) as u64;
+    let first = today + 1 - days;
+
+    let mut slots = vec![None; days as usize];
+    for rollup in rollups {
+        let day = rollup.bucket_start.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+            / DAY_SECONDS;
+        if let Some(slot) = day.checked_sub(first).and_then(|i| slots.get_mut(i as usize)) {
+            *slot = Some(rollup);
+        }
+    }
+
+    let (up, checks) = slots
+        .iter()
+        .flatten()
+        .fold((0, 0), |(up, checks), r| (up + r.up, checks + r.checks));
+    let summary = if checks > 0 {
+        format!("  {days}d uptime: {:.2}%", up as f64 * 100.0 / checks as f64)
+    } else {
+        format!("  {days}d uptime: no data")
+    };
+
+    let cells: Vec<Span> = std::iter::once(Span::raw("  "))
+        .chain(slots.iter().map(|slot| match slot {
+            Some(r) if r.checks > 0 => Span::styled(
+                "▮",
+                Style::default().fg(uptime_color(theme, r.up as f64 * 100.0 / r.checks as f64)),
+            ),
+            _ => Span::styled("▮", Style::default().fg(theme.muted)),
+        }))
+        .collect();
+
+    vec![Line::from(summary), Line::from(cells)]
+}
 
 pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
+    let theme = &state.theme;
     let focus_style = if state.focus == crate::tui::types::Focus::Stats {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        Style::default().fg(theme.warning).add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::Cyan)
+        Style::default().fg(theme.accent)
     };
 
     let title = if state.focus == crate::tui::types::Focus::Stats {
@@ -29,21 +90,43 @@ pub fn render(f: &mut Frame, area: Rect, state: &AppState) {
         let truncated_name: String = monitor.name.chars().take(20).collect();
         lines.push(Line::from(Span::styled(
             format!("Monitor: {truncated_name}"),
-            Style::default().fg(Color::Yellow),
+            Style::default().fg(theme.warning),
         )));
         lines.push(Line::from(format!("  Uptime:  {uptime:.1}%")));
         lines.push(Line::from(format!("  Success: {success_count} / {total_checks}")));
         lines.push(Line::from(format!("  Latency: {avg_latency} ms")));
+        lines.extend(uptime_bar(
+            theme,
+            &state.daily_rollups,
+            area.width.saturating_sub(4) as usize,
+        ));
     } else {
         lines.push(Line::from("No monitor selected"));
     }
 
     lines.push(Line::from(""));
-    lines.push(Line::from(Span::styled("Global Stats", Style::default().fg(Color::Yellow))));
+    lines.push(Line::from(Span::styled("Global Stats", Style::default().fg(theme.warning))));
     lines.push(Line::from(format!("  Total:  {total_monitors}")));
     lines.push(Line::from(format!("  Online: {online_monitors}")));
     lines.push(Line::from(format!("  Avg:    {global_uptime:.1}%")));
 
+    let groups = state.tag_groups();
+    if !groups.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Groups", Style::default().fg(theme.warning))));
+        for (tag, up, total) in groups {
+            let color = match up {
+                up if up == total => theme.success,
+                0 => theme.error,
+                _ => theme.warning,
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("  {tag}: ")),
+                Span::styled(format!("{up}/{total} up"), Style::default().fg(color)),
+            ]));
+        }
+    }
+
     let widget = Paragraph::new(lines)
         .block(Block::default().borders(Borders::ALL).title(title).border_style(focus_style));
 
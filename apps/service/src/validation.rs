@@ -1,4 +1,5 @@
 use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
 use std::net::{IpAddr, ToSocketAddrs};
 use url::Url;
 
@@ -149,6 +150,34 @@ pub fn validate_icmp_endpoint(target: &str) -> ValidationResult {
     }
 }
 
+/// Validate certificate expiry endpoint (https URL, host:port or host)
+pub fn validate_cert_expiry_endpoint(target: &str) -> ValidationResult {
+    if target.contains("://") {
+        validate_https_endpoint(target)
+    } else if target.contains(':') && target.parse::<IpAddr>().is_err() {
+        validate_tcp_endpoint(target)
+    } else {
+        validate_icmp_endpoint(target)
+    }
+}
+
+/// Validate heartbeat token, the secret part of the URL a heartbeat pings
+pub fn validate_heartbeat_token(target: &str) -> ValidationResult {
+    if target.len() < 16 {
+        return ValidationResult::err("Heartbeat token too short (min 16 characters)");
+    }
+
+    if target.len() > 64 {
+        return ValidationResult::err("Heartbeat token too long (max 64 characters)");
+    }
+
+    if target.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        ValidationResult::ok()
+    } else {
+        ValidationResult::err("Heartbeat token may only contain letters, digits, '-' and '_'")
+    }
+}
+
 /// Validate monitor target based on check type
 pub fn validate_monitor_target(target: &str, check_type: &str) -> ValidationResult {
     match check_type.to_lowercase().as_str() {
@@ -156,6 +185,8 @@ pub fn validate_monitor_target(target: &str, check_type: &str) -> ValidationResu
         "https" => validate_https_endpoint(target),
         "tcp" => validate_tcp_endpoint(target),
         "icmp" => validate_icmp_endpoint(target),
+        "cert_expiry" => validate_cert_expiry_endpoint(target),
+        "heartbeat" => validate_heartbeat_token(target),
         _ => ValidationResult::err(format!("Unknown check type: {check_type}")),
     }
 }
@@ -188,6 +219,15 @@ pub fn validate_interval(interval: u64) -> ValidationResult {
     ValidationResult::ok()
 }
 
+/// Validate how late a heartbeat may be
+pub fn validate_grace(grace: u64) -> ValidationResult {
+    if grace > 86400 {
+        return ValidationResult::err("Grace period too long (max 24 hours)");
+    }
+
+    ValidationResult::ok()
+}
+
 /// Validate monitor timeout
 pub fn validate_timeout(timeout: u64, interval: u64) -> ValidationResult {
     if timeout == 0 {
@@ -201,6 +241,100 @@ pub fn validate_timeout(timeout: u64, interval: u64) -> ValidationResult {
     ValidationResult::ok()
 }
 
+/// Validate monitor tags, as returned by `Monitor::parse_tags`
+pub fn validate_tags(tags: &[String]) -> ValidationResult {
+    if tags.len() > 10 {
+        return ValidationResult::err("Too many tags (max 10)");
+    }
+
+    for tag in tags {
+        if tag.len() > 32 {
+            return ValidationResult::err(format!("Tag '{tag}' too long (max 32 characters)"));
+        }
+        if !tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+        {
+            return ValidationResult::err(format!(
+                "Tag '{tag}' may only contain letters, digits, '-', '_', '.' and ':'"
+            ));
+        }
+    }
+
+    ValidationResult::ok()
+}
+
+/// Validate expected HTTP status codes: single codes ("200") or inclusive
+/// ranges ("200-299") between 100 and 599
+pub fn validate_expected_status_codes(codes: &[String]) -> ValidationResult {
+    if codes.len() > 20 {
+        return ValidationResult::err("Too many expected status codes (max 20)");
+    }
+
+    for code in codes {
+        let (start, end) = code.split_once('-').unwrap_or((code, code));
+        let valid = match (start.trim().parse::<u16>(), end.trim().parse::<u16>()) {
+            (Ok(start), Ok(end)) => (100..=599).contains(&start) && (start..=599).contains(&end),
+            _ => false,
+        };
+        if !valid {
+            return ValidationResult::err(format!(
+                "Invalid status code '{code}' (expected e.g. 200 or 200-299)"
+            ));
+        }
+    }
+
+    ValidationResult::ok()
+}
+
+/// Validate HTTP check headers
+pub fn validate_headers(headers: &BTreeMap<String, String>) -> ValidationResult {
+    if headers.len() > 20 {
+        return ValidationResult::err("Too many headers (max 20)");
+    }
+
+    for (name, value) in headers {
+        if name.is_empty()
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+        {
+            return ValidationResult::err(format!("Invalid header name '{name}'"));
+        }
+        if value.len() > 1024 {
+            return ValidationResult::err(format!("Header '{name}' too long (max 1024 bytes)"));
+        }
+        if value.chars().any(|c| c.is_control() && c != '\t') {
+            return ValidationResult::err(format!("Header '{name}' contains control characters"));
+        }
+    }
+
+    ValidationResult::ok()
+}
+
+/// Largest DHT debug record value, well below the Kademlia record size limit
+pub const MAX_DHT_VALUE_BYTES: usize = 16 * 1024;
+
+/// Validate a DHT debug record key and value; the key is namespaced under the
+/// debug prefix when published
+pub fn validate_dht_record(key: &str, value: &str) -> ValidationResult {
+    if key.is_empty() {
+        return ValidationResult::err("Key cannot be empty");
+    }
+    if key.len() > 128 {
+        return ValidationResult::err("Key too long (max 128 characters)");
+    }
+    if key.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return ValidationResult::err("Key cannot contain whitespace");
+    }
+    if value.len() > MAX_DHT_VALUE_BYTES {
+        return ValidationResult::err(format!(
+            "Value too large ({} bytes, max {MAX_DHT_VALUE_BYTES})",
+            value.len()
+        ));
+    }
+
+    ValidationResult::ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -246,6 +380,28 @@ mod tests {
         assert!(!validate_icmp_endpoint("invalid hostname").is_valid);
     }
 
+    #[test]
+    fn test_cert_expiry_validation() {
+        assert!(validate_cert_expiry_endpoint("https://example.com").is_valid);
+        assert!(validate_cert_expiry_endpoint("example.com:8443").is_valid);
+        assert!(validate_cert_expiry_endpoint("example.com").is_valid);
+        assert!(validate_cert_expiry_endpoint("2001:db8::1").is_valid);
+
+        assert!(!validate_cert_expiry_endpoint("http://example.com").is_valid);
+        assert!(!validate_cert_expiry_endpoint("example.com:abc").is_valid);
+        assert!(!validate_cert_expiry_endpoint("").is_valid);
+    }
+
+    #[test]
+    fn test_heartbeat_token_validation() {
+        assert!(validate_heartbeat_token("0123456789abcdef").is_valid);
+        assert!(validate_monitor_target("nightly-backup_job-7f3a", "heartbeat").is_valid);
+
+        assert!(!validate_heartbeat_token("short").is_valid);
+        assert!(!validate_heartbeat_token("0123456789abcdef/../x").is_valid);
+        assert!(!validate_heartbeat_token(&"a".repeat(65)).is_valid);
+    }
+
     #[test]
     fn test_name_validation() {
         assert!(validate_monitor_name("My Monitor").is_valid);
@@ -262,4 +418,47 @@ mod tests {
         assert!(!validate_timeout(15, 10).is_valid);
         assert!(!validate_timeout(0, 10).is_valid);
     }
+
+    #[test]
+    fn test_tags_validation() {
+        use crate::database::models::Monitor;
+
+        let tags = Monitor::parse_tags("Prod, #eu-west prod  db:primary");
+        assert_eq!(tags, ["prod", "eu-west", "db:primary"]);
+        assert!(validate_tags(&tags).is_valid);
+        assert!(Monitor::parse_tags(" , ").is_empty());
+
+        assert!(!validate_tags(&Monitor::parse_tags("a/b")).is_valid);
+        assert!(!validate_tags(&["x".repeat(33)]).is_valid);
+        let many: Vec<String> = (0..11).map(|i| format!("t{i}")).collect();
+        assert!(!validate_tags(&many).is_valid);
+    }
+
+    #[test]
+    fn test_expected_status_codes_validation() {
+        let codes = |codes: &[&str]| codes.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        assert!(validate_expected_status_codes(&codes(&["200", "301-308"])).is_valid);
+        assert!(validate_expected_status_codes(&[]).is_valid);
+        assert!(!validate_expected_status_codes(&codes(&["600"])).is_valid);
+        assert!(!validate_expected_status_codes(&codes(&["299-200"])).is_valid);
+        assert!(!validate_expected_status_codes(&codes(&["2xx"])).is_valid);
+    }
+
+    #[test]
+    fn test_headers_validation() {
+        let headers = |name: &str, value: &str| BTreeMap::from([(name.into(), value.into())]);
+        assert!(validate_headers(&headers("Authorization", "Bearer abc")).is_valid);
+        assert!(!validate_headers(&headers("Bad Name", "x")).is_valid);
+        assert!(!validate_headers(&headers("X-Test", "a\r\nInjected: 1")).is_valid);
+    }
+
+    #[test]
+    fn test_dht_record_validation() {
+        assert!(validate_dht_record("seed/bootstrap", "hello").is_valid);
+        assert!(validate_dht_record("k", "").is_valid);
+        assert!(!validate_dht_record("", "value").is_valid);
+        assert!(!validate_dht_record("two words", "value").is_valid);
+        assert!(!validate_dht_record(&"k".repeat(129), "value").is_valid);
+        assert!(!validate_dht_record("k", &"v".repeat(MAX_DHT_VALUE_BYTES + 1)).is_valid);
+    }
 }
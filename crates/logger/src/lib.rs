@@ -1,3 +1,9 @@
+#[cfg(feature = "otlp")]
+pub mod otlp;
 pub mod tracing;
 
+#[cfg(feature = "otlp")]
+pub use otlp::{OtlpConfig, OtlpGuard};
 pub use tracing::init as init_tracing;
+#[cfg(feature = "otlp")]
+pub use tracing::init_with_otlp;
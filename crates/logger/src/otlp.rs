@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use opentelemetry::{global, trace::TracerProvider as _};
+use opentelemetry_otlp::{ExporterBuildError, MetricExporter, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    Resource,
+    metrics::{PeriodicReader, SdkMeterProvider},
+    trace::SdkTracerProvider,
+};
+use tracing_subscriber::{Layer, Registry};
+
+/// Where and how to export traces and metrics over OTLP/HTTP.
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    /// Collector base URL, e.g. `http://localhost:4318`.
+    pub endpoint: String,
+    /// `service.name` resource attribute.
+    pub service_name: String,
+    /// How often metrics are pushed.
+    pub metrics_interval: Duration,
+}
+
+/// Keeps the exporters alive; flushes and shuts them down when dropped.
+pub struct OtlpGuard {
+    tracer_provider: SdkTracerProvider,
+    meter_provider: SdkMeterProvider,
+}
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        if let Err(error) = self.tracer_provider.shutdown() {
+            eprintln!("Failed to shut down OTLP trace export: {error}");
+        }
+        if let Err(error) = self.meter_provider.shutdown() {
+            eprintln!("Failed to shut down OTLP metric export: {error}");
+        }
+    }
+}
+
+/// Build the exporters, register the global meter provider and return a layer
+/// forwarding spans to the collector.
+pub(crate) fn init(
+    config: &OtlpConfig,
+) -> Result<(Box<dyn Layer<Registry> + Send + Sync>, OtlpGuard), ExporterBuildError> {
+    let endpoint = config.endpoint.trim_end_matches('/');
+    let resource = Resource::builder().with_service_name(config.service_name.clone()).build();
+
+    let span_exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{endpoint}/v1/traces"))
+        .build()?;
+    let tracer_provider = SdkTracerProvider::builder()
+        .with_batch_exporter(span_exporter)
+        .with_resource(resource.clone())
+        .build();
+
+    let metric_exporter = MetricExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{endpoint}/v1/metrics"))
+        .build()?;
+    let reader =
+        PeriodicReader::builder(metric_exporter).with_interval(config.metrics_interval).build();
+    let meter_provider =
+        SdkMeterProvider::builder().with_reader(reader).with_resource(resource).build();
+    global::set_meter_provider(meter_provider.clone());
+
+    let tracer = tracer_provider.tracer(config.service_name.clone());
+    let layer = tracing_opentelemetry::layer().with_tracer(tracer).boxed();
+
+    Ok((layer, OtlpGuard { tracer_provider, meter_provider }))
+}
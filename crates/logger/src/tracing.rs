@@ -1,16 +1,46 @@
 use std::env::var;
 
 use tracing::{level_filters::LevelFilter, warn};
-use tracing_subscriber::{Layer, filter::EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{
+    Layer, Registry, filter::EnvFilter, layer::SubscriberExt, util::SubscriberInitExt,
+};
+
+#[cfg(feature = "otlp")]
+use crate::otlp::{self, OtlpConfig, OtlpGuard};
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
 
 pub fn init() {
-    initialize_tracing(LevelFilter::INFO);
+    initialize_tracing(LevelFilter::INFO, None);
 }
 
-/// Initialize tracing subscriber with default configuration.
-fn initialize_tracing(level: LevelFilter) {
-    let env_filter = EnvFilter::builder().with_default_directive(level.into()).from_env_lossy();
+/// Initialize tracing and, if `config` is given, export spans and metrics over
+/// OTLP. Keep the returned guard alive for as long as telemetry should be sent.
+///
+/// Failing to set up the exporter is logged and leaves plain logging in place.
+#[cfg(feature = "otlp")]
+pub fn init_with_otlp(config: Option<&OtlpConfig>) -> Option<OtlpGuard> {
+    let Some(config) = config else {
+        init();
+        return None;
+    };
 
+    match otlp::init(config) {
+        Ok((layer, guard)) => {
+            initialize_tracing(LevelFilter::INFO, Some(layer));
+            tracing::info!("Exporting traces and metrics to {}", config.endpoint);
+            Some(guard)
+        }
+        Err(error) => {
+            init();
+            warn!("Failed to set up OTLP export to {}: {error}", config.endpoint);
+            None
+        }
+    }
+}
+
+/// Initialize tracing subscriber with default configuration.
+fn initialize_tracing(level: LevelFilter, telemetry: Option<BoxedLayer>) {
     let log_format = var("RUST_LOG_FORMAT")
         .inspect_err(|error| {
             warn!("Failed to read RUST_LOG_FORMAT, falling back to default: {error}")
@@ -18,13 +48,19 @@ fn initialize_tracing(level: LevelFilter) {
         .unwrap_or_default();
 
     let log_layer = match log_format.as_str() {
-        "json" => tracing_subscriber::fmt::layer().json().with_filter(env_filter).boxed(),
+        "json" => tracing_subscriber::fmt::layer().json().with_filter(env_filter(level)).boxed(),
         _ => tracing_subscriber::fmt::layer()
             .compact()
             .without_time()
-            .with_filter(env_filter)
+            .with_filter(env_filter(level))
             .boxed(),
     };
+    // Exported spans follow the same `RUST_LOG` directives as the logs
+    let telemetry = telemetry.map(|layer| layer.with_filter(env_filter(level)));
+
+    tracing_subscriber::registry().with(telemetry).with(log_layer).init();
+}
 
-    tracing_subscriber::registry().with(log_layer).init();
+fn env_filter(level: LevelFilter) -> EnvFilter {
+    EnvFilter::builder().with_default_directive(level.into()).from_env_lossy()
 }
@@ -0,0 +1,80 @@
+//! Encoding cost of a gossiped result: JSON against the binary wire format.
+//!
+//! Run with `cargo bench -p peerup --bench wire`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use serde::{Deserialize, Serialize};
+
+use peerup::protocol::wire;
+use peerup::WireEncoder;
+
+/// Shaped like the service's signed result message
+#[derive(Serialize, Deserialize)]
+struct SignedResult {
+    monitor_id: [u8; 16],
+    target: String,
+    timestamp: (u64, u32),
+    status: String,
+    latency_ms: Option<u64>,
+    status_code: Option<u16>,
+    error_message: Option<String>,
+    peer_id: String,
+    signature: Option<Vec<u8>>,
+    public_key: [u8; 32],
+}
+
+fn sample() -> SignedResult {
+    SignedResult {
+        monitor_id: [7; 16],
+        target: "https://status.example.com/health".to_string(),
+        timestamp: (1_700_000_000, 123_456_789),
+        status: "up".to_string(),
+        latency_ms: Some(87),
+        status_code: Some(200),
+        error_message: None,
+        peer_id: "12D3KooWGzh5H1bNQPMRDbq7Ck7ZBJmHCaFMGTHxfFs6GyqaTEoR".to_string(),
+        signature: Some(vec![42; 64]),
+        public_key: [9; 32],
+    }
+}
+
+fn encode(c: &mut Criterion) {
+    let result = sample();
+    let mut group = c.benchmark_group("encode_result");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("json", |b| {
+        b.iter(|| {
+            let json = serde_json::to_string(black_box(&result)).unwrap();
+            // Previous publish path: a copy for the DHT and one into gossipsub
+            black_box((json.clone().into_bytes(), json.as_bytes().to_vec()));
+        })
+    });
+
+    let mut encoder = WireEncoder::new();
+    group.bench_function("wire", |b| {
+        b.iter(|| {
+            let payload = encoder.encode(1, black_box(&result)).unwrap();
+            black_box(payload.len());
+        })
+    });
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let result = sample();
+    let json = serde_json::to_vec(&result).unwrap();
+    let payload = WireEncoder::new().encode(1, &result).unwrap().to_vec();
+    let mut group = c.benchmark_group("decode_result");
+
+    group.bench_function("json", |b| {
+        b.iter(|| serde_json::from_slice::<SignedResult>(black_box(&json)).unwrap())
+    });
+    group.bench_function("wire", |b| {
+        b.iter(|| wire::decode::<SignedResult>(1, black_box(&payload)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, encode, decode);
+criterion_main!(benches);
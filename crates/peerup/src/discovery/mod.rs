@@ -4,7 +4,9 @@
 
 pub mod kademlia;
 pub mod mdns;
+pub mod records;
 
 // Re-export main functions
 pub use kademlia::{add_bootstrap_peers, configure_kademlia, create_dev_kademlia};
 pub use mdns::{configure_mdns, create_dev_mdns, is_mdns_available};
+pub use records::{RecordValidator, RecordValidators};
@@ -0,0 +1,120 @@
+//! Validation of DHT records stored under application namespaces.
+//!
+//! Kademlia stores whatever a peer asks it to, so by default an attacker can
+//! place oversized or malformed records under keys the application reads.
+//! Applications register a namespace (a key prefix) with a size limit and
+//! optionally a validator checking the record's schema or signature. Once any
+//! namespace is registered, records from peers are only stored after passing
+//! these checks; rejected records are neither stored nor served to others.
+//! Keys outside every namespace are stored as before.
+
+use std::{fmt, sync::Arc};
+
+use anyhow::{anyhow, Result};
+
+/// Checks a record stored under a namespace
+pub trait RecordValidator: Send + Sync {
+    /// Return an error describing why the record must not be stored
+    fn validate(&self, key: &[u8], value: &[u8]) -> Result<()>;
+}
+
+impl<F> RecordValidator for F
+where
+    F: Fn(&[u8], &[u8]) -> Result<()> + Send + Sync,
+{
+    fn validate(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self(key, value)
+    }
+}
+
+#[derive(Clone)]
+struct Namespace {
+    prefix: Vec<u8>,
+    max_value_bytes: usize,
+    validator: Option<Arc<dyn RecordValidator>>,
+}
+
+/// Record checks by key namespace
+///
+/// When namespaces overlap, the longest matching prefix applies.
+#[derive(Clone, Default)]
+pub struct RecordValidators {
+    namespaces: Vec<Namespace>,
+}
+
+impl RecordValidators {
+    /// No namespaces; every record is stored
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit values stored under `prefix` to `max_value_bytes`
+    pub fn limit(self, prefix: impl AsRef<[u8]>, max_value_bytes: usize) -> Self {
+        self.add(prefix.as_ref(), max_value_bytes, None)
+    }
+
+    /// Limit values stored under `prefix` to `max_value_bytes` and check them
+    /// with `validator`
+    pub fn namespace(
+        self,
+        prefix: impl AsRef<[u8]>,
+        max_value_bytes: usize,
+        validator: impl RecordValidator + 'static,
+    ) -> Self {
+        self.add(prefix.as_ref(), max_value_bytes, Some(Arc::new(validator)))
+    }
+
+    fn add(
+        mut self,
+        prefix: &[u8],
+        max_value_bytes: usize,
+        validator: Option<Arc<dyn RecordValidator>>,
+    ) -> Self {
+        self.namespaces.retain(|namespace| namespace.prefix != prefix);
+        self.namespaces.push(Namespace { prefix: prefix.to_vec(), max_value_bytes, validator });
+        self
+    }
+
+    /// Whether no namespace is registered
+    pub fn is_empty(&self) -> bool {
+        self.namespaces.is_empty()
+    }
+
+    /// Check a record about to be stored; keys outside every namespace pass
+    pub fn validate(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        let Some(namespace) = self
+            .namespaces
+            .iter()
+            .filter(|namespace| key.starts_with(&namespace.prefix))
+            .max_by_key(|namespace| namespace.prefix.len())
+        else {
+            return Ok(());
+        };
+
+        if value.len() > namespace.max_value_bytes {
+            return Err(anyhow!(
+                "Record of {} bytes exceeds the {} byte limit",
+                value.len(),
+                namespace.max_value_bytes
+            ));
+        }
+        match &namespace.validator {
+            Some(validator) => validator.validate(key, value),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for RecordValidators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.namespaces.iter().map(|namespace| {
+                (
+                    String::from_utf8_lossy(&namespace.prefix),
+                    namespace.max_value_bytes,
+                    namespace.validator.is_some(),
+                )
+            }))
+            .finish()
+    }
+}
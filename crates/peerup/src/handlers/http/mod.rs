@@ -7,4 +7,4 @@ mod request;
 mod response;
 
 pub use extract::extract_response_headers;
-pub use request::{handle_probe_request, perform_http_request};
+pub use request::{handle_probe_request, handle_probe_request_with_policy, perform_http_request};
@@ -3,13 +3,24 @@
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
-use tracing::info;
+use tracing::{info, warn};
+use url::Url;
 
 use super::extract::extract_response_headers;
+use crate::handlers::target::TargetPolicy;
+use crate::handlers::validation::validate_probe_request;
 use crate::protocol::{ProbeRequest, ProbeResponse};
 
-/// Handle an HTTP probe request
+/// Handle an HTTP probe request, only reaching publicly routable targets
 pub async fn handle_probe_request(request: ProbeRequest) -> ProbeResponse {
+    handle_probe_request_with_policy(request, &TargetPolicy::default()).await
+}
+
+/// Handle an HTTP probe request, reaching the targets `policy` allows
+pub async fn handle_probe_request_with_policy(
+    request: ProbeRequest,
+    policy: &TargetPolicy,
+) -> ProbeResponse {
     info!("Handling probe request: {} {}", request.method, request.target_url);
 
     // Record start time
@@ -19,7 +30,7 @@ pub async fn handle_probe_request(request: ProbeRequest) -> ProbeResponse {
     let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
 
     // Perform the HTTP request
-    let result = perform_http_request(&request).await;
+    let result = perform_http_request(&request, policy).await;
 
     // Calculate duration
     let duration = start.elapsed().as_millis() as u64;
@@ -52,11 +63,26 @@ pub async fn handle_probe_request(request: ProbeRequest) -> ProbeResponse {
 }
 
 /// Perform the actual HTTP request
-pub async fn perform_http_request(request: &ProbeRequest) -> Result<(u16, Vec<(String, String)>)> {
-    // Build HTTP client with timeout
+///
+/// The target is resolved and checked against `policy` first, and the
+/// request only connects to the addresses that passed. Redirects are not
+/// followed, since their targets would skip the check.
+pub async fn perform_http_request(
+    request: &ProbeRequest,
+    policy: &TargetPolicy,
+) -> Result<(u16, Vec<(String, String)>)> {
+    validate_probe_request(request)?;
+    let url = Url::parse(&request.target_url)?;
+    let (host, addrs) = policy.resolve(&url).await.inspect_err(|e| {
+        warn!("Refusing probe of {} for {}: {}", request.target_url, request.requested_by, e);
+    })?;
+
+    // Build HTTP client with timeout, pinned to the checked addresses
     let client = reqwest::Client::builder()
         .timeout(Duration::from_millis(request.timeout))
         .user_agent("peerup/1.0")
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve_to_addrs(&host, &addrs)
         .build()?;
 
     // Prepare the HTTP request
@@ -4,12 +4,14 @@
 
 pub mod http;
 pub mod response;
+pub mod target;
 pub mod validation;
 
 // Re-export main handler function
-pub use http::handle_probe_request;
+pub use http::{handle_probe_request, handle_probe_request_with_policy};
 pub use response::{
     build_error_response, build_network_error_response, build_success_response,
     build_timeout_response,
 };
+pub use target::TargetPolicy;
 pub use validation::validate_probe_request;
@@ -0,0 +1,219 @@
+//! Target address policy for PeerUP handlers.
+//!
+//! Probe requests come from remote peers, so the handler must not let them
+//! reach the node's own network. Targets are resolved before the request is
+//! made and rejected when any address is loopback, private, link-local or
+//! otherwise not publicly routable. Operators can allow internal targets they
+//! mean to probe; cloud metadata services stay off limits either way. The
+//! request is then pinned to the checked addresses, so the name cannot
+//! resolve somewhere else by the time it is sent.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use anyhow::{anyhow, Result};
+use url::Url;
+
+/// Cloud metadata service addresses, never allowed
+const METADATA_ADDRESSES: [IpAddr; 4] = [
+    // AWS, GCP, Azure, OpenStack and most others
+    IpAddr::V4(Ipv4Addr::new(169, 254, 169, 254)),
+    // AWS ECS task metadata
+    IpAddr::V4(Ipv4Addr::new(169, 254, 170, 2)),
+    // Alibaba Cloud
+    IpAddr::V4(Ipv4Addr::new(100, 100, 100, 200)),
+    // AWS over IPv6
+    IpAddr::V6(Ipv6Addr::new(0xfd00, 0xec2, 0, 0, 0, 0, 0, 0x254)),
+];
+
+/// An IP network in CIDR notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl Cidr {
+    /// Parse `addr/len`; a bare address is treated as a single host
+    pub fn parse(s: &str) -> Result<Self> {
+        let (addr, prefix_len) = match s.trim().split_once('/') {
+            Some((addr, len)) => (addr, Some(len)),
+            None => (s.trim(), None),
+        };
+        let network: IpAddr = addr.parse().map_err(|_| anyhow!("Invalid CIDR address: {s}"))?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(len) => len.parse::<u8>().map_err(|_| anyhow!("Invalid CIDR prefix: {s}"))?,
+            None => max_len,
+        };
+        if prefix_len > max_len {
+            return Err(anyhow!("CIDR prefix out of range: {s}"));
+        }
+
+        Ok(Self { network, prefix_len })
+    }
+
+    /// Whether `ip` lies inside this network
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(*ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Which targets the probe handler may reach
+///
+/// The default policy only allows publicly routable addresses.
+#[derive(Debug, Clone, Default)]
+pub struct TargetPolicy {
+    allowed_cidrs: Vec<Cidr>,
+    allowed_hosts: Vec<String>,
+}
+
+impl TargetPolicy {
+    /// Allow internal targets matching one of `entries`
+    ///
+    /// An entry is an address or CIDR, or a host name that also covers its
+    /// subdomains. Malformed CIDRs are an error rather than ignored.
+    pub fn with_allowlist<S: AsRef<str>>(entries: &[S]) -> Result<Self> {
+        let mut policy = Self::default();
+        for entry in entries {
+            let entry = entry.as_ref().trim();
+            if entry.is_empty() {
+                continue;
+            }
+            if entry.contains('/') || entry.parse::<IpAddr>().is_ok() {
+                policy.allowed_cidrs.push(Cidr::parse(entry)?);
+            } else {
+                policy.allowed_hosts.push(entry.trim_start_matches('.').to_lowercase());
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Check a single address
+    pub fn check_ip(&self, ip: &IpAddr) -> Result<()> {
+        if is_metadata(ip) {
+            return Err(anyhow!("Target address {ip} is a cloud metadata service"));
+        }
+        if !is_public(ip) && !self.allowed_cidrs.iter().any(|cidr| cidr.contains(ip)) {
+            return Err(anyhow!("Target address {ip} is not publicly routable"));
+        }
+
+        Ok(())
+    }
+
+    /// Resolve the host of `url` and check every address it maps to
+    ///
+    /// Returns the host and the addresses the request should be pinned to.
+    pub async fn resolve(&self, url: &Url) -> Result<(String, Vec<SocketAddr>)> {
+        let host = url.host_str().ok_or_else(|| anyhow!("URL {url} has no host"))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_lowercase();
+        let port = url.port_or_known_default().unwrap_or(80);
+
+        let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => tokio::net::lookup_host((host.as_str(), port))
+                .await
+                .map_err(|e| anyhow!("Failed to resolve {host}: {e}"))?
+                .collect(),
+        };
+        if addrs.is_empty() {
+            return Err(anyhow!("{host} did not resolve to any address"));
+        }
+
+        let allowed_host = self.is_allowed_host(&host);
+        for addr in &addrs {
+            let ip = addr.ip();
+            if allowed_host && !is_metadata(&ip) {
+                continue;
+            }
+            self.check_ip(&ip)?;
+        }
+
+        Ok((host, addrs))
+    }
+
+    fn is_allowed_host(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.');
+        self.allowed_hosts
+            .iter()
+            .any(|allowed| host == allowed || host.ends_with(&format!(".{allowed}")))
+    }
+}
+
+/// Whether an address belongs to a cloud metadata service
+fn is_metadata(ip: &IpAddr) -> bool {
+    let ip = match ip {
+        IpAddr::V6(v6) => embedded_v4(v6).map_or(*ip, IpAddr::V4),
+        IpAddr::V4(_) => *ip,
+    };
+    METADATA_ADDRESSES.contains(&ip)
+}
+
+/// Whether an address is globally routable
+pub fn is_public(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match embedded_v4(ip) {
+            Some(v4) => is_public_v4(&v4),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+/// IPv4 address an IPv6 address carries and is routed to: IPv4-mapped
+/// `::ffff:0:0/96`, IPv4-compatible `::/96` (which holds `::` and `::1`),
+/// NAT64 `64:ff9b::/96` and 6to4 `2002::/16`
+fn embedded_v4(ip: &Ipv6Addr) -> Option<Ipv4Addr> {
+    let [.., a, b, c, d] = ip.octets();
+    match ip.segments() {
+        [0, 0, 0, 0, 0, 0xffff, _, _] | [0, 0, 0, 0, 0, 0, _, _] => Some(Ipv4Addr::new(a, b, c, d)),
+        [0x64, 0xff9b, 0, 0, 0, 0, _, _] => Some(Ipv4Addr::new(a, b, c, d)),
+        [0x2002, high, low, ..] => Some(Ipv4Addr::from(u32::from(high) << 16 | u32::from(low))),
+        _ => None,
+    }
+}
+
+fn is_public_v4(ip: &Ipv4Addr) -> bool {
+    let octets = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        // 0.0.0.0/8 "this network"
+        || octets[0] == 0
+        // 192.0.0.0/24 IETF protocol assignments
+        || (octets[0] == 192 && octets[1] == 0 && octets[2] == 0)
+        // 100.64.0.0/10 carrier-grade NAT
+        || (octets[0] == 100 && (octets[1] & 0xc0) == 64)
+        // 198.18.0.0/15 benchmarking
+        || (octets[0] == 198 && (octets[1] & 0xfe) == 18)
+        // 240.0.0.0/4 reserved
+        || octets[0] >= 240)
+}
+
+fn is_public_v6(ip: &Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // 64:ff9b:1::/48 local-use NAT64
+        || (first == 0x64 && ip.segments()[1] == 0xff9b && ip.segments()[2] == 1)
+        // fc00::/7 unique local
+        || (first & 0xfe00) == 0xfc00
+        // fe80::/10 link-local
+        || (first & 0xffc0) == 0xfe80
+        // 2001:db8::/32 documentation
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+}
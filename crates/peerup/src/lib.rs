@@ -9,20 +9,36 @@ pub mod network;
 pub mod node;
 pub mod protocol;
 pub mod relay;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod transport;
 
 // Re-export main types
 /// Re-export common error types
 pub use anyhow;
 pub use network::{PeerUPBehaviour, PeerUPBehaviourState, PeerUPEvent};
-pub use node::{core::gossipsub::MONITORING_RESULTS_TOPIC, NodeConfig, PeerNode};
-pub use protocol::{ProbeCodec, ProbeRequest, ProbeResponse, PROBE_PROTOCOL};
+pub use node::{
+    core::gossipsub::MONITORING_RESULTS_TOPIC, EventFilter, EventInterest, NodeConfig, NodeMetrics,
+    PeerNode, ProtocolMetrics,
+};
+pub use protocol::{
+    ControlMessage, ProbeCodec, ProbeRequest, ProbeResponse, ProtocolTraffic, ResultsSyncCodec,
+    ResultsSyncRequest, ResultsSyncResponse, WireEncoder, PROBE_PROTOCOL, RESULTS_SYNC_PROTOCOL,
+};
 
 // Re-export commonly needed libp2p types for consumers
+pub use libp2p::PeerId;
+
 pub mod swarm {
     pub use libp2p::swarm::SwarmEvent;
 }
 
+pub mod kad {
+    pub use libp2p::kad::{
+        Event, GetRecordError, GetRecordOk, QueryId, QueryResult, Record, RecordKey,
+    };
+}
+
 /// PeerUP result type using anyhow for error handling
 pub type Result<T> = anyhow::Result<T>;
 
@@ -7,7 +7,8 @@ use std::time::Duration;
 
 use anyhow::Result;
 use libp2p::{
-    gossipsub,
+    allow_block_list::{self, AllowedPeers, BlockedPeers},
+    dcutr, gossipsub, identify,
     identity::Keypair,
     kad::{
         store::MemoryStore,
@@ -20,35 +21,81 @@ use libp2p::{
 
 use super::events::PeerUPEvent;
 use crate::{
-    node::NodeConfig,
-    protocol::{ProbeCodec, PROBE_PROTOCOL},
+    node::{core::gossipsub::MONITORING_RESULTS_TOPIC, NodeConfig},
+    protocol::{
+        ProbeCodec, ProtocolTraffic, ResultsSyncCodec, PROBE_PROTOCOL, RESULTS_SYNC_PROTOCOL,
+    },
 };
 
 /// The main network behaviour for PeerUP
 #[derive(NetworkBehaviour)]
 #[behaviour(to_swarm = "PeerUPEvent")]
 pub struct PeerUPBehaviour {
+    /// Connection gate refusing blocked peers
+    pub blocked_peers: allow_block_list::Behaviour<BlockedPeers>,
+    /// Connection gate refusing peers missing from the allowlist, when one is
+    /// configured
+    pub allowed_peers: Toggle<allow_block_list::Behaviour<AllowedPeers>>,
     /// Gossipsub for pub/sub messaging (result broadcasting)
     pub gossipsub: gossipsub::Behaviour,
     /// Request/response protocol for probes
     pub request_response: request_response::Behaviour<ProbeCodec>,
+    /// Request/response protocol for paging through peers' stored results
+    pub results_sync: request_response::Behaviour<ResultsSyncCodec>,
     /// mDNS for local peer discovery
     pub mdns: Toggle<mdns::tokio::Behaviour>,
     /// Kademlia for DHT functionality
     pub kademlia: Toggle<kad::Behaviour<MemoryStore>>,
     /// Relay for NAT traversal
     pub relay: Toggle<libp2p::relay::Behaviour>,
+    /// Relay client for reaching and being reached through other peers' relays
+    pub relay_client: Toggle<libp2p::relay::client::Behaviour>,
+    /// Identify, telling peers the address they are seen at so they have
+    /// candidates to hole punch with
+    pub identify: Toggle<identify::Behaviour>,
+    /// DCUtR, upgrading relayed connections to direct ones
+    pub dcutr: Toggle<dcutr::Behaviour>,
 }
 
 impl PeerUPBehaviour {
     /// Create a new PeerUPBehaviour
-    pub async fn new(keypair: &Keypair, config: &NodeConfig) -> Result<Self> {
+    ///
+    /// Probe and results sync protocol bytes are counted into `probe_traffic`
+    /// and `sync_traffic`.
+    pub async fn new(
+        keypair: &Keypair,
+        config: &NodeConfig,
+        probe_traffic: ProtocolTraffic,
+        sync_traffic: ProtocolTraffic,
+    ) -> Result<Self> {
         let local_peer_id = PeerId::from(keypair.public());
 
-        // Create gossipsub for result broadcasting
-        let gossipsub = Self::create_gossipsub(keypair)?;
+        // Create gossipsub for result broadcasting; it drops messages from and
+        // stops forwarding to blocked peers
+        let mut gossipsub = Self::create_gossipsub(keypair, config.enable_peer_scoring)?;
+
+        let mut blocked_peers = allow_block_list::Behaviour::<BlockedPeers>::default();
+        for peer in &config.blocked_peers {
+            blocked_peers.block_peer(*peer);
+            gossipsub.blacklist_peer(peer);
+        }
+        let allowed_peers = if config.allowed_peers.is_empty() {
+            None
+        } else {
+            let mut allowed_peers = allow_block_list::Behaviour::<AllowedPeers>::default();
+            for peer in &config.allowed_peers {
+                allowed_peers.allow_peer(*peer);
+            }
+            tracing::info!("Only {} allowed peer(s) may connect", config.allowed_peers.len());
+            Some(allowed_peers)
+        };
 
-        let request_response = Self::create_probe_protocol();
+        let request_response =
+            Self::create_protocol(PROBE_PROTOCOL, ProbeCodec::with_traffic(probe_traffic));
+        let results_sync = Self::create_protocol(
+            RESULTS_SYNC_PROTOCOL,
+            ResultsSyncCodec::with_traffic(sync_traffic),
+        );
 
         // Create mDNS if enabled (gracefully handle platform limitations)
         let mdns = if config.enable_mdns {
@@ -77,7 +124,12 @@ impl PeerUPBehaviour {
         // Create Kademlia if enabled (production-ready DHT peer discovery)
         let kademlia = if config.enable_kademlia {
             let store = MemoryStore::new(local_peer_id);
-            let mut kademlia = kad::Behaviour::new(local_peer_id, store);
+            let mut kad_config = kad::Config::new(kad::PROTOCOL_NAME);
+            if !config.record_validators.is_empty() {
+                // Records from peers are stored by PeerNode once validated
+                kad_config.set_record_filtering(kad::StoreInserts::FilterBoth);
+            }
+            let mut kademlia = kad::Behaviour::with_config(local_peer_id, store, kad_config);
 
             // Set to server mode for better network participation
             kademlia.set_mode(Some(kad::Mode::Server));
@@ -89,28 +141,69 @@ impl PeerUPBehaviour {
             None
         };
 
-        // Create relay if enabled
+        // Create relay if enabled, within the configured server limits
         let relay = if config.enable_relay {
-            let relay_config = libp2p::relay::Config::default();
+            let relay_config = match &config.relay_server {
+                Some(limits) => {
+                    tracing::info!(
+                        "Relay server enabled: {} reservations, {} circuits, {} bytes per circuit",
+                        limits.max_reservations,
+                        limits.max_circuits,
+                        limits.max_circuit_bytes
+                    );
+                    limits.to_config()
+                }
+                None => libp2p::relay::Config::default(),
+            };
             Some(libp2p::relay::Behaviour::new(local_peer_id, relay_config))
         } else {
             None
         };
 
+        // Hole punching needs a relayed connection to coordinate over and
+        // observed addresses (from identify) to punch with
+        let (identify, dcutr) = if config.enable_relay && config.enable_hole_punching {
+            let identify_config = identify::Config::new(
+                format!("/peerup/{}", crate::PROTOCOL_VERSION),
+                keypair.public(),
+            );
+            tracing::info!("Hole punching over relayed connections enabled");
+            (
+                Some(identify::Behaviour::new(identify_config)),
+                Some(dcutr::Behaviour::new(local_peer_id)),
+            )
+        } else {
+            (None, None)
+        };
+
         Ok(Self {
+            blocked_peers,
+            allowed_peers: allowed_peers.into(),
             gossipsub,
             request_response,
+            results_sync,
             mdns: mdns.into(),
             kademlia: kademlia.into(),
             relay: relay.into(),
+            relay_client: None.into(),
+            identify: identify.into(),
+            dcutr: dcutr.into(),
         })
     }
 
-    fn create_gossipsub(keypair: &Keypair) -> Result<gossipsub::Behaviour> {
-        // Configure gossipsub for monitoring results
+    /// Attach the relay client the swarm's relay transport was built with
+    pub fn with_relay_client(mut self, client: libp2p::relay::client::Behaviour) -> Self {
+        self.relay_client = Some(client).into();
+        self
+    }
+
+    fn create_gossipsub(keypair: &Keypair, peer_scoring: bool) -> Result<gossipsub::Behaviour> {
+        // Configure gossipsub for monitoring results. Messages are only forwarded
+        // once PeerNode has validated them (rate limits), see `next_event`.
         let gossipsub_config = gossipsub::ConfigBuilder::default()
             .heartbeat_interval(Duration::from_secs(10))
             .validation_mode(gossipsub::ValidationMode::Strict)
+            .validate_messages()
             .message_id_fn(|msg| {
                 // Use message data hash as ID for deduplication
                 use std::hash::{Hash, Hasher};
@@ -121,23 +214,64 @@ impl PeerUPBehaviour {
             .build()
             .map_err(|e| anyhow::anyhow!("Failed to create gossipsub config: {}", e))?;
 
-        gossipsub::Behaviour::new(
+        let mut gossipsub = gossipsub::Behaviour::new(
             gossipsub::MessageAuthenticity::Signed(keypair.clone()),
             gossipsub_config,
         )
-        .map_err(|e| anyhow::anyhow!("Failed to create gossipsub behaviour: {}", e))
+        .map_err(|e| anyhow::anyhow!("Failed to create gossipsub behaviour: {}", e))?;
+
+        if peer_scoring {
+            gossipsub
+                .with_peer_score(
+                    Self::peer_score_params(),
+                    gossipsub::PeerScoreThresholds::default(),
+                )
+                .map_err(|e| anyhow::anyhow!("Failed to enable gossipsub peer scoring: {}", e))?;
+            tracing::info!("Gossipsub peer scoring enabled");
+        }
+
+        Ok(gossipsub)
+    }
+
+    /// Peer scoring parameters for the monitoring results topic
+    ///
+    /// Invalid (rejected) messages are penalised heavily so flooding peers are
+    /// graylisted quickly. Mesh delivery penalties stay off because results are
+    /// published at monitor intervals and quiet peers are not misbehaving.
+    fn peer_score_params() -> gossipsub::PeerScoreParams {
+        let results_topic = gossipsub::TopicScoreParams {
+            topic_weight: 1.0,
+            time_in_mesh_weight: 0.01,
+            time_in_mesh_quantum: Duration::from_secs(1),
+            time_in_mesh_cap: 3600.0,
+            first_message_deliveries_weight: 1.0,
+            first_message_deliveries_decay: 0.9,
+            first_message_deliveries_cap: 100.0,
+            mesh_message_deliveries_weight: 0.0,
+            mesh_failure_penalty_weight: 0.0,
+            invalid_message_deliveries_weight: -100.0,
+            invalid_message_deliveries_decay: 0.5,
+            ..Default::default()
+        };
+
+        let mut params = gossipsub::PeerScoreParams::default();
+        params
+            .topics
+            .insert(gossipsub::IdentTopic::new(MONITORING_RESULTS_TOPIC).hash(), results_topic);
+        params
     }
 
-    fn create_probe_protocol() -> request_response::Behaviour<ProbeCodec> {
+    fn create_protocol<C>(protocol: &'static str, codec: C) -> request_response::Behaviour<C>
+    where
+        C: request_response::Codec<Protocol = libp2p::StreamProtocol> + Clone + Send + 'static,
+    {
         let config = request_response::Config::default()
             .with_request_timeout(Duration::from_secs(30))
             .with_max_concurrent_streams(5);
 
-        request_response::Behaviour::new(
-            [(
-                libp2p::StreamProtocol::new(PROBE_PROTOCOL),
-                request_response::ProtocolSupport::Full,
-            )],
+        request_response::Behaviour::with_codec(
+            codec,
+            [(libp2p::StreamProtocol::new(protocol), request_response::ProtocolSupport::Full)],
             config,
         )
     }
@@ -0,0 +1,13 @@
+//! Conversions from connection gate events to PeerUPEvent.
+//!
+//! The allow and block lists only refuse connections and never emit events.
+
+use std::convert::Infallible;
+
+use crate::network::events::PeerUPEvent;
+
+impl From<Infallible> for PeerUPEvent {
+    fn from(event: Infallible) -> Self {
+        match event {}
+    }
+}
@@ -0,0 +1,17 @@
+//! Conversions from DCUtR and identify events to PeerUPEvent.
+
+use libp2p::{dcutr, identify};
+
+use crate::network::events::PeerUPEvent;
+
+impl From<dcutr::Event> for PeerUPEvent {
+    fn from(event: dcutr::Event) -> Self {
+        PeerUPEvent::Dcutr(event)
+    }
+}
+
+impl From<identify::Event> for PeerUPEvent {
+    fn from(event: identify::Event) -> Self {
+        PeerUPEvent::Identify(Box::new(event))
+    }
+}
@@ -1,8 +1,8 @@
 //! Conversions from Kademlia events to PeerUPEvent.
 //!
-//! Note: This conversion is a best-effort mapping. Not all Kademlia events map
-//! cleanly to PeerUPEvent, and some variants use PeerDiscovered with a random
-//! PeerId as a fallback. Adjust as needed for your use case.
+//! Note: This conversion is a best-effort mapping. Peer discovery results map
+//! to PeerDiscovered; every other event, including record queries, is passed
+//! through as PeerUPEvent::Kademlia so consumers can filter on record keys.
 
 use libp2p::{kad, PeerId};
 
@@ -19,11 +19,10 @@ impl From<kad::Event> for PeerUPEvent {
                     PeerUPEvent::PeerDiscovered(PeerId::random())
                 }
             }
-            OutboundQueryProgressed { .. } => PeerUPEvent::PeerDiscovered(PeerId::random()),
             RoutingUpdated { peer, .. } | PendingRoutablePeer { peer, .. } => {
                 PeerUPEvent::PeerDiscovered(peer)
             }
-            _ => PeerUPEvent::PeerDiscovered(PeerId::random()),
+            other => PeerUPEvent::Kademlia(other),
         }
     }
 }
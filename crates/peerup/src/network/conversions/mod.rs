@@ -2,6 +2,8 @@
 //!
 //! This module implements conversions from libp2p events to PeerUPEvent.
 
+pub mod allow_block_list;
+pub mod dcutr;
 pub mod gossipsub;
 pub mod kad;
 pub mod mdns;
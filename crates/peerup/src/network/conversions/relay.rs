@@ -21,3 +21,9 @@ impl From<relay::Event> for PeerUPEvent {
         }
     }
 }
+
+impl From<relay::client::Event> for PeerUPEvent {
+    fn from(event: relay::client::Event) -> Self {
+        PeerUPEvent::RelayClient(event)
+    }
+}
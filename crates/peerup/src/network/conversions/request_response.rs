@@ -1,44 +1,93 @@
 //! Conversions from request_response events to PeerUPEvent.
 //!
-//! Note: The libp2p request_response API does not expose a request_id in all
-//! events. As a result, we use a placeholder value (0) for request_id in
-//! ProbeResponseReceived, OutboundProbeFailure, and InboundProbeFailure. If you
-//! need to track request IDs, you must do so externally.
+//! libp2p keeps the numeric request ID private, so it is read back from the
+//! ID's `Display` output. The same conversion is used when a probe or results
+//! sync request is sent, which lets callers match responses and failures to
+//! their requests. Each protocol numbers its requests separately.
 
 use libp2p::request_response;
+use std::fmt::Display;
 
 use crate::{
     network::events::PeerUPEvent,
-    protocol::{ProbeRequest, ProbeResponse},
+    protocol::{ProbeRequest, ProbeResponse, ResultsSyncRequest, ResultsSyncResponse},
 };
 
+/// Numeric value of a libp2p request ID
+pub(crate) fn request_id_value(id: impl Display) -> u64 {
+    id.to_string().parse().unwrap_or_default()
+}
+
 impl From<request_response::Event<ProbeRequest, ProbeResponse>> for PeerUPEvent {
     fn from(event: request_response::Event<ProbeRequest, ProbeResponse>) -> Self {
         match event {
-            request_response::Event::Message { peer, message, .. } => {
-                match message {
-                    request_response::Message::Request { request, channel, .. } => {
-                        PeerUPEvent::ProbeRequestReceived { peer, request, channel }
-                    }
-                    request_response::Message::Response { response, .. } => {
-                        // TODO: Track request_id externally if needed
-                        let request_id = 0u64; // Placeholder - libp2p does not expose request_id
-                        PeerUPEvent::ProbeResponseReceived { peer, request_id, response }
+            request_response::Event::Message { peer, message, .. } => match message {
+                request_response::Message::Request { request, channel, .. } => {
+                    PeerUPEvent::ProbeRequestReceived { peer, request, channel }
+                }
+                request_response::Message::Response { request_id, response } => {
+                    PeerUPEvent::ProbeResponseReceived {
+                        peer,
+                        request_id: request_id_value(request_id),
+                        response,
                     }
                 }
+            },
+            request_response::Event::OutboundFailure { peer, request_id, error, .. } => {
+                PeerUPEvent::OutboundProbeFailure {
+                    peer,
+                    request_id: request_id_value(request_id),
+                    error,
+                }
             }
-            request_response::Event::OutboundFailure { peer, error, .. } => {
-                let request_id = 0u64; // Placeholder - libp2p does not expose request_id
-                PeerUPEvent::OutboundProbeFailure { peer, request_id, error }
+            request_response::Event::InboundFailure { peer, request_id, error, .. } => {
+                PeerUPEvent::InboundProbeFailure {
+                    peer,
+                    request_id: request_id_value(request_id),
+                    error,
+                }
             }
-            request_response::Event::InboundFailure { peer, error, .. } => {
-                let request_id = 0u64; // Placeholder - libp2p does not expose request_id
-                PeerUPEvent::InboundProbeFailure { peer, request_id, error }
+            request_response::Event::ResponseSent { peer, request_id, .. } => {
+                PeerUPEvent::ProbeResponseSent { peer, request_id: request_id_value(request_id) }
             }
-            request_response::Event::ResponseSent { .. } => {
-                // No-op: ResponseSent is not mapped to a PeerUPEvent variant
-                // You may log or handle this event elsewhere if needed
-                PeerUPEvent::PeerDiscovered(libp2p::PeerId::random())
+        }
+    }
+}
+
+impl From<request_response::Event<ResultsSyncRequest, ResultsSyncResponse>> for PeerUPEvent {
+    fn from(event: request_response::Event<ResultsSyncRequest, ResultsSyncResponse>) -> Self {
+        match event {
+            request_response::Event::Message { peer, message, .. } => match message {
+                request_response::Message::Request { request, channel, .. } => {
+                    PeerUPEvent::ResultsSyncRequestReceived { peer, request, channel }
+                }
+                request_response::Message::Response { request_id, response } => {
+                    PeerUPEvent::ResultsSyncResponseReceived {
+                        peer,
+                        request_id: request_id_value(request_id),
+                        response,
+                    }
+                }
+            },
+            request_response::Event::OutboundFailure { peer, request_id, error, .. } => {
+                PeerUPEvent::OutboundResultsSyncFailure {
+                    peer,
+                    request_id: request_id_value(request_id),
+                    error,
+                }
+            }
+            request_response::Event::InboundFailure { peer, request_id, error, .. } => {
+                PeerUPEvent::InboundResultsSyncFailure {
+                    peer,
+                    request_id: request_id_value(request_id),
+                    error,
+                }
+            }
+            request_response::Event::ResponseSent { peer, request_id, .. } => {
+                PeerUPEvent::ResultsSyncResponseSent {
+                    peer,
+                    request_id: request_id_value(request_id),
+                }
             }
         }
     }
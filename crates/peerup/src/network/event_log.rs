@@ -0,0 +1,225 @@
+//! Bounded log of recent network activity.
+//!
+//! Keeps short, structured summaries of swarm, gossip and DHT events so UIs
+//! and diagnostics can show what the node has been doing without tailing logs.
+
+use std::{collections::VecDeque, time::SystemTime};
+
+use libp2p::swarm::SwarmEvent;
+use serde::{Deserialize, Serialize};
+
+use super::events::PeerUPEvent;
+
+/// Default number of entries kept in the log
+pub const DEFAULT_EVENT_LOG_CAPACITY: usize = 256;
+
+/// Category of a logged network event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkEventKind {
+    /// Connections, dial errors and peer discovery
+    Connection,
+    /// Gossipsub messages and subscriptions
+    Gossip,
+    /// Kademlia DHT activity
+    Dht,
+    /// Probe request-response traffic
+    Probe,
+    /// Results sync request-response traffic
+    Sync,
+    /// Listeners, relay, mDNS and other swarm events
+    Swarm,
+}
+
+/// A single entry in the network event log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkLogEntry {
+    /// When the event was observed
+    pub at: SystemTime,
+    /// Event category
+    pub kind: NetworkEventKind,
+    /// Peer the event relates to, if any
+    pub peer: Option<String>,
+    /// Short human-readable description
+    pub summary: String,
+}
+
+impl NetworkLogEntry {
+    /// Summarise a swarm event
+    pub fn from_event(event: &SwarmEvent<PeerUPEvent>) -> Self {
+        use NetworkEventKind::*;
+
+        let (kind, peer, summary) = match event {
+            SwarmEvent::Behaviour(behaviour_event) => match behaviour_event {
+                PeerUPEvent::GossipsubMessage { peer, message, .. } => (
+                    Gossip,
+                    Some(peer.to_string()),
+                    format!("message on {} ({} bytes)", message.topic, message.data.len()),
+                ),
+                PeerUPEvent::GossipsubSubscribed { peer, topic } => {
+                    (Gossip, Some(peer.to_string()), format!("peer subscribed to {topic}"))
+                }
+                PeerUPEvent::GossipsubUnsubscribed { peer, topic } => {
+                    (Gossip, Some(peer.to_string()), format!("peer unsubscribed from {topic}"))
+                }
+                PeerUPEvent::Gossipsub(ev) => (Gossip, None, format!("{ev:?}")),
+                PeerUPEvent::ProbeRequestReceived { peer, request, .. } => (
+                    Probe,
+                    Some(peer.to_string()),
+                    format!("probe request for {}", request.target_url),
+                ),
+                PeerUPEvent::ProbeResponseReceived { peer, request_id, .. } => {
+                    (Probe, Some(peer.to_string()), format!("probe response #{request_id}"))
+                }
+                PeerUPEvent::ProbeResponseSent { peer, request_id } => {
+                    (Probe, Some(peer.to_string()), format!("probe response #{request_id} sent"))
+                }
+                PeerUPEvent::OutboundProbeFailure { peer, error, .. } => {
+                    (Probe, Some(peer.to_string()), format!("outbound probe failed: {error}"))
+                }
+                PeerUPEvent::InboundProbeFailure { peer, error, .. } => {
+                    (Probe, Some(peer.to_string()), format!("inbound probe failed: {error}"))
+                }
+                PeerUPEvent::RequestResponse(ev) => (Probe, None, format!("{ev:?}")),
+                PeerUPEvent::ResultsSyncRequestReceived { peer, request, .. } => (
+                    Sync,
+                    Some(peer.to_string()),
+                    format!("results sync request for {}", request.monitor_id),
+                ),
+                PeerUPEvent::ResultsSyncResponseReceived { peer, request_id, response } => (
+                    Sync,
+                    Some(peer.to_string()),
+                    format!(
+                        "results sync response #{request_id} ({} results)",
+                        response.results.len()
+                    ),
+                ),
+                PeerUPEvent::ResultsSyncResponseSent { peer, request_id } => (
+                    Sync,
+                    Some(peer.to_string()),
+                    format!("results sync response #{request_id} sent"),
+                ),
+                PeerUPEvent::OutboundResultsSyncFailure { peer, error, .. } => {
+                    (Sync, Some(peer.to_string()), format!("outbound results sync failed: {error}"))
+                }
+                PeerUPEvent::InboundResultsSyncFailure { peer, error, .. } => {
+                    (Sync, Some(peer.to_string()), format!("inbound results sync failed: {error}"))
+                }
+                PeerUPEvent::PeerGreylisted { peer, duration, offences } => (
+                    Connection,
+                    Some(peer.to_string()),
+                    format!("greylisted for {duration:?} (offence {offences})"),
+                ),
+                PeerUPEvent::PeerDiscovered(peer) => {
+                    (Connection, Some(peer.to_string()), "peer discovered".to_string())
+                }
+                PeerUPEvent::PeerRemoved(peer) => {
+                    (Connection, Some(peer.to_string()), "peer removed".to_string())
+                }
+                PeerUPEvent::ConnectionEstablished(peer) => {
+                    (Connection, Some(peer.to_string()), "connection established".to_string())
+                }
+                PeerUPEvent::ConnectionClosed(peer) => {
+                    (Connection, Some(peer.to_string()), "connection closed".to_string())
+                }
+                PeerUPEvent::RecordRejected { peer, key, reason } => (
+                    Dht,
+                    Some(peer.to_string()),
+                    format!("record {} rejected: {reason}", String::from_utf8_lossy(key.as_ref())),
+                ),
+                PeerUPEvent::Kademlia(ev) => (Dht, None, format!("{ev:?}")),
+                PeerUPEvent::Relay(ev) => (Swarm, None, format!("relay: {ev:?}")),
+                PeerUPEvent::RelayClient(ev) => (Swarm, None, format!("relay client: {ev:?}")),
+                PeerUPEvent::Dcutr(ev) => (
+                    Connection,
+                    Some(ev.remote_peer_id.to_string()),
+                    match &ev.result {
+                        Ok(_) => "upgraded to a direct connection".to_string(),
+                        Err(e) => format!("hole punching failed: {e}"),
+                    },
+                ),
+                PeerUPEvent::Identify(ev) => (Swarm, None, format!("identify: {ev:?}")),
+                PeerUPEvent::Mdns(ev) => (Swarm, None, format!("mdns: {ev:?}")),
+            },
+            SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => (
+                Connection,
+                Some(peer_id.to_string()),
+                format!("connected via {}", endpoint.get_remote_address()),
+            ),
+            SwarmEvent::ConnectionClosed { peer_id, cause, .. } => (
+                Connection,
+                Some(peer_id.to_string()),
+                match cause {
+                    Some(cause) => format!("disconnected: {cause}"),
+                    None => "disconnected".to_string(),
+                },
+            ),
+            SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
+                (Connection, peer_id.map(|p| p.to_string()), format!("dial failed: {error}"))
+            }
+            SwarmEvent::IncomingConnectionError { send_back_addr, error, .. } => {
+                (Connection, None, format!("incoming from {send_back_addr} failed: {error}"))
+            }
+            SwarmEvent::NewListenAddr { address, .. } => {
+                (Swarm, None, format!("listening on {address}"))
+            }
+            SwarmEvent::ExpiredListenAddr { address, .. } => {
+                (Swarm, None, format!("stopped listening on {address}"))
+            }
+            other => (Swarm, None, format!("{other:?}")),
+        };
+
+        Self { at: SystemTime::now(), kind, peer, summary }
+    }
+}
+
+/// Fixed-capacity ring buffer of network log entries
+#[derive(Debug, Clone)]
+pub struct EventLog {
+    entries: VecDeque<NetworkLogEntry>,
+    capacity: usize,
+}
+
+impl EventLog {
+    /// Create a log holding at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self { entries: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    /// Append an entry, evicting the oldest when full
+    pub fn push(&mut self, entry: NetworkLogEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Most recent entries, newest first
+    pub fn recent(&self, limit: usize) -> Vec<NetworkLogEntry> {
+        self.entries.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Most recent entries of one kind, newest first
+    pub fn recent_of_kind(&self, kind: NetworkEventKind, limit: usize) -> Vec<NetworkLogEntry> {
+        self.entries.iter().rev().filter(|e| e.kind == kind).take(limit).cloned().collect()
+    }
+
+    /// Number of entries currently held
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the log is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_LOG_CAPACITY)
+    }
+}
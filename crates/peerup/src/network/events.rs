@@ -4,7 +4,7 @@
 
 use libp2p::{gossipsub, request_response, PeerId};
 
-use crate::protocol::{ProbeRequest, ProbeResponse};
+use crate::protocol::{ProbeRequest, ProbeResponse, ResultsSyncRequest, ResultsSyncResponse};
 
 /// Events emitted by the PeerUPBehaviour
 #[derive(Debug)]
@@ -37,6 +37,8 @@ pub enum PeerUPEvent {
         request_id: u64,
         response: ProbeResponse,
     },
+    /// Our response to a probe request was sent
+    ProbeResponseSent { peer: PeerId, request_id: u64 },
     /// Outbound probe request failed
     OutboundProbeFailure {
         peer: PeerId,
@@ -49,6 +51,46 @@ pub enum PeerUPEvent {
         request_id: u64,
         error: request_response::InboundFailure,
     },
+    /// A peer asked for the results we stored
+    ResultsSyncRequestReceived {
+        peer: PeerId,
+        request: ResultsSyncRequest,
+        channel: request_response::ResponseChannel<ResultsSyncResponse>,
+    },
+    /// A page of results arrived for a results sync request
+    ResultsSyncResponseReceived {
+        peer: PeerId,
+        request_id: u64,
+        response: ResultsSyncResponse,
+    },
+    /// Our page of results was sent
+    ResultsSyncResponseSent { peer: PeerId, request_id: u64 },
+    /// Outbound results sync request failed
+    OutboundResultsSyncFailure {
+        peer: PeerId,
+        request_id: u64,
+        error: request_response::OutboundFailure,
+    },
+    /// Inbound results sync request failed
+    InboundResultsSyncFailure {
+        peer: PeerId,
+        request_id: u64,
+        error: request_response::InboundFailure,
+    },
+    /// A peer went over its inbound rate limit too often and everything it
+    /// sends is dropped for `duration`; `offences` counts how often it has
+    /// been greylisted
+    PeerGreylisted {
+        peer: PeerId,
+        duration: std::time::Duration,
+        offences: u32,
+    },
+    /// A DHT record sent by a peer failed validation and was not stored
+    RecordRejected {
+        peer: PeerId,
+        key: libp2p::kad::RecordKey,
+        reason: String,
+    },
     /// A peer was discovered
     PeerDiscovered(PeerId),
     /// A peer was removed from the network
@@ -61,6 +103,12 @@ pub enum PeerUPEvent {
     Gossipsub(gossipsub::Event),
     /// Relay event
     Relay(libp2p::relay::Event),
+    /// Relay client event (reservations and circuits through other relays)
+    RelayClient(libp2p::relay::client::Event),
+    /// Hole punching attempt over a relayed connection finished
+    Dcutr(libp2p::dcutr::Event),
+    /// Identify event
+    Identify(Box<libp2p::identify::Event>),
     /// Kademlia event
     Kademlia(libp2p::kad::Event),
     /// Mdns event
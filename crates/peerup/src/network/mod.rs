@@ -5,12 +5,18 @@
 
 pub mod behaviour;
 pub mod conversions;
+pub mod event_log;
 pub mod events;
 pub mod helpers;
+pub mod peer_limit;
+pub mod rate_limit;
 pub mod state;
 
 // Re-export main types
 pub use behaviour::PeerUPBehaviour;
+pub use event_log::{EventLog, NetworkEventKind, NetworkLogEntry};
 pub use events::PeerUPEvent;
 pub use helpers::{create_test_multiaddr, extract_peer_id_from_multiaddr, validate_multiaddr};
-pub use state::PeerUPBehaviourState;
+pub use peer_limit::{PeerRateLimiter, PeerVerdict};
+pub use rate_limit::TopicRateLimiter;
+pub use state::{PeerUPBehaviourState, TopicCounters};
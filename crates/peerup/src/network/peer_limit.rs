@@ -0,0 +1,154 @@
+//! Per-peer inbound rate limiting and greylisting.
+//!
+//! Counts everything a peer sends us, gossip it published on any topic and
+//! probe requests alike, in fixed windows. Messages over the limit are dropped. A
+//! peer that goes over the limit in several windows in a row is greylisted:
+//! everything it sends is dropped until the greylist expires. Each repeat
+//! offence doubles how long the peer stays greylisted.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use libp2p::PeerId;
+
+use crate::node::PeerRateLimit;
+
+/// Number of tracked peers above which idle entries are pruned, at most once
+/// a window
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// Greylist durations stop doubling after this many repeat offences
+const MAX_ESCALATION: u32 = 4;
+
+/// What to do with a message from a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerVerdict {
+    /// Within the limit
+    Allow,
+    /// Over the limit in the current window; drop the message
+    Throttled,
+    /// The peer is greylisted; drop the message
+    Greylisted,
+    /// The peer was greylisted by this message for `duration`; `offences`
+    /// counts how often it has been greylisted, this time included
+    NewlyGreylisted { duration: Duration, offences: u32 },
+}
+
+impl PeerVerdict {
+    /// Whether the message should be handled
+    pub fn allowed(self) -> bool {
+        self == PeerVerdict::Allow
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PeerWindow {
+    started: Instant,
+    count: u32,
+    /// Windows in a row that went over the limit
+    strikes: u32,
+    greylisted_until: Option<Instant>,
+    offences: u32,
+}
+
+/// Fixed-window rate limiter keyed by the sending peer
+#[derive(Debug, Clone)]
+pub struct PeerRateLimiter {
+    limit: PeerRateLimit,
+    peers: HashMap<PeerId, PeerWindow>,
+    /// When the peers may next be pruned
+    next_prune: Option<Instant>,
+}
+
+impl PeerRateLimiter {
+    /// Create a limiter enforcing `limit`
+    pub fn new(limit: PeerRateLimit) -> Self {
+        Self { limit, peers: HashMap::new(), next_prune: None }
+    }
+
+    /// Record a message from `peer` and decide what to do with it
+    pub fn check(&mut self, peer: PeerId, now: Instant) -> PeerVerdict {
+        // Entries only go idle over whole windows, so a busy limiter does not
+        // scan them all on every message
+        if self.peers.len() > PRUNE_THRESHOLD && self.next_prune.is_none_or(|at| now >= at) {
+            self.prune(now);
+        }
+
+        let limit = self.limit;
+        let entry = self.peers.entry(peer).or_insert(PeerWindow {
+            started: now,
+            count: 0,
+            strikes: 0,
+            greylisted_until: None,
+            offences: 0,
+        });
+
+        if let Some(until) = entry.greylisted_until {
+            if now < until {
+                return PeerVerdict::Greylisted;
+            }
+            entry.greylisted_until = None;
+        }
+
+        let elapsed = now.duration_since(entry.started);
+        if elapsed >= limit.window {
+            // Strikes only add up over windows in a row that all went over
+            if entry.count <= limit.max_messages || elapsed >= limit.window * 2 {
+                entry.strikes = 0;
+            }
+            entry.started = now;
+            entry.count = 0;
+        }
+
+        entry.count += 1;
+        if entry.count <= limit.max_messages {
+            return PeerVerdict::Allow;
+        }
+        if entry.count == limit.max_messages + 1 {
+            entry.strikes += 1;
+        }
+        if entry.strikes < limit.strikes {
+            return PeerVerdict::Throttled;
+        }
+
+        entry.strikes = 0;
+        entry.offences += 1;
+        let duration = limit.greylist_duration * 2u32.pow((entry.offences - 1).min(MAX_ESCALATION));
+        entry.greylisted_until = Some(now + duration);
+        PeerVerdict::NewlyGreylisted { duration, offences: entry.offences }
+    }
+
+    /// Whether `peer` is greylisted at `now`
+    pub fn is_greylisted(&self, peer: &PeerId, now: Instant) -> bool {
+        self.peers
+            .get(peer)
+            .and_then(|entry| entry.greylisted_until)
+            .is_some_and(|until| now < until)
+    }
+
+    /// Peers greylisted at `now`, with when their greylist expires
+    pub fn greylisted(&self, now: Instant) -> Vec<(PeerId, Instant)> {
+        self.peers
+            .iter()
+            .filter_map(|(peer, entry)| {
+                entry.greylisted_until.filter(|until| now < *until).map(|until| (*peer, until))
+            })
+            .collect()
+    }
+
+    /// Drop idle peers, remembering past offenders for as long as their
+    /// longest greylist would last
+    pub fn prune(&mut self, now: Instant) {
+        let window = self.limit.window * 2;
+        let remember = self.limit.greylist_duration * 2u32.pow(MAX_ESCALATION);
+        self.peers.retain(|_, entry| {
+            let idle = now.duration_since(entry.started);
+            idle < window
+                || entry.greylisted_until.is_some_and(|until| now < until)
+                || (entry.offences > 0 && idle < remember)
+        });
+        self.next_prune = Some(now + self.limit.window);
+    }
+}
@@ -0,0 +1,57 @@
+//! Per-topic gossip rate limiting.
+//!
+//! Counts messages per (source peer, topic) in fixed windows so a single
+//! publisher cannot flood a topic and drown out honest traffic.
+
+use std::{collections::HashMap, time::Instant};
+
+use libp2p::PeerId;
+
+use crate::node::GossipRateLimit;
+
+/// Number of tracked windows above which stale entries are pruned, at most
+/// once a window
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// Fixed-window rate limiter keyed by source peer and topic
+#[derive(Debug, Clone)]
+pub struct TopicRateLimiter {
+    limit: GossipRateLimit,
+    windows: HashMap<(PeerId, String), (Instant, u32)>,
+    /// When the windows may next be pruned
+    next_prune: Option<Instant>,
+}
+
+impl TopicRateLimiter {
+    /// Create a limiter enforcing `limit`
+    pub fn new(limit: GossipRateLimit) -> Self {
+        Self { limit, windows: HashMap::new(), next_prune: None }
+    }
+
+    /// Record a message and return whether it is within the limit
+    pub fn check(&mut self, source: PeerId, topic: &str, now: Instant) -> bool {
+        // Windows only go stale once one has elapsed, so a busy limiter does
+        // not scan them all on every message
+        if self.windows.len() > PRUNE_THRESHOLD && self.next_prune.is_none_or(|at| now >= at) {
+            self.prune(now);
+        }
+
+        let window = self.limit.window;
+        let (started, count) = self.windows.entry((source, topic.to_string())).or_insert((now, 0));
+
+        if now.duration_since(*started) >= window {
+            *started = now;
+            *count = 0;
+        }
+
+        *count += 1;
+        *count <= self.limit.max_messages
+    }
+
+    /// Drop windows that have already elapsed
+    pub fn prune(&mut self, now: Instant) {
+        let window = self.limit.window;
+        self.windows.retain(|_, (started, _)| now.duration_since(*started) < window);
+        self.next_prune = Some(now + window);
+    }
+}
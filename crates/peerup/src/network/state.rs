@@ -3,22 +3,83 @@
 //! This module manages the internal state that's not part of the
 //! NetworkBehaviour.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Instant};
 
 use libp2p::PeerId;
 
+use super::{event_log::EventLog, peer_limit::PeerRateLimiter, rate_limit::TopicRateLimiter};
+use crate::protocol::ProtocolTraffic;
+
+/// Gossip message and byte counters for a single topic
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TopicCounters {
+    /// Messages published by this node
+    pub messages_published: u64,
+    /// Messages received from peers
+    pub messages_received: u64,
+    /// Payload bytes published by this node
+    pub bytes_published: u64,
+    /// Payload bytes received from peers
+    pub bytes_received: u64,
+}
+
 /// Internal state for PeerUPBehaviour
 pub struct PeerUPBehaviourState {
     /// Track pending outbound requests
     pub pending_requests: HashMap<u64, PeerId>,
     /// Request counter for tracking
     pub request_counter: u64,
+    /// Gossipsub topics and how many consumers currently hold a subscription
+    pub topic_subscriptions: HashMap<String, usize>,
+    /// Per-topic gossip counters since the node started
+    pub topic_counters: HashMap<String, TopicCounters>,
+    /// Bytes sent and received over the probe protocol
+    pub probe_traffic: ProtocolTraffic,
+    /// Bytes sent and received over the results sync protocol
+    pub sync_traffic: ProtocolTraffic,
+    /// When the node state was created
+    pub started_at: Instant,
+    /// Per-topic gossip rate limiter (disabled when `None`)
+    pub rate_limiter: Option<TopicRateLimiter>,
+    /// Per-peer inbound rate limiter and greylist (disabled when `None`)
+    pub peer_limiter: Option<PeerRateLimiter>,
+    /// Recent swarm, gossip and DHT activity
+    pub event_log: EventLog,
+    /// Counters at the time of the previous metrics snapshot, used for rates
+    pub(crate) last_snapshot: (Instant, HashMap<String, TopicCounters>),
 }
 
 impl PeerUPBehaviourState {
     /// Create a new state instance
     pub fn new() -> Self {
-        Self { pending_requests: HashMap::new(), request_counter: 0 }
+        let now = Instant::now();
+        Self {
+            pending_requests: HashMap::new(),
+            request_counter: 0,
+            topic_subscriptions: HashMap::new(),
+            topic_counters: HashMap::new(),
+            probe_traffic: ProtocolTraffic::default(),
+            sync_traffic: ProtocolTraffic::default(),
+            started_at: now,
+            rate_limiter: None,
+            peer_limiter: None,
+            event_log: EventLog::default(),
+            last_snapshot: (now, HashMap::new()),
+        }
+    }
+
+    /// Record a gossip message published on a topic
+    pub fn record_published(&mut self, topic: &str, bytes: usize) {
+        let counters = self.topic_counters.entry(topic.to_string()).or_default();
+        counters.messages_published += 1;
+        counters.bytes_published += bytes as u64;
+    }
+
+    /// Record a gossip message received on a topic
+    pub fn record_received(&mut self, topic: &str, bytes: usize) {
+        let counters = self.topic_counters.entry(topic.to_string()).or_default();
+        counters.messages_received += 1;
+        counters.bytes_received += bytes as u64;
     }
 
     /// Get the next request ID
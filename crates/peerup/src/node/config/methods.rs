@@ -2,7 +2,12 @@
 //!
 //! This module defines the configuration methods for PeerUP nodes.
 
-use super::types::{NodeConfig, NodeConfigBuilder};
+use std::time::Duration;
+
+use libp2p::PeerId;
+
+use super::types::{DialPolicy, GossipRateLimit, NodeConfig, NodeConfigBuilder, PeerRateLimit};
+use crate::{discovery::RecordValidators, handlers::TargetPolicy, relay::RelayServerLimits};
 
 impl NodeConfig {
     /// Enable or disable mDNS discovery
@@ -23,6 +28,20 @@ impl NodeConfig {
         self
     }
 
+    /// Enable or disable hole punching over relayed connections
+    pub fn with_hole_punching(mut self, enable: bool) -> Self {
+        self.enable_hole_punching = enable;
+        self
+    }
+
+    /// Host a relay for NATed peers within the given limits; enables relay
+    /// support
+    pub fn relay_server(mut self, limits: RelayServerLimits) -> Self {
+        self.enable_relay = true;
+        self.relay_server = Some(limits);
+        self
+    }
+
     /// Set bootstrap peers
     pub fn with_bootstrap_peers(mut self, peers: Vec<String>) -> Self {
         self.bootstrap_peers = peers;
@@ -40,6 +59,66 @@ impl NodeConfig {
         self.port_range = range;
         self
     }
+
+    /// Set the metrics snapshot interval
+    pub fn with_metrics_interval(mut self, interval: Duration) -> Self {
+        self.metrics_interval = interval;
+        self
+    }
+
+    /// Enable or disable gossipsub peer scoring
+    pub fn with_peer_scoring(mut self, enable: bool) -> Self {
+        self.enable_peer_scoring = enable;
+        self
+    }
+
+    /// Set the per-topic gossip rate limit (`None` disables it)
+    pub fn with_gossip_rate_limit(mut self, limit: Option<GossipRateLimit>) -> Self {
+        self.gossip_rate_limit = limit;
+        self
+    }
+
+    /// Set the per-peer inbound rate limit (`None` disables it)
+    pub fn with_peer_rate_limit(mut self, limit: Option<PeerRateLimit>) -> Self {
+        self.peer_rate_limit = limit;
+        self
+    }
+
+    /// Set the outbound dial policy
+    pub fn with_dial_policy(mut self, policy: DialPolicy) -> Self {
+        self.dial_policy = policy;
+        self
+    }
+
+    /// Set how many recent network events are kept
+    pub fn with_event_log_capacity(mut self, capacity: usize) -> Self {
+        self.event_log_capacity = capacity;
+        self
+    }
+
+    /// Set the checks DHT records from peers must pass before they are stored
+    pub fn with_record_validators(mut self, validators: RecordValidators) -> Self {
+        self.record_validators = validators;
+        self
+    }
+
+    /// Set which targets probe requests may reach
+    pub fn with_probe_targets(mut self, policy: TargetPolicy) -> Self {
+        self.probe_targets = policy;
+        self
+    }
+
+    /// Set the only peers allowed to connect (empty allows every peer)
+    pub fn with_allowed_peers(mut self, peers: Vec<PeerId>) -> Self {
+        self.allowed_peers = peers;
+        self
+    }
+
+    /// Set the peers refused at connection time
+    pub fn with_blocked_peers(mut self, peers: Vec<PeerId>) -> Self {
+        self.blocked_peers = peers;
+        self
+    }
 }
 
 impl NodeConfigBuilder {
@@ -107,4 +186,89 @@ impl NodeConfigBuilder {
         self.config.enable_relay = false;
         self
     }
+
+    /// Enable hole punching over relayed connections
+    pub fn enable_hole_punching(mut self) -> Self {
+        self.config.enable_hole_punching = true;
+        self
+    }
+
+    /// Disable hole punching over relayed connections
+    pub fn disable_hole_punching(mut self) -> Self {
+        self.config.enable_hole_punching = false;
+        self
+    }
+
+    /// Host a relay for NATed peers within the given limits; enables relay
+    /// support
+    pub fn relay_server(mut self, limits: RelayServerLimits) -> Self {
+        self.config = self.config.relay_server(limits);
+        self
+    }
+
+    /// Set the metrics snapshot interval
+    pub fn metrics_interval(mut self, interval: Duration) -> Self {
+        self.config.metrics_interval = interval;
+        self
+    }
+
+    /// Enable gossipsub peer scoring
+    pub fn enable_peer_scoring(mut self) -> Self {
+        self.config.enable_peer_scoring = true;
+        self
+    }
+
+    /// Disable gossipsub peer scoring
+    pub fn disable_peer_scoring(mut self) -> Self {
+        self.config.enable_peer_scoring = false;
+        self
+    }
+
+    /// Set the per-topic gossip rate limit (`None` disables it)
+    pub fn gossip_rate_limit(mut self, limit: Option<GossipRateLimit>) -> Self {
+        self.config.gossip_rate_limit = limit;
+        self
+    }
+
+    /// Set the per-peer inbound rate limit (`None` disables it)
+    pub fn peer_rate_limit(mut self, limit: Option<PeerRateLimit>) -> Self {
+        self.config.peer_rate_limit = limit;
+        self
+    }
+
+    /// Set the outbound dial policy
+    pub fn dial_policy(mut self, policy: DialPolicy) -> Self {
+        self.config.dial_policy = policy;
+        self
+    }
+
+    /// Set how many recent network events are kept
+    pub fn event_log_capacity(mut self, capacity: usize) -> Self {
+        self.config.event_log_capacity = capacity;
+        self
+    }
+
+    /// Set the checks DHT records from peers must pass before they are stored
+    pub fn record_validators(mut self, validators: RecordValidators) -> Self {
+        self.config.record_validators = validators;
+        self
+    }
+
+    /// Set which targets probe requests may reach
+    pub fn probe_targets(mut self, policy: TargetPolicy) -> Self {
+        self.config.probe_targets = policy;
+        self
+    }
+
+    /// Only let these peers connect, in addition to any allowed before
+    pub fn allow_peers(mut self, peers: impl IntoIterator<Item = PeerId>) -> Self {
+        self.config.allowed_peers.extend(peers);
+        self
+    }
+
+    /// Refuse these peers, in addition to any blocked before
+    pub fn block_peers(mut self, peers: impl IntoIterator<Item = PeerId>) -> Self {
+        self.config.blocked_peers.extend(peers);
+        self
+    }
 }
@@ -5,4 +5,4 @@
 mod methods;
 mod types;
 
-pub use types::{NodeConfig, NodeConfigBuilder};
+pub use types::{DialPolicy, GossipRateLimit, NodeConfig, NodeConfigBuilder, PeerRateLimit};
@@ -2,7 +2,14 @@
 //!
 //! This module defines the configuration data structures for PeerUP nodes.
 
-use crate::DEFAULT_PORT_RANGE;
+use std::time::Duration;
+
+use libp2p::PeerId;
+
+use crate::{
+    discovery::RecordValidators, handlers::TargetPolicy,
+    network::event_log::DEFAULT_EVENT_LOG_CAPACITY, relay::RelayServerLimits, DEFAULT_PORT_RANGE,
+};
 
 /// Configuration options for a PeerUP node
 #[derive(Debug, Clone)]
@@ -24,6 +31,118 @@ pub struct NodeConfig {
 
     /// Whether to enable relay support
     pub enable_relay: bool,
+
+    /// Whether peers that meet over a relay try to upgrade to a direct
+    /// connection (DCUtR hole punching); needs relay support
+    pub enable_hole_punching: bool,
+
+    /// Limits of the relay when the node hosts one for NATed peers; libp2p's
+    /// defaults apply when `None`
+    pub relay_server: Option<RelayServerLimits>,
+
+    /// How often consumers should take a metrics snapshot
+    pub metrics_interval: Duration,
+
+    /// Whether to enable gossipsub peer scoring
+    pub enable_peer_scoring: bool,
+
+    /// Per-peer, per-topic gossip rate limit (no limit when `None`)
+    pub gossip_rate_limit: Option<GossipRateLimit>,
+
+    /// Per-peer limit on everything a peer sends, with greylisting of
+    /// repeat offenders (no limit when `None`)
+    pub peer_rate_limit: Option<PeerRateLimit>,
+
+    /// Outbound dial concurrency and redial backoff
+    pub dial_policy: DialPolicy,
+
+    /// Number of recent network events kept in memory
+    pub event_log_capacity: usize,
+
+    /// Targets probe requests from peers may reach; public addresses only
+    /// unless the operator allows internal ones
+    pub probe_targets: TargetPolicy,
+
+    /// Checks DHT records from peers must pass before they are stored; every
+    /// record is stored when empty
+    pub record_validators: RecordValidators,
+
+    /// Peers allowed to connect; when not empty every other peer is refused,
+    /// bootstrap and relay peers included
+    pub allowed_peers: Vec<PeerId>,
+
+    /// Peers refused at connection time, whose gossip is dropped and who are
+    /// kept out of the Kademlia routing table
+    pub blocked_peers: Vec<PeerId>,
+}
+
+/// Outbound connection policy for dials initiated by the node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DialPolicy {
+    /// Maximum number of outbound dials in flight at once
+    pub max_concurrent_dials: usize,
+    /// Delay before the first redial of a failed address
+    pub initial_backoff: Duration,
+    /// Upper bound for the exponential redial delay
+    pub max_backoff: Duration,
+}
+
+impl Default for DialPolicy {
+    fn default() -> Self {
+        Self {
+            max_concurrent_dials: 8,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Gossip message rate limit applied to each message source on each topic
+///
+/// Messages over the limit are ignored rather than rejected, so a publisher
+/// that is merely busy isn't penalised. The default lets a node publish the
+/// results of 1,500 monitors checked every 30 seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GossipRateLimit {
+    /// Messages accepted from one source on one topic per window
+    pub max_messages: u32,
+    /// Length of the rate limit window
+    pub window: Duration,
+}
+
+impl Default for GossipRateLimit {
+    fn default() -> Self {
+        Self { max_messages: 3000, window: Duration::from_secs(60) }
+    }
+}
+
+/// Inbound message limit applied to each peer across all topics and
+/// request-response protocols
+///
+/// Gossip is counted against the peer that published it, requests against
+/// the peer that sent them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerRateLimit {
+    /// Messages accepted from one peer per window
+    pub max_messages: u32,
+    /// Length of the rate limit window
+    pub window: Duration,
+    /// Windows in a row over the limit before the peer is greylisted
+    pub strikes: u32,
+    /// How long a first offender stays greylisted; doubles with each repeat
+    pub greylist_duration: Duration,
+}
+
+impl Default for PeerRateLimit {
+    fn default() -> Self {
+        Self {
+            // Above the gossip limit, which covers a single topic
+            max_messages: 6000,
+            window: Duration::from_secs(60),
+            strikes: 3,
+            greylist_duration: Duration::from_secs(600),
+        }
+    }
 }
 
 impl Default for NodeConfig {
@@ -35,6 +154,18 @@ impl Default for NodeConfig {
             enable_mdns: true,
             enable_kademlia: true,
             enable_relay: true,
+            enable_hole_punching: true,
+            relay_server: None,
+            metrics_interval: Duration::from_secs(30),
+            enable_peer_scoring: true,
+            gossip_rate_limit: Some(GossipRateLimit::default()),
+            peer_rate_limit: Some(PeerRateLimit::default()),
+            dial_policy: DialPolicy::default(),
+            event_log_capacity: DEFAULT_EVENT_LOG_CAPACITY,
+            probe_targets: TargetPolicy::default(),
+            record_validators: RecordValidators::default(),
+            allowed_peers: Vec::new(),
+            blocked_peers: Vec::new(),
         }
     }
 }
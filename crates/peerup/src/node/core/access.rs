@@ -0,0 +1,59 @@
+//! Peer allow and block list methods for PeerNode.
+//!
+//! The lists gate connections at the swarm level. Blocked peers are also
+//! blacklisted in gossipsub, so messages they published are dropped even
+//! when relayed by others, and removed from the Kademlia routing table.
+
+use libp2p::PeerId;
+use tracing::info;
+
+use crate::node::core::peer_node::PeerNode;
+
+impl PeerNode {
+    /// Block a peer, closing its connections; returns false if it was already
+    /// blocked
+    pub fn block_peer(&mut self, peer: PeerId) -> bool {
+        let behaviour = self.swarm.behaviour_mut();
+        if behaviour.blocked_peers.blocked_peers().contains(&peer) {
+            return false;
+        }
+
+        behaviour.blocked_peers.block_peer(peer);
+        behaviour.gossipsub.blacklist_peer(&peer);
+        if let Some(kademlia) = behaviour.kademlia.as_mut() {
+            kademlia.remove_peer(&peer);
+        }
+        info!("Blocked peer {}", peer);
+        true
+    }
+
+    /// Lift a block set at runtime or in the configuration; returns false if
+    /// the peer was not blocked
+    pub fn unblock_peer(&mut self, peer: PeerId) -> bool {
+        let behaviour = self.swarm.behaviour_mut();
+        if !behaviour.blocked_peers.blocked_peers().contains(&peer) {
+            return false;
+        }
+
+        behaviour.blocked_peers.unblock_peer(peer);
+        behaviour.gossipsub.remove_blacklisted_peer(&peer);
+        info!("Unblocked peer {}", peer);
+        true
+    }
+
+    /// Peers currently blocked
+    pub fn blocked_peers(&self) -> Vec<PeerId> {
+        self.swarm.behaviour().blocked_peers.blocked_peers().iter().copied().collect()
+    }
+
+    /// Whether a peer may connect: it is not blocked and, when an allowlist is
+    /// configured, on it
+    pub fn is_peer_permitted(&self, peer: &PeerId) -> bool {
+        let behaviour = self.swarm.behaviour();
+        !behaviour.blocked_peers.blocked_peers().contains(peer)
+            && behaviour
+                .allowed_peers
+                .as_ref()
+                .is_none_or(|allowed| allowed.allowed_peers().contains(peer))
+    }
+}
@@ -0,0 +1,44 @@
+//! DHT record methods for PeerNode.
+//!
+//! Records are stored and looked up through Kademlia; results of a lookup
+//! arrive later as `PeerUPEvent::Kademlia` events carrying the returned query ID.
+
+use anyhow::{anyhow, Result};
+use libp2p::kad::{self, QueryId, Quorum, Record, RecordKey};
+
+use crate::node::core::peer_node::PeerNode;
+
+impl PeerNode {
+    /// Store a record under `key` locally and on the peers closest to it
+    ///
+    /// Records failing the configured record validators are refused, since
+    /// peers enforcing the same checks would not store them either.
+    pub fn put_record(&mut self, key: impl AsRef<[u8]>, value: Vec<u8>) -> Result<QueryId> {
+        self.config.record_validators.validate(key.as_ref(), &value)?;
+        let kademlia = self.kademlia()?;
+        let record = Record::new(RecordKey::new(&key), value);
+
+        kademlia
+            .put_record(record, Quorum::One)
+            .map_err(|e| anyhow!("Failed to store DHT record: {:?}", e))
+    }
+
+    /// Start looking up the records stored under `key`
+    pub fn get_record(&mut self, key: impl AsRef<[u8]>) -> Result<QueryId> {
+        Ok(self.kademlia()?.get_record(RecordKey::new(&key)))
+    }
+
+    /// Stop a running lookup early; its last progress event reports it as
+    /// finished. Returns false if the query is no longer running
+    pub fn finish_query(&mut self, id: QueryId) -> Result<bool> {
+        Ok(self.kademlia()?.query_mut(&id).map(|mut query| query.finish()).is_some())
+    }
+
+    fn kademlia(&mut self) -> Result<&mut kad::Behaviour<kad::store::MemoryStore>> {
+        self.swarm
+            .behaviour_mut()
+            .kademlia
+            .as_mut()
+            .ok_or_else(|| anyhow!("Kademlia is not enabled"))
+    }
+}
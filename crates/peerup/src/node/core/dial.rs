@@ -0,0 +1,146 @@
+//! Outbound dial scheduling for PeerNode.
+//!
+//! Dials go through a small scheduler that caps concurrency and redials
+//! failed addresses with exponential backoff and jitter, so dead bootstrap
+//! nodes don't turn into dial storms.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use libp2p::{
+    swarm::{dial_opts::DialOpts, ConnectionId},
+    Multiaddr,
+};
+use rand::Rng;
+
+use crate::node::{config::DialPolicy, core::peer_node::PeerNode};
+
+/// Failure history for a single address
+#[derive(Debug, Clone, Copy)]
+struct Backoff {
+    failures: u32,
+    retry_at: Instant,
+}
+
+/// Dial queue with concurrency limit and per-address backoff
+#[derive(Debug, Clone)]
+pub struct DialScheduler {
+    policy: DialPolicy,
+    queue: VecDeque<Multiaddr>,
+    in_flight: HashMap<ConnectionId, Multiaddr>,
+    backoff: HashMap<Multiaddr, Backoff>,
+}
+
+impl DialScheduler {
+    /// Create a scheduler enforcing `policy`
+    pub fn new(policy: DialPolicy) -> Self {
+        Self { policy, queue: VecDeque::new(), in_flight: HashMap::new(), backoff: HashMap::new() }
+    }
+
+    /// Queue an address for dialing unless it is already queued or in flight
+    pub fn schedule(&mut self, addr: Multiaddr) {
+        if !self.queue.contains(&addr) && !self.in_flight.values().any(|a| *a == addr) {
+            self.queue.push_back(addr);
+        }
+    }
+
+    /// Take the addresses that may be dialed now
+    ///
+    /// Addresses still backing off stay queued; at most
+    /// `max_concurrent_dials` dials are in flight at once.
+    pub fn ready(&mut self, now: Instant) -> Vec<Multiaddr> {
+        let slots = self.policy.max_concurrent_dials.saturating_sub(self.in_flight.len());
+        let mut ready = Vec::new();
+        let mut waiting = VecDeque::new();
+
+        while let Some(addr) = self.queue.pop_front() {
+            let backing_off = self.backoff.get(&addr).is_some_and(|b| b.retry_at > now);
+            if ready.len() < slots && !backing_off {
+                ready.push(addr);
+            } else {
+                waiting.push_back(addr);
+            }
+        }
+
+        self.queue = waiting;
+        ready
+    }
+
+    /// Record that a dial to `addr` started with connection `id`
+    pub fn started(&mut self, id: ConnectionId, addr: Multiaddr) {
+        self.in_flight.insert(id, addr);
+    }
+
+    /// Record a successful dial, clearing the address backoff
+    pub fn succeeded(&mut self, id: ConnectionId) {
+        if let Some(addr) = self.in_flight.remove(&id) {
+            self.backoff.remove(&addr);
+        }
+    }
+
+    /// Record a failed dial and queue a redial, returning the backoff delay
+    ///
+    /// Returns `None` for connections the scheduler did not start.
+    pub fn failed(&mut self, id: ConnectionId, now: Instant) -> Option<Duration> {
+        let addr = self.in_flight.remove(&id)?;
+        let failures = self.backoff.get(&addr).map_or(0, |b| b.failures) + 1;
+        let delay = self.backoff_delay(failures);
+
+        self.backoff.insert(addr.clone(), Backoff { failures, retry_at: now + delay });
+        self.schedule(addr);
+        Some(delay)
+    }
+
+    /// Exponential delay for the given failure count, with +/-20% jitter
+    pub fn backoff_delay(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(16);
+        let base = self.policy.initial_backoff.saturating_mul(1 << exponent);
+        let capped = base.min(self.policy.max_backoff);
+        capped.mul_f64(rand::thread_rng().gen_range(0.8..=1.2))
+    }
+
+    /// Earliest time a queued address comes out of backoff
+    pub fn next_retry(&self) -> Option<Instant> {
+        self.queue.iter().filter_map(|addr| self.backoff.get(addr).map(|b| b.retry_at)).min()
+    }
+
+    /// Number of dials currently in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Number of addresses waiting to be dialed
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+impl PeerNode {
+    /// Start every dial the scheduler allows right now
+    pub(crate) fn drive_dials(&mut self) {
+        for addr in self.dialer.ready(Instant::now()) {
+            let opts = DialOpts::unknown_peer_id().address(addr.clone()).build();
+            let connection_id = opts.connection_id();
+
+            match self.swarm.dial(opts) {
+                Ok(()) => {
+                    tracing::info!("Dialing peer at {}", addr);
+                    self.dialer.started(connection_id, addr);
+                }
+                Err(e) => {
+                    // Synchronous failures still go through backoff
+                    tracing::warn!("Failed to dial {}: {}", addr, e);
+                    self.dialer.started(connection_id, addr);
+                    self.dialer.failed(connection_id, Instant::now());
+                }
+            }
+        }
+    }
+
+    /// Dial scheduler state (in-flight and queued dials)
+    pub fn dial_scheduler(&self) -> &DialScheduler {
+        &self.dialer
+    }
+}
@@ -0,0 +1,181 @@
+//! Event subscription filtering for PeerNode.
+//!
+//! Consumers register the event classes they care about so that
+//! [`PeerNode::next_event`] only hands back matching events. Without a filter
+//! every event is delivered.
+
+use libp2p::{kad, swarm::SwarmEvent};
+
+use crate::{network::PeerUPEvent, node::core::peer_node::PeerNode};
+
+/// A class of events a consumer is interested in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventInterest {
+    /// Gossip messages and subscription changes on topics starting with the prefix
+    GossipTopicPrefix(String),
+    /// Kademlia record events for keys starting with the prefix
+    DhtKeyPrefix(Vec<u8>),
+    /// Connections opening, closing or upgrading from relayed to direct, and
+    /// peers being discovered, removed or greylisted
+    Connections,
+    /// Probe request-response traffic
+    Probes,
+    /// Results sync request-response traffic
+    ResultsSync,
+    /// Remaining swarm-level events (listeners, relay, mDNS, ...)
+    Swarm,
+}
+
+/// Set of event interests registered by a consumer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    interests: Vec<EventInterest>,
+}
+
+impl EventFilter {
+    /// Create a filter that matches nothing until interests are added
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an interest to the filter
+    pub fn with(mut self, interest: EventInterest) -> Self {
+        if !self.interests.contains(&interest) {
+            self.interests.push(interest);
+        }
+        self
+    }
+
+    /// Match gossip traffic on topics starting with `prefix`
+    pub fn gossip_topic_prefix(self, prefix: impl Into<String>) -> Self {
+        self.with(EventInterest::GossipTopicPrefix(prefix.into()))
+    }
+
+    /// Match DHT record events for keys starting with `prefix`
+    pub fn dht_key_prefix(self, prefix: impl Into<Vec<u8>>) -> Self {
+        self.with(EventInterest::DhtKeyPrefix(prefix.into()))
+    }
+
+    /// Match connection and peer discovery events
+    pub fn connections(self) -> Self {
+        self.with(EventInterest::Connections)
+    }
+
+    /// Match probe request-response events
+    pub fn probes(self) -> Self {
+        self.with(EventInterest::Probes)
+    }
+
+    /// Match results sync request-response events
+    pub fn results_sync(self) -> Self {
+        self.with(EventInterest::ResultsSync)
+    }
+
+    /// Match remaining swarm-level events
+    pub fn swarm(self) -> Self {
+        self.with(EventInterest::Swarm)
+    }
+
+    /// Registered interests
+    pub fn interests(&self) -> &[EventInterest] {
+        &self.interests
+    }
+
+    /// Whether the event belongs to one of the registered classes
+    pub fn matches(&self, event: &SwarmEvent<PeerUPEvent>) -> bool {
+        match event {
+            SwarmEvent::Behaviour(PeerUPEvent::GossipsubMessage { message, .. }) => {
+                self.matches_topic(message.topic.as_str())
+            }
+            SwarmEvent::Behaviour(
+                PeerUPEvent::GossipsubSubscribed { topic, .. }
+                | PeerUPEvent::GossipsubUnsubscribed { topic, .. },
+            ) => self.matches_topic(&topic.to_string()),
+            SwarmEvent::Behaviour(PeerUPEvent::Kademlia(event)) => match record_key(event) {
+                Some(key) => self.matches_dht_key(key.as_ref()),
+                None => self.matches_dht_key(&[]),
+            },
+            SwarmEvent::Behaviour(PeerUPEvent::RecordRejected { key, .. }) => {
+                self.matches_dht_key(key.as_ref())
+            }
+            SwarmEvent::Behaviour(
+                PeerUPEvent::PeerDiscovered(_)
+                | PeerUPEvent::PeerRemoved(_)
+                | PeerUPEvent::PeerGreylisted { .. }
+                | PeerUPEvent::ConnectionEstablished(_)
+                | PeerUPEvent::ConnectionClosed(_)
+                | PeerUPEvent::Dcutr(_),
+            )
+            | SwarmEvent::ConnectionEstablished { .. }
+            | SwarmEvent::ConnectionClosed { .. }
+            | SwarmEvent::OutgoingConnectionError { .. }
+            | SwarmEvent::IncomingConnectionError { .. } => {
+                self.interests.contains(&EventInterest::Connections)
+            }
+            SwarmEvent::Behaviour(
+                PeerUPEvent::ProbeRequestReceived { .. }
+                | PeerUPEvent::ProbeResponseReceived { .. }
+                | PeerUPEvent::ProbeResponseSent { .. }
+                | PeerUPEvent::OutboundProbeFailure { .. }
+                | PeerUPEvent::InboundProbeFailure { .. }
+                | PeerUPEvent::RequestResponse(_),
+            ) => self.interests.contains(&EventInterest::Probes),
+            SwarmEvent::Behaviour(
+                PeerUPEvent::ResultsSyncRequestReceived { .. }
+                | PeerUPEvent::ResultsSyncResponseReceived { .. }
+                | PeerUPEvent::ResultsSyncResponseSent { .. }
+                | PeerUPEvent::OutboundResultsSyncFailure { .. }
+                | PeerUPEvent::InboundResultsSyncFailure { .. },
+            ) => self.interests.contains(&EventInterest::ResultsSync),
+            _ => self.interests.contains(&EventInterest::Swarm),
+        }
+    }
+
+    fn matches_topic(&self, topic: &str) -> bool {
+        self.interests.iter().any(|interest| {
+            matches!(interest, EventInterest::GossipTopicPrefix(prefix) if topic.starts_with(prefix.as_str()))
+        })
+    }
+
+    /// Keyless DHT events (bootstrap, routing updates) only match an empty prefix
+    fn matches_dht_key(&self, key: &[u8]) -> bool {
+        self.interests.iter().any(|interest| {
+            matches!(interest, EventInterest::DhtKeyPrefix(prefix) if key.starts_with(prefix))
+        })
+    }
+}
+
+/// Extract the record key a Kademlia event refers to, if any
+fn record_key(event: &kad::Event) -> Option<kad::RecordKey> {
+    use kad::{Event::*, QueryResult::*};
+
+    match event {
+        OutboundQueryProgressed {
+            result: GetRecord(Ok(kad::GetRecordOk::FoundRecord(r))), ..
+        } => Some(r.record.key.clone()),
+        OutboundQueryProgressed { result: GetRecord(Err(e)), .. } => Some(e.key().clone()),
+        OutboundQueryProgressed { result: PutRecord(Ok(ok)), .. } => Some(ok.key.clone()),
+        OutboundQueryProgressed { result: PutRecord(Err(e)), .. } => Some(e.key().clone()),
+        InboundRequest { request: kad::InboundRequest::PutRecord { record: Some(r), .. } } => {
+            Some(r.key.clone())
+        }
+        _ => None,
+    }
+}
+
+impl PeerNode {
+    /// Only deliver events matching `filter` from [`PeerNode::next_event`]
+    pub fn set_event_filter(&mut self, filter: EventFilter) {
+        self.event_filter = Some(filter);
+    }
+
+    /// Deliver every event again
+    pub fn clear_event_filter(&mut self) {
+        self.event_filter = None;
+    }
+
+    /// The currently registered event filter, if any
+    pub fn event_filter(&self) -> Option<&EventFilter> {
+        self.event_filter.as_ref()
+    }
+}
@@ -9,53 +9,132 @@ use crate::node::core::peer_node::PeerNode;
 pub const MONITORING_RESULTS_TOPIC: &str = "uppe/monitoring/results/v1";
 
 impl PeerNode {
+    /// Subscribe to a gossipsub topic, returning the new reference count
+    ///
+    /// The underlying gossipsub subscription is only created for the first
+    /// reference, so independent consumers can share a topic safely.
+    pub fn subscribe_topic(&mut self, topic: &str) -> Result<usize> {
+        let count = self.state.topic_subscriptions.get(topic).copied().unwrap_or(0);
+
+        if count == 0 {
+            self.swarm
+                .behaviour_mut()
+                .gossipsub
+                .subscribe(&IdentTopic::new(topic))
+                .map_err(|e| anyhow::anyhow!("Failed to subscribe to topic: {}", e))?;
+            tracing::info!("Subscribed to topic {}", topic);
+        }
+
+        self.state.topic_subscriptions.insert(topic.to_string(), count + 1);
+        Ok(count + 1)
+    }
+
+    /// Release one reference to a gossipsub topic, returning the remaining count
+    ///
+    /// The gossipsub subscription is dropped once the last reference goes away.
+    pub fn unsubscribe_topic(&mut self, topic: &str) -> Result<usize> {
+        let Some(count) = self.state.topic_subscriptions.get_mut(topic) else {
+            tracing::warn!("Was not subscribed to topic {}", topic);
+            return Ok(0);
+        };
+
+        *count -= 1;
+        let remaining = *count;
+
+        if remaining == 0 {
+            self.state.topic_subscriptions.remove(topic);
+            self.swarm.behaviour_mut().gossipsub.unsubscribe(&IdentTopic::new(topic));
+            tracing::info!("Unsubscribed from topic {}", topic);
+        }
+
+        Ok(remaining)
+    }
+
+    /// Topics with at least one active subscription and their reference counts
+    pub fn active_subscriptions(&self) -> Vec<(String, usize)> {
+        let mut subscriptions: Vec<(String, usize)> = self
+            .state
+            .topic_subscriptions
+            .iter()
+            .map(|(topic, count)| (topic.clone(), *count))
+            .collect();
+        subscriptions.sort();
+        subscriptions
+    }
+
+    /// Re-issue gossipsub subscriptions for every tracked topic
+    ///
+    /// Useful after the swarm was rebuilt or gossipsub lost its topic state;
+    /// topics that are already subscribed are left untouched.
+    pub fn resubscribe_all(&mut self) -> Result<()> {
+        let topics: Vec<String> = self.state.topic_subscriptions.keys().cloned().collect();
+
+        for topic in topics {
+            self.swarm
+                .behaviour_mut()
+                .gossipsub
+                .subscribe(&IdentTopic::new(topic.as_str()))
+                .map_err(|e| anyhow::anyhow!("Failed to resubscribe to {}: {}", topic, e))?;
+        }
+        Ok(())
+    }
+
     /// Subscribe to the monitoring results topic
     pub fn subscribe_to_results(&mut self) -> Result<()> {
-        let topic = IdentTopic::new(MONITORING_RESULTS_TOPIC);
-        self.swarm
-            .behaviour_mut()
-            .gossipsub
-            .subscribe(&topic)
-            .map_err(|e| anyhow::anyhow!("Failed to subscribe to topic: {}", e))?;
-        tracing::info!("Subscribed to monitoring results topic");
+        self.subscribe_topic(MONITORING_RESULTS_TOPIC)?;
         Ok(())
     }
 
     /// Unsubscribe from the monitoring results topic
     pub fn unsubscribe_from_results(&mut self) -> Result<()> {
-        let topic = IdentTopic::new(MONITORING_RESULTS_TOPIC);
-        let was_subscribed = self.swarm.behaviour_mut().gossipsub.unsubscribe(&topic);
-
-        if was_subscribed {
-            tracing::info!("Unsubscribed from monitoring results topic");
-        } else {
-            tracing::warn!("Was not subscribed to monitoring results topic");
-        }
+        self.unsubscribe_topic(MONITORING_RESULTS_TOPIC)?;
         Ok(())
     }
 
     /// Publish a monitoring result to the network
-    pub fn publish_result(&mut self, result_json: String) -> Result<()> {
-        let topic = IdentTopic::new(MONITORING_RESULTS_TOPIC);
+    ///
+    /// Takes the payload as JSON text or bytes, e.g. from a
+    /// [`WireEncoder`](crate::protocol::WireEncoder); an owned buffer is
+    /// handed to gossipsub without copying it.
+    pub fn publish_result(&mut self, payload: impl Into<Vec<u8>>) -> Result<()> {
+        self.publish_to_topic(MONITORING_RESULTS_TOPIC, payload)
+    }
 
-        match self.swarm.behaviour_mut().gossipsub.publish(topic, result_json.as_bytes()) {
+    /// Publish a payload to any gossip topic, treating a lack of subscribed
+    /// peers as success like `publish_result` does
+    pub fn publish_to_topic(&mut self, topic: &str, payload: impl Into<Vec<u8>>) -> Result<()> {
+        let payload = payload.into();
+        let len = payload.len();
+
+        match self.swarm.behaviour_mut().gossipsub.publish(IdentTopic::new(topic), payload) {
             Ok(_) => {
-                tracing::debug!("Published result to gossipsub network");
+                self.state.record_published(topic, len);
+                tracing::debug!("Published message to gossipsub topic {}", topic);
                 Ok(())
             }
             Err(e) => {
                 // Some libp2p versions do not expose a typed InsufficientPeers variant; detect by message content.
                 let msg = e.to_string();
                 if msg.contains("InsufficientPeers") || msg.to_lowercase().contains("no peers") {
-                    tracing::debug!("No peers connected to receive published result (normal during startup or isolation)");
+                    tracing::debug!(
+                        "No peers connected to receive published message on {} (normal during startup or isolation)",
+                        topic
+                    );
                     Ok(())
                 } else {
-                    Err(anyhow::anyhow!("Failed to publish result: {}", e))
+                    Err(anyhow::anyhow!("Failed to publish to {}: {}", topic, e))
                 }
             }
         }
     }
 
+    /// Whether any connected peer is subscribed to the results topic, so a
+    /// published result reaches at least one node
+    pub fn has_result_peers(&self) -> bool {
+        let topic = TopicHash::from_raw(MONITORING_RESULTS_TOPIC);
+        self.swarm.behaviour().gossipsub.all_peers().any(|(_, topics)| topics.contains(&&topic))
+    }
+
     /// Get list of peers subscribed to a topic
     pub fn get_topic_peers(&self, topic: &str) -> Vec<libp2p::PeerId> {
         let topic_hash = TopicHash::from_raw(topic);
@@ -0,0 +1,165 @@
+//! Metrics snapshot for PeerNode.
+
+use std::time::Instant;
+
+use libp2p::gossipsub::TopicHash;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    node::core::peer_node::PeerNode,
+    protocol::{PROBE_PROTOCOL, RESULTS_SYNC_PROTOCOL},
+};
+
+/// Gossip statistics for a single topic
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TopicMetrics {
+    /// Topic name
+    pub topic: String,
+    /// Whether this node is currently subscribed to the topic
+    pub subscribed: bool,
+    /// Number of peers in our mesh for this topic
+    pub mesh_peers: usize,
+    /// Messages published since the node started
+    pub messages_published: u64,
+    /// Messages received since the node started
+    pub messages_received: u64,
+    /// Published messages per second since the previous snapshot
+    pub publish_rate: f64,
+    /// Received messages per second since the previous snapshot
+    pub receive_rate: f64,
+    /// Payload bytes published since the node started
+    pub bytes_published: u64,
+    /// Payload bytes received since the node started
+    pub bytes_received: u64,
+}
+
+/// Traffic for a request-response protocol
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolMetrics {
+    /// Protocol name
+    pub protocol: String,
+    /// Bytes written since the node started
+    pub bytes_sent: u64,
+    /// Bytes read since the node started
+    pub bytes_received: u64,
+}
+
+/// Point-in-time metrics for a PeerUP node
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeMetrics {
+    /// Number of peers with an open connection
+    pub connected_peers: usize,
+    /// Per-topic gossip statistics
+    pub topics: Vec<TopicMetrics>,
+    /// Number of entries in the Kademlia routing table (0 when disabled)
+    pub routing_table_size: usize,
+    /// Outbound probe requests awaiting a response
+    pub pending_queries: usize,
+    /// Total gossip payload bytes published
+    pub bytes_published: u64,
+    /// Total gossip payload bytes received
+    pub bytes_received: u64,
+    /// Per-protocol request-response traffic
+    pub protocols: Vec<ProtocolMetrics>,
+    /// Seconds since the node was created
+    pub uptime_secs: u64,
+}
+
+impl PeerNode {
+    /// Take a metrics snapshot of the node
+    ///
+    /// Message rates are computed against the previous call, so calling this
+    /// on a fixed interval yields per-interval rates.
+    pub fn metrics(&mut self) -> NodeMetrics {
+        let now = Instant::now();
+        let (last_at, last_counters) = &self.state.last_snapshot;
+        let elapsed = now.duration_since(*last_at).as_secs_f64().max(f64::EPSILON);
+
+        let subscribed = self.get_subscribed_topics();
+        let mut topics: Vec<TopicMetrics> = self
+            .state
+            .topic_counters
+            .iter()
+            .map(|(topic, counters)| {
+                let previous = last_counters.get(topic).copied().unwrap_or_default();
+                let topic_hash = TopicHash::from_raw(topic.as_str());
+
+                TopicMetrics {
+                    topic: topic.clone(),
+                    subscribed: subscribed.contains(topic),
+                    mesh_peers: self.swarm.behaviour().gossipsub.mesh_peers(&topic_hash).count(),
+                    messages_published: counters.messages_published,
+                    messages_received: counters.messages_received,
+                    publish_rate: (counters.messages_published - previous.messages_published)
+                        as f64
+                        / elapsed,
+                    receive_rate: (counters.messages_received - previous.messages_received) as f64
+                        / elapsed,
+                    bytes_published: counters.bytes_published,
+                    bytes_received: counters.bytes_received,
+                }
+            })
+            .collect();
+
+        // Subscribed topics without any traffic yet still show up in the snapshot
+        for topic in subscribed {
+            if !topics.iter().any(|t| t.topic == topic) {
+                let topic_hash = TopicHash::from_raw(topic.as_str());
+                topics.push(TopicMetrics {
+                    mesh_peers: self.swarm.behaviour().gossipsub.mesh_peers(&topic_hash).count(),
+                    topic,
+                    subscribed: true,
+                    ..Default::default()
+                });
+            }
+        }
+        topics.sort_by(|a, b| a.topic.cmp(&b.topic));
+
+        let routing_table_size = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .as_mut()
+            .map(|kademlia| kademlia.kbuckets().map(|bucket| bucket.num_entries()).sum())
+            .unwrap_or(0);
+
+        let (bytes_published, bytes_received) = self
+            .state
+            .topic_counters
+            .values()
+            .fold((0, 0), |(out, inc), c| (out + c.bytes_published, inc + c.bytes_received));
+
+        self.state.last_snapshot = (now, self.state.topic_counters.clone());
+
+        NodeMetrics {
+            connected_peers: self.swarm.connected_peers().count(),
+            topics,
+            routing_table_size,
+            pending_queries: self.state.pending_requests.len(),
+            bytes_published,
+            bytes_received,
+            protocols: vec![
+                ProtocolMetrics {
+                    protocol: PROBE_PROTOCOL.to_string(),
+                    bytes_sent: self.state.probe_traffic.bytes_sent(),
+                    bytes_received: self.state.probe_traffic.bytes_received(),
+                },
+                ProtocolMetrics {
+                    protocol: RESULTS_SYNC_PROTOCOL.to_string(),
+                    bytes_sent: self.state.sync_traffic.bytes_sent(),
+                    bytes_received: self.state.sync_traffic.bytes_received(),
+                },
+            ],
+            uptime_secs: self.state.started_at.elapsed().as_secs(),
+        }
+    }
+}
+
+impl NodeMetrics {
+    /// All bytes sent and received over gossip and request-response protocols
+    pub fn total_bytes(&self) -> u64 {
+        self.bytes_published
+            + self.bytes_received
+            + self.protocols.iter().map(|p| p.bytes_sent + p.bytes_received).sum::<u64>()
+    }
+}
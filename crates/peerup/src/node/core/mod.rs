@@ -2,9 +2,21 @@
 //!
 //! This module contains the core PeerNode struct and its methods.
 
+mod access;
+mod dht;
+mod dial;
+mod filter;
 pub mod gossipsub;
+mod metrics;
 mod node_methods;
 mod peer_node;
+mod probe;
+mod results_sync;
 mod run;
+mod shutdown;
 
+pub use dial::DialScheduler;
+pub use filter::{EventFilter, EventInterest};
+pub use metrics::{NodeMetrics, ProtocolMetrics, TopicMetrics};
 pub use peer_node::PeerNode;
+pub use shutdown::ShutdownSummary;
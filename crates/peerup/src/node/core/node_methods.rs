@@ -6,7 +6,7 @@ use tracing::info;
 
 use super::peer_node::PeerNode;
 use crate::{
-    network::{PeerUPBehaviour, PeerUPBehaviourState},
+    network::{EventLog, PeerRateLimiter, PeerUPBehaviour, PeerUPBehaviourState, TopicRateLimiter},
     node::{config::NodeConfig, crypto::load_or_generate_keypair},
     transport,
 };
@@ -32,25 +32,41 @@ impl PeerNode {
         // Set up transport (used by swarm builder)
         let _transport = transport::build_transport(&keypair)?;
 
+        let mut state = PeerUPBehaviourState::new();
+        state.rate_limiter = config.gossip_rate_limit.map(TopicRateLimiter::new);
+        state.peer_limiter = config.peer_rate_limit.map(PeerRateLimiter::new);
+        state.event_log = EventLog::new(config.event_log_capacity);
+
         // Create behavior
-        let behaviour = PeerUPBehaviour::new(&keypair, &config).await?;
+        let behaviour = PeerUPBehaviour::new(
+            &keypair,
+            &config,
+            state.probe_traffic.clone(),
+            state.sync_traffic.clone(),
+        )
+        .await?;
 
         // Build the swarm
-        let swarm = libp2p::SwarmBuilder::with_new_identity()
+        let swarm = libp2p::SwarmBuilder::with_existing_identity(keypair.clone())
             .with_tokio()
             .with_tcp(
                 libp2p::tcp::Config::default(),
                 libp2p::noise::Config::new,
                 libp2p::yamux::Config::default,
             )?
-            .with_behaviour(|_| behaviour)?
+            .with_relay_client(libp2p::noise::Config::new, libp2p::yamux::Config::default)?
+            .with_behaviour(|_, relay_client| {
+                if config.enable_relay {
+                    behaviour.with_relay_client(relay_client)
+                } else {
+                    behaviour
+                }
+            })?
             .with_swarm_config(|c| {
                 c.with_idle_connection_timeout(std::time::Duration::from_secs(60))
             })
             .build();
 
-        let state = PeerUPBehaviourState::new();
-
         Ok(PeerNode::new_internal(swarm, peer_id, config, Vec::new(), state))
     }
 
@@ -85,18 +101,51 @@ impl PeerNode {
         Ok(())
     }
 
+    /// Reserve a slot on a relay and listen through it, so peers that cannot
+    /// dial us directly reach us over the relay; with hole punching enabled
+    /// those connections are then upgraded to direct ones
+    ///
+    /// `relay` is the relay's address including its `/p2p/<peer id>`.
+    pub fn listen_via_relay(&mut self, relay: &str) -> Result<()> {
+        use libp2p::{multiaddr::Protocol, Multiaddr};
+
+        if !self.swarm.behaviour().relay_client.is_enabled() {
+            anyhow::bail!("Relay support is disabled");
+        }
+        let relay: Multiaddr =
+            relay.parse().map_err(|e| anyhow::anyhow!("Invalid multiaddr '{}': {}", relay, e))?;
+        if !relay.iter().any(|protocol| matches!(protocol, Protocol::P2p(_))) {
+            anyhow::bail!("Relay address '{}' has no /p2p/<peer id>", relay);
+        }
+
+        let addr = relay.with(Protocol::P2pCircuit);
+        let listener_id = self
+            .swarm
+            .listen_on(addr.clone())
+            .map_err(|e| anyhow::anyhow!("Failed to listen via relay {}: {}", addr, e))?;
+        info!("Listening via relay on {}", addr);
+        self.listeners.push((listener_id, addr));
+        Ok(())
+    }
+
     /// Dial a peer at the specified address
+    ///
+    /// The dial is queued behind the configured concurrency limit and retried
+    /// with backoff if it fails. Addresses of peers that may not connect are
+    /// refused.
     pub fn dial(&mut self, addr: &str) -> Result<()> {
-        use libp2p::Multiaddr;
+        use libp2p::{multiaddr::Protocol, Multiaddr};
 
         let multiaddr: Multiaddr =
             addr.parse().map_err(|e| anyhow::anyhow!("Invalid multiaddr '{}': {}", addr, e))?;
+        if let Some(Protocol::P2p(peer)) = multiaddr.iter().last() {
+            if !self.is_peer_permitted(&peer) {
+                anyhow::bail!("Peer {} is blocked or not allowed", peer);
+            }
+        }
 
-        self.swarm
-            .dial(multiaddr.clone())
-            .map_err(|e| anyhow::anyhow!("Failed to dial {}: {}", multiaddr, e))?;
-
-        info!("Dialing peer at {}", multiaddr);
+        self.dialer.schedule(multiaddr);
+        self.drive_dials();
         Ok(())
     }
 
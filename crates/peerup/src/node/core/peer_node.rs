@@ -1,9 +1,19 @@
 //! PeerNode struct definition.
 
-use libp2p::{core::transport::ListenerId, multiaddr::Multiaddr, swarm::Swarm, PeerId};
+use std::collections::VecDeque;
+use std::sync::Arc;
 
+use libp2p::{
+    core::transport::ListenerId,
+    multiaddr::Multiaddr,
+    swarm::{Swarm, SwarmEvent},
+    PeerId,
+};
+
+use super::{dial::DialScheduler, filter::EventFilter};
 use crate::{
-    network::{PeerUPBehaviour, PeerUPBehaviourState},
+    handlers::TargetPolicy,
+    network::{PeerUPBehaviour, PeerUPBehaviourState, PeerUPEvent},
     node::config::NodeConfig,
 };
 
@@ -23,8 +33,26 @@ pub struct PeerNode {
 
     /// Network behaviour state
     pub state: PeerUPBehaviourState,
+
+    /// Event classes consumers want delivered (all events when `None`)
+    pub(crate) event_filter: Option<EventFilter>,
+
+    /// Outbound dial queue with concurrency limit and backoff
+    pub(crate) dialer: DialScheduler,
+
+    /// Events raised by the node itself, delivered before the next swarm event
+    pub(crate) pending_events: VecDeque<SwarmEvent<PeerUPEvent>>,
+
+    /// Targets probe requests may reach, built from the configuration once
+    pub(crate) probe_policy: Arc<TargetPolicy>,
 }
 
+// PeerNode must stay Send so consumers can drive it from a multi-threaded runtime
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    assert_send::<PeerNode>();
+};
+
 impl PeerNode {
     /// Get the peer ID of this node
     pub fn peer_id(&self) -> PeerId {
@@ -48,6 +76,18 @@ impl PeerNode {
         listeners: Vec<(ListenerId, Multiaddr)>,
         state: PeerUPBehaviourState,
     ) -> Self {
-        Self { swarm, peer_id, config, listeners, state }
+        let dialer = DialScheduler::new(config.dial_policy);
+        let probe_policy = Arc::new(config.probe_targets.clone());
+        Self {
+            swarm,
+            peer_id,
+            config,
+            listeners,
+            state,
+            event_filter: None,
+            dialer,
+            pending_events: VecDeque::new(),
+            probe_policy,
+        }
     }
 }
@@ -0,0 +1,52 @@
+//! Probe request/response methods for PeerNode.
+//!
+//! Outbound probes are tracked as pending requests until their response or
+//! failure comes back through `next_event`, so shutdown can wait for them.
+
+use std::future::Future;
+
+use anyhow::Result;
+use libp2p::{request_response::ResponseChannel, PeerId};
+
+use crate::handlers;
+use crate::network::conversions::request_response::request_id_value;
+use crate::node::core::peer_node::PeerNode;
+use crate::protocol::{ProbeRequest, ProbeResponse};
+
+impl PeerNode {
+    /// Ask a peer to probe a target, returning the request ID its response
+    /// or failure event will carry
+    pub fn send_probe(&mut self, peer: &PeerId, request: ProbeRequest) -> u64 {
+        let request_id = request_id_value(
+            self.swarm.behaviour_mut().request_response.send_request(peer, request),
+        );
+        self.state.add_pending_request(request_id, *peer);
+        tracing::debug!("Sent probe request #{} to {}", request_id, peer);
+        request_id
+    }
+
+    /// Probe the target of a peer's request under the node's target policy
+    ///
+    /// The returned future does not borrow the node, so it can be spawned
+    /// while the node keeps running; hand its response to `respond_probe`.
+    pub fn answer_probe(
+        &self,
+        request: ProbeRequest,
+    ) -> impl Future<Output = ProbeResponse> + Send + 'static {
+        let policy = self.probe_policy.clone();
+        async move { handlers::handle_probe_request_with_policy(request, &policy).await }
+    }
+
+    /// Answer a probe request received from a peer
+    pub fn respond_probe(
+        &mut self,
+        channel: ResponseChannel<ProbeResponse>,
+        response: ProbeResponse,
+    ) -> Result<()> {
+        self.swarm
+            .behaviour_mut()
+            .request_response
+            .send_response(channel, response)
+            .map_err(|_| anyhow::anyhow!("Probe requester is no longer connected"))
+    }
+}
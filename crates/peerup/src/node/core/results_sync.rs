@@ -0,0 +1,35 @@
+//! Results sync request/response methods for PeerNode.
+//!
+//! Unlike probes, sync requests are not tracked as pending: a node catching
+//! up can simply ask again after a restart, so shutdown does not wait for them.
+
+use anyhow::Result;
+use libp2p::{request_response::ResponseChannel, PeerId};
+
+use crate::network::conversions::request_response::request_id_value;
+use crate::node::core::peer_node::PeerNode;
+use crate::protocol::{ResultsSyncRequest, ResultsSyncResponse};
+
+impl PeerNode {
+    /// Ask a peer for a page of the results it stored, returning the request
+    /// ID its response or failure event will carry
+    pub fn send_results_sync(&mut self, peer: &PeerId, request: ResultsSyncRequest) -> u64 {
+        let request_id =
+            request_id_value(self.swarm.behaviour_mut().results_sync.send_request(peer, request));
+        tracing::debug!("Sent results sync request #{} to {}", request_id, peer);
+        request_id
+    }
+
+    /// Answer a results sync request received from a peer
+    pub fn respond_results_sync(
+        &mut self,
+        channel: ResponseChannel<ResultsSyncResponse>,
+        response: ResultsSyncResponse,
+    ) -> Result<()> {
+        self.swarm
+            .behaviour_mut()
+            .results_sync
+            .send_response(channel, response)
+            .map_err(|_| anyhow::anyhow!("Results sync requester is no longer connected"))
+    }
+}
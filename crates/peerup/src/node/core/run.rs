@@ -1,22 +1,288 @@
 //! PeerNode run loop for driving the swarm and handling events.
 
 use anyhow::Result;
+use std::time::Instant;
+
 use futures::StreamExt;
-use tokio::signal;
-use tracing::info;
+use libp2p::{
+    gossipsub,
+    kad::{self, store::RecordStore},
+    swarm::SwarmEvent,
+    PeerId,
+};
+use tokio::{signal, sync::mpsc};
+use tracing::{info, warn};
 
 use super::peer_node::PeerNode;
+use crate::network::{NetworkEventKind, NetworkLogEntry, PeerUPEvent, PeerVerdict};
 
 impl PeerNode {
+    /// Wait for the next swarm event, updating node counters along the way.
+    ///
+    /// Consumers driving their own select loop should prefer this over polling
+    /// `swarm` directly so that metrics stay accurate. When an event filter is
+    /// set, events outside the registered interests are consumed here.
+    pub async fn next_event(&mut self) -> SwarmEvent<PeerUPEvent> {
+        loop {
+            let event = match self.pending_events.pop_front() {
+                Some(event) => event,
+                None => {
+                    let retry_at = self.dialer.next_retry();
+                    tokio::select! {
+                        event = self.swarm.select_next_some() => event,
+                        _ = sleep_until_retry(retry_at) => {
+                            self.drive_dials();
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            if !self.admit_inbound(&event) {
+                continue;
+            }
+
+            self.state.event_log.push(NetworkLogEntry::from_event(&event));
+
+            match &event {
+                SwarmEvent::ConnectionEstablished { connection_id, .. } => {
+                    self.dialer.succeeded(*connection_id);
+                    self.drive_dials();
+                }
+                SwarmEvent::OutgoingConnectionError { connection_id, .. } => {
+                    if let Some(delay) = self.dialer.failed(*connection_id, Instant::now()) {
+                        tracing::debug!("Dial failed, retrying in {:?}", delay);
+                    }
+                    self.drive_dials();
+                }
+                SwarmEvent::Behaviour(
+                    PeerUPEvent::ProbeResponseReceived { request_id, .. }
+                    | PeerUPEvent::OutboundProbeFailure { request_id, .. },
+                ) => {
+                    self.state.remove_pending_request(*request_id);
+                }
+                _ => {}
+            }
+
+            if let SwarmEvent::Behaviour(PeerUPEvent::GossipsubMessage {
+                peer,
+                message_id,
+                message,
+            }) = &event
+            {
+                if !self.validate_gossip(peer, message_id, message) {
+                    continue;
+                }
+                self.state.record_received(message.topic.as_str(), message.data.len());
+            }
+
+            // Peers outside the allow and block lists can't connect, but
+            // Kademlia may still learn of them through addresses we add
+            if let SwarmEvent::Behaviour(PeerUPEvent::Kademlia(kad::Event::RoutingUpdated {
+                peer,
+                ..
+            })) = &event
+            {
+                if !self.is_peer_permitted(peer) {
+                    if let Some(kademlia) = self.swarm.behaviour_mut().kademlia.as_mut() {
+                        kademlia.remove_peer(peer);
+                    }
+                    continue;
+                }
+            }
+
+            if let SwarmEvent::Behaviour(PeerUPEvent::Kademlia(kad::Event::InboundRequest {
+                request: kad::InboundRequest::PutRecord { source, record: Some(record), .. },
+            })) = &event
+            {
+                self.store_inbound_record(*source, record.clone());
+            }
+
+            match &self.event_filter {
+                Some(filter) if !filter.matches(&event) => {
+                    tracing::trace!("Filtered swarm event: {:?}", event);
+                }
+                _ => return event,
+            }
+        }
+    }
+
+    /// Recent network events, newest first
+    pub fn recent_events(&self, limit: usize) -> Vec<NetworkLogEntry> {
+        self.state.event_log.recent(limit)
+    }
+
+    /// Recent network events of one kind, newest first
+    pub fn recent_events_of_kind(
+        &self,
+        kind: NetworkEventKind,
+        limit: usize,
+    ) -> Vec<NetworkLogEntry> {
+        self.state.event_log.recent_of_kind(kind, limit)
+    }
+
+    /// Check a gossip message, probe or results sync request against its
+    /// sender's rate limit, returning whether it should be handled
+    ///
+    /// Gossip counts against the peer that published it rather than the
+    /// neighbour that relayed it, so relaying other publishers' traffic never
+    /// uses up a neighbour's allowance. Dropped gossip is rejected (penalising
+    /// the sender's score) only when a greylisted publisher sent it to us
+    /// itself, and ignored otherwise. Dropped probe and results sync requests
+    /// are never answered. A peer being greylisted is reported as a
+    /// `PeerGreylisted` event.
+    fn admit_inbound(&mut self, event: &SwarmEvent<PeerUPEvent>) -> bool {
+        let (peer, relayed) = match event {
+            SwarmEvent::Behaviour(PeerUPEvent::GossipsubMessage { peer, message, .. }) => {
+                let source = message.source.unwrap_or(*peer);
+                (source, source != *peer)
+            }
+            SwarmEvent::Behaviour(
+                PeerUPEvent::ProbeRequestReceived { peer, .. }
+                | PeerUPEvent::ResultsSyncRequestReceived { peer, .. },
+            ) => (*peer, false),
+            _ => return true,
+        };
+        let Some(limiter) = &mut self.state.peer_limiter else {
+            return true;
+        };
+
+        let verdict = limiter.check(peer, Instant::now());
+        match verdict {
+            PeerVerdict::Allow => return true,
+            PeerVerdict::Throttled => {
+                tracing::debug!("Dropping message from {}: rate limited", peer);
+            }
+            PeerVerdict::Greylisted => {
+                tracing::trace!("Dropping message from greylisted peer {}", peer);
+            }
+            PeerVerdict::NewlyGreylisted { duration, offences } => {
+                warn!(
+                    "Greylisting {} for {:?} after repeated floods (offence {})",
+                    peer, duration, offences
+                );
+                self.pending_events.push_back(SwarmEvent::Behaviour(PeerUPEvent::PeerGreylisted {
+                    peer,
+                    duration,
+                    offences,
+                }));
+            }
+        }
+
+        if let SwarmEvent::Behaviour(PeerUPEvent::GossipsubMessage {
+            peer: propagation_source,
+            message_id,
+            ..
+        }) = event
+        {
+            let acceptance = if verdict == PeerVerdict::Throttled || relayed {
+                gossipsub::MessageAcceptance::Ignore
+            } else {
+                gossipsub::MessageAcceptance::Reject
+            };
+            self.swarm.behaviour_mut().gossipsub.report_message_validation_result(
+                message_id,
+                propagation_source,
+                acceptance,
+            );
+        }
+        false
+    }
+
+    /// Report the validation result for a gossip message, returning whether it was accepted
+    ///
+    /// Messages over the rate limit and messages published by a peer missing
+    /// from the allowlist are ignored: they are dropped without penalising
+    /// anyone's score, as honest publishers can go over the limit too.
+    fn validate_gossip(
+        &mut self,
+        propagation_source: &PeerId,
+        message_id: &gossipsub::MessageId,
+        message: &gossipsub::Message,
+    ) -> bool {
+        let source = message.source.unwrap_or(*propagation_source);
+        if !self.is_peer_permitted(&source) {
+            tracing::debug!("Ignoring gossip published by {}: not allowed", source);
+            self.swarm.behaviour_mut().gossipsub.report_message_validation_result(
+                message_id,
+                propagation_source,
+                gossipsub::MessageAcceptance::Ignore,
+            );
+            return false;
+        }
+        let within_limit = match &mut self.state.rate_limiter {
+            Some(limiter) => limiter.check(source, message.topic.as_str(), Instant::now()),
+            None => true,
+        };
+
+        let acceptance = if within_limit {
+            gossipsub::MessageAcceptance::Accept
+        } else {
+            tracing::debug!("Ignoring gossip from {} on {}: rate limited", source, message.topic);
+            gossipsub::MessageAcceptance::Ignore
+        };
+
+        self.swarm.behaviour_mut().gossipsub.report_message_validation_result(
+            message_id,
+            propagation_source,
+            acceptance,
+        );
+        within_limit
+    }
+
+    /// Store a record a peer asked us to keep if it passes the registered
+    /// record validators
+    ///
+    /// Kademlia only hands records over for validation when validators are
+    /// registered. Rejected records are reported as a `RecordRejected` event.
+    fn store_inbound_record(&mut self, source: PeerId, record: kad::Record) {
+        if let Err(e) = self.config.record_validators.validate(record.key.as_ref(), &record.value) {
+            tracing::debug!("Rejecting DHT record from {}: {}", source, e);
+            self.pending_events.push_back(SwarmEvent::Behaviour(PeerUPEvent::RecordRejected {
+                peer: source,
+                key: record.key,
+                reason: e.to_string(),
+            }));
+            return;
+        }
+
+        if let Some(kademlia) = self.swarm.behaviour_mut().kademlia.as_mut() {
+            if let Err(e) = kademlia.store_mut().put(record) {
+                warn!("Failed to store DHT record from {}: {:?}", source, e);
+            }
+        }
+    }
+
     /// Run the node event loop, polling the swarm and handling events.
+    /// Probe requests are answered under the configured target policy.
     /// This will block until Ctrl+C is pressed or an error occurs.
     pub async fn run(mut self) -> Result<()> {
         info!("PeerNode event loop started. Press Ctrl+C to exit.");
+        let (answers_tx, mut answers) = mpsc::unbounded_channel();
         loop {
             tokio::select! {
-                event = self.swarm.select_next_some() => {
-                    info!("Swarm event: {:?}", event);
-                    // In the future, handle events more granularly here
+                event = self.next_event() => match event {
+                    SwarmEvent::Behaviour(PeerUPEvent::ProbeRequestReceived {
+                        peer,
+                        request,
+                        channel,
+                    }) => {
+                        info!("Probing {} for {}", request.target_url, peer);
+                        let probe = self.answer_probe(request);
+                        let answers_tx = answers_tx.clone();
+                        tokio::spawn(async move {
+                            let _ = answers_tx.send((channel, probe.await));
+                        });
+                    }
+                    event => {
+                        info!("Swarm event: {:?}", event);
+                        // In the future, handle events more granularly here
+                    }
+                },
+                Some((channel, response)) = answers.recv() => {
+                    if let Err(e) = self.respond_probe(channel, response) {
+                        tracing::debug!("Failed to answer probe request: {}", e);
+                    }
                 }
                 _ = signal::ctrl_c() => {
                     info!("Ctrl+C received, shutting down node.");
@@ -27,3 +293,11 @@ impl PeerNode {
         Ok(())
     }
 }
+
+/// Sleep until the next scheduled redial, or forever when none is pending
+async fn sleep_until_retry(retry_at: Option<Instant>) {
+    match retry_at {
+        Some(at) => tokio::time::sleep_until(at.into()).await,
+        None => std::future::pending().await,
+    }
+}
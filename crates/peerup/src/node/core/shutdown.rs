@@ -0,0 +1,93 @@
+//! Graceful shutdown for PeerNode.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::StreamExt;
+use libp2p::{gossipsub::IdentTopic, swarm::SwarmEvent};
+use tokio::time::Instant;
+
+use super::gossipsub::MONITORING_RESULTS_TOPIC;
+use crate::{network::PeerUPEvent, node::core::peer_node::PeerNode, protocol::ControlMessage};
+
+/// How long the swarm is polled to flush the goodbye message to peers
+const GOODBYE_FLUSH: Duration = Duration::from_millis(250);
+
+/// What happened while shutting a node down
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownSummary {
+    /// Whether a goodbye message was handed to gossipsub
+    pub goodbye_sent: bool,
+    /// Topics that were unsubscribed
+    pub topics_unsubscribed: usize,
+    /// Listeners that were closed
+    pub listeners_closed: usize,
+    /// Outbound requests still unanswered when the drain timed out
+    pub requests_abandoned: usize,
+}
+
+impl PeerNode {
+    /// Shut the node down gracefully
+    ///
+    /// Publishes a goodbye message on the results topic, unsubscribes from
+    /// every topic, closes all listeners and then keeps polling the swarm until
+    /// in-flight probe requests are answered or `drain_timeout` elapses.
+    pub async fn shutdown(mut self, drain_timeout: Duration) -> Result<ShutdownSummary> {
+        let mut summary = ShutdownSummary::default();
+
+        let goodbye = ControlMessage::Goodbye { peer_id: self.peer_id.to_string() };
+        let payload = serde_json::to_vec(&goodbye)?;
+        summary.goodbye_sent = self
+            .swarm
+            .behaviour_mut()
+            .gossipsub
+            .publish(IdentTopic::new(MONITORING_RESULTS_TOPIC), payload)
+            .is_ok();
+
+        let topics: Vec<String> = self.state.topic_subscriptions.drain().map(|(t, _)| t).collect();
+        for topic in &topics {
+            self.swarm.behaviour_mut().gossipsub.unsubscribe(&IdentTopic::new(topic.as_str()));
+        }
+        summary.topics_unsubscribed = topics.len();
+
+        for (listener_id, addr) in self.listeners.drain(..) {
+            if self.swarm.remove_listener(listener_id) {
+                tracing::debug!("Closed listener on {}", addr);
+                summary.listeners_closed += 1;
+            }
+        }
+
+        let started = Instant::now();
+        let flush_until = started + GOODBYE_FLUSH.min(drain_timeout);
+        let drain_until = started + drain_timeout;
+
+        loop {
+            let now = Instant::now();
+            let draining = !self.state.pending_requests.is_empty() && now < drain_until;
+            if now >= flush_until && !draining {
+                break;
+            }
+
+            let deadline = if draining { drain_until } else { flush_until };
+            if let Ok(SwarmEvent::Behaviour(
+                PeerUPEvent::ProbeResponseReceived { request_id, .. }
+                | PeerUPEvent::OutboundProbeFailure { request_id, .. },
+            )) = tokio::time::timeout_at(deadline, self.swarm.select_next_some()).await
+            {
+                self.state.remove_pending_request(request_id);
+            }
+        }
+
+        summary.requests_abandoned = self.state.pending_requests.len();
+        if summary.requests_abandoned > 0 {
+            tracing::warn!(
+                "Abandoning {} in-flight request(s) after {:?}",
+                summary.requests_abandoned,
+                drain_timeout
+            );
+        }
+
+        tracing::info!("PeerNode {} shut down", self.peer_id);
+        Ok(summary)
+    }
+}
@@ -2,21 +2,24 @@
 //!
 //! This module handles network events and swarm events.
 
+use std::sync::Arc;
+
 use libp2p::{request_response::ResponseChannel, swarm::SwarmEvent, PeerId};
 use tracing::{debug, info, warn};
 
 use crate::{
-    handlers,
+    handlers::{self, TargetPolicy},
     network::PeerUPEvent,
     protocol::{ProbeRequest, ProbeResponse},
 };
 
-/// Handle a PeerUP network event
-pub fn handle_peerup_event(event: PeerUPEvent) {
+/// Handle a PeerUP network event; probe requests may only reach targets
+/// `policy` allows
+pub fn handle_peerup_event(event: PeerUPEvent, policy: &Arc<TargetPolicy>) {
     match event {
         PeerUPEvent::ProbeRequestReceived { peer, request, channel } => {
             info!("Received probe request from {}: {:?}", peer, request);
-            handle_probe_request(peer, request, channel);
+            handle_probe_request(peer, request, channel, policy.clone());
         }
         PeerUPEvent::ProbeResponseReceived { peer, request_id, response } => {
             info!("Received probe response from {} (ID: {}): {:?}", peer, request_id, response);
@@ -42,6 +45,10 @@ pub fn handle_peerup_event(event: PeerUPEvent) {
         PeerUPEvent::Relay(ev) => {
             debug!("Relay event: {:?}", ev);
         }
+        PeerUPEvent::Dcutr(ev) => match ev.result {
+            Ok(_) => info!("Upgraded to a direct connection with {}", ev.remote_peer_id),
+            Err(e) => debug!("Hole punching to {} failed: {}", ev.remote_peer_id, e),
+        },
         PeerUPEvent::Kademlia(ev) => {
             debug!("Kademlia event: {:?}", ev);
         }
@@ -59,10 +66,11 @@ fn handle_probe_request(
     peer: PeerId,
     request: ProbeRequest,
     channel: ResponseChannel<ProbeResponse>,
+    policy: Arc<TargetPolicy>,
 ) {
     // Handle the probe request asynchronously
     tokio::spawn(async move {
-        let response = handlers::handle_probe_request(request).await;
+        let response = handlers::handle_probe_request_with_policy(request, &policy).await;
 
         // Note: In libp2p 0.56+, ResponseChannel might need different handling
         // For now, we'll just drop the channel since the API has changed
@@ -71,11 +79,11 @@ fn handle_probe_request(
     });
 }
 
-/// Handle swarm events
-pub fn handle_swarm_event(event: SwarmEvent<PeerUPEvent>) {
+/// Handle swarm events; probe requests may only reach targets `policy` allows
+pub fn handle_swarm_event(event: SwarmEvent<PeerUPEvent>, policy: &Arc<TargetPolicy>) {
     match event {
         SwarmEvent::Behaviour(peerup_event) => {
-            handle_peerup_event(peerup_event);
+            handle_peerup_event(peerup_event, policy);
         }
         SwarmEvent::NewListenAddr { address, .. } => {
             info!("Listening on {}", address);
@@ -9,8 +9,11 @@ pub mod crypto;
 pub mod events;
 
 // Re-export main types
-pub use core::PeerNode;
+pub use core::{
+    EventFilter, EventInterest, NodeMetrics, PeerNode, ProtocolMetrics, ShutdownSummary,
+    TopicMetrics,
+};
 
-pub use config::{NodeConfig, NodeConfigBuilder};
+pub use config::{DialPolicy, GossipRateLimit, NodeConfig, NodeConfigBuilder, PeerRateLimit};
 pub use crypto::{generate_keypair, load_keypair, load_or_generate_keypair, save_keypair};
 pub use events::{handle_peerup_event, handle_swarm_event};
@@ -3,23 +3,95 @@
 //! This module implements the libp2p Codec trait for serializing/deserializing
 //! protocol messages.
 
-use std::io;
+use std::{
+    fmt, io,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use async_trait::async_trait;
 use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use libp2p::{request_response::Codec, StreamProtocol};
+use serde::{de::DeserializeOwned, Serialize};
 
-use super::types::{ProbeRequest, ProbeResponse};
+use super::types::{ProbeRequest, ProbeResponse, ResultsSyncRequest, ResultsSyncResponse};
 
-/// Codec for serializing/deserializing probe protocol messages
+/// Byte counters shared by every copy of a codec
 #[derive(Debug, Clone, Default)]
-pub struct ProbeCodec;
+pub struct ProtocolTraffic {
+    sent: Arc<AtomicU64>,
+    received: Arc<AtomicU64>,
+}
+
+impl ProtocolTraffic {
+    /// Total bytes written to streams
+    pub fn bytes_sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Total bytes read from streams
+    pub fn bytes_received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    fn record_sent(&self, bytes: usize) {
+        self.sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn record_received(&self, bytes: usize) {
+        self.received.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+/// Codec exchanging requests and responses as JSON
+pub struct JsonCodec<Req, Res> {
+    traffic: ProtocolTraffic,
+    messages: PhantomData<fn() -> (Req, Res)>,
+}
+
+/// Codec for serializing/deserializing probe protocol messages
+pub type ProbeCodec = JsonCodec<ProbeRequest, ProbeResponse>;
+
+/// Codec for serializing/deserializing results sync protocol messages
+pub type ResultsSyncCodec = JsonCodec<ResultsSyncRequest, ResultsSyncResponse>;
+
+impl<Req, Res> JsonCodec<Req, Res> {
+    /// Create a codec that counts its traffic into `traffic`
+    pub fn with_traffic(traffic: ProtocolTraffic) -> Self {
+        Self { traffic, messages: PhantomData }
+    }
+}
+
+impl<Req, Res> Default for JsonCodec<Req, Res> {
+    fn default() -> Self {
+        Self::with_traffic(ProtocolTraffic::default())
+    }
+}
+
+impl<Req, Res> Clone for JsonCodec<Req, Res> {
+    fn clone(&self) -> Self {
+        Self::with_traffic(self.traffic.clone())
+    }
+}
+
+impl<Req, Res> fmt::Debug for JsonCodec<Req, Res> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonCodec").field("traffic", &self.traffic).finish()
+    }
+}
 
 #[async_trait]
-impl Codec for ProbeCodec {
+impl<Req, Res> Codec for JsonCodec<Req, Res>
+where
+    Req: Serialize + DeserializeOwned + Send + 'static,
+    Res: Serialize + DeserializeOwned + Send + 'static,
+{
     type Protocol = StreamProtocol;
-    type Request = ProbeRequest;
-    type Response = ProbeResponse;
+    type Request = Req;
+    type Response = Res;
 
     async fn read_request<T>(&mut self, _: &Self::Protocol, io: &mut T) -> io::Result<Self::Request>
     where
@@ -27,6 +99,7 @@ impl Codec for ProbeCodec {
     {
         let mut buf = Vec::new();
         io.read_to_end(&mut buf).await?;
+        self.traffic.record_received(buf.len());
 
         let request = serde_json::from_slice(&buf)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
@@ -44,6 +117,7 @@ impl Codec for ProbeCodec {
     {
         let mut buf = Vec::new();
         io.read_to_end(&mut buf).await?;
+        self.traffic.record_received(buf.len());
 
         let response = serde_json::from_slice(&buf)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
@@ -65,6 +139,7 @@ impl Codec for ProbeCodec {
 
         io.write_all(&json_data).await?;
         io.close().await?;
+        self.traffic.record_sent(json_data.len());
 
         Ok(())
     }
@@ -83,6 +158,7 @@ impl Codec for ProbeCodec {
 
         io.write_all(&json_data).await?;
         io.close().await?;
+        self.traffic.record_sent(json_data.len());
 
         Ok(())
     }
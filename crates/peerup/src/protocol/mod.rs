@@ -4,9 +4,16 @@
 
 pub mod codec;
 pub mod types;
+pub mod wire;
 
-pub use codec::ProbeCodec;
-pub use types::{ProbeRequest, ProbeResponse};
+pub use codec::{JsonCodec, ProbeCodec, ProtocolTraffic, ResultsSyncCodec};
+pub use types::{
+    ControlMessage, ProbeRequest, ProbeResponse, ResultsSyncRequest, ResultsSyncResponse,
+};
+pub use wire::WireEncoder;
 
 /// Protocol name for probe requests/responses
 pub const PROBE_PROTOCOL: &str = "/peerup/probe/1.0";
+
+/// Protocol name for paging through the results a peer stored
+pub const RESULTS_SYNC_PROTOCOL: &str = "/peerup/results-sync/1.0";
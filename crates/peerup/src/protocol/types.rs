@@ -49,3 +49,48 @@ pub struct ProbeResponse {
 
     pub body: Option<String>,
 }
+
+/// A request for the results a peer stored, sent by a node catching up on
+/// what it missed while offline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResultsSyncRequest {
+    /// Monitor the results are for
+    pub monitor_id: String,
+
+    /// Only results checked at or after this time (seconds since the Unix epoch)
+    pub since: u64,
+
+    /// Cursor from the previous page's response; `None` for the first page
+    pub cursor: Option<u64>,
+
+    /// Most results to return; the responder may return fewer
+    pub limit: u32,
+}
+
+/// A page of results answering a results sync request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultsSyncResponse {
+    /// Results in the application's own encoding
+    pub results: Vec<serde_json::Value>,
+
+    /// Cursor to request the next page with; `None` on the last page
+    pub next_cursor: Option<u64>,
+}
+
+/// Control messages exchanged over gossip alongside regular payloads
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlMessage {
+    /// The sending node is shutting down
+    Goodbye {
+        /// Peer ID of the departing node
+        peer_id: String,
+    },
+}
+
+impl ControlMessage {
+    /// Parse a gossip payload as a control message, if it is one
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        serde_json::from_slice(data).ok()
+    }
+}
@@ -0,0 +1,78 @@
+//! Compact binary encoding for gossip payloads.
+//!
+//! A payload starts with [`WIRE_MAGIC`], a byte no JSON document starts with,
+//! then a kind byte chosen by the application and the value in postcard
+//! encoding. Receivers tell binary payloads from JSON ones by the first byte,
+//! so both can share a topic. [`WireEncoder`] keeps its buffer between
+//! messages and only allocates while the buffer still grows.
+//!
+//! Decoding ignores bytes after the value, so a newer layout may append
+//! fields and older receivers still read the fields they know;
+//! [`decode_prefix`] hands the appended bytes back.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// First byte of every binary payload
+pub const WIRE_MAGIC: u8 = 0xB7;
+
+/// Encoder reusing one buffer for every payload it produces
+#[derive(Debug, Default)]
+pub struct WireEncoder {
+    buffer: Vec<u8>,
+}
+
+impl WireEncoder {
+    /// Create an encoder with an empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encode `value` as a payload of the given kind
+    ///
+    /// The returned slice borrows the encoder's buffer and is overwritten by
+    /// the next call.
+    pub fn encode<T: Serialize + ?Sized>(&mut self, kind: u8, value: &T) -> Result<&[u8]> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        buffer.extend_from_slice(&[WIRE_MAGIC, kind]);
+        self.buffer = postcard::to_extend(value, buffer)
+            .map_err(|e| anyhow!("Failed to encode payload: {}", e))?;
+        Ok(&self.buffer)
+    }
+
+    /// Bytes the buffer can hold without growing
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+}
+
+/// Kind of a binary payload, `None` for anything else
+pub fn wire_kind(data: &[u8]) -> Option<u8> {
+    match data {
+        [WIRE_MAGIC, kind, ..] => Some(*kind),
+        _ => None,
+    }
+}
+
+/// Decode a binary payload of the given kind
+pub fn decode<'a, T: Deserialize<'a>>(kind: u8, data: &'a [u8]) -> Result<T> {
+    decode_prefix(kind, data).map(|(value, _)| value)
+}
+
+/// Decode the value at the start of a binary payload of the given kind,
+/// returning it with the bytes that follow it
+pub fn decode_prefix<'a, T: Deserialize<'a>>(kind: u8, data: &'a [u8]) -> Result<(T, &'a [u8])> {
+    match wire_kind(data) {
+        Some(found) if found == kind => postcard::take_from_bytes(&data[2..])
+            .map_err(|e| anyhow!("Failed to decode payload: {}", e)),
+        Some(found) => Err(anyhow!("Expected payload kind {}, got {}", kind, found)),
+        None => Err(anyhow!("Not a binary payload")),
+    }
+}
+
+/// Decode a value appended after a payload's value, as returned by
+/// [`decode_prefix`], with the bytes that follow it
+pub fn decode_appended<'a, T: Deserialize<'a>>(rest: &'a [u8]) -> Result<(T, &'a [u8])> {
+    postcard::take_from_bytes(rest).map_err(|e| anyhow!("Failed to decode appended field: {}", e))
+}
@@ -2,10 +2,84 @@
 //!
 //! This module provides configuration utilities for libp2p relay.
 
+use std::num::NonZeroU32;
+use std::time::Duration;
+
 use anyhow::Result;
 use libp2p::{relay, PeerId};
 use tracing::debug;
 
+/// Limits of a node serving as a Circuit Relay v2 relay for NATed peers
+///
+/// Reservations let a peer be reached through the relay, circuits carry the
+/// traffic of one connection through it. Both are limited in total and per
+/// peer, and each circuit is cut off once it ran for `max_circuit_duration`
+/// or carried `max_circuit_bytes`. The defaults match libp2p's, except that
+/// requests are only rate limited per peer, not per IP address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelayServerLimits {
+    /// Reservations held at once, across all peers
+    pub max_reservations: usize,
+    /// Reservations one peer may hold at once
+    pub max_reservations_per_peer: usize,
+    /// How long a reservation lasts before the peer has to renew it
+    pub reservation_duration: Duration,
+    /// Circuits relayed at once, across all peers
+    pub max_circuits: usize,
+    /// Circuits one peer may have relayed at once
+    pub max_circuits_per_peer: usize,
+    /// How long a single circuit may stay open
+    pub max_circuit_duration: Duration,
+    /// Bytes relayed over a single circuit, in both directions together
+    pub max_circuit_bytes: u64,
+    /// Reservation and circuit requests one peer may make per
+    /// `request_window`; 0 lifts the limit
+    pub max_requests_per_peer: u32,
+    /// Length of the request rate limit window
+    pub request_window: Duration,
+}
+
+impl Default for RelayServerLimits {
+    fn default() -> Self {
+        Self {
+            max_reservations: 128,
+            max_reservations_per_peer: 4,
+            reservation_duration: Duration::from_secs(60 * 60),
+            max_circuits: 16,
+            max_circuits_per_peer: 4,
+            max_circuit_duration: Duration::from_secs(2 * 60),
+            max_circuit_bytes: 1 << 17,
+            max_requests_per_peer: 30,
+            request_window: Duration::from_secs(2 * 60),
+        }
+    }
+}
+
+impl RelayServerLimits {
+    /// The libp2p relay configuration enforcing these limits
+    pub fn to_config(&self) -> relay::Config {
+        let mut config = relay::Config {
+            max_reservations: self.max_reservations,
+            max_reservations_per_peer: self.max_reservations_per_peer,
+            reservation_duration: self.reservation_duration,
+            max_circuits: self.max_circuits,
+            max_circuits_per_peer: self.max_circuits_per_peer,
+            max_circuit_duration: self.max_circuit_duration,
+            max_circuit_bytes: self.max_circuit_bytes,
+            ..relay::Config::default()
+        };
+        // Replace the default rate limits with ours
+        config.reservation_rate_limiters.clear();
+        config.circuit_src_rate_limiters.clear();
+        if let Some(limit) = NonZeroU32::new(self.max_requests_per_peer) {
+            config = config
+                .reservation_rate_per_peer(limit, self.request_window)
+                .circuit_src_per_peer(limit, self.request_window);
+        }
+        config
+    }
+}
+
 /// Configure relay client
 pub fn configure_relay_client(local_peer_id: PeerId) -> Result<relay::Behaviour> {
     let config = relay::Config::default();
@@ -15,12 +89,12 @@ pub fn configure_relay_client(local_peer_id: PeerId) -> Result<relay::Behaviour>
 }
 
 /// Configure relay server
-pub fn configure_relay_server(local_peer_id: PeerId) -> Result<relay::Behaviour> {
-    let config = relay::Config::default();
-    // Configure as relay server
-    // Note: Specific server configuration depends on libp2p version
-    let relay_behaviour = relay::Behaviour::new(local_peer_id, config);
-    debug!("Relay server initialized for peer {}", local_peer_id);
+pub fn configure_relay_server(
+    local_peer_id: PeerId,
+    limits: &RelayServerLimits,
+) -> Result<relay::Behaviour> {
+    let relay_behaviour = relay::Behaviour::new(local_peer_id, limits.to_config());
+    debug!("Relay server initialized for peer {} with {:?}", local_peer_id, limits);
     Ok(relay_behaviour)
 }
 
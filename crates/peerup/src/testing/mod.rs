@@ -0,0 +1,19 @@
+//! Network simulation harness for multi-node tests.
+//!
+//! Runs many in-process nodes over a virtual network in virtual time, so
+//! logic spanning peers (consensus, helper reassignment, results sync) can be
+//! tested deterministically. Nodes implement [`SimNode`] and exchange
+//! messages over the links of a [`Topology`], each with its own latency,
+//! jitter and loss. A [`Scenario`] scripts what happens to the network while
+//! the simulation runs: partitions, healing, churn and injected messages.
+//!
+//! Everything random is drawn from the seed the simulation was created with,
+//! so a run replays exactly.
+
+mod scenario;
+mod simulation;
+mod topology;
+
+pub use scenario::{Action, Scenario};
+pub use simulation::{Context, NodeId, SimNode, SimStats, Simulation};
+pub use topology::{LinkConfig, Topology};
@@ -0,0 +1,84 @@
+//! Scripted changes to a simulated network.
+
+use std::time::Duration;
+
+use super::simulation::NodeId;
+use super::topology::LinkConfig;
+
+/// Something that happens to the simulated network at a point in time
+#[derive(Debug, Clone)]
+pub enum Action<M> {
+    /// Take a node down; messages to it are lost and its timers cancelled
+    Stop(NodeId),
+    /// Bring a stopped node back; it keeps the state it had
+    Start(NodeId),
+    /// Split the network into groups that cannot reach each other; nodes in
+    /// no group form one more group
+    Partition(Vec<Vec<NodeId>>),
+    /// Undo the partition
+    Heal,
+    /// Add or change a link
+    Link(NodeId, NodeId, LinkConfig),
+    /// Remove a link
+    Unlink(NodeId, NodeId),
+    /// Hand a node a message as if `from` sent it, bypassing the network
+    Inject { from: NodeId, to: NodeId, message: M },
+}
+
+/// Actions scheduled at times relative to when the scenario is scheduled
+#[derive(Debug, Clone)]
+pub struct Scenario<M> {
+    pub(crate) steps: Vec<(Duration, Action<M>)>,
+}
+
+impl<M> Default for Scenario<M> {
+    fn default() -> Self {
+        Self { steps: Vec::new() }
+    }
+}
+
+impl<M> Scenario<M> {
+    /// An empty scenario
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run an action at `at`
+    pub fn at(mut self, at: Duration, action: Action<M>) -> Self {
+        self.steps.push((at, action));
+        self
+    }
+
+    /// Partition the network from `from` until `until`
+    pub fn partition(self, from: Duration, until: Duration, groups: Vec<Vec<NodeId>>) -> Self {
+        self.at(from, Action::Partition(groups)).at(until, Action::Heal)
+    }
+
+    /// Stop a node every `period` for `downtime`, starting at `from`, until
+    /// `until`; the node is up again at the end. `period` should be longer
+    /// than `downtime`
+    pub fn churn(
+        mut self,
+        node: NodeId,
+        from: Duration,
+        period: Duration,
+        downtime: Duration,
+        until: Duration,
+    ) -> Self {
+        if period.is_zero() {
+            return self;
+        }
+        let mut at = from;
+        while at < until {
+            let back = (at + downtime).min(until);
+            self = self.at(at, Action::Stop(node)).at(back, Action::Start(node));
+            at += period;
+        }
+        self
+    }
+
+    /// The scheduled actions, in the order they were added
+    pub fn steps(&self) -> &[(Duration, Action<M>)] {
+        &self.steps
+    }
+}
@@ -0,0 +1,419 @@
+//! Discrete event simulation of nodes exchanging messages.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::Duration;
+
+use libp2p::{identity::Keypair, PeerId};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::scenario::{Action, Scenario};
+use super::topology::{LinkConfig, Topology};
+
+/// Index of a node in a simulation, in the order nodes were added
+pub type NodeId = usize;
+
+/// Logic of a simulated node
+///
+/// Handlers run in virtual time and act through the [`Context`] they are
+/// given; nothing they do takes effect before they return.
+pub trait SimNode {
+    /// Messages nodes exchange
+    type Message: Clone;
+
+    /// The node started, or came back after being stopped
+    fn on_start(&mut self, ctx: &mut Context<Self::Message>) {
+        let _ = ctx;
+    }
+
+    /// A message from another node arrived
+    fn on_message(
+        &mut self,
+        ctx: &mut Context<Self::Message>,
+        from: NodeId,
+        message: Self::Message,
+    );
+
+    /// A timer set with [`Context::set_timer`] fired
+    fn on_timer(&mut self, ctx: &mut Context<Self::Message>, timer: u64) {
+        let _ = (ctx, timer);
+    }
+}
+
+/// What a node handler can see of and do to the simulated network
+pub struct Context<M> {
+    id: NodeId,
+    now: Duration,
+    peers: Vec<NodeId>,
+    sends: Vec<(NodeId, M)>,
+    timers: Vec<(Duration, u64)>,
+}
+
+impl<M: Clone> Context<M> {
+    /// The node the handler runs for
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Virtual time since the simulation started
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Linked nodes that are up and on the same side of any partition
+    pub fn peers(&self) -> &[NodeId] {
+        &self.peers
+    }
+
+    /// Send a message to a node; it is lost when no link reaches it
+    pub fn send(&mut self, to: NodeId, message: M) {
+        self.sends.push((to, message));
+    }
+
+    /// Send a message to every peer
+    pub fn broadcast(&mut self, message: M) {
+        for &peer in &self.peers {
+            self.sends.push((peer, message.clone()));
+        }
+    }
+
+    /// Fire `on_timer` with `timer` after `delay`, unless the node stops first
+    pub fn set_timer(&mut self, delay: Duration, timer: u64) {
+        self.timers.push((delay, timer));
+    }
+}
+
+/// Message counts of a simulation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimStats {
+    /// Messages nodes sent
+    pub sent: u64,
+    /// Messages handed to their recipient
+    pub delivered: u64,
+    /// Messages lost to link loss
+    pub lost: u64,
+    /// Messages dropped for a missing link, a partition or a stopped node
+    pub dropped: u64,
+}
+
+enum Event<M> {
+    Deliver { from: NodeId, to: NodeId, message: M },
+    Timer { node: NodeId, epoch: u64, timer: u64 },
+    Action(Action<M>),
+}
+
+struct NodeState<N> {
+    node: N,
+    peer_id: PeerId,
+    up: bool,
+    /// Bumped on every stop, cancelling the timers set before it
+    epoch: u64,
+}
+
+/// Nodes on a simulated network, run in virtual time
+///
+/// Events happening at the same time run in the order they were scheduled,
+/// and all randomness comes from the seed, so runs are deterministic.
+pub struct Simulation<N: SimNode> {
+    seed: u64,
+    nodes: Vec<NodeState<N>>,
+    /// Links by their lower and higher node
+    links: HashMap<(NodeId, NodeId), LinkConfig>,
+    /// Partition group of each node while partitioned
+    groups: Option<Vec<usize>>,
+    /// Latest delivery time on each directed link, keeping messages in order
+    last_delivery: HashMap<(NodeId, NodeId), Duration>,
+    queue: BinaryHeap<Reverse<(Duration, u64)>>,
+    events: HashMap<u64, Event<N::Message>>,
+    next_event: u64,
+    now: Duration,
+    rng: StdRng,
+    stats: SimStats,
+}
+
+impl<N: SimNode> Simulation<N> {
+    /// An empty simulation drawing all randomness from `seed`
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            nodes: Vec::new(),
+            links: HashMap::new(),
+            groups: None,
+            last_delivery: HashMap::new(),
+            queue: BinaryHeap::new(),
+            events: HashMap::new(),
+            next_event: 0,
+            now: Duration::ZERO,
+            rng: StdRng::seed_from_u64(seed),
+            stats: SimStats::default(),
+        }
+    }
+
+    /// Add a stopped node; start it with [`Simulation::start`] once linked
+    pub fn add_node(&mut self, node: N) -> NodeId {
+        let id = self.nodes.len();
+        // Derived from the seed, so peer IDs are stable across runs
+        let mut secret = [0u8; 32];
+        StdRng::seed_from_u64(self.seed ^ (id as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15))
+            .fill(&mut secret);
+        let peer_id = Keypair::ed25519_from_bytes(secret)
+            .expect("32 bytes make an Ed25519 secret key")
+            .public()
+            .to_peer_id();
+        self.nodes.push(NodeState { node, peer_id, up: false, epoch: 0 });
+        id
+    }
+
+    /// Add the links of a topology
+    pub fn apply(&mut self, topology: &Topology) {
+        for &(a, b, link) in topology.links() {
+            self.link(a, b, link);
+        }
+    }
+
+    /// Add or change a link
+    pub fn link(&mut self, a: NodeId, b: NodeId, link: LinkConfig) {
+        self.links.insert(link_key(a, b), link);
+    }
+
+    /// Remove a link; messages already in flight still arrive
+    pub fn unlink(&mut self, a: NodeId, b: NodeId) {
+        self.links.remove(&link_key(a, b));
+    }
+
+    /// Start every stopped node, in order
+    pub fn start_all(&mut self) {
+        for id in 0..self.nodes.len() {
+            self.start(id);
+        }
+    }
+
+    /// Start a node, or bring it back after [`Simulation::stop`]
+    pub fn start(&mut self, id: NodeId) {
+        if self.nodes[id].up {
+            return;
+        }
+        self.nodes[id].up = true;
+        self.handle(id, |node, ctx| node.on_start(ctx));
+    }
+
+    /// Stop a node; messages to it are dropped and its timers cancelled
+    pub fn stop(&mut self, id: NodeId) {
+        let state = &mut self.nodes[id];
+        if state.up {
+            state.up = false;
+            state.epoch += 1;
+        }
+    }
+
+    /// Split the network into groups that cannot reach each other; nodes in
+    /// no group form one more group. Messages in flight between groups are
+    /// dropped.
+    pub fn partition(&mut self, groups: &[Vec<NodeId>]) {
+        let mut assigned = vec![groups.len(); self.nodes.len()];
+        for (group, nodes) in groups.iter().enumerate() {
+            for &node in nodes {
+                assigned[node] = group;
+            }
+        }
+        self.groups = Some(assigned);
+    }
+
+    /// Undo the partition
+    pub fn heal(&mut self) {
+        self.groups = None;
+    }
+
+    /// Schedule a scenario's actions relative to now
+    pub fn schedule(&mut self, scenario: Scenario<N::Message>) {
+        for (at, action) in scenario.steps {
+            self.push(self.now + at, Event::Action(action));
+        }
+    }
+
+    /// Run events until `until` virtual time, then advance the clock to it
+    pub fn run_until(&mut self, until: Duration) {
+        while let Some(&Reverse((at, _))) = self.queue.peek() {
+            if at > until {
+                break;
+            }
+            self.step();
+        }
+        self.now = self.now.max(until);
+    }
+
+    /// Run events for `duration` of virtual time
+    pub fn run_for(&mut self, duration: Duration) {
+        self.run_until(self.now + duration);
+    }
+
+    /// Run the next event, returning false when none is left
+    pub fn step(&mut self) -> bool {
+        let Some(Reverse((at, key))) = self.queue.pop() else {
+            return false;
+        };
+        self.now = at;
+        match self.events.remove(&key) {
+            Some(Event::Deliver { from, to, message }) => {
+                if self.nodes[to].up && self.reachable(from, to) {
+                    self.stats.delivered += 1;
+                    self.handle(to, |node, ctx| node.on_message(ctx, from, message));
+                } else {
+                    self.stats.dropped += 1;
+                }
+            }
+            Some(Event::Timer { node, epoch, timer }) => {
+                let state = &self.nodes[node];
+                if state.up && state.epoch == epoch {
+                    self.handle(node, |node, ctx| node.on_timer(ctx, timer));
+                }
+            }
+            Some(Event::Action(action)) => self.act(action),
+            None => {}
+        }
+        true
+    }
+
+    /// Virtual time since the simulation started
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the simulation has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// A node's logic
+    pub fn node(&self, id: NodeId) -> &N {
+        &self.nodes[id].node
+    }
+
+    /// A node's logic, to inspect or change it between steps
+    pub fn node_mut(&mut self, id: NodeId) -> &mut N {
+        &mut self.nodes[id].node
+    }
+
+    /// The nodes' logic, in the order they were added
+    pub fn nodes(&self) -> impl Iterator<Item = &N> {
+        self.nodes.iter().map(|state| &state.node)
+    }
+
+    /// A node's peer ID, stable for the same seed
+    pub fn peer_id(&self, id: NodeId) -> PeerId {
+        self.nodes[id].peer_id
+    }
+
+    /// Whether a node is running
+    pub fn is_up(&self, id: NodeId) -> bool {
+        self.nodes[id].up
+    }
+
+    /// Linked nodes a node can currently reach
+    pub fn peers(&self, id: NodeId) -> Vec<NodeId> {
+        let mut peers: Vec<NodeId> = self
+            .links
+            .keys()
+            .filter_map(|&(a, b)| {
+                if a == id {
+                    Some(b)
+                } else if b == id {
+                    Some(a)
+                } else {
+                    None
+                }
+            })
+            .filter(|&peer| self.nodes[peer].up && self.reachable(id, peer))
+            .collect();
+        peers.sort_unstable();
+        peers
+    }
+
+    /// Message counts so far
+    pub fn stats(&self) -> SimStats {
+        self.stats
+    }
+
+    fn act(&mut self, action: Action<N::Message>) {
+        match action {
+            Action::Stop(node) => self.stop(node),
+            Action::Start(node) => self.start(node),
+            Action::Partition(groups) => self.partition(&groups),
+            Action::Heal => self.heal(),
+            Action::Link(a, b, link) => self.link(a, b, link),
+            Action::Unlink(a, b) => self.unlink(a, b),
+            Action::Inject { from, to, message } => {
+                if self.nodes[to].up {
+                    self.handle(to, |node, ctx| node.on_message(ctx, from, message));
+                }
+            }
+        }
+    }
+
+    /// Run a handler of a node and carry out what it asked for
+    fn handle(&mut self, id: NodeId, f: impl FnOnce(&mut N, &mut Context<N::Message>)) {
+        let mut ctx = Context {
+            id,
+            now: self.now,
+            peers: self.peers(id),
+            sends: Vec::new(),
+            timers: Vec::new(),
+        };
+        f(&mut self.nodes[id].node, &mut ctx);
+
+        let epoch = self.nodes[id].epoch;
+        for (delay, timer) in ctx.timers {
+            self.push(self.now + delay, Event::Timer { node: id, epoch, timer });
+        }
+        for (to, message) in ctx.sends {
+            self.send(id, to, message);
+        }
+    }
+
+    fn send(&mut self, from: NodeId, to: NodeId, message: N::Message) {
+        self.stats.sent += 1;
+        let link = match self.links.get(&link_key(from, to)) {
+            Some(link) if to < self.nodes.len() && self.reachable(from, to) => *link,
+            _ => {
+                self.stats.dropped += 1;
+                return;
+            }
+        };
+        if link.loss > 0.0 && self.rng.gen_bool(link.loss.min(1.0)) {
+            self.stats.lost += 1;
+            return;
+        }
+
+        let jitter = if link.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            link.jitter.mul_f64(self.rng.gen::<f64>())
+        };
+        // Links deliver in order, like the streams nodes talk over
+        let last = self.last_delivery.entry((from, to)).or_default();
+        let at = (self.now + link.latency + jitter).max(*last);
+        *last = at;
+        self.push(at, Event::Deliver { from, to, message });
+    }
+
+    fn reachable(&self, a: NodeId, b: NodeId) -> bool {
+        // Nodes added after the partition are in no group
+        self.groups.as_ref().is_none_or(|groups| groups.get(a) == groups.get(b))
+    }
+
+    fn push(&mut self, at: Duration, event: Event<N::Message>) {
+        let key = self.next_event;
+        self.next_event += 1;
+        self.events.insert(key, event);
+        self.queue.push(Reverse((at, key)));
+    }
+}
+
+fn link_key(a: NodeId, b: NodeId) -> (NodeId, NodeId) {
+    (a.min(b), a.max(b))
+}
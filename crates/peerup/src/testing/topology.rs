@@ -0,0 +1,98 @@
+//! Simulated network links and topologies.
+
+use std::time::Duration;
+
+use super::simulation::NodeId;
+
+/// Properties of a simulated link between two nodes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkConfig {
+    /// One-way delay of every message
+    pub latency: Duration,
+    /// Extra delay of up to this much, drawn for each message
+    pub jitter: Duration,
+    /// Share of messages lost, from 0.0 to 1.0
+    pub loss: f64,
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        Self { latency: Duration::from_millis(20), jitter: Duration::ZERO, loss: 0.0 }
+    }
+}
+
+impl LinkConfig {
+    /// A lossless link with a fixed latency
+    pub fn new(latency: Duration) -> Self {
+        Self { latency, ..Self::default() }
+    }
+
+    /// Add up to `jitter` of random delay to each message
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Lose the given share of messages
+    pub fn with_loss(mut self, loss: f64) -> Self {
+        self.loss = loss.clamp(0.0, 1.0);
+        self
+    }
+}
+
+/// Links between simulated nodes; links are symmetric
+#[derive(Debug, Clone, Default)]
+pub struct Topology {
+    pub(crate) links: Vec<(NodeId, NodeId, LinkConfig)>,
+}
+
+impl Topology {
+    /// No links
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every node linked to every other
+    pub fn full_mesh(nodes: usize, link: LinkConfig) -> Self {
+        let mut topology = Self::new();
+        for a in 0..nodes {
+            for b in a + 1..nodes {
+                topology = topology.link(a, b, link);
+            }
+        }
+        topology
+    }
+
+    /// Each node linked to the next, the last one to the first
+    pub fn ring(nodes: usize, link: LinkConfig) -> Self {
+        let mut topology = Self::new();
+        if nodes < 2 {
+            return topology;
+        }
+        for a in 0..nodes {
+            topology = topology.link(a, (a + 1) % nodes, link);
+        }
+        topology
+    }
+
+    /// Node 0 linked to every other node, which are not linked to each other
+    pub fn star(nodes: usize, link: LinkConfig) -> Self {
+        (1..nodes).fold(Self::new(), |topology, node| topology.link(0, node, link))
+    }
+
+    /// Add a link, replacing an existing one between the same nodes
+    pub fn link(mut self, a: NodeId, b: NodeId, link: LinkConfig) -> Self {
+        self.links.retain(|(x, y, _)| !same_link((*x, *y), (a, b)));
+        self.links.push((a, b, link));
+        self
+    }
+
+    /// The links, in the order they were added
+    pub fn links(&self) -> &[(NodeId, NodeId, LinkConfig)] {
+        &self.links
+    }
+}
+
+fn same_link(a: (NodeId, NodeId), b: (NodeId, NodeId)) -> bool {
+    a == b || a == (b.1, b.0)
+}
@@ -2,4 +2,5 @@ pub mod headers_body_tests;
 pub mod http_method_tests;
 pub mod http_tests;
 pub mod response_tests;
+pub mod target_tests;
 pub mod validation;
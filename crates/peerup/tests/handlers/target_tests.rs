@@ -0,0 +1,126 @@
+//! Tests for the probe target policy
+
+use std::net::IpAddr;
+
+use peerup::{
+    handlers::{handle_probe_request, handle_probe_request_with_policy, TargetPolicy},
+    protocol::ProbeRequest,
+    NodeConfig, PeerNode,
+};
+use tokio::{io::AsyncWriteExt, net::TcpListener};
+
+fn request(target_url: String) -> ProbeRequest {
+    ProbeRequest {
+        target_url,
+        method: "GET".to_string(),
+        timeout: 2000,
+        body: None,
+        headers: None,
+        requested_by: "peer123".to_string(),
+    }
+}
+
+#[test]
+fn test_rejects_internal_addresses() {
+    let policy = TargetPolicy::default();
+    for ip in [
+        "127.0.0.1",
+        "10.1.2.3",
+        "192.168.1.1",
+        "169.254.169.254",
+        "100.100.100.200",
+        "0.0.0.0",
+        "::1",
+        "fe80::1",
+        "fd00:ec2::254",
+        "::ffff:10.0.0.1",
+        "192.0.0.8",
+        // IPv4 embedded in IPv6: compatible, NAT64 and 6to4
+        "::10.0.0.1",
+        "::a9fe:a9fe",
+        "64:ff9b::7f00:1",
+        "64:ff9b::a9fe:a9fe",
+        "64:ff9b:1::808:808",
+        "2002:a00:1::1",
+        "2002:c0a8:101::",
+    ] {
+        let ip: IpAddr = ip.parse().unwrap();
+        assert!(policy.check_ip(&ip).is_err(), "{ip} should be rejected");
+    }
+
+    assert!(policy.check_ip(&"1.1.1.1".parse().unwrap()).is_ok());
+    assert!(policy.check_ip(&"2606:4700:4700::1111".parse().unwrap()).is_ok());
+    assert!(policy.check_ip(&"64:ff9b::101:101".parse().unwrap()).is_ok());
+    assert!(policy.check_ip(&"2002:101:101::1".parse().unwrap()).is_ok());
+}
+
+#[test]
+fn test_allowlist_never_allows_metadata() {
+    let policy = TargetPolicy::with_allowlist(&["10.0.0.0/8", "169.254.0.0/16"]).unwrap();
+
+    assert!(policy.check_ip(&"10.1.2.3".parse().unwrap()).is_ok());
+    assert!(policy.check_ip(&"169.254.1.1".parse().unwrap()).is_ok());
+    assert!(policy.check_ip(&"169.254.169.254".parse().unwrap()).is_err());
+    assert!(policy.check_ip(&"192.168.1.1".parse().unwrap()).is_err());
+
+    let policy = TargetPolicy::with_allowlist(&["64:ff9b::/96"]).unwrap();
+    assert!(policy.check_ip(&"64:ff9b::a00:1".parse().unwrap()).is_ok());
+    assert!(policy.check_ip(&"64:ff9b::a9fe:a9fe".parse().unwrap()).is_err());
+
+    assert!(TargetPolicy::with_allowlist(&["10.0.0.0/33"]).is_err());
+}
+
+#[tokio::test]
+async fn test_probe_of_internal_target_is_refused() {
+    let response = handle_probe_request(request("http://127.0.0.1:9/".to_string())).await;
+
+    assert_eq!(response.status, None);
+    assert!(response.error.unwrap().contains("not publicly routable"));
+
+    let response = handle_probe_request(request("http://localhost:9/".to_string())).await;
+    assert!(response.error.unwrap().contains("not publicly routable"));
+}
+
+#[tokio::test]
+async fn test_allowlisted_target_is_probed() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        socket.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").await.unwrap();
+    });
+
+    let policy = TargetPolicy::with_allowlist(&["localhost"]).unwrap();
+    let response =
+        handle_probe_request_with_policy(request(format!("http://localhost:{port}/")), &policy)
+            .await;
+
+    assert_eq!(response.error, None);
+    assert_eq!(response.status, Some(204));
+}
+
+#[tokio::test]
+async fn test_node_probes_targets_its_config_allows() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        socket.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").await.unwrap();
+    });
+
+    let allowing = NodeConfig::builder()
+        .port_range((0, 0))
+        .disable_mdns()
+        .probe_targets(TargetPolicy::with_allowlist(&["localhost"]).unwrap())
+        .build();
+    let node = PeerNode::with_config(allowing).await.unwrap();
+    let response = node.answer_probe(request(format!("http://localhost:{port}/"))).await;
+    assert_eq!(response.error, None);
+    assert_eq!(response.status, Some(204));
+
+    // Without the allowlist the node refuses the same target
+    let default = NodeConfig::builder().port_range((0, 0)).disable_mdns().build();
+    let node = PeerNode::with_config(default).await.unwrap();
+    let response = node.answer_probe(request(format!("http://localhost:{port}/"))).await;
+    assert!(response.error.unwrap().contains("not publicly routable"));
+}
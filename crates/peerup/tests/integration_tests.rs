@@ -101,3 +101,554 @@ async fn test_multiple_nodes() {
     assert!(node1.is_ok(), "Failed to create first node");
     assert!(node2.is_ok(), "Failed to create second node");
 }
+
+#[tokio::test]
+async fn test_node_metrics_snapshot() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let config = NodeConfig::builder().port_range((0, 0)).disable_mdns().build();
+    let mut node = PeerNode::with_config(config).await.unwrap();
+    node.subscribe_to_results().unwrap();
+    node.state.record_received(peerup::MONITORING_RESULTS_TOPIC, 128);
+
+    let metrics = node.metrics();
+    assert_eq!(metrics.connected_peers, 0);
+    assert_eq!(metrics.bytes_received, 128);
+
+    let topic = metrics
+        .topics
+        .iter()
+        .find(|t| t.topic == peerup::MONITORING_RESULTS_TOPIC)
+        .expect("results topic should be reported");
+    assert!(topic.subscribed);
+    assert_eq!(topic.messages_received, 1);
+    assert_eq!(topic.bytes_received, 128);
+
+    let probe = metrics
+        .protocols
+        .iter()
+        .find(|p| p.protocol == peerup::PROBE_PROTOCOL)
+        .expect("probe protocol should be reported");
+    assert_eq!(probe.bytes_sent, 0);
+    assert_eq!(metrics.total_bytes(), 128);
+}
+
+#[tokio::test]
+async fn test_probe_codec_counts_traffic() {
+    use futures::io::Cursor;
+    use libp2p::{request_response::Codec, StreamProtocol};
+    use peerup::{ProbeCodec, ProtocolTraffic};
+
+    let traffic = ProtocolTraffic::default();
+    let mut codec = ProbeCodec::with_traffic(traffic.clone());
+    let protocol = StreamProtocol::new(peerup::PROBE_PROTOCOL);
+    let request = ProbeRequest {
+        target_url: "https://example.com".to_string(),
+        method: "GET".to_string(),
+        timeout: 5000,
+        headers: None,
+        body: None,
+        requested_by: "peer123".to_string(),
+    };
+
+    let mut written = Cursor::new(Vec::new());
+    codec.write_request(&protocol, &mut written, request).await.unwrap();
+    let bytes = written.into_inner();
+    assert_eq!(traffic.bytes_sent(), bytes.len() as u64);
+
+    let mut reader = Cursor::new(bytes.clone());
+    codec.read_request(&protocol, &mut reader).await.unwrap();
+    assert_eq!(traffic.bytes_received(), bytes.len() as u64);
+}
+
+#[tokio::test]
+async fn test_event_filter_matching() {
+    use libp2p::{gossipsub, PeerId};
+    use peerup::{swarm::SwarmEvent, EventFilter, PeerUPEvent};
+
+    let gossip = |topic: &str| {
+        SwarmEvent::Behaviour(PeerUPEvent::GossipsubMessage {
+            peer: PeerId::random(),
+            message_id: gossipsub::MessageId::from("id"),
+            message: gossipsub::Message {
+                source: None,
+                data: vec![],
+                sequence_number: None,
+                topic: gossipsub::TopicHash::from_raw(topic),
+            },
+        })
+    };
+    let discovered = SwarmEvent::Behaviour(PeerUPEvent::PeerDiscovered(PeerId::random()));
+
+    let filter = EventFilter::new().gossip_topic_prefix("uppe/monitoring/");
+    assert!(filter.matches(&gossip(peerup::MONITORING_RESULTS_TOPIC)));
+    assert!(!filter.matches(&gossip("other/topic")));
+    assert!(!filter.matches(&discovered));
+
+    let filter = filter.connections();
+    assert!(filter.matches(&discovered));
+    assert_eq!(filter.interests().len(), 2);
+
+    let mut node = PeerNode::new().await.unwrap();
+    assert!(node.event_filter().is_none());
+    node.set_event_filter(filter.clone());
+    assert_eq!(node.event_filter(), Some(&filter));
+    node.clear_event_filter();
+    assert!(node.event_filter().is_none());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_node_runs_on_multi_thread_runtime() {
+    let config = NodeConfig::builder().port_range((0, 0)).disable_mdns().build();
+    let mut node = PeerNode::with_config(config).await.unwrap();
+
+    let handle = tokio::spawn(async move {
+        node.start_listening().unwrap();
+        let _ = timeout(Duration::from_millis(200), node.next_event()).await;
+        node.peer_id()
+    });
+
+    assert!(handle.await.is_ok(), "node event loop should run on a worker thread");
+}
+
+#[tokio::test]
+async fn test_topic_subscription_refcounting() {
+    let mut node = PeerNode::new().await.unwrap();
+    let topic = "uppe/test/v1";
+
+    assert_eq!(node.subscribe_topic(topic).unwrap(), 1);
+    assert_eq!(node.subscribe_topic(topic).unwrap(), 2);
+    assert!(node.get_subscribed_topics().contains(&topic.to_string()));
+    assert_eq!(node.active_subscriptions(), vec![(topic.to_string(), 2)]);
+
+    assert_eq!(node.unsubscribe_topic(topic).unwrap(), 1);
+    assert!(node.get_subscribed_topics().contains(&topic.to_string()));
+
+    assert_eq!(node.unsubscribe_topic(topic).unwrap(), 0);
+    assert!(!node.get_subscribed_topics().contains(&topic.to_string()));
+    assert!(node.active_subscriptions().is_empty());
+    assert_eq!(node.unsubscribe_topic(topic).unwrap(), 0);
+}
+
+#[test]
+fn test_topic_rate_limiter_window() {
+    use libp2p::PeerId;
+    use peerup::{network::TopicRateLimiter, node::GossipRateLimit};
+    use std::time::Instant;
+
+    let mut limiter =
+        TopicRateLimiter::new(GossipRateLimit { max_messages: 2, window: Duration::from_secs(10) });
+    let peer = PeerId::random();
+    let now = Instant::now();
+
+    assert!(limiter.check(peer, "topic", now));
+    assert!(limiter.check(peer, "topic", now));
+    assert!(!limiter.check(peer, "topic", now));
+
+    // Other topics and peers have their own budget
+    assert!(limiter.check(peer, "other", now));
+    assert!(limiter.check(PeerId::random(), "topic", now));
+
+    // A new window resets the budget
+    assert!(limiter.check(peer, "topic", now + Duration::from_secs(10)));
+}
+
+#[test]
+fn test_peer_rate_limiter_greylists_repeat_offenders() {
+    use libp2p::PeerId;
+    use peerup::{
+        network::{PeerRateLimiter, PeerVerdict},
+        node::PeerRateLimit,
+    };
+    use std::time::Instant;
+
+    let window = Duration::from_secs(10);
+    let mut limiter = PeerRateLimiter::new(PeerRateLimit {
+        max_messages: 2,
+        window,
+        strikes: 2,
+        greylist_duration: Duration::from_secs(60),
+    });
+    let peer = PeerId::random();
+    let start = Instant::now();
+
+    // First window over the limit only throttles
+    assert_eq!(limiter.check(peer, start), PeerVerdict::Allow);
+    assert_eq!(limiter.check(peer, start), PeerVerdict::Allow);
+    assert_eq!(limiter.check(peer, start), PeerVerdict::Throttled);
+    assert!(limiter.check(PeerId::random(), start).allowed());
+
+    // Going over again in the next window greylists the peer
+    let next = start + window;
+    limiter.check(peer, next);
+    limiter.check(peer, next);
+    assert_eq!(
+        limiter.check(peer, next),
+        PeerVerdict::NewlyGreylisted { duration: Duration::from_secs(60), offences: 1 }
+    );
+    assert!(limiter.is_greylisted(&peer, next));
+    assert_eq!(limiter.check(peer, next + Duration::from_secs(59)), PeerVerdict::Greylisted);
+
+    // Once the greylist expires the peer starts over, and a repeat offence lasts longer
+    let later = next + Duration::from_secs(60);
+    assert_eq!(limiter.check(peer, later), PeerVerdict::Allow);
+    for at in [later, later + window] {
+        while limiter.check(peer, at) == PeerVerdict::Allow {}
+    }
+    assert!(limiter.is_greylisted(&peer, later + window + Duration::from_secs(119)));
+    assert_eq!(limiter.greylisted(later + window + Duration::from_secs(120)), vec![]);
+
+    // A quiet window in between clears the strikes
+    let other = PeerId::random();
+    limiter.check(other, start);
+    limiter.check(other, start);
+    assert_eq!(limiter.check(other, start), PeerVerdict::Throttled);
+    let after_gap = start + window * 2;
+    limiter.check(other, after_gap);
+    limiter.check(other, after_gap);
+    assert_eq!(limiter.check(other, after_gap), PeerVerdict::Throttled);
+}
+
+#[tokio::test]
+async fn test_node_with_peer_scoring() {
+    let config =
+        NodeConfig::builder().port_range((0, 0)).disable_mdns().enable_peer_scoring().build();
+    let node = PeerNode::with_config(config).await.unwrap();
+    assert!(node.state.rate_limiter.is_some());
+    assert!(node.state.peer_limiter.is_some());
+
+    let config = NodeConfig::builder()
+        .port_range((0, 0))
+        .disable_mdns()
+        .disable_peer_scoring()
+        .gossip_rate_limit(None)
+        .peer_rate_limit(None)
+        .build();
+    let node = PeerNode::with_config(config).await.unwrap();
+    assert!(node.state.rate_limiter.is_none());
+    assert!(node.state.peer_limiter.is_none());
+}
+
+#[tokio::test]
+async fn test_dial_backoff_and_concurrency() {
+    use peerup::node::{core::DialScheduler, DialPolicy};
+    use std::time::Instant;
+
+    let policy = DialPolicy {
+        max_concurrent_dials: 1,
+        initial_backoff: Duration::from_secs(1),
+        max_backoff: Duration::from_secs(8),
+    };
+    let scheduler = DialScheduler::new(policy);
+
+    for failures in 1..=10 {
+        let delay = scheduler.backoff_delay(failures);
+        assert!(delay <= Duration::from_secs_f64(8.0 * 1.2), "backoff must be capped");
+    }
+    assert!(scheduler.backoff_delay(1) <= Duration::from_secs_f64(1.2));
+    assert!(scheduler.backoff_delay(3) >= Duration::from_secs_f64(3.2));
+
+    // Dials beyond the concurrency limit stay queued
+    let config =
+        NodeConfig::builder().port_range((0, 0)).disable_mdns().dial_policy(policy).build();
+    let mut node = PeerNode::with_config(config).await.unwrap();
+    node.dial("/ip4/127.0.0.1/tcp/1").unwrap();
+    node.dial("/ip4/127.0.0.1/tcp/2").unwrap();
+    assert_eq!(node.dial_scheduler().in_flight(), 1);
+    assert_eq!(node.dial_scheduler().queued(), 1);
+
+    // A refused dial is put back into the queue with a backoff
+    let _ = timeout(Duration::from_secs(2), async {
+        while node.dial_scheduler().next_retry().is_none() {
+            node.next_event().await;
+        }
+    })
+    .await;
+    assert!(node.dial_scheduler().next_retry().is_some_and(|at| at > Instant::now()));
+}
+
+#[tokio::test]
+async fn test_event_log_ring_buffer() {
+    use libp2p::PeerId;
+    use peerup::{
+        network::{EventLog, NetworkEventKind, NetworkLogEntry},
+        swarm::SwarmEvent,
+        PeerUPEvent,
+    };
+
+    let mut log = EventLog::new(2);
+    for _ in 0..3 {
+        log.push(NetworkLogEntry::from_event(&SwarmEvent::Behaviour(PeerUPEvent::PeerDiscovered(
+            PeerId::random(),
+        ))));
+    }
+    assert_eq!(log.len(), 2);
+    assert_eq!(log.recent_of_kind(NetworkEventKind::Connection, 10).len(), 2);
+    assert!(log.recent_of_kind(NetworkEventKind::Dht, 10).is_empty());
+
+    // Starting a listener is recorded as swarm activity
+    let config =
+        NodeConfig::builder().port_range((0, 0)).disable_mdns().event_log_capacity(16).build();
+    let mut node = PeerNode::with_config(config).await.unwrap();
+    node.start_listening().unwrap();
+    let _ = timeout(Duration::from_millis(500), node.next_event()).await;
+
+    let recent = node.recent_events(10);
+    assert!(!recent.is_empty());
+    assert_eq!(recent[0].kind, NetworkEventKind::Swarm);
+}
+
+#[tokio::test]
+async fn test_dht_records_require_kademlia() {
+    let config = NodeConfig::builder().port_range((0, 0)).disable_mdns().build();
+    let mut node = PeerNode::with_config(config).await.unwrap();
+    assert!(node.put_record(b"uppe/test/key", b"value".to_vec()).is_ok());
+    assert!(node.get_record(b"uppe/test/key").is_ok());
+
+    let config = NodeConfig::builder().port_range((0, 0)).disable_mdns().disable_kademlia().build();
+    let mut node = PeerNode::with_config(config).await.unwrap();
+    assert!(node.put_record(b"uppe/test/key", b"value".to_vec()).is_err());
+    assert!(node.get_record(b"uppe/test/key").is_err());
+}
+
+#[tokio::test]
+async fn test_dht_record_validators() {
+    use peerup::{anyhow::anyhow, discovery::RecordValidators};
+
+    let validators = RecordValidators::new().limit(b"uppe/", 16).namespace(
+        b"uppe/signed/",
+        64,
+        |_: &[u8], value: &[u8]| {
+            if value.starts_with(b"sig:") {
+                Ok(())
+            } else {
+                Err(anyhow!("Record is not signed"))
+            }
+        },
+    );
+    assert!(validators.validate(b"uppe/small", b"value").is_ok());
+    assert!(validators.validate(b"uppe/large", &[0; 17]).is_err());
+    assert!(validators.validate(b"uppe/signed/key", b"sig:a longer signed value").is_ok());
+    assert!(validators.validate(b"uppe/signed/key", b"unsigned").is_err());
+    assert!(validators.validate(b"other/key", &[0; 1024]).is_ok());
+
+    let config = NodeConfig::builder()
+        .port_range((0, 0))
+        .disable_mdns()
+        .record_validators(validators)
+        .build();
+    let mut node = PeerNode::with_config(config).await.unwrap();
+    assert!(node.put_record(b"uppe/small", b"value".to_vec()).is_ok());
+    assert!(node.put_record(b"uppe/large", vec![0; 17]).is_err());
+    assert!(node.put_record(b"uppe/signed/key", b"unsigned".to_vec()).is_err());
+}
+
+#[tokio::test]
+async fn test_node_shutdown() {
+    use peerup::ControlMessage;
+
+    let config = NodeConfig::builder().port_range((0, 0)).disable_mdns().build();
+    let mut node = PeerNode::with_config(config).await.unwrap();
+    node.start_listening().unwrap();
+    node.subscribe_to_results().unwrap();
+
+    let summary = node.shutdown(Duration::from_millis(100)).await.unwrap();
+    assert_eq!(summary.topics_unsubscribed, 1);
+    assert_eq!(summary.listeners_closed, 1);
+    assert_eq!(summary.requests_abandoned, 0);
+
+    let goodbye = ControlMessage::Goodbye { peer_id: "peer".to_string() };
+    let payload = serde_json::to_vec(&goodbye).unwrap();
+    assert_eq!(ControlMessage::parse(&payload), Some(goodbye));
+    assert_eq!(ControlMessage::parse(b"{\"result\":{}}"), None);
+}
+
+#[tokio::test]
+async fn test_probe_round_trip() {
+    use libp2p::swarm::SwarmEvent;
+    use peerup::PeerUPEvent;
+
+    let config = || NodeConfig::builder().port_range((0, 0)).disable_mdns().build();
+    let mut server = PeerNode::with_config(config()).await.unwrap();
+    let mut client = PeerNode::with_config(config()).await.unwrap();
+    server.start_listening().unwrap();
+
+    let round_trip = async {
+        let addr = loop {
+            if let SwarmEvent::NewListenAddr { address, .. } = server.next_event().await {
+                break address;
+            }
+        };
+        client.dial(&addr.to_string()).unwrap();
+
+        let server_id = server.peer_id();
+        let mut sent = None;
+        loop {
+            tokio::select! {
+                event = client.next_event() => match event {
+                    SwarmEvent::ConnectionEstablished { peer_id, .. } if peer_id == server_id => {
+                        let request = ProbeRequest {
+                            target_url: "https://example.com".to_string(),
+                            method: "GET".to_string(),
+                            timeout: 5000,
+                            headers: None,
+                            body: None,
+                            requested_by: client.peer_id().to_string(),
+                        };
+                        sent = Some(client.send_probe(&server_id, request));
+                        assert_eq!(client.metrics().pending_queries, 1);
+                    }
+                    SwarmEvent::Behaviour(PeerUPEvent::ProbeResponseReceived {
+                        request_id, response, ..
+                    }) => break (sent, request_id, response),
+                    _ => {}
+                },
+                event = server.next_event() => {
+                    if let SwarmEvent::Behaviour(PeerUPEvent::ProbeRequestReceived {
+                        request, channel, ..
+                    }) = event
+                    {
+                        let response = ProbeResponse {
+                            status: Some(200),
+                            duration: 12,
+                            error: None,
+                            probed_by: server_id.to_string(),
+                            timestamp: 0,
+                            headers: None,
+                            body: Some(request.target_url),
+                        };
+                        server.respond_probe(channel, response).unwrap();
+                    }
+                }
+            }
+        }
+    };
+
+    let (sent, request_id, response) =
+        tokio::time::timeout(Duration::from_secs(10), round_trip).await.unwrap();
+    assert_eq!(sent, Some(request_id));
+    assert_eq!(response.status, Some(200));
+    assert_eq!(response.body.as_deref(), Some("https://example.com"));
+    assert_eq!(client.metrics().pending_queries, 0);
+}
+
+#[tokio::test]
+async fn test_node_with_relay_server_limits() {
+    use peerup::relay::RelayServerLimits;
+
+    let limits = RelayServerLimits {
+        max_reservations: 8,
+        max_reservations_per_peer: 1,
+        max_circuits: 4,
+        max_circuits_per_peer: 1,
+        max_circuit_duration: Duration::from_secs(30),
+        max_circuit_bytes: 1 << 20,
+        max_requests_per_peer: 0,
+        ..RelayServerLimits::default()
+    };
+    let relay = limits.to_config();
+    assert_eq!(relay.max_reservations, 8);
+    assert_eq!(relay.max_circuits_per_peer, 1);
+    assert_eq!(relay.max_circuit_bytes, 1 << 20);
+    assert!(relay.reservation_rate_limiters.is_empty());
+    assert_eq!(RelayServerLimits::default().to_config().circuit_src_rate_limiters.len(), 1);
+
+    // Hosting a relay turns relay support on
+    let config = NodeConfig::builder().port_range((0, 0)).disable_mdns().disable_relay().build();
+    let config = config.relay_server(limits);
+    assert!(config.enable_relay);
+    assert_eq!(config.relay_server, Some(limits));
+
+    let result = PeerNode::with_config(config).await;
+    assert!(result.is_ok(), "Failed to create relay server node: {:?}", result.err());
+}
+
+#[tokio::test]
+async fn test_node_with_hole_punching() {
+    let config = NodeConfig::builder().port_range((0, 0)).disable_mdns().enable_relay().build();
+    assert!(config.enable_hole_punching);
+    let mut node = PeerNode::with_config(config).await.expect("Failed to create node");
+    let behaviour = node.swarm.behaviour();
+    assert!(behaviour.relay_client.is_enabled());
+    assert!(behaviour.identify.is_enabled());
+    assert!(behaviour.dcutr.is_enabled());
+
+    // A relay is only usable through its peer id
+    assert!(node.listen_via_relay("/ip4/127.0.0.1/tcp/4001").is_err());
+    let relay = format!("/ip4/127.0.0.1/tcp/4001/p2p/{}", libp2p::PeerId::random());
+    assert!(node.listen_via_relay(&relay).is_ok());
+    assert!(node.listeners().iter().any(|(_, addr)| addr.to_string().ends_with("/p2p-circuit")));
+
+    // Without relay support there is nothing to punch holes over
+    let config = NodeConfig::builder()
+        .port_range((0, 0))
+        .disable_mdns()
+        .disable_relay()
+        .enable_hole_punching()
+        .build();
+    let mut node = PeerNode::with_config(config).await.expect("Failed to create node");
+    assert!(!node.swarm.behaviour().dcutr.is_enabled());
+    assert!(node.listen_via_relay(&relay).is_err());
+}
+
+#[tokio::test]
+async fn test_peer_allow_and_block_lists() {
+    let (blocked, allowed) = (libp2p::PeerId::random(), libp2p::PeerId::random());
+    let config =
+        NodeConfig::builder().port_range((0, 0)).disable_mdns().block_peers([blocked]).build();
+    assert_eq!(config.blocked_peers, vec![blocked]);
+    let mut node = PeerNode::with_config(config).await.unwrap();
+    assert!(!node.is_peer_permitted(&blocked));
+    assert!(node.is_peer_permitted(&allowed));
+    assert!(node.dial(&format!("/ip4/127.0.0.1/tcp/4001/p2p/{blocked}")).is_err());
+
+    // Blocks can be lifted and added at runtime
+    assert!(node.unblock_peer(blocked));
+    assert!(!node.unblock_peer(blocked));
+    assert!(node.dial(&format!("/ip4/127.0.0.1/tcp/4001/p2p/{blocked}")).is_ok());
+    assert!(node.block_peer(allowed));
+    assert!(!node.block_peer(allowed));
+    assert_eq!(node.blocked_peers(), vec![allowed]);
+
+    // With an allowlist, every other peer is refused
+    let config =
+        NodeConfig::builder().port_range((0, 0)).disable_mdns().allow_peers([allowed]).build();
+    let node = PeerNode::with_config(config).await.unwrap();
+    assert!(node.is_peer_permitted(&allowed));
+    assert!(!node.is_peer_permitted(&blocked));
+}
+
+#[tokio::test]
+async fn test_blocked_peer_is_refused() {
+    use libp2p::swarm::{ListenError, SwarmEvent};
+
+    let config = || NodeConfig::builder().port_range((0, 0)).disable_mdns().build();
+    let mut server = PeerNode::with_config(config()).await.unwrap();
+    let mut client = PeerNode::with_config(config()).await.unwrap();
+    assert!(server.block_peer(client.peer_id()));
+    server.start_listening().unwrap();
+
+    let refused = async {
+        let addr = loop {
+            if let SwarmEvent::NewListenAddr { address, .. } = server.next_event().await {
+                break address;
+            }
+        };
+        client.dial(&addr.to_string()).unwrap();
+
+        loop {
+            tokio::select! {
+                event = server.next_event() => match event {
+                    SwarmEvent::IncomingConnectionError { error: ListenError::Denied { .. }, .. } => {
+                        break;
+                    }
+                    SwarmEvent::ConnectionEstablished { .. } => panic!("Blocked peer connected"),
+                    _ => {}
+                },
+                _ = client.next_event() => {}
+            }
+        }
+    };
+
+    tokio::time::timeout(Duration::from_secs(10), refused).await.unwrap();
+}
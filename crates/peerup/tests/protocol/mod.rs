@@ -4,4 +4,6 @@ pub mod basic_response_tests;
 pub mod peer_message_tests;
 pub mod request_serialization_tests;
 pub mod response_serialization_tests;
+pub mod results_sync_tests;
 pub mod url_tests;
+pub mod wire_tests;
@@ -0,0 +1,22 @@
+//! Tests for the results sync request and response types
+
+use peerup::protocol::{ResultsSyncRequest, ResultsSyncResponse};
+
+#[test]
+fn test_results_sync_roundtrip() {
+    let request = ResultsSyncRequest {
+        monitor_id: "3f1c2a9e-1b7d-4c55-9b43-6f0e2d8a7c10".to_string(),
+        since: 1_700_000_000,
+        cursor: Some(42),
+        limit: 200,
+    };
+    let serialized = serde_json::to_string(&request).unwrap();
+    assert_eq!(serde_json::from_str::<ResultsSyncRequest>(&serialized).unwrap(), request);
+
+    let response = ResultsSyncResponse {
+        results: vec![serde_json::json!({ "peer_id": "abc", "timestamp": 1_700_000_060 })],
+        next_cursor: None,
+    };
+    let serialized = serde_json::to_string(&response).unwrap();
+    assert_eq!(serde_json::from_str::<ResultsSyncResponse>(&serialized).unwrap(), response);
+}
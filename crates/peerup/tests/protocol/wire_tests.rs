@@ -0,0 +1,70 @@
+//! Tests for the binary wire format
+
+use peerup::protocol::wire::{self, WIRE_MAGIC};
+use peerup::protocol::{ProbeResponse, WireEncoder};
+
+fn response() -> ProbeResponse {
+    ProbeResponse {
+        status: Some(200),
+        duration: 100,
+        error: None,
+        headers: None,
+        body: Some("OK".to_string()),
+        probed_by: "peer123".to_string(),
+        timestamp: 1234567890,
+    }
+}
+
+#[test]
+fn test_wire_roundtrip() {
+    let mut encoder = WireEncoder::new();
+    let payload = encoder.encode(3, &response()).unwrap().to_vec();
+
+    assert_eq!(&payload[..2], &[WIRE_MAGIC, 3]);
+    assert_eq!(wire::wire_kind(&payload), Some(3));
+    let decoded: ProbeResponse = wire::decode(3, &payload).unwrap();
+    assert_eq!(decoded.status, Some(200));
+    assert_eq!(decoded.body.as_deref(), Some("OK"));
+    assert_eq!(decoded.probed_by, "peer123");
+    assert_eq!(decoded.timestamp, 1234567890);
+
+    // Smaller than the same value as JSON
+    assert!(payload.len() < serde_json::to_vec(&response()).unwrap().len());
+}
+
+#[test]
+fn test_wire_rejects_other_payloads() {
+    let json = serde_json::to_vec(&response()).unwrap();
+    assert_eq!(wire::wire_kind(&json), None);
+    assert!(wire::decode::<ProbeResponse>(3, &json).is_err());
+
+    let payload = WireEncoder::new().encode(3, &response()).unwrap().to_vec();
+    assert!(wire::decode::<ProbeResponse>(4, &payload).is_err());
+}
+
+#[test]
+fn test_wire_encoder_reuses_buffer() {
+    let mut encoder = WireEncoder::new();
+    let first = encoder.encode(3, &response()).unwrap().len();
+    let capacity = encoder.capacity();
+
+    for _ in 0..10 {
+        assert_eq!(encoder.encode(3, &response()).unwrap().len(), first);
+    }
+    assert_eq!(encoder.capacity(), capacity);
+}
+
+#[test]
+fn test_wire_tolerates_appended_fields() {
+    let mut encoder = WireEncoder::new();
+    let mut payload = encoder.encode(3, &response()).unwrap().to_vec();
+    payload.extend_from_slice(&[0xAC, 0x02, 9]);
+
+    let decoded: ProbeResponse = wire::decode(3, &payload).unwrap();
+    assert_eq!(decoded.probed_by, "peer123");
+    let (decoded, rest): (ProbeResponse, _) = wire::decode_prefix(3, &payload).unwrap();
+    assert_eq!(decoded.timestamp, 1234567890);
+    let (appended, rest): (u32, _) = wire::decode_appended(rest).unwrap();
+    assert_eq!(appended, 300);
+    assert_eq!(rest, &[9]);
+}
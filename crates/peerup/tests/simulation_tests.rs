@@ -0,0 +1,210 @@
+//! Tests for the network simulation harness
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use peerup::testing::{
+    Action, Context, LinkConfig, NodeId, Scenario, SimNode, Simulation, Topology,
+};
+
+/// Floods every message it sees for the first time to its peers
+#[derive(Default)]
+struct Flood {
+    received_at: HashMap<u32, Duration>,
+}
+
+impl SimNode for Flood {
+    type Message = u32;
+
+    fn on_message(&mut self, ctx: &mut Context<u32>, _from: NodeId, message: u32) {
+        if let Entry::Vacant(entry) = self.received_at.entry(message) {
+            entry.insert(ctx.now());
+            ctx.broadcast(message);
+        }
+    }
+}
+
+/// Sends a heartbeat to its peers every second and counts those it receives
+#[derive(Default)]
+struct Heartbeat {
+    starts: u32,
+    received: HashMap<NodeId, u32>,
+}
+
+impl SimNode for Heartbeat {
+    type Message = ();
+
+    fn on_start(&mut self, ctx: &mut Context<()>) {
+        self.starts += 1;
+        ctx.set_timer(Duration::from_secs(1), 0);
+    }
+
+    fn on_message(&mut self, _ctx: &mut Context<()>, from: NodeId, _message: ()) {
+        *self.received.entry(from).or_default() += 1;
+    }
+
+    fn on_timer(&mut self, ctx: &mut Context<()>, timer: u64) {
+        ctx.broadcast(());
+        ctx.set_timer(Duration::from_secs(1), timer);
+    }
+}
+
+fn flood_network(seed: u64, nodes: usize, topology: Topology) -> Simulation<Flood> {
+    let mut sim = Simulation::new(seed);
+    for _ in 0..nodes {
+        sim.add_node(Flood::default());
+    }
+    sim.apply(&topology);
+    sim.start_all();
+    sim
+}
+
+fn inject(sim: &mut Simulation<Flood>, at: Duration, message: u32) {
+    sim.schedule(Scenario::new().at(at, Action::Inject { from: 0, to: 0, message }));
+}
+
+#[test]
+fn test_simulation_latency() {
+    let link = LinkConfig::new(Duration::from_millis(10));
+    let mut sim = flood_network(1, 4, Topology::ring(4, link));
+    assert_eq!(sim.peers(0), vec![1, 3]);
+
+    inject(&mut sim, Duration::ZERO, 7);
+    sim.run_for(Duration::from_secs(1));
+
+    let received: Vec<_> = sim.nodes().map(|node| node.received_at[&7]).collect();
+    assert_eq!(
+        received,
+        [0, 10, 20, 10].map(Duration::from_millis).to_vec(),
+        "Messages should take one link latency per hop"
+    );
+    assert_eq!(sim.now(), Duration::from_secs(1));
+    assert_eq!(sim.stats().lost, 0);
+}
+
+#[test]
+fn test_simulation_partition_and_heal() {
+    let link = LinkConfig::new(Duration::from_millis(5));
+    let mut sim = flood_network(2, 4, Topology::full_mesh(4, link));
+    sim.schedule(Scenario::new().partition(
+        Duration::ZERO,
+        Duration::from_secs(10),
+        vec![vec![0, 1], vec![2, 3]],
+    ));
+    inject(&mut sim, Duration::from_secs(1), 1);
+    inject(&mut sim, Duration::from_secs(11), 2);
+
+    sim.run_until(Duration::from_secs(5));
+    assert_eq!(sim.peers(0), vec![1]);
+    let reached: HashSet<_> =
+        (0..sim.len()).filter(|&id| sim.node(id).received_at.contains_key(&1)).collect();
+    assert_eq!(reached, HashSet::from([0, 1]));
+
+    sim.run_until(Duration::from_secs(20));
+    assert_eq!(sim.peers(0), vec![1, 2, 3]);
+    assert!(sim.nodes().all(|node| node.received_at.contains_key(&2)));
+    assert_eq!(sim.stats().dropped, 0, "Nodes only broadcast to peers they reach");
+
+    // Messages in flight when the partition forms are dropped
+    sim.schedule(
+        Scenario::new()
+            .at(Duration::ZERO, Action::Inject { from: 0, to: 0, message: 3 })
+            .at(Duration::from_millis(1), Action::Partition(vec![vec![0]])),
+    );
+    sim.run_for(Duration::from_secs(1));
+    assert_eq!(sim.stats().dropped, 3);
+    assert!(sim.nodes().skip(1).all(|node| !node.received_at.contains_key(&3)));
+}
+
+#[test]
+fn test_simulation_churn_cancels_timers() {
+    let link = LinkConfig::new(Duration::from_millis(10));
+    let mut sim = Simulation::new(3);
+    for _ in 0..3 {
+        sim.add_node(Heartbeat::default());
+    }
+    sim.apply(&Topology::star(3, link));
+    sim.start_all();
+    sim.schedule(Scenario::new().churn(
+        1,
+        Duration::from_millis(2500),
+        Duration::from_secs(10),
+        Duration::from_secs(5),
+        Duration::from_secs(10),
+    ));
+
+    sim.run_until(Duration::from_millis(4000));
+    assert!(!sim.is_up(1));
+    assert_eq!(sim.peers(0), vec![2]);
+
+    sim.run_until(Duration::from_millis(20_500));
+    assert!(sim.is_up(1));
+    assert_eq!(sim.node(1).starts, 2);
+    // Down from 2.5s to 7.5s; the restart reset its timer, and nothing was
+    // sent to it while it was down
+    assert_eq!(sim.node(0).received[&1], 2 + 12);
+    assert_eq!(sim.node(1).received[&0], 2 + 13);
+    assert_eq!(sim.node(0).received[&2], 20);
+    assert!(!sim.node(1).received.contains_key(&2), "Leaves of a star are not linked");
+}
+
+#[test]
+fn test_simulation_links_keep_order() {
+    struct Collect(Vec<u32>);
+    impl SimNode for Collect {
+        type Message = u32;
+
+        fn on_start(&mut self, ctx: &mut Context<u32>) {
+            if ctx.id() == 0 {
+                for message in 0..100 {
+                    ctx.send(1, message);
+                }
+            }
+        }
+
+        fn on_message(&mut self, _ctx: &mut Context<u32>, _from: NodeId, message: u32) {
+            self.0.push(message);
+        }
+    }
+
+    let link = LinkConfig::new(Duration::from_millis(10)).with_jitter(Duration::from_millis(100));
+    let mut sim = Simulation::new(4);
+    sim.add_node(Collect(Vec::new()));
+    sim.add_node(Collect(Vec::new()));
+    sim.link(0, 1, link);
+    sim.start(1);
+    sim.start(0);
+    sim.run_for(Duration::from_secs(1));
+
+    assert_eq!(sim.node(1).0, (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_simulation_is_deterministic() {
+    let run = |seed| {
+        let link = LinkConfig::new(Duration::from_millis(20))
+            .with_jitter(Duration::from_millis(30))
+            .with_loss(0.2);
+        let mut sim = flood_network(seed, 8, Topology::full_mesh(8, link));
+        for message in 0..20 {
+            inject(&mut sim, Duration::from_millis(100 * message as u64), message);
+        }
+        sim.run_for(Duration::from_secs(10));
+        let received: Vec<Vec<(u32, Duration)>> = sim
+            .nodes()
+            .map(|node| {
+                let mut received: Vec<_> =
+                    node.received_at.iter().map(|(&m, &at)| (m, at)).collect();
+                received.sort();
+                received
+            })
+            .collect();
+        (sim.stats(), received, sim.peer_id(3))
+    };
+
+    let first = run(42);
+    assert_eq!(first, run(42));
+    assert!(first.0.lost > 0);
+    assert_ne!(first.2, run(43).2, "Peer IDs should derive from the seed");
+}